@@ -10,6 +10,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build_client(true)
         .out_dir("src/proto")
         .file_descriptor_set_path(out_dir.join("logi_descriptor.bin"))
+        // google.rpc.Status (per-entry batch results) is provided by tonic-types
+        .extern_path(".google.rpc.Status", "::tonic_types::Status")
         .compile_protos(
             &[
                 format!("{}/common.proto", proto_dir),
@@ -27,12 +29,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 format!("{}/bot_config.proto", proto_dir),
                 format!("{}/access_request.proto", proto_dir),
                 format!("{}/items.proto", proto_dir),
+                format!("{}/scheduler.proto", proto_dir),
+                format!("{}/jobs.proto", proto_dir),
+                format!("{}/notifications.proto", proto_dir),
+                format!("{}/webhooks.proto", proto_dir),
+                format!("{}/etc.proto", proto_dir),
+                format!("{}/fuel.proto", proto_dir),
+                format!("{}/ichiban_cars.proto", proto_dir),
+                format!("{}/reports.proto", proto_dir),
+                format!("{}/admin.proto", proto_dir),
+                format!("{}/api_keys.proto", proto_dir),
+                format!("{}/search.proto", proto_dir),
+                format!("{}/geofences.proto", proto_dir),
+                // v2 packages (v1 = logi.* above, frozen)
+                format!("{}/v2/files.proto", proto_dir),
             ],
             &[proto_dir],
         )?;
 
     // Rerun if proto files change
     println!("cargo:rerun-if-changed={}/", proto_dir);
+    // Rerun if migrations change (embedded via sqlx::migrate!)
+    println!("cargo:rerun-if-changed=migrations");
 
     Ok(())
 }
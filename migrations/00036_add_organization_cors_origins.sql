@@ -0,0 +1,5 @@
+-- Migration: Per-organization CORS allowed origins
+-- CORS_PER_ORGANIZATION=true の場合、全組織の許可オリジンの和集合を CORS で許可する
+
+ALTER TABLE organizations
+    ADD COLUMN cors_allowed_origins TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,8 @@
+-- Migration: SECURITY DEFINER functions for per-service health checks
+-- ヘルスチェックは組織コンテキストなしで実行されるため、RLS をバイパスして存在確認のみ行う
+
+CREATE OR REPLACE FUNCTION flickr_tokens_exist()
+RETURNS BOOLEAN
+LANGUAGE sql STABLE SECURITY DEFINER AS $$
+    SELECT EXISTS (SELECT 1 FROM flickr_tokens);
+$$;
@@ -0,0 +1,90 @@
+-- Migration: Durable background job queue
+-- tokio::spawn の fire-and-forget を置き換える。ワーカーは claim_job で1件ずつ取得し、
+-- complete_job / fail_job で結果を返す（ack されないまま lock_timeout を過ぎた job は再取得される）
+
+CREATE TABLE jobs (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+    -- 同じ対象の二重登録防止（pending / running の間だけ一意）
+    dedupe_key TEXT,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'succeeded', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 5,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_by TEXT,
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_jobs_claim ON jobs(run_at, id) WHERE status IN ('pending', 'running');
+CREATE INDEX idx_jobs_org_status ON jobs(organization_id, status, created_at DESC);
+CREATE UNIQUE INDEX idx_jobs_dedupe ON jobs(organization_id, kind, dedupe_key)
+    WHERE dedupe_key IS NOT NULL AND status IN ('pending', 'running');
+
+ALTER TABLE jobs ENABLE ROW LEVEL SECURITY;
+ALTER TABLE jobs FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON jobs
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON jobs TO rust_logi_app;
+GRANT USAGE ON SEQUENCE jobs_id_seq TO rust_logi_app;
+
+-- ワーカー用: 全組織から実行可能な job を1件取得してロックする（RLS バイパス）
+CREATE OR REPLACE FUNCTION claim_job(p_worker TEXT, p_kinds TEXT[], p_lock_timeout INTERVAL)
+RETURNS TABLE(id BIGINT, organization_id TEXT, kind TEXT, payload JSONB, attempts INTEGER, max_attempts INTEGER)
+LANGUAGE sql SECURITY DEFINER AS $$
+    UPDATE jobs j
+    SET status = 'running',
+        attempts = j.attempts + 1,
+        locked_by = p_worker,
+        locked_at = NOW(),
+        updated_at = NOW()
+    WHERE j.id = (
+        SELECT c.id FROM jobs c
+        WHERE c.kind = ANY(p_kinds)
+          AND ((c.status = 'pending' AND c.run_at <= NOW())
+               OR (c.status = 'running' AND c.locked_at < NOW() - p_lock_timeout))
+        ORDER BY c.run_at, c.id
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+    )
+    RETURNING j.id, j.organization_id::text, j.kind, j.payload, j.attempts, j.max_attempts;
+$$;
+
+-- 成功
+CREATE OR REPLACE FUNCTION complete_job(p_id BIGINT, p_worker TEXT)
+RETURNS BOOLEAN
+LANGUAGE sql SECURITY DEFINER AS $$
+    WITH updated AS (
+        UPDATE jobs
+        SET status = 'succeeded', locked_by = NULL, locked_at = NULL, last_error = NULL,
+            updated_at = NOW(), finished_at = NOW()
+        WHERE id = p_id AND locked_by = p_worker AND status = 'running'
+        RETURNING 1
+    )
+    SELECT EXISTS (SELECT 1 FROM updated);
+$$;
+
+-- 失敗: max_attempts 未満なら p_retry_at に再実行、到達していれば failed。新しい status を返す
+CREATE OR REPLACE FUNCTION fail_job(p_id BIGINT, p_worker TEXT, p_error TEXT, p_retry_at TIMESTAMPTZ)
+RETURNS TEXT
+LANGUAGE sql SECURITY DEFINER AS $$
+    UPDATE jobs
+    SET status = CASE WHEN attempts >= max_attempts THEN 'failed' ELSE 'pending' END,
+        run_at = CASE WHEN attempts >= max_attempts THEN run_at ELSE p_retry_at END,
+        finished_at = CASE WHEN attempts >= max_attempts THEN NOW() ELSE NULL END,
+        last_error = p_error,
+        locked_by = NULL,
+        locked_at = NULL,
+        updated_at = NOW()
+    WHERE id = p_id AND locked_by = p_worker AND status = 'running'
+    RETURNING status;
+$$;
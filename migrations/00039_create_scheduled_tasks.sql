@@ -0,0 +1,42 @@
+-- Migration: Per-organization cron schedules
+-- 組織ごとに登録タスク（カメラ同期、車検期限通知、削除済みファイルの完全削除など）の cron 式を保存する。
+-- スケジューラーは next_run_at を過ぎたタスクを jobs に登録する（同じタスクの job が未完了なら登録しない）
+
+CREATE TABLE scheduled_tasks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    task TEXT NOT NULL,
+    -- 5 フィールド（分 時 日 月 曜日）または秒付き 6 フィールド、JST
+    cron_expression TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    last_job_id BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (organization_id, task)
+);
+
+CREATE INDEX idx_scheduled_tasks_due ON scheduled_tasks(next_run_at) WHERE enabled;
+
+ALTER TABLE scheduled_tasks ENABLE ROW LEVEL SECURITY;
+ALTER TABLE scheduled_tasks FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON scheduled_tasks
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON scheduled_tasks TO rust_logi_app;
+
+-- スケジューラー用: 全組織の実行時刻を過ぎたタスク（next_run_at 未計算を含む）（RLS バイパス）
+CREATE OR REPLACE FUNCTION due_scheduled_tasks(p_tasks TEXT[])
+RETURNS TABLE(id UUID, organization_id TEXT, task TEXT, cron_expression TEXT, next_run_at TIMESTAMPTZ)
+LANGUAGE sql STABLE SECURITY DEFINER AS $$
+    SELECT s.id, s.organization_id::text, s.task, s.cron_expression, s.next_run_at
+    FROM scheduled_tasks s
+    WHERE s.enabled
+      AND s.task = ANY(p_tasks)
+      AND (s.next_run_at IS NULL OR s.next_run_at <= NOW())
+    ORDER BY s.next_run_at NULLS FIRST
+    LIMIT 100;
+$$;
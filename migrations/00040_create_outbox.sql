@@ -0,0 +1,86 @@
+-- Migration: Transactional outbox for outbound notifications
+-- 外部通知（LINE WORKS 等）は業務データと同じトランザクションで outbox に書き、配送ワーカーが送る。
+-- 配送先（organization_id, target）ごとに id 順で1件ずつ送り、先頭が送れるまで後続は待つ（at-least-once）
+
+CREATE TABLE outbox (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    target TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_by TEXT,
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_outbox_pending ON outbox(organization_id, target, id) WHERE status = 'pending';
+
+ALTER TABLE outbox ENABLE ROW LEVEL SECURITY;
+ALTER TABLE outbox FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON outbox
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON outbox TO rust_logi_app;
+GRANT USAGE ON SEQUENCE outbox_id_seq TO rust_logi_app;
+
+-- 配送ワーカー用: 各配送先の先頭（最小 id の pending）のうち送信可能なものをロックして返す（RLS バイパス）
+CREATE OR REPLACE FUNCTION claim_outbox(p_worker TEXT, p_targets TEXT[], p_lock_timeout INTERVAL, p_limit INTEGER)
+RETURNS TABLE(id BIGINT, organization_id TEXT, target TEXT, event_type TEXT, payload JSONB, attempts INTEGER)
+LANGUAGE sql SECURITY DEFINER AS $$
+    UPDATE outbox o
+    SET locked_by = p_worker,
+        locked_at = NOW(),
+        attempts = o.attempts + 1
+    WHERE o.id IN (
+        SELECT h.id FROM (
+            SELECT DISTINCT ON (p.organization_id, p.target) p.id, p.next_attempt_at, p.locked_at
+            FROM outbox p
+            WHERE p.status = 'pending' AND p.target = ANY(p_targets)
+            ORDER BY p.organization_id, p.target, p.id
+        ) h
+        WHERE h.next_attempt_at <= NOW()
+          AND (h.locked_at IS NULL OR h.locked_at < NOW() - p_lock_timeout)
+        ORDER BY h.id
+        LIMIT p_limit
+    )
+      -- 同時に claim した他のワーカーとの競合を再チェック
+      AND o.status = 'pending'
+      AND (o.locked_at IS NULL OR o.locked_at < NOW() - p_lock_timeout)
+    RETURNING o.id, o.organization_id::text, o.target, o.event_type, o.payload, o.attempts;
+$$;
+
+-- 送信成功
+CREATE OR REPLACE FUNCTION ack_outbox(p_id BIGINT, p_worker TEXT)
+RETURNS BOOLEAN
+LANGUAGE sql SECURITY DEFINER AS $$
+    WITH updated AS (
+        UPDATE outbox
+        SET status = 'delivered', delivered_at = NOW(), last_error = NULL,
+            locked_by = NULL, locked_at = NULL
+        WHERE id = p_id AND locked_by = p_worker AND status = 'pending'
+        RETURNING 1
+    )
+    SELECT EXISTS (SELECT 1 FROM updated);
+$$;
+
+-- 送信失敗: p_max_attempts 未満なら p_retry_at に再送、到達したら failed（後続の配送を進める）。新しい status を返す
+CREATE OR REPLACE FUNCTION fail_outbox(p_id BIGINT, p_worker TEXT, p_error TEXT, p_retry_at TIMESTAMPTZ, p_max_attempts INTEGER)
+RETURNS TEXT
+LANGUAGE sql SECURITY DEFINER AS $$
+    UPDATE outbox
+    SET status = CASE WHEN attempts >= p_max_attempts THEN 'failed' ELSE 'pending' END,
+        next_attempt_at = p_retry_at,
+        last_error = p_error,
+        locked_by = NULL,
+        locked_at = NULL
+    WHERE id = p_id AND locked_by = p_worker AND status = 'pending'
+    RETURNING status;
+$$;
@@ -0,0 +1,6 @@
+-- Migration: Allow cancelling jobs (JobsService.CancelJob)
+-- running 中に cancelled にした job は、ワーカーの complete_job / fail_job が status = 'running' を条件にしているため結果が反映されない
+
+ALTER TABLE jobs DROP CONSTRAINT jobs_status_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_status_check
+    CHECK (status IN ('pending', 'running', 'succeeded', 'failed', 'cancelled'));
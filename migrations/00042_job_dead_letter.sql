@@ -0,0 +1,27 @@
+-- Migration: Dead-letter state for jobs
+-- max_attempts に達した job は failed ではなく dead_letter にして残す（last_error 付き）。
+-- JobsService.RequeueDeadLetters / RetryJob で再実行できる
+
+UPDATE jobs SET status = 'dead_letter' WHERE status = 'failed';
+
+ALTER TABLE jobs DROP CONSTRAINT jobs_status_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_status_check
+    CHECK (status IN ('pending', 'running', 'succeeded', 'dead_letter', 'cancelled'));
+
+CREATE INDEX idx_jobs_dead_letter ON jobs(organization_id, kind) WHERE status = 'dead_letter';
+
+-- 失敗: max_attempts 未満なら p_retry_at に再実行、到達していれば dead_letter。新しい status を返す
+CREATE OR REPLACE FUNCTION fail_job(p_id BIGINT, p_worker TEXT, p_error TEXT, p_retry_at TIMESTAMPTZ)
+RETURNS TEXT
+LANGUAGE sql SECURITY DEFINER AS $$
+    UPDATE jobs
+    SET status = CASE WHEN attempts >= max_attempts THEN 'dead_letter' ELSE 'pending' END,
+        run_at = CASE WHEN attempts >= max_attempts THEN run_at ELSE p_retry_at END,
+        finished_at = CASE WHEN attempts >= max_attempts THEN NOW() ELSE NULL END,
+        last_error = p_error,
+        locked_by = NULL,
+        locked_at = NULL,
+        updated_at = NOW()
+    WHERE id = p_id AND locked_by = p_worker AND status = 'running'
+    RETURNING status;
+$$;
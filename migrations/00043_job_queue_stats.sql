@@ -0,0 +1,16 @@
+-- Migration: Job queue depth metrics
+-- ワーカーの滞留監視用: 全組織の kind ごとの件数（RLS バイパス）
+
+CREATE OR REPLACE FUNCTION job_queue_stats(p_kinds TEXT[])
+RETURNS TABLE(kind TEXT, ready BIGINT, delayed BIGINT, running BIGINT, oldest_ready_secs DOUBLE PRECISION)
+LANGUAGE sql SECURITY DEFINER STABLE AS $$
+    SELECT j.kind,
+           COUNT(*) FILTER (WHERE j.status = 'pending' AND j.run_at <= NOW()),
+           COUNT(*) FILTER (WHERE j.status = 'pending' AND j.run_at > NOW()),
+           COUNT(*) FILTER (WHERE j.status = 'running'),
+           EXTRACT(EPOCH FROM NOW() - MIN(j.run_at) FILTER (WHERE j.status = 'pending' AND j.run_at <= NOW()))::double precision
+    FROM jobs j
+    WHERE j.kind = ANY(p_kinds) AND j.status IN ('pending', 'running')
+    GROUP BY j.kind
+    ORDER BY j.kind;
+$$;
@@ -0,0 +1,114 @@
+-- Migration: Startup recovery of interrupted background work
+-- デプロイ・クラッシュで job 登録前に止まった処理（自動解析、Flickr アップロード、mp4 保存、STANDARD 昇格）を
+-- 起動時に見つけて job を登録し直す（RLS バイパス）。kind が NULL のものはスキップ。
+-- 同じ (kind, dedupe_key) の job が一度でも登録されていれば対象外（成功済み・dead_letter は再実行しない）。
+-- 昇格だけは降格後に再び条件を満たすことがあるので succeeded / cancelled の job は無視する
+
+CREATE OR REPLACE FUNCTION recover_interrupted_work(
+    p_auto_parse_kind TEXT,
+    p_flickr_upload_kind TEXT,
+    p_mp4_download_kind TEXT,
+    p_storage_promotion_kind TEXT,
+    p_since INTERVAL
+)
+RETURNS TABLE(job_kind TEXT, enqueued BIGINT)
+LANGUAGE plpgsql SECURITY DEFINER AS $$
+BEGIN
+    -- アップロード済みで自動解析 job がない JSON / PDF
+    IF p_auto_parse_kind IS NOT NULL THEN
+        WITH inserted AS (
+            INSERT INTO jobs (organization_id, kind, payload, dedupe_key)
+            SELECT f.organization_id, p_auto_parse_kind,
+                   jsonb_build_object('file_uuid', f.uuid::text, 'mime_type', f.type), f.uuid::text
+            FROM files f
+            WHERE f.deleted_at IS NULL
+              AND f.type IN ('application/json', 'application/pdf')
+              AND f.created_at > NOW() - p_since
+              AND NOT EXISTS (
+                  SELECT 1 FROM jobs j
+                  WHERE j.organization_id = f.organization_id AND j.kind = p_auto_parse_kind
+                    AND j.dedupe_key = f.uuid::text
+              )
+            ON CONFLICT (organization_id, kind, dedupe_key)
+                WHERE dedupe_key IS NOT NULL AND status IN ('pending', 'running')
+                DO NOTHING
+            RETURNING 1
+        )
+        SELECT p_auto_parse_kind, COUNT(*) FROM inserted INTO job_kind, enqueued;
+        RETURN NEXT;
+    END IF;
+
+    -- 同期で取り込んだが Flickr アップロード job がないカメラファイル
+    IF p_flickr_upload_kind IS NOT NULL THEN
+        WITH inserted AS (
+            INSERT INTO jobs (organization_id, kind, payload, dedupe_key)
+            SELECT c.organization_id, p_flickr_upload_kind, jsonb_build_object('name', c.name), c.name
+            FROM cam_files c
+            WHERE c.flickr_id IS NULL
+              AND c.created_at > NOW() - p_since
+              AND NOT EXISTS (
+                  SELECT 1 FROM jobs j
+                  WHERE j.organization_id = c.organization_id AND j.kind = p_flickr_upload_kind
+                    AND j.dedupe_key = c.name
+              )
+            ON CONFLICT (organization_id, kind, dedupe_key)
+                WHERE dedupe_key IS NOT NULL AND status IN ('pending', 'running')
+                DO NOTHING
+            RETURNING 1
+        )
+        SELECT p_flickr_upload_kind, COUNT(*) FROM inserted INTO job_kind, enqueued;
+        RETURN NEXT;
+    END IF;
+
+    -- mp4 が未保存で job がない DVR 通知
+    IF p_mp4_download_kind IS NOT NULL THEN
+        WITH inserted AS (
+            INSERT INTO jobs (organization_id, kind, payload, dedupe_key)
+            SELECT d.organization_id, p_mp4_download_kind, jsonb_build_object('mp4_url', d.mp4_url), d.mp4_url
+            FROM dvr_notifications d
+            WHERE (d.download_status = 'pending' OR d.download_status IS NULL)
+              AND d.mp4_url NOT LIKE 'https://example.com%'
+              AND d.created_at > NOW() - p_since
+              AND NOT EXISTS (
+                  SELECT 1 FROM jobs j
+                  WHERE j.organization_id = d.organization_id AND j.kind = p_mp4_download_kind
+                    AND j.dedupe_key = d.mp4_url
+              )
+            ON CONFLICT (organization_id, kind, dedupe_key)
+                WHERE dedupe_key IS NOT NULL AND status IN ('pending', 'running')
+                DO NOTHING
+            RETURNING 1
+        )
+        SELECT p_mp4_download_kind, COUNT(*) FROM inserted INTO job_kind, enqueued;
+        RETURN NEXT;
+    END IF;
+
+    -- 昇格条件（直近7日で3回以上アクセス）を満たしているのに STANDARD になっていないファイル
+    IF p_storage_promotion_kind IS NOT NULL THEN
+        WITH inserted AS (
+            INSERT INTO jobs (organization_id, kind, payload, dedupe_key)
+            SELECT f.organization_id, p_storage_promotion_kind,
+                   jsonb_build_object('file_uuid', f.uuid::text), f.uuid::text
+            FROM files f
+            WHERE f.deleted_at IS NULL
+              AND f.s3_key IS NOT NULL
+              AND f.storage_class IS DISTINCT FROM 'STANDARD'
+              AND (
+                  SELECT COUNT(*) FROM file_access_logs l
+                  WHERE l.file_uuid = f.uuid AND l.accessed_at > NOW() - INTERVAL '7 days'
+              ) >= 3
+              AND NOT EXISTS (
+                  SELECT 1 FROM jobs j
+                  WHERE j.organization_id = f.organization_id AND j.kind = p_storage_promotion_kind
+                    AND j.dedupe_key = f.uuid::text AND j.status IN ('pending', 'running', 'dead_letter')
+              )
+            ON CONFLICT (organization_id, kind, dedupe_key)
+                WHERE dedupe_key IS NOT NULL AND status IN ('pending', 'running')
+                DO NOTHING
+            RETURNING 1
+        )
+        SELECT p_storage_promotion_kind, COUNT(*) FROM inserted INTO job_kind, enqueued;
+        RETURN NEXT;
+    END IF;
+END;
+$$;
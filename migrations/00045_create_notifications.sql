@@ -0,0 +1,135 @@
+-- Migration: Notification subsystem (email first)
+-- 通知はテンプレートを描画して notification_deliveries に1宛先1行で記録し、job（notifications.deliver）で送る。
+-- 送信結果（sent / failed、試行回数、最後のエラー）を行に残す。
+
+-- 組織ごとの送信元設定（未登録ならサーバー既定の SMTP_FROM）
+CREATE TABLE notification_settings (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id),
+    email_from_address TEXT,
+    email_from_name TEXT,
+    email_reply_to TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE notification_settings ENABLE ROW LEVEL SECURITY;
+ALTER TABLE notification_settings FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON notification_settings
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON notification_settings TO rust_logi_app;
+
+CREATE TABLE notification_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    channel TEXT NOT NULL,
+    template TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_notification_deliveries_org ON notification_deliveries(organization_id, id DESC);
+
+ALTER TABLE notification_deliveries ENABLE ROW LEVEL SECURITY;
+ALTER TABLE notification_deliveries FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON notification_deliveries
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON notification_deliveries TO rust_logi_app;
+GRANT USAGE ON SEQUENCE notification_deliveries_id_seq TO rust_logi_app;
+
+-- 組織の管理者のメールアドレス（app_users は RLS で直接読めないため）
+CREATE OR REPLACE FUNCTION org_admin_emails(p_org_id UUID)
+RETURNS TABLE(email TEXT)
+LANGUAGE sql STABLE SECURITY DEFINER SET search_path = public
+AS $$
+    SELECT DISTINCT u.email
+    FROM user_organizations uo
+    JOIN app_users u ON u.id = uo.user_id
+    WHERE uo.organization_id = p_org_id
+      AND uo.role = 'admin'
+      AND u.email IS NOT NULL AND u.email <> ''
+      AND u.deleted_at IS NULL;
+$$;
+
+-- パスワード再設定トークン（平文は保存せず SHA-256 のみ）
+CREATE TABLE password_reset_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    credential_id UUID NOT NULL REFERENCES password_credentials(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 認証前に使うため SECURITY DEFINER 関数経由でのみ操作する
+ALTER TABLE password_reset_tokens ENABLE ROW LEVEL SECURITY;
+
+-- 再設定トークンを発行し、送信先メールアドレスを返す（該当ユーザーなし / メール未登録なら 0 行）
+CREATE OR REPLACE FUNCTION create_password_reset(
+    p_org_id UUID,
+    p_username TEXT,
+    p_token_hash TEXT,
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS TABLE(email TEXT)
+LANGUAGE sql SECURITY DEFINER SET search_path = public
+AS $$
+    WITH target AS (
+        SELECT pc.id, u.email
+        FROM password_credentials pc
+        JOIN app_users u ON u.id = pc.app_user_id
+        WHERE pc.organization_id = p_org_id
+          AND pc.username = p_username
+          AND pc.enabled = true
+          AND u.deleted_at IS NULL
+          AND u.email IS NOT NULL AND u.email <> ''
+    ), inserted AS (
+        INSERT INTO password_reset_tokens (credential_id, token_hash, expires_at)
+        SELECT id, p_token_hash, p_expires_at FROM target
+        RETURNING credential_id
+    )
+    SELECT t.email FROM target t JOIN inserted i ON i.credential_id = t.id;
+$$;
+
+-- トークンを使ってパスワードを更新（同じ資格情報の他のトークンも無効化）。成功時は organization_id
+CREATE OR REPLACE FUNCTION consume_password_reset(p_token_hash TEXT, p_password_hash TEXT)
+RETURNS TEXT
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = public
+AS $$
+DECLARE
+    v_credential_id UUID;
+    v_org_id UUID;
+BEGIN
+    UPDATE password_reset_tokens
+    SET used_at = NOW()
+    WHERE token_hash = p_token_hash AND used_at IS NULL AND expires_at > NOW()
+    RETURNING credential_id INTO v_credential_id;
+
+    IF v_credential_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    UPDATE password_reset_tokens SET used_at = NOW()
+    WHERE credential_id = v_credential_id AND used_at IS NULL;
+
+    UPDATE password_credentials
+    SET password_hash = p_password_hash, updated_at = NOW()
+    WHERE id = v_credential_id AND enabled = true
+    RETURNING organization_id INTO v_org_id;
+
+    RETURN v_org_id::text;
+END;
+$$;
@@ -0,0 +1,24 @@
+-- Migration: LINE WORKS channel for notifications
+-- 組織の LINE WORKS Bot（bot_configs）から、LINE WORKS でログインしたことのあるメンバー（oauth_accounts）へ個別に送る。
+
+-- 通知に使う Bot（NULL なら組織の最初の有効な LINE WORKS Bot）
+ALTER TABLE notification_settings
+    ADD COLUMN lineworks_bot_config_id UUID REFERENCES bot_configs(id) ON DELETE SET NULL;
+
+-- 組織メンバーの LINE WORKS ユーザー ID（有効な LINE WORKS Bot がない組織は 0 行）
+CREATE OR REPLACE FUNCTION org_lineworks_user_ids(p_org_id UUID, p_admin_only BOOLEAN DEFAULT FALSE)
+RETURNS TABLE(user_id TEXT)
+LANGUAGE sql STABLE SECURITY DEFINER SET search_path = public
+AS $$
+    SELECT DISTINCT oa.provider_account_id
+    FROM user_organizations uo
+    JOIN app_users u ON u.id = uo.user_id
+    JOIN oauth_accounts oa ON oa.app_user_id = u.id AND oa.provider = 'lineworks'
+    WHERE uo.organization_id = p_org_id
+      AND (NOT p_admin_only OR uo.role = 'admin')
+      AND u.deleted_at IS NULL
+      AND EXISTS (
+          SELECT 1 FROM bot_configs b
+          WHERE b.organization_id = p_org_id AND b.provider = 'lineworks' AND b.enabled = TRUE
+      );
+$$;
@@ -0,0 +1,24 @@
+-- Migration: Slack / Discord incoming webhooks for notifications
+-- 組織ごとに登録した Webhook へ通知を送る（バックオフィス向け）。URL はトークンを含むため暗号化して保存する。
+
+CREATE TABLE notification_webhooks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL CHECK (provider IN ('slack', 'discord')),
+    name TEXT NOT NULL,
+    url_encrypted TEXT NOT NULL,             -- AES-256-GCM encrypted
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_notification_webhooks_org ON notification_webhooks(organization_id) WHERE enabled = TRUE;
+
+ALTER TABLE notification_webhooks ENABLE ROW LEVEL SECURITY;
+ALTER TABLE notification_webhooks FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON notification_webhooks
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON notification_webhooks TO rust_logi_app;
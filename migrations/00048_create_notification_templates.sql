@@ -0,0 +1,23 @@
+-- Migration: Per-organization notification templates
+-- 組み込みテンプレート（src/notifications/template.rs）の件名・本文を組織ごとに上書きする。
+-- channel が空文字なら全チャネル共通、指定するとそのチャネルだけ（チャネル指定が優先）。
+
+CREATE TABLE notification_templates (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    template_key TEXT NOT NULL,
+    channel TEXT NOT NULL DEFAULT '',
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, template_key, channel)
+);
+
+ALTER TABLE notification_templates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE notification_templates FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON notification_templates
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON notification_templates TO rust_logi_app;
@@ -0,0 +1,48 @@
+-- Migration: In-app notification feed
+-- Web UI のベルアイコン用。外部チャネルと同じ Notifier::send から、宛先ユーザーごとに1行書く。
+-- INSERT 時に pg_notify するので、コミットされた通知だけが各インスタンスの WatchNotifications に届く。
+
+CREATE TABLE notifications (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES app_users(id) ON DELETE CASCADE,
+    template TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_notifications_user ON notifications(organization_id, user_id, id DESC);
+CREATE INDEX idx_notifications_unread ON notifications(organization_id, user_id) WHERE read_at IS NULL;
+
+ALTER TABLE notifications ENABLE ROW LEVEL SECURITY;
+ALTER TABLE notifications FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON notifications
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON notifications TO rust_logi_app;
+GRANT USAGE ON SEQUENCE notifications_id_seq TO rust_logi_app;
+
+CREATE OR REPLACE FUNCTION notify_in_app_notification()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    PERFORM pg_notify(
+        'in_app_notifications',
+        json_build_object(
+            'id', NEW.id,
+            'organization_id', NEW.organization_id,
+            'user_id', NEW.user_id
+        )::text
+    );
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER notifications_notify
+    AFTER INSERT ON notifications
+    FOR EACH ROW EXECUTE FUNCTION notify_in_app_notification();
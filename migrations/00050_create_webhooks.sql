@@ -0,0 +1,55 @@
+-- Migration: Tenant webhooks
+-- 組織が登録したエンドポイントへ rust-logi のイベント（outbox と同じイベント）を HMAC-SHA256 署名付きで POST する。
+-- outbox の webhooks 配送先が、購読しているエンドポイントごとに webhook_deliveries を1行作り、job（webhooks.deliver）で送る。
+
+CREATE TABLE webhook_endpoints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    event_types TEXT[] NOT NULL DEFAULT '{}',   -- 空なら全イベント
+    secret_encrypted TEXT NOT NULL,             -- AES-256-GCM encrypted (署名キー)
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_endpoints_org ON webhook_endpoints(organization_id) WHERE enabled = TRUE;
+
+ALTER TABLE webhook_endpoints ENABLE ROW LEVEL SECURITY;
+ALTER TABLE webhook_endpoints FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON webhook_endpoints
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON webhook_endpoints TO rust_logi_app;
+
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    outbox_id BIGINT,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+    -- outbox は at-least-once なので同じイベントを二重に作らない
+    UNIQUE (endpoint_id, outbox_id)
+);
+
+CREATE INDEX idx_webhook_deliveries_org ON webhook_deliveries(organization_id, id DESC);
+
+ALTER TABLE webhook_deliveries ENABLE ROW LEVEL SECURITY;
+ALTER TABLE webhook_deliveries FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON webhook_deliveries
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON webhook_deliveries TO rust_logi_app;
+GRANT USAGE ON SEQUENCE webhook_deliveries_id_seq TO rust_logi_app;
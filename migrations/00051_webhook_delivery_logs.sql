@@ -0,0 +1,36 @@
+-- Migration: Webhook delivery logs and auto-disable
+-- 送信のたびに webhook_delivery_attempts に結果（ステータスコード、エラー、レスポンス先頭、所要時間）を残す。
+-- 連続して失敗し続けるエンドポイントは自動で無効化する（disabled_reason を残し、管理者が再有効化する）。
+
+ALTER TABLE webhook_deliveries
+    ADD COLUMN last_status_code INTEGER,
+    ADD COLUMN last_error TEXT;
+
+ALTER TABLE webhook_endpoints
+    ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN last_success_at TIMESTAMPTZ,
+    ADD COLUMN disabled_reason TEXT;
+
+CREATE TABLE webhook_delivery_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    delivery_id BIGINT NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    status_code INTEGER,                 -- 接続できなかった場合は NULL
+    error TEXT,
+    response_body TEXT,                  -- 先頭 1KB まで
+    duration_ms INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_delivery_attempts_delivery ON webhook_delivery_attempts(delivery_id, id);
+
+ALTER TABLE webhook_delivery_attempts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE webhook_delivery_attempts FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON webhook_delivery_attempts
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON webhook_delivery_attempts TO rust_logi_app;
+GRANT USAGE ON SEQUENCE webhook_delivery_attempts_id_seq TO rust_logi_app;
@@ -0,0 +1,31 @@
+-- Migration: SMS channel for urgent notifications
+-- 緊急通知（DVR イベント等）を組織が登録した電話番号へ SMS で送る。
+-- SMS は従量課金のため組織ごとに月間上限（sms_monthly_limit、0 なら SMS 無効）を設け、
+-- 送信前に sms_usage を原子的に加算して上限を超える分は送らない（notification_deliveries は skipped）。
+
+ALTER TABLE notification_settings
+    ADD COLUMN sms_recipients TEXT[] NOT NULL DEFAULT '{}',   -- E.164（例: +819012345678）
+    ADD COLUMN sms_monthly_limit INTEGER NOT NULL DEFAULT 0 CHECK (sms_monthly_limit >= 0);
+
+-- 上限超過などで送らなかった宛先
+ALTER TABLE notification_deliveries DROP CONSTRAINT notification_deliveries_status_check;
+ALTER TABLE notification_deliveries ADD CONSTRAINT notification_deliveries_status_check
+    CHECK (status IN ('pending', 'sent', 'failed', 'skipped'));
+
+-- 月ごとの SMS 送信数（月は JST の月初日）
+CREATE TABLE sms_usage (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    month DATE NOT NULL,
+    sent_count INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, month)
+);
+
+ALTER TABLE sms_usage ENABLE ROW LEVEL SECURITY;
+ALTER TABLE sms_usage FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON sms_usage
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON sms_usage TO rust_logi_app;
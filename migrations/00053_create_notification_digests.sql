@@ -0,0 +1,40 @@
+-- Migration: Scheduled digest notifications
+-- 個別の通知の代わりに、日次 / 週次で新規車検証・期限間近の車両・失敗した同期・在庫切れ間近の備品を
+-- 1通にまとめて送る（購読したユーザーごとにメール + アプリ内通知）。
+
+-- ユーザーごとの購読（行がなければ購読しない）
+CREATE TABLE notification_digest_subscriptions (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES app_users(id) ON DELETE CASCADE,
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, user_id)
+);
+
+ALTER TABLE notification_digest_subscriptions ENABLE ROW LEVEL SECURITY;
+ALTER TABLE notification_digest_subscriptions FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON notification_digest_subscriptions
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON notification_digest_subscriptions TO rust_logi_app;
+
+-- 数量がこの値以下の組織備品を「在庫切れ間近」としてまとめに載せる
+ALTER TABLE notification_settings
+    ADD COLUMN digest_low_stock_threshold INTEGER NOT NULL DEFAULT 0;
+
+-- 購読中のメンバー（app_users は RLS で直接読めないため）。email は未登録なら NULL
+CREATE OR REPLACE FUNCTION org_digest_recipients(p_org_id UUID, p_frequency TEXT)
+RETURNS TABLE(user_id TEXT, email TEXT)
+LANGUAGE sql STABLE SECURITY DEFINER SET search_path = public
+AS $$
+    SELECT u.id::text, NULLIF(u.email, '')
+    FROM notification_digest_subscriptions s
+    JOIN user_organizations uo ON uo.user_id = s.user_id AND uo.organization_id = s.organization_id
+    JOIN app_users u ON u.id = s.user_id
+    WHERE s.organization_id = p_org_id
+      AND s.frequency = p_frequency
+      AND u.deleted_at IS NULL;
+$$;
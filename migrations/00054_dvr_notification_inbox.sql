@@ -0,0 +1,29 @@
+-- Migration: DVR notification inbox (severity, acknowledgement)
+-- 一覧 RPC の絞り込み（車両・重要度・期間）とキーセットページネーション、対応済み（誰が・いつ・メモ）の記録。
+
+-- ページネーション・Acknowledge 用の連番（既存行にも採番される）
+ALTER TABLE dvr_notifications ADD COLUMN id BIGSERIAL;
+ALTER TABLE dvr_notifications ADD CONSTRAINT dvr_notifications_id_key UNIQUE (id);
+GRANT USAGE ON SEQUENCE dvr_notifications_id_seq TO rust_logi_app;
+
+-- イベント種別から重要度を決める（critical / warning / info）
+CREATE OR REPLACE FUNCTION dvr_event_severity(p_event_type TEXT)
+RETURNS TEXT
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT CASE
+        WHEN p_event_type ~ '(衝突|衝撃|事故|緊急|SOS)' THEN 'critical'
+        WHEN p_event_type ~ '(急ブレーキ|急減速|急加速|急発進|急ハンドル|速度超過|居眠り|脇見|ふらつき|車間)' THEN 'warning'
+        ELSE 'info'
+    END;
+$$;
+
+ALTER TABLE dvr_notifications
+    ADD COLUMN severity TEXT GENERATED ALWAYS AS (dvr_event_severity(event_type)) STORED,
+    ADD COLUMN acknowledged_at TIMESTAMPTZ,
+    ADD COLUMN acknowledged_by UUID REFERENCES app_users(id) ON DELETE SET NULL,
+    ADD COLUMN acknowledgement_note TEXT;
+
+CREATE INDEX idx_dvr_notifications_inbox ON dvr_notifications(organization_id, dvr_datetime DESC, id DESC);
+CREATE INDEX idx_dvr_notifications_unacknowledged ON dvr_notifications(organization_id, id)
+    WHERE acknowledged_at IS NULL;
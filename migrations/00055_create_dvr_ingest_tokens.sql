@@ -0,0 +1,34 @@
+-- Migration: Inbound DVR webhook ingestion
+-- DVR ベンダーの Webhook を POST /ingest/dvr で直接受ける（ブラウザ経由のポーリング中継が不要になる）。
+-- 認証は組織ごとの取り込みトークン（平文は発行時に1度だけ返し、SHA-256 のみ保存）。
+
+CREATE TABLE dvr_ingest_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    token_hint TEXT NOT NULL,              -- 末尾4文字（一覧表示用）
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE dvr_ingest_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE dvr_ingest_tokens FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON dvr_ingest_tokens
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON dvr_ingest_tokens TO rust_logi_app;
+
+-- 認証前（組織未確定）にトークンから組織を引く。見つかれば last_used_at を更新
+CREATE OR REPLACE FUNCTION resolve_dvr_ingest_token(p_token_hash TEXT)
+RETURNS TEXT
+LANGUAGE sql SECURITY DEFINER SET search_path = public
+AS $$
+    UPDATE dvr_ingest_tokens SET last_used_at = NOW()
+    WHERE token_hash = p_token_hash
+    RETURNING organization_id::text;
+$$;
+
+GRANT EXECUTE ON FUNCTION resolve_dvr_ingest_token(TEXT) TO rust_logi_app;
@@ -0,0 +1,10 @@
+-- Migration: DVR video archive
+-- DVR 側の保存期間が短いため、保存した mp4 を files に登録して通知から参照する（FilesService で取得できる）。
+-- 最終試行まで失敗した場合は download_status = 'failed' とエラーを残す（RetryPendingDownloads で再登録できる）。
+
+ALTER TABLE dvr_notifications
+    ADD COLUMN file_uuid UUID REFERENCES files(uuid) ON DELETE SET NULL,
+    ADD COLUMN archived_at TIMESTAMPTZ,
+    ADD COLUMN download_error TEXT;
+
+CREATE INDEX idx_dvr_notifications_file_uuid ON dvr_notifications(file_uuid) WHERE file_uuid IS NOT NULL;
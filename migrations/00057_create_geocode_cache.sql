@@ -0,0 +1,18 @@
+-- Migration: Reverse geocoding cache
+-- GPS 座標 → 住所の結果（組織共通、1/10000 度 ≒ 11m 単位）。見つからなかった地点も address = NULL で保存し、
+-- 事業者（Nominatim / Google）への再問い合わせを避ける。住所自体は組織のデータではないので RLS なし。
+
+CREATE TABLE geocode_cache (
+    lat_e4 INTEGER NOT NULL,
+    lon_e4 INTEGER NOT NULL,
+    address TEXT,
+    provider TEXT NOT NULL,
+    resolved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (lat_e4, lon_e4)
+);
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON geocode_cache TO rust_logi_app;
+
+-- 住所の埋め戻し対象（address_disp_c が空で GPS あり）
+CREATE INDEX idx_dtakologs_missing_address ON dtakologs(organization_id, gps_latitude, gps_longitude)
+    WHERE (address_disp_c IS NULL OR address_disp_c = '') AND gps_latitude <> 0;
@@ -0,0 +1,16 @@
+-- Migration: Weather enrichment
+-- Open-Meteo の毎時データを地点（1/100 度 ≒ 1km）× JST の日付でキャッシュする（組織共通、RLS なし）。
+-- DVR 通知には発生時刻・地点の気象（WeatherConditions の JSON）を保存する。
+
+CREATE TABLE weather_cache (
+    lat_e2 INTEGER NOT NULL,
+    lon_e2 INTEGER NOT NULL,
+    date DATE NOT NULL,
+    hourly JSONB NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (lat_e2, lon_e2, date)
+);
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON weather_cache TO rust_logi_app;
+
+ALTER TABLE dvr_notifications ADD COLUMN weather JSONB;
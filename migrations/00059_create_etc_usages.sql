@@ -0,0 +1,37 @@
+-- Migration: ETC usage records (toll costs)
+-- ETC 利用明細 CSV の取り込み先。車両は ichiban_cars に紐づける（見つからなければ NULL）。
+-- 同じ明細（カード・出口日時・出口・料金）は取り込み直しても重複しない。
+
+CREATE TABLE etc_usages (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    entry_at TIMESTAMP,                      -- JST
+    exit_at TIMESTAMP NOT NULL,              -- JST
+    entry_ic TEXT NOT NULL DEFAULT '',
+    exit_ic TEXT NOT NULL DEFAULT '',
+    toll_before_discount INTEGER,
+    discount INTEGER,
+    toll INTEGER NOT NULL,
+    vehicle_class TEXT NOT NULL DEFAULT '',
+    plate_number TEXT NOT NULL DEFAULT '',
+    card_number TEXT NOT NULL DEFAULT '',
+    note TEXT NOT NULL DEFAULT '',
+    ichiban_car_id TEXT,
+    source_filename TEXT NOT NULL DEFAULT '',
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT etc_usages_unique UNIQUE (organization_id, card_number, exit_at, exit_ic, toll)
+);
+
+CREATE INDEX idx_etc_usages_exit_at ON etc_usages(organization_id, exit_at DESC, id DESC);
+CREATE INDEX idx_etc_usages_car ON etc_usages(organization_id, ichiban_car_id, exit_at);
+CREATE INDEX idx_etc_usages_unmatched ON etc_usages(organization_id, card_number) WHERE ichiban_car_id IS NULL;
+
+ALTER TABLE etc_usages ENABLE ROW LEVEL SECURITY;
+ALTER TABLE etc_usages FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON etc_usages
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON etc_usages TO rust_logi_app;
+GRANT USAGE ON SEQUENCE etc_usages_id_seq TO rust_logi_app;
@@ -0,0 +1,34 @@
+-- Migration: Fuel card transactions
+-- 給油カードの利用明細 CSV の取り込み先。車両は ichiban_cars に紐づける（見つからなければ NULL）。
+-- odometer_km は CSV にメーター値があるときだけ入り、なければ燃費の集計時に dtakologs から引く。
+
+CREATE TABLE fuel_transactions (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    fueled_at TIMESTAMP NOT NULL,            -- JST
+    station TEXT NOT NULL DEFAULT '',
+    product TEXT NOT NULL DEFAULT '',
+    quantity NUMERIC(10, 2) NOT NULL,        -- リットル
+    unit_price NUMERIC(10, 2),
+    amount INTEGER NOT NULL,                 -- 円
+    plate_number TEXT NOT NULL DEFAULT '',
+    card_number TEXT NOT NULL DEFAULT '',
+    odometer_km NUMERIC(10, 1),
+    ichiban_car_id TEXT,
+    source_filename TEXT NOT NULL DEFAULT '',
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fuel_transactions_unique UNIQUE (organization_id, card_number, fueled_at, product, quantity, amount)
+);
+
+CREATE INDEX idx_fuel_transactions_car ON fuel_transactions(organization_id, ichiban_car_id, fueled_at);
+CREATE INDEX idx_fuel_transactions_fueled_at ON fuel_transactions(organization_id, fueled_at DESC, id DESC);
+
+ALTER TABLE fuel_transactions ENABLE ROW LEVEL SECURITY;
+ALTER TABLE fuel_transactions FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON fuel_transactions
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON fuel_transactions TO rust_logi_app;
+GRANT USAGE ON SEQUENCE fuel_transactions_id_seq TO rust_logi_app;
@@ -0,0 +1,51 @@
+-- Migration: Reports (PDF / XLSX)
+-- report_runs: 作成したレポート（作成は reports.generate job、ファイルは files に保存）
+-- report_schedules: 定期作成（scheduled_tasks の reports.scheduled.*）の形式・対象期間・送信先
+
+CREATE TABLE report_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    report_kind TEXT NOT NULL,
+    format TEXT NOT NULL CHECK (format IN ('pdf', 'xlsx')),
+    period_from DATE NOT NULL,
+    period_to DATE NOT NULL,                 -- 含む
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'completed', 'failed')),
+    file_uuid UUID REFERENCES files(uuid) ON DELETE SET NULL,
+    row_count INTEGER,
+    error TEXT,
+    requested_by UUID,                       -- app_users.id（定期作成は NULL）
+    recipients TEXT[] NOT NULL DEFAULT '{}', -- 作成後にリンクを送るメールアドレス
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_report_runs_created ON report_runs(organization_id, created_at DESC, id DESC);
+
+ALTER TABLE report_runs ENABLE ROW LEVEL SECURITY;
+ALTER TABLE report_runs FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON report_runs
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON report_runs TO rust_logi_app;
+
+CREATE TABLE report_schedules (
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    report_kind TEXT NOT NULL,
+    format TEXT NOT NULL DEFAULT 'xlsx' CHECK (format IN ('pdf', 'xlsx')),
+    period TEXT NOT NULL DEFAULT 'previous_month'
+        CHECK (period IN ('previous_day', 'previous_week', 'previous_month')),
+    recipients TEXT[] NOT NULL DEFAULT '{}',  -- 空なら組織の管理者
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, report_kind)
+);
+
+ALTER TABLE report_schedules ENABLE ROW LEVEL SECURITY;
+ALTER TABLE report_schedules FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON report_schedules
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON report_schedules TO rust_logi_app;
@@ -0,0 +1,4 @@
+-- Migration: 部門ごとのレポート（monthly_compliance）
+-- report_runs.branch: ichiban_cars.bumon_code_id で絞り込んだときの部門コード（NULL は全部門）
+
+ALTER TABLE report_runs ADD COLUMN branch TEXT;
@@ -0,0 +1,176 @@
+-- Migration: Platform admin (app_users.is_superadmin) stats
+-- 運用者向けの組織横断の集計（行数・ストレージ容量・API 呼び出し数・エラー率）。
+-- 集計は RLS をまたぐので SECURITY DEFINER 関数経由で行う。
+
+-- files.size_bytes: 保存時のバイト数（既存の blob は base64 の長さから、DVR 動画は dvr_notifications から埋める）
+ALTER TABLE files ADD COLUMN size_bytes BIGINT;
+
+UPDATE files
+SET size_bytes = length(blob) / 4 * 3
+    - CASE WHEN blob LIKE '%==' THEN 2 WHEN blob LIKE '%=' THEN 1 ELSE 0 END
+WHERE blob IS NOT NULL AND size_bytes IS NULL;
+
+UPDATE files f
+SET size_bytes = d.file_size_bytes
+FROM dvr_notifications d
+WHERE d.file_uuid = f.uuid AND d.file_size_bytes IS NOT NULL AND f.size_bytes IS NULL;
+
+-- api_usage_hourly: 組織 × 1 時間ごとの API 呼び出し数（ApiUsageLayer がまとめて書き込む）
+CREATE TABLE api_usage_hourly (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    hour TIMESTAMPTZ NOT NULL,
+    request_count BIGINT NOT NULL DEFAULT 0,
+    error_count BIGINT NOT NULL DEFAULT 0,        -- OK 以外（HTTP 4xx/5xx を含む）
+    server_error_count BIGINT NOT NULL DEFAULT 0, -- Internal / Unavailable / Unknown / DataLoss / DeadlineExceeded、HTTP 5xx
+    PRIMARY KEY (organization_id, hour)
+);
+
+CREATE INDEX idx_api_usage_hourly_hour ON api_usage_hourly(hour);
+
+ALTER TABLE api_usage_hourly ENABLE ROW LEVEL SECURITY;
+ALTER TABLE api_usage_hourly FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON api_usage_hourly
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT ON api_usage_hourly TO rust_logi_app;
+
+-- 複数組織分をまとめて加算（存在しない組織は無視）
+CREATE OR REPLACE FUNCTION record_api_usage(
+    p_organization_ids UUID[],
+    p_hours TIMESTAMPTZ[],
+    p_requests BIGINT[],
+    p_errors BIGINT[],
+    p_server_errors BIGINT[]
+)
+RETURNS VOID
+LANGUAGE sql SECURITY DEFINER VOLATILE AS $$
+    INSERT INTO api_usage_hourly (organization_id, hour, request_count, error_count, server_error_count)
+    SELECT u.organization_id, u.hour, u.requests, u.errors, u.server_errors
+    FROM unnest(p_organization_ids, p_hours, p_requests, p_errors, p_server_errors)
+         AS u(organization_id, hour, requests, errors, server_errors)
+    WHERE EXISTS (SELECT 1 FROM organizations o WHERE o.id = u.organization_id)
+    ON CONFLICT (organization_id, hour) DO UPDATE
+    SET request_count = api_usage_hourly.request_count + EXCLUDED.request_count,
+        error_count = api_usage_hourly.error_count + EXCLUDED.error_count,
+        server_error_count = api_usage_hourly.server_error_count + EXCLUDED.server_error_count;
+$$;
+
+CREATE OR REPLACE FUNCTION is_platform_admin(p_user_id UUID)
+RETURNS BOOLEAN
+LANGUAGE sql SECURITY DEFINER STABLE AS $$
+    SELECT COALESCE(
+        (SELECT is_superadmin FROM app_users WHERE id = p_user_id AND deleted_at IS NULL),
+        false
+    );
+$$;
+
+-- 組織ごとの行数・ストレージ・API 呼び出し（p_since 以降）
+CREATE OR REPLACE FUNCTION platform_tenant_stats(p_since TIMESTAMPTZ)
+RETURNS TABLE(
+    organization_id UUID,
+    name TEXT,
+    slug TEXT,
+    created_at TIMESTAMPTZ,
+    member_count BIGINT,
+    row_counts JSONB,
+    file_count BIGINT,
+    storage_bytes BIGINT,
+    files_without_size BIGINT,
+    storage_class_bytes JSONB,
+    api_requests BIGINT,
+    api_errors BIGINT,
+    api_server_errors BIGINT,
+    last_request_at TIMESTAMPTZ
+)
+LANGUAGE sql SECURITY DEFINER STABLE AS $$
+    WITH members AS (
+        SELECT uo.organization_id, COUNT(*) AS n FROM user_organizations uo GROUP BY uo.organization_id
+    ),
+    counts AS (
+        SELECT t.organization_id, jsonb_object_agg(t.table_name, t.n) AS row_counts
+        FROM (
+            SELECT x.organization_id, 'car_inspection' AS table_name, COUNT(*) AS n FROM car_inspection x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'dtakologs', COUNT(*) FROM dtakologs x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'files', COUNT(*) FROM files x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'dvr_notifications', COUNT(*) FROM dvr_notifications x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'cam_files', COUNT(*) FROM cam_files x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'items', COUNT(*) FROM items x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'jobs', COUNT(*) FROM jobs x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'notification_deliveries', COUNT(*) FROM notification_deliveries x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'etc_usages', COUNT(*) FROM etc_usages x GROUP BY 1
+            UNION ALL SELECT x.organization_id, 'fuel_transactions', COUNT(*) FROM fuel_transactions x GROUP BY 1
+        ) t
+        GROUP BY t.organization_id
+    ),
+    storage AS (
+        SELECT s.organization_id,
+               SUM(s.file_count)::bigint AS file_count,
+               SUM(s.bytes)::bigint AS storage_bytes,
+               SUM(s.unknown)::bigint AS files_without_size,
+               jsonb_object_agg(s.storage_class, s.bytes) AS storage_class_bytes
+        FROM (
+            SELECT f.organization_id,
+                   CASE WHEN f.s3_key IS NULL THEN 'DATABASE' ELSE COALESCE(f.storage_class, 'STANDARD') END
+                       AS storage_class,
+                   COUNT(*) AS file_count,
+                   COALESCE(SUM(f.size_bytes), 0) AS bytes,
+                   COUNT(*) FILTER (WHERE f.size_bytes IS NULL) AS unknown
+            FROM files f
+            WHERE f.deleted_at IS NULL
+            GROUP BY 1, 2
+        ) s
+        GROUP BY s.organization_id
+    ),
+    api AS (
+        SELECT a.organization_id,
+               SUM(a.request_count)::bigint AS requests,
+               SUM(a.error_count)::bigint AS errors,
+               SUM(a.server_error_count)::bigint AS server_errors,
+               MAX(a.hour) AS last_hour
+        FROM api_usage_hourly a
+        WHERE a.hour >= date_trunc('hour', p_since)
+        GROUP BY a.organization_id
+    )
+    SELECT o.id, o.name, o.slug, o.created_at,
+           COALESCE(m.n, 0),
+           COALESCE(c.row_counts, '{}'::jsonb),
+           COALESCE(s.file_count, 0),
+           COALESCE(s.storage_bytes, 0),
+           COALESCE(s.files_without_size, 0),
+           COALESCE(s.storage_class_bytes, '{}'::jsonb),
+           COALESCE(a.requests, 0),
+           COALESCE(a.errors, 0),
+           COALESCE(a.server_errors, 0),
+           a.last_hour
+    FROM organizations o
+    LEFT JOIN members m ON m.organization_id = o.id
+    LEFT JOIN counts c ON c.organization_id = o.id
+    LEFT JOIN storage s ON s.organization_id = o.id
+    LEFT JOIN api a ON a.organization_id = o.id
+    WHERE o.deleted_at IS NULL
+    ORDER BY COALESCE(s.storage_bytes, 0) DESC, o.name;
+$$;
+
+-- 組織の日ごと（JST）の API 呼び出し
+CREATE OR REPLACE FUNCTION platform_api_usage_daily(p_organization_id UUID, p_since TIMESTAMPTZ)
+RETURNS TABLE(day DATE, requests BIGINT, errors BIGINT, server_errors BIGINT)
+LANGUAGE sql SECURITY DEFINER STABLE AS $$
+    SELECT (a.hour AT TIME ZONE 'Asia/Tokyo')::date,
+           SUM(a.request_count)::bigint,
+           SUM(a.error_count)::bigint,
+           SUM(a.server_error_count)::bigint
+    FROM api_usage_hourly a
+    WHERE a.organization_id = p_organization_id AND a.hour >= date_trunc('hour', p_since)
+    GROUP BY 1
+    ORDER BY 1;
+$$;
+
+REVOKE ALL ON FUNCTION record_api_usage(UUID[], TIMESTAMPTZ[], BIGINT[], BIGINT[], BIGINT[]) FROM PUBLIC;
+REVOKE ALL ON FUNCTION platform_tenant_stats(TIMESTAMPTZ) FROM PUBLIC;
+REVOKE ALL ON FUNCTION platform_api_usage_daily(UUID, TIMESTAMPTZ) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION record_api_usage(UUID[], TIMESTAMPTZ[], BIGINT[], BIGINT[], BIGINT[]) TO rust_logi_app;
+GRANT EXECUTE ON FUNCTION is_platform_admin(UUID) TO rust_logi_app;
+GRANT EXECUTE ON FUNCTION platform_tenant_stats(TIMESTAMPTZ) TO rust_logi_app;
+GRANT EXECUTE ON FUNCTION platform_api_usage_daily(UUID, TIMESTAMPTZ) TO rust_logi_app;
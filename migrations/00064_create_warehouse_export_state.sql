@@ -0,0 +1,29 @@
+-- Migration: Data warehouse export cursors
+-- 運行ログ・車検証・イベント（outbox）を BigQuery / Avro に差分エクスポートする位置を組織 × ソースごとに保持する。
+-- (cursor_ts, cursor_key) より後の行を次回エクスポートする（書き込み成功後にバッチごとに進める）。
+
+CREATE TABLE warehouse_export_state (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    source TEXT NOT NULL,                 -- 'dtakologs' / 'car_inspection' / 'events'
+    cursor_ts TIMESTAMPTZ,
+    cursor_key TEXT NOT NULL DEFAULT '',
+    exported_rows BIGINT NOT NULL DEFAULT 0,
+    last_exported_at TIMESTAMPTZ,
+    last_error TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, source)
+);
+
+ALTER TABLE warehouse_export_state ENABLE ROW LEVEL SECURITY;
+ALTER TABLE warehouse_export_state FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON warehouse_export_state
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON warehouse_export_state TO rust_logi_app;
+
+-- 差分の取り出し用（作成順・更新順）
+CREATE INDEX idx_dtakologs_created_at ON dtakologs(organization_id, created_at);
+CREATE INDEX idx_car_inspection_modified_at ON car_inspection(organization_id, modified_at);
+CREATE INDEX idx_outbox_created_at ON outbox(organization_id, created_at);
@@ -0,0 +1,79 @@
+-- Migration: Platform cost breakdown (AdminService.GetCostBreakdown)
+-- 組織ごとのクラウド費用の目安（ストレージクラス別の容量・ダウンロードによる egress・Flickr アップロード）。
+-- 単価は Rust 側（src/cost.rs）で掛ける。ここでは使用量だけを集計する。
+
+-- Flickr へのアップロード日時・バイト数（以前のアップロードは NULL）
+ALTER TABLE cam_files ADD COLUMN flickr_uploaded_at TIMESTAMPTZ;
+ALTER TABLE cam_files ADD COLUMN flickr_upload_bytes BIGINT;
+
+CREATE INDEX idx_cam_files_flickr_uploaded_at ON cam_files(organization_id, flickr_uploaded_at)
+    WHERE flickr_uploaded_at IS NOT NULL;
+
+-- 組織ごとの使用量（p_since 以降のダウンロード・アップロード）
+CREATE OR REPLACE FUNCTION platform_cost_usage(p_since TIMESTAMPTZ)
+RETURNS TABLE(
+    organization_id UUID,
+    name TEXT,
+    slug TEXT,
+    storage_class_bytes JSONB,   -- 現在の保存容量（クラス別、DATABASE は files.blob）
+    downloads BIGINT,            -- file_access_logs の件数
+    download_bytes BIGINT,       -- サイズ不明のファイルは含まない
+    retrieval_class_bytes JSONB, -- ダウンロード時のストレージクラス別バイト数（取り出し料金用）
+    flickr_uploads BIGINT,
+    flickr_upload_bytes BIGINT
+)
+LANGUAGE sql SECURITY DEFINER STABLE AS $$
+    WITH storage AS (
+        SELECT s.organization_id, jsonb_object_agg(s.storage_class, s.bytes) AS storage_class_bytes
+        FROM (
+            SELECT f.organization_id,
+                   CASE WHEN f.s3_key IS NULL THEN 'DATABASE' ELSE COALESCE(f.storage_class, 'STANDARD') END
+                       AS storage_class,
+                   COALESCE(SUM(f.size_bytes), 0) AS bytes
+            FROM files f
+            WHERE f.deleted_at IS NULL
+            GROUP BY 1, 2
+        ) s
+        GROUP BY s.organization_id
+    ),
+    access AS (
+        SELECT a.organization_id,
+               SUM(a.n)::bigint AS downloads,
+               SUM(a.bytes)::bigint AS download_bytes,
+               jsonb_object_agg(a.storage_class, a.bytes) AS retrieval_class_bytes
+        FROM (
+            SELECT l.organization_id,
+                   COALESCE(l.storage_class_at_access, 'STANDARD') AS storage_class,
+                   COUNT(*) AS n,
+                   COALESCE(SUM(f.size_bytes), 0) AS bytes
+            FROM file_access_logs l
+            JOIN files f ON f.uuid = l.file_uuid
+            WHERE l.accessed_at >= p_since
+            GROUP BY 1, 2
+        ) a
+        GROUP BY a.organization_id
+    ),
+    flickr AS (
+        SELECT c.organization_id,
+               COUNT(*) AS uploads,
+               COALESCE(SUM(c.flickr_upload_bytes), 0)::bigint AS bytes
+        FROM cam_files c
+        WHERE c.flickr_uploaded_at >= p_since
+        GROUP BY c.organization_id
+    )
+    SELECT o.id, o.name, o.slug,
+           COALESCE(s.storage_class_bytes, '{}'::jsonb),
+           COALESCE(a.downloads, 0),
+           COALESCE(a.download_bytes, 0),
+           COALESCE(a.retrieval_class_bytes, '{}'::jsonb),
+           COALESCE(fl.uploads, 0),
+           COALESCE(fl.bytes, 0)
+    FROM organizations o
+    LEFT JOIN storage s ON s.organization_id = o.id
+    LEFT JOIN access a ON a.organization_id = o.id
+    LEFT JOIN flickr fl ON fl.organization_id = o.id
+    WHERE o.deleted_at IS NULL;
+$$;
+
+REVOKE ALL ON FUNCTION platform_cost_usage(TIMESTAMPTZ) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION platform_cost_usage(TIMESTAMPTZ) TO rust_logi_app;
@@ -0,0 +1,80 @@
+-- Migration: KPI materialized views
+-- ダッシュボードで重い集計（車両ごとの最新運行ログ・車両ごとの最新の車検証）を materialized view にする。
+-- materialized view には RLS が効かないので、アプリは組織で絞った view（security_barrier）経由でだけ読む。
+-- 更新は refresh_kpi_view()（SECURITY DEFINER、所有者として REFRESH CONCURRENTLY）で行い、
+-- 最終更新時刻を kpi_view_refreshes に残す（古すぎる場合はアプリが元のテーブルを直接集計する）。
+
+-- 車両ごとの最新運行ログ
+CREATE MATERIALIZED VIEW mv_dtakologs_latest AS
+SELECT DISTINCT ON (organization_id, vehicle_cd) *
+FROM dtakologs
+ORDER BY organization_id, vehicle_cd, data_date_time DESC;
+
+-- REFRESH CONCURRENTLY に一意インデックスが必要
+CREATE UNIQUE INDEX idx_mv_dtakologs_latest_key ON mv_dtakologs_latest(organization_id, vehicle_cd);
+
+CREATE VIEW dtakologs_latest WITH (security_barrier) AS
+SELECT * FROM mv_dtakologs_latest
+WHERE organization_id::text = current_setting('app.current_organization_id', true);
+
+-- 車両（CarId）ごとの最新の車検証（ListCurrentCarInspections と同じ順）
+CREATE MATERIALIZED VIEW mv_car_inspection_current AS
+SELECT DISTINCT ON (ci.organization_id, ci."CarId")
+    ci.organization_id,
+    ci."CarId" AS car_id,
+    ci.id AS car_inspection_id,
+    ci."ElectCertMgNo" AS elect_cert_mg_no,
+    ci."TwodimensionCodeInfoValidPeriodExpirdate" AS expirdate   -- YYMMDD
+FROM car_inspection ci
+ORDER BY ci.organization_id, ci."CarId",
+         ci."TwodimensionCodeInfoValidPeriodExpirdate" DESC,
+         ci.created_at DESC;
+
+CREATE UNIQUE INDEX idx_mv_car_inspection_current_key ON mv_car_inspection_current(organization_id, car_id);
+
+-- 期限の区分（期限切れ / 30・60・90 日以内）はアプリ側で参照時の日付から決める
+CREATE VIEW car_inspection_current WITH (security_barrier) AS
+SELECT * FROM mv_car_inspection_current
+WHERE organization_id::text = current_setting('app.current_organization_id', true);
+
+REVOKE ALL ON mv_dtakologs_latest FROM PUBLIC;
+REVOKE ALL ON mv_car_inspection_current FROM PUBLIC;
+GRANT SELECT ON dtakologs_latest TO rust_logi_app;
+GRANT SELECT ON car_inspection_current TO rust_logi_app;
+
+-- 最終更新（組織をまたぐ情報は持たないので RLS なし）
+CREATE TABLE kpi_view_refreshes (
+    view_name TEXT PRIMARY KEY,
+    refreshed_at TIMESTAMPTZ NOT NULL,   -- 更新開始時刻（この時点のデータ）
+    duration_ms BIGINT NOT NULL
+);
+
+GRANT SELECT ON kpi_view_refreshes TO rust_logi_app;
+
+INSERT INTO kpi_view_refreshes (view_name, refreshed_at, duration_ms)
+VALUES ('mv_dtakologs_latest', NOW(), 0), ('mv_car_inspection_current', NOW(), 0);
+
+CREATE OR REPLACE FUNCTION refresh_kpi_view(p_view TEXT)
+RETURNS BIGINT
+LANGUAGE plpgsql SECURITY DEFINER VOLATILE AS $$
+DECLARE
+    v_started TIMESTAMPTZ := clock_timestamp();
+    v_duration_ms BIGINT;
+BEGIN
+    IF p_view NOT IN ('mv_dtakologs_latest', 'mv_car_inspection_current') THEN
+        RAISE EXCEPTION 'Unknown KPI view: %', p_view;
+    END IF;
+
+    EXECUTE format('REFRESH MATERIALIZED VIEW CONCURRENTLY %I', p_view);
+
+    v_duration_ms := (extract(epoch FROM clock_timestamp() - v_started) * 1000)::bigint;
+    INSERT INTO kpi_view_refreshes (view_name, refreshed_at, duration_ms)
+    VALUES (p_view, v_started, v_duration_ms)
+    ON CONFLICT (view_name) DO UPDATE
+        SET refreshed_at = EXCLUDED.refreshed_at, duration_ms = EXCLUDED.duration_ms;
+    RETURN v_duration_ms;
+END;
+$$;
+
+REVOKE ALL ON FUNCTION refresh_kpi_view(TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION refresh_kpi_view(TEXT) TO rust_logi_app;
@@ -0,0 +1,152 @@
+-- Migration: User data deletion / anonymization (AdminService.DeleteUserData)
+-- 個人情報の削除請求に対応する。ユーザーの個人データを全組織で決まった順に削除・匿名化し、
+-- 実行結果（手順ごとの件数）を user_data_deletions に残す。
+-- app_users の行は消さずに匿名化する（承認者・招待者・既読者などの参照は匿名のユーザーを指したまま残る）。
+
+CREATE TABLE user_data_deletions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,                   -- 匿名化済みの app_users.id
+    requested_by UUID,                       -- 実行した運用者（app_users.id）
+    report JSONB NOT NULL,                   -- [{step, table, action, affected}, ...]（個人情報は含めない）
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_data_deletions_user_id ON user_data_deletions(user_id);
+
+-- 運用者用の記録のため SECURITY DEFINER 関数経由でのみ書き込む
+ALTER TABLE user_data_deletions ENABLE ROW LEVEL SECURITY;
+
+-- 他のメンバーがいる組織で唯一の管理者になっている組織（先に管理者を移してから削除する）
+CREATE OR REPLACE FUNCTION user_sole_admin_organizations(p_user_id UUID)
+RETURNS SETOF UUID
+LANGUAGE sql SECURITY DEFINER STABLE AS $$
+    SELECT uo.organization_id
+    FROM user_organizations uo
+    WHERE uo.user_id = p_user_id
+      AND uo.role = 'admin'
+      AND NOT EXISTS (
+          SELECT 1 FROM user_organizations other
+          WHERE other.organization_id = uo.organization_id
+            AND other.user_id <> p_user_id
+            AND other.role = 'admin'
+      )
+      AND EXISTS (
+          SELECT 1 FROM user_organizations other
+          WHERE other.organization_id = uo.organization_id
+            AND other.user_id <> p_user_id
+      );
+$$;
+
+-- 削除・匿名化を順に実行し、手順ごとの件数を返す（ユーザーがいなければ 0 行）
+-- 呼び出し側のトランザクションで実行する（dry run はロールバック）
+CREATE OR REPLACE FUNCTION delete_user_data(p_user_id UUID, p_requested_by UUID)
+RETURNS TABLE(step INTEGER, table_name TEXT, action TEXT, affected BIGINT)
+LANGUAGE plpgsql SECURITY DEFINER VOLATILE AS $$
+DECLARE
+    v_email TEXT;
+    v_count BIGINT;
+    v_report JSONB := '[]'::jsonb;
+BEGIN
+    SELECT lower(u.email) INTO v_email FROM app_users u WHERE u.id = p_user_id FOR UPDATE;
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    -- 1-5: ログイン手段と本人宛てのデータ（削除）
+    step := 1; table_name := 'notification_digest_subscriptions'; action := 'delete';
+    DELETE FROM notification_digest_subscriptions s WHERE s.user_id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 2; table_name := 'notifications'; action := 'delete';
+    DELETE FROM notifications n WHERE n.user_id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 3; table_name := 'password_credentials'; action := 'delete';
+    DELETE FROM password_credentials c WHERE c.app_user_id = p_user_id;  -- password_reset_tokens は CASCADE
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 4; table_name := 'oauth_accounts'; action := 'delete';
+    DELETE FROM oauth_accounts oa WHERE oa.app_user_id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 5; table_name := 'items'; action := 'delete';
+    DELETE FROM items i WHERE i.owner_type = 'personal' AND i.user_id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    -- 6: 組織からの脱退
+    step := 6; table_name := 'user_organizations'; action := 'delete';
+    DELETE FROM user_organizations uo WHERE uo.user_id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    -- 7-10: 組織側の記録に残った連絡先（匿名化、行は残す）
+    step := 7; table_name := 'access_requests'; action := 'anonymize';
+    UPDATE access_requests ar
+    SET email = '', display_name = '', avatar_url = NULL, updated_at = NOW()
+    WHERE ar.user_id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 8; table_name := 'invitations'; action := 'anonymize';
+    UPDATE invitations inv SET email = ''
+    WHERE v_email IS NOT NULL AND lower(inv.email) = v_email;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 9; table_name := 'notification_deliveries'; action := 'anonymize';
+    UPDATE notification_deliveries d SET recipient = '', subject = '', body = ''
+    WHERE v_email IS NOT NULL AND lower(d.recipient) = v_email;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    step := 10; table_name := 'report_schedules'; action := 'anonymize';
+    UPDATE report_schedules rs
+    SET recipients = ARRAY(SELECT r FROM unnest(rs.recipients) r WHERE lower(r) <> v_email)
+    WHERE v_email IS NOT NULL AND EXISTS (SELECT 1 FROM unnest(rs.recipients) r WHERE lower(r) = v_email);
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    UPDATE report_runs rr
+    SET recipients = ARRAY(SELECT r FROM unnest(rr.recipients) r WHERE lower(r) <> v_email)
+    WHERE v_email IS NOT NULL AND EXISTS (SELECT 1 FROM unnest(rr.recipients) r WHERE lower(r) = v_email);
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := affected + v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    -- 11: 監査上の参照（ユーザー ID のみ、匿名化した app_users を指したまま残す）
+    step := 11; table_name := 'audit_references'; action := 'retain';
+    SELECT
+        (SELECT count(*) FROM access_requests ar WHERE ar.reviewed_by = p_user_id)
+      + (SELECT count(*) FROM invitations inv WHERE inv.invited_by = p_user_id OR inv.accepted_by = p_user_id)
+      + (SELECT count(*) FROM dvr_notifications dn WHERE dn.acknowledged_by = p_user_id)
+      + (SELECT count(*) FROM report_runs rr WHERE rr.requested_by = p_user_id)
+    INTO affected;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    -- 12: 本人（匿名化、ログイン不可）
+    step := 12; table_name := 'app_users'; action := 'anonymize';
+    UPDATE app_users u
+    SET email = NULL, display_name = 'Deleted user', avatar_url = NULL, is_superadmin = false,
+        deleted_at = COALESCE(u.deleted_at, NOW()), updated_at = NOW()
+    WHERE u.id = p_user_id;
+    GET DIAGNOSTICS v_count = ROW_COUNT; affected := v_count;
+    v_report := v_report || jsonb_build_object('step', step, 'table', table_name, 'action', action, 'affected', affected);
+    RETURN NEXT;
+
+    INSERT INTO user_data_deletions (user_id, requested_by, report)
+    VALUES (p_user_id, p_requested_by, v_report);
+END;
+$$;
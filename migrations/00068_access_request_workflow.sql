@@ -0,0 +1,42 @@
+-- Migration: Access request expiry / reminders, organization audit log
+-- 参加リクエストは expires_at を過ぎると期限切れ（status = 'expired'）。承認待ちが続くと管理者にリマインドを送る。
+-- 承認・却下・期限切れは audit_logs（組織の監査ログ）に残す。
+
+ALTER TABLE access_requests
+    ADD COLUMN expires_at TIMESTAMPTZ,
+    ADD COLUMN reminded_at TIMESTAMPTZ;
+
+-- 既存の承認待ちは今から 14 日（ACCESS_REQUEST_EXPIRY_DAYS の既定）
+UPDATE access_requests
+SET expires_at = GREATEST(created_at, NOW()) + INTERVAL '14 days'
+WHERE expires_at IS NULL;
+
+ALTER TABLE access_requests ALTER COLUMN expires_at SET NOT NULL;
+
+CREATE INDEX idx_access_requests_pending_expires
+    ON access_requests(organization_id, expires_at) WHERE status = 'pending';
+
+-- audit_logs: 組織の監査ログ（誰が・何を・どの対象に）。追記のみ
+CREATE TABLE audit_logs (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    actor_user_id UUID,                      -- app_users.id（定期実行などシステムによる操作は NULL）
+    action TEXT NOT NULL,                    -- 'access_request.approved' など
+    target_type TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_logs_org_created ON audit_logs(organization_id, created_at DESC);
+CREATE INDEX idx_audit_logs_target ON audit_logs(organization_id, target_type, target_id);
+
+ALTER TABLE audit_logs ENABLE ROW LEVEL SECURITY;
+ALTER TABLE audit_logs FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON audit_logs
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT ON audit_logs TO rust_logi_app;
+GRANT USAGE ON SEQUENCE audit_logs_id_seq TO rust_logi_app;
@@ -0,0 +1,38 @@
+-- Migration: Bot routing rules
+-- 通知の種類（テンプレートのキー、例: 'car_inspection.expiring'）ごとに送信に使う LINE WORKS Bot を選び、
+-- channel_id があればそのトークルームにも送る。ルールのない通知は notification_settings.lineworks_bot_config_id
+-- （未設定なら最初の有効な Bot）で送る。
+
+CREATE TABLE bot_routing_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,                    -- notification_deliveries.template
+    bot_config_id UUID NOT NULL REFERENCES bot_configs(id) ON DELETE CASCADE,
+    channel_id TEXT,                             -- LINE WORKS のトークルーム（channelId）、NULL ならメンバー個別のみ
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (organization_id, event_type)
+);
+
+CREATE INDEX idx_bot_routing_rules_bot ON bot_routing_rules(bot_config_id);
+
+ALTER TABLE bot_routing_rules ENABLE ROW LEVEL SECURITY;
+ALTER TABLE bot_routing_rules FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON bot_routing_rules
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON bot_routing_rules TO rust_logi_app;
+
+-- ユーザーの LINE WORKS ユーザー ID（Bot のテスト送信の既定の宛先、未連携なら NULL）
+CREATE OR REPLACE FUNCTION user_lineworks_user_id(p_user_id UUID)
+RETURNS TEXT
+LANGUAGE sql STABLE SECURITY DEFINER SET search_path = public
+AS $$
+    SELECT oa.provider_account_id
+    FROM oauth_accounts oa
+    WHERE oa.app_user_id = p_user_id AND oa.provider = 'lineworks'
+    ORDER BY oa.created_at DESC
+    LIMIT 1;
+$$;
@@ -0,0 +1,32 @@
+-- Migration: SSO client secret rotation
+-- RotateSsoClientSecret で新しい client_secret に切り替えたあとも、猶予期間（previous_secret_expires_at）の間は
+-- 古い secret でのコード交換を許す（プロバイダー側の切り替えが後になってもログインできなくならないように）。
+-- client_secret_version は secret を入れ替えるたびに増える。
+
+ALTER TABLE sso_provider_configs
+    ADD COLUMN client_secret_version INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN previous_client_secret_encrypted TEXT,
+    ADD COLUMN previous_secret_expires_at TIMESTAMPTZ,
+    ADD COLUMN secret_rotated_at TIMESTAMPTZ;
+
+-- 戻り値を増やすので作り直す（猶予期間を過ぎた古い secret は返さない）
+DROP FUNCTION IF EXISTS lookup_sso_config_for_login(TEXT, TEXT);
+
+CREATE FUNCTION lookup_sso_config_for_login(p_provider TEXT, p_external_org_id TEXT)
+RETURNS TABLE(client_id TEXT, client_secret_encrypted TEXT, organization_id TEXT, org_slug TEXT,
+              previous_client_secret_encrypted TEXT)
+LANGUAGE plpgsql SECURITY DEFINER AS $$
+BEGIN
+    RETURN QUERY
+    SELECT c.client_id, c.client_secret_encrypted, c.organization_id::text, o.slug,
+           CASE WHEN c.previous_secret_expires_at > NOW() THEN c.previous_client_secret_encrypted END
+    FROM sso_provider_configs c
+    JOIN organizations o ON o.id = c.organization_id
+    WHERE c.provider = p_provider
+      AND c.external_org_id = p_external_org_id
+      AND c.enabled = TRUE
+    LIMIT 1;
+END;
+$$;
+
+GRANT EXECUTE ON FUNCTION lookup_sso_config_for_login(TEXT, TEXT) TO rust_logi_app;
@@ -0,0 +1,81 @@
+-- Migration: Per-organization password policies
+-- パスワードの作成・変更時（招待の受諾、再設定、CLI）に組織のポリシー（長さ・文字種・再利用禁止）を適用し、
+-- ログイン時に有効期限（max_age_days）を確かめる。行がない組織は既定（8 文字以上のみ）。
+
+CREATE TABLE password_policies (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    min_length INTEGER NOT NULL DEFAULT 8 CHECK (min_length BETWEEN 8 AND 128),
+    require_uppercase BOOLEAN NOT NULL DEFAULT FALSE,
+    require_lowercase BOOLEAN NOT NULL DEFAULT FALSE,
+    require_digit BOOLEAN NOT NULL DEFAULT FALSE,
+    require_symbol BOOLEAN NOT NULL DEFAULT FALSE,
+    history_count INTEGER NOT NULL DEFAULT 0 CHECK (history_count BETWEEN 0 AND 24),  -- 直近 N 個と同じパスワードは不可
+    max_age_days INTEGER NOT NULL DEFAULT 0 CHECK (max_age_days BETWEEN 0 AND 3650),  -- 0 なら期限なし
+    updated_by UUID,                                                                 -- app_users.id
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 招待の受諾・再設定は認証前なので、organization を設定して読む（FORCE なし、password_credentials と同じ）
+ALTER TABLE password_policies ENABLE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation_policy ON password_policies
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE ON password_policies TO rust_logi_app;
+
+-- パスワードを変えた日時（有効期限の起点）
+ALTER TABLE password_credentials
+    ADD COLUMN password_changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+UPDATE password_credentials SET password_changed_at = updated_at;
+
+-- 過去のパスワード（ハッシュのみ、再利用禁止の判定用）
+CREATE TABLE password_history (
+    id BIGSERIAL PRIMARY KEY,
+    credential_id UUID NOT NULL REFERENCES password_credentials(id) ON DELETE CASCADE,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    password_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_password_history_credential ON password_history(credential_id, created_at DESC);
+
+ALTER TABLE password_history ENABLE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation_policy ON password_history
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT ON password_history TO rust_logi_app;
+
+-- password_hash が変わったら古いハッシュを履歴に移し、変更日時を更新する（consume_password_reset などの経路も含む）
+CREATE OR REPLACE FUNCTION record_password_change()
+RETURNS TRIGGER
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = public
+AS $$
+BEGIN
+    IF NEW.password_hash IS DISTINCT FROM OLD.password_hash THEN
+        INSERT INTO password_history (credential_id, organization_id, password_hash)
+        VALUES (OLD.id, OLD.organization_id, OLD.password_hash);
+        NEW.password_changed_at := NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER password_credentials_record_change
+    BEFORE UPDATE OF password_hash ON password_credentials
+    FOR EACH ROW EXECUTE FUNCTION record_password_change();
+
+-- 再設定トークンの対象（有効なトークンのみ）。パスワードを更新する前にポリシーを確かめるのに使う
+CREATE OR REPLACE FUNCTION password_reset_target(p_token_hash TEXT)
+RETURNS TABLE(credential_id TEXT, organization_id TEXT)
+LANGUAGE sql STABLE SECURITY DEFINER SET search_path = public
+AS $$
+    SELECT pc.id::text, pc.organization_id::text
+    FROM password_reset_tokens t
+    JOIN password_credentials pc ON pc.id = t.credential_id
+    WHERE t.token_hash = p_token_hash
+      AND t.used_at IS NULL
+      AND t.expires_at > NOW()
+      AND pc.enabled = true;
+$$;
@@ -0,0 +1,28 @@
+-- Migration: Resumable chunked uploads (FilesService.UploadFile)
+-- 分割アップロードの途中状態。ストレージにはパート単位（マルチパートアップロード）で送り、
+-- 送り終えた位置（received_bytes）を記録する。中断したら uuid を指定して received_bytes から再開し、
+-- 最後のパートを送ったら files に行を作る。放置されたものは files.retention_purge で中止・削除する。
+
+CREATE TABLE file_uploads (
+    uuid UUID PRIMARY KEY,                        -- 完了後の files.uuid
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    type TEXT NOT NULL,
+    total_size BIGINT NOT NULL CHECK (total_size > 0),
+    received_bytes BIGINT NOT NULL DEFAULT 0,     -- ストレージに送り終えたバイト数（パートの境界）
+    s3_key TEXT NOT NULL,
+    session_id TEXT NOT NULL,                     -- R2: UploadId、GCS: resumable upload の session URL
+    parts JSONB NOT NULL DEFAULT '[]'::jsonb,     -- [{part_number, etag}]
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_file_uploads_updated_at ON file_uploads(organization_id, updated_at);
+
+ALTER TABLE file_uploads ENABLE ROW LEVEL SECURITY;
+ALTER TABLE file_uploads FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation_policy ON file_uploads
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON file_uploads TO rust_logi_app;
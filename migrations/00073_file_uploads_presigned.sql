@@ -0,0 +1,6 @@
+-- Migration: Presigned URL uploads (FilesService.GetUploadUrl / CompleteUpload)
+-- 署名付き URL へクライアントが直接 PUT するアップロードも file_uploads で追跡する。
+-- マルチパートではないので session_id は NULL。CompleteUpload でオブジェクトを確認して files に登録し、
+-- 完了しないまま放置されたものは files.retention_purge がオブジェクトごと削除する。
+
+ALTER TABLE file_uploads ALTER COLUMN session_id DROP NOT NULL;
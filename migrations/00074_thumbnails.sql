@@ -0,0 +1,24 @@
+-- Migration: Server-side thumbnails for files and cam_files
+-- jpg / png をアップロードしたとき（files は files.thumbnail job、cam_files は Flickr アップロード時）に
+-- small / medium の JPEG を生成してストレージの {org}/thumbnails/... に置き、そのキーを記録する。
+-- source_type は 'file'（source_id = files.uuid）か 'cam_file'（source_id = cam_files.name）。
+
+CREATE TABLE thumbnails (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    source_type TEXT NOT NULL CHECK (source_type IN ('file', 'cam_file')),
+    source_id TEXT NOT NULL,
+    size TEXT NOT NULL CHECK (size IN ('small', 'medium')),
+    s3_key TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, source_type, source_id, size)
+);
+
+ALTER TABLE thumbnails ENABLE ROW LEVEL SECURITY;
+ALTER TABLE thumbnails FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation_policy ON thumbnails
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON thumbnails TO rust_logi_app;
@@ -0,0 +1,109 @@
+-- Migration: Refresh tokens for AuthService.RefreshToken / RevokeToken
+-- ログイン時に長期のリフレッシュトークンを発行し、SHA-256 のみを保存する。
+-- RefreshToken のたびに新しいトークンへ置き換え（ローテーション）、古いトークンは失効させる。
+-- 失効済みトークンが再利用されたら漏洩とみなし、同じ family（最初のログインから続く系列）を全て失効させる。
+
+CREATE TABLE refresh_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    family_id UUID NOT NULL,
+    app_user_id UUID NOT NULL REFERENCES app_users(id) ON DELETE CASCADE,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_refresh_tokens_family ON refresh_tokens(family_id);
+CREATE INDEX idx_refresh_tokens_user ON refresh_tokens(app_user_id);
+
+-- 認証前に使うため SECURITY DEFINER 関数経由でのみ操作する
+ALTER TABLE refresh_tokens ENABLE ROW LEVEL SECURITY;
+
+-- ログイン時に新しい family のトークンを発行
+CREATE OR REPLACE FUNCTION create_refresh_token(
+    p_user_id UUID,
+    p_org_id UUID,
+    p_provider TEXT,
+    p_token_hash TEXT,
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS VOID
+LANGUAGE sql SECURITY DEFINER SET search_path = public
+AS $$
+    INSERT INTO refresh_tokens (family_id, app_user_id, organization_id, provider, token_hash, expires_at)
+    VALUES (gen_random_uuid(), p_user_id, p_org_id, p_provider, p_token_hash, p_expires_at);
+$$;
+
+-- 古いトークンを失効させ、同じ family の新しいトークンを発行する。
+-- 成功時は JWT の発行に必要な情報を返す（無効・期限切れ・再利用・組織から外れた場合は 0 行）
+CREATE OR REPLACE FUNCTION rotate_refresh_token(
+    p_token_hash TEXT,
+    p_new_token_hash TEXT,
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS TABLE(user_id TEXT, org_id TEXT, provider TEXT, username TEXT, org_slug TEXT)
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = public
+AS $$
+DECLARE
+    v_token refresh_tokens%ROWTYPE;
+BEGIN
+    SELECT * INTO v_token FROM refresh_tokens t WHERE t.token_hash = p_token_hash FOR UPDATE;
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF v_token.revoked_at IS NOT NULL THEN
+        -- 再利用: 系列ごと失効させる
+        UPDATE refresh_tokens t SET revoked_at = NOW()
+        WHERE t.family_id = v_token.family_id AND t.revoked_at IS NULL;
+        RETURN;
+    END IF;
+
+    IF v_token.expires_at <= NOW() THEN
+        RETURN;
+    END IF;
+
+    UPDATE refresh_tokens t SET revoked_at = NOW() WHERE t.id = v_token.id;
+
+    RETURN QUERY
+    WITH member AS (
+        SELECT
+            au.id::text AS user_id,
+            o.id::text AS org_id,
+            v_token.provider AS provider,
+            COALESCE(au.email, au.display_name) AS username,
+            o.slug AS org_slug
+        FROM app_users au
+        JOIN user_organizations uo ON uo.user_id = au.id AND uo.organization_id = v_token.organization_id
+        JOIN organizations o ON o.id = uo.organization_id
+        WHERE au.id = v_token.app_user_id
+          AND au.deleted_at IS NULL
+          AND o.deleted_at IS NULL
+    ), inserted AS (
+        INSERT INTO refresh_tokens (family_id, app_user_id, organization_id, provider, token_hash, expires_at)
+        SELECT v_token.family_id, v_token.app_user_id, v_token.organization_id, v_token.provider,
+               p_new_token_hash, p_expires_at
+        FROM member
+        RETURNING 1
+    )
+    SELECT m.user_id, m.org_id, m.provider, m.username, m.org_slug
+    FROM member m, inserted;
+END;
+$$;
+
+-- ログアウト: トークンの系列を失効させる。失効させた件数を返す
+CREATE OR REPLACE FUNCTION revoke_refresh_token(p_token_hash TEXT)
+RETURNS BIGINT
+LANGUAGE sql SECURITY DEFINER SET search_path = public
+AS $$
+    WITH revoked AS (
+        UPDATE refresh_tokens t SET revoked_at = NOW()
+        WHERE t.family_id = (SELECT family_id FROM refresh_tokens WHERE token_hash = p_token_hash)
+          AND t.revoked_at IS NULL
+        RETURNING 1
+    )
+    SELECT COUNT(*) FROM revoked;
+$$;
+
@@ -0,0 +1,51 @@
+-- Migration: API keys for machine clients
+-- 車載ゲートウェイや cron スクリプトは Google / SSO ログインができないため、x-api-key ヘッダーで認証する。
+-- キーは組織に固定し、許可したサービス（services、例: 'logi.dtakologs.DtakologsService'）だけを呼び出せる。
+-- 平文は発行時に1度だけ返し、SHA-256 のみ保存する。操作は発行した管理者（created_by）として記録する。
+
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    key_hint TEXT NOT NULL,                -- 末尾4文字（一覧表示用）
+    services TEXT[] NOT NULL,
+    created_by UUID NOT NULL REFERENCES app_users(id) ON DELETE CASCADE,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_api_keys_organization ON api_keys(organization_id, created_at);
+
+ALTER TABLE api_keys ENABLE ROW LEVEL SECURITY;
+ALTER TABLE api_keys FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON api_keys
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON api_keys TO rust_logi_app;
+
+-- 認証前（組織未確定）にキーから組織を引く。失効済み・発行者が組織から外れたキーは 0 行。
+-- 見つかれば last_used_at を更新（毎回の書き込みを避けるため 1 分に 1 回まで）
+CREATE OR REPLACE FUNCTION resolve_api_key(p_key_hash TEXT)
+RETURNS TABLE(api_key_id TEXT, organization_id TEXT, created_by TEXT, services TEXT[], org_slug TEXT)
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT k.id::text, k.organization_id::text, k.created_by::text, k.services, o.slug
+    FROM api_keys k
+    JOIN organizations o ON o.id = k.organization_id AND o.deleted_at IS NULL
+    JOIN user_organizations uo ON uo.user_id = k.created_by AND uo.organization_id = k.organization_id
+    WHERE k.key_hash = p_key_hash AND k.revoked_at IS NULL;
+
+    UPDATE api_keys k SET last_used_at = NOW()
+    WHERE k.key_hash = p_key_hash
+      AND k.revoked_at IS NULL
+      AND (k.last_used_at IS NULL OR k.last_used_at < NOW() - INTERVAL '1 minute');
+END;
+$$;
+
+GRANT EXECUTE ON FUNCTION resolve_api_key(TEXT) TO rust_logi_app;
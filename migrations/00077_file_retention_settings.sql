@@ -0,0 +1,23 @@
+-- Migration: Per-organization retention of deleted files
+-- DeleteFile はソフトデリート。ゴミ箱（ListDeletedFiles）から RestoreDeletedFile で戻せ、
+-- 保持日数を過ぎたものは files.retention_purge が DB とストレージから完全削除する。行がない組織は 30 日。
+
+CREATE TABLE file_retention_settings (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    retention_days INTEGER NOT NULL CHECK (retention_days BETWEEN 1 AND 3650),
+    updated_by UUID,                                                                 -- app_users.id
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE file_retention_settings ENABLE ROW LEVEL SECURITY;
+ALTER TABLE file_retention_settings FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON file_retention_settings
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE ON file_retention_settings TO rust_logi_app;
+
+-- ゴミ箱一覧（削除の新しい順）
+CREATE INDEX idx_files_deleted_at ON files(deleted_at DESC, uuid DESC) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,32 @@
+-- Migration: Partial-match search (SearchService)
+-- 日本語は空白で区切られないため tsvector ではなく pg_trgm の部分一致（LIKE '%...%'）で検索する。
+-- search_key() で NFKC 正規化（全角英数字・半角カナ）・小文字化し、空白とハイフン・中黒を除いた値を索引する。
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE OR REPLACE FUNCTION search_key(p_value TEXT)
+RETURNS TEXT
+LANGUAGE sql IMMUTABLE PARALLEL SAFE
+AS $$
+    SELECT lower(translate(normalize(COALESCE(p_value, ''), NFKC), ' -・', ''));
+$$;
+
+-- 検索語を LIKE の部分一致パターンにする（% _ \ はエスケープ）
+CREATE OR REPLACE FUNCTION search_like_pattern(p_query TEXT)
+RETURNS TEXT
+LANGUAGE sql IMMUTABLE PARALLEL SAFE
+AS $$
+    SELECT '%' || replace(replace(replace(search_key(p_query), '\', '\\'), '%', '\%'), '_', '\_') || '%';
+$$;
+
+CREATE INDEX idx_files_search ON files
+    USING gin (search_key(filename) gin_trgm_ops)
+    WHERE deleted_at IS NULL;
+
+-- 列はすべて NOT NULL（concat_ws は IMMUTABLE でないので || で連結する）
+CREATE INDEX idx_car_inspection_search ON car_inspection
+    USING gin (search_key(
+        "EntryNoCarNo" || '|' || "CarNo" || '|' ||
+        "OwnernameLowLevelChar" || '|' || "OwnernameHighLevelChar" || '|' ||
+        "UsernameLowLevelChar" || '|' || "UsernameHighLevelChar"
+    ) gin_trgm_ops);
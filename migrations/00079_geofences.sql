@@ -0,0 +1,76 @@
+-- Migration: Geofences
+-- 営業所・得意先などの範囲（円または多角形）を組織ごとに登録し、運行ログの書き込み時に車両の出入りを判定する。
+-- 車両ごとの内外は geofence_states に持ち、変わったときだけ geofence_events に記録する。
+-- 初めて見た車両（状態なし）は内外を記録するだけで、イベントにはしない（登録時に中にいた車両を「進入」にしない）。
+-- data_date_time が状態より古い行（再送・遅れて届いた行）は判定に使わない。
+
+CREATE TABLE geofences (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    shape TEXT NOT NULL CHECK (shape IN ('circle', 'polygon')),
+    center_lat DOUBLE PRECISION,           -- circle: 中心（度）
+    center_lon DOUBLE PRECISION,
+    radius_m DOUBLE PRECISION,             -- circle: 半径（m）
+    polygon JSONB,                         -- polygon: [[lat, lon], ...]（3 点以上、閉じなくてよい）
+    notify BOOLEAN NOT NULL DEFAULT FALSE, -- 出入りを LINE WORKS にも送る（Webhook には常に送る）
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (
+        (shape = 'circle' AND center_lat IS NOT NULL AND center_lon IS NOT NULL AND radius_m > 0)
+        OR (shape = 'polygon' AND jsonb_typeof(polygon) = 'array')
+    )
+);
+
+CREATE INDEX idx_geofences_organization ON geofences(organization_id, name);
+
+-- 車両ごとの内外（最後に判定した運行ログの日時）
+CREATE TABLE geofence_states (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    geofence_id UUID NOT NULL REFERENCES geofences(id) ON DELETE CASCADE,
+    vehicle_cd INTEGER NOT NULL,
+    inside BOOLEAN NOT NULL,
+    data_date_time TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (geofence_id, vehicle_cd)
+);
+
+CREATE TABLE geofence_events (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    geofence_id UUID NOT NULL REFERENCES geofences(id) ON DELETE CASCADE,
+    vehicle_cd INTEGER NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN ('enter', 'exit')),
+    data_date_time TEXT NOT NULL,          -- 判定した運行ログの日時
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_geofence_events_organization ON geofence_events(organization_id, id DESC);
+CREATE INDEX idx_geofence_events_geofence ON geofence_events(geofence_id, id DESC);
+
+ALTER TABLE geofences ENABLE ROW LEVEL SECURITY;
+ALTER TABLE geofences FORCE ROW LEVEL SECURITY;
+ALTER TABLE geofence_states ENABLE ROW LEVEL SECURITY;
+ALTER TABLE geofence_states FORCE ROW LEVEL SECURITY;
+ALTER TABLE geofence_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE geofence_events FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON geofences
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+CREATE POLICY organization_isolation_policy ON geofence_states
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+CREATE POLICY organization_isolation_policy ON geofence_events
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON geofences TO rust_logi_app;
+GRANT SELECT, INSERT, UPDATE, DELETE ON geofence_states TO rust_logi_app;
+GRANT SELECT, INSERT, UPDATE, DELETE ON geofence_events TO rust_logi_app;
+GRANT USAGE ON SEQUENCE geofence_events_id_seq TO rust_logi_app;
@@ -0,0 +1,43 @@
+-- Migration: Car inspection expiry notices
+-- car_inspection.expiry_notify で通知済みの車両を記録し、同じ車両・同じ有効期限・同じ区分（30 日以内 / 期限切れ）では
+-- 2 回目以降を送らない（毎日実行しても、区分が変わったときと車検を更新したときだけ送る）。
+-- あわせて全組織に既定のスケジュール（毎日 9 時）を登録し、新しい組織にも作成時に登録する。
+
+CREATE TABLE car_inspection_expiry_notices (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    car_id TEXT NOT NULL,                  -- car_inspection."CarId"
+    expirdate TEXT NOT NULL,               -- YYMMDD
+    notice_window TEXT NOT NULL CHECK (notice_window IN ('within_30_days', 'expired')),
+    notified_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, car_id, expirdate, notice_window)
+);
+
+ALTER TABLE car_inspection_expiry_notices ENABLE ROW LEVEL SECURITY;
+ALTER TABLE car_inspection_expiry_notices FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON car_inspection_expiry_notices
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON car_inspection_expiry_notices TO rust_logi_app;
+
+-- 既定のスケジュール（登録済みの組織は変えない。止める場合は UpdateScheduledTask で enabled = false）
+INSERT INTO scheduled_tasks (organization_id, task, cron_expression)
+SELECT id, 'car_inspection.expiry_notify', '0 9 * * *' FROM organizations
+ON CONFLICT (organization_id, task) DO NOTHING;
+
+CREATE OR REPLACE FUNCTION schedule_default_expiry_notify()
+RETURNS TRIGGER
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = public
+AS $$
+BEGIN
+    INSERT INTO scheduled_tasks (organization_id, task, cron_expression)
+    VALUES (NEW.id, 'car_inspection.expiry_notify', '0 9 * * *')
+    ON CONFLICT (organization_id, task) DO NOTHING;
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER organizations_schedule_expiry_notify
+    AFTER INSERT ON organizations
+    FOR EACH ROW EXECUTE FUNCTION schedule_default_expiry_notify();
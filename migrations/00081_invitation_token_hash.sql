@@ -0,0 +1,8 @@
+-- Migration: Store invitation tokens as SHA-256
+-- 招待トークンは署名付き（`{招待 id}.{有効期限}.{HMAC}`）になり、invitations.token には SHA-256 だけを保存する。
+-- 既存の平文トークンはハッシュに置き換え、未受諾の招待は形式が変わって受諾できないので期限切れにする（招待し直す）。
+
+UPDATE invitations SET token = encode(sha256(convert_to(token, 'UTF8')), 'hex');
+
+UPDATE invitations SET expires_at = NOW()
+WHERE accepted_at IS NULL AND expires_at > NOW();
@@ -0,0 +1,53 @@
+-- Migration: Per-organization storage usage and quota
+-- files の保存量（size_bytes の合計・件数）を組織ごとに持つ。files への INSERT で増やし、完全削除（DELETE）で減らす。
+-- ソフトデリート（deleted_at）はストレージに残っているので数えたまま。
+-- quota_bytes は組織ごとの上限（NULL ならサーバー既定の STORAGE_QUOTA_BYTES、それも未設定なら無制限）。
+
+CREATE TABLE organization_storage_usage (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    storage_bytes BIGINT NOT NULL DEFAULT 0,
+    file_count BIGINT NOT NULL DEFAULT 0,
+    quota_bytes BIGINT CHECK (quota_bytes >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE organization_storage_usage ENABLE ROW LEVEL SECURITY;
+ALTER TABLE organization_storage_usage FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY organization_isolation_policy ON organization_storage_usage
+    USING (organization_id::text = current_setting('app.current_organization_id', true))
+    WITH CHECK (organization_id::text = current_setting('app.current_organization_id', true));
+
+-- 集計はトリガーだけが更新する
+GRANT SELECT ON organization_storage_usage TO rust_logi_app;
+
+INSERT INTO organization_storage_usage (organization_id, storage_bytes, file_count)
+SELECT organization_id, COALESCE(SUM(size_bytes), 0), COUNT(*) FROM files GROUP BY organization_id;
+
+CREATE OR REPLACE FUNCTION track_file_storage_usage()
+RETURNS TRIGGER
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = public
+AS $$
+BEGIN
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        UPDATE organization_storage_usage
+        SET storage_bytes = GREATEST(storage_bytes - COALESCE(OLD.size_bytes, 0), 0),
+            file_count = GREATEST(file_count - 1, 0),
+            updated_at = NOW()
+        WHERE organization_id = OLD.organization_id;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        INSERT INTO organization_storage_usage (organization_id, storage_bytes, file_count)
+        VALUES (NEW.organization_id, COALESCE(NEW.size_bytes, 0), 1)
+        ON CONFLICT (organization_id) DO UPDATE
+        SET storage_bytes = organization_storage_usage.storage_bytes + EXCLUDED.storage_bytes,
+            file_count = organization_storage_usage.file_count + 1,
+            updated_at = NOW();
+    END IF;
+    RETURN NULL;
+END;
+$$;
+
+CREATE TRIGGER files_track_storage_usage
+    AFTER INSERT OR DELETE OR UPDATE OF size_bytes, organization_id ON files
+    FOR EACH ROW EXECUTE FUNCTION track_file_storage_usage();
@@ -12,3 +12,18 @@ export * from "./gen/sso_settings_pb";
 export * from "./gen/bot_config_pb";
 export * from "./gen/access_request_pb";
 export * from "./gen/items_pb";
+export * from "./gen/scheduler_pb";
+export * from "./gen/jobs_pb";
+export * from "./gen/notifications_pb";
+export * from "./gen/webhooks_pb";
+export * from "./gen/etc_pb";
+export * from "./gen/fuel_pb";
+export * from "./gen/ichiban_cars_pb";
+export * from "./gen/reports_pb";
+export * from "./gen/admin_pb";
+export * from "./gen/api_keys_pb";
+export * from "./gen/search_pb";
+export * from "./gen/geofences_pb";
+
+// v2 packages (names overlap with v1, so they are namespaced)
+export * as filesV2 from "./gen/v2/files_pb";
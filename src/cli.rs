@@ -0,0 +1,493 @@
+// Operational subcommands for the rust-logi binary
+//
+// 運用作業（マイグレーション、管理者作成、ファイル再解析、ストレージ移行、シークレットの再暗号化）を
+// psql やアドホックスクリプトなしで実行するための CLI。
+
+use std::sync::Arc;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Argon2, PasswordHasher,
+};
+use clap::{Args, Parser, Subcommand};
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::crypto::{SecretBox, SECRET_COLUMNS};
+use crate::db::{set_current_organization, OrgScopedPool};
+use crate::http_client::HttpClient;
+use crate::outbox::Outbox;
+use crate::services::password_policy::PasswordPolicy;
+use crate::services::FileAutoParser;
+use crate::storage::{self, StorageBackend};
+
+#[derive(Debug, Parser)]
+#[command(name = "rust-logi", version, about = "gRPC-Web API server for logistics management")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// gRPC サーバーを起動（サブコマンド省略時のデフォルト）
+    Serve,
+    /// migrations/ の未適用マイグレーションを実行
+    Migrate,
+    /// 設定を読み込み、DB・ストレージへの接続を確認
+    CheckConfig,
+    /// 組織の管理者ユーザー（パスワードログイン）を作成
+    CreateAdminUser(CreateAdminUserArgs),
+    /// アップロード済みファイルの自動解析（車検証 JSON / PDF）を再実行
+    ReprocessFiles(ReprocessFilesArgs),
+    /// DB blob または別バックエンドから、オブジェクトストレージへファイルを移行
+    StorageMigrate(StorageMigrateArgs),
+    /// 保存済みのトークン・シークレットを現在の鍵（SECRETS_KEYS の先頭）で暗号化し直す
+    EncryptSecrets(EncryptSecretsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CreateAdminUserArgs {
+    /// 組織の slug
+    #[arg(long)]
+    pub org_slug: String,
+    /// メールアドレス
+    #[arg(long)]
+    pub email: String,
+    /// ログイン用ユーザー名（組織内で一意）
+    #[arg(long)]
+    pub username: String,
+    /// パスワード（シェル履歴に残さないよう環境変数推奨）
+    #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+    pub password: String,
+    /// 表示名（省略時はメールアドレス）
+    #[arg(long)]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReprocessFilesArgs {
+    /// 対象組織 ID（省略時は全組織）
+    #[arg(long)]
+    pub organization_id: Option<String>,
+    /// 対象ファイル UUID（複数指定可）
+    #[arg(long = "uuid")]
+    pub uuids: Vec<String>,
+    /// この日時以降に作成されたファイルのみ（例: 2025-01-01）
+    #[arg(long)]
+    pub since: Option<String>,
+    /// 処理件数の上限
+    #[arg(long)]
+    pub limit: Option<i64>,
+    /// 対象を表示するのみで解析しない
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StorageMigrateArgs {
+    /// 移行元: "db"（files.blob）, "gcs", "r2"
+    #[arg(long, default_value = "db")]
+    pub from: String,
+    /// 移行先: "gcs" / "r2"（省略時は STORAGE_BACKEND）
+    #[arg(long)]
+    pub to: Option<String>,
+    /// 対象組織 ID（省略時は全組織）
+    #[arg(long)]
+    pub organization_id: Option<String>,
+    /// 処理件数の上限
+    #[arg(long)]
+    pub limit: Option<i64>,
+    /// 対象を表示するのみで移行しない
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct EncryptSecretsArgs {
+    /// 対象件数を表示するのみで更新しない
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// 未適用のマイグレーションを実行
+pub async fn migrate(pool: &PgPool) -> anyhow::Result<()> {
+    let migrator = sqlx::migrate!("./migrations");
+    migrator.run(pool).await?;
+    tracing::info!("Migrations applied ({} total)", migrator.iter().count());
+    Ok(())
+}
+
+/// 設定値と外部接続を検証
+pub async fn check_config(config: &Config) -> anyhow::Result<()> {
+    config.log_effective();
+
+    let pool = crate::db::create_pool(&config.database_url).await?;
+    sqlx::query("SELECT 1").execute(&pool).await?;
+    tracing::info!("Database: OK");
+
+    match storage::create_backend(config, config.storage_backend.as_deref()).await? {
+        Some(backend) => tracing::info!("Storage: OK (bucket={})", backend.bucket()),
+        None => tracing::info!("Storage: not configured (database blob storage)"),
+    }
+
+    let http_client = HttpClient::from_config(&config.http);
+    let secrets = SecretBox::from_config(config.secrets.as_ref(), &config.jwt_secret, &http_client).await?;
+    tracing::info!("Secrets: OK (current key={})", secrets.current_kid());
+
+    if config.google_client_ids.is_empty() {
+        tracing::warn!("GOOGLE_CLIENT_IDS is not set; Google login is disabled");
+    }
+    if config.cam_config.is_none() {
+        tracing::warn!("CAM_* is not set; camera sync is disabled");
+    }
+    if config.dvr_notification_enabled && config.dvr_lineworks_bot_url.is_none() {
+        tracing::warn!("DVR_NOTIFICATION_ENABLED=true but DVR_LINEWORKS_BOT_URL is not set");
+    }
+
+    tracing::info!("Configuration OK");
+    Ok(())
+}
+
+/// 組織の管理者ユーザーを作成（既存ユーザーは admin に昇格）
+pub async fn create_admin_user(pool: &PgPool, args: &CreateAdminUserArgs) -> anyhow::Result<()> {
+    let org: Option<(String,)> = sqlx::query_as(
+        "SELECT id::text FROM organizations WHERE slug = $1 AND deleted_at IS NULL",
+    )
+    .bind(&args.org_slug)
+    .fetch_optional(pool)
+    .await?;
+    let (org_id,) = org.ok_or_else(|| anyhow::anyhow!("Organization not found: {}", args.org_slug))?;
+
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(args.password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Password hash error: {}", e))?
+        .to_string();
+
+    let mut tx = pool.begin_scoped(&org_id).await?;
+
+    PasswordPolicy::load(&mut tx)
+        .await?
+        .check(&args.password)
+        .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+
+    let existing_user: Option<(String,)> = sqlx::query_as(
+        "SELECT id::text FROM app_users WHERE email = $1 AND deleted_at IS NULL",
+    )
+    .bind(&args.email)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let user_id = match existing_user {
+        Some((id,)) => id,
+        None => {
+            let display_name = args.display_name.as_deref().unwrap_or(&args.email);
+            let (id,): (String,) = sqlx::query_as(
+                "INSERT INTO app_users (email, display_name) VALUES ($1, $2) RETURNING id::text",
+            )
+            .bind(&args.email)
+            .bind(display_name)
+            .fetch_one(&mut *tx)
+            .await?;
+            id
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO password_credentials (app_user_id, organization_id, username, password_hash)
+         VALUES ($1::uuid, $2::uuid, $3, $4)
+         ON CONFLICT (organization_id, username)
+         DO UPDATE SET app_user_id = EXCLUDED.app_user_id,
+                       password_hash = EXCLUDED.password_hash,
+                       enabled = TRUE,
+                       updated_at = NOW()",
+    )
+    .bind(&user_id)
+    .bind(&org_id)
+    .bind(&args.username)
+    .bind(&password_hash)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO user_organizations (user_id, organization_id, role, is_default)
+         VALUES ($1::uuid, $2::uuid, 'admin', true)
+         ON CONFLICT (user_id, organization_id)
+         DO UPDATE SET role = 'admin', updated_at = NOW()",
+    )
+    .bind(&user_id)
+    .bind(&org_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Admin user ready: user_id={}, org={}, username={}",
+        user_id,
+        args.org_slug,
+        args.username
+    );
+    Ok(())
+}
+
+/// ファイル自動解析を再実行
+pub async fn reprocess_files(
+    pool: &PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    args: &ReprocessFilesArgs,
+) -> anyhow::Result<()> {
+    let targets: Vec<(String, String, String, Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT uuid::text, organization_id::text, type, s3_key, blob
+        FROM files
+        WHERE deleted_at IS NULL
+          AND type IN ('application/json', 'application/pdf')
+          AND ($1::uuid IS NULL OR organization_id = $1::uuid)
+          AND (cardinality($2::text[]) = 0 OR uuid::text = ANY($2::text[]))
+          AND ($3::timestamptz IS NULL OR created_at >= $3::timestamptz)
+        ORDER BY created_at
+        LIMIT $4
+        "#,
+    )
+    .bind(&args.organization_id)
+    .bind(&args.uuids)
+    .bind(&args.since)
+    .bind(args.limit)
+    .fetch_all(pool)
+    .await?;
+
+    tracing::info!("Reprocessing {} files", targets.len());
+
+    // 再解析では Webhook（files.parsed）を送らない
+    let parser = FileAutoParser::new(pool.clone(), Outbox::default());
+    let (mut processed, mut failed) = (0usize, 0usize);
+
+    for (uuid, org_id, file_type, s3_key, blob) in targets {
+        if args.dry_run {
+            tracing::info!("[dry-run] {} ({}) org={}", uuid, file_type, org_id);
+            continue;
+        }
+
+        let data = match load_file_data(storage.as_deref(), s3_key.as_deref(), blob.as_deref()).await
+        {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!("Failed to load {}: {}", uuid, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let result = if file_type == "application/json" {
+            parser.process_json_upload(&uuid, &data, &org_id).await
+        } else {
+            parser.process_pdf_upload(&uuid, &data, &org_id).await
+        };
+
+        match result {
+            Ok(()) => processed += 1,
+            Err(e) => {
+                tracing::error!("Auto-parse failed for {}: {}", uuid, e);
+                failed += 1;
+            }
+        }
+    }
+
+    tracing::info!("Reprocess finished: processed={}, failed={}", processed, failed);
+    Ok(())
+}
+
+/// ファイル本体を取得（ストレージ優先、なければ DB blob）
+async fn load_file_data(
+    storage: Option<&dyn StorageBackend>,
+    s3_key: Option<&str>,
+    blob: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    if let (Some(storage), Some(key)) = (storage, s3_key) {
+        return Ok(storage.download(key).await?);
+    }
+    if let Some(blob) = blob {
+        return Ok(base64::Engine::decode(
+            &base64::engine::general_purpose::STANDARD,
+            blob,
+        )?);
+    }
+    anyhow::bail!("no storage object or blob available")
+}
+
+/// ファイル本体をオブジェクトストレージへ移行
+pub async fn storage_migrate(
+    config: &Config,
+    pool: &PgPool,
+    args: &StorageMigrateArgs,
+) -> anyhow::Result<()> {
+    let to_name = args
+        .to
+        .as_deref()
+        .or(config.storage_backend.as_deref())
+        .unwrap_or("gcs");
+    if args.from == to_name {
+        anyhow::bail!("--from and --to must differ");
+    }
+
+    let dest = storage::create_backend(config, Some(to_name))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Destination storage '{}' is not configured", to_name))?;
+
+    let source = match args.from.as_str() {
+        "db" => None,
+        name => Some(
+            storage::create_backend(config, Some(name))
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Source storage '{}' is not configured", name))?,
+        ),
+    };
+
+    // db: blob のみ保持している行 / gcs・r2: s3_key を持つ行
+    let targets: Vec<(String, String, String, Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT uuid::text, organization_id::text, type, s3_key, blob
+        FROM files
+        WHERE deleted_at IS NULL
+          AND ($1::uuid IS NULL OR organization_id = $1::uuid)
+          AND CASE WHEN $2 THEN blob IS NOT NULL AND s3_key IS NULL
+                   ELSE s3_key IS NOT NULL END
+        ORDER BY created_at
+        LIMIT $3
+        "#,
+    )
+    .bind(&args.organization_id)
+    .bind(source.is_none())
+    .bind(args.limit)
+    .fetch_all(pool)
+    .await?;
+
+    tracing::info!(
+        "Migrating {} files: {} -> {} (bucket={})",
+        targets.len(),
+        args.from,
+        to_name,
+        dest.bucket()
+    );
+
+    let (mut migrated, mut failed) = (0usize, 0usize);
+
+    for (uuid, org_id, file_type, s3_key, blob) in targets {
+        let key = s3_key.clone().unwrap_or_else(|| format!("{}/{}", org_id, uuid));
+
+        if args.dry_run {
+            tracing::info!("[dry-run] {} -> {}", uuid, key);
+            continue;
+        }
+
+        // バックエンド間コピーはストリームで流す（大きなファイルも全体をメモリに載せない）
+        if let (Some(source), Some(src_key)) = (source.as_deref(), s3_key.as_deref()) {
+            let copied = match source.download_stream(src_key).await {
+                Ok(stream) => dest.upload_stream(&key, stream, &file_type).await,
+                Err(e) => Err(e),
+            };
+            match copied {
+                Ok(_) => migrated += 1,
+                Err(e) => {
+                    tracing::error!("Failed to copy {}: {}", uuid, e.report());
+                    failed += 1;
+                }
+            }
+            continue;
+        }
+
+        let data = match load_file_data(source.as_deref(), s3_key.as_deref(), blob.as_deref()).await
+        {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!("Failed to load {}: {:#}", uuid, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        if let Err(e) = dest.upload(&key, &data, &file_type).await {
+            tracing::error!("Failed to upload {}: {}", uuid, e.report());
+            failed += 1;
+            continue;
+        }
+
+        // DB blob からの移行時のみメタデータを更新（バックエンド間コピーはキー不変）
+        if source.is_none() {
+            let mut conn = pool.acquire_scoped(&org_id).await?;
+            sqlx::query(
+                "UPDATE files SET s3_key = $1, storage_class = 'STANDARD', blob = NULL WHERE uuid = $2::uuid",
+            )
+            .bind(&key)
+            .bind(&uuid)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        migrated += 1;
+    }
+
+    tracing::info!("Storage migration finished: migrated={}, failed={}", migrated, failed);
+    Ok(())
+}
+
+/// 平文・従来形式・古い鍵の行を現在の鍵で暗号化し直す（何度実行してもよい）
+pub async fn encrypt_secrets(config: &Config, pool: &PgPool, args: &EncryptSecretsArgs) -> anyhow::Result<()> {
+    let http_client = HttpClient::from_config(&config.http);
+    let secrets = SecretBox::from_config(config.secrets.as_ref(), &config.jwt_secret, &http_client).await?;
+    tracing::info!("Encrypting stored secrets with key '{}'", secrets.current_kid());
+
+    let (mut encrypted, mut failed) = (0usize, 0usize);
+
+    for column in SECRET_COLUMNS {
+        let organization = column
+            .organization_column
+            .map(|c| format!("{}::text", c))
+            .unwrap_or_else(|| "NULL::text".to_string());
+        let select = format!(
+            "SELECT id::text, {}, {} FROM {} WHERE {} IS NOT NULL",
+            organization, column.column, column.table, column.column
+        );
+        let rows: Vec<(String, Option<String>, String)> = sqlx::query_as(&select).fetch_all(pool).await?;
+        let targets: Vec<_> = rows.into_iter().filter(|(_, _, stored)| !secrets.is_current(stored)).collect();
+
+        tracing::info!("{}.{}: {} rows to encrypt", column.table, column.column, targets.len());
+        if args.dry_run {
+            continue;
+        }
+
+        // 読んだ後に書き換えられた行は上書きしない
+        let update = format!(
+            "UPDATE {} SET {} = $1 WHERE id = $2::uuid AND {} = $3",
+            column.table, column.column, column.column
+        );
+        for (id, organization_id, stored) in targets {
+            let plaintext = match secrets.decrypt(column, &stored) {
+                Ok(plaintext) => plaintext,
+                Err(e) => {
+                    tracing::error!("Failed to decrypt {}.{} id={}: {}", column.table, column.column, id, e.report());
+                    failed += 1;
+                    continue;
+                }
+            };
+            let value = secrets.encrypt(column, &plaintext)?;
+
+            let mut conn = pool.acquire().await?;
+            if let Some(organization_id) = &organization_id {
+                set_current_organization(&mut conn, organization_id).await?;
+            }
+            sqlx::query(&update)
+                .bind(&value)
+                .bind(&id)
+                .bind(&stored)
+                .execute(&mut *conn)
+                .await?;
+            encrypted += 1;
+        }
+    }
+
+    tracing::info!("Secret encryption finished: encrypted={}, failed={}", encrypted, failed);
+    Ok(())
+}
@@ -0,0 +1,128 @@
+// Cloud cost estimation (AdminService.GetCostBreakdown)
+//
+// 使用量（platform_cost_usage、migration 00065）に公開単価の目安を掛けて組織ごとの費用を見積もる。
+// 請求額そのものではなく、どの組織が費用を押し上げているかを比べるためのもの。
+// 単価は GCS（asia-northeast1）・Cloud SQL の公開価格（USD）。変わったらここを直す。
+
+use std::collections::HashMap;
+
+/// 1 GiB
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+/// 月額を期間の日数に按分するときの 1 か月
+const DAYS_PER_MONTH: f64 = 30.0;
+
+/// ストレージクラスごとの保存単価（USD / GiB・月）
+fn storage_price(storage_class: &str) -> f64 {
+    match storage_class {
+        "NEARLINE" => 0.016,
+        "COLDLINE" => 0.006,
+        "ARCHIVE" => 0.0025,
+        // R2 Infrequent Access（files.storage_demotion の降格先）
+        "STANDARD_IA" => 0.01,
+        // files.blob（Cloud SQL の SSD ストレージ）
+        "DATABASE" => 0.221,
+        _ => 0.023,
+    }
+}
+
+/// 取り出し単価（USD / GiB）。STANDARD / DATABASE は無料
+fn retrieval_price(storage_class: &str) -> f64 {
+    match storage_class {
+        "NEARLINE" => 0.01,
+        "COLDLINE" => 0.02,
+        "ARCHIVE" => 0.05,
+        "STANDARD_IA" => 0.01,
+        _ => 0.0,
+    }
+}
+
+/// インターネットへの egress（USD / GiB、アジア宛て）
+const EGRESS_PRICE: f64 = 0.12;
+
+/// 組織の使用量
+#[derive(Debug, Clone, Default)]
+pub struct CostUsage {
+    /// 保存容量（クラス別）
+    pub storage_class_bytes: HashMap<String, i64>,
+    pub download_bytes: i64,
+    /// ダウンロード時のクラス別バイト数
+    pub retrieval_class_bytes: HashMap<String, i64>,
+    pub flickr_upload_bytes: i64,
+}
+
+/// 期間の費用の見積もり（USD）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostEstimate {
+    /// 保存費用（期間の日数で按分、クラス別）
+    pub storage_class_cost: HashMap<String, f64>,
+    pub storage_cost: f64,
+    /// ダウンロード（API 経由の配信）の egress
+    pub egress_cost: f64,
+    /// NEARLINE 以下の取り出し料金
+    pub retrieval_cost: f64,
+    /// Flickr へのアップロードの egress
+    pub flickr_egress_cost: f64,
+    pub total_cost: f64,
+}
+
+fn gib(bytes: i64) -> f64 {
+    bytes.max(0) as f64 / GIB
+}
+
+pub fn estimate(usage: &CostUsage, days: i32) -> CostEstimate {
+    let months = days as f64 / DAYS_PER_MONTH;
+    let storage_class_cost: HashMap<String, f64> = usage
+        .storage_class_bytes
+        .iter()
+        .map(|(class, bytes)| (class.clone(), gib(*bytes) * storage_price(class) * months))
+        .collect();
+    let storage_cost: f64 = storage_class_cost.values().sum();
+    let egress_cost = gib(usage.download_bytes) * EGRESS_PRICE;
+    let retrieval_cost: f64 = usage
+        .retrieval_class_bytes
+        .iter()
+        .map(|(class, bytes)| gib(*bytes) * retrieval_price(class))
+        .sum();
+    let flickr_egress_cost = gib(usage.flickr_upload_bytes) * EGRESS_PRICE;
+
+    CostEstimate {
+        total_cost: storage_cost + egress_cost + retrieval_cost + flickr_egress_cost,
+        storage_class_cost,
+        storage_cost,
+        egress_cost,
+        retrieval_cost,
+        flickr_egress_cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let gib = 1024 * 1024 * 1024;
+        let usage = CostUsage {
+            storage_class_bytes: HashMap::from([
+                ("STANDARD".to_string(), 100 * gib),
+                ("ARCHIVE".to_string(), 100 * gib),
+            ]),
+            download_bytes: 10 * gib,
+            retrieval_class_bytes: HashMap::from([
+                ("ARCHIVE".to_string(), 2 * gib),
+                ("STANDARD".to_string(), 8 * gib),
+            ]),
+            flickr_upload_bytes: 5 * gib,
+        };
+        let cost = estimate(&usage, 30);
+        assert!((cost.storage_class_cost["STANDARD"] - 2.3).abs() < 1e-9);
+        assert!((cost.storage_cost - 2.55).abs() < 1e-9);
+        assert!((cost.egress_cost - 1.2).abs() < 1e-9);
+        assert!((cost.retrieval_cost - 0.1).abs() < 1e-9);
+        assert!((cost.flickr_egress_cost - 0.6).abs() < 1e-9);
+        assert!((cost.total_cost - 4.45).abs() < 1e-9);
+
+        // 期間が半分なら保存費用も半分
+        assert!((estimate(&usage, 15).storage_cost - 1.275).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,320 @@
+// 保存するシークレット（外部サービスのトークン・クライアントシークレット）の列暗号化
+//
+// AES-256-GCM。保存形式は `enc:v1:<kid>:<base64(nonce | ciphertext | tag)>` で、
+// AAD に「テーブル.列」を入れる（別の列にコピーされた値は復号できない）。
+// 鍵は SECRETS_KEYS（`kid:base64鍵` のカンマ区切り、先頭が暗号化に使う鍵）。
+// SECRETS_KMS_KEY があれば SECRETS_KEYS の値は Cloud KMS で包んだ鍵として起動時に復号する。
+// JWT_SECRET から導出した鍵（kid = "jwt"）は常に復号用に持つ:
+// - 従来の lineworks_auth::encrypt_secret の値（接頭辞なし）を読む
+// - SECRETS_KEYS 未設定時はこの鍵で暗号化する（開発環境向け、起動時に警告）
+// 接頭辞のない平文の行はそのまま読める。`rust-logi encrypt-secrets` で現在の鍵に暗号化し直す。
+
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::SecretsConfig;
+use crate::error::{AppError, AppResult};
+use crate::http_client::HttpClient;
+
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+/// JWT_SECRET から導出した鍵の kid
+const JWT_KID: &str = "jwt";
+
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const KMS_API_URL: &str = "https://cloudkms.googleapis.com/v1";
+
+/// 暗号化前の行の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Legacy {
+    /// 平文で保存されていた
+    Plaintext,
+    /// lineworks_auth::encrypt_secret（JWT_SECRET 由来の鍵・AAD なし）で保存されていた
+    JwtKey,
+}
+
+/// 暗号化して保存する列
+#[derive(Debug, Clone, Copy)]
+pub struct SecretColumn {
+    pub table: &'static str,
+    pub column: &'static str,
+    /// RLS 用（encrypt-secrets で行ごとに set_current_organization する）
+    pub organization_column: Option<&'static str>,
+    pub legacy: Legacy,
+}
+
+impl SecretColumn {
+    fn aad(&self) -> String {
+        format!("{}.{}", self.table, self.column)
+    }
+}
+
+pub const FLICKR_ACCESS_TOKEN: SecretColumn = SecretColumn {
+    table: "flickr_tokens",
+    column: "access_token",
+    organization_column: Some("organization_id"),
+    legacy: Legacy::Plaintext,
+};
+pub const FLICKR_ACCESS_TOKEN_SECRET: SecretColumn = SecretColumn {
+    table: "flickr_tokens",
+    column: "access_token_secret",
+    organization_column: Some("organization_id"),
+    legacy: Legacy::Plaintext,
+};
+pub const OAUTH_ACCESS_TOKEN: SecretColumn = SecretColumn {
+    table: "oauth_accounts",
+    column: "access_token",
+    organization_column: None,
+    legacy: Legacy::Plaintext,
+};
+pub const OAUTH_REFRESH_TOKEN: SecretColumn = SecretColumn {
+    table: "oauth_accounts",
+    column: "refresh_token",
+    organization_column: None,
+    legacy: Legacy::Plaintext,
+};
+pub const SSO_CLIENT_SECRET: SecretColumn = SecretColumn {
+    table: "sso_provider_configs",
+    column: "client_secret_encrypted",
+    organization_column: Some("organization_id"),
+    legacy: Legacy::JwtKey,
+};
+/// ローテーション後の猶予期間中の古い client_secret
+pub const SSO_PREVIOUS_CLIENT_SECRET: SecretColumn = SecretColumn {
+    table: "sso_provider_configs",
+    column: "previous_client_secret_encrypted",
+    organization_column: Some("organization_id"),
+    legacy: Legacy::JwtKey,
+};
+
+/// encrypt-secrets の対象（列を増やしたらここにも追加する）
+pub const SECRET_COLUMNS: &[SecretColumn] = &[
+    FLICKR_ACCESS_TOKEN,
+    FLICKR_ACCESS_TOKEN_SECRET,
+    OAUTH_ACCESS_TOKEN,
+    OAUTH_REFRESH_TOKEN,
+    SSO_CLIENT_SECRET,
+    SSO_PREVIOUS_CLIENT_SECRET,
+];
+
+#[derive(Debug, Deserialize)]
+struct MetadataToken {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KmsDecryptResponse {
+    plaintext: String,
+}
+
+/// 列暗号化の鍵束
+pub struct SecretBox {
+    /// 暗号化に使う鍵の kid
+    current: String,
+    keys: HashMap<String, LessSafeKey>,
+    rng: SystemRandom,
+}
+
+impl SecretBox {
+    /// (kid, 32 バイトの鍵) のリストから作る（先頭が暗号化に使う鍵）。空なら JWT_SECRET 由来の鍵で暗号化する
+    pub fn new(keys: Vec<(String, Vec<u8>)>, jwt_secret: &str) -> anyhow::Result<Self> {
+        let current = keys
+            .first()
+            .map(|(kid, _)| kid.clone())
+            .unwrap_or_else(|| JWT_KID.to_string());
+        let mut map = HashMap::new();
+        map.insert(JWT_KID.to_string(), aes_key(&jwt_key(jwt_secret))?);
+        for (kid, key) in keys {
+            if kid.is_empty() || kid.contains(':') {
+                anyhow::bail!("Invalid secrets key id '{}'", kid);
+            }
+            if kid == JWT_KID {
+                anyhow::bail!("Secrets key id '{}' is reserved", JWT_KID);
+            }
+            if map.insert(kid.clone(), aes_key(&key)?).is_some() {
+                anyhow::bail!("Duplicate secrets key id '{}'", kid);
+            }
+        }
+        Ok(Self {
+            current,
+            keys: map,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// SECRETS_KEYS（+ SECRETS_KMS_KEY）から作る。KMS で包まれた鍵はここで復号する
+    pub async fn from_config(
+        config: Option<&SecretsConfig>,
+        jwt_secret: &str,
+        http_client: &HttpClient,
+    ) -> anyhow::Result<Self> {
+        let Some(config) = config else {
+            tracing::warn!("SECRETS_KEYS is not set; stored secrets are encrypted with a key derived from JWT_SECRET");
+            return Self::new(Vec::new(), jwt_secret);
+        };
+
+        let mut keys = Vec::with_capacity(config.keys.len());
+        for (kid, encoded) in &config.keys {
+            let key = match &config.kms_key {
+                Some(kms_key) => kms_unwrap(http_client, kms_key, encoded)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to unwrap secrets key '{}': {:#}", kid, e))?,
+                None => BASE64
+                    .decode(encoded)
+                    .map_err(|e| anyhow::anyhow!("Secrets key '{}' is not valid base64: {}", kid, e))?,
+            };
+            keys.push((kid.clone(), key));
+        }
+        Self::new(keys, jwt_secret)
+    }
+
+    pub fn current_kid(&self) -> &str {
+        &self.current
+    }
+
+    /// 暗号化して保存形式の文字列にする
+    pub fn encrypt(&self, column: &SecretColumn, plaintext: &str) -> AppResult<String> {
+        let key = &self.keys[&self.current];
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce_bytes)
+            .map_err(|_| AppError::internal("Failed to generate nonce"))?;
+
+        let mut in_out = plaintext.as_bytes().to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::from(column.aad().as_bytes()),
+            &mut in_out,
+        )
+        .map_err(|_| AppError::internal(format!("Failed to encrypt {}", column.aad())))?;
+
+        let mut data = Vec::with_capacity(NONCE_LEN + in_out.len());
+        data.extend_from_slice(&nonce_bytes);
+        data.extend_from_slice(&in_out);
+        Ok(format!("{}{}:{}", PREFIX, self.current, BASE64.encode(&data)))
+    }
+
+    /// 保存された値を平文に戻す（接頭辞なしの値は列の Legacy に従う）
+    pub fn decrypt(&self, column: &SecretColumn, stored: &str) -> AppResult<String> {
+        let failed = || AppError::internal(format!("Failed to decrypt {}", column.aad()));
+
+        let Some(rest) = stored.strip_prefix(PREFIX) else {
+            return match column.legacy {
+                Legacy::Plaintext => Ok(stored.to_string()),
+                Legacy::JwtKey => open(&self.keys[JWT_KID], b"", stored).ok_or_else(failed),
+            };
+        };
+        let (kid, payload) = rest.split_once(':').ok_or_else(failed)?;
+        let key = self
+            .keys
+            .get(kid)
+            .ok_or_else(|| AppError::internal(format!("Unknown secrets key id '{}' in {}", kid, column.aad())))?;
+        open(key, column.aad().as_bytes(), payload).ok_or_else(failed)
+    }
+
+    /// 現在の鍵で暗号化済みか（encrypt-secrets の対象判定）
+    pub fn is_current(&self, stored: &str) -> bool {
+        stored
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .is_some_and(|(kid, _)| kid == self.current)
+    }
+}
+
+fn open(key: &LessSafeKey, aad: &[u8], payload: &str) -> Option<String> {
+    let data = BASE64.decode(payload).ok()?;
+    if data.len() < NONCE_LEN + aead::AES_256_GCM.tag_len() {
+        return None;
+    }
+    let (nonce_bytes, ciphertext) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce_bytes).ok()?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key.open_in_place(nonce, Aad::from(aad), &mut in_out).ok()?;
+    String::from_utf8(plaintext.to_vec()).ok()
+}
+
+fn aes_key(bytes: &[u8]) -> anyhow::Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, bytes)
+        .map_err(|_| anyhow::anyhow!("Secrets key must be 32 bytes (got {})", bytes.len()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// lineworks_auth と同じ導出（SHA-256）
+fn jwt_key(jwt_secret: &str) -> Vec<u8> {
+    Sha256::digest(jwt_secret.as_bytes()).to_vec()
+}
+
+/// Cloud KMS の decrypt で鍵を取り出す（メタデータサーバーのトークンを使う）
+async fn kms_unwrap(http_client: &HttpClient, kms_key: &str, wrapped: &str) -> anyhow::Result<Vec<u8>> {
+    let token: MetadataToken = http_client
+        .get_json_with_headers(METADATA_TOKEN_URL, &[("Metadata-Flavor", "Google")])
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to get access token from metadata server: {}", e))?;
+    let response = http_client
+        .post_json_with_bearer(
+            &format!("{}/{}:decrypt", KMS_API_URL, kms_key),
+            &token.access_token,
+            &serde_json::json!({ "ciphertext": wrapped }),
+        )
+        .await?
+        .error_for_status()?;
+    let decrypted: KmsDecryptResponse = response.json().await?;
+    Ok(BASE64.decode(decrypted.plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::lineworks_auth;
+
+    fn secret_box() -> SecretBox {
+        SecretBox::new(vec![("k1".to_string(), vec![7u8; 32])], "jwt-secret-key").unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let secrets = secret_box();
+        let stored = secrets.encrypt(&FLICKR_ACCESS_TOKEN, "72157-token").unwrap();
+        assert!(stored.starts_with("enc:v1:k1:"));
+        assert!(secrets.is_current(&stored));
+        assert_eq!(secrets.decrypt(&FLICKR_ACCESS_TOKEN, &stored).unwrap(), "72157-token");
+
+        // 別の列にコピーした値は復号できない
+        assert!(secrets.decrypt(&FLICKR_ACCESS_TOKEN_SECRET, &stored).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_legacy_values() {
+        let secrets = secret_box();
+        assert_eq!(secrets.decrypt(&OAUTH_ACCESS_TOKEN, "plain-token").unwrap(), "plain-token");
+        assert!(!secrets.is_current("plain-token"));
+
+        let legacy = lineworks_auth::encrypt_secret("client-secret", "jwt-secret-key").unwrap();
+        assert_eq!(secrets.decrypt(&SSO_CLIENT_SECRET, &legacy).unwrap(), "client-secret");
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_keys() {
+        let old = SecretBox::new(vec![("k1".to_string(), vec![7u8; 32])], "jwt-secret-key").unwrap();
+        let stored = old.encrypt(&SSO_CLIENT_SECRET, "client-secret").unwrap();
+
+        let rotated = SecretBox::new(
+            vec![("k2".to_string(), vec![9u8; 32]), ("k1".to_string(), vec![7u8; 32])],
+            "jwt-secret-key",
+        )
+        .unwrap();
+        assert!(!rotated.is_current(&stored));
+        assert_eq!(rotated.decrypt(&SSO_CLIENT_SECRET, &stored).unwrap(), "client-secret");
+
+        let unknown = SecretBox::new(vec![("k3".to_string(), vec![1u8; 32])], "jwt-secret-key").unwrap();
+        assert!(unknown.decrypt(&SSO_CLIENT_SECRET, &stored).is_err());
+
+        assert!(SecretBox::new(vec![("k1".to_string(), vec![0u8; 16])], "jwt").is_err());
+    }
+}
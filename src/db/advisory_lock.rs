@@ -0,0 +1,63 @@
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+
+/// PostgreSQL のセッションレベル advisory lock
+///
+/// 複数インスタンスで同時に動かしてはいけない処理（スケジューラ、カメラSD同期など）の排他に使う。
+/// ロックを取ったコネクションを保持し、release で解放する。release せずに drop した場合は
+/// コネクションをプールに戻さず切断するため、セッション終了でロックも解放される。
+pub struct AdvisoryLock {
+    conn: Option<PoolConnection<Postgres>>,
+    key: String,
+}
+
+impl AdvisoryLock {
+    /// ロックを試みる（他のインスタンスが保持していれば None）
+    pub async fn try_acquire(pool: &PgPool, key: impl Into<String>) -> Result<Option<Self>, sqlx::Error> {
+        let key = key.into();
+        let mut conn = pool.acquire().await?;
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock(hashtextextended($1, 0))")
+            .bind(&key)
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(acquired.then(|| Self {
+            conn: Some(conn),
+            key,
+        }))
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// ロックを保持しているコネクションがまだ生きているか
+    pub async fn is_held(&mut self) -> bool {
+        match self.conn.as_mut() {
+            Some(conn) => sqlx::query("SELECT 1").execute(&mut **conn).await.is_ok(),
+            None => false,
+        }
+    }
+
+    pub async fn release(mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        let unlocked = sqlx::query("SELECT pg_advisory_unlock(hashtextextended($1, 0))")
+            .bind(&self.key)
+            .execute(&mut *conn)
+            .await;
+        if let Err(e) = unlocked {
+            tracing::warn!("Failed to release advisory lock {}: {}", self.key, e);
+            // ロックが残ったコネクションをプールに戻さない
+            drop(conn.detach());
+        }
+    }
+}
+
+impl Drop for AdvisoryLock {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            drop(conn.detach());
+        }
+    }
+}
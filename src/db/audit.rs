@@ -0,0 +1,56 @@
+use serde_json::Value;
+use sqlx::PgConnection;
+
+/// 組織の監査ログ（audit_logs）に残す 1 件
+///
+/// 対象の操作と同じトランザクション（organization 設定済み）で `record` する。
+/// ロールバックされた操作はログにも残らない。
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    action: &'static str,
+    target_type: &'static str,
+    target_id: String,
+    actor_user_id: Option<String>,
+    details: Value,
+}
+
+impl AuditEvent {
+    pub fn new(action: &'static str, target_type: &'static str, target_id: impl Into<String>) -> Self {
+        Self {
+            action,
+            target_type,
+            target_id: target_id.into(),
+            actor_user_id: None,
+            details: Value::Object(Default::default()),
+        }
+    }
+
+    /// 操作したユーザー（未指定ならシステムによる操作）
+    pub fn actor(mut self, user_id: impl Into<String>) -> Self {
+        self.actor_user_id = Some(user_id.into());
+        self
+    }
+
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub async fn record(&self, conn: &mut PgConnection, organization_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_logs (organization_id, actor_user_id, action, target_type, target_id, details)
+            VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(organization_id)
+        .bind(&self.actor_user_id)
+        .bind(self.action)
+        .bind(self.target_type)
+        .bind(&self.target_id)
+        .bind(&self.details)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+// read_mask (google.protobuf.FieldMask) support for wide tables
+//
+// マスク外の文字列列は SELECT しない。FromRow モデル側の #[sqlx(default)] で空文字になる。
+
+use prost_types::FieldMask;
+use tonic::Status;
+
+/// read_mask で絞り込み可能な列定義
+pub struct MaskableColumns {
+    /// 常に SELECT する列名（キー・非文字列列）
+    pub fixed: &'static [&'static str],
+    /// 常に返す proto フィールド名（read_mask に指定してもよい）
+    pub fixed_fields: &'static [&'static str],
+    /// (proto フィールド名, 列名)。マスク外は SELECT しない（モデルに #[sqlx(default)] が必要）
+    pub text_columns: &'static [(&'static str, &'static str)],
+}
+
+impl MaskableColumns {
+    /// SELECT 句を組み立てる。マスク未指定（または空）の場合は `*`
+    pub fn select_list(&self, mask: Option<&FieldMask>) -> Result<String, Status> {
+        let Some(mask) = mask.filter(|m| !m.paths.is_empty()) else {
+            return Ok("*".to_string());
+        };
+
+        // 列名（"CarName" など）ではなく proto のフィールド名だけを受け付ける
+        for path in &mask.paths {
+            let known = self.fixed_fields.contains(&path.as_str())
+                || self.text_columns.iter().any(|(field, _)| field == path);
+            if !known {
+                return Err(Status::invalid_argument(format!(
+                    "Unknown read_mask path: {}",
+                    path
+                )));
+            }
+        }
+
+        let mut columns: Vec<String> = self.fixed.iter().map(|c| format!("\"{}\"", c)).collect();
+        for (field, column) in self.text_columns {
+            if !self.fixed.contains(column) && mask.paths.iter().any(|p| p == field) {
+                columns.push(format!("\"{}\"", column));
+            }
+        }
+        Ok(columns.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: MaskableColumns = MaskableColumns {
+        fixed: &["id", "Key"],
+        fixed_fields: &["id"],
+        text_columns: &[("key", "Key"), ("car_name", "CarName"), ("car_no", "CarNo")],
+    };
+
+    #[test]
+    fn test_select_list_masks_unrequested_columns() {
+        let mask = FieldMask {
+            paths: vec!["car_name".to_string()],
+        };
+        assert_eq!(
+            COLUMNS.select_list(Some(&mask)).unwrap(),
+            r#""id", "Key", "CarName""#
+        );
+        assert_eq!(COLUMNS.select_list(None).unwrap(), "*");
+    }
+
+    #[test]
+    fn test_select_list_rejects_unknown_path() {
+        let mask = FieldMask {
+            paths: vec!["nope".to_string()],
+        };
+        assert!(COLUMNS.select_list(Some(&mask)).is_err());
+    }
+
+    #[test]
+    fn test_select_list_rejects_column_names() {
+        let mask = FieldMask {
+            paths: vec!["Key".to_string()],
+        };
+        let err = COLUMNS.select_list(Some(&mask)).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let mask = FieldMask {
+            paths: vec!["id".to_string(), "key".to_string()],
+        };
+        assert_eq!(COLUMNS.select_list(Some(&mask)).unwrap(), r#""id", "Key""#);
+    }
+}
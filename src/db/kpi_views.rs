@@ -0,0 +1,126 @@
+// KPI materialized views (migration 00066)
+//
+// ダッシュボードの重い集計を materialized view に持ち、バックグラウンドで定期的に REFRESH する。
+// 読む側は `is_fresh` で最終更新を確認し、古ければ元のテーブルを直接集計する。
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+
+use super::AdvisoryLock;
+
+/// 更新が必要な view を確認する間隔
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// 複数インスタンスで同時に REFRESH しない
+const REFRESH_LOCK_KEY: &str = "kpi_views.refresh";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KpiView {
+    /// 車両ごとの最新運行ログ（dtakologs_latest）
+    DtakologsLatest,
+    /// 車両ごとの最新の車検証（car_inspection_current）
+    CarInspectionCurrent,
+}
+
+impl KpiView {
+    pub const ALL: [KpiView; 2] = [KpiView::DtakologsLatest, KpiView::CarInspectionCurrent];
+
+    /// materialized view 名（kpi_view_refreshes.view_name）
+    pub fn name(&self) -> &'static str {
+        match self {
+            KpiView::DtakologsLatest => "mv_dtakologs_latest",
+            KpiView::CarInspectionCurrent => "mv_car_inspection_current",
+        }
+    }
+
+    /// 更新間隔（運行ログは数分おきに取り込まれる）
+    fn refresh_every(&self) -> chrono::Duration {
+        match self {
+            KpiView::DtakologsLatest => chrono::Duration::minutes(1),
+            KpiView::CarInspectionCurrent => chrono::Duration::minutes(10),
+        }
+    }
+
+    /// これより古ければ読む側は view を使わない
+    fn max_stale(&self) -> chrono::Duration {
+        match self {
+            KpiView::DtakologsLatest => chrono::Duration::minutes(5),
+            KpiView::CarInspectionCurrent => chrono::Duration::hours(1),
+        }
+    }
+}
+
+async fn refreshed_at(conn: &mut PgConnection, view: KpiView) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query_scalar("SELECT refreshed_at FROM kpi_view_refreshes WHERE view_name = $1")
+        .bind(view.name())
+        .fetch_optional(conn)
+        .await
+}
+
+/// 最終更新が許容範囲内ならその時刻（確認できなければ None）
+pub async fn fresh_as_of(conn: &mut PgConnection, view: KpiView) -> Option<DateTime<Utc>> {
+    match refreshed_at(conn, view).await {
+        Ok(Some(at)) if Utc::now() - at <= view.max_stale() => Some(at),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Failed to check {} freshness: {}", view.name(), e);
+            None
+        }
+    }
+}
+
+/// 最終更新が許容範囲内か
+pub async fn is_fresh(conn: &mut PgConnection, view: KpiView) -> bool {
+    fresh_as_of(conn, view).await.is_some()
+}
+
+/// materialized view を定期的に REFRESH する
+pub struct KpiViewRefresher {
+    pool: PgPool,
+}
+
+impl KpiViewRefresher {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.tick().await {
+                    tracing::warn!("Failed to refresh KPI views: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn tick(&self) -> Result<(), sqlx::Error> {
+        let Some(lock) = AdvisoryLock::try_acquire(&self.pool, REFRESH_LOCK_KEY).await? else {
+            return Ok(());
+        };
+        let mut conn = self.pool.acquire().await?;
+        for view in KpiView::ALL {
+            let due = match refreshed_at(&mut conn, view).await? {
+                Some(at) => Utc::now() - at >= view.refresh_every(),
+                None => true,
+            };
+            if !due {
+                continue;
+            }
+            match sqlx::query_scalar::<_, i64>("SELECT refresh_kpi_view($1)")
+                .bind(view.name())
+                .fetch_one(&mut *conn)
+                .await
+            {
+                Ok(duration_ms) => tracing::debug!("Refreshed {} in {} ms", view.name(), duration_ms),
+                Err(e) => tracing::warn!("Failed to refresh {}: {}", view.name(), e),
+            }
+        }
+        drop(conn);
+        lock.release().await;
+        Ok(())
+    }
+}
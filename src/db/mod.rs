@@ -1,7 +1,21 @@
+pub mod advisory_lock;
+pub mod audit;
+pub mod field_mask;
+pub mod kpi_views;
+pub mod order_by;
 pub mod pool;
 pub mod organization;
+pub mod pagination;
+pub mod tx;
 
+pub use advisory_lock::AdvisoryLock;
+pub use audit::AuditEvent;
+pub use field_mask::MaskableColumns;
+pub use kpi_views::{KpiView, KpiViewRefresher};
+pub use order_by::{OrderBy, SortableColumns};
 pub use pool::create_pool;
+pub use pagination::Paginator;
+pub use tx::with_org_transaction;
 pub use organization::{
     set_current_organization,
     get_current_organization,
@@ -9,6 +23,7 @@ pub use organization::{
     get_organization_from_request,
     OrganizationContext,
     OrganizationConnection,
+    OrgScopedPool,
     DEFAULT_ORGANIZATION_ID,
     ORGANIZATION_METADATA_KEY,
 };
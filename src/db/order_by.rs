@@ -0,0 +1,206 @@
+// Whitelist-validated order_by for List RPCs
+//
+// order_by は "field [asc|desc], field2 [asc|desc]" 形式。フィールド名はホワイトリストで
+// 列名に変換するため、任意の SQL が ORDER BY に入ることはない。
+// 末尾には一意キー列を自動で追加し、キーセットページネーションを成立させる。
+// page_token には一意キーを格納し、カーソル行の並び替え列を JOIN で引いて比較する。
+
+use sqlx::{Postgres, QueryBuilder};
+use tonic::Status;
+
+use super::Paginator;
+
+/// order_by で並び替え可能な列の定義
+pub struct SortableColumns {
+    /// カーソル行を引くテーブル
+    pub table: &'static str,
+    /// 一意キー列と型（page_token に格納。タイブレークとして常に末尾に追加）
+    pub key_columns: &'static [(&'static str, &'static str)],
+    /// order_by のフィールド名 → 列（複数列で 1 フィールドを表す場合あり）
+    pub fields: &'static [(&'static str, &'static [&'static str])],
+}
+
+impl SortableColumns {
+    pub fn field_names(&self) -> Vec<&'static str> {
+        self.fields.iter().map(|(name, _)| *name).collect()
+    }
+}
+
+/// 並び替え指定（列, 降順か）
+#[derive(Debug)]
+pub struct OrderBy {
+    columns: &'static SortableColumns,
+    terms: Vec<(&'static str, bool)>,
+}
+
+impl OrderBy {
+    /// order_by を解析する。空文字列なら None（各 RPC の既定順）
+    pub fn parse(spec: &str, columns: &'static SortableColumns) -> Result<Option<Self>, Status> {
+        if spec.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut terms: Vec<(&'static str, bool)> = Vec::new();
+        let mut seen: Vec<&str> = Vec::new();
+        for part in spec.split(',') {
+            let mut words = part.split_whitespace();
+            let name = words
+                .next()
+                .ok_or_else(|| Status::invalid_argument("Empty order_by term"))?;
+            let desc = match words.next().map(|w| w.to_ascii_lowercase()).as_deref() {
+                None | Some("asc") => false,
+                Some("desc") => true,
+                Some(other) => {
+                    return Err(Status::invalid_argument(format!(
+                        "Invalid order_by direction: {}",
+                        other
+                    )))
+                }
+            };
+            if words.next().is_some() {
+                return Err(Status::invalid_argument(format!("Invalid order_by term: {}", part.trim())));
+            }
+
+            let Some((_, cols)) = columns.fields.iter().find(|(f, _)| *f == name) else {
+                return Err(Status::invalid_argument(format!(
+                    "Unsupported order_by field: {} (allowed: {})",
+                    name,
+                    columns.field_names().join(", ")
+                )));
+            };
+            if seen.contains(&name) {
+                return Err(Status::invalid_argument(format!("Duplicate order_by field: {}", name)));
+            }
+            seen.push(name);
+            terms.extend(cols.iter().map(|c| (*c, desc)));
+        }
+
+        // 一意キーでタイブレーク（先頭の向きに揃える）
+        let tiebreak_desc = terms.first().map(|(_, d)| *d).unwrap_or(false);
+        for (key, _) in columns.key_columns {
+            if !terms.iter().any(|(c, _)| c == key) {
+                terms.push((key, tiebreak_desc));
+            }
+        }
+
+        Ok(Some(Self { columns, terms }))
+    }
+
+    /// カーソル行を JOIN する（FROM 句の直後に呼ぶ）
+    pub fn push_cursor_join(&self, qb: &mut QueryBuilder<'_, Postgres>, paginator: &Paginator) -> Result<(), Status> {
+        let Some(cursor) = paginator.cursor_keys() else {
+            return Ok(());
+        };
+        if cursor.len() != self.columns.key_columns.len() {
+            return Err(Status::invalid_argument("Invalid page_token"));
+        }
+
+        qb.push(" CROSS JOIN (SELECT ");
+        for (i, (col, _)) in self.terms.iter().enumerate() {
+            if i > 0 {
+                qb.push(", ");
+            }
+            qb.push(format!("\"{}\" AS sort_{}", col, i));
+        }
+        qb.push(format!(" FROM {} WHERE ", self.columns.table));
+        for (i, ((col, ty), value)) in self.columns.key_columns.iter().zip(cursor).enumerate() {
+            if i > 0 {
+                qb.push(" AND ");
+            }
+            qb.push(format!("\"{}\" = ", col));
+            qb.push_bind(value.clone());
+            qb.push(format!("::{}", ty));
+        }
+        qb.push(") AS cursor_row");
+        Ok(())
+    }
+
+    /// カーソル条件・ORDER BY・LIMIT を追加する（WHERE 句の末尾で呼ぶ）
+    pub fn push_page(&self, qb: &mut QueryBuilder<'_, Postgres>, alias: &str, paginator: &Paginator) {
+        if paginator.cursor_keys().is_some() {
+            // (a > c) OR (a = c AND b > d) OR ... の形で「カーソル行より後ろ」を表す
+            qb.push(" AND (");
+            for i in 0..self.terms.len() {
+                if i > 0 {
+                    qb.push(" OR ");
+                }
+                qb.push("(");
+                for (j, (col, desc)) in self.terms.iter().enumerate().take(i + 1) {
+                    if j > 0 {
+                        qb.push(" AND ");
+                    }
+                    let op = if j < i { "=" } else if *desc { "<" } else { ">" };
+                    qb.push(format!("{}.\"{}\" {} cursor_row.sort_{}", alias, col, op, j));
+                }
+                qb.push(")");
+            }
+            qb.push(")");
+        }
+
+        qb.push(" ORDER BY ");
+        for (i, (col, desc)) in self.terms.iter().enumerate() {
+            if i > 0 {
+                qb.push(", ");
+            }
+            qb.push(format!("{}.\"{}\" {}", alias, col, if *desc { "DESC" } else { "ASC" }));
+        }
+        qb.push(" LIMIT ");
+        qb.push_bind(paginator.limit());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::pagination::encode_page_token;
+    use crate::proto::common::PaginationRequest;
+
+    static COLUMNS: SortableColumns = SortableColumns {
+        table: "files",
+        key_columns: &[("uuid", "uuid")],
+        fields: &[("filename", &["filename"]), ("created_at", &["created_at"])],
+    };
+
+    #[test]
+    fn test_parse_appends_key_tiebreak() {
+        let order = OrderBy::parse("filename desc, created_at", &COLUMNS).unwrap().unwrap();
+        assert_eq!(
+            order.terms,
+            vec![("filename", true), ("created_at", false), ("uuid", true)]
+        );
+        assert!(OrderBy::parse("", &COLUMNS).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_fields() {
+        assert!(OrderBy::parse("blob", &COLUMNS).is_err());
+        assert!(OrderBy::parse("filename; DROP TABLE files", &COLUMNS).is_err());
+        assert!(OrderBy::parse("filename sideways", &COLUMNS).is_err());
+        assert!(OrderBy::parse("filename, filename desc", &COLUMNS).is_err());
+    }
+
+    #[test]
+    fn test_sql_fragments() {
+        let order = OrderBy::parse("filename", &COLUMNS).unwrap().unwrap();
+        let paginator = Paginator::from_request(Some(&PaginationRequest {
+            page: 0,
+            per_page: 10,
+            page_token: encode_page_token(&["u1".to_string()]),
+        }))
+        .unwrap();
+
+        let mut qb = QueryBuilder::<Postgres>::new("SELECT * FROM files");
+        order.push_cursor_join(&mut qb, &paginator).unwrap();
+        qb.push(" WHERE files.deleted_at IS NULL");
+        order.push_page(&mut qb, "files", &paginator);
+        assert_eq!(
+            qb.sql(),
+            "SELECT * FROM files CROSS JOIN (SELECT \"filename\" AS sort_0, \"uuid\" AS sort_1 \
+             FROM files WHERE \"uuid\" = $1::uuid) AS cursor_row \
+             WHERE files.deleted_at IS NULL \
+             AND ((files.\"filename\" > cursor_row.sort_0) \
+             OR (files.\"filename\" = cursor_row.sort_0 AND files.\"uuid\" > cursor_row.sort_1)) \
+             ORDER BY files.\"filename\" ASC, files.\"uuid\" ASC LIMIT $2"
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use sqlx::{PgConnection, PgPool, Executor};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, PgPool, Executor, Postgres, Transaction};
 use std::future::Future;
 use tonic::metadata::MetadataMap;
 
@@ -35,6 +36,11 @@ pub fn get_organization_from_request<T>(request: &tonic::Request<T>) -> String {
 
 /// Sets the current organization for the database session.
 /// This must be called at the beginning of each request/transaction.
+#[tracing::instrument(
+    name = "db.set_current_organization",
+    skip(conn),
+    fields(otel.kind = "client", db.system = "postgresql")
+)]
 pub async fn set_current_organization(
     conn: &mut PgConnection,
     organization_id: &str,
@@ -46,6 +52,45 @@ pub async fn set_current_organization(
     Ok(())
 }
 
+/// Acquires connections with the organization context already set.
+///
+/// `set_current_organization` の呼び忘れを防ぐため、サービスはこちらで接続を取る。
+/// 設定した組織（と `set_current_user`）はプールに返すときに `create_pool` の after_release で消える。
+pub trait OrgScopedPool {
+    /// 組織を設定したコネクション
+    fn acquire_scoped(
+        &self,
+        organization_id: &str,
+    ) -> impl Future<Output = Result<PoolConnection<Postgres>, sqlx::Error>> + Send;
+
+    /// 組織を設定したトランザクション
+    fn begin_scoped(
+        &self,
+        organization_id: &str,
+    ) -> impl Future<Output = Result<Transaction<'static, Postgres>, sqlx::Error>> + Send;
+}
+
+impl OrgScopedPool for PgPool {
+    async fn acquire_scoped(&self, organization_id: &str) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        let mut conn = self.acquire().await?;
+        set_current_organization(&mut conn, organization_id).await?;
+        Ok(conn)
+    }
+
+    async fn begin_scoped(&self, organization_id: &str) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        set_current_organization(&mut tx, organization_id).await?;
+        Ok(tx)
+    }
+}
+
+/// プールに返すコネクションの組織・ユーザーを消す（前のリクエストの RLS 設定を引き継がない）
+pub async fn reset_session_context(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    conn.execute("RESET app.current_organization_id; RESET app.current_user_id")
+        .await?;
+    Ok(())
+}
+
 /// Gets the current organization ID from the database session.
 pub async fn get_current_organization(conn: &mut PgConnection) -> Result<Option<String>, sqlx::Error> {
     let result: Option<(Option<String>,)> = sqlx::query_as("SELECT get_current_organization()")
@@ -0,0 +1,205 @@
+// Keyset pagination shared by all List RPCs
+//
+// page_token は「前ページ最終行のソートキー」を JSON 配列 → base64url で符号化したもの。
+// OFFSET を使わないため、深いページでも一定コストで取得でき、途中で行が追加されてもずれない。
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use tonic::Status;
+
+use crate::proto::common::{PaginationMeta, PaginationRequest};
+
+/// per_page 未指定（0）時の件数
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+/// per_page の上限
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+/// キーセットページネーション
+///
+/// `pagination` 未指定のリクエストは従来通り全件を返す（後方互換）。
+#[derive(Debug, Clone, Default)]
+pub struct Paginator {
+    page_size: Option<i64>,
+    cursor: Option<Vec<String>>,
+}
+
+impl Paginator {
+    pub fn from_request(pagination: Option<&PaginationRequest>) -> Result<Self, Status> {
+        let Some(p) = pagination else {
+            return Ok(Self::default());
+        };
+
+        if p.per_page < 0 {
+            return Err(Status::invalid_argument("per_page must not be negative"));
+        }
+        let page_size = if p.per_page == 0 {
+            DEFAULT_PAGE_SIZE
+        } else {
+            (p.per_page as i64).min(MAX_PAGE_SIZE)
+        };
+
+        let cursor = if p.page_token.is_empty() {
+            None
+        } else {
+            Some(decode_page_token(&p.page_token)?)
+        };
+
+        Ok(Self {
+            page_size: Some(page_size),
+            cursor,
+        })
+    }
+
+    /// SQL の LIMIT に渡す値（次ページ判定用に +1）。全件取得時は None（LIMIT NULL）
+    pub fn limit(&self) -> Option<i64> {
+        self.page_size.map(|n| n + 1)
+    }
+
+    /// カーソルの idx 番目のキー値（先頭ページでは None）
+    pub fn cursor(&self, idx: usize) -> Option<&str> {
+        self.cursor
+            .as_ref()
+            .and_then(|keys| keys.get(idx))
+            .map(|s| s.as_str())
+    }
+
+    /// カーソルのキー値すべて（先頭ページでは None）
+    pub fn cursor_keys(&self) -> Option<&[String]> {
+        self.cursor.as_deref()
+    }
+
+    /// カーソルの idx 番目を数値等にパースして取得
+    pub fn cursor_as<T: std::str::FromStr>(&self, idx: usize) -> Result<Option<T>, Status> {
+        self.cursor(idx)
+            .map(|s| {
+                s.parse::<T>()
+                    .map_err(|_| Status::invalid_argument("Invalid page_token"))
+            })
+            .transpose()
+    }
+
+    /// SQL で LIMIT/カーソル適用済みの結果から、ページとメタ情報を組み立てる
+    ///
+    /// ページング時は COUNT を取らないため total / total_pages は None（続きは has_more で判断する）
+    pub fn finish<T>(&self, mut rows: Vec<T>, key: impl Fn(&T) -> Vec<String>) -> (Vec<T>, PaginationMeta) {
+        let Some(page_size) = self.page_size else {
+            let total = rows.len() as i32;
+            return (
+                rows,
+                PaginationMeta {
+                    total: Some(total),
+                    page: 1,
+                    per_page: total,
+                    total_pages: Some(1),
+                    next_page_token: String::new(),
+                    has_more: false,
+                },
+            );
+        };
+
+        let has_more = rows.len() as i64 > page_size;
+        rows.truncate(page_size as usize);
+        let next_page_token = if has_more {
+            rows.last().map(|last| encode_page_token(&key(last))).unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        (
+            rows,
+            PaginationMeta {
+                total: None,
+                page: 0,
+                per_page: page_size as i32,
+                total_pages: None,
+                next_page_token,
+                has_more,
+            },
+        )
+    }
+
+    /// アプリケーション側でソート済みの全件からページを切り出す
+    pub fn paginate<T>(&self, rows: Vec<T>, key: impl Fn(&T) -> Vec<String>) -> (Vec<T>, PaginationMeta) {
+        let rows = match (&self.cursor, self.page_size) {
+            (Some(cursor), Some(page_size)) => {
+                let mut iter = rows.into_iter();
+                // カーソル行の次から取得（カーソル行が消えている場合は空ページ）
+                for row in iter.by_ref() {
+                    if &key(&row) == cursor {
+                        break;
+                    }
+                }
+                iter.take(page_size as usize + 1).collect()
+            }
+            (None, Some(page_size)) => rows.into_iter().take(page_size as usize + 1).collect(),
+            _ => rows,
+        };
+        self.finish(rows, key)
+    }
+}
+
+pub fn encode_page_token(keys: &[String]) -> String {
+    let json = serde_json::to_vec(keys).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+pub fn decode_page_token(token: &str) -> Result<Vec<String>, Status> {
+    URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Vec<String>>(&bytes).ok())
+        .ok_or_else(|| Status::invalid_argument("Invalid page_token"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(per_page: i32, page_token: &str) -> PaginationRequest {
+        PaginationRequest {
+            page: 0,
+            per_page,
+            page_token: page_token.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_page_token_roundtrip() {
+        let keys = vec!["2026-01-24T21:06:00+09:00".to_string(), "42".to_string()];
+        let token = encode_page_token(&keys);
+        assert_eq!(decode_page_token(&token).unwrap(), keys);
+        assert!(decode_page_token("not-a-token!").is_err());
+    }
+
+    #[test]
+    fn test_paginate_walks_all_pages() {
+        let rows: Vec<i32> = (1..=5).collect();
+        let key = |r: &i32| vec![r.to_string()];
+
+        let p1 = Paginator::from_request(Some(&request(2, ""))).unwrap();
+        let (page, meta) = p1.paginate(rows.clone(), key);
+        assert_eq!(page, vec![1, 2]);
+        assert!(meta.has_more);
+        assert_eq!(meta.total, None);
+        assert_eq!(meta.total_pages, None);
+
+        let p2 = Paginator::from_request(Some(&request(2, &meta.next_page_token))).unwrap();
+        let (page, meta) = p2.paginate(rows.clone(), key);
+        assert_eq!(page, vec![3, 4]);
+
+        let p3 = Paginator::from_request(Some(&request(2, &meta.next_page_token))).unwrap();
+        let (page, meta) = p3.paginate(rows, key);
+        assert_eq!(page, vec![5]);
+        assert!(!meta.has_more);
+        assert!(meta.next_page_token.is_empty());
+    }
+
+    #[test]
+    fn test_unpaged_returns_everything() {
+        let paginator = Paginator::from_request(None).unwrap();
+        assert_eq!(paginator.limit(), None);
+        let (page, meta) = paginator.finish(vec![1, 2, 3], |r: &i32| vec![r.to_string()]);
+        assert_eq!(page.len(), 3);
+        assert_eq!(meta.total, Some(3));
+        assert_eq!(meta.total_pages, Some(1));
+    }
+}
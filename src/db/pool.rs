@@ -2,10 +2,14 @@ use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use std::time::Duration;
 
+use super::organization::reset_session_context;
+
+/// 返却時に組織・ユーザーの設定を消す（消せなかったコネクションは閉じる）
 pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
     PgPoolOptions::new()
         .max_connections(10)
         .acquire_timeout(Duration::from_secs(30))
+        .after_release(|conn, _meta| Box::pin(async move { Ok(reset_session_context(conn).await.is_ok()) }))
         .connect(database_url)
         .await
 }
@@ -0,0 +1,24 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use std::future::Future;
+
+use crate::db::OrgScopedPool;
+use crate::error::AppError;
+
+/// 組織を設定したトランザクションで f を実行し、Ok ならコミットする（複数の書き込みをまとめて反映する）
+///
+/// f は受け取ったトランザクションを結果と一緒に返す。Err を返すとトランザクションはそこで drop され、ロールバックされる。
+/// begin / commit の失敗は AppError として E に変換する（Status・anyhow::Error・AppError のいずれでも使える）。
+pub async fn with_org_transaction<T, E, F, Fut>(pool: &PgPool, organization_id: &str, f: F) -> Result<T, E>
+where
+    F: FnOnce(Transaction<'static, Postgres>) -> Fut,
+    Fut: Future<Output = Result<(Transaction<'static, Postgres>, T), E>>,
+    E: From<AppError>,
+{
+    let tx = pool
+        .begin_scoped(organization_id)
+        .await
+        .map_err(AppError::from)?;
+    let (tx, value) = f(tx).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(value)
+}
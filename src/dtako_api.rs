@@ -0,0 +1,156 @@
+// dtako API（hono-api、デジタコの車両データ）
+//
+// DTAKO_API_URL は API の基底 URL（従来どおりエンドポイントの完全な URL でもよい）。
+// DTAKO_API_TOKEN があれば Bearer で送る。一覧は配列、またはページ形式
+// （{"data": [...], "next_cursor": "..."}）のどちらも受け付け、カーソルを辿って全件を返す。
+// 応答は DTAKO_HOME_CARS_CACHE_TTL_SECS の間使い回す（キャッシュは組織ごと。別の組織の呼び出しとは共有しない）。
+// サービスは `DtakoApi` trait 経由で使う（テストでは差し替える）。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::http_client::HttpClient;
+use crate::models::HomeCarEntry;
+
+/// ホーム車両一覧（本社営業所の現在の車両）
+const HOME_CARS_PATH: &str = "/dtakologs/currentListAllHome";
+/// ページを辿る上限（カーソルが循環しても止まるように）
+const MAX_PAGES: usize = 100;
+/// 応答を使い回す時間の既定（同時に来た RPC で dtako API を重ねて呼ばない）
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+#[tonic::async_trait]
+pub trait DtakoApi: Send + Sync {
+    /// ホーム車両一覧（呼び出した組織のキャッシュを使う。refresh なら使い回さずに取り直す）
+    async fn home_cars(&self, organization_id: &str, refresh: bool) -> anyhow::Result<Vec<HomeCarEntry>>;
+}
+
+/// 一覧の応答（配列そのもの、またはページ）
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ListResponse<T> {
+    Items(Vec<T>),
+    Page {
+        #[serde(alias = "items")]
+        data: Vec<T>,
+        #[serde(default, alias = "nextCursor")]
+        next_cursor: Option<String>,
+    },
+}
+
+impl<T> ListResponse<T> {
+    fn into_parts(self) -> (Vec<T>, Option<String>) {
+        match self {
+            ListResponse::Items(items) => (items, None),
+            ListResponse::Page { data, next_cursor } => (data, next_cursor.filter(|c| !c.is_empty())),
+        }
+    }
+}
+
+pub struct DtakoApiClient {
+    http_client: Arc<HttpClient>,
+    base_url: String,
+    token: Option<String>,
+    cache_ttl: Duration,
+}
+
+impl DtakoApiClient {
+    pub fn new(http_client: Arc<HttpClient>, base_url: String, token: Option<String>) -> Self {
+        Self {
+            http_client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            cache_ttl: DEFAULT_CACHE_TTL,
+        }
+    }
+
+    /// 応答を使い回す時間を変える（0 なら同時の呼び出しをまとめるだけ）
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// エンドポイントの URL（基底 URL がすでにエンドポイントならそのまま）
+    fn endpoint(&self, path: &str) -> String {
+        if self.base_url.ends_with(path) {
+            self.base_url.clone()
+        } else {
+            format!("{}{}", self.base_url, path)
+        }
+    }
+
+    /// 一覧をカーソルで最後まで取得する（キャッシュのキーに組織を含める）
+    async fn list<T: DeserializeOwned>(&self, path: &str, organization_id: &str, refresh: bool) -> anyhow::Result<Vec<T>> {
+        let url = self.endpoint(path);
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let mut request = self.http_client.client().get(&url);
+            if let Some(cursor) = &cursor {
+                request = request.query(&[("cursor", cursor)]);
+            }
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let response = if refresh {
+                self.http_client.refresh_cached(request, self.cache_ttl, Some(organization_id)).await?
+            } else {
+                self.http_client.send_cached(request, self.cache_ttl, Some(organization_id)).await?
+            };
+            if !response.status.is_success() {
+                anyhow::bail!("dtako API {} returned {}", path, response.status);
+            }
+            let (page, next) = response.json::<ListResponse<T>>()?.into_parts();
+            items.extend(page);
+            match next {
+                Some(next) => cursor = Some(next),
+                None => return Ok(items),
+            }
+        }
+        anyhow::bail!("dtako API {} returned more than {} pages", path, MAX_PAGES)
+    }
+}
+
+#[tonic::async_trait]
+impl DtakoApi for DtakoApiClient {
+    async fn home_cars(&self, organization_id: &str, refresh: bool) -> anyhow::Result<Vec<HomeCarEntry>> {
+        self.list(HOME_CARS_PATH, organization_id, refresh).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_response_formats() {
+        let items: ListResponse<HomeCarEntry> =
+            serde_json::from_str(r#"[{"VehicleCD": 101, "VehicleName": "1号車"}]"#).unwrap();
+        let (cars, next) = items.into_parts();
+        assert_eq!(cars[0].vehicle_cd, 101);
+        assert_eq!(next, None);
+
+        let page: ListResponse<HomeCarEntry> =
+            serde_json::from_str(r#"{"data": [{"VehicleCD": 102}], "nextCursor": "abc"}"#).unwrap();
+        let (cars, next) = page.into_parts();
+        assert_eq!(cars[0].vehicle_cd, 102);
+        assert_eq!(next.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_endpoint_accepts_base_or_full_url() {
+        let http_client = Arc::new(HttpClient::new());
+        let client = DtakoApiClient::new(http_client.clone(), "https://example.com/api/".to_string(), None);
+        assert_eq!(client.endpoint(HOME_CARS_PATH), "https://example.com/api/dtakologs/currentListAllHome");
+
+        let legacy = DtakoApiClient::new(
+            http_client,
+            "https://example.com/api/dtakologs/currentListAllHome".to_string(),
+            None,
+        );
+        assert_eq!(legacy.endpoint(HOME_CARS_PATH), "https://example.com/api/dtakologs/currentListAllHome");
+    }
+}
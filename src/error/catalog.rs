@@ -0,0 +1,298 @@
+// User-facing error catalog
+//
+// 安定したエラーコード（google.rpc.ErrorInfo.reason）と日本語/英語メッセージの対応表。
+// クライアントは accept-language で言語を選び、画面表示には grpc-message（LocalizedMessage）、
+// 分岐には ErrorInfo.reason を使う。SQL 等の内部詳細はクライアントへ返さずログにのみ残す。
+
+use std::collections::HashMap;
+
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+
+/// ErrorInfo.domain
+pub const ERROR_DOMAIN: &str = "logi";
+
+/// ErrorInfo.metadata に元のメッセージを入れるキー（内部エラー以外）
+const DETAIL_KEY: &str = "detail";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Ja,
+    En,
+}
+
+impl Locale {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::Ja => "ja-JP",
+            Locale::En => "en-US",
+        }
+    }
+
+    /// accept-language から対応言語を選ぶ（q 値順、未対応・未指定は日本語）
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        let Some(header) = header else {
+            return Locale::Ja;
+        };
+
+        let mut candidates: Vec<(f32, &str)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.trim().split(';');
+                let tag = pieces.next()?.trim();
+                let q = pieces
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((q, tag))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (_, tag) in candidates {
+            let primary = tag.split('-').next().unwrap_or("").to_ascii_lowercase();
+            match primary.as_str() {
+                "ja" => return Locale::Ja,
+                "en" => return Locale::En,
+                _ => {}
+            }
+        }
+        Locale::Ja
+    }
+}
+
+/// 安定エラーコード
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    InvalidArgument,
+    InvalidPageToken,
+    InvalidOrderBy,
+    TooManyEntries,
+    NotFound,
+    AlreadyExists,
+    Unauthenticated,
+    PermissionDenied,
+    FailedPrecondition,
+    ResourceExhausted,
+    Aborted,
+    Unavailable,
+    Unimplemented,
+    DeadlineExceeded,
+    Cancelled,
+    DatabaseError,
+    StorageError,
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::InvalidArgument,
+        ErrorCode::InvalidPageToken,
+        ErrorCode::InvalidOrderBy,
+        ErrorCode::TooManyEntries,
+        ErrorCode::NotFound,
+        ErrorCode::AlreadyExists,
+        ErrorCode::Unauthenticated,
+        ErrorCode::PermissionDenied,
+        ErrorCode::FailedPrecondition,
+        ErrorCode::ResourceExhausted,
+        ErrorCode::Aborted,
+        ErrorCode::Unavailable,
+        ErrorCode::Unimplemented,
+        ErrorCode::DeadlineExceeded,
+        ErrorCode::Cancelled,
+        ErrorCode::DatabaseError,
+        ErrorCode::StorageError,
+        ErrorCode::Internal,
+    ];
+
+    /// ErrorInfo.reason（クライアントが分岐に使う。変更禁止）
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::InvalidPageToken => "INVALID_PAGE_TOKEN",
+            ErrorCode::InvalidOrderBy => "INVALID_ORDER_BY",
+            ErrorCode::TooManyEntries => "TOO_MANY_ENTRIES",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::AlreadyExists => "ALREADY_EXISTS",
+            ErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::FailedPrecondition => "FAILED_PRECONDITION",
+            ErrorCode::ResourceExhausted => "RESOURCE_EXHAUSTED",
+            ErrorCode::Aborted => "ABORTED",
+            ErrorCode::Unavailable => "UNAVAILABLE",
+            ErrorCode::Unimplemented => "UNIMPLEMENTED",
+            ErrorCode::DeadlineExceeded => "DEADLINE_EXCEEDED",
+            ErrorCode::Cancelled => "CANCELLED",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::StorageError => "STORAGE_ERROR",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    pub fn from_reason(reason: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.as_str() == reason)
+    }
+
+    pub fn message(self, locale: Locale) -> &'static str {
+        match (self, locale) {
+            (ErrorCode::InvalidArgument, Locale::Ja) => "入力内容に誤りがあります",
+            (ErrorCode::InvalidArgument, Locale::En) => "The request contains invalid values",
+            (ErrorCode::InvalidPageToken, Locale::Ja) => "ページ情報が無効です。最初のページから再読み込みしてください",
+            (ErrorCode::InvalidPageToken, Locale::En) => "The page token is invalid. Reload from the first page",
+            (ErrorCode::InvalidOrderBy, Locale::Ja) => "指定された並び順には対応していません",
+            (ErrorCode::InvalidOrderBy, Locale::En) => "The requested sort order is not supported",
+            (ErrorCode::TooManyEntries, Locale::Ja) => "一度に処理できる件数を超えています",
+            (ErrorCode::TooManyEntries, Locale::En) => "Too many entries in a single request",
+            (ErrorCode::NotFound, Locale::Ja) => "対象のデータが見つかりません",
+            (ErrorCode::NotFound, Locale::En) => "The requested resource was not found",
+            (ErrorCode::AlreadyExists, Locale::Ja) => "同じデータが既に登録されています",
+            (ErrorCode::AlreadyExists, Locale::En) => "The resource already exists",
+            (ErrorCode::Unauthenticated, Locale::Ja) => "ログインが必要です。再度ログインしてください",
+            (ErrorCode::Unauthenticated, Locale::En) => "Authentication required. Please sign in again",
+            (ErrorCode::PermissionDenied, Locale::Ja) => "この操作を行う権限がありません",
+            (ErrorCode::PermissionDenied, Locale::En) => "You do not have permission to perform this action",
+            (ErrorCode::FailedPrecondition, Locale::Ja) => "現在の状態ではこの操作を実行できません",
+            (ErrorCode::FailedPrecondition, Locale::En) => "The operation cannot be performed in the current state",
+            (ErrorCode::ResourceExhausted, Locale::Ja) => "利用上限に達しました。しばらくしてから再度お試しください",
+            (ErrorCode::ResourceExhausted, Locale::En) => "Limit reached. Please try again later",
+            (ErrorCode::Aborted, Locale::Ja) => "他の操作と競合しました。再読み込みしてください",
+            (ErrorCode::Aborted, Locale::En) => "The operation conflicted with another change. Please reload",
+            (ErrorCode::Unavailable, Locale::Ja) => "サービスが一時的に利用できません",
+            (ErrorCode::Unavailable, Locale::En) => "The service is temporarily unavailable",
+            (ErrorCode::Unimplemented, Locale::Ja) => "この機能は利用できません",
+            (ErrorCode::Unimplemented, Locale::En) => "This feature is not available",
+            (ErrorCode::DeadlineExceeded, Locale::Ja) => "処理がタイムアウトしました",
+            (ErrorCode::DeadlineExceeded, Locale::En) => "The request timed out",
+            (ErrorCode::Cancelled, Locale::Ja) => "処理がキャンセルされました",
+            (ErrorCode::Cancelled, Locale::En) => "The request was cancelled",
+            (ErrorCode::DatabaseError, Locale::Ja) => "データベースでエラーが発生しました",
+            (ErrorCode::DatabaseError, Locale::En) => "A database error occurred",
+            (ErrorCode::StorageError, Locale::Ja) => "ファイルの保存・取得に失敗しました",
+            (ErrorCode::StorageError, Locale::En) => "Failed to store or retrieve the file",
+            (ErrorCode::Internal, Locale::Ja) => "サーバーでエラーが発生しました",
+            (ErrorCode::Internal, Locale::En) => "An internal server error occurred",
+        }
+    }
+
+    /// 内部エラー（元のメッセージをクライアントに返さない）
+    pub fn is_internal(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::DatabaseError | ErrorCode::StorageError | ErrorCode::Internal
+        )
+    }
+
+    /// コードを明示した Status を作る（ハンドラで分類を確定させたい場合）
+    pub fn status(self, code: Code, detail: impl Into<String>) -> Status {
+        let detail = detail.into();
+        let details = ErrorDetails::with_error_info(self.as_str(), ERROR_DOMAIN, HashMap::new());
+        Status::with_error_details(code, detail, details)
+    }
+
+    /// 既存の Status（コード + メッセージ）から分類する
+    fn classify(status: &Status) -> Self {
+        let message = status.message();
+        match status.code() {
+            Code::InvalidArgument if message.contains("page_token") => ErrorCode::InvalidPageToken,
+            Code::InvalidArgument if message.contains("order_by") => ErrorCode::InvalidOrderBy,
+            Code::InvalidArgument if message.starts_with("Too many entries") => ErrorCode::TooManyEntries,
+            Code::InvalidArgument | Code::OutOfRange => ErrorCode::InvalidArgument,
+            Code::NotFound => ErrorCode::NotFound,
+            Code::AlreadyExists => ErrorCode::AlreadyExists,
+            Code::Unauthenticated => ErrorCode::Unauthenticated,
+            Code::PermissionDenied => ErrorCode::PermissionDenied,
+            Code::FailedPrecondition => ErrorCode::FailedPrecondition,
+            Code::ResourceExhausted => ErrorCode::ResourceExhausted,
+            Code::Aborted => ErrorCode::Aborted,
+            Code::Unavailable => ErrorCode::Unavailable,
+            Code::Unimplemented => ErrorCode::Unimplemented,
+            Code::DeadlineExceeded => ErrorCode::DeadlineExceeded,
+            Code::Cancelled => ErrorCode::Cancelled,
+            _ if message.starts_with("Database")
+                || message.starts_with("Failed to fetch")
+                || message.starts_with("Failed to acquire")
+                || message.starts_with("Failed to set organization") =>
+            {
+                ErrorCode::DatabaseError
+            }
+            _ if message.starts_with("Storage") || message.contains("GCS") || message.contains("R2") => {
+                ErrorCode::StorageError
+            }
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+/// Status をカタログのメッセージに置き換え、ErrorInfo + LocalizedMessage を付与する
+///
+/// 既に ErrorInfo を持つ Status はその reason を使う。内部エラーの元メッセージはログにのみ出力する。
+pub fn localize(status: Status, locale: Locale) -> Status {
+    if status.code() == Code::Ok {
+        return status;
+    }
+
+    let existing = status.get_error_details();
+    let code = existing
+        .error_info()
+        .and_then(|info| ErrorCode::from_reason(&info.reason))
+        .unwrap_or_else(|| ErrorCode::classify(&status));
+
+    let mut metadata = existing
+        .error_info()
+        .map(|info| info.metadata.clone())
+        .unwrap_or_default();
+    if code.is_internal() {
+        tracing::error!("{} ({}): {}", code.as_str(), status.code(), status.message());
+    } else if !status.message().is_empty() {
+        metadata
+            .entry(DETAIL_KEY.to_string())
+            .or_insert_with(|| status.message().to_string());
+    }
+
+    let message = code.message(locale);
+    let mut details = existing;
+    details.set_error_info(code.as_str(), ERROR_DOMAIN, metadata);
+    details.set_localized_message(locale.tag(), message);
+    Status::with_error_details(status.code(), message, details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_accept_language() {
+        assert_eq!(Locale::from_accept_language(None), Locale::Ja);
+        assert_eq!(Locale::from_accept_language(Some("en-US,en;q=0.9")), Locale::En);
+        assert_eq!(Locale::from_accept_language(Some("fr, en;q=0.5, ja;q=0.8")), Locale::Ja);
+        assert_eq!(Locale::from_accept_language(Some("de")), Locale::Ja);
+    }
+
+    #[test]
+    fn test_localize_hides_internal_details() {
+        let status = localize(
+            Status::internal("Database error: relation \"files\" does not exist"),
+            Locale::En,
+        );
+        assert_eq!(status.code(), Code::Internal);
+        assert_eq!(status.message(), "A database error occurred");
+
+        let details = status.get_error_details();
+        let info = details.error_info().unwrap();
+        assert_eq!(info.reason, "DATABASE_ERROR");
+        assert!(info.metadata.is_empty());
+        assert_eq!(details.localized_message().unwrap().locale, "en-US");
+    }
+
+    #[test]
+    fn test_localize_keeps_explicit_reason() {
+        let status = ErrorCode::TooManyEntries.status(Code::InvalidArgument, "max 100");
+        let status = localize(status, Locale::Ja);
+        let details = status.get_error_details();
+        let info = details.error_info().unwrap();
+        assert_eq!(info.reason, "TOO_MANY_ENTRIES");
+        assert_eq!(info.metadata.get("detail").map(String::as_str), Some("max 100"));
+        assert_eq!(status.message(), "一度に処理できる件数を超えています");
+    }
+}
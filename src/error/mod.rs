@@ -0,0 +1,236 @@
+pub mod catalog;
+pub mod reporting;
+
+use std::error::Error as StdError;
+
+use thiserror::Error;
+use tonic::Status;
+
+use reporting::EventKind;
+
+/// 原因として保持する任意のエラー
+pub type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Database error")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Failed precondition: {0}")]
+    FailedPrecondition(String),
+
+    #[error("Resource exhausted: {0}")]
+    ResourceExhausted(String),
+
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("{message}")]
+    Internal {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("{message}")]
+    Storage {
+        message: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// 処理中の対象（ファイル・組織など）を添えたエラー。gRPC のコードは元のエラーで決まる
+    #[error("{context}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<AppError>,
+    },
+}
+
+impl AppError {
+    pub fn storage(message: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        AppError::Storage {
+            message: message.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    pub fn internal_with(message: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        AppError::Internal {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// 説明を外側に重ねる（"creating file <uuid> for org <id>: GCS upload failed: <cause>"）
+    pub fn context(self, context: impl Into<String>) -> Self {
+        AppError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// context を剥がした元のエラー
+    pub fn root(&self) -> &AppError {
+        match self {
+            AppError::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// 原因を ": " でつないだログ用の文字列
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = self.source();
+        while let Some(err) = source {
+            report.push_str(": ");
+            report.push_str(&err.to_string());
+            source = err.source();
+        }
+        report
+    }
+}
+
+/// `Result` に context を付けて `AppError` にする
+pub trait ResultExt<T> {
+    fn context(self, context: impl Into<String>) -> AppResult<T>;
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> AppResult<T>;
+}
+
+impl<T, E: Into<AppError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> AppResult<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> AppResult<T> {
+        self.map_err(|e| e.into().context(f()))
+    }
+}
+
+/// sqlx のエラーを gRPC のコードに対応付ける。SQL 文やテーブル名はクライアントに返さずログ（`report`）にだけ残す
+fn database_status(err: &sqlx::Error, report: &str) -> Status {
+    match err {
+        sqlx::Error::RowNotFound => Status::not_found("Record not found"),
+        sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) | sqlx::Error::Tls(_) => {
+            tracing::warn!("{}", report);
+            Status::unavailable("Database unavailable")
+        }
+        sqlx::Error::Database(db) => {
+            let code = db.code().unwrap_or_default();
+            match code.as_ref() {
+                // unique_violation
+                "23505" => Status::already_exists("Record already exists"),
+                // foreign_key_violation / restrict_violation
+                "23503" | "23001" => Status::failed_precondition("Referenced record is missing or still in use"),
+                // not_null / check violation, data exception（22xxx）
+                "23502" | "23514" => Status::invalid_argument("Invalid value"),
+                c if c.starts_with("22") => Status::invalid_argument("Invalid value"),
+                // serialization_failure / deadlock_detected
+                "40001" | "40P01" => Status::aborted("Concurrent update, please retry"),
+                // insufficient_resources / too_many_connections
+                c if c.starts_with("53") => {
+                    tracing::warn!("{}", report);
+                    Status::resource_exhausted("Database resource exhausted")
+                }
+                // query_canceled（statement_timeout）
+                "57014" => Status::deadline_exceeded("Database query timed out"),
+                // insufficient_privilege（RLS の WITH CHECK 違反を含む）
+                "42501" => Status::permission_denied("Permission denied"),
+                _ => {
+                    tracing::error!("{}", report);
+                    reporting::capture(EventKind::InternalError, report);
+                    Status::internal("Database error")
+                }
+            }
+        }
+        _ => {
+            tracing::error!("{}", report);
+            reporting::capture(EventKind::InternalError, report);
+            Status::internal("Database error")
+        }
+    }
+}
+
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        match err.root() {
+            AppError::Database(e) => database_status(e, &err.report()),
+            AppError::NotFound(msg) => Status::not_found(msg.clone()),
+            AppError::InvalidInput(msg) => Status::invalid_argument(msg.clone()),
+            AppError::FailedPrecondition(msg) => Status::failed_precondition(msg.clone()),
+            AppError::ResourceExhausted(msg) => Status::resource_exhausted(msg.clone()),
+            AppError::Unavailable(msg) => Status::unavailable(msg.clone()),
+            AppError::Internal { .. } => {
+                let report = err.report();
+                tracing::error!("{}", report);
+                reporting::capture(EventKind::InternalError, &report);
+                Status::internal("Internal error")
+            }
+            AppError::Storage { .. } => {
+                let report = err.report();
+                tracing::error!("{}", report);
+                reporting::capture(EventKind::InternalError, &report);
+                Status::internal("Storage error")
+            }
+            AppError::Context { .. } => unreachable!("root() never returns Context"),
+        }
+    }
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn test_status_mapping() {
+        assert_eq!(Status::from(AppError::Database(sqlx::Error::RowNotFound)).code(), Code::NotFound);
+        assert_eq!(Status::from(AppError::Database(sqlx::Error::PoolTimedOut)).code(), Code::Unavailable);
+        assert_eq!(Status::from(AppError::ResourceExhausted("quota".into())).code(), Code::ResourceExhausted);
+        assert_eq!(Status::from(AppError::FailedPrecondition("x".into())).code(), Code::FailedPrecondition);
+
+        // 内部の詳細はクライアントに返さない
+        let status = Status::from(AppError::Database(sqlx::Error::Protocol(
+            "relation \"files\" does not exist".into(),
+        )));
+        assert_eq!(status.code(), Code::Internal);
+        assert_eq!(status.message(), "Database error");
+    }
+
+    #[test]
+    fn test_context_chain() {
+        let io = std::io::Error::new(std::io::ErrorKind::TimedOut, "connection reset");
+        let result: AppResult<()> = Err(AppError::storage("GCS upload failed", io));
+        let err = result.context("creating file 0190-aa for org 0190-bb").unwrap_err();
+        assert_eq!(
+            err.report(),
+            "creating file 0190-aa for org 0190-bb: GCS upload failed: connection reset"
+        );
+        assert!(matches!(err.root(), AppError::Storage { .. }));
+
+        // コードは元のエラーで決まり、context はクライアントに返さない
+        let status = Status::from(AppError::NotFound("file".into()).context("loading file"));
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "file");
+
+        let status = Status::from(Err::<(), _>(sqlx::Error::RowNotFound).context("loading file").unwrap_err());
+        assert_eq!(status.code(), Code::NotFound);
+    }
+}
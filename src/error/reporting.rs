@@ -0,0 +1,323 @@
+// エラー報告（Sentry 互換、SENTRY_DSN）
+//
+// 想定外の INTERNAL エラーと panic を、リクエストの path・request id・組織・trace id、
+// release / environment を付けて envelope API に送る。送信は共有の HttpClient で、
+// リクエストとは別タスクで行う（失敗してもログに出すだけ）。未設定なら何もしない。
+// リクエストの情報は CatchPanicLayer が task-local に置く（spawn したタスクには引き継がれない）。
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::json;
+
+use crate::config::ErrorReportingConfig;
+use crate::http_client::HttpClient;
+use crate::middleware::trace_context::TraceContext;
+
+/// 1 分あたりに送るイベントの上限（障害時に大量に送らない）
+const MAX_EVENTS_PER_MINUTE: u64 = 60;
+
+static REPORTER: OnceLock<ErrorReporter> = OnceLock::new();
+
+tokio::task_local! {
+    static REQUEST: RequestContext;
+}
+
+/// 報告に添えるリクエストの情報
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    pub path: String,
+    pub request_id: String,
+    pub organization_id: Option<String>,
+    /// このリクエストで既に報告したか（panic と INTERNAL 応答を二重に送らない）
+    reported: Arc<AtomicBool>,
+}
+
+impl RequestContext {
+    pub fn new(path: String, request_id: String, organization_id: Option<String>) -> Self {
+        Self {
+            path,
+            request_id,
+            organization_id,
+            reported: Arc::default(),
+        }
+    }
+
+    pub fn current() -> Option<Self> {
+        REQUEST.try_with(|ctx| ctx.clone()).ok()
+    }
+
+    pub fn is_reported(&self) -> bool {
+        self.reported.load(Ordering::Relaxed)
+    }
+
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        REQUEST.scope(self, future).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Panic,
+    InternalError,
+}
+
+impl EventKind {
+    fn level(self) -> &'static str {
+        match self {
+            EventKind::Panic => "fatal",
+            EventKind::InternalError => "error",
+        }
+    }
+
+    fn exception_type(self) -> &'static str {
+        match self {
+            EventKind::Panic => "Panic",
+            EventKind::InternalError => "InternalError",
+        }
+    }
+}
+
+/// `https://<public_key>@<host>[/<path>]/<project_id>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dsn {
+    pub public_key: String,
+    pub envelope_url: String,
+}
+
+impl Dsn {
+    pub fn parse(dsn: &str) -> Option<Self> {
+        let url = reqwest::Url::parse(dsn).ok()?;
+        let public_key = url.username();
+        if public_key.is_empty() {
+            return None;
+        }
+        let path = url.path().trim_end_matches('/');
+        let (prefix, project_id) = path.rsplit_once('/')?;
+        if project_id.is_empty() {
+            return None;
+        }
+        let port = url.port().map(|p| format!(":{}", p)).unwrap_or_default();
+        Some(Self {
+            public_key: public_key.to_string(),
+            envelope_url: format!(
+                "{}://{}{}{}/api/{}/envelope/",
+                url.scheme(),
+                url.host_str()?,
+                port,
+                prefix,
+                project_id
+            ),
+        })
+    }
+}
+
+pub struct ErrorReporter {
+    http_client: Arc<HttpClient>,
+    dsn: Dsn,
+    environment: String,
+    release: String,
+    /// (分, その分に送った数)
+    window: AtomicU64,
+}
+
+impl ErrorReporter {
+    /// 全体で 1 つの reporter を登録し、panic hook を入れる（DSN が不正なら警告して無効のまま）
+    pub fn install(http_client: Arc<HttpClient>, config: &ErrorReportingConfig) {
+        let Some(dsn) = Dsn::parse(&config.dsn) else {
+            tracing::warn!("SENTRY_DSN is invalid, error reporting disabled");
+            return;
+        };
+        let reporter = ErrorReporter {
+            http_client,
+            dsn,
+            environment: config.environment.clone(),
+            release: config.release.clone(),
+            window: AtomicU64::new(0),
+        };
+        if REPORTER.set(reporter).is_err() {
+            return;
+        }
+
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("<non-string panic>");
+            let message = match info.location() {
+                Some(location) => format!("{} at {}:{}", message, location.file(), location.line()),
+                None => message.to_string(),
+            };
+            capture(EventKind::Panic, &message);
+        }));
+        tracing::info!("Error reporting enabled (environment={})", config.environment);
+    }
+
+    /// 1 分あたりの上限内なら true
+    fn allow(&self) -> bool {
+        let minute = (Utc::now().timestamp() / 60) as u64;
+        let mut current = self.window.load(Ordering::Relaxed);
+        loop {
+            let (window_minute, count) = (current >> 16, current & 0xffff);
+            let next = if window_minute == minute {
+                if count >= MAX_EVENTS_PER_MINUTE {
+                    return false;
+                }
+                current + 1
+            } else {
+                (minute << 16) | 1
+            };
+            match self.window.compare_exchange_weak(current, next, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    fn event(&self, kind: EventKind, message: &str, request: Option<&RequestContext>) -> serde_json::Value {
+        let mut tags = serde_json::Map::new();
+        tags.insert("kind".into(), kind.exception_type().into());
+        if let Some(trace) = TraceContext::current() {
+            tags.insert("trace_id".into(), trace.trace_id_hex().into());
+        }
+        if let Some(request) = request {
+            tags.insert("path".into(), request.path.clone().into());
+            tags.insert("request_id".into(), request.request_id.clone().into());
+            if let Some(organization_id) = &request.organization_id {
+                tags.insert("organization_id".into(), organization_id.clone().into());
+            }
+        }
+        json!({
+            "event_id": uuid::Uuid::new_v4().simple().to_string(),
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "platform": "native",
+            "level": kind.level(),
+            "logger": "rust-logi",
+            "release": self.release,
+            "environment": self.environment,
+            "transaction": request.map(|r| r.path.as_str()),
+            "message": { "formatted": message },
+            "exception": {
+                "values": [{ "type": kind.exception_type(), "value": message }]
+            },
+            "tags": tags,
+        })
+    }
+
+    fn envelope(event: &serde_json::Value) -> String {
+        let header = json!({
+            "event_id": event["event_id"],
+            "sent_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        });
+        let payload = event.to_string();
+        let item = json!({ "type": "event", "length": payload.len() });
+        format!("{}\n{}\n{}\n", header, item, payload)
+    }
+
+    fn send(&'static self, event: serde_json::Value) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let body = Self::envelope(&event);
+        runtime.spawn(async move {
+            let request = self
+                .http_client
+                .client()
+                .post(&self.dsn.envelope_url)
+                .header("content-type", "application/x-sentry-envelope")
+                .header(
+                    "x-sentry-auth",
+                    format!(
+                        "Sentry sentry_version=7, sentry_key={}, sentry_client=rust-logi/{}",
+                        self.dsn.public_key,
+                        env!("CARGO_PKG_VERSION")
+                    ),
+                )
+                .body(body);
+            match self.http_client.send(request).await {
+                Ok(response) if !response.status().is_success() => {
+                    tracing::warn!("Error report rejected: {}", response.status());
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to send error report: {}", e.without_url()),
+            }
+        });
+    }
+}
+
+/// 報告する（未設定・上限超過なら何もしない）。処理中のリクエストの情報を添える
+pub fn capture(kind: EventKind, message: &str) {
+    let Some(reporter) = REPORTER.get() else {
+        return;
+    };
+    let request = RequestContext::current();
+    if let Some(request) = &request {
+        // panic の後の INTERNAL 応答などを重ねて送らない
+        if request.reported.swap(true, Ordering::Relaxed) {
+            return;
+        }
+    }
+    if !reporter.allow() {
+        return;
+    }
+    let event = reporter.event(kind, message, request.as_ref());
+    reporter.send(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dsn() {
+        let dsn = Dsn::parse("https://abc123@o42.ingest.sentry.io/4505").unwrap();
+        assert_eq!(dsn.public_key, "abc123");
+        assert_eq!(dsn.envelope_url, "https://o42.ingest.sentry.io/api/4505/envelope/");
+
+        // セルフホスト（パスの前置きとポート）
+        let dsn = Dsn::parse("http://key@errors.example.com:9000/sentry/7").unwrap();
+        assert_eq!(dsn.envelope_url, "http://errors.example.com:9000/sentry/api/7/envelope/");
+
+        assert!(Dsn::parse("https://o42.ingest.sentry.io/4505").is_none());
+        assert!(Dsn::parse("not a dsn").is_none());
+    }
+
+    #[test]
+    fn test_event_includes_request_context() {
+        let reporter = ErrorReporter {
+            http_client: Arc::new(HttpClient::new()),
+            dsn: Dsn::parse("https://abc@sentry.example.com/1").unwrap(),
+            environment: "staging".to_string(),
+            release: "rust-logi@1.2.3".to_string(),
+            window: AtomicU64::new(0),
+        };
+        let request = RequestContext::new(
+            "/logi.files.FilesService/CreateFile".to_string(),
+            "req-1".to_string(),
+            Some("0190-org".to_string()),
+        );
+        let event = reporter.event(EventKind::InternalError, "GCS upload failed", Some(&request));
+        assert_eq!(event["level"], "error");
+        assert_eq!(event["release"], "rust-logi@1.2.3");
+        assert_eq!(event["environment"], "staging");
+        assert_eq!(event["tags"]["organization_id"], "0190-org");
+        assert_eq!(event["tags"]["request_id"], "req-1");
+        assert_eq!(event["transaction"], "/logi.files.FilesService/CreateFile");
+
+        let envelope = ErrorReporter::envelope(&event);
+        let lines: Vec<&str> = envelope.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("\"type\":\"event\""));
+
+        for _ in 0..MAX_EVENTS_PER_MINUTE {
+            assert!(reporter.allow());
+        }
+        assert!(!reporter.allow());
+    }
+}
@@ -0,0 +1,156 @@
+// In-process entity change event bus
+//
+// 各サービスが作成・更新・削除時に publish し、Watch* RPC が subscribe して
+// フロントエンドへ配信する。同一インスタンス内で発生した変更のみを扱う。
+
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+
+use crate::proto::car_inspection::CarInspection;
+use crate::proto::common::ChangeType;
+use crate::proto::dtakologs::Dtakolog;
+use crate::proto::files::File;
+use crate::proto::items::Item;
+use crate::proto::notifications::InAppNotification;
+
+/// 購読者ごとのバッファ（これを超えて遅れた購読者はイベントを取りこぼす）
+const EVENT_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub enum EntityChange {
+    /// 削除時は uuid のみ設定された File
+    File(File),
+    /// 削除時はキー列のみ設定された CarInspection
+    CarInspection(CarInspection),
+    /// 書き込まれた運行ログ（1 回の書き込みで車両ごとに最新の 1 行）
+    Dtakolog(Dtakolog),
+    /// item が None の場合はクライアント側で再取得する
+    Item { id: String, item: Option<Item> },
+    /// アプリ内通知（user_id 宛て、作成のみ）
+    Notification(InAppNotification),
+}
+
+#[derive(Debug, Clone)]
+pub struct EntityEvent {
+    pub organization_id: String,
+    /// 個人所有データ（personal items）の場合の所有ユーザー。None なら組織全体に配信
+    pub user_id: Option<String>,
+    pub change_type: ChangeType,
+    pub change: EntityChange,
+}
+
+impl EntityEvent {
+    /// 購読者（組織・ユーザー）に配信してよいか
+    pub fn visible_to(&self, organization_id: &str, user_id: Option<&str>) -> bool {
+        match &self.user_id {
+            Some(owner) => user_id == Some(owner.as_str()),
+            None => self.organization_id == organization_id,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EntityEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+
+    /// イベントを配信（購読者がいなければ何もしない）
+    pub fn publish(&self, event: EntityEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EntityEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Watch* RPC 用のストリームを作る
+///
+/// 購読者に見えるイベントだけを `map` で proto メッセージに変換して送る。
+/// 購読が遅れてイベントを取りこぼした場合は ABORTED で終了し、クライアントに再取得を促す。
+pub fn watch_stream<T, F>(
+    bus: &EventBus,
+    organization_id: String,
+    user_id: Option<String>,
+    map: F,
+) -> ReceiverStream<Result<T, Status>>
+where
+    T: Send + 'static,
+    F: Fn(&EntityEvent) -> Option<T> + Send + 'static,
+{
+    let mut events = bus.subscribe();
+    let (tx, rx) = mpsc::channel(64);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tx.closed() => break,
+                received = events.recv() => match received {
+                    Ok(event) => {
+                        if !event.visible_to(&organization_id, user_id.as_deref()) {
+                            continue;
+                        }
+                        if let Some(message) = map(&event) {
+                            if tx.send(Ok(message)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Watch subscriber lagged, skipped {} events", skipped);
+                        let _ = tx
+                            .send(Err(Status::aborted("Watch stream lagged; refetch and watch again")))
+                            .await;
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(organization_id: &str, user_id: Option<&str>) -> EntityEvent {
+        EntityEvent {
+            organization_id: organization_id.to_string(),
+            user_id: user_id.map(|s| s.to_string()),
+            change_type: ChangeType::Created,
+            change: EntityChange::Item { id: "1".to_string(), item: None },
+        }
+    }
+
+    #[test]
+    fn test_visible_to() {
+        assert!(event("org-a", None).visible_to("org-a", Some("u1")));
+        assert!(!event("org-a", None).visible_to("org-b", Some("u1")));
+        assert!(event("org-a", Some("u1")).visible_to("org-b", Some("u1")));
+        assert!(!event("org-a", Some("u1")).visible_to("org-a", Some("u2")));
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let bus = EventBus::new();
+        let mut rx = bus.subscribe();
+        bus.publish(event("org-a", None));
+        assert_eq!(rx.recv().await.unwrap().organization_id, "org-a");
+    }
+}
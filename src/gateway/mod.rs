@@ -0,0 +1,499 @@
+// REST/JSON gateway (grpc-gateway style)
+//
+// proto の `google.api.http` アノテーションからルートを生成し、JSON リクエストを
+// gRPC メッセージに変換して同一プロセス内の tonic サービスを呼び出す。
+// 認証・RLS は gRPC と同じ経路（AuthLayer → 各サービス）を通る。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Query, RawPathParams, Request};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, on, MethodFilter, MethodRouter};
+use axum::{Json, Router};
+use bytes::{BufMut, Bytes, BytesMut};
+use http_body_util::BodyExt;
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, Kind, MessageDescriptor,
+    MethodDescriptor, SerializeOptions,
+};
+use serde_json::{Map, Value};
+use tonic::service::Routes;
+use tonic::{Code, Status};
+use tonic_types::StatusExt;
+use tower::ServiceExt;
+
+use crate::error::catalog::{localize, Locale};
+use crate::middleware::authorization::authorize;
+use crate::proto::FILE_DESCRIPTOR_SET;
+
+pub mod openapi;
+pub mod schema;
+
+/// `google.api.http` 拡張のフルネーム
+const HTTP_RULE_EXTENSION: &str = "google.api.http";
+
+/// REST リクエストボディの上限
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// アノテーションから解決した 1 ルート
+#[derive(Debug, Clone)]
+pub struct HttpRoute {
+    pub method: Method,
+    /// proto のパステンプレート（例: `/v1/files/{uuid}`）
+    pub path: String,
+    /// `body: "*"` / `body: "field"` / 未指定
+    pub body: Option<String>,
+    pub descriptor: MethodDescriptor,
+}
+
+impl HttpRoute {
+    /// gRPC のリクエストパス（`/logi.files.FilesService/ListFiles`）
+    pub fn grpc_path(&self) -> String {
+        format!(
+            "/{}/{}",
+            self.descriptor.parent_service().full_name(),
+            self.descriptor.name()
+        )
+    }
+
+    /// パステンプレート中の `{field}` 名（出現順）
+    pub fn path_fields(&self) -> Vec<String> {
+        template_fields(&self.path)
+    }
+}
+
+/// 埋め込み descriptor から HTTP アノテーション付きの unary RPC を列挙
+pub fn http_routes() -> anyhow::Result<Vec<HttpRoute>> {
+    let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET)?;
+    let Some(extension) = pool.get_extension_by_name(HTTP_RULE_EXTENSION) else {
+        return Ok(Vec::new());
+    };
+
+    let mut routes = Vec::new();
+    for service in pool.services() {
+        for method in service.methods() {
+            let options = method.options();
+            if !options.has_extension(&extension) {
+                continue;
+            }
+            if method.is_client_streaming() || method.is_server_streaming() {
+                tracing::warn!(
+                    "Skipping HTTP rule on streaming method {}",
+                    method.full_name()
+                );
+                continue;
+            }
+
+            let value = options.get_extension(&extension);
+            let Some(rule) = value.as_message() else {
+                continue;
+            };
+            let mut rules = vec![rule.clone()];
+            if let Some(additional) = rule
+                .get_field_by_name("additional_bindings")
+                .and_then(|v| v.as_list().map(|l| l.to_vec()))
+            {
+                rules.extend(additional.iter().filter_map(|v| v.as_message().cloned()));
+            }
+
+            for rule in rules {
+                match parse_rule(&rule) {
+                    Some((http_method, path, body)) => routes.push(HttpRoute {
+                        method: http_method,
+                        path,
+                        body,
+                        descriptor: method.clone(),
+                    }),
+                    None => tracing::warn!(
+                        "Unsupported HTTP rule on {}",
+                        method.full_name()
+                    ),
+                }
+            }
+        }
+    }
+    Ok(routes)
+}
+
+fn parse_rule(rule: &DynamicMessage) -> Option<(Method, String, Option<String>)> {
+    let string_field = |name: &str| {
+        rule.has_field_by_name(name)
+            .then(|| rule.get_field_by_name(name))
+            .flatten()
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+    };
+
+    let (method, path) = [
+        ("get", Method::GET),
+        ("post", Method::POST),
+        ("put", Method::PUT),
+        ("patch", Method::PATCH),
+        ("delete", Method::DELETE),
+    ]
+    .into_iter()
+    .find_map(|(name, method)| string_field(name).map(|path| (method, path)))?;
+
+    let body = string_field("body").filter(|b| !b.is_empty());
+    Some((method, path, body))
+}
+
+/// `{field}` / `{field=*}` のフィールド名を取り出す
+pub(crate) fn template_fields(template: &str) -> Vec<String> {
+    template
+        .split('/')
+        .filter_map(|seg| seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')))
+        .map(|s| s.split('=').next().unwrap_or(s).to_string())
+        .collect()
+}
+
+/// proto のパステンプレートを axum のルート表記に変換（`{a.b}` → `:a__b`）
+fn axum_path(template: &str) -> String {
+    template
+        .split('/')
+        .map(|seg| match seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(field) => format!(":{}", param_name(field.split('=').next().unwrap_or(field))),
+            None => seg.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn param_name(field: &str) -> String {
+    field.replace('.', "__")
+}
+
+fn method_filter(method: &Method) -> MethodFilter {
+    match *method {
+        Method::GET => MethodFilter::GET,
+        Method::PUT => MethodFilter::PUT,
+        Method::PATCH => MethodFilter::PATCH,
+        Method::DELETE => MethodFilter::DELETE,
+        _ => MethodFilter::POST,
+    }
+}
+
+/// REST ルーターを構築（`grpc` は同一プロセスの tonic ルート）
+pub fn router(grpc: Routes) -> anyhow::Result<Router> {
+    let routes = http_routes()?;
+    let spec = Arc::new(openapi::document(&routes));
+    let mut by_path: HashMap<String, MethodRouter> = HashMap::new();
+
+    for route in routes {
+        tracing::debug!("REST {} {} -> {}", route.method, route.path, route.grpc_path());
+        let path = axum_path(&route.path);
+        let filter = method_filter(&route.method);
+        let route = Arc::new(route);
+        let grpc = grpc.clone();
+
+        let handler = move |params: RawPathParams,
+                            Query(query): Query<Vec<(String, String)>>,
+                            req: Request| {
+            let route = route.clone();
+            let grpc = grpc.clone();
+            let locale = Locale::from_accept_language(
+                req.headers()
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|v| v.to_str().ok()),
+            );
+            async move {
+                transcode(&route, grpc, params, query, req)
+                    .await
+                    .unwrap_or_else(|status| error_response(localize(status, locale)))
+            }
+        };
+
+        let method_router = match by_path.remove(&path) {
+            Some(existing) => existing.on(filter, handler),
+            None => on(filter, handler),
+        };
+        by_path.insert(path, method_router);
+    }
+
+    let router = by_path
+        .into_iter()
+        .fold(Router::new(), |router, (path, method_router)| {
+            router.route(&path, method_router)
+        });
+
+    // OpenAPI ドキュメント・descriptor（クライアント生成用、認証不要）
+    Ok(router
+        .route(
+            "/openapi.json",
+            get(move || {
+                let spec = spec.clone();
+                async move { Json(spec.as_ref().clone()) }
+            }),
+        )
+        .merge(schema::routes()))
+}
+
+async fn transcode(
+    route: &HttpRoute,
+    grpc: Routes,
+    params: RawPathParams,
+    query: Vec<(String, String)>,
+    req: Request,
+) -> Result<Response, Status> {
+    let input = route.descriptor.input();
+    let (parts, body) = req.into_parts();
+
+    // 1. JSON 組み立て: body → path → query の順で上書き
+    let mut json = Map::new();
+    let body = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| Status::invalid_argument(format!("Failed to read body: {}", e)))?;
+    match route.body.as_deref() {
+        Some(_) if body.is_empty() => {}
+        Some("*") => match serde_json::from_slice(&body) {
+            Ok(Value::Object(obj)) => json = obj,
+            Ok(_) => return Err(Status::invalid_argument("Request body must be a JSON object")),
+            Err(e) => return Err(Status::invalid_argument(format!("Invalid JSON body: {}", e))),
+        },
+        Some(field) => {
+            let value: Value = serde_json::from_slice(&body)
+                .map_err(|e| Status::invalid_argument(format!("Invalid JSON body: {}", e)))?;
+            json.insert(field.to_string(), value);
+        }
+        None => {}
+    }
+
+    let path_fields = route.path_fields();
+    for (name, value) in params.iter() {
+        let Some(field) = path_fields.iter().find(|f| param_name(f) == name) else {
+            continue;
+        };
+        set_field(&mut json, &input, field, value)?;
+    }
+
+    if route.body.as_deref() != Some("*") {
+        for (key, value) in &query {
+            if path_fields.contains(key) {
+                continue;
+            }
+            set_field(&mut json, &input, key, value)?;
+        }
+    }
+
+    let message = DynamicMessage::deserialize_with_options(
+        input,
+        Value::Object(json),
+        &DeserializeOptions::new().deny_unknown_fields(true),
+    )
+    .map_err(|e| Status::invalid_argument(format!("Invalid request: {}", e)))?;
+
+    // 2. gRPC フレームで同一プロセス内の tonic サービスを呼び出す
+    let payload = message.encode_to_vec();
+    let mut frame = BytesMut::with_capacity(5 + payload.len());
+    frame.put_u8(0);
+    frame.put_u32(payload.len() as u32);
+    frame.put_slice(&payload);
+
+    let mut grpc_req = http::Request::builder()
+        .method(Method::POST)
+        .uri(route.grpc_path())
+        .version(http::Version::HTTP_2)
+        .body(Body::from(frame.freeze()))
+        .map_err(|e| Status::internal(format!("Failed to build request: {}", e)))?;
+    for (name, value) in parts.headers.iter() {
+        if name == header::CONTENT_TYPE || name == header::CONTENT_LENGTH || name == header::ACCEPT {
+            continue;
+        }
+        grpc_req.headers_mut().append(name.clone(), value.clone());
+    }
+    grpc_req
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    grpc_req.headers_mut().insert("te", HeaderValue::from_static("trailers"));
+    // 外側の AuthorizationLayer は /v1/... を見ないため、ここで gRPC メソッドのロールを確認する
+    authorize(&route.grpc_path(), &parts.extensions)?;
+    // AuthLayer が付与した AuthenticatedUser などをそのまま引き継ぐ
+    *grpc_req.extensions_mut() = parts.extensions;
+
+    let response = grpc
+        .oneshot(grpc_req)
+        .await
+        .map_err(|e| Status::internal(format!("Gateway error: {:?}", e)))?;
+    let (resp_parts, resp_body) = response.into_parts();
+    let collected = resp_body
+        .collect()
+        .await
+        .map_err(|e| Status::internal(format!("Failed to read gRPC response: {}", e)))?;
+
+    let status = collected
+        .trailers()
+        .and_then(Status::from_header_map)
+        .or_else(|| Status::from_header_map(&resp_parts.headers));
+    if let Some(status) = status {
+        if status.code() != Code::Ok {
+            return Err(status);
+        }
+    }
+
+    // 3. レスポンスメッセージを JSON に変換
+    let bytes = collected.to_bytes();
+    let message = decode_frame(&bytes, route.descriptor.output())?;
+    let value = message
+        .serialize_with_options(
+            serde_json::value::Serializer,
+            &SerializeOptions::new().skip_default_fields(false),
+        )
+        .map_err(|e| Status::internal(format!("Failed to encode response: {}", e)))?;
+
+    Ok(Json(value).into_response())
+}
+
+fn decode_frame(bytes: &Bytes, descriptor: MessageDescriptor) -> Result<DynamicMessage, Status> {
+    if bytes.len() < 5 {
+        return Err(Status::internal("Empty gRPC response"));
+    }
+    let len = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize;
+    let payload = bytes
+        .get(5..5 + len)
+        .ok_or_else(|| Status::internal("Truncated gRPC response"))?;
+    DynamicMessage::decode(descriptor, payload)
+        .map_err(|e| Status::internal(format!("Failed to decode response: {}", e)))
+}
+
+/// `a.b.c=value` 形式のパラメータを JSON オブジェクトに設定する
+fn set_field(
+    json: &mut Map<String, Value>,
+    descriptor: &MessageDescriptor,
+    path: &str,
+    raw: &str,
+) -> Result<(), Status> {
+    let (head, rest) = match path.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (path, None),
+    };
+    let field = descriptor
+        .get_field_by_name(head)
+        .or_else(|| descriptor.get_field_by_json_name(head))
+        .ok_or_else(|| Status::invalid_argument(format!("Unknown parameter: {}", path)))?;
+    let key = field.name().to_string();
+
+    match (rest, field.kind()) {
+        (Some(rest), Kind::Message(nested)) if !field.is_list() && !field.is_map() => {
+            let entry = json
+                .entry(key)
+                .or_insert_with(|| Value::Object(Map::new()));
+            let Value::Object(obj) = entry else {
+                return Err(Status::invalid_argument(format!("Conflicting parameter: {}", path)));
+            };
+            set_field(obj, &nested, rest, raw)
+        }
+        (Some(_), _) => Err(Status::invalid_argument(format!("Unknown parameter: {}", path))),
+        (None, kind) => {
+            let value = scalar_value(&kind, raw);
+            if field.is_list() {
+                match json.entry(key).or_insert_with(|| Value::Array(Vec::new())) {
+                    Value::Array(items) => items.push(value),
+                    _ => return Err(Status::invalid_argument(format!("Conflicting parameter: {}", path))),
+                }
+            } else {
+                json.insert(key, value);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// クエリ文字列の値をフィールド型に合わせた JSON 値にする
+fn scalar_value(kind: &Kind, raw: &str) -> Value {
+    match kind {
+        Kind::Bool => match raw {
+            "true" | "1" => Value::Bool(true),
+            "false" | "0" | "" => Value::Bool(false),
+            _ => Value::String(raw.to_string()),
+        },
+        Kind::Int32
+        | Kind::Sint32
+        | Kind::Sfixed32
+        | Kind::Uint32
+        | Kind::Fixed32
+        | Kind::Enum(_) => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(raw.to_string())),
+        Kind::Float | Kind::Double => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(|f| serde_json::Number::from_f64(f).map(Value::Number))
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+        // 64bit 整数・bytes・FieldMask 等は proto JSON でも文字列表現
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+/// gRPC ステータスを HTTP ステータスに変換（grpc-gateway 準拠）
+pub fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::Cancelled => StatusCode::from_u16(499).unwrap_or(StatusCode::BAD_REQUEST),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_response(status: Status) -> Response {
+    let details = status.get_error_details();
+    let body = serde_json::json!({
+        "code": status.code() as i32,
+        "reason": details.error_info().map(|info| info.reason.as_str()).unwrap_or_default(),
+        "message": status.message(),
+    });
+    (http_status(status.code()), Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axum_path_from_template() {
+        assert_eq!(axum_path("/v1/files/{uuid}"), "/v1/files/:uuid");
+        assert_eq!(axum_path("/v1/items/{item.id=*}"), "/v1/items/:item__id");
+        assert_eq!(template_fields("/v1/a/{x}/b/{y.z}"), vec!["x", "y.z"]);
+    }
+
+    #[test]
+    fn test_routes_from_annotations() {
+        let routes = http_routes().unwrap();
+        let get_file = routes
+            .iter()
+            .find(|r| r.path == "/v1/files/{uuid}" && r.method == Method::GET)
+            .expect("GET /v1/files/{uuid}");
+        assert_eq!(get_file.grpc_path(), "/logi.files.FilesService/GetFile");
+        assert!(routes.iter().any(|r| r.path == "/v1/dtakologs"));
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_request_rejected() {
+        // authorize は gRPC を呼ぶ前に確かめるので、サービスは空でよい
+        let app = router(Routes::default()).unwrap();
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri("/v1/files")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = to_bytes(response.into_body(), MAX_BODY_BYTES).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], Code::Unauthenticated as i32);
+    }
+}
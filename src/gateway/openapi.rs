@@ -0,0 +1,278 @@
+// OpenAPI 3 document for the REST gateway
+//
+// ルート・スキーマは gateway と同じ `google.api.http` アノテーションと descriptor から生成する。
+
+use std::collections::BTreeMap;
+
+use prost_reflect::{FieldDescriptor, Kind, MessageDescriptor};
+use serde_json::{json, Map, Value};
+
+use super::schema::schema_version;
+use super::{template_fields, HttpRoute};
+
+/// クエリパラメータとして展開するネストの深さ上限
+const MAX_QUERY_DEPTH: usize = 3;
+
+/// エラーレスポンスのスキーマ名
+const ERROR_SCHEMA: &str = "Error";
+
+/// HTTP ルートから OpenAPI 3.0 ドキュメントを生成
+pub fn document(routes: &[HttpRoute]) -> Value {
+    let mut schemas: BTreeMap<String, Value> = BTreeMap::new();
+    let mut paths: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+
+    for route in routes {
+        let operation = operation(route, &mut schemas);
+        paths
+            .entry(openapi_path(&route.path))
+            .or_default()
+            .insert(route.method.as_str().to_lowercase(), operation);
+    }
+
+    schemas.insert(
+        ERROR_SCHEMA.to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "code": { "type": "integer", "format": "int32", "description": "gRPC status code" },
+                "reason": { "type": "string", "description": "Stable error code (ErrorInfo.reason)" },
+                "message": { "type": "string", "description": "Localized by Accept-Language" }
+            }
+        }),
+    );
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "rust-logi REST API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "x-schema-version": schema_version(),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "authToken": { "type": "apiKey", "in": "header", "name": "x-auth-token" }
+            }
+        },
+        "security": [{ "authToken": [] }],
+    })
+}
+
+/// `{field=*}` → `{field}`
+fn openapi_path(template: &str) -> String {
+    template
+        .split('/')
+        .map(|seg| match seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(field) => format!("{{{}}}", field.split('=').next().unwrap_or(field)),
+            None => seg.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn operation(route: &HttpRoute, schemas: &mut BTreeMap<String, Value>) -> Value {
+    let method = &route.descriptor;
+    let input = method.input();
+    let path_fields = template_fields(&route.path);
+
+    let mut parameters = Vec::new();
+    for name in &path_fields {
+        let schema = field_by_path(&input, name)
+            .map(|f| scalar_schema(&f, schemas))
+            .unwrap_or_else(|| json!({ "type": "string" }));
+        parameters.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": schema,
+        }));
+    }
+
+    if route.body.as_deref() != Some("*") {
+        let body_field = route.body.as_deref();
+        for field in input.fields() {
+            if Some(field.name()) == body_field {
+                continue;
+            }
+            query_parameters(&field, field.name(), &path_fields, 0, schemas, &mut parameters);
+        }
+    }
+
+    let mut op = json!({
+        "operationId": format!("{}_{}", method.parent_service().name(), method.name()),
+        "tags": [method.parent_service().name()],
+        "parameters": parameters,
+        "responses": {
+            "200": {
+                "description": "OK",
+                "content": { "application/json": { "schema": message_ref(&method.output(), schemas) } }
+            },
+            "default": {
+                "description": "Error",
+                "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", ERROR_SCHEMA) } } }
+            }
+        }
+    });
+
+    let body_schema = match route.body.as_deref() {
+        Some("*") => Some(message_ref(&input, schemas)),
+        Some(name) => input.get_field_by_name(name).map(|f| field_schema(&f, schemas)),
+        None => None,
+    };
+    if let Some(schema) = body_schema {
+        op["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": schema } }
+        });
+    }
+    op
+}
+
+/// ネストしたメッセージはドット区切りのクエリパラメータに展開する
+fn query_parameters(
+    field: &FieldDescriptor,
+    name: &str,
+    path_fields: &[String],
+    depth: usize,
+    schemas: &mut BTreeMap<String, Value>,
+    out: &mut Vec<Value>,
+) {
+    if path_fields.iter().any(|p| p == name) || field.is_map() {
+        return;
+    }
+    match field.kind() {
+        Kind::Message(nested) if !is_string_wkt(&nested) && !field.is_list() => {
+            if depth >= MAX_QUERY_DEPTH {
+                return;
+            }
+            for child in nested.fields() {
+                let child_name = format!("{}.{}", name, child.name());
+                query_parameters(&child, &child_name, path_fields, depth + 1, schemas, out);
+            }
+        }
+        Kind::Message(_) if field.is_list() => {}
+        _ => {
+            let schema = if field.is_list() {
+                json!({ "type": "array", "items": scalar_schema(field, schemas) })
+            } else {
+                scalar_schema(field, schemas)
+            };
+            let mut param = json!({ "name": name, "in": "query", "schema": schema });
+            if field.is_list() {
+                param["explode"] = json!(true);
+            }
+            out.push(param);
+        }
+    }
+}
+
+fn field_by_path(descriptor: &MessageDescriptor, path: &str) -> Option<FieldDescriptor> {
+    let (head, rest) = match path.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (path, None),
+    };
+    let field = descriptor.get_field_by_name(head)?;
+    match (rest, field.kind()) {
+        (None, _) => Some(field),
+        (Some(rest), Kind::Message(nested)) => field_by_path(&nested, rest),
+        _ => None,
+    }
+}
+
+/// JSON 文字列で表現される Well-Known Type
+fn is_string_wkt(message: &MessageDescriptor) -> bool {
+    matches!(
+        message.full_name(),
+        "google.protobuf.FieldMask" | "google.protobuf.Timestamp" | "google.protobuf.Duration"
+    )
+}
+
+fn message_ref(message: &MessageDescriptor, schemas: &mut BTreeMap<String, Value>) -> Value {
+    match message.full_name() {
+        "google.protobuf.FieldMask" => return json!({ "type": "string" }),
+        "google.protobuf.Timestamp" => return json!({ "type": "string", "format": "date-time" }),
+        "google.protobuf.Duration" => return json!({ "type": "string" }),
+        _ => {}
+    }
+
+    let name = message.full_name().to_string();
+    if !schemas.contains_key(&name) {
+        // 再帰型に備えて先に登録してからプロパティを埋める
+        schemas.insert(name.clone(), json!({ "type": "object" }));
+        let mut properties = Map::new();
+        for field in message.fields() {
+            properties.insert(field.json_name().to_string(), field_schema(&field, schemas));
+        }
+        schemas.insert(
+            name.clone(),
+            json!({ "type": "object", "properties": properties }),
+        );
+    }
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn field_schema(field: &FieldDescriptor, schemas: &mut BTreeMap<String, Value>) -> Value {
+    if field.is_map() {
+        let value_schema = match field.kind() {
+            Kind::Message(entry) => field_schema(&entry.map_entry_value_field(), schemas),
+            _ => json!({}),
+        };
+        return json!({ "type": "object", "additionalProperties": value_schema });
+    }
+    let item = match field.kind() {
+        Kind::Message(message) => message_ref(&message, schemas),
+        _ => scalar_schema(field, schemas),
+    };
+    if field.is_list() {
+        json!({ "type": "array", "items": item })
+    } else {
+        item
+    }
+}
+
+fn scalar_schema(field: &FieldDescriptor, schemas: &mut BTreeMap<String, Value>) -> Value {
+    match field.kind() {
+        Kind::Bool => json!({ "type": "boolean" }),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => json!({ "type": "integer", "format": "int32" }),
+        Kind::Uint32 | Kind::Fixed32 => json!({ "type": "integer", "format": "int64", "minimum": 0 }),
+        // proto JSON では 64bit 整数は文字列
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => json!({ "type": "string", "format": "int64" }),
+        Kind::Uint64 | Kind::Fixed64 => json!({ "type": "string", "format": "uint64" }),
+        Kind::Float => json!({ "type": "number", "format": "float" }),
+        Kind::Double => json!({ "type": "number", "format": "double" }),
+        Kind::String => json!({ "type": "string" }),
+        Kind::Bytes => json!({ "type": "string", "format": "byte" }),
+        Kind::Enum(e) => json!({
+            "type": "string",
+            "enum": e.values().map(|v| v.name().to_string()).collect::<Vec<_>>(),
+        }),
+        Kind::Message(message) => message_ref(&message, schemas),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::http_routes;
+
+    #[test]
+    fn test_document_covers_rest_routes() {
+        let doc = document(&http_routes().unwrap());
+        assert_eq!(doc["openapi"], "3.0.3");
+
+        let get_file = &doc["paths"]["/v1/files/{uuid}"]["get"];
+        assert_eq!(get_file["operationId"], "FilesService_GetFile");
+        assert_eq!(get_file["parameters"][0]["in"], "path");
+
+        let list_files = &doc["paths"]["/v1/files"]["get"];
+        let names: Vec<&str> = list_files["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|p| p["name"].as_str())
+            .collect();
+        assert!(names.contains(&"pagination.per_page"));
+        assert!(doc["components"]["schemas"]["logi.files.ListFilesResponse"].is_object());
+    }
+}
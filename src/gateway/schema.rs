@@ -0,0 +1,85 @@
+// Compiled API schema download
+//
+// クライアントがビルド時のスキーマを固定・差分比較できるよう、
+// FILE_DESCRIPTOR_SET（protoc --descriptor_set_out と同じ形式）とバージョン文字列を配信する。
+
+use std::sync::OnceLock;
+
+use axum::http::{header, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use sha2::{Digest, Sha256};
+
+use crate::proto::FILE_DESCRIPTOR_SET;
+
+/// レスポンスヘッダー名（descriptor.bin にも付与）
+pub const SCHEMA_VERSION_HEADER: &str = "x-schema-version";
+
+/// descriptor の SHA-256（hex）
+pub fn schema_digest() -> &'static str {
+    static DIGEST: OnceLock<String> = OnceLock::new();
+    DIGEST.get_or_init(|| {
+        Sha256::digest(FILE_DESCRIPTOR_SET)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    })
+}
+
+/// `<パッケージバージョン>+<descriptor SHA-256 先頭12桁>`（proto が変われば必ず変わる）
+pub fn schema_version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| format!("{}+{}", env!("CARGO_PKG_VERSION"), &schema_digest()[..12]))
+}
+
+/// `GET /schema/version` と `GET /schema/descriptor.bin`
+pub fn routes() -> Router {
+    Router::new()
+        .route(
+            "/schema/version",
+            get(|| async {
+                Json(serde_json::json!({
+                    "version": schema_version(),
+                    "package_version": env!("CARGO_PKG_VERSION"),
+                    "sha256": schema_digest(),
+                    "size": FILE_DESCRIPTOR_SET.len(),
+                }))
+            }),
+        )
+        .route("/schema/descriptor.bin", get(|| async { descriptor_response() }))
+}
+
+fn descriptor_response() -> Response {
+    let mut response = FILE_DESCRIPTOR_SET.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_static("attachment; filename=\"logi_descriptor.bin\""),
+    );
+    if let Ok(value) = HeaderValue::from_str(schema_version()) {
+        headers.insert(SCHEMA_VERSION_HEADER, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("\"{}\"", schema_digest())) {
+        headers.insert(header::ETAG, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_version_format() {
+        let version = schema_version();
+        let (package, digest) = version.split_once('+').unwrap();
+        assert_eq!(package, env!("CARGO_PKG_VERSION"));
+        assert_eq!(digest.len(), 12);
+        assert!(schema_digest().starts_with(digest));
+    }
+}
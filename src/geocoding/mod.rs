@@ -0,0 +1,382 @@
+// Reverse geocoding
+//
+// GPS 座標から住所を引く。事業者（Nominatim / Google）は GEOCODING_PROVIDER で切り替え、
+// 結果は geocode_cache（約11m 単位、組織共通）に保存して同じ地点は再問い合わせしない。
+// 運行ログの address_disp_c が空の行は dtakologs.geocode_backfill で埋める。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::config::GeocodingConfig;
+use crate::db::OrgScopedPool;
+use crate::http_client::HttpClient;
+use crate::jobs::{Job, JobHandler, NewJob, ScheduledTaskDef};
+
+pub mod track;
+
+/// address_disp_c が空の運行ログに住所を入れる
+pub const GEOCODE_BACKFILL_JOB: &str = "dtakologs.geocode_backfill";
+
+pub const GEOCODE_BACKFILL_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: GEOCODE_BACKFILL_JOB,
+    description: "住所（address_disp_c）が空の運行ログを GPS 座標から逆ジオコーディングして埋める",
+    default_cron: "*/15 * * * *",
+};
+
+/// 1回の job で問い合わせる地点数の上限
+const BACKFILL_BATCH: i64 = 100;
+const GOOGLE_GEOCODE_URL: &str = "https://maps.googleapis.com/maps/api/geocode/json";
+
+/// 緯度経度（度）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lon: f64) -> Option<Self> {
+        let valid = (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) && (lat, lon) != (0.0, 0.0);
+        valid.then_some(Self { lat, lon })
+    }
+
+    /// dtakologs.gps_latitude / gps_longitude（1/1000 秒単位）から
+    pub fn from_dtako(lat_ms: i32, lon_ms: i32) -> Option<Self> {
+        Self::new(lat_ms as f64 / 3_600_000.0, lon_ms as f64 / 3_600_000.0)
+    }
+
+    /// geocode_cache のキー（1/10000 度単位、SQL 側は round(gps_latitude / 360.0)）
+    pub fn cache_key(&self) -> (i32, i32) {
+        ((self.lat * 10_000.0).round() as i32, (self.lon * 10_000.0).round() as i32)
+    }
+}
+
+/// 逆ジオコーディング事業者
+#[tonic::async_trait]
+pub trait GeocodingProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 連続して問い合わせる場合の間隔（利用規約のレート制限）
+    fn min_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// 住所が見つからなければ None
+    async fn reverse(&self, point: GeoPoint) -> anyhow::Result<Option<String>>;
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NominatimAddress {
+    state: Option<String>,
+    province: Option<String>,
+    city: Option<String>,
+    town: Option<String>,
+    village: Option<String>,
+    city_district: Option<String>,
+    suburb: Option<String>,
+    quarter: Option<String>,
+    neighbourhood: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimResponse {
+    address: Option<NominatimAddress>,
+    error: Option<String>,
+}
+
+/// 都道府県 + 市区町村 + 町名（日本の住所の順）
+fn format_nominatim_address(address: &NominatimAddress) -> Option<String> {
+    let parts = [
+        address.state.as_ref().or(address.province.as_ref()),
+        address.city.as_ref().or(address.town.as_ref()).or(address.village.as_ref()),
+        address.city_district.as_ref(),
+        address.suburb.as_ref(),
+        address.quarter.as_ref().or(address.neighbourhood.as_ref()),
+    ];
+    let text: String = parts.iter().flatten().map(|s| s.as_str()).collect();
+    (!text.is_empty()).then_some(text)
+}
+
+/// OpenStreetMap Nominatim（公開サーバーは 1 秒 1 件まで）
+pub struct Nominatim {
+    http_client: Arc<HttpClient>,
+    base_url: String,
+    user_agent: String,
+}
+
+impl Nominatim {
+    pub fn new(http_client: Arc<HttpClient>, base_url: String, user_agent: String) -> Self {
+        Self {
+            http_client,
+            base_url,
+            user_agent,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl GeocodingProvider for Nominatim {
+    fn name(&self) -> &'static str {
+        "nominatim"
+    }
+
+    fn min_interval(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    async fn reverse(&self, point: GeoPoint) -> anyhow::Result<Option<String>> {
+        let url = format!(
+            "{}/reverse?format=jsonv2&accept-language=ja&zoom=17&lat={}&lon={}",
+            self.base_url.trim_end_matches('/'),
+            point.lat,
+            point.lon
+        );
+        let response: NominatimResponse = self
+            .http_client
+            .get_json_with_headers(&url, &[("User-Agent", self.user_agent.as_str())])
+            .await?;
+        if response.error.is_some() {
+            // "Unable to geocode"（海上など）
+            return Ok(None);
+        }
+        Ok(response.address.as_ref().and_then(format_nominatim_address))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleGeocodeResult {
+    formatted_address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleGeocodeResponse {
+    status: String,
+    #[serde(default)]
+    results: Vec<GoogleGeocodeResult>,
+    error_message: Option<String>,
+}
+
+/// "日本、〒100-0005 東京都千代田区丸の内１丁目" → "東京都千代田区丸の内１丁目"
+fn strip_google_address(formatted: &str) -> String {
+    let text = formatted.strip_prefix("日本、").unwrap_or(formatted);
+    let text = match text.strip_prefix('〒') {
+        Some(rest) => rest.split_once(' ').map(|(_, address)| address).unwrap_or(rest),
+        None => text,
+    };
+    text.trim().to_string()
+}
+
+/// Google Geocoding API
+pub struct GoogleGeocoder {
+    http_client: Arc<HttpClient>,
+    api_key: String,
+}
+
+impl GoogleGeocoder {
+    pub fn new(http_client: Arc<HttpClient>, api_key: String) -> Self {
+        Self { http_client, api_key }
+    }
+}
+
+#[tonic::async_trait]
+impl GeocodingProvider for GoogleGeocoder {
+    fn name(&self) -> &'static str {
+        "google"
+    }
+
+    async fn reverse(&self, point: GeoPoint) -> anyhow::Result<Option<String>> {
+        let url = format!(
+            "{}?latlng={},{}&language=ja&result_type=street_address|premise|sublocality&key={}",
+            GOOGLE_GEOCODE_URL, point.lat, point.lon, self.api_key
+        );
+        let response: GoogleGeocodeResponse = self.http_client.get_json_with_headers(&url, &[]).await?;
+        match response.status.as_str() {
+            "OK" => Ok(response
+                .results
+                .first()
+                .map(|r| strip_google_address(&r.formatted_address))),
+            "ZERO_RESULTS" => Ok(None),
+            status => anyhow::bail!(
+                "Google Geocoding returned {}: {}",
+                status,
+                response.error_message.unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// 設定から事業者を作る
+pub fn geocoding_provider(config: &GeocodingConfig, http_client: Arc<HttpClient>) -> Arc<dyn GeocodingProvider> {
+    match config {
+        GeocodingConfig::Nominatim { url, user_agent } => {
+            Arc::new(Nominatim::new(http_client, url.clone(), user_agent.clone()))
+        }
+        GeocodingConfig::Google { api_key } => Arc::new(GoogleGeocoder::new(http_client, api_key.clone())),
+    }
+}
+
+/// 逆ジオコーディングの結果
+#[derive(Debug, Clone)]
+pub struct ResolvedAddress {
+    pub address: Option<String>,
+    pub cached: bool,
+}
+
+/// キャッシュ付き逆ジオコーディング
+pub struct Geocoder {
+    pool: PgPool,
+    provider: Arc<dyn GeocodingProvider>,
+}
+
+impl Geocoder {
+    pub fn new(pool: PgPool, provider: Arc<dyn GeocodingProvider>) -> Self {
+        Self { pool, provider }
+    }
+
+    /// 住所を引く（見つからなかった地点もキャッシュする）
+    pub async fn reverse(&self, point: GeoPoint) -> anyhow::Result<ResolvedAddress> {
+        let (lat_key, lon_key) = point.cache_key();
+        let cached: Option<(Option<String>,)> =
+            sqlx::query_as("SELECT address FROM geocode_cache WHERE lat_e4 = $1 AND lon_e4 = $2")
+                .bind(lat_key)
+                .bind(lon_key)
+                .fetch_optional(&self.pool)
+                .await?;
+        if let Some((address,)) = cached {
+            return Ok(ResolvedAddress { address, cached: true });
+        }
+
+        let address = self.provider.reverse(point).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO geocode_cache (lat_e4, lon_e4, address, provider)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (lat_e4, lon_e4) DO UPDATE
+                SET address = EXCLUDED.address, provider = EXCLUDED.provider, resolved_at = NOW()
+            "#,
+        )
+        .bind(lat_key)
+        .bind(lon_key)
+        .bind(&address)
+        .bind(self.provider.name())
+        .execute(&self.pool)
+        .await?;
+        Ok(ResolvedAddress { address, cached: false })
+    }
+
+    /// 組織の運行ログのうち住所が空の地点を埋める（更新した行数）
+    pub async fn backfill(&self, organization_id: &str) -> anyhow::Result<u64> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+
+        // 住所が見つからないとわかっている地点は除く
+        let points: Vec<(i32, i32)> = sqlx::query_as(
+            r#"
+            SELECT d.gps_latitude, d.gps_longitude
+            FROM dtakologs d
+            WHERE (d.address_disp_c IS NULL OR d.address_disp_c = '')
+              AND d.gps_latitude <> 0 AND d.gps_longitude <> 0
+              AND NOT EXISTS (
+                  SELECT 1 FROM geocode_cache c
+                  WHERE c.lat_e4 = round(d.gps_latitude / 360.0)::int
+                    AND c.lon_e4 = round(d.gps_longitude / 360.0)::int
+                    AND c.address IS NULL
+              )
+            GROUP BY d.gps_latitude, d.gps_longitude
+            ORDER BY MAX(d.data_date_time) DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(BACKFILL_BATCH)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut updated = 0;
+        for (lat_ms, lon_ms) in points {
+            let Some(point) = GeoPoint::from_dtako(lat_ms, lon_ms) else {
+                continue;
+            };
+            let resolved = self.reverse(point).await?;
+            if let Some(address) = resolved.address {
+                updated += sqlx::query(
+                    r#"
+                    UPDATE dtakologs SET address_disp_c = $1
+                    WHERE (address_disp_c IS NULL OR address_disp_c = '')
+                      AND gps_latitude = $2 AND gps_longitude = $3
+                    "#,
+                )
+                .bind(&address)
+                .bind(lat_ms)
+                .bind(lon_ms)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected();
+            }
+            if !resolved.cached {
+                tokio::time::sleep(self.provider.min_interval()).await;
+            }
+        }
+        Ok(updated)
+    }
+}
+
+/// 住所の埋め戻し job（BulkCreate で住所のない行があったとき、または定期実行）
+pub fn backfill_job() -> NewJob {
+    NewJob::new(GEOCODE_BACKFILL_JOB, serde_json::json!({})).dedupe_key(GEOCODE_BACKFILL_JOB)
+}
+
+pub struct GeocodeBackfillJobHandler {
+    geocoder: Arc<Geocoder>,
+}
+
+impl GeocodeBackfillJobHandler {
+    pub fn new(geocoder: Arc<Geocoder>) -> Self {
+        Self { geocoder }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for GeocodeBackfillJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let updated = self.geocoder.backfill(&job.organization_id).await?;
+        if updated > 0 {
+            tracing::info!("Backfilled address for {} dtakologs ({})", updated, job.organization_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geo_point_from_dtako() {
+        // 東京駅付近（35.6812, 139.7671）
+        let point = GeoPoint::from_dtako(128_452_320, 503_161_560).unwrap();
+        assert_eq!(point.cache_key(), (356812, 1397671));
+        assert_eq!(point.cache_key().0, (128_452_320_f64 / 360.0).round() as i32);
+        assert!(GeoPoint::from_dtako(0, 0).is_none());
+        assert!(GeoPoint::new(91.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_address_formatting() {
+        let address = NominatimAddress {
+            state: Some("東京都".to_string()),
+            city: Some("千代田区".to_string()),
+            quarter: Some("丸の内一丁目".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(format_nominatim_address(&address).as_deref(), Some("東京都千代田区丸の内一丁目"));
+        assert_eq!(format_nominatim_address(&NominatimAddress::default()), None);
+
+        assert_eq!(
+            strip_google_address("日本、〒100-0005 東京都千代田区丸の内１丁目"),
+            "東京都千代田区丸の内１丁目"
+        );
+        assert_eq!(strip_google_address("北海道帯広市西１条南"), "北海道帯広市西１条南");
+    }
+}
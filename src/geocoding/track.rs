@@ -0,0 +1,109 @@
+// 走行軌跡の距離計算と間引き（GetVehicleTrack）
+
+use super::GeoPoint;
+
+/// 地球の平均半径（m）
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// 2 点間の大円距離（m、haversine）
+pub fn distance_m(a: GeoPoint, b: GeoPoint) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.lon - a.lon).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+}
+
+/// 軌跡の総距離（m）
+pub fn path_length_m(points: &[GeoPoint]) -> f64 {
+    points.windows(2).map(|w| distance_m(w[0], w[1])).sum()
+}
+
+/// origin を原点とする平面座標（m、正距円筒図法。車両 1 台の軌跡の範囲なら誤差は小さい）
+fn project(origin: GeoPoint, p: GeoPoint) -> (f64, f64) {
+    let x = (p.lon - origin.lon).to_radians() * origin.lat.to_radians().cos() * EARTH_RADIUS_M;
+    let y = (p.lat - origin.lat).to_radians() * EARTH_RADIUS_M;
+    (x, y)
+}
+
+/// 点 p と線分 ab の距離（平面座標）
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    };
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - cx).powi(2) + (p.1 - cy).powi(2)).sqrt()
+}
+
+/// Douglas-Peucker で間引いて残す点の添字（昇順、始点と終点は必ず残す）
+///
+/// tolerance_m 以内のずれに収まる中間点を落とす。再帰の深さが点数に比例しないようスタックで処理する。
+pub fn simplify(points: &[GeoPoint], tolerance_m: f64) -> Vec<usize> {
+    if points.len() <= 2 || tolerance_m <= 0.0 {
+        return (0..points.len()).collect();
+    }
+    let projected: Vec<(f64, f64)> = points.iter().map(|p| project(points[0], *p)).collect();
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let mut farthest = None;
+        let mut max_distance = tolerance_m;
+        for i in start + 1..end {
+            let d = segment_distance(projected[i], projected[start], projected[end]);
+            if d > max_distance {
+                max_distance = d;
+                farthest = Some(i);
+            }
+        }
+        if let Some(i) = farthest {
+            keep[i] = true;
+            stack.push((start, i));
+            stack.push((i, end));
+        }
+    }
+
+    keep.iter()
+        .enumerate()
+        .filter_map(|(i, k)| k.then_some(i))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(lat: f64, lon: f64) -> GeoPoint {
+        GeoPoint::new(lat, lon).unwrap()
+    }
+
+    #[test]
+    fn test_distance() {
+        // 東京駅 → 大阪駅（約 403 km）
+        let d = distance_m(point(35.6812, 139.7671), point(34.7025, 135.4959));
+        assert!((d - 403_000.0).abs() < 2_000.0, "{}", d);
+        assert_eq!(distance_m(point(35.0, 135.0), point(35.0, 135.0)), 0.0);
+    }
+
+    #[test]
+    fn test_simplify_drops_points_within_tolerance() {
+        // ほぼ直線（北へ約 1.1 km ずつ、中間点は東へ数 m ずれる）+ 最後に東へ曲がる
+        let points = [
+            point(35.00, 135.0),
+            point(35.01, 135.00003),
+            point(35.02, 135.0),
+            point(35.03, 135.00002),
+            point(35.03, 135.02),
+        ];
+        assert_eq!(simplify(&points, 10.0), vec![0, 3, 4]);
+        assert_eq!(simplify(&points, 1.0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(simplify(&points, 0.0).len(), 5);
+        assert_eq!(simplify(&points[..2], 10.0), vec![0, 1]);
+    }
+}
@@ -0,0 +1,405 @@
+// Geofencing
+//
+// 営業所・得意先などの範囲（geofences）への車両の出入りを、運行ログの書き込み時に判定する。
+// 車両ごとの内外は geofence_states に持ち、変わったときだけ geofence_events と outbox に書く。
+// 初めて見た車両は内外を記録するだけで、状態より古い運行ログ（再送・遅延）は判定に使わない。
+// 新旧は data_date_time を日時にして比べる（文字列のままだとオフセットの違う行で順序を誤る）。
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::geocoding::track::distance_m;
+use crate::geocoding::GeoPoint;
+use crate::models::GeofenceModel;
+use crate::outbox::{Outbox, OutboxEvent, GEOFENCE_ENTERED, GEOFENCE_EXITED};
+use crate::proto::dtakologs::Dtakolog;
+use crate::webhooks::WEBHOOK_TARGET;
+
+pub const EVENT_ENTER: &str = "enter";
+pub const EVENT_EXIT: &str = "exit";
+
+/// 範囲の形
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeofenceShape {
+    Circle { center: GeoPoint, radius_m: f64 },
+    /// 頂点（始点に戻らなくてよい）
+    Polygon(Vec<GeoPoint>),
+}
+
+impl GeofenceShape {
+    pub fn contains(&self, point: GeoPoint) -> bool {
+        match self {
+            Self::Circle { center, radius_m } => distance_m(*center, point) <= *radius_m,
+            Self::Polygon(vertices) => polygon_contains(vertices, point),
+        }
+    }
+}
+
+/// 点が多角形の内側か（経度を x、緯度を y とした ray casting。日付変更線をまたぐ多角形は扱わない）
+fn polygon_contains(vertices: &[GeoPoint], point: GeoPoint) -> bool {
+    if vertices.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = vertices.len() - 1;
+    for i in 0..vertices.len() {
+        let (a, b) = (vertices[i], vertices[j]);
+        if (a.lat > point.lat) != (b.lat > point.lat)
+            && point.lon < (b.lon - a.lon) * (point.lat - a.lat) / (b.lat - a.lat) + a.lon
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// 判定する運行ログの位置
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub vehicle_cd: i32,
+    pub vehicle_name: String,
+    pub data_date_time: String,
+    /// data_date_time を日時にしたもの（順序の比較に使う）
+    pub at: DateTime<Utc>,
+    pub point: GeoPoint,
+}
+
+impl Position {
+    /// GPS のない行・日時が RFC 3339 でない行は None
+    pub fn from_dtakolog(dtakolog: &Dtakolog) -> Option<Self> {
+        Some(Self {
+            vehicle_cd: dtakolog.vehicle_cd,
+            vehicle_name: dtakolog.vehicle_name.clone(),
+            data_date_time: dtakolog.data_date_time.clone(),
+            at: parse_data_date_time(&dtakolog.data_date_time)?,
+            point: GeoPoint::from_dtako(dtakolog.gps_latitude, dtakolog.gps_longitude)?,
+        })
+    }
+}
+
+/// 運行ログの日時（RFC 3339）を UTC にする
+fn parse_data_date_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// 車両ごとの内外（geofence_states の 1 行）
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehicleState {
+    pub inside: bool,
+    pub data_date_time: String,
+}
+
+/// 出入り 1 件
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub geofence_id: Uuid,
+    pub event_type: &'static str,
+    pub position: Position,
+}
+
+/// 位置を日時順に範囲と照合し、出入りを返す。更新した状態のキーを touched に入れる
+pub fn evaluate_positions(
+    geofences: &[(Uuid, GeofenceShape)],
+    states: &mut HashMap<(Uuid, i32), VehicleState>,
+    positions: &[Position],
+    touched: &mut HashSet<(Uuid, i32)>,
+) -> Vec<Transition> {
+    let mut positions: Vec<&Position> = positions.iter().collect();
+    positions.sort_by(|a, b| {
+        a.vehicle_cd
+            .cmp(&b.vehicle_cd)
+            .then_with(|| a.at.cmp(&b.at))
+    });
+
+    let mut transitions = Vec::new();
+    for position in positions {
+        for (geofence_id, shape) in geofences {
+            let key = (*geofence_id, position.vehicle_cd);
+            let inside = shape.contains(position.point);
+            match states.get(&key) {
+                // 状態の日時が読めなければ古いものとして扱う
+                Some(state)
+                    if parse_data_date_time(&state.data_date_time).is_some_and(|at| at >= position.at) =>
+                {
+                    continue
+                }
+                Some(state) if state.inside != inside => transitions.push(Transition {
+                    geofence_id: *geofence_id,
+                    event_type: if inside { EVENT_ENTER } else { EVENT_EXIT },
+                    position: position.clone(),
+                }),
+                _ => {}
+            }
+            states.insert(
+                key,
+                VehicleState {
+                    inside,
+                    data_date_time: position.data_date_time.clone(),
+                },
+            );
+            touched.insert(key);
+        }
+    }
+    transitions
+}
+
+/// 運行ログの書き込み後に範囲の出入りを判定する
+#[derive(Clone, Default)]
+pub struct GeofenceEvaluator {
+    outbox: Outbox,
+}
+
+impl GeofenceEvaluator {
+    pub fn new(outbox: Outbox) -> Self {
+        Self { outbox }
+    }
+
+    fn outbox_event(geofence: &GeofenceModel, transition: &Transition) -> OutboxEvent {
+        let position = &transition.position;
+        let (event_type, verb) = if transition.event_type == EVENT_ENTER {
+            (GEOFENCE_ENTERED, "に入りました")
+        } else {
+            (GEOFENCE_EXITED, "を出ました")
+        };
+        let event = OutboxEvent::new(
+            event_type,
+            serde_json::json!({
+                "geofence_id": geofence.id.to_string(),
+                "geofence_name": geofence.name,
+                "vehicle_cd": position.vehicle_cd,
+                "vehicle_name": position.vehicle_name,
+                "data_date_time": position.data_date_time,
+                "latitude": position.point.lat,
+                "longitude": position.point.lon,
+            }),
+        )
+        .message(format!(
+            "{}（{}）が「{}」{}（{}）",
+            position.vehicle_name, position.vehicle_cd, geofence.name, verb, position.data_date_time
+        ));
+        if geofence.notify {
+            event
+        } else {
+            event.only(&[WEBHOOK_TARGET])
+        }
+    }
+
+    /// organization 設定済みのトランザクションで呼ぶ。記録した出入りの件数を返す
+    pub async fn evaluate(
+        &self,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        positions: &[Position],
+    ) -> Result<usize, sqlx::Error> {
+        if positions.is_empty() {
+            return Ok(0);
+        }
+        let geofences: Vec<GeofenceModel> = sqlx::query_as(
+            r#"
+            SELECT id, name, shape, center_lat, center_lon, radius_m, polygon, notify, active,
+                   created_at, updated_at
+            FROM geofences
+            WHERE active
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        let shapes: Vec<(Uuid, GeofenceShape)> = geofences
+            .iter()
+            .filter_map(|g| Some((g.id, g.geometry()?)))
+            .collect();
+        if shapes.is_empty() {
+            return Ok(0);
+        }
+
+        let mut vehicle_cds: Vec<i32> = positions.iter().map(|p| p.vehicle_cd).collect();
+        vehicle_cds.sort_unstable();
+        vehicle_cds.dedup();
+        let rows: Vec<(Uuid, i32, bool, String)> = sqlx::query_as(
+            r#"
+            SELECT geofence_id, vehicle_cd, inside, data_date_time
+            FROM geofence_states
+            WHERE vehicle_cd = ANY($1)
+            FOR UPDATE
+            "#,
+        )
+        .bind(&vehicle_cds)
+        .fetch_all(&mut *conn)
+        .await?;
+        let mut states: HashMap<(Uuid, i32), VehicleState> = rows
+            .into_iter()
+            .map(|(geofence_id, vehicle_cd, inside, data_date_time)| {
+                ((geofence_id, vehicle_cd), VehicleState { inside, data_date_time })
+            })
+            .collect();
+
+        let mut touched = HashSet::new();
+        let transitions = evaluate_positions(&shapes, &mut states, positions, &mut touched);
+
+        let mut geofence_ids = Vec::with_capacity(touched.len());
+        let mut state_vehicle_cds = Vec::with_capacity(touched.len());
+        let mut insides = Vec::with_capacity(touched.len());
+        let mut data_date_times = Vec::with_capacity(touched.len());
+        for key in &touched {
+            let state = &states[key];
+            geofence_ids.push(key.0);
+            state_vehicle_cds.push(key.1);
+            insides.push(state.inside);
+            data_date_times.push(state.data_date_time.clone());
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO geofence_states (organization_id, geofence_id, vehicle_cd, inside, data_date_time)
+            SELECT $1::uuid, s.geofence_id, s.vehicle_cd, s.inside, s.data_date_time
+            FROM UNNEST($2::uuid[], $3::int[], $4::bool[], $5::text[])
+                AS s(geofence_id, vehicle_cd, inside, data_date_time)
+            ON CONFLICT (geofence_id, vehicle_cd) DO UPDATE SET
+                inside = EXCLUDED.inside,
+                data_date_time = EXCLUDED.data_date_time,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(organization_id)
+        .bind(&geofence_ids)
+        .bind(&state_vehicle_cds)
+        .bind(&insides)
+        .bind(&data_date_times)
+        .execute(&mut *conn)
+        .await?;
+
+        for transition in &transitions {
+            let Some(geofence) = geofences.iter().find(|g| g.id == transition.geofence_id) else {
+                continue;
+            };
+            let position = &transition.position;
+            sqlx::query(
+                r#"
+                INSERT INTO geofence_events
+                    (organization_id, geofence_id, vehicle_cd, event_type, data_date_time, latitude, longitude)
+                VALUES ($1::uuid, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(organization_id)
+            .bind(geofence.id)
+            .bind(position.vehicle_cd)
+            .bind(transition.event_type)
+            .bind(&position.data_date_time)
+            .bind(position.point.lat)
+            .bind(position.point.lon)
+            .execute(&mut *conn)
+            .await?;
+            self.outbox
+                .write(conn, organization_id, &Self::outbox_event(geofence, transition))
+                .await?;
+        }
+        Ok(transitions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(lat: f64, lon: f64) -> GeoPoint {
+        GeoPoint::new(lat, lon).unwrap()
+    }
+
+    fn position(vehicle_cd: i32, data_date_time: &str, lat: f64, lon: f64) -> Position {
+        Position {
+            vehicle_cd,
+            vehicle_name: String::new(),
+            data_date_time: data_date_time.to_string(),
+            at: parse_data_date_time(data_date_time).unwrap(),
+            point: point(lat, lon),
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let circle = GeofenceShape::Circle {
+            center: point(35.0, 135.0),
+            radius_m: 200.0,
+        };
+        assert!(circle.contains(point(35.001, 135.0)));
+        assert!(!circle.contains(point(35.003, 135.0)));
+
+        // 凹型（L 字）
+        let polygon = GeofenceShape::Polygon(vec![
+            point(35.0, 135.0),
+            point(35.0, 135.2),
+            point(35.1, 135.2),
+            point(35.1, 135.1),
+            point(35.2, 135.1),
+            point(35.2, 135.0),
+        ]);
+        assert!(polygon.contains(point(35.05, 135.15)));
+        assert!(polygon.contains(point(35.15, 135.05)));
+        assert!(!polygon.contains(point(35.15, 135.15)));
+        assert!(!GeofenceShape::Polygon(vec![point(35.0, 135.0), point(35.1, 135.1)])
+            .contains(point(35.05, 135.05)));
+    }
+
+    #[test]
+    fn test_evaluate_positions() {
+        let depot = Uuid::new_v4();
+        let geofences = vec![(
+            depot,
+            GeofenceShape::Circle {
+                center: point(35.0, 135.0),
+                radius_m: 500.0,
+            },
+        )];
+        let mut states = HashMap::new();
+        let mut touched = HashSet::new();
+
+        // 初めて見た車両は記録だけ。順不同でも日時順に判定する
+        let transitions = evaluate_positions(
+            &geofences,
+            &mut states,
+            &[
+                position(1, "2026-01-24T10:10:00+09:00", 35.0, 135.0),
+                position(1, "2026-01-24T10:00:00+09:00", 35.1, 135.0),
+                position(1, "2026-01-24T10:20:00+09:00", 35.2, 135.0),
+            ],
+            &mut touched,
+        );
+        let kinds: Vec<&str> = transitions.iter().map(|t| t.event_type).collect();
+        assert_eq!(kinds, vec![EVENT_ENTER, EVENT_EXIT]);
+        assert_eq!(transitions[0].position.data_date_time, "2026-01-24T10:10:00+09:00");
+        assert_eq!(touched.len(), 1);
+        assert!(!states[&(depot, 1)].inside);
+
+        // 状態より古い行は無視する
+        touched.clear();
+        let transitions = evaluate_positions(
+            &geofences,
+            &mut states,
+            &[position(1, "2026-01-24T10:15:00+09:00", 35.0, 135.0)],
+            &mut touched,
+        );
+        assert!(transitions.is_empty());
+        assert!(touched.is_empty());
+        assert_eq!(states[&(depot, 1)].data_date_time, "2026-01-24T10:20:00+09:00");
+
+        // オフセットが違っても日時で比べる（01:30Z は 10:20+09:00 より後）
+        let transitions = evaluate_positions(
+            &geofences,
+            &mut states,
+            &[
+                position(1, "2026-01-24T01:15:00Z", 35.0, 135.0),
+                position(1, "2026-01-24T01:30:00Z", 35.0, 135.0),
+            ],
+            &mut touched,
+        );
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].event_type, EVENT_ENTER);
+        assert_eq!(transitions[0].position.data_date_time, "2026-01-24T01:30:00Z");
+        assert_eq!(states[&(depot, 1)].data_date_time, "2026-01-24T01:30:00Z");
+    }
+}
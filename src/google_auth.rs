@@ -1,10 +1,11 @@
 use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::http_client::HttpClient;
+
 /// Google JWKS endpoint
 const GOOGLE_JWKS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
 
@@ -63,15 +64,15 @@ struct JwksCache {
 /// Google ID token verifier with JWKS caching
 #[derive(Clone)]
 pub struct GoogleTokenVerifier {
-    client: Client,
+    http_client: Arc<HttpClient>,
     client_ids: Vec<String>,
     cache: Arc<RwLock<Option<JwksCache>>>,
 }
 
 impl GoogleTokenVerifier {
-    pub fn new(client_ids: Vec<String>) -> Self {
+    pub fn new(client_ids: Vec<String>, http_client: Arc<HttpClient>) -> Self {
         Self {
-            client: Client::new(),
+            http_client,
             client_ids,
             cache: Arc::new(RwLock::new(None)),
         }
@@ -141,9 +142,8 @@ impl GoogleTokenVerifier {
 
         // Fetch fresh JWKS
         let response = self
-            .client
+            .http_client
             .get(GOOGLE_JWKS_URL)
-            .send()
             .await
             .map_err(|e| format!("Failed to fetch JWKS: {}", e))?;
 
@@ -1,10 +1,281 @@
-use reqwest::Client;
+use bytes::Bytes;
+use rand::Rng;
+use reqwest::{Client, Method, Request, RequestBuilder, Response, ResponseBuilderExt, StatusCode, Url};
 use serde::de::DeserializeOwned;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{HttpCircuitBreakerConfig, HttpClientConfig, HttpRetryConfig};
+use crate::middleware::trace_context::TraceContext;
+
+/// 外部呼び出しの再試行（指数バックオフ + full jitter）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最初の呼び出しを含む試行回数（1 なら再試行しない）
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &HttpRetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: Duration::from_millis(config.base_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+        }
+    }
+
+    /// attempt 回目の失敗後の待ち時間の上限
+    fn max_backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(1 << exponent).min(self.max_delay)
+    }
+
+    /// 0〜上限の一様乱数（同時に失敗した呼び出しの再試行をずらす）
+    fn backoff(&self, attempt: u32) -> Duration {
+        let max = self.max_backoff(attempt).as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=max))
+    }
+}
+
+/// 同じリクエストを繰り返しても結果が変わらないメソッド
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+/// 再試行する応答。429 / 503 は処理されていないのでメソッドによらず、その他の 5xx は冪等なメソッドだけ
+fn is_retryable_status(status: StatusCode, idempotent: bool) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        s => idempotent && s.is_server_error(),
+    }
+}
+
+/// 再試行するエラー。接続失敗は送信前なのでメソッドによらず、タイムアウトは冪等なメソッドだけ
+fn is_retryable_error(err: &reqwest::Error, idempotent: bool) -> bool {
+    err.is_connect() || (idempotent && err.is_timeout())
+}
+
+/// circuit breaker が開いていて送らなかった応答（503）に付くヘッダー
+pub const CIRCUIT_OPEN_HEADER: &str = "x-circuit-breaker";
+
+/// ホストごとの circuit breaker（連続して失敗したホストには一定時間送らない）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreakerPolicy {
+    /// 開くまでの連続失敗数（0 なら無効）
+    pub failure_threshold: u32,
+    /// 開いている時間。過ぎたら 1 回だけ試し、成功すれば閉じる
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+impl CircuitBreakerPolicy {
+    pub fn from_config(config: &HttpCircuitBreakerConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold,
+            open_duration: Duration::from_secs(config.open_secs),
+        }
+    }
+}
+
+#[derive(Default)]
+struct HostCircuit {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// 開いた後の試しの呼び出しを始めた時刻（結果が出るまで他は送らない）
+    probe_started: Option<Instant>,
+}
+
+/// ホスト（host:port）ごとの連続失敗数（HttpClient の clone 間で共有）
+#[derive(Default)]
+struct CircuitBreaker {
+    hosts: Mutex<HashMap<String, HostCircuit>>,
+}
+
+impl CircuitBreaker {
+    /// 送ってよいか。開いている時間が過ぎていれば 1 つだけ通す
+    fn allow(&self, policy: &CircuitBreakerPolicy, host: &str, now: Instant) -> bool {
+        if policy.failure_threshold == 0 {
+            return true;
+        }
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(circuit) = hosts.get_mut(host) else {
+            return true;
+        };
+        match circuit.open_until {
+            None => true,
+            Some(until) if now < until => false,
+            // 試しの呼び出しが取り消されても止まったままにならないよう、open_duration 経てば次を通す
+            Some(_) if circuit.probe_started.is_some_and(|started| now < started + policy.open_duration) => false,
+            Some(_) => {
+                circuit.probe_started = Some(now);
+                true
+            }
+        }
+    }
+
+    fn record_success(&self, host: &str) {
+        self.hosts.lock().unwrap_or_else(|e| e.into_inner()).remove(host);
+    }
+
+    fn record_failure(&self, policy: &CircuitBreakerPolicy, host: &str, now: Instant) {
+        if policy.failure_threshold == 0 {
+            return;
+        }
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let circuit = hosts.entry(host.to_string()).or_default();
+        circuit.failures = circuit.failures.saturating_add(1);
+        circuit.probe_started = None;
+        // 試しの呼び出しが失敗したらすぐ開き直す
+        if circuit.failures >= policy.failure_threshold || circuit.open_until.is_some() {
+            if !matches!(circuit.open_until, Some(until) if until > now) {
+                tracing::warn!(
+                    "HTTP circuit for {} opened after {} consecutive failures",
+                    host,
+                    circuit.failures
+                );
+            }
+            circuit.open_until = Some(now + policy.open_duration);
+        }
+    }
+}
+
+/// circuit breaker のキー
+fn circuit_key(url: &Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+/// ホストの不調とみなす結果（5xx・接続失敗・タイムアウト）。None はホストの状態と関係ないエラー
+fn is_host_failure(result: &Result<Response, reqwest::Error>) -> Option<bool> {
+    match result {
+        Ok(response) => Some(response.status().is_server_error()),
+        Err(e) if e.is_connect() || e.is_timeout() => Some(true),
+        Err(_) => None,
+    }
+}
+
+/// circuit breaker が開いているときに送らずに返す応答
+fn circuit_open_response(url: &Url) -> Response {
+    http::Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(CIRCUIT_OPEN_HEADER, "open")
+        .url(url.clone())
+        .body(Vec::<u8>::new())
+        .expect("valid circuit open response")
+        .into()
+}
+
+/// キャッシュする応答の上限（超えたら期限の近いものから捨てる）
+const MAX_CACHE_ENTRIES: usize = 1000;
+
+/// キャッシュした GET の応答（2xx だけ保存する）
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+impl CachedResponse {
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// GET 応答の TTL キャッシュ（HttpClient の clone 間で共有）
+#[derive(Default)]
+struct ResponseCache {
+    entries: Mutex<HashMap<String, (Instant, CachedResponse)>>,
+    /// 取得中のキー（同時に来た RPC で同じ呼び出しを重ねない）
+    inflight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl ResponseCache {
+    fn get(&self, key: &str, now: Instant) -> Option<CachedResponse> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|(expires_at, _)| *expires_at > now)
+            .map(|(_, response)| response.clone())
+    }
+
+    fn insert(&self, key: String, response: CachedResponse, expires_at: Instant, now: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            entries.retain(|_, (at, _)| *at > now);
+        }
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, (at, _))| *at).map(|(k, _)| k.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (expires_at, response));
+    }
+
+    fn gate(&self, key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+        inflight.entry(key.to_string()).or_default().clone()
+    }
+
+    fn release(&self, key: &str) {
+        self.inflight.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+    }
+}
+
+/// キャッシュのキー（メソッド・URL・ヘッダー）。scope を指定すると Authorization の代わりに使う
+/// （OAuth 1.0a のように署名の nonce で毎回変わるヘッダーの場合、利用者を区別する値を渡す）
+fn cache_key(request: &Request, scope: Option<&str>) -> String {
+    let mut headers: Vec<String> = request
+        .headers()
+        .iter()
+        .filter(|(name, _)| scope.is_none() || *name != reqwest::header::AUTHORIZATION)
+        .map(|(name, value)| format!("{}={}", name, String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    headers.sort();
+    format!(
+        "{} {}\n{}\n{}",
+        request.method(),
+        request.url(),
+        headers.join("\n"),
+        scope.unwrap_or_default()
+    )
+}
 
 #[derive(Clone)]
 pub struct HttpClient {
     client: Client,
+    retry: RetryPolicy,
+    /// 1 回の呼び出し全体のタイムアウト（Client の既定を上書き）
+    timeout: Option<Duration>,
+    breaker: CircuitBreakerPolicy,
+    circuit: Arc<CircuitBreaker>,
+    cache: Arc<ResponseCache>,
 }
 
 impl HttpClient {
@@ -14,15 +285,218 @@ impl HttpClient {
                 .timeout(Duration::from_secs(30))
                 .build()
                 .expect("Failed to create HTTP client"),
+            retry: RetryPolicy::default(),
+            timeout: None,
+            breaker: CircuitBreakerPolicy::default(),
+            circuit: Arc::default(),
+            cache: Arc::default(),
+        }
+    }
+
+    /// 設定（HTTP_* / OUTBOUND_* 環境変数）のタイムアウト・接続プール・プロキシ・再試行・circuit breaker で作る
+    pub fn from_config(config: &HttpClientConfig) -> Self {
+        let keepalive = (config.tcp_keepalive_secs > 0)
+            .then(|| Duration::from_secs(config.tcp_keepalive_secs));
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+            .read_timeout(Duration::from_millis(config.read_timeout_ms))
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+            .tcp_keepalive(keepalive);
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(proxy.clone());
         }
+        Self {
+            client: builder.build().expect("Failed to create HTTP client"),
+            retry: RetryPolicy::from_config(&config.retry),
+            timeout: None,
+            breaker: CircuitBreakerPolicy::from_config(&config.circuit_breaker),
+            circuit: Arc::default(),
+            cache: Arc::default(),
+        }
+    }
+
+    /// 再試行ポリシーを変える
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 1 回の呼び出し全体のタイムアウトを変える（再試行は別に数える。リクエストに個別の指定があればそちら）
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// circuit breaker の設定を変える（状態は clone 間で共有したまま）
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreakerPolicy) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// リクエストの組み立て用（送信は `send` で行う）
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// 再試行ポリシーに従って送信する。本文がストリームで複製できないリクエストは 1 回だけ
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        self.execute(request.build()?).await
+    }
+
+    async fn execute(&self, mut request: Request) -> Result<Response, reqwest::Error> {
+        // キャッシュのキーを作った後に付ける（呼び出しごとに span id が変わるため）
+        TraceContext::inject(request.headers_mut());
+        if request.timeout().is_none() {
+            *request.timeout_mut() = self.timeout;
+        }
+        let idempotent = is_idempotent(request.method());
+        let host = circuit_key(request.url());
+        let mut attempt = 1;
+        loop {
+            if !self.circuit.allow(&self.breaker, &host, Instant::now()) {
+                tracing::warn!(
+                    "HTTP {} {} not sent: circuit for {} is open",
+                    request.method(),
+                    request.url().path(),
+                    host
+                );
+                return Ok(circuit_open_response(request.url()));
+            }
+            let current = match request.try_clone() {
+                Some(copy) if attempt < self.retry.max_attempts => copy,
+                _ => {
+                    let result = self.client.execute(request).await;
+                    self.record(&host, &result);
+                    return result;
+                }
+            };
+            let result = self.client.execute(current).await;
+            self.record(&host, &result);
+            match result {
+                Ok(response) if is_retryable_status(response.status(), idempotent) => {
+                    tracing::warn!(
+                        "HTTP {} {} returned {} (attempt {}/{}), retrying",
+                        request.method(),
+                        request.url().path(),
+                        response.status(),
+                        attempt,
+                        self.retry.max_attempts
+                    );
+                }
+                Err(e) if is_retryable_error(&e, idempotent) => {
+                    tracing::warn!(
+                        "HTTP {} {} failed (attempt {}/{}), retrying: {}",
+                        request.method(),
+                        request.url().path(),
+                        attempt,
+                        self.retry.max_attempts,
+                        e.without_url()
+                    );
+                }
+                result => return result,
+            }
+            tokio::time::sleep(self.retry.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    fn record(&self, host: &str, result: &Result<Response, reqwest::Error>) {
+        match is_host_failure(result) {
+            Some(true) => self.circuit.record_failure(&self.breaker, host, Instant::now()),
+            Some(false) => self.circuit.record_success(host),
+            None => {}
+        }
+    }
+
+    /// GET を ttl の間キャッシュして送る（opt-in）。2xx 以外は保存しない。GET 以外はキャッシュしない
+    pub async fn send_cached(
+        &self,
+        request: RequestBuilder,
+        ttl: Duration,
+        scope: Option<&str>,
+    ) -> Result<CachedResponse, reqwest::Error> {
+        self.fetch_cached(request, ttl, scope, false).await
+    }
+
+    /// `send_cached` と同じだがキャッシュを使わずに送り、2xx なら保存し直す（利用者の再読み込み用）
+    pub async fn refresh_cached(
+        &self,
+        request: RequestBuilder,
+        ttl: Duration,
+        scope: Option<&str>,
+    ) -> Result<CachedResponse, reqwest::Error> {
+        self.fetch_cached(request, ttl, scope, true).await
+    }
+
+    async fn fetch_cached(
+        &self,
+        request: RequestBuilder,
+        ttl: Duration,
+        scope: Option<&str>,
+        refresh: bool,
+    ) -> Result<CachedResponse, reqwest::Error> {
+        let request = request.build()?;
+        if request.method() != Method::GET {
+            let response = self.execute(request).await?;
+            return Ok(CachedResponse { status: response.status(), body: response.bytes().await? });
+        }
+
+        let key = cache_key(&request, scope);
+        if let Some(hit) = self.cache.get(&key, Instant::now()).filter(|_| !refresh) {
+            return Ok(hit);
+        }
+        let gate = self.cache.gate(&key);
+        let _guard = gate.lock().await;
+        // 待っている間に先行の呼び出しが保存していればそれを使う
+        if let Some(hit) = self.cache.get(&key, Instant::now()).filter(|_| !refresh) {
+            return Ok(hit);
+        }
+
+        let result: Result<CachedResponse, reqwest::Error> = async {
+            let response = self.execute(request).await?;
+            Ok(CachedResponse { status: response.status(), body: response.bytes().await? })
+        }
+        .await;
+        if let Ok(response) = &result {
+            if response.status.is_success() {
+                let now = Instant::now();
+                self.cache.insert(key.clone(), response.clone(), now + ttl, now);
+            }
+        }
+        self.cache.release(&key);
+        result
+    }
+
+    /// `get_json` のキャッシュ版（ホーム車両一覧など）。2xx 以外はエラー
+    pub async fn get_json_cached<T: DeserializeOwned>(&self, url: &str, ttl: Duration) -> anyhow::Result<T> {
+        let response = self.send_cached(self.client.get(url), ttl, None).await?;
+        if !response.status.is_success() {
+            anyhow::bail!("HTTP status {}", response.status);
+        }
+        Ok(response.json()?)
     }
 
     pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, reqwest::Error> {
-        self.client.get(url).send().await?.json().await
+        self.send(self.client.get(url)).await?.json().await
+    }
+
+    /// 追加ヘッダー付き GET（User-Agent 必須の API など）。2xx 以外はエラー
+    pub async fn get_json_with_headers<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<T, reqwest::Error> {
+        let mut request = self.client.get(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.send(request).await?.error_for_status()?.json().await
     }
 
     pub async fn get(&self, url: &str) -> Result<reqwest::Response, reqwest::Error> {
-        self.client.get(url).send().await
+        self.send(self.client.get(url)).await
     }
 
     pub async fn post_json<T: serde::Serialize>(
@@ -30,7 +504,51 @@ impl HttpClient {
         url: &str,
         body: &T,
     ) -> Result<reqwest::Response, reqwest::Error> {
-        self.client.post(url).json(body).send().await
+        self.send(self.client.post(url).json(body)).await
+    }
+
+    pub async fn post_form<T: serde::Serialize + ?Sized>(
+        &self,
+        url: &str,
+        form: &T,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.send(self.client.post(url).form(form)).await
+    }
+
+    pub async fn post_form_with_basic_auth<T: serde::Serialize + ?Sized>(
+        &self,
+        url: &str,
+        username: &str,
+        password: &str,
+        form: &T,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.send(self.client.post(url).basic_auth(username, Some(password)).form(form)).await
+    }
+
+    /// シリアライズ済みの JSON 本文を追加ヘッダー付きで送る（署名対象の本文をそのまま送るため）
+    pub async fn post_json_with_headers(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: String,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.send(request.body(body)).await
+    }
+
+    pub async fn post_json_with_bearer<T: serde::Serialize>(
+        &self,
+        url: &str,
+        token: &str,
+        body: &T,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.send(self.client.post(url).bearer_auth(token).json(body)).await
     }
 }
 
@@ -39,3 +557,95 @@ impl Default for HttpClient {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.max_backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.max_backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.max_backoff(10), Duration::from_secs(5));
+        for attempt in 1..10 {
+            assert!(policy.backoff(attempt) <= policy.max_backoff(attempt));
+        }
+    }
+
+    #[test]
+    fn test_cache_key_scope_replaces_authorization() {
+        let client = Client::new();
+        let build = |nonce: &str| {
+            client
+                .get("https://www.flickr.com/services/rest/?photo_id=1")
+                .header("Authorization", format!("OAuth oauth_nonce=\"{}\"", nonce))
+                .build()
+                .unwrap()
+        };
+        assert_ne!(cache_key(&build("a"), None), cache_key(&build("b"), None));
+        assert_eq!(cache_key(&build("a"), Some("token")), cache_key(&build("b"), Some("token")));
+        assert_ne!(cache_key(&build("a"), Some("token")), cache_key(&build("a"), Some("other")));
+    }
+
+    #[test]
+    fn test_response_cache_expires() {
+        let cache = ResponseCache::default();
+        let now = Instant::now();
+        let response = CachedResponse { status: StatusCode::OK, body: Bytes::from_static(b"[]") };
+        cache.insert("k".to_string(), response, now + Duration::from_secs(30), now);
+        assert!(cache.get("k", now).is_some());
+        assert!(cache.get("k", now + Duration::from_secs(31)).is_none());
+        assert!(cache.get("other", now).is_none());
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_probes() {
+        let policy = CircuitBreakerPolicy { failure_threshold: 2, open_duration: Duration::from_secs(30) };
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        breaker.record_failure(&policy, "api:443", now);
+        assert!(breaker.allow(&policy, "api:443", now));
+        breaker.record_failure(&policy, "api:443", now);
+        assert!(!breaker.allow(&policy, "api:443", now));
+        assert!(breaker.allow(&policy, "other:443", now));
+
+        // 期限後は 1 つだけ通し、失敗したら開き直す
+        let later = now + Duration::from_secs(31);
+        assert!(breaker.allow(&policy, "api:443", later));
+        assert!(!breaker.allow(&policy, "api:443", later));
+        breaker.record_failure(&policy, "api:443", later);
+        assert!(!breaker.allow(&policy, "api:443", later + Duration::from_secs(29)));
+
+        let recovered = later + Duration::from_secs(31);
+        assert!(breaker.allow(&policy, "api:443", recovered));
+        breaker.record_success("api:443");
+        assert!(breaker.allow(&policy, "api:443", recovered));
+        assert!(breaker.allow(&policy, "api:443", recovered));
+
+        let disabled = CircuitBreakerPolicy { failure_threshold: 0, ..policy };
+        for _ in 0..10 {
+            breaker.record_failure(&disabled, "api:443", now);
+        }
+        assert!(breaker.allow(&disabled, "api:443", now));
+    }
+
+    #[test]
+    fn test_circuit_open_response() {
+        let url = Url::parse("https://hono-api.mtamaramu.com/api/home-cars").unwrap();
+        assert_eq!(circuit_key(&url), "hono-api.mtamaramu.com:443");
+        let response = circuit_open_response(&url);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[CIRCUIT_OPEN_HEADER], "open");
+        assert_eq!(response.url(), &url);
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY, true));
+        assert!(!is_retryable_status(StatusCode::BAD_GATEWAY, false));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND, true));
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{DateTime, FixedOffset};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::proto::dvr_notifications::DvrNotification;
+use crate::services::DvrNotificationsServiceImpl;
+
+/// DVR ベンダーの Webhook を受けるパス
+pub const DVR_INGEST_PATH: &str = "/ingest/dvr";
+/// 取り込みトークンのヘッダー（Authorization は cf-grpc-proxy の IAM トークンに使われるため別ヘッダー）
+const TOKEN_HEADER: &str = "x-ingest-token";
+const TOKEN_PREFIX: &str = "dvrin_";
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// ベンダーごとに異なるフィールド名（先に見つかったものを使う）
+const MP4_URL_KEYS: &[&str] = &["mp4_url", "mp4Url", "video_url", "videoUrl", "movie_url", "movieUrl"];
+const VEHICLE_CD_KEYS: &[&str] = &["vehicle_cd", "vehicleCd", "vehicle_id", "vehicleId", "car_cd", "carCd"];
+const VEHICLE_NAME_KEYS: &[&str] = &["vehicle_name", "vehicleName", "car_name", "carName"];
+const SERIAL_NO_KEYS: &[&str] = &["serial_no", "serialNo", "device_serial", "deviceSerial", "serial"];
+const FILE_NAME_KEYS: &[&str] = &["file_name", "fileName"];
+const EVENT_TYPE_KEYS: &[&str] = &["event_type", "eventType", "event", "type"];
+const DATETIME_KEYS: &[&str] = &["dvr_datetime", "dvrDatetime", "event_time", "eventTime", "occurred_at", "datetime", "timestamp"];
+const DRIVER_NAME_KEYS: &[&str] = &["driver_name", "driverName", "driver"];
+/// イベントの配列を包むキー
+const ENVELOPE_KEYS: &[&str] = &["events", "notifications", "data"];
+
+/// 取り込みトークンを生成（発行時に1度だけ返す）
+pub fn generate_ingest_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|e| format!("RNG error: {}", e))?;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}{}", TOKEN_PREFIX, hex))
+}
+
+/// トークンは SHA-256 だけを保存する
+pub fn hash_ingest_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn field<'a>(event: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|key| event.get(*key)).filter(|v| !v.is_null())
+}
+
+fn string_field(event: &Value, keys: &[&str]) -> Option<String> {
+    match field(event, keys)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// RFC3339 なら dvr_datetime の形式（JST の "YYYY-MM-DD HH:MM:SS"）に揃える
+fn normalize_datetime(value: &str) -> String {
+    let jst = FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(t) => t.with_timezone(&jst).format("%Y-%m-%d %H:%M:%S").to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+fn normalize_event(index: usize, event: &Value) -> Result<DvrNotification, String> {
+    let required = |keys: &[&str]| {
+        string_field(event, keys).ok_or_else(|| format!("events[{}]: {} is required", index, keys[0]))
+    };
+    let mp4_url = required(MP4_URL_KEYS)?;
+    let vehicle_cd = required(VEHICLE_CD_KEYS)?
+        .parse::<i64>()
+        .map_err(|_| format!("events[{}]: vehicle_cd must be an integer", index))?;
+    let file_name = string_field(event, FILE_NAME_KEYS).unwrap_or_else(|| {
+        let path = mp4_url.split(['?', '#']).next().unwrap_or_default();
+        path.rsplit('/').next().unwrap_or_default().to_string()
+    });
+
+    Ok(DvrNotification {
+        vehicle_cd,
+        vehicle_name: string_field(event, VEHICLE_NAME_KEYS).unwrap_or_default(),
+        serial_no: string_field(event, SERIAL_NO_KEYS).unwrap_or_default(),
+        file_name,
+        event_type: required(EVENT_TYPE_KEYS)?,
+        dvr_datetime: normalize_datetime(&required(DATETIME_KEYS)?),
+        driver_name: string_field(event, DRIVER_NAME_KEYS).unwrap_or_default(),
+        mp4_url,
+    })
+}
+
+/// ベンダーの本文（1件のオブジェクト、配列、`{"events": [...]}` 等）を DvrNotification に変換
+pub fn normalize_dvr_payload(payload: &Value) -> Result<Vec<DvrNotification>, String> {
+    let events: Vec<&Value> = match payload {
+        Value::Array(events) => events.iter().collect(),
+        Value::Object(map) => match ENVELOPE_KEYS.iter().find_map(|key| map.get(*key)) {
+            Some(Value::Array(events)) => events.iter().collect(),
+            _ => vec![payload],
+        },
+        _ => return Err("payload must be a JSON object or array".to_string()),
+    };
+    events
+        .into_iter()
+        .enumerate()
+        .map(|(index, event)| normalize_event(index, event))
+        .collect()
+}
+
+#[derive(Clone)]
+struct IngestState {
+    pool: PgPool,
+    dvr_notifications: Arc<DvrNotificationsServiceImpl>,
+}
+
+pub fn router(pool: PgPool, dvr_notifications: Arc<DvrNotificationsServiceImpl>) -> Router {
+    Router::new()
+        .route(DVR_INGEST_PATH, post(ingest_dvr))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(IngestState { pool, dvr_notifications })
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "error": message.into() }))).into_response()
+}
+
+async fn ingest_dvr(
+    State(state): State<IngestState>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> Response {
+    let token = headers
+        .get(TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| query.get("token").cloned());
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        return error_response(StatusCode::UNAUTHORIZED, "Ingest token required");
+    };
+
+    let organization_id: Option<String> = match sqlx::query_scalar("SELECT resolve_dvr_ingest_token($1)")
+        .bind(hash_ingest_token(&token))
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(org) => org,
+        Err(e) => {
+            tracing::error!("Failed to resolve DVR ingest token: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error");
+        }
+    };
+    let Some(organization_id) = organization_id else {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid ingest token");
+    };
+
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)),
+    };
+    let notifications = match normalize_dvr_payload(&payload) {
+        Ok(notifications) => notifications,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+
+    match state
+        .dvr_notifications
+        .create_notifications(&organization_id, notifications)
+        .await
+    {
+        Ok(result) => {
+            let status = if result.success { StatusCode::OK } else { StatusCode::INTERNAL_SERVER_ERROR };
+            (
+                status,
+                Json(serde_json::json!({
+                    "records_added": result.records_added,
+                    "total_records": result.total_records,
+                    "message": result.message,
+                })),
+            )
+                .into_response()
+        }
+        Err(status) => error_response(StatusCode::INTERNAL_SERVER_ERROR, status.message()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_dvr_payload_accepts_vendor_field_names() {
+        let payload = serde_json::json!({
+            "events": [{
+                "videoUrl": "https://dvr.example.com/clips/abc.mp4?sig=1",
+                "vehicleId": "1001",
+                "carName": "1号車",
+                "eventType": "急ブレーキ",
+                "eventTime": "2026-10-16T00:30:00Z",
+            }]
+        });
+        let notifications = normalize_dvr_payload(&payload).unwrap();
+        assert_eq!(notifications.len(), 1);
+        let n = &notifications[0];
+        assert_eq!(n.mp4_url, "https://dvr.example.com/clips/abc.mp4?sig=1");
+        assert_eq!(n.vehicle_cd, 1001);
+        assert_eq!(n.vehicle_name, "1号車");
+        assert_eq!(n.file_name, "abc.mp4");
+        assert_eq!(n.dvr_datetime, "2026-10-16 09:30:00");
+        assert_eq!(n.driver_name, "");
+    }
+
+    #[test]
+    fn test_normalize_dvr_payload_reports_missing_fields() {
+        let payload = serde_json::json!([
+            { "mp4_url": "https://dvr.example.com/a.mp4", "vehicle_cd": 1, "event_type": "衝撃", "dvr_datetime": "2026-10-16 09:30:00" },
+            { "mp4_url": "https://dvr.example.com/b.mp4", "vehicle_cd": 1 },
+        ]);
+        assert_eq!(
+            normalize_dvr_payload(&payload).unwrap_err(),
+            "events[1]: event_type is required"
+        );
+        assert!(normalize_dvr_payload(&serde_json::json!("text")).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+// Inbound HTTP ingestion
+//
+// 外部システムからの Webhook を gRPC と同じリスナーで受ける（axum ルート）。
+// JWT の代わりに組織ごとの取り込みトークン（X-Ingest-Token ヘッダー、または ?token=）で認証し、
+// トークンから組織を決めて既存のサービスの処理（保存・通知）に渡す。
+
+pub mod dvr;
+
+use std::sync::Arc;
+
+use axum::Router;
+use sqlx::PgPool;
+
+use crate::services::DvrNotificationsServiceImpl;
+
+pub use dvr::{generate_ingest_token, hash_ingest_token, normalize_dvr_payload, DVR_INGEST_PATH};
+
+/// 取り込みルート（main.rs で gRPC / REST のルートにマージする）
+pub fn router(pool: PgPool, dvr_notifications: Arc<DvrNotificationsServiceImpl>) -> Router {
+    dvr::router(pool, dvr_notifications)
+}
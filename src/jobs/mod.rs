@@ -0,0 +1,17 @@
+// Durable background job queue
+//
+// tokio::spawn の fire-and-forget では再起動時に処理が失われるため、jobs テーブルに登録して
+// ワーカーが claim → 実行 → ack する。失敗時は指数バックオフで再実行し、max_attempts で dead_letter にする。
+// 登録は呼び出し元の（組織設定済み）コネクションで行うため、業務データと同じトランザクションに含められる。
+// 定期実行は scheduler が組織ごとの cron 式（scheduled_tasks）に従って job を登録する（advisory lock でリーダーの1台のみ）。
+// 起動時は StartupRecovery が job 登録前に止まった処理を探して登録し直す。
+
+pub mod queue;
+pub mod recovery;
+pub mod scheduler;
+pub mod worker;
+
+pub use queue::{enqueue, Job, NewJob};
+pub use recovery::StartupRecovery;
+pub use scheduler::{ScheduledTaskDef, Scheduler};
+pub use worker::{JobHandler, JobWorkerPool, QueueDepth};
@@ -0,0 +1,108 @@
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::{FromRow, PgConnection};
+
+/// 既定の最大試行回数
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+/// backlog_limit を超えた1件ごとに遅らせる秒数（最大1時間）
+const BACKLOG_DELAY_SECS: i64 = 5;
+
+/// claim_job で取得した job
+#[derive(Debug, Clone, FromRow)]
+pub struct Job {
+    pub id: i64,
+    pub organization_id: String,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// 今回の実行を含む試行回数
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+impl Job {
+    pub fn payload<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        serde_json::from_value(self.payload.clone())
+            .map_err(|e| anyhow!("Invalid payload for {}: {}", self.kind, e))
+    }
+}
+
+/// 登録する job
+#[derive(Debug, Clone)]
+pub struct NewJob {
+    kind: &'static str,
+    payload: serde_json::Value,
+    dedupe_key: Option<String>,
+    max_attempts: i32,
+    backlog_limit: Option<i64>,
+}
+
+impl NewJob {
+    pub fn new(kind: &'static str, payload: impl Serialize) -> Self {
+        Self {
+            kind,
+            payload: serde_json::to_value(payload).unwrap_or_default(),
+            dedupe_key: None,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            backlog_limit: None,
+        }
+    }
+
+    /// 同じ kind + key の job が pending/running の間は登録しない
+    pub fn dedupe_key(mut self, key: impl Into<String>) -> Self {
+        self.dedupe_key = Some(key.into());
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// 組織内で同じ kind の pending がこの件数を超えていたら、超過分に応じて実行開始を遅らせる
+    /// （アップロード集中時などにキューを一気に流さないためのバックプレッシャー）
+    pub fn backlog_limit(mut self, limit: i64) -> Self {
+        self.backlog_limit = Some(limit.max(0));
+        self
+    }
+}
+
+/// job を登録（RLS のため organization 設定済みのコネクションを渡す）
+///
+/// dedupe_key が重複して登録されなかった場合は None
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    organization_id: &str,
+    job: NewJob,
+) -> Result<Option<i64>, sqlx::Error> {
+    let id: Option<(i64,)> = sqlx::query_as(
+        r#"
+        INSERT INTO jobs (organization_id, kind, payload, dedupe_key, max_attempts, run_at)
+        VALUES ($1::uuid, $2, $3, $4, $5, CASE
+            WHEN $6::bigint IS NULL THEN NOW()
+            ELSE NOW() + make_interval(secs => LEAST(3600, $7 * GREATEST(0, (
+                SELECT COUNT(*) FROM jobs p
+                WHERE p.organization_id = $1::uuid AND p.kind = $2 AND p.status = 'pending'
+            ) - $6 + 1)))
+        END)
+        ON CONFLICT (organization_id, kind, dedupe_key)
+            WHERE dedupe_key IS NOT NULL AND status IN ('pending', 'running')
+            DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(organization_id)
+    .bind(job.kind)
+    .bind(&job.payload)
+    .bind(&job.dedupe_key)
+    .bind(job.max_attempts)
+    .bind(job.backlog_limit)
+    .bind(BACKLOG_DELAY_SECS)
+    .fetch_optional(conn)
+    .await?;
+
+    if let Some((id,)) = id {
+        tracing::debug!("Enqueued job {} ({})", id, job.kind);
+    }
+    Ok(id.map(|(id,)| id))
+}
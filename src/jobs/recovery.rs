@@ -0,0 +1,88 @@
+use sqlx::{FromRow, PgPool};
+
+use crate::db::AdvisoryLock;
+
+/// 複数インスタンスが同時に起動しても1台だけが実行する
+const RECOVERY_LOCK_KEY: &str = "jobs.startup_recovery";
+/// これより古いデータは対象外（job 導入前のデータを一斉に再処理しない）
+const RECOVERY_WINDOW: &str = "7 days";
+
+#[derive(Debug, FromRow)]
+struct Recovered {
+    job_kind: String,
+    enqueued: i64,
+}
+
+/// 起動時に中断された処理を見つけて job を登録し直す
+///
+/// job 登録前にプロセスが止まった処理（アップロード直後の自動解析、同期途中の Flickr アップロード、
+/// DVR の mp4 保存、STANDARD 昇格）が対象。running のまま止まった job は claim_job のロックタイムアウトで
+/// 再取得されるのでここでは扱わない。kind を登録しなかったものはスキップする。
+#[derive(Default)]
+pub struct StartupRecovery {
+    auto_parse: Option<&'static str>,
+    flickr_upload: Option<&'static str>,
+    mp4_download: Option<&'static str>,
+    storage_promotion: Option<&'static str>,
+}
+
+impl StartupRecovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn auto_parse(mut self, kind: &'static str) -> Self {
+        self.auto_parse = Some(kind);
+        self
+    }
+
+    pub fn flickr_upload(mut self, kind: &'static str) -> Self {
+        self.flickr_upload = Some(kind);
+        self
+    }
+
+    pub fn mp4_download(mut self, kind: &'static str) -> Self {
+        self.mp4_download = Some(kind);
+        self
+    }
+
+    pub fn storage_promotion(mut self, kind: &'static str) -> Self {
+        self.storage_promotion = Some(kind);
+        self
+    }
+
+    /// バックグラウンドで1回だけ実行（起動を待たせない）
+    pub fn spawn(self, pool: PgPool) {
+        tokio::spawn(async move {
+            if let Err(e) = self.run(&pool).await {
+                tracing::warn!("Startup recovery failed: {}", e);
+            }
+        });
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let Some(lock) = AdvisoryLock::try_acquire(pool, RECOVERY_LOCK_KEY).await? else {
+            tracing::info!("Startup recovery is running on another instance, skipping");
+            return Ok(());
+        };
+
+        let recovered = sqlx::query_as::<_, Recovered>(
+            "SELECT * FROM recover_interrupted_work($1, $2, $3, $4, $5::interval)",
+        )
+        .bind(self.auto_parse)
+        .bind(self.flickr_upload)
+        .bind(self.mp4_download)
+        .bind(self.storage_promotion)
+        .bind(RECOVERY_WINDOW)
+        .fetch_all(pool)
+        .await;
+        lock.release().await;
+
+        for r in recovered? {
+            if r.enqueued > 0 {
+                tracing::info!("Startup recovery: enqueued {} {} jobs", r.enqueued, r.job_kind);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use cron::Schedule;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use super::{enqueue, NewJob};
+use crate::db::{AdvisoryLock, OrgScopedPool};
+
+/// 実行時刻を過ぎたタスクを確認する間隔
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// 同じタスクの job が pending/running の間は次の回を登録しない
+const SCHEDULED_DEDUPE_KEY: &str = "scheduled";
+/// スケジューラのリーダーを1インスタンスに決める advisory lock
+const LEADER_LOCK_KEY: &str = "jobs.scheduler.leader";
+
+/// スケジュール実行できるタスク（name は job の kind）
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduledTaskDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// 組織のスケジュールが未登録のときに一覧に表示する推奨値
+    pub default_cron: &'static str,
+}
+
+/// cron 式を解釈（5 フィールドなら秒 = 0 を補う）
+pub fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let expression = expression.trim();
+    let normalized = match expression.split_whitespace().count() {
+        5 => format!("0 {}", expression),
+        6 | 7 => expression.to_string(),
+        _ => return Err(format!("cron expression must have 5 or 6 fields: {:?}", expression)),
+    };
+    Schedule::from_str(&normalized)
+        .map_err(|e| format!("Invalid cron expression {:?}: {}", expression, e))
+}
+
+/// after より後の次回実行時刻（cron 式は JST として評価）
+pub fn next_run_after(expression: &str, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+    let jst = FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    Ok(parse_cron(expression)?
+        .after(&after.with_timezone(&jst))
+        .next()
+        .map(|t| t.with_timezone(&Utc)))
+}
+
+#[derive(Debug, FromRow)]
+struct DueTask {
+    id: Uuid,
+    organization_id: String,
+    task: String,
+    cron_expression: String,
+    next_run_at: Option<DateTime<Utc>>,
+}
+
+/// scheduled_tasks を監視し、実行時刻になったタスクを job として登録する
+///
+/// 複数インスタンスで動かした場合は advisory lock を取れた1台だけが登録する
+/// （リーダーが落ちると他のインスタンスが引き継ぐ。切り替わり時も next_run_at の楽観ロックで1回だけ）。
+/// 停止中に過ぎた回はまとめて1回だけ実行する。
+pub struct Scheduler {
+    pool: PgPool,
+    tasks: Vec<ScheduledTaskDef>,
+}
+
+impl Scheduler {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn task(mut self, task: ScheduledTaskDef) -> Self {
+        self.tasks.push(task);
+        self
+    }
+
+    pub fn tasks(&self) -> Vec<ScheduledTaskDef> {
+        self.tasks.clone()
+    }
+
+    pub fn spawn(self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        tracing::info!(
+            "Starting scheduler for: {:?}",
+            self.tasks.iter().map(|t| t.name).collect::<Vec<_>>()
+        );
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            let mut leader: Option<AdvisoryLock> = None;
+            loop {
+                interval.tick().await;
+                if !self.ensure_leader(&mut leader).await {
+                    continue;
+                }
+                self.tick().await;
+            }
+        });
+    }
+
+    /// リーダーのロックを保持しているか確認し、なければ取得を試みる
+    async fn ensure_leader(&self, leader: &mut Option<AdvisoryLock>) -> bool {
+        if let Some(lock) = leader.as_mut() {
+            if lock.is_held().await {
+                return true;
+            }
+            tracing::warn!("Lost scheduler leadership (lock connection closed)");
+            *leader = None;
+        }
+
+        match AdvisoryLock::try_acquire(&self.pool, LEADER_LOCK_KEY).await {
+            Ok(Some(lock)) => {
+                tracing::info!("Acquired scheduler leadership");
+                *leader = Some(lock);
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                tracing::warn!("Failed to acquire scheduler leadership: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn tick(&self) {
+        let names: Vec<&str> = self.tasks.iter().map(|t| t.name).collect();
+        let due = sqlx::query_as::<_, DueTask>("SELECT * FROM due_scheduled_tasks($1)")
+            .bind(&names)
+            .fetch_all(&self.pool)
+            .await;
+
+        match due {
+            Ok(due) => {
+                for task in due {
+                    if let Err(e) = self.fire(&task).await {
+                        tracing::warn!(
+                            "Failed to schedule {} for organization {}: {}",
+                            task.task, task.organization_id, e
+                        );
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load due scheduled tasks: {}", e),
+        }
+    }
+
+    /// next_run_at を進め、（初回計算でなければ）job を登録
+    async fn fire(&self, due: &DueTask) -> Result<(), sqlx::Error> {
+        let next_run_at = match next_run_after(&due.cron_expression, Utc::now()) {
+            Ok(next) => next,
+            Err(e) => {
+                tracing::warn!("Scheduled task {} ({}): {}", due.task, due.id, e);
+                return Ok(());
+            }
+        };
+
+        let mut tx = self.pool.begin_scoped(&due.organization_id).await?;
+
+        let advanced = sqlx::query(
+            r#"
+            UPDATE scheduled_tasks SET next_run_at = $2, updated_at = NOW()
+            WHERE id = $1 AND enabled AND next_run_at IS NOT DISTINCT FROM $3
+            "#,
+        )
+        .bind(due.id)
+        .bind(next_run_at)
+        .bind(due.next_run_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // 他のインスタンスが処理済み / 登録直後で次回時刻を計算しただけ
+        if advanced == 0 || due.next_run_at.is_none() {
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        let job = NewJob::new(due.task_name(&self.tasks), serde_json::json!({ "scheduled_at": due.next_run_at }))
+            .dedupe_key(SCHEDULED_DEDUPE_KEY);
+        match enqueue(&mut tx, &due.organization_id, job).await? {
+            Some(job_id) => {
+                sqlx::query("UPDATE scheduled_tasks SET last_run_at = NOW(), last_job_id = $2 WHERE id = $1")
+                    .bind(due.id)
+                    .bind(job_id)
+                    .execute(&mut *tx)
+                    .await?;
+                tracing::info!("Scheduled {} for organization {} (job {})", due.task, due.organization_id, job_id);
+            }
+            None => tracing::info!(
+                "Skipped {} for organization {}: previous run still in progress",
+                due.task, due.organization_id
+            ),
+        }
+
+        tx.commit().await
+    }
+}
+
+impl DueTask {
+    /// NewJob は &'static str の kind を取るため登録済み定義から引く
+    fn task_name(&self, tasks: &[ScheduledTaskDef]) -> &'static str {
+        tasks
+            .iter()
+            .find(|t| t.name == self.task)
+            .map(|t| t.name)
+            .expect("due_scheduled_tasks only returns registered tasks")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_run_after_uses_jst() {
+        // 毎日 09:00 JST = 00:00 UTC
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 1, 0, 0).unwrap();
+        let next = next_run_after("0 9 * * *", after).unwrap().unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_parse_cron_rejects_invalid() {
+        assert!(parse_cron("0 3 * * *").is_ok());
+        assert!(parse_cron("0 0 3 * * *").is_ok());
+        assert!(parse_cron("* *").is_err());
+        assert!(parse_cron("61 * * * *").is_err());
+    }
+}
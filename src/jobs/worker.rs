@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::{FromRow, PgPool};
+use tokio::sync::Semaphore;
+
+use super::Job;
+use crate::db::set_current_organization;
+use crate::outbox::{Outbox, OutboxEvent, JOB_DEAD_LETTERED};
+use crate::shutdown;
+
+/// 実行可能な job がないときのポーリング間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// ack されない running job を再取得するまでの時間（ワーカー停止・クラッシュ対策）
+const LOCK_TIMEOUT: &str = "10 minutes";
+/// 再試行間隔（30秒 → 1分 → 2分 … 最大1時間）
+const RETRY_BASE_SECS: u64 = 30;
+const RETRY_MAX_SECS: u64 = 3600;
+/// キューの滞留状況をログに出す間隔
+const MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+/// 実行待ちがこの件数・待ち時間を超えたら warn
+const BACKLOG_WARN_DEPTH: i64 = 100;
+const BACKLOG_WARN_WAIT_SECS: f64 = 300.0;
+
+/// job の種類ごとの処理
+#[tonic::async_trait]
+pub trait JobHandler: Send + Sync {
+    /// Err を返すとバックオフ後に再実行される
+    async fn run(&self, job: &Job) -> anyhow::Result<()>;
+}
+
+/// attempts 回目の失敗後の待ち時間
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::from_secs(RETRY_BASE_SECS.saturating_mul(1 << exponent).min(RETRY_MAX_SECS))
+}
+
+/// kind ごとのキュー滞留状況（job_queue_stats）
+#[derive(Debug, Clone, FromRow)]
+pub struct QueueDepth {
+    pub kind: String,
+    /// 実行可能（run_at 到達済み）で待っている件数
+    pub ready: i64,
+    /// 再試行・バックプレッシャーで run_at が先の件数
+    pub delayed: i64,
+    pub running: i64,
+    /// 最も古い実行可能 job の待ち時間（秒）
+    pub oldest_ready_secs: Option<f64>,
+}
+
+/// jobs テーブルをポーリングして登録済みハンドラで実行するワーカー群
+///
+/// 同時実行数は concurrency 個のワーカーで上限を決め、重い kind は limit でさらに絞る
+/// （上限に達した kind は claim しないので、他の kind の実行は止まらない）。
+pub struct JobWorkerPool {
+    pool: PgPool,
+    name: &'static str,
+    concurrency: usize,
+    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+    limits: HashMap<&'static str, Arc<Semaphore>>,
+    outbox: Outbox,
+}
+
+impl JobWorkerPool {
+    pub fn new(pool: PgPool, concurrency: usize) -> Self {
+        Self {
+            pool,
+            name: "jobs",
+            concurrency: concurrency.max(1),
+            handlers: HashMap::new(),
+            limits: HashMap::new(),
+            outbox: Outbox::default(),
+        }
+    }
+
+    /// ログ・ワーカー ID に使う名前
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// kind の同時実行数の上限（インスタンスごと）
+    pub fn limit(mut self, kind: &'static str, max_concurrent: usize) -> Self {
+        self.limits.insert(kind, Arc::new(Semaphore::new(max_concurrent.max(1))));
+        self
+    }
+
+    /// dead_letter になった job を管理者に通知する outbox
+    pub fn outbox(mut self, outbox: Outbox) -> Self {
+        self.outbox = outbox;
+        self
+    }
+
+    pub fn register(mut self, kind: &'static str, handler: impl JobHandler + 'static) -> Self {
+        self.handlers.insert(kind, Arc::new(handler));
+        self
+    }
+
+    /// concurrency 個のワーカータスクと滞留状況のモニターを起動
+    ///
+    /// ワーカーは drain が始まると新しく claim せず、実行中の job を終えてから止まる（終了時に待つ）。
+    pub fn spawn(self) {
+        if self.handlers.is_empty() {
+            return;
+        }
+        let instance = uuid::Uuid::new_v4().simple().to_string();
+        let workers = self.concurrency;
+        let pool = Arc::new(self);
+        tracing::info!(
+            "Starting {} {} workers for: {:?} (limits: {:?})",
+            workers,
+            pool.name,
+            pool.handlers.keys().collect::<Vec<_>>(),
+            pool.limits
+                .iter()
+                .map(|(kind, s)| (*kind, s.available_permits()))
+                .collect::<HashMap<_, _>>()
+        );
+
+        for i in 0..workers {
+            let pool = pool.clone();
+            let worker_id = format!("{}-{}-{}", pool.name, &instance[..8], i);
+            shutdown::spawn(async move { pool.worker_loop(worker_id).await });
+        }
+        tokio::spawn(async move { pool.monitor_loop().await });
+    }
+
+    /// 全組織の kind ごとの滞留状況
+    pub async fn queue_depth(pool: &PgPool, kinds: &[&str]) -> Result<Vec<QueueDepth>, sqlx::Error> {
+        sqlx::query_as::<_, QueueDepth>("SELECT * FROM job_queue_stats($1)")
+            .bind(kinds)
+            .fetch_all(pool)
+            .await
+    }
+
+    async fn monitor_loop(&self) {
+        let kinds: Vec<&str> = self.handlers.keys().copied().collect();
+        let mut interval = tokio::time::interval(MONITOR_INTERVAL);
+        loop {
+            interval.tick().await;
+            let depths = match Self::queue_depth(&self.pool, &kinds).await {
+                Ok(depths) => depths,
+                Err(e) => {
+                    tracing::warn!("Failed to load {} queue depth: {}", self.name, e);
+                    continue;
+                }
+            };
+            for d in depths {
+                let wait = d.oldest_ready_secs.unwrap_or_default();
+                if d.ready >= BACKLOG_WARN_DEPTH || wait >= BACKLOG_WARN_WAIT_SECS {
+                    tracing::warn!(
+                        "{} queue backlog: kind={} ready={} delayed={} running={} oldest_wait={:.0}s",
+                        self.name, d.kind, d.ready, d.delayed, d.running, wait
+                    );
+                } else {
+                    tracing::debug!(
+                        "{} queue: kind={} ready={} delayed={} running={} oldest_wait={:.0}s",
+                        self.name, d.kind, d.ready, d.delayed, d.running, wait
+                    );
+                }
+            }
+        }
+    }
+
+    /// 同時実行数の上限に達していない kind
+    fn claimable_kinds(&self) -> Vec<&'static str> {
+        self.handlers
+            .keys()
+            .copied()
+            .filter(|kind| !matches!(self.limits.get(kind), Some(s) if s.available_permits() == 0))
+            .collect()
+    }
+
+    async fn worker_loop(&self, worker_id: String) {
+        while !shutdown::is_draining() {
+            let kinds = self.claimable_kinds();
+            if kinds.is_empty() {
+                shutdown::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            let claimed = sqlx::query_as::<_, Job>(
+                "SELECT * FROM claim_job($1, $2, $3::interval)",
+            )
+            .bind(&worker_id)
+            .bind(&kinds)
+            .bind(LOCK_TIMEOUT)
+            .fetch_optional(&self.pool)
+            .await;
+
+            match claimed {
+                Ok(Some(job)) => {
+                    // claimable_kinds から claim までの間に他のワーカーが枠を使った場合は空くまで待つ
+                    let _permit = match self.limits.get(job.kind.as_str()) {
+                        Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+                        None => None,
+                    };
+                    self.execute(job, &worker_id).await
+                }
+                Ok(None) => shutdown::sleep(POLL_INTERVAL).await,
+                Err(e) => {
+                    tracing::warn!("Failed to claim job: {}", e);
+                    shutdown::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+        tracing::debug!("Job worker {} stopped", worker_id);
+    }
+
+    async fn execute(&self, job: Job, worker_id: &str) {
+        let Some(handler) = self.handlers.get(job.kind.as_str()).cloned() else {
+            return;
+        };
+        tracing::debug!("Running job {} ({}) attempt {}/{}", job.id, job.kind, job.attempts, job.max_attempts);
+
+        // パニックも失敗として扱う
+        let task_job = job.clone();
+        let result = tokio::spawn(async move { handler.run(&task_job).await })
+            .await
+            .unwrap_or_else(|e| Err(anyhow::anyhow!("Job panicked: {}", e)));
+
+        match result {
+            Ok(()) => {
+                let acked = sqlx::query_scalar::<_, bool>("SELECT complete_job($1, $2)")
+                    .bind(job.id)
+                    .bind(worker_id)
+                    .fetch_one(&self.pool)
+                    .await;
+                match acked {
+                    Ok(true) => tracing::debug!("Job {} ({}) succeeded", job.id, job.kind),
+                    Ok(false) => tracing::warn!("Job {} ({}) was cancelled or reclaimed before ack", job.id, job.kind),
+                    Err(e) => tracing::error!("Failed to ack job {}: {}", job.id, e),
+                }
+            }
+            Err(err) => {
+                let retry_at = chrono::Utc::now()
+                    + chrono::Duration::from_std(retry_delay(job.attempts)).unwrap_or_default();
+                match self.record_failure(&job, worker_id, &format!("{:#}", err), retry_at).await {
+                    Ok(Some(status)) if status == "dead_letter" => tracing::error!(
+                        "Job {} ({}) moved to dead letter after {} attempts: {:#}",
+                        job.id, job.kind, job.attempts, err
+                    ),
+                    Ok(_) => tracing::warn!(
+                        "Job {} ({}) failed (attempt {}/{}), retrying at {}: {:#}",
+                        job.id, job.kind, job.attempts, job.max_attempts, retry_at, err
+                    ),
+                    Err(e) => tracing::error!("Failed to record failure of job {}: {}", job.id, e),
+                }
+            }
+        }
+    }
+
+    /// fail_job で再試行 / dead_letter にし、dead_letter なら同じトランザクションで管理者通知を outbox に書く
+    async fn record_failure(
+        &self,
+        job: &Job,
+        worker_id: &str,
+        error: &str,
+        retry_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let status = sqlx::query_scalar::<_, Option<String>>("SELECT fail_job($1, $2, $3, $4)")
+            .bind(job.id)
+            .bind(worker_id)
+            .bind(error)
+            .bind(retry_at)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if status.as_deref() == Some("dead_letter") {
+            let event = OutboxEvent::new(
+                JOB_DEAD_LETTERED,
+                serde_json::json!({ "job_id": job.id, "kind": job.kind, "attempts": job.attempts, "error": error }),
+            )
+            .message(format!(
+                "【ジョブ失敗】\n{} (#{})\n{}回失敗したため停止しました\n{}",
+                job.kind, job.id, job.attempts, error
+            ));
+            set_current_organization(&mut tx, &job.organization_id).await?;
+            self.outbox.write(&mut tx, &job.organization_id, &event).await?;
+        }
+
+        tx.commit().await?;
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(4), Duration::from_secs(240));
+        assert_eq!(retry_delay(20), Duration::from_secs(3600));
+    }
+}
@@ -1,14 +1,34 @@
+pub mod cli;
 pub mod config;
+pub mod cost;
+pub mod crypto;
 pub mod db;
+pub mod dtako_api;
 pub mod error;
+pub mod events;
+pub mod gateway;
+pub mod geocoding;
+pub mod geofencing;
 pub mod google_auth;
 pub mod http_client;
+pub mod ingest;
+pub mod jobs;
 pub mod middleware;
 pub mod models;
+pub mod notifications;
+pub mod outbox;
 pub mod proto;
+pub mod reports;
 pub mod services;
+pub mod shutdown;
 pub mod storage;
+pub mod telemetry;
+pub mod text_encoding;
+pub mod usage;
+pub mod warehouse;
+pub mod weather;
+pub mod webhooks;
 
 pub use config::Config;
-pub use error::{AppError, AppResult};
+pub use error::{AppError, AppResult, ResultExt};
 pub use http_client::HttpClient;
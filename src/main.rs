@@ -1,11 +1,37 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
+use rust_logi::cli::{self, Cli, Command};
 use rust_logi::config::Config;
-use rust_logi::db::create_pool;
+use rust_logi::crypto::SecretBox;
+use rust_logi::db::{create_pool, KpiViewRefresher};
+use rust_logi::error::reporting::ErrorReporter;
+use rust_logi::events::EventBus;
+use rust_logi::gateway;
+use rust_logi::geocoding::{
+    geocoding_provider, GeocodeBackfillJobHandler, Geocoder, GEOCODE_BACKFILL_JOB, GEOCODE_BACKFILL_TASK,
+};
+use rust_logi::dtako_api::{DtakoApi, DtakoApiClient};
 use rust_logi::http_client::HttpClient;
 use rust_logi::middleware::auth::AuthLayer;
+use rust_logi::middleware::authorization::AuthorizationLayer;
+use rust_logi::middleware::api_usage::{ApiUsage, ApiUsageLayer};
+use rust_logi::middleware::catch_panic::CatchPanicLayer;
+use rust_logi::middleware::trace_context::TraceContextLayer;
+use rust_logi::middleware::cors::{build_cors_layer, OrganizationOrigins};
+use rust_logi::middleware::drain::DrainLayer;
 use rust_logi::middleware::grpc_web_fix::GrpcWebTrailerFixLayer;
+use rust_logi::middleware::localized_error::LocalizedErrorLayer;
+use rust_logi::notifications::{
+    sms_provider, ChatWebhookChannel, DigestFrequency, DigestJobHandler, EmailChannel,
+    LineWorksChannel, NotificationFeedListener, NotificationJobHandler, Notifier, SmsChannel,
+    DAILY_DIGEST_JOB, DAILY_DIGEST_TASK, DISCORD_CHANNEL, EMAIL_CHANNEL, LINEWORKS_CHANNEL,
+    NOTIFICATION_DELIVER_JOB, SLACK_CHANNEL, SMS_CHANNEL, WEEKLY_DIGEST_JOB, WEEKLY_DIGEST_TASK,
+};
+use rust_logi::outbox::{LineWorksTarget, Outbox, OutboxWorker, LINEWORKS_TARGET};
+use rust_logi::webhooks::{WebhookDeliveryJobHandler, WebhookFanout, WEBHOOK_DELIVER_JOB, WEBHOOK_TARGET};
+use rust_logi::proto;
 use rust_logi::proto::cam_files::cam_file_exe_stage_service_server::CamFileExeStageServiceServer;
 use rust_logi::proto::cam_files::cam_files_service_server::CamFilesServiceServer;
 use rust_logi::proto::car_inspection::car_inspection_files_service_server::CarInspectionFilesServiceServer;
@@ -23,8 +49,49 @@ use rust_logi::proto::bot_config::bot_config_service_server::BotConfigServiceSer
 use rust_logi::proto::access_request::access_request_service_server::AccessRequestServiceServer;
 use rust_logi::proto::items::items_service_server::ItemsServiceServer;
 use rust_logi::proto::car_inspection::nfc_tag_service_server::NfcTagServiceServer;
-use rust_logi::services::cam_files_service::CamFileExeStageServiceImpl;
+use rust_logi::proto::v2::files::files_service_server::FilesServiceServer as FilesV2ServiceServer;
+use rust_logi::proto::scheduler::scheduler_service_server::SchedulerServiceServer;
+use rust_logi::proto::jobs::jobs_service_server::JobsServiceServer;
+use rust_logi::proto::notifications::notification_feed_service_server::NotificationFeedServiceServer;
+use rust_logi::proto::notifications::notification_service_server::NotificationServiceServer;
+use rust_logi::proto::webhooks::webhook_service_server::WebhookServiceServer;
+use rust_logi::proto::etc::etc_service_server::EtcServiceServer;
+use rust_logi::proto::fuel::fuel_service_server::FuelServiceServer;
+use rust_logi::proto::ichiban_cars::ichiban_cars_service_server::IchibanCarsServiceServer;
+use rust_logi::proto::reports::report_service_server::ReportServiceServer;
+use rust_logi::proto::admin::admin_service_server::AdminServiceServer;
+use rust_logi::proto::api_keys::api_keys_service_server::ApiKeysServiceServer;
+use rust_logi::proto::search::search_service_server::SearchServiceServer;
+use rust_logi::proto::geofences::geofences_service_server::GeofencesServiceServer;
+use rust_logi::jobs::{JobWorkerPool, Scheduler, StartupRecovery};
+use rust_logi::reports::{
+    ReportJobHandler, ReportKind, ScheduledReportJobHandler, REPORT_GENERATE_JOB,
+    SCHEDULED_DRIVER_HOURS_JOB, SCHEDULED_DRIVER_HOURS_TASK, SCHEDULED_INSPECTION_COMPLIANCE_JOB,
+    SCHEDULED_INSPECTION_COMPLIANCE_TASK, SCHEDULED_MONTHLY_COMPLIANCE_JOB,
+    SCHEDULED_MONTHLY_COMPLIANCE_TASK, SCHEDULED_VEHICLE_UTILIZATION_JOB,
+    SCHEDULED_VEHICLE_UTILIZATION_TASK,
+};
+use rust_logi::services::access_request_service::{
+    AccessRequestJobHandler, ACCESS_REQUEST_MAINTENANCE_JOB, ACCESS_REQUEST_MAINTENANCE_TASK,
+};
+use rust_logi::services::cam_files_service::{
+    CamFileExeStageServiceImpl, CamSyncJobHandler, FlickrUploadJobHandler, CAM_SYNC_JOB,
+    CAM_SYNC_TASK, FLICKR_UPLOAD_JOB,
+};
+use rust_logi::services::car_inspection_service::{
+    ExpiryNotifyJobHandler, EXPIRY_NOTIFY_JOB, EXPIRY_NOTIFY_TASK,
+};
+use rust_logi::services::dvr_notifications_service::{Mp4DownloadJobHandler, MP4_DOWNLOAD_JOB};
+use rust_logi::services::file_auto_parser::{AutoParseJobHandler, AUTO_PARSE_JOB};
+use rust_logi::services::thumbnails::{ThumbnailJobHandler, THUMBNAIL_JOB};
+use rust_logi::services::files_service::{
+    FilePurgeJobHandler, StorageDemotionJobHandler, StoragePromotionJobHandler, FILE_PURGE_JOB,
+    FILE_PURGE_TASK, STORAGE_DEMOTION_JOB, STORAGE_DEMOTION_TASK, STORAGE_PROMOTION_JOB,
+};
 use rust_logi::services::flickr_service::FlickrConfig;
+use rust_logi::services::health_service::{Dependency, HealthChecker, HealthRegistry};
+use rust_logi::services::home_car_cache::HomeCarCache;
+use rust_logi::services::v2::FilesV2ServiceImpl;
 use rust_logi::services::{
     CamFilesServiceImpl, CarInspectionFilesServiceImpl, CarInspectionServiceImpl,
     FileAutoParser, FilesServiceImpl, HealthServiceImpl, DtakologsServiceImpl, FlickrServiceImpl,
@@ -35,32 +102,114 @@ use rust_logi::services::{
     AccessRequestServiceImpl,
     ItemsServiceImpl,
     NfcTagServiceImpl,
+    SchedulerServiceImpl,
+    JobsServiceImpl,
+    NotificationServiceImpl,
+    NotificationFeedServiceImpl,
+    WebhookServiceImpl,
+    EtcServiceImpl,
+    FuelServiceImpl,
+    IchibanCarsServiceImpl,
+    ReportServiceImpl,
+    AdminServiceImpl,
+    ApiKeysServiceImpl,
+    SearchServiceImpl,
+    GeofencesServiceImpl,
 };
-use rust_logi::storage::{StorageBackend, GcsBackend, R2Backend};
+use rust_logi::storage::{self, StorageBackend};
+use rust_logi::shutdown;
+use rust_logi::telemetry::Telemetry;
+use rust_logi::warehouse::{
+    self, WarehouseExportJobHandler, WarehouseExporter, WAREHOUSE_EXPORT_JOB, WAREHOUSE_EXPORT_TASK,
+};
+use rust_logi::weather::{DvrWeatherJobHandler, WeatherService, DVR_WEATHER_JOB};
+use rust_logi::AppError;
 
+use clap::Parser;
+use tonic::service::Routes;
 use tonic::transport::Server;
 use tonic_reflection::server::Builder as ReflectionBuilder;
-use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
-// Include file descriptor for gRPC reflection
-pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("logi_descriptor");
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
+    let command = Cli::parse().command.unwrap_or(Command::Serve);
+
+    // Initialize tracing（OTLP exporter は OTEL_EXPORTER_OTLP_ENDPOINT があるときだけ）
+    let telemetry = Telemetry::from_env()?;
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "rust_logi=debug,tower_http=debug".into()),
+            tracing_subscriber::fmt::layer().with_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| "rust_logi=debug,tower_http=debug".into()),
+            ),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(telemetry.as_ref().map(|telemetry| telemetry.layer()))
         .init();
 
     // Load configuration
-    let config = Config::from_env().expect("Failed to load configuration");
+    let config = Config::from_env().map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    let result = run(command, config).await;
 
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    result
+}
+
+/// サブコマンドを実行
+async fn run(command: Command, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Serve => serve(config).await,
+        Command::Migrate => {
+            let pool = create_pool(&config.database_url).await?;
+            cli::migrate(&pool).await?;
+            Ok(())
+        }
+        Command::CheckConfig => {
+            cli::check_config(&config).await?;
+            Ok(())
+        }
+        Command::CreateAdminUser(args) => {
+            let pool = create_pool(&config.database_url).await?;
+            cli::create_admin_user(&pool, &args).await?;
+            Ok(())
+        }
+        Command::ReprocessFiles(args) => {
+            let pool = create_pool(&config.database_url).await?;
+            let storage = create_storage(&config).await;
+            cli::reprocess_files(&pool, storage, &args).await?;
+            Ok(())
+        }
+        Command::StorageMigrate(args) => {
+            let pool = create_pool(&config.database_url).await?;
+            cli::storage_migrate(&config, &pool, &args).await?;
+            Ok(())
+        }
+        Command::EncryptSecrets(args) => {
+            let pool = create_pool(&config.database_url).await?;
+            cli::encrypt_secrets(&config, &pool, &args).await?;
+            Ok(())
+        }
+    }
+}
+
+/// STORAGE_BACKEND に応じたストレージを生成（初期化失敗時は DB blob 保存にフォールバック）
+async fn create_storage(config: &Config) -> Option<Arc<dyn StorageBackend>> {
+    match storage::create_backend(config, config.storage_backend.as_deref()).await {
+        Ok(storage) => storage,
+        Err(AppError::InvalidInput(msg)) => panic!("{}", msg),
+        Err(e) => {
+            tracing::error!("Failed to create storage backend: {}", e.report());
+            None
+        }
+    }
+}
+
+async fn serve(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Starting rust-logi gRPC server...");
+    config.log_effective();
     tracing::info!("Connecting to database...");
 
     // Create database pool
@@ -68,126 +217,434 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Database connection established");
 
     // Create storage backend based on STORAGE_BACKEND env var
-    let storage: Option<Arc<dyn StorageBackend>> = match config.storage_backend.as_deref() {
-        Some("r2") => {
-            let bucket = config.r2_bucket.as_ref()
-                .expect("R2_BUCKET required when STORAGE_BACKEND=r2");
-            let account_id = config.r2_account_id.as_ref()
-                .expect("R2_ACCOUNT_ID required when STORAGE_BACKEND=r2");
-            let access_key = config.r2_access_key.as_ref()
-                .expect("R2_ACCESS_KEY required when STORAGE_BACKEND=r2");
-            let secret_key = config.r2_secret_key.as_ref()
-                .expect("R2_SECRET_KEY required when STORAGE_BACKEND=r2");
-
-            tracing::info!("R2 storage enabled: bucket={}", bucket);
-            match R2Backend::new(
-                bucket.clone(),
-                account_id.clone(),
-                access_key.clone(),
-                secret_key.clone(),
-            ) {
-                Ok(backend) => Some(Arc::new(backend)),
-                Err(e) => {
-                    tracing::error!("Failed to create R2 backend: {}", e);
-                    None
-                }
-            }
-        }
-        Some("gcs") | None => {
-            if let Some(bucket) = &config.gcs_bucket {
-                tracing::info!("GCS storage enabled: bucket={}", bucket);
-                match GcsBackend::new(bucket.clone()).await {
-                    Ok(backend) => Some(Arc::new(backend)),
-                    Err(e) => {
-                        tracing::error!("Failed to create GCS backend: {}", e);
-                        None
-                    }
-                }
-            } else {
-                tracing::info!("No storage backend configured, using database blob storage");
-                None
-            }
-        }
-        Some(other) => {
-            panic!("Unknown STORAGE_BACKEND: '{}'. Expected 'gcs' or 'r2'", other);
-        }
-    };
+    let storage = create_storage(&config).await;
 
     // Create HTTP client for external API calls
-    let http_client = Arc::new(HttpClient::new());
+    let http_client = Arc::new(HttpClient::from_config(&config.http));
+
+    // 想定外の INTERNAL エラー / panic の報告（SENTRY_DSN）
+    if let Some(reporting) = &config.error_reporting {
+        ErrorReporter::install(http_client.clone(), reporting);
+    }
+
+    // 保存するシークレット（Flickr / OAuth トークン、SSO クライアントシークレット）の列暗号化
+    let secrets = Arc::new(
+        SecretBox::from_config(config.secrets.as_ref(), &config.jwt_secret, &http_client)
+            .await
+            .expect("Failed to load secrets keys"),
+    );
+
+    // Entity change events (Watch* RPCs)
+    let events = EventBus::new();
+
+    // Outbound notifications: written to the outbox in the business transaction, delivered in order
+    // Tenant webhooks are always a target: events fan out to each org's subscribed endpoints
+    let mut outbox_worker = OutboxWorker::new(pool.clone())
+        .target(WEBHOOK_TARGET, WebhookFanout::new(pool.clone()));
+    if let Some(bot_url) = &config.dvr_lineworks_bot_url {
+        outbox_worker = outbox_worker.target(
+            LINEWORKS_TARGET,
+            LineWorksTarget::new(http_client.clone(), bot_url.clone()),
+        );
+    }
+    let outbox = Outbox::new(outbox_worker.target_names());
+    outbox_worker.spawn();
+
+    // Per-recipient notifications (email, LINE WORKS, Slack/Discord): rendered from templates, delivered by the job queue
+    // LINE WORKS and webhooks use per-organization settings, so they are always available
+    let mut notification_handler = NotificationJobHandler::new(pool.clone())
+        .channel(
+            LINEWORKS_CHANNEL,
+            LineWorksChannel::new(pool.clone(), http_client.clone(), config.jwt_secret.clone()),
+        )
+        .channel(
+            SLACK_CHANNEL,
+            ChatWebhookChannel::new(pool.clone(), http_client.clone(), config.jwt_secret.clone()),
+        )
+        .channel(
+            DISCORD_CHANNEL,
+            ChatWebhookChannel::new(pool.clone(), http_client.clone(), config.jwt_secret.clone()),
+        );
+    if let Some(email) = &config.email {
+        match EmailChannel::new(email, http_client.clone()) {
+            Ok(email) => notification_handler = notification_handler.channel(EMAIL_CHANNEL, email),
+            Err(e) => tracing::warn!("Email notifications disabled: {:#}", e),
+        }
+    }
+    if let Some(sms) = &config.sms {
+        notification_handler = notification_handler.channel(
+            SMS_CHANNEL,
+            SmsChannel::new(pool.clone(), sms_provider(sms, http_client.clone())),
+        );
+    }
+    let notifier = Notifier::new(notification_handler.channel_names());
+    // In-app notifications (bell icon): committed rows are relayed to WatchNotifications via LISTEN/NOTIFY
+    NotificationFeedListener::spawn(pool.clone(), events.clone());
 
     // Create services
-    let file_auto_parser = Arc::new(FileAutoParser::new(pool.clone()));
-    let files_service = FilesServiceImpl::new(pool.clone(), storage.clone(), file_auto_parser);
+    let files_service = Arc::new(FilesServiceImpl::new(
+        pool.clone(),
+        storage.clone(),
+        events.clone(),
+        config.storage_quota_bytes,
+    ));
+    // v2 shares the v1 implementation (logi.v2.files)
+    let files_v2_service = FilesV2ServiceImpl::new(files_service.clone());
+    let dtako_api: Arc<dyn DtakoApi> = Arc::new(
+        DtakoApiClient::new(
+            http_client.clone(),
+            config.dtako_api_url.clone(),
+            config.dtako_api_token.clone(),
+        )
+        .with_cache_ttl(Duration::from_secs(config.dtako_home_cars_cache_ttl_secs)),
+    );
     let car_inspection_service = CarInspectionServiceImpl::new(
         pool.clone(),
-        http_client.clone(),
-        config.dtako_api_url.clone(),
+        storage.clone(),
+        dtako_api.clone(),
+        HomeCarCache::new(config.dtako_home_cars_max_stale_secs),
+        events.clone(),
+        outbox.clone(),
+    );
+    let car_inspection_files_service = CarInspectionFilesServiceImpl::new(
+        pool.clone(),
+        storage.clone(),
+        events.clone(),
+        outbox.clone(),
     );
-    let car_inspection_files_service = CarInspectionFilesServiceImpl::new(pool.clone());
     let cam_files_service = CamFilesServiceImpl::new(
         pool.clone(),
+        http_client.clone(),
         config.cam_config.clone(),
         FlickrConfig::from_env(),
+        outbox.clone(),
+        storage.clone(),
     );
     let cam_file_exe_stage_service = CamFileExeStageServiceImpl::new(pool.clone());
-    let health_service = HealthServiceImpl::new();
-    let dtakologs_service = DtakologsServiceImpl::new(pool.clone());
-    let flickr_service = FlickrServiceImpl::new(pool.clone());
-    let dvr_notifications_service = DvrNotificationsServiceImpl::new(
+    let health_registry = HealthRegistry::new();
+    let health_service = HealthServiceImpl::new(health_registry.clone());
+    // Reverse geocoding (GEOCODING_PROVIDER): on-demand lookups + address_disp_c backfill
+    let geocoder = config.geocoding.as_ref().map(|geocoding| {
+        Arc::new(Geocoder::new(pool.clone(), geocoding_provider(geocoding, http_client.clone())))
+    });
+    let dtakologs_service = DtakologsServiceImpl::new(
         pool.clone(),
-        config.clone(),
-        http_client.clone(),
+        geocoder.clone(),
         storage.clone(),
+        events.clone(),
+        outbox.clone(),
     );
+    let flickr_service = FlickrServiceImpl::new(pool.clone(), http_client.clone(), secrets.clone());
+    // gRPC と取り込みルート（/ingest/dvr）で共有する
+    let dvr_notifications_service = Arc::new(DvrNotificationsServiceImpl::new(
+        pool.clone(),
+        config.clone(),
+        storage.clone(),
+        outbox.clone(),
+        notifier.clone(),
+    ));
     let auth_service = AuthServiceImpl::new(
         pool.clone(),
         config.jwt_secret.clone(),
         config.google_client_ids.clone(),
+        http_client.clone(),
+        secrets.clone(),
+        notifier.clone(),
+        config.app_base_url.clone(),
+    );
+    let organization_service = OrganizationServiceImpl::new(pool.clone(), config.storage_quota_bytes);
+    let member_service = MemberServiceImpl::new(
+        pool.clone(),
+        config.jwt_secret.clone(),
+        notifier.clone(),
+        config.app_base_url.clone(),
     );
-    let organization_service = OrganizationServiceImpl::new(pool.clone());
-    let member_service = MemberServiceImpl::new(pool.clone(), config.jwt_secret.clone());
     let sso_settings_service =
-        SsoSettingsServiceImpl::new(pool.clone(), config.jwt_secret.clone());
+        SsoSettingsServiceImpl::new(pool.clone(), secrets.clone(), http_client.clone());
     let bot_config_service =
-        BotConfigServiceImpl::new(pool.clone(), config.jwt_secret.clone());
+        BotConfigServiceImpl::new(pool.clone(), config.jwt_secret.clone(), http_client.clone());
     let access_request_service = AccessRequestServiceImpl::new(
         pool.clone(),
         config.clone(),
         http_client.clone(),
     );
 
-    let items_service = ItemsServiceImpl::new(pool.clone());
+    let items_service = ItemsServiceImpl::new(pool.clone(), events.clone());
     let nfc_tag_service = NfcTagServiceImpl::new(pool.clone());
+    let jobs_service = JobsServiceImpl::new(pool.clone());
+    let notification_service = NotificationServiceImpl::new(pool.clone(), config.jwt_secret.clone());
+    let notification_feed_service = NotificationFeedServiceImpl::new(pool.clone(), events.clone());
+    let webhook_service = WebhookServiceImpl::new(pool.clone(), config.jwt_secret.clone());
+    let etc_service = EtcServiceImpl::new(pool.clone());
+    let fuel_service = FuelServiceImpl::new(pool.clone());
+    let ichiban_cars_service = IchibanCarsServiceImpl::new(pool.clone());
+    let report_service = ReportServiceImpl::new(pool.clone());
+    let admin_service = AdminServiceImpl::new(pool.clone());
+    let api_keys_service = ApiKeysServiceImpl::new(pool.clone())?;
+    let search_service = SearchServiceImpl::new(pool.clone());
+    let geofences_service = GeofencesServiceImpl::new(pool.clone());
+
+    // Durable background jobs (auto-parse, Flickr uploads, DVR mp4 downloads, scheduled tasks)
+    // Heavy transfers are capped per kind so a burst can't occupy every worker
+    let file_auto_parser = Arc::new(FileAutoParser::new(pool.clone(), outbox.clone()));
+    let mut job_workers = JobWorkerPool::new(pool.clone(), config.job_workers)
+        .name("jobs")
+        .outbox(outbox.clone())
+        .limit(FLICKR_UPLOAD_JOB, 2)
+        .limit(MP4_DOWNLOAD_JOB, 2)
+        .limit(WEBHOOK_DELIVER_JOB, 4)
+        // 事業者のレート制限（Nominatim は 1 秒 1 件）があるので直列に
+        .limit(GEOCODE_BACKFILL_JOB, 1)
+        .limit(WAREHOUSE_EXPORT_JOB, 1)
+        .register(
+            AUTO_PARSE_JOB,
+            AutoParseJobHandler::new(pool.clone(), storage.clone(), file_auto_parser),
+        )
+        .register(
+            FLICKR_UPLOAD_JOB,
+            FlickrUploadJobHandler::new(
+                pool.clone(),
+                http_client.clone(),
+                config.cam_config.clone(),
+                FlickrConfig::from_env(),
+                secrets.clone(),
+                storage.clone(),
+            ),
+        )
+        .register(
+            STORAGE_PROMOTION_JOB,
+            StoragePromotionJobHandler::new(pool.clone(), storage.clone()),
+        )
+        .register(THUMBNAIL_JOB, ThumbnailJobHandler::new(pool.clone(), storage.clone()))
+        .register(
+            MP4_DOWNLOAD_JOB,
+            Mp4DownloadJobHandler::new(pool.clone(), storage.clone(), http_client.clone()),
+        )
+        .register(
+            CAM_SYNC_JOB,
+            CamSyncJobHandler::new(CamFilesServiceImpl::new(
+                pool.clone(),
+                http_client.clone(),
+                config.cam_config.clone(),
+                FlickrConfig::from_env(),
+                outbox.clone(),
+                storage.clone(),
+            )),
+        )
+        .register(
+            EXPIRY_NOTIFY_JOB,
+            ExpiryNotifyJobHandler::new(pool.clone(), outbox.clone(), notifier.clone()),
+        )
+        .register(
+            FILE_PURGE_JOB,
+            FilePurgeJobHandler::new(pool.clone(), storage.clone()),
+        )
+        .register(
+            DAILY_DIGEST_JOB,
+            DigestJobHandler::new(pool.clone(), notifier.clone(), DigestFrequency::Daily),
+        )
+        .register(
+            WEEKLY_DIGEST_JOB,
+            DigestJobHandler::new(pool.clone(), notifier.clone(), DigestFrequency::Weekly),
+        )
+        .register(
+            ACCESS_REQUEST_MAINTENANCE_JOB,
+            AccessRequestJobHandler::new(
+                pool.clone(),
+                notifier.clone(),
+                config.access_request_reminder_days,
+            ),
+        )
+        .register(NOTIFICATION_DELIVER_JOB, notification_handler)
+        .register(
+            WEBHOOK_DELIVER_JOB,
+            WebhookDeliveryJobHandler::new(
+                pool.clone(),
+                http_client.clone(),
+                config.jwt_secret.clone(),
+                notifier.clone(),
+            ),
+        )
+        .register(
+            REPORT_GENERATE_JOB,
+            ReportJobHandler::new(
+                pool.clone(),
+                storage.clone(),
+                notifier.clone(),
+                config.app_base_url.clone(),
+            ),
+        )
+        .register(
+            SCHEDULED_INSPECTION_COMPLIANCE_JOB,
+            ScheduledReportJobHandler::new(pool.clone(), ReportKind::InspectionCompliance),
+        )
+        .register(
+            SCHEDULED_DRIVER_HOURS_JOB,
+            ScheduledReportJobHandler::new(pool.clone(), ReportKind::DriverHours),
+        )
+        .register(
+            SCHEDULED_VEHICLE_UTILIZATION_JOB,
+            ScheduledReportJobHandler::new(pool.clone(), ReportKind::VehicleUtilization),
+        )
+        .register(
+            SCHEDULED_MONTHLY_COMPLIANCE_JOB,
+            ScheduledReportJobHandler::new(pool.clone(), ReportKind::MonthlyCompliance),
+        );
+    if let Some(weather) = &config.weather {
+        let weather = Arc::new(WeatherService::new(pool.clone(), http_client.clone(), weather.clone()));
+        job_workers = job_workers.register(DVR_WEATHER_JOB, DvrWeatherJobHandler::new(pool.clone(), weather));
+    }
+    if let Some(geocoder) = &geocoder {
+        job_workers =
+            job_workers.register(GEOCODE_BACKFILL_JOB, GeocodeBackfillJobHandler::new(geocoder.clone()));
+    }
+    if let Some(days) = config.storage_demotion_days {
+        job_workers = job_workers.register(
+            STORAGE_DEMOTION_JOB,
+            StorageDemotionJobHandler::new(
+                pool.clone(),
+                storage.clone(),
+                days,
+                config.storage_demotion_class.clone(),
+            ),
+        );
+    }
+    let warehouse_exporter = config.warehouse.as_ref().and_then(|warehouse| {
+        match warehouse::create_sink(warehouse, http_client.clone(), storage.clone()) {
+            Ok(sink) => {
+                tracing::info!("Warehouse export enabled: {}", sink.name());
+                Some(Arc::new(WarehouseExporter::new(pool.clone(), sink)))
+            }
+            Err(e) => {
+                tracing::error!("Warehouse export disabled: {}", e);
+                None
+            }
+        }
+    });
+    if let Some(exporter) = &warehouse_exporter {
+        job_workers = job_workers.register(WAREHOUSE_EXPORT_JOB, WarehouseExportJobHandler::new(exporter.clone()));
+    }
+    job_workers.spawn();
+
+    // Re-enqueue work interrupted before its job was registered (previous crash / deploy)
+    let mut recovery = StartupRecovery::new().auto_parse(AUTO_PARSE_JOB);
+    if config.cam_config.is_some() && FlickrConfig::from_env().is_some() {
+        recovery = recovery.flickr_upload(FLICKR_UPLOAD_JOB);
+    }
+    if storage.is_some() {
+        recovery = recovery
+            .mp4_download(MP4_DOWNLOAD_JOB)
+            .storage_promotion(STORAGE_PROMOTION_JOB);
+    }
+    recovery.spawn(pool.clone());
+
+    // Per-organization cron schedules (scheduled_tasks) -> jobs
+    let mut scheduler = Scheduler::new(pool.clone())
+        .task(CAM_SYNC_TASK)
+        .task(EXPIRY_NOTIFY_TASK)
+        .task(FILE_PURGE_TASK)
+        .task(DAILY_DIGEST_TASK)
+        .task(WEEKLY_DIGEST_TASK)
+        .task(ACCESS_REQUEST_MAINTENANCE_TASK)
+        .task(SCHEDULED_INSPECTION_COMPLIANCE_TASK)
+        .task(SCHEDULED_DRIVER_HOURS_TASK)
+        .task(SCHEDULED_VEHICLE_UTILIZATION_TASK)
+        .task(SCHEDULED_MONTHLY_COMPLIANCE_TASK);
+    if geocoder.is_some() {
+        scheduler = scheduler.task(GEOCODE_BACKFILL_TASK);
+    }
+    if warehouse_exporter.is_some() {
+        scheduler = scheduler.task(WAREHOUSE_EXPORT_TASK);
+    }
+    if storage.is_some() && config.storage_demotion_days.is_some() {
+        scheduler = scheduler.task(STORAGE_DEMOTION_TASK);
+    }
+    let scheduler_service = SchedulerServiceImpl::new(pool.clone(), scheduler.tasks());
+    scheduler.spawn();
+
+    // Per-service health (grpc.health.v1) based on dependencies
+    const DB: &[Dependency] = &[Dependency::Database];
+    const DB_STORAGE: &[Dependency] = &[Dependency::Database, Dependency::Storage];
+    let mut health_checker = HealthChecker::new(
+        pool.clone(),
+        health_registry,
+        config.cam_config.is_some(),
+        FlickrConfig::from_env().is_some(),
+    )
+    .storage(storage.clone());
+    if config.health_check_dtako_api {
+        health_checker = health_checker.dtako_api(dtako_api.clone());
+    }
+    health_checker
+    .service::<FilesServiceServer<FilesServiceImpl>>(DB_STORAGE)
+    .service::<FilesV2ServiceServer<FilesV2ServiceImpl>>(DB_STORAGE)
+    .service::<CarInspectionServiceServer<CarInspectionServiceImpl>>(&[Dependency::Database, Dependency::DtakoApi])
+    .service::<CarInspectionFilesServiceServer<CarInspectionFilesServiceImpl>>(DB_STORAGE)
+    .service::<CamFilesServiceServer<CamFilesServiceImpl>>(&[Dependency::Database, Dependency::CamConfig, Dependency::Storage])
+    .service::<CamFileExeStageServiceServer<CamFileExeStageServiceImpl>>(DB)
+    .service::<DtakologsServiceServer<DtakologsServiceImpl>>(DB)
+    .service::<FlickrServiceServer<FlickrServiceImpl>>(&[Dependency::Database, Dependency::Flickr])
+    .service::<DvrNotificationsServiceServer<DvrNotificationsServiceImpl>>(DB_STORAGE)
+    .service::<AuthServiceServer<AuthServiceImpl>>(DB)
+    .service::<OrganizationServiceServer<OrganizationServiceImpl>>(DB)
+    .service::<MemberServiceServer<MemberServiceImpl>>(DB)
+    .service::<SsoSettingsServiceServer<SsoSettingsServiceImpl>>(DB)
+    .service::<BotConfigServiceServer<BotConfigServiceImpl>>(DB)
+    .service::<AccessRequestServiceServer<AccessRequestServiceImpl>>(DB)
+    .service::<ItemsServiceServer<ItemsServiceImpl>>(DB)
+    .service::<NfcTagServiceServer<NfcTagServiceImpl>>(DB)
+    .service::<SchedulerServiceServer<SchedulerServiceImpl>>(DB)
+    .service::<JobsServiceServer<JobsServiceImpl>>(DB)
+    .service::<NotificationServiceServer<NotificationServiceImpl>>(DB)
+    .service::<NotificationFeedServiceServer<NotificationFeedServiceImpl>>(DB)
+    .service::<WebhookServiceServer<WebhookServiceImpl>>(DB)
+    .service::<EtcServiceServer<EtcServiceImpl>>(DB)
+    .service::<FuelServiceServer<FuelServiceImpl>>(DB)
+    .service::<IchibanCarsServiceServer<IchibanCarsServiceImpl>>(DB)
+    .service::<ReportServiceServer<ReportServiceImpl>>(DB_STORAGE)
+    .service::<AdminServiceServer<AdminServiceImpl>>(DB)
+    .service::<ApiKeysServiceServer<ApiKeysServiceImpl>>(DB)
+    .service::<SearchServiceServer<SearchServiceImpl>>(DB)
+    .service::<GeofencesServiceServer<GeofencesServiceImpl>>(DB)
+    .spawn()
+    .await;
 
     // Auth middleware layer
     let auth_layer = AuthLayer::new(pool.clone(), config.jwt_secret.clone());
 
-    // CORS layer for gRPC-Web
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_headers(Any)
-        .allow_methods(Any)
-        .expose_headers(Any);
+    // 組織ごとの API 呼び出し数（AdminService の集計用、1 分ごとに api_usage_hourly へ）
+    let api_usage = ApiUsage::new();
+    api_usage.spawn_flush(pool.clone());
+
+    // ダッシュボード用の materialized view を定期的に REFRESH（migration 00066）
+    KpiViewRefresher::new(pool.clone()).spawn();
+
+    // CORS layer for gRPC-Web (CORS_ALLOWED_ORIGINS / CORS_ALLOW_ANY)
+    let org_origins = if config.cors.per_organization {
+        let origins = OrganizationOrigins::default();
+        if let Err(e) = origins.refresh(&pool).await {
+            tracing::warn!("Failed to load organization CORS origins: {}", e);
+        }
+        origins.spawn_refresh(pool.clone());
+        Some(origins)
+    } else {
+        None
+    };
+    let cors = build_cors_layer(&config.cors, org_origins);
 
     // Build reflection service
     let reflection_service = ReflectionBuilder::configure()
-        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
         .build_v1()?;
 
     // Parse server address
     let addr: SocketAddr = config.server_addr().parse()?;
     tracing::info!("Listening on {}", addr);
 
-    // Build and run server with gRPC-Web support
-    Server::builder()
-        .accept_http1(true) // Required for gRPC-Web
-        .layer(GrpcWebTrailerFixLayer::new()) // Fix trailers-only for CF Containers
-        .layer(cors)
-        .layer(tonic_web::GrpcWebLayer::new()) // Enable gRPC-Web
-        .layer(auth_layer) // JWT authentication
-        .add_service(reflection_service)
-        .add_service(FilesServiceServer::new(files_service))
+    // gRPC services (also the in-process target of the REST gateway)
+    let grpc_routes = Routes::new(reflection_service)
+        .add_service(FilesServiceServer::from_arc(files_service))
+        .add_service(FilesV2ServiceServer::new(files_v2_service))
         .add_service(CarInspectionServiceServer::new(car_inspection_service))
         .add_service(CarInspectionFilesServiceServer::new(
             car_inspection_files_service,
@@ -197,7 +654,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .add_service(HealthServer::new(health_service))
         .add_service(DtakologsServiceServer::new(dtakologs_service))
         .add_service(FlickrServiceServer::new(flickr_service))
-        .add_service(DvrNotificationsServiceServer::new(dvr_notifications_service))
+        .add_service(DvrNotificationsServiceServer::from_arc(dvr_notifications_service.clone()))
         .add_service(AuthServiceServer::new(auth_service))
         .add_service(OrganizationServiceServer::new(organization_service))
         .add_service(MemberServiceServer::new(member_service))
@@ -206,8 +663,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .add_service(AccessRequestServiceServer::new(access_request_service))
         .add_service(ItemsServiceServer::new(items_service))
         .add_service(NfcTagServiceServer::new(nfc_tag_service))
-        .serve(addr)
-        .await?;
+        .add_service(SchedulerServiceServer::new(scheduler_service))
+        .add_service(JobsServiceServer::new(jobs_service))
+        .add_service(NotificationServiceServer::new(notification_service))
+        .add_service(NotificationFeedServiceServer::new(notification_feed_service))
+        .add_service(WebhookServiceServer::new(webhook_service))
+        .add_service(EtcServiceServer::new(etc_service))
+        .add_service(FuelServiceServer::new(fuel_service))
+        .add_service(IchibanCarsServiceServer::new(ichiban_cars_service))
+        .add_service(ReportServiceServer::new(report_service))
+        .add_service(AdminServiceServer::new(admin_service))
+        .add_service(ApiKeysServiceServer::new(api_keys_service))
+        .add_service(SearchServiceServer::new(search_service))
+        .add_service(GeofencesServiceServer::new(geofences_service));
+
+    // REST/JSON gateway generated from google.api.http annotations (/v1/...)
+    let rest_router = gateway::router(grpc_routes.clone())?;
+    // DVR ベンダー Webhook（取り込みトークン認証）
+    let ingest_router = rust_logi::ingest::router(pool.clone(), dvr_notifications_service);
+
+    // Build and run server with gRPC-Web support
+    let server = Server::builder()
+        .accept_http1(true) // Required for gRPC-Web
+        .layer(GrpcWebTrailerFixLayer::new()) // Fix trailers-only for CF Containers
+        .layer(TraceContextLayer::new()) // traceparent を受け取り外部呼び出しに引き継ぐ
+        .layer(cors)
+        .layer(tonic_web::GrpcWebLayer::new()) // Enable gRPC-Web
+        .layer(DrainLayer::new()) // 終了処理中は新しい RPC を UNAVAILABLE で断る
+        .layer(auth_layer) // JWT authentication
+        .layer(AuthorizationLayer::new()) // ロールによるメソッド単位の認可
+        .layer(ApiUsageLayer::new(api_usage)) // 組織ごとの API 呼び出し数
+        .layer(LocalizedErrorLayer::new()) // accept-language に応じたエラーメッセージ
+        .layer(CatchPanicLayer::new()) // ハンドラの panic を INTERNAL に変換
+        .add_routes(Routes::from(
+            grpc_routes
+                .into_axum_router()
+                .merge(rest_router)
+                .merge(ingest_router),
+        ))
+        .serve_with_shutdown(addr, shutdown::signal());
+
+    // SIGTERM / SIGINT 後は実行中の RPC（Watch などの stream を含む）とバックグラウンドのタスクを期限まで待つ
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    tokio::select! {
+        result = server => result?,
+        _ = shutdown::deadline(shutdown_timeout) => {
+            tracing::warn!("Timed out waiting for in-flight requests, closing connections");
+        }
+    }
+    shutdown::drain(shutdown_timeout).await;
+    tracing::info!("Server stopped");
 
     Ok(())
 }
@@ -0,0 +1,265 @@
+/// Middleware that counts API calls per organization for the platform admin stats.
+///
+/// Counts are kept in memory per (organization, hour) and flushed to
+/// `api_usage_hourly` every minute via `record_api_usage` (SECURITY DEFINER),
+/// so the request path never waits on the database.
+/// Errors are detected from trailers-only responses (grpc-status header) and
+/// HTTP status codes (REST gateway / ingest).
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use chrono::{DateTime, DurationRound, Utc};
+use http::{HeaderMap, Request as HttpRequest, Response as HttpResponse, StatusCode};
+use sqlx::PgPool;
+use tonic::{Code, Status};
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+/// x-organization-id metadata key（AuthLayer が JWT の組織で上書き済み）
+const ORG_HEADER: &str = "x-organization-id";
+
+/// api_usage_hourly への書き込み間隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct UsageCounts {
+    requests: i64,
+    errors: i64,
+    server_errors: i64,
+}
+
+/// 組織 × 時間ごとの API 呼び出し数（未書き込み分）
+#[derive(Clone, Default)]
+pub struct ApiUsage {
+    pending: Arc<Mutex<HashMap<(Uuid, DateTime<Utc>), UsageCounts>>>,
+}
+
+impl ApiUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, organization_id: Uuid, outcome: Outcome) {
+        let hour = Utc::now()
+            .duration_trunc(chrono::Duration::hours(1))
+            .unwrap_or_else(|_| Utc::now());
+        if let Ok(mut pending) = self.pending.lock() {
+            let counts = pending.entry((organization_id, hour)).or_default();
+            counts.requests += 1;
+            if outcome != Outcome::Ok {
+                counts.errors += 1;
+            }
+            if outcome == Outcome::ServerError {
+                counts.server_errors += 1;
+            }
+        }
+    }
+
+    /// 溜まった件数を書き込む（失敗したら次回に持ち越す）
+    pub async fn flush(&self, pool: &PgPool) -> Result<usize, sqlx::Error> {
+        let drained: Vec<((Uuid, DateTime<Utc>), UsageCounts)> = match self.pending.lock() {
+            Ok(mut pending) => pending.drain().collect(),
+            Err(_) => return Ok(0),
+        };
+        if drained.is_empty() {
+            return Ok(0);
+        }
+
+        let mut organization_ids = Vec::with_capacity(drained.len());
+        let mut hours = Vec::with_capacity(drained.len());
+        let mut requests = Vec::with_capacity(drained.len());
+        let mut errors = Vec::with_capacity(drained.len());
+        let mut server_errors = Vec::with_capacity(drained.len());
+        for ((organization_id, hour), counts) in &drained {
+            organization_ids.push(*organization_id);
+            hours.push(*hour);
+            requests.push(counts.requests);
+            errors.push(counts.errors);
+            server_errors.push(counts.server_errors);
+        }
+
+        let result = sqlx::query("SELECT record_api_usage($1, $2, $3, $4, $5)")
+            .bind(&organization_ids)
+            .bind(&hours)
+            .bind(&requests)
+            .bind(&errors)
+            .bind(&server_errors)
+            .execute(pool)
+            .await;
+        if let Err(e) = result {
+            if let Ok(mut pending) = self.pending.lock() {
+                for (key, counts) in drained {
+                    let entry = pending.entry(key).or_default();
+                    entry.requests += counts.requests;
+                    entry.errors += counts.errors;
+                    entry.server_errors += counts.server_errors;
+                }
+            }
+            return Err(e);
+        }
+        Ok(drained.len())
+    }
+
+    /// 定期書き込みタスクを起動
+    pub fn spawn_flush(&self, pool: PgPool) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = this.flush(&pool).await {
+                    tracing::warn!("Failed to flush API usage: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Ok,
+    ClientError,
+    ServerError,
+}
+
+fn is_server_error(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Internal | Code::Unavailable | Code::Unknown | Code::DataLoss | Code::DeadlineExceeded
+    )
+}
+
+/// レスポンスヘッダー（trailers-only の grpc-status）と HTTP ステータスから結果を判定
+fn classify(status: StatusCode, headers: &HeaderMap) -> Outcome {
+    if status.is_server_error() {
+        return Outcome::ServerError;
+    }
+    if let Some(grpc) = Status::from_header_map(headers) {
+        return match grpc.code() {
+            Code::Ok => Outcome::Ok,
+            code if is_server_error(code) => Outcome::ServerError,
+            _ => Outcome::ClientError,
+        };
+    }
+    if status.is_client_error() {
+        Outcome::ClientError
+    } else {
+        Outcome::Ok
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiUsageLayer {
+    usage: ApiUsage,
+}
+
+impl ApiUsageLayer {
+    pub fn new(usage: ApiUsage) -> Self {
+        Self { usage }
+    }
+}
+
+impl<S> Layer<S> for ApiUsageLayer {
+    type Service = ApiUsageMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiUsageMiddleware {
+            inner,
+            usage: self.usage.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiUsageMiddleware<S> {
+    inner: S,
+    usage: ApiUsage,
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for ApiUsageMiddleware<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = HttpResponse<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = HttpResponse<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+
+        let organization_id = req
+            .headers()
+            .get(ORG_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<Uuid>().ok());
+        let usage = self.usage.clone();
+
+        Box::pin(async move {
+            let result = inner.call(req).await;
+            if let Some(organization_id) = organization_id {
+                let outcome = match &result {
+                    Ok(response) => classify(response.status(), response.headers()),
+                    Err(_) => Outcome::ServerError,
+                };
+                usage.record(organization_id, outcome);
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(classify(StatusCode::OK, &headers), Outcome::Ok);
+        assert_eq!(classify(StatusCode::NOT_FOUND, &headers), Outcome::ClientError);
+        assert_eq!(classify(StatusCode::BAD_GATEWAY, &headers), Outcome::ServerError);
+
+        Status::not_found("File not found").add_header(&mut headers).unwrap();
+        assert_eq!(classify(StatusCode::OK, &headers), Outcome::ClientError);
+
+        let mut headers = HeaderMap::new();
+        Status::internal("Database error").add_header(&mut headers).unwrap();
+        assert_eq!(classify(StatusCode::OK, &headers), Outcome::ServerError);
+    }
+
+    #[test]
+    fn test_record_counts_per_hour() {
+        let usage = ApiUsage::new();
+        let org = Uuid::new_v4();
+        usage.record(org, Outcome::Ok);
+        usage.record(org, Outcome::ClientError);
+        usage.record(org, Outcome::ServerError);
+        // 時間の境目をまたいでも合計は変わらない
+        let pending = usage.pending.lock().unwrap();
+        let total = pending.values().fold(UsageCounts::default(), |acc, c| UsageCounts {
+            requests: acc.requests + c.requests,
+            errors: acc.errors + c.errors,
+            server_errors: acc.server_errors + c.server_errors,
+        });
+        assert_eq!(
+            total,
+            UsageCounts {
+                requests: 3,
+                errors: 2,
+                server_errors: 1
+            }
+        );
+    }
+}
@@ -11,6 +11,7 @@ use sqlx::PgPool;
 use tonic::Status;
 use tower::{Layer, Service};
 
+use crate::services::api_keys_service::hash_api_key;
 use crate::services::auth_service::Claims;
 
 /// Authenticated user info injected by the auth middleware into request extensions.
@@ -23,6 +24,22 @@ pub struct AuthenticatedUser {
     pub org_slug: String,
 }
 
+/// Services an API key (x-api-key) may call, injected next to `AuthenticatedUser`.
+/// Checked by the authorization layer.
+#[derive(Clone, Debug)]
+pub struct ApiKeyScope {
+    pub api_key_id: String,
+    pub services: Vec<String>,
+}
+
+impl ApiKeyScope {
+    /// gRPC パス（`/package.Service/Method`）のサービスが許可されているか
+    pub fn allows(&self, path: &str) -> bool {
+        let service = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+        self.services.iter().any(|s| s == service)
+    }
+}
+
 /// Public paths that do not require JWT authentication
 const PUBLIC_PATHS: &[&str] = &[
     "/logi.auth.AuthService/Login",
@@ -30,18 +47,31 @@ const PUBLIC_PATHS: &[&str] = &[
     "/logi.auth.AuthService/LoginWithGoogle",
     "/logi.auth.AuthService/ValidateToken",
     "/logi.member.MemberService/AcceptInvitation",
+    "/logi.member.MemberService/GetPasswordPolicy",
     "/grpc.health.v1.Health/Check",
     "/grpc.health.v1.Health/Watch",
     "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
     "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
     "/logi.auth.AuthService/ResolveSsoProvider",
     "/logi.auth.AuthService/LoginWithSsoProvider",
+    "/logi.auth.AuthService/RequestPasswordReset",
+    "/logi.auth.AuthService/ResetPassword",
+    "/logi.auth.AuthService/RefreshToken",
+    "/logi.auth.AuthService/RevokeToken",
     "/logi.access_request.AccessRequestService/GetOrganizationBySlug",
 ];
 
+/// JWT なしで呼べるメソッド（それ以外は AuthorizationLayer / gateway が UNAUTHENTICATED にする）
+pub(crate) fn is_public_path(path: &str) -> bool {
+    PUBLIC_PATHS.contains(&path)
+}
+
 /// x-organization-id metadata key
 const ORG_HEADER: &str = "x-organization-id";
 
+/// 機械クライアント用の API キー（ApiKeysService で発行）
+const API_KEY_HEADER: &str = "x-api-key";
+
 #[derive(Clone)]
 pub struct AuthLayer {
     pool: PgPool,
@@ -73,9 +103,9 @@ pub struct AuthMiddleware<S> {
     jwt_secret: String,
 }
 
-type BoxBody = UnsyncBoxBody<bytes::Bytes, Status>;
+pub(crate) type BoxBody = UnsyncBoxBody<bytes::Bytes, Status>;
 
-fn grpc_status_response(status: Status) -> HttpResponse<BoxBody> {
+pub(crate) fn grpc_status_response(status: Status) -> HttpResponse<BoxBody> {
     let code = status.code() as i32;
     let message = status.message().to_string();
 
@@ -122,7 +152,7 @@ where
             let path = req.uri().path().to_string();
 
             // Check if this is a public path
-            if PUBLIC_PATHS.iter().any(|p| path == *p) {
+            if is_public_path(&path) {
                 return inner.call(req).await;
             }
 
@@ -157,33 +187,29 @@ where
                     .filter(|s| !s.is_empty())
                     .map(|s| s.to_string());
 
-                let (effective_org_id, role) = if let Some(ref org_id) = requested_org {
-                    if *org_id != jwt_org {
-                        // User is requesting a different org — verify membership
-                        match verify_membership(&pool, &claims.sub, org_id).await {
-                            Ok(role) => (org_id.clone(), role),
-                            Err(_) => {
-                                tracing::warn!(
-                                    "User {} not a member of org {}",
-                                    claims.sub,
-                                    org_id
-                                );
-                                return Ok(grpc_status_response(Status::permission_denied(
-                                    "Not a member of the requested organization",
-                                )));
-                            }
-                        }
-                    } else {
-                        match verify_membership(&pool, &claims.sub, org_id).await {
-                            Ok(role) => (org_id.clone(), role),
-                            Err(_) => (jwt_org.clone(), "member".to_string()),
-                        }
+                let effective_org_id = requested_org.unwrap_or_else(|| jwt_org.clone());
+                let membership = match verify_membership(&pool, &claims.sub, &effective_org_id).await {
+                    Ok(membership) => membership,
+                    Err(e) => {
+                        tracing::error!("Failed to verify membership: {}", e);
+                        return Ok(grpc_status_response(Status::internal("Database error")));
                     }
-                } else {
-                    match verify_membership(&pool, &claims.sub, &jwt_org).await {
-                        Ok(role) => (jwt_org.clone(), role),
-                        Err(_) => (jwt_org.clone(), "member".to_string()),
+                };
+                let role = match membership {
+                    Some(role) => role,
+                    None if effective_org_id != jwt_org => {
+                        // User is requesting a different org — must be a member
+                        tracing::warn!(
+                            "User {} not a member of org {}",
+                            claims.sub,
+                            effective_org_id
+                        );
+                        return Ok(grpc_status_response(Status::permission_denied(
+                            "Not a member of the requested organization",
+                        )));
                     }
+                    // JWT の組織だが所属の行がない（外された・外部発行の JWT）: 読み取りのみ
+                    None => "viewer".to_string(),
                 };
 
                 // Inject AuthenticatedUser into extensions
@@ -199,17 +225,52 @@ where
                 if let Ok(value) = effective_org_id.parse() {
                     req.headers_mut().insert(ORG_HEADER, value);
                 }
+            } else if let Some(api_key) = req
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+            {
+                // API key — organization is fixed by the key (x-organization-id is overwritten)
+                let resolved = match resolve_api_key(&pool, &api_key).await {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        tracing::error!("Failed to resolve API key: {}", e);
+                        return Ok(grpc_status_response(Status::internal("Database error")));
+                    }
+                };
+                let Some((api_key_id, org_id, created_by, services, org_slug)) = resolved else {
+                    return Ok(grpc_status_response(Status::unauthenticated("Invalid API key")));
+                };
+
+                // 操作は発行した管理者として記録する。ロールは member まで
+                req.extensions_mut().insert(AuthenticatedUser {
+                    user_id: created_by,
+                    org_id: org_id.clone(),
+                    role: "member".to_string(),
+                    provider: "api_key".to_string(),
+                    org_slug,
+                });
+                req.extensions_mut().insert(ApiKeyScope { api_key_id, services });
+                if let Ok(value) = org_id.parse() {
+                    req.headers_mut().insert(ORG_HEADER, value);
+                }
             }
-            // No valid JWT — pass through (backwards compatible)
-            // Existing services use get_organization_from_request() which falls back to
-            // x-organization-id header or DEFAULT_ORGANIZATION_ID
+            // No valid JWT — pass through; AuthorizationLayer (gRPC) and the gateway (REST)
+            // reject the request with UNAUTHENTICATED
 
             inner.call(req).await
         })
     }
 }
 
-async fn verify_membership(pool: &PgPool, user_id: &str, org_id: &str) -> Result<String, ()> {
+/// 組織でのロール（所属していなければ None）
+async fn verify_membership(
+    pool: &PgPool,
+    user_id: &str,
+    org_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
     sqlx::query_scalar::<_, String>(
         "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
     )
@@ -217,6 +278,15 @@ async fn verify_membership(pool: &PgPool, user_id: &str, org_id: &str) -> Result
     .bind(org_id)
     .fetch_optional(pool)
     .await
-    .map_err(|_| ())?
-    .ok_or(())
+}
+
+type ResolvedApiKey = (String, String, String, Vec<String>, String);
+
+/// x-api-key から (api_key_id, organization_id, created_by, services, org_slug)
+async fn resolve_api_key(pool: &PgPool, key: &str) -> Result<Option<ResolvedApiKey>, sqlx::Error> {
+    // SECURITY DEFINER function (api_keys has RLS, org unknown before auth)
+    sqlx::query_as("SELECT * FROM resolve_api_key($1)")
+        .bind(hash_api_key(key))
+        .fetch_optional(pool)
+        .await
 }
@@ -0,0 +1,250 @@
+/// Role-based authorization for gRPC methods.
+///
+/// Runs inside `AuthLayer` and checks the role it resolved from
+/// `user_organizations.role` against a static policy table.
+/// Requests without a JWT or API key are rejected unless the method is public
+/// (login, health check, ... — `auth::PUBLIC_PATHS`).
+/// API keys (`ApiKeyScope`) are additionally limited to the services they were issued for.
+/// The REST gateway calls `authorize` with the target gRPC path as well.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::Extensions;
+use http::Request as HttpRequest;
+use http::Response as HttpResponse;
+use tonic::Status;
+use tower::{Layer, Service};
+
+use super::auth::{grpc_status_response, is_public_path, BoxBody};
+use super::{ApiKeyScope, AuthenticatedUser};
+
+/// 組織内のロール（admin > member > viewer）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Member,
+    Admin,
+}
+
+impl Role {
+    /// user_organizations.role から変換（未知のロールは viewer 扱い）
+    pub fn parse(role: &str) -> Self {
+        match role {
+            "admin" => Role::Admin,
+            "member" => Role::Member,
+            _ => Role::Viewer,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Member => "member",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// メソッドごとの必要ロール。`/` で終わるものはサービス全体に当てはまる（完全一致が優先）
+const POLICY: &[(&str, Role)] = &[
+    ("/logi.dtakologs.DtakologsService/DeleteAll", Role::Admin),
+    ("/logi.dtakologs.DtakologsService/BackfillAddresses", Role::Admin),
+    ("/logi.files.FilesService/PurgeFile", Role::Admin),
+    ("/logi.files.FilesService/UpdateFileRetention", Role::Admin),
+    ("/logi.jobs.JobsService/", Role::Admin),
+    ("/logi.scheduler.SchedulerService/", Role::Admin),
+    ("/logi.webhooks.WebhookService/", Role::Admin),
+    ("/logi.api_keys.ApiKeysService/", Role::Admin),
+    // 本人の操作（ログイン・組織切り替え・自分宛ての通知）は viewer でも可
+    ("/logi.auth.AuthService/", Role::Viewer),
+    ("/logi.member.MemberService/AcceptInvitation", Role::Viewer),
+    ("/logi.notifications.NotificationFeedService/", Role::Viewer),
+];
+
+/// 表にないメソッドで viewer に許可する読み取り系の接頭辞
+const READ_PREFIXES: &[&str] = &[
+    "Get", "List", "Watch", "Stream", "Current", "Search", "Download", "Export", "BatchGet",
+];
+
+/// 表で指定されたロール（なければ None）
+fn policy_role(path: &str) -> Option<Role> {
+    if let Some((_, role)) = POLICY.iter().find(|(p, _)| *p == path) {
+        return Some(*role);
+    }
+    POLICY
+        .iter()
+        .find(|(p, _)| p.ends_with('/') && path.starts_with(p))
+        .map(|(_, role)| *role)
+}
+
+/// gRPC パス（`/package.Service/Method`）に必要なロール
+pub fn required_role(path: &str) -> Role {
+    if let Some(role) = policy_role(path) {
+        return role;
+    }
+    let method = path.rsplit('/').next().unwrap_or_default();
+    if READ_PREFIXES.iter().any(|prefix| method.starts_with(prefix)) {
+        Role::Viewer
+    } else {
+        Role::Member
+    }
+}
+
+/// ロールが足りない・API キーで許可されていないサービスなら PERMISSION_DENIED
+pub fn authorize(path: &str, extensions: &Extensions) -> Result<(), Status> {
+    if let Some(scope) = extensions.get::<ApiKeyScope>() {
+        if !scope.allows(path) {
+            tracing::warn!("API key {} denied {}", scope.api_key_id, path);
+            return Err(Status::permission_denied("API key is not permitted to call this service"));
+        }
+    }
+
+    let Some(user) = extensions.get::<AuthenticatedUser>() else {
+        // JWT / API キーなしは公開メソッドだけ
+        if is_public_path(path) {
+            return Ok(());
+        }
+        return Err(Status::unauthenticated("Authentication required"));
+    };
+
+    let required = required_role(path);
+    if Role::parse(&user.role) < required {
+        tracing::warn!(
+            "User {} ({}) denied {} in org {}",
+            user.user_id,
+            user.role,
+            path,
+            user.org_id
+        );
+        return Err(Status::permission_denied(format!(
+            "This operation requires the {} role",
+            required.as_str()
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Default)]
+pub struct AuthorizationLayer;
+
+impl AuthorizationLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for AuthorizationLayer {
+    type Service = AuthorizationMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthorizationMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthorizationMiddleware<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<HttpRequest<ReqBody>> for AuthorizationMiddleware<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = HttpResponse<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = HttpResponse<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+
+        Box::pin(async move {
+            // REST (/v1/...) や ingest は gRPC パスではないので対象外（gateway 側で確認する）
+            let path = req.uri().path();
+            if path.starts_with("/logi.") {
+                if let Err(status) = authorize(path, req.extensions()) {
+                    return Ok(grpc_status_response(status));
+                }
+            }
+            inner.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(role: &str) -> Extensions {
+        let mut extensions = Extensions::new();
+        extensions.insert(AuthenticatedUser {
+            user_id: "u".to_string(),
+            org_id: "o".to_string(),
+            role: role.to_string(),
+            provider: "password".to_string(),
+            org_slug: "s".to_string(),
+        });
+        extensions
+    }
+
+    #[test]
+    fn required_role_uses_policy_then_method_name() {
+        assert_eq!(required_role("/logi.dtakologs.DtakologsService/DeleteAll"), Role::Admin);
+        assert_eq!(required_role("/logi.jobs.JobsService/ListJobs"), Role::Admin);
+        assert_eq!(required_role("/logi.dtakologs.DtakologsService/ListAll"), Role::Viewer);
+        assert_eq!(required_role("/logi.files.FilesService/DeleteFile"), Role::Member);
+        assert_eq!(required_role("/logi.files.FilesService/BatchGetFiles"), Role::Viewer);
+        assert_eq!(required_role("/logi.files.FilesService/BatchDeleteFiles"), Role::Member);
+        assert_eq!(required_role("/logi.files.FilesService/PurgeFile"), Role::Admin);
+        assert_eq!(required_role("/logi.auth.AuthService/SwitchOrganization"), Role::Viewer);
+    }
+
+    #[test]
+    fn authorize_compares_roles() {
+        let delete_all = "/logi.dtakologs.DtakologsService/DeleteAll";
+        assert!(authorize(delete_all, &user("admin")).is_ok());
+        let err = authorize(delete_all, &user("member")).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        let create = "/logi.files.FilesService/CreateFile";
+        assert!(authorize(create, &user("member")).is_ok());
+        assert!(authorize(create, &user("viewer")).is_err());
+        assert!(authorize("/logi.files.FilesService/ListFiles", &user("viewer")).is_ok());
+    }
+
+    #[test]
+    fn unauthenticated_requests_only_allowed_for_public_methods() {
+        let none = Extensions::new();
+        for path in [
+            "/logi.files.FilesService/CreateFile",
+            "/logi.files.FilesService/BatchDeleteFiles",
+            "/logi.files.FilesService/ListFiles",
+            "/logi.dtakologs.DtakologsService/DeleteAll",
+        ] {
+            let err = authorize(path, &none).unwrap_err();
+            assert_eq!(err.code(), tonic::Code::Unauthenticated);
+        }
+        assert!(authorize("/logi.auth.AuthService/Login", &none).is_ok());
+        assert!(authorize("/grpc.health.v1.Health/Check", &none).is_ok());
+    }
+
+    #[test]
+    fn api_keys_are_limited_to_their_services() {
+        let mut extensions = user("member");
+        extensions.insert(ApiKeyScope {
+            api_key_id: "k".to_string(),
+            services: vec!["logi.dtakologs.DtakologsService".to_string()],
+        });
+        assert!(authorize("/logi.dtakologs.DtakologsService/Create", &extensions).is_ok());
+        let err = authorize("/logi.files.FilesService/ListFiles", &extensions).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+    }
+}
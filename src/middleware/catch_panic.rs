@@ -0,0 +1,175 @@
+/// Middleware that turns a panic inside a handler into a clean INTERNAL status.
+///
+/// Without it a panic tears down the HTTP/2 stream and the client only sees a
+/// reset. The panic is logged with the request id (`x-request-id`, otherwise the
+/// trace id from `TraceContextLayer`) and a trailers-only `grpc-status: 13`
+/// response is returned, so LocalizedErrorLayer / ApiUsageLayer see a normal error.
+///
+/// It also sets the request context (path, request id, organization) used by
+/// `crate::error::reporting`, and reports INTERNAL responses that were not
+/// already reported by a panic or `AppError`.
+use std::any::Any;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::header::CONTENT_TYPE;
+use http::{HeaderValue, Request as HttpRequest, Response as HttpResponse};
+use tonic::{Code, Status};
+use tower::{Layer, Service};
+
+use super::trace_context::TraceContext;
+use crate::error::reporting::{self, EventKind, RequestContext};
+
+const REQUEST_ID: &str = "x-request-id";
+/// x-organization-id metadata key（AuthLayer が JWT の組織で上書き済み）
+const ORG_HEADER: &str = "x-organization-id";
+
+#[derive(Debug, Clone, Default)]
+pub struct CatchPanicLayer;
+
+impl CatchPanicLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for CatchPanicLayer {
+    type Service = CatchPanic<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CatchPanic { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CatchPanic<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for CatchPanic<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = HttpResponse<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = HttpResponse<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| TraceContext::current().map(|ctx| ctx.trace_id_hex()))
+            .unwrap_or_default();
+        let path = req.uri().path().to_string();
+        let organization_id = req
+            .headers()
+            .get(ORG_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let context = RequestContext::new(path.clone(), request_id.clone(), organization_id);
+
+        // call() 自体の panic も拾う
+        let mut future = match catch_unwind(AssertUnwindSafe(|| Box::pin(inner.call(req)))) {
+            Ok(future) => future,
+            Err(panic) => return Box::pin(async move { Ok(panic_response(panic, &request_id, &path)) }),
+        };
+
+        Box::pin(context.clone().scope(async move {
+            let result = std::future::poll_fn(|cx| match catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                Ok(poll) => poll.map(Ok),
+                Err(panic) => Poll::Ready(Err(panic)),
+            })
+            .await;
+            match result {
+                Ok(Ok(response)) => {
+                    report_internal(&response, &context);
+                    Ok(response)
+                }
+                Ok(Err(e)) => Err(e),
+                Err(panic) => Ok(panic_response(panic, &request_id, &path)),
+            }
+        }))
+    }
+}
+
+/// ハンドラが直接作った INTERNAL（AppError を通らないもの）を報告する
+fn report_internal<B>(response: &HttpResponse<B>, context: &RequestContext) {
+    if context.is_reported() {
+        return;
+    }
+    if let Some(status) = Status::from_header_map(response.headers()) {
+        if status.code() == Code::Internal {
+            reporting::capture(EventKind::InternalError, status.message());
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<non-string panic>")
+}
+
+/// panic をログに残し、trailers-only の INTERNAL を返す
+fn panic_response<B: Default>(panic: Box<dyn Any + Send>, request_id: &str, path: &str) -> HttpResponse<B> {
+    tracing::error!(
+        request_id = %request_id,
+        path = %path,
+        "Handler panicked: {}",
+        panic_message(panic.as_ref())
+    );
+
+    let mut response = HttpResponse::new(B::default());
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        headers.insert(REQUEST_ID, value);
+    }
+    if let Err(e) = Status::internal("Internal error").add_header(headers) {
+        tracing::warn!("Failed to write panic status headers: {}", e);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    async fn panicking(_req: HttpRequest<()>) -> Result<HttpResponse<String>, std::convert::Infallible> {
+        panic!("boom")
+    }
+
+    #[tokio::test]
+    async fn test_panic_becomes_internal_status() {
+        let service = CatchPanicLayer::new().layer(tower::service_fn(panicking));
+
+        let req = HttpRequest::builder()
+            .uri("/logi.files.FilesService/GetFile")
+            .header(REQUEST_ID, "req-1")
+            .body(())
+            .unwrap();
+        let response = service.oneshot(req).await.unwrap();
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Internal);
+        assert_eq!(status.message(), "Internal error");
+        assert_eq!(response.headers().get(REQUEST_ID).unwrap(), "req-1");
+    }
+}
@@ -0,0 +1,116 @@
+// CORS layer configured per deployment (and optionally per organization)
+
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use http::{HeaderName, HeaderValue, Method};
+use sqlx::PgPool;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::config::CorsConfig;
+
+/// 組織ごとの許可オリジン更新間隔
+const ORG_ORIGINS_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// gRPC-Web クライアントに公開するレスポンスヘッダー
+const EXPOSE_HEADERS: &[&str] = &["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+/// 組織設定から読み込んだ許可オリジン（バックグラウンドで定期更新）
+#[derive(Clone, Default)]
+pub struct OrganizationOrigins {
+    origins: Arc<RwLock<HashSet<String>>>,
+}
+
+impl OrganizationOrigins {
+    pub fn contains(&self, origin: &str) -> bool {
+        self.origins
+            .read()
+            .map(|set| set.contains(origin))
+            .unwrap_or(false)
+    }
+
+    pub async fn refresh(&self, pool: &PgPool) -> Result<usize, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT DISTINCT unnest(cors_allowed_origins) FROM organizations WHERE deleted_at IS NULL",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let set: HashSet<String> = rows.into_iter().map(|(o,)| o).collect();
+        let count = set.len();
+        if let Ok(mut guard) = self.origins.write() {
+            *guard = set;
+        }
+        Ok(count)
+    }
+
+    /// 定期更新タスクを起動
+    pub fn spawn_refresh(&self, pool: PgPool) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ORG_ORIGINS_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                match this.refresh(&pool).await {
+                    Ok(count) => tracing::debug!("Refreshed organization CORS origins: {}", count),
+                    Err(e) => tracing::warn!("Failed to refresh organization CORS origins: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// CorsConfig から CorsLayer を構築
+///
+/// `allow_any` の場合のみ従来通り Any を許可する（開発用）。
+pub fn build_cors_layer(config: &CorsConfig, org_origins: Option<OrganizationOrigins>) -> CorsLayer {
+    if config.allow_any {
+        tracing::warn!("CORS_ALLOW_ANY=true: allowing any origin (development only)");
+        return CorsLayer::new()
+            .allow_origin(Any)
+            .allow_headers(Any)
+            .allow_methods(Any)
+            .expose_headers(Any);
+    }
+
+    let static_origins: HashSet<String> = config.allowed_origins.iter().cloned().collect();
+    let allow_origin = AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+        static_origins.contains(origin)
+            || org_origins.as_ref().is_some_and(|o| o.contains(origin))
+    });
+
+    let allowed_headers: Vec<HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|h| match HeaderName::try_from(h.as_str()) {
+            Ok(name) => Some(name),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS header name: {}", h);
+                None
+            }
+        })
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_headers(allowed_headers)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .expose_headers(
+            EXPOSE_HEADERS
+                .iter()
+                .map(|h| HeaderName::from_static(h))
+                .collect::<Vec<_>>(),
+        )
+        .max_age(Duration::from_secs(config.max_age_secs))
+}
@@ -0,0 +1,91 @@
+/// Refuses new requests once graceful shutdown has started.
+///
+/// After SIGTERM / SIGINT (`crate::shutdown`) gRPC calls get a trailers-only
+/// `UNAVAILABLE` and REST / ingest requests a 503, so clients retry against
+/// another instance while in-flight requests finish.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::{Request as HttpRequest, Response as HttpResponse, StatusCode};
+use tonic::Status;
+use tower::{Layer, Service};
+
+use super::auth::{grpc_status_response, BoxBody};
+use crate::shutdown;
+
+#[derive(Clone, Default)]
+pub struct DrainLayer;
+
+impl DrainLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for DrainLayer {
+    type Service = DrainMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DrainMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct DrainMiddleware<S> {
+    inner: S,
+}
+
+/// drain 中の応答（gRPC パスは UNAVAILABLE、それ以外は 503）
+fn unavailable_response(path: &str) -> HttpResponse<BoxBody> {
+    if path.starts_with("/logi.") || path.starts_with("/grpc.") {
+        return grpc_status_response(Status::unavailable("Server is shutting down"));
+    }
+    let mut response = HttpResponse::new(BoxBody::default());
+    *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    response
+}
+
+impl<S, ReqBody> Service<HttpRequest<ReqBody>> for DrainMiddleware<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = HttpResponse<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = HttpResponse<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        if shutdown::is_draining() {
+            let response = unavailable_response(req.uri().path());
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+        Box::pin(inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn test_unavailable_response() {
+        let response = unavailable_response("/logi.files.FilesService/GetFile");
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Unavailable);
+
+        let response = unavailable_response("/v1/files");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(Status::from_header_map(response.headers()).is_none());
+    }
+}
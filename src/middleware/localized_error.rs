@@ -0,0 +1,101 @@
+/// Middleware that replaces gRPC error messages with localized catalog messages.
+///
+/// Picks the language from the `accept-language` header and rewrites
+/// grpc-message / grpc-status-details-bin of trailers-only error responses
+/// (every unary error and errors returned before a stream starts).
+/// See `crate::error::catalog` for the codes and messages.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::header::ACCEPT_LANGUAGE;
+use http::{HeaderMap, Request as HttpRequest, Response as HttpResponse};
+use tonic::Status;
+use tower::{Layer, Service};
+
+use crate::error::catalog::{localize, Locale};
+
+#[derive(Debug, Clone, Default)]
+pub struct LocalizedErrorLayer;
+
+impl LocalizedErrorLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for LocalizedErrorLayer {
+    type Service = LocalizedError<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LocalizedError { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalizedError<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for LocalizedError<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = HttpResponse<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = HttpResponse<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+
+        let locale = Locale::from_accept_language(
+            req.headers().get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()),
+        );
+
+        Box::pin(async move {
+            let mut response = inner.call(req).await?;
+            localize_headers(response.headers_mut(), locale);
+            Ok(response)
+        })
+    }
+}
+
+fn localize_headers(headers: &mut HeaderMap, locale: Locale) {
+    let Some(status) = Status::from_header_map(headers) else {
+        return;
+    };
+    let localized = localize(status, locale);
+
+    headers.remove("grpc-status");
+    headers.remove("grpc-message");
+    headers.remove("grpc-status-details-bin");
+    if let Err(e) = localized.add_header(headers) {
+        tracing::warn!("Failed to write localized status headers: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn test_localize_trailers_only_headers() {
+        let mut headers = HeaderMap::new();
+        Status::not_found("File not found").add_header(&mut headers).unwrap();
+
+        localize_headers(&mut headers, Locale::En);
+        let status = Status::from_header_map(&headers).unwrap();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "The requested resource was not found");
+        assert!(!status.details().is_empty());
+    }
+}
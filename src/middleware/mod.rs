@@ -1,4 +1,11 @@
 pub mod auth;
+pub mod authorization;
+pub mod api_usage;
+pub mod catch_panic;
+pub mod cors;
+pub mod drain;
 pub mod grpc_web_fix;
+pub mod localized_error;
+pub mod trace_context;
 
-pub use auth::AuthenticatedUser;
+pub use auth::{ApiKeyScope, AuthenticatedUser};
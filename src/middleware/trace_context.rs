@@ -0,0 +1,248 @@
+/// W3C Trace Context (traceparent / tracestate) propagation.
+///
+/// Incoming requests join the caller's trace when a valid `traceparent` is
+/// present (Cloud Run / cf-grpc-proxy / browsers), otherwise a new trace is
+/// started. The context is kept in a task-local for the duration of the
+/// request, and `HttpClient` injects a child `traceparent` into every outbound
+/// call so spans from the dtako API, cameras and other services join the trace.
+/// When the OTLP exporter is enabled (`telemetry`), the request span is
+/// exported with the caller as its parent and its IDs replace the context's.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::{HeaderMap, HeaderValue, Request as HttpRequest};
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+use crate::telemetry;
+
+const TRACEPARENT: &str = "traceparent";
+const TRACESTATE: &str = "tracestate";
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: u128,
+    pub span_id: u64,
+    pub sampled: bool,
+    /// ベンダー固有の情報（受け取ったものをそのまま引き継ぐ）
+    pub tracestate: Option<String>,
+}
+
+fn random_nonzero<T: Default + PartialEq>(mut gen: impl FnMut() -> T) -> T {
+    loop {
+        let value = gen();
+        if value != T::default() {
+            return value;
+        }
+    }
+}
+
+impl TraceContext {
+    /// 新しい trace を始める
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_nonzero(rand::random::<u128>),
+            span_id: random_nonzero(rand::random::<u64>),
+            sampled: true,
+            tracestate: None,
+        }
+    }
+
+    /// `00-{trace_id}-{parent_id}-{flags}` を読む（不正・全 0 の ID は None）
+    pub fn parse(traceparent: &str, tracestate: Option<&str>) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        // version 00 は 4 項目ちょうど、それ以降の version は後ろに項目が増えうる
+        if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+            return None;
+        }
+        let trace_id = u128::from_str_radix(trace_id, 16).ok().filter(|id| *id != 0)?;
+        let span_id = u64::from_str_radix(span_id, 16).ok().filter(|id| *id != 0)?;
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id,
+            span_id,
+            sampled: flags & 0x01 == 0x01,
+            tracestate: tracestate.map(str::to_string).filter(|s| !s.is_empty()),
+        })
+    }
+
+    /// 同じ trace の子 span
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: random_nonzero(rand::random::<u64>),
+            ..self.clone()
+        }
+    }
+
+    pub fn trace_id_hex(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id,
+            self.span_id,
+            u8::from(self.sampled)
+        )
+    }
+
+    /// 受信ヘッダーの呼び出し元の context
+    pub fn remote_parent(headers: &HeaderMap) -> Option<Self> {
+        let tracestate = headers.get(TRACESTATE).and_then(|v| v.to_str().ok());
+        headers
+            .get(TRACEPARENT)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| Self::parse(v, tracestate))
+    }
+
+    /// 受信ヘッダーから（なければ新しい trace）。この処理自体を子 span にする
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self::remote_parent(headers)
+            .map(|parent| parent.child())
+            .unwrap_or_else(Self::new_root)
+    }
+
+    /// 処理中のリクエストの context
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|ctx| ctx.clone()).ok()
+    }
+
+    /// 外部呼び出しに traceparent / tracestate を付ける（リクエスト外なら新しい trace）
+    ///
+    /// exporter があれば実行中の span（ストレージ操作など）を親にする。
+    pub fn inject(headers: &mut HeaderMap) {
+        let current = Self::current();
+        let ctx = match telemetry::current_span_ids() {
+            Some((trace_id, span_id, sampled)) => Self {
+                trace_id,
+                span_id,
+                sampled,
+                tracestate: current.and_then(|ctx| ctx.tracestate),
+            },
+            None => current.map(|ctx| ctx.child()).unwrap_or_else(Self::new_root),
+        };
+        if let Ok(value) = HeaderValue::from_str(&ctx.traceparent()) {
+            headers.insert(TRACEPARENT, value);
+        }
+        if let Some(value) = ctx.tracestate.as_deref().and_then(|s| HeaderValue::from_str(s).ok()) {
+            headers.insert(TRACESTATE, value);
+        }
+    }
+
+    /// この context の中で実行する（spawn したタスクには引き継がれない）
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TraceContextLayer;
+
+impl TraceContextLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for TraceContextLayer {
+    type Service = TraceContextMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceContextMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct TraceContextMiddleware<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<HttpRequest<ReqBody>> for TraceContextMiddleware<S>
+where
+    S: Service<HttpRequest<ReqBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+
+        let parent = TraceContext::remote_parent(req.headers());
+        let mut ctx = parent
+            .as_ref()
+            .map(TraceContext::child)
+            .unwrap_or_else(TraceContext::new_root);
+        let span = tracing::info_span!(
+            "request",
+            otel.kind = "server",
+            trace_id = tracing::field::Empty,
+            path = %req.uri().path()
+        );
+        // exporter があれば送る span の ID を使う（新しい trace では trace_id も exporter が決める）
+        let remote = parent.map(|p| (p.trace_id, p.span_id, p.sampled));
+        if let Some((trace_id, span_id, sampled)) = telemetry::link_remote_parent(&span, remote) {
+            ctx = TraceContext { trace_id, span_id, sampled, ..ctx };
+        }
+        span.record("trace_id", tracing::field::display(ctx.trace_id_hex()));
+        Box::pin(ctx.scope(inner.call(req).instrument(span)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_traceparent() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let ctx = TraceContext::parse(header, Some("congo=t61rcWkgMzE")).unwrap();
+        assert_eq!(ctx.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(ctx.sampled);
+        assert_eq!(ctx.traceparent(), header);
+
+        let child = ctx.child();
+        assert_eq!(child.trace_id, ctx.trace_id);
+        assert_ne!(child.span_id, ctx.span_id);
+        assert_eq!(child.tracestate.as_deref(), Some("congo=t61rcWkgMzE"));
+
+        assert!(TraceContext::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01", None).is_none());
+        assert!(TraceContext::parse("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", None).is_none());
+        assert!(TraceContext::parse("00-4bf92f35-00f067aa0ba902b7-01", None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inject_uses_current_trace() {
+        let ctx = TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", None).unwrap();
+        let headers = ctx
+            .scope(async {
+                let mut headers = HeaderMap::new();
+                TraceContext::inject(&mut headers);
+                headers
+            })
+            .await;
+        let injected = headers.get(TRACEPARENT).unwrap().to_str().unwrap();
+        assert!(injected.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(!injected.contains("00f067aa0ba902b7"));
+    }
+}
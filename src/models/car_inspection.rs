@@ -1,28 +1,161 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::db::{MaskableColumns, SortableColumns};
+
+/// ListCarInspections の order_by で指定可能な列（キー列は read_mask に関わらず常に取得される）
+pub static CAR_INSPECTION_SORT_COLUMNS: SortableColumns = SortableColumns {
+    table: "car_inspection",
+    key_columns: &[
+        ("ElectCertMgNo", "text"),
+        ("GrantdateE", "text"),
+        ("GrantdateY", "text"),
+        ("GrantdateM", "text"),
+        ("GrantdateD", "text"),
+    ],
+    fields: &[
+        ("elect_cert_mg_no", &["ElectCertMgNo"]),
+        ("car_id", &["CarId"]),
+        ("car_no", &["CarNo"]),
+        ("grantdate", &["GrantdateY", "GrantdateM", "GrantdateD"]),
+        ("valid_period_expirdate", &["TwodimensionCodeInfoValidPeriodExpirdate"]),
+    ],
+};
+
+/// read_mask 対応の列定義（キー列と非文字列列は常に取得。マスク外の文字列列は CarInspectionModel で空文字になる）
+pub const CAR_INSPECTION_COLUMNS: MaskableColumns = MaskableColumns {
+    fixed: &[
+        "id",
+        "ElectCertMgNo",
+        "GrantdateE",
+        "GrantdateY",
+        "GrantdateM",
+        "GrantdateD",
+        "created_at",
+        "modified_at",
+    ],
+    fixed_fields: &["id", "created", "modified", "pdf_uuid", "json_uuid"],
+    text_columns: &[
+        ("cert_info_import_file_version", "CertInfoImportFileVersion"),
+        ("acceptoutputno", "Acceptoutputno"),
+        ("form_type", "FormType"),
+        ("elect_cert_mg_no", "ElectCertMgNo"),
+        ("car_id", "CarId"),
+        ("elect_cert_publishdate_e", "ElectCertPublishdateE"),
+        ("elect_cert_publishdate_y", "ElectCertPublishdateY"),
+        ("elect_cert_publishdate_m", "ElectCertPublishdateM"),
+        ("elect_cert_publishdate_d", "ElectCertPublishdateD"),
+        ("grantdate_e", "GrantdateE"),
+        ("grantdate_y", "GrantdateY"),
+        ("grantdate_m", "GrantdateM"),
+        ("grantdate_d", "GrantdateD"),
+        ("transpotation_bureauchiefname", "TranspotationBureauchiefName"),
+        ("entry_no_car_no", "EntryNoCarNo"),
+        ("reggrantdate_e", "ReggrantdateE"),
+        ("reggrantdate_y", "ReggrantdateY"),
+        ("reggrantdate_m", "ReggrantdateM"),
+        ("reggrantdate_d", "ReggrantdateD"),
+        ("firstregistdate_e", "FirstregistdateE"),
+        ("firstregistdate_y", "FirstregistdateY"),
+        ("firstregistdate_m", "FirstregistdateM"),
+        ("car_name", "CarName"),
+        ("car_name_code", "CarNameCode"),
+        ("car_no", "CarNo"),
+        ("model", "Model"),
+        ("engine_model", "EngineModel"),
+        ("ownername_low_level_char", "OwnernameLowLevelChar"),
+        ("ownername_high_level_char", "OwnernameHighLevelChar"),
+        ("owner_address_char", "OwnerAddressChar"),
+        ("owner_address_num_value", "OwnerAddressNumValue"),
+        ("owner_address_code", "OwnerAddressCode"),
+        ("username_low_level_char", "UsernameLowLevelChar"),
+        ("username_high_level_char", "UsernameHighLevelChar"),
+        ("user_address_char", "UserAddressChar"),
+        ("user_address_num_value", "UserAddressNumValue"),
+        ("user_address_code", "UserAddressCode"),
+        ("useheadqrter_char", "UseheadqrterChar"),
+        ("useheadqrter_num_value", "UseheadqrterNumValue"),
+        ("useheadqrter_code", "UseheadqrterCode"),
+        ("car_kind", "CarKind"),
+        ("use", "Use"),
+        ("private_business", "PrivateBusiness"),
+        ("car_shape", "CarShape"),
+        ("car_shape_code", "CarShapeCode"),
+        ("note_cap", "NoteCap"),
+        ("cap", "Cap"),
+        ("note_maxloadage", "NoteMaxloadage"),
+        ("maxloadage", "Maxloadage"),
+        ("note_car_wgt", "NoteCarWgt"),
+        ("car_wgt", "CarWgt"),
+        ("note_car_total_wgt", "NoteCarTotalWgt"),
+        ("car_total_wgt", "CarTotalWgt"),
+        ("note_length", "NoteLength"),
+        ("length", "Length"),
+        ("note_width", "NoteWidth"),
+        ("width", "Width"),
+        ("note_height", "NoteHeight"),
+        ("height", "Height"),
+        ("ff_ax_wgt", "FfAxWgt"),
+        ("fr_ax_wgt", "FrAxWgt"),
+        ("rf_ax_wgt", "RfAxWgt"),
+        ("rr_ax_wgt", "RrAxWgt"),
+        ("displacement", "Displacement"),
+        ("fuel_class", "FuelClass"),
+        ("model_specify_no", "ModelSpecifyNo"),
+        ("classify_around_no", "ClassifyAroundNo"),
+        ("valid_period_expirdate_e", "ValidPeriodExpirdateE"),
+        ("valid_period_expirdate_y", "ValidPeriodExpirdateY"),
+        ("valid_period_expirdate_m", "ValidPeriodExpirdateM"),
+        ("valid_period_expirdate_d", "ValidPeriodExpirdateD"),
+        ("note_info", "NoteInfo"),
+        ("twodimension_code_info_entry_no_car_no", "TwodimensionCodeInfoEntryNoCarNo"),
+        ("twodimension_code_info_car_no", "TwodimensionCodeInfoCarNo"),
+        ("twodimension_code_info_valid_period_expirdate", "TwodimensionCodeInfoValidPeriodExpirdate"),
+        ("twodimension_code_info_model", "TwodimensionCodeInfoModel"),
+        ("twodimension_code_info_model_specify_no_classify_around_no", "TwodimensionCodeInfoModelSpecifyNoClassifyAroundNo"),
+        ("twodimension_code_info_char_info", "TwodimensionCodeInfoCharInfo"),
+        ("twodimension_code_info_engine_model", "TwodimensionCodeInfoEngineModel"),
+        ("twodimension_code_info_car_no_stamp_place", "TwodimensionCodeInfoCarNoStampPlace"),
+        ("twodimension_code_info_firstregistdate", "TwodimensionCodeInfoFirstregistdate"),
+        ("twodimension_code_info_ff_ax_wgt", "TwodimensionCodeInfoFfAxWgt"),
+        ("twodimension_code_info_fr_ax_wgt", "TwodimensionCodeInfoFrAxWgt"),
+        ("twodimension_code_info_rf_ax_wgt", "TwodimensionCodeInfoRfAxWgt"),
+        ("twodimension_code_info_rr_ax_wgt", "TwodimensionCodeInfoRrAxWgt"),
+        ("twodimension_code_info_noise_reg", "TwodimensionCodeInfoNoiseReg"),
+        ("twodimension_code_info_near_noise_reg", "TwodimensionCodeInfoNearNoiseReg"),
+        ("twodimension_code_info_drive_method", "TwodimensionCodeInfoDriveMethod"),
+        ("twodimension_code_info_opacimeter_meas_car", "TwodimensionCodeInfoOpacimeterMeasCar"),
+        ("twodimension_code_info_nox_pm_meas_mode", "TwodimensionCodeInfoNoxPmMeasMode"),
+        ("twodimension_code_info_nox_value", "TwodimensionCodeInfoNoxValue"),
+        ("twodimension_code_info_pm_value", "TwodimensionCodeInfoPmValue"),
+        ("twodimension_code_info_safe_std_date", "TwodimensionCodeInfoSafeStdDate"),
+        ("twodimension_code_info_fuel_class_code", "TwodimensionCodeInfoFuelClassCode"),
+        ("regist_car_light_car", "RegistCarLightCar"),
+    ],
+};
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct CarInspectionModel {
     pub id: i32,
-    #[sqlx(rename = "CertInfoImportFileVersion")]
+    #[sqlx(rename = "CertInfoImportFileVersion", default)]
     pub cert_info_import_file_version: String,
-    #[sqlx(rename = "Acceptoutputno")]
+    #[sqlx(rename = "Acceptoutputno", default)]
     pub acceptoutputno: String,
-    #[sqlx(rename = "FormType")]
+    #[sqlx(rename = "FormType", default)]
     pub form_type: String,
     #[sqlx(rename = "ElectCertMgNo")]
     pub elect_cert_mg_no: String,
-    #[sqlx(rename = "CarId")]
+    #[sqlx(rename = "CarId", default)]
     pub car_id: String,
 
     // 電子証明書発行日
-    #[sqlx(rename = "ElectCertPublishdateE")]
+    #[sqlx(rename = "ElectCertPublishdateE", default)]
     pub elect_cert_publishdate_e: String,
-    #[sqlx(rename = "ElectCertPublishdateY")]
+    #[sqlx(rename = "ElectCertPublishdateY", default)]
     pub elect_cert_publishdate_y: String,
-    #[sqlx(rename = "ElectCertPublishdateM")]
+    #[sqlx(rename = "ElectCertPublishdateM", default)]
     pub elect_cert_publishdate_m: String,
-    #[sqlx(rename = "ElectCertPublishdateD")]
+    #[sqlx(rename = "ElectCertPublishdateD", default)]
     pub elect_cert_publishdate_d: String,
 
     // 交付日
@@ -35,201 +168,201 @@ pub struct CarInspectionModel {
     #[sqlx(rename = "GrantdateD")]
     pub grantdate_d: String,
 
-    #[sqlx(rename = "TranspotationBureauchiefName")]
+    #[sqlx(rename = "TranspotationBureauchiefName", default)]
     pub transpotation_bureauchiefname: String,
-    #[sqlx(rename = "EntryNoCarNo")]
+    #[sqlx(rename = "EntryNoCarNo", default)]
     pub entry_no_car_no: String,
 
     // 登録年月日
-    #[sqlx(rename = "ReggrantdateE")]
+    #[sqlx(rename = "ReggrantdateE", default)]
     pub reggrantdate_e: String,
-    #[sqlx(rename = "ReggrantdateY")]
+    #[sqlx(rename = "ReggrantdateY", default)]
     pub reggrantdate_y: String,
-    #[sqlx(rename = "ReggrantdateM")]
+    #[sqlx(rename = "ReggrantdateM", default)]
     pub reggrantdate_m: String,
-    #[sqlx(rename = "ReggrantdateD")]
+    #[sqlx(rename = "ReggrantdateD", default)]
     pub reggrantdate_d: String,
 
     // 初度登録年月
-    #[sqlx(rename = "FirstregistdateE")]
+    #[sqlx(rename = "FirstregistdateE", default)]
     pub firstregistdate_e: String,
-    #[sqlx(rename = "FirstregistdateY")]
+    #[sqlx(rename = "FirstregistdateY", default)]
     pub firstregistdate_y: String,
-    #[sqlx(rename = "FirstregistdateM")]
+    #[sqlx(rename = "FirstregistdateM", default)]
     pub firstregistdate_m: String,
 
     // 車両情報
-    #[sqlx(rename = "CarName")]
+    #[sqlx(rename = "CarName", default)]
     pub car_name: String,
-    #[sqlx(rename = "CarNameCode")]
+    #[sqlx(rename = "CarNameCode", default)]
     pub car_name_code: String,
-    #[sqlx(rename = "CarNo")]
+    #[sqlx(rename = "CarNo", default)]
     pub car_no: String,
-    #[sqlx(rename = "Model")]
+    #[sqlx(rename = "Model", default)]
     pub model: String,
-    #[sqlx(rename = "EngineModel")]
+    #[sqlx(rename = "EngineModel", default)]
     pub engine_model: String,
 
     // 所有者情報
-    #[sqlx(rename = "OwnernameLowLevelChar")]
+    #[sqlx(rename = "OwnernameLowLevelChar", default)]
     pub ownername_low_level_char: String,
-    #[sqlx(rename = "OwnernameHighLevelChar")]
+    #[sqlx(rename = "OwnernameHighLevelChar", default)]
     pub ownername_high_level_char: String,
-    #[sqlx(rename = "OwnerAddressChar")]
+    #[sqlx(rename = "OwnerAddressChar", default)]
     pub owner_address_char: String,
-    #[sqlx(rename = "OwnerAddressNumValue")]
+    #[sqlx(rename = "OwnerAddressNumValue", default)]
     pub owner_address_num_value: String,
-    #[sqlx(rename = "OwnerAddressCode")]
+    #[sqlx(rename = "OwnerAddressCode", default)]
     pub owner_address_code: String,
 
     // 使用者情報
-    #[sqlx(rename = "UsernameLowLevelChar")]
+    #[sqlx(rename = "UsernameLowLevelChar", default)]
     pub username_low_level_char: String,
-    #[sqlx(rename = "UsernameHighLevelChar")]
+    #[sqlx(rename = "UsernameHighLevelChar", default)]
     pub username_high_level_char: String,
-    #[sqlx(rename = "UserAddressChar")]
+    #[sqlx(rename = "UserAddressChar", default)]
     pub user_address_char: String,
-    #[sqlx(rename = "UserAddressNumValue")]
+    #[sqlx(rename = "UserAddressNumValue", default)]
     pub user_address_num_value: String,
-    #[sqlx(rename = "UserAddressCode")]
+    #[sqlx(rename = "UserAddressCode", default)]
     pub user_address_code: String,
 
     // 使用本拠地
-    #[sqlx(rename = "UseheadqrterChar")]
+    #[sqlx(rename = "UseheadqrterChar", default)]
     pub useheadqrter_char: String,
-    #[sqlx(rename = "UseheadqrterNumValue")]
+    #[sqlx(rename = "UseheadqrterNumValue", default)]
     pub useheadqrter_num_value: String,
-    #[sqlx(rename = "UseheadqrterCode")]
+    #[sqlx(rename = "UseheadqrterCode", default)]
     pub useheadqrter_code: String,
 
     // 車両詳細
-    #[sqlx(rename = "CarKind")]
+    #[sqlx(rename = "CarKind", default)]
     pub car_kind: String,
-    #[sqlx(rename = "Use")]
+    #[sqlx(rename = "Use", default)]
     pub use_field: String,
-    #[sqlx(rename = "PrivateBusiness")]
+    #[sqlx(rename = "PrivateBusiness", default)]
     pub private_business: String,
-    #[sqlx(rename = "CarShape")]
+    #[sqlx(rename = "CarShape", default)]
     pub car_shape: String,
-    #[sqlx(rename = "CarShapeCode")]
+    #[sqlx(rename = "CarShapeCode", default)]
     pub car_shape_code: String,
 
     // 定員・積載量
-    #[sqlx(rename = "NoteCap")]
+    #[sqlx(rename = "NoteCap", default)]
     pub note_cap: String,
-    #[sqlx(rename = "Cap")]
+    #[sqlx(rename = "Cap", default)]
     pub cap: String,
-    #[sqlx(rename = "NoteMaxloadage")]
+    #[sqlx(rename = "NoteMaxloadage", default)]
     pub note_maxloadage: String,
-    #[sqlx(rename = "Maxloadage")]
+    #[sqlx(rename = "Maxloadage", default)]
     pub maxloadage: String,
 
     // 重量
-    #[sqlx(rename = "NoteCarWgt")]
+    #[sqlx(rename = "NoteCarWgt", default)]
     pub note_car_wgt: String,
-    #[sqlx(rename = "CarWgt")]
+    #[sqlx(rename = "CarWgt", default)]
     pub car_wgt: String,
-    #[sqlx(rename = "NoteCarTotalWgt")]
+    #[sqlx(rename = "NoteCarTotalWgt", default)]
     pub note_car_total_wgt: String,
-    #[sqlx(rename = "CarTotalWgt")]
+    #[sqlx(rename = "CarTotalWgt", default)]
     pub car_total_wgt: String,
 
     // サイズ
-    #[sqlx(rename = "NoteLength")]
+    #[sqlx(rename = "NoteLength", default)]
     pub note_length: String,
-    #[sqlx(rename = "Length")]
+    #[sqlx(rename = "Length", default)]
     pub length: String,
-    #[sqlx(rename = "NoteWidth")]
+    #[sqlx(rename = "NoteWidth", default)]
     pub note_width: String,
-    #[sqlx(rename = "Width")]
+    #[sqlx(rename = "Width", default)]
     pub width: String,
-    #[sqlx(rename = "NoteHeight")]
+    #[sqlx(rename = "NoteHeight", default)]
     pub note_height: String,
-    #[sqlx(rename = "Height")]
+    #[sqlx(rename = "Height", default)]
     pub height: String,
 
     // 車軸重
-    #[sqlx(rename = "FfAxWgt")]
+    #[sqlx(rename = "FfAxWgt", default)]
     pub ff_ax_wgt: String,
-    #[sqlx(rename = "FrAxWgt")]
+    #[sqlx(rename = "FrAxWgt", default)]
     pub fr_ax_wgt: String,
-    #[sqlx(rename = "RfAxWgt")]
+    #[sqlx(rename = "RfAxWgt", default)]
     pub rf_ax_wgt: String,
-    #[sqlx(rename = "RrAxWgt")]
+    #[sqlx(rename = "RrAxWgt", default)]
     pub rr_ax_wgt: String,
 
     // エンジン
-    #[sqlx(rename = "Displacement")]
+    #[sqlx(rename = "Displacement", default)]
     pub displacement: String,
-    #[sqlx(rename = "FuelClass")]
+    #[sqlx(rename = "FuelClass", default)]
     pub fuel_class: String,
 
     // 型式指定番号・類別区分番号
-    #[sqlx(rename = "ModelSpecifyNo")]
+    #[sqlx(rename = "ModelSpecifyNo", default)]
     pub model_specify_no: String,
-    #[sqlx(rename = "ClassifyAroundNo")]
+    #[sqlx(rename = "ClassifyAroundNo", default)]
     pub classify_around_no: String,
 
     // 有効期限
-    #[sqlx(rename = "ValidPeriodExpirdateE")]
+    #[sqlx(rename = "ValidPeriodExpirdateE", default)]
     pub valid_period_expirdate_e: String,
-    #[sqlx(rename = "ValidPeriodExpirdateY")]
+    #[sqlx(rename = "ValidPeriodExpirdateY", default)]
     pub valid_period_expirdate_y: String,
-    #[sqlx(rename = "ValidPeriodExpirdateM")]
+    #[sqlx(rename = "ValidPeriodExpirdateM", default)]
     pub valid_period_expirdate_m: String,
-    #[sqlx(rename = "ValidPeriodExpirdateD")]
+    #[sqlx(rename = "ValidPeriodExpirdateD", default)]
     pub valid_period_expirdate_d: String,
 
-    #[sqlx(rename = "NoteInfo")]
+    #[sqlx(rename = "NoteInfo", default)]
     pub note_info: String,
 
     // 二次元コード情報
-    #[sqlx(rename = "TwodimensionCodeInfoEntryNoCarNo")]
+    #[sqlx(rename = "TwodimensionCodeInfoEntryNoCarNo", default)]
     pub twodimension_code_info_entry_no_car_no: String,
-    #[sqlx(rename = "TwodimensionCodeInfoCarNo")]
+    #[sqlx(rename = "TwodimensionCodeInfoCarNo", default)]
     pub twodimension_code_info_car_no: String,
-    #[sqlx(rename = "TwodimensionCodeInfoValidPeriodExpirdate")]
+    #[sqlx(rename = "TwodimensionCodeInfoValidPeriodExpirdate", default)]
     pub twodimension_code_info_valid_period_expirdate: String,
-    #[sqlx(rename = "TwodimensionCodeInfoModel")]
+    #[sqlx(rename = "TwodimensionCodeInfoModel", default)]
     pub twodimension_code_info_model: String,
-    #[sqlx(rename = "TwodimensionCodeInfoModelSpecifyNoClassifyAroundNo")]
+    #[sqlx(rename = "TwodimensionCodeInfoModelSpecifyNoClassifyAroundNo", default)]
     pub twodimension_code_info_model_specify_no_classify_around_no: String,
-    #[sqlx(rename = "TwodimensionCodeInfoCharInfo")]
+    #[sqlx(rename = "TwodimensionCodeInfoCharInfo", default)]
     pub twodimension_code_info_char_info: String,
-    #[sqlx(rename = "TwodimensionCodeInfoEngineModel")]
+    #[sqlx(rename = "TwodimensionCodeInfoEngineModel", default)]
     pub twodimension_code_info_engine_model: String,
-    #[sqlx(rename = "TwodimensionCodeInfoCarNoStampPlace")]
+    #[sqlx(rename = "TwodimensionCodeInfoCarNoStampPlace", default)]
     pub twodimension_code_info_car_no_stamp_place: String,
-    #[sqlx(rename = "TwodimensionCodeInfoFirstregistdate")]
+    #[sqlx(rename = "TwodimensionCodeInfoFirstregistdate", default)]
     pub twodimension_code_info_firstregistdate: String,
-    #[sqlx(rename = "TwodimensionCodeInfoFfAxWgt")]
+    #[sqlx(rename = "TwodimensionCodeInfoFfAxWgt", default)]
     pub twodimension_code_info_ff_ax_wgt: String,
-    #[sqlx(rename = "TwodimensionCodeInfoFrAxWgt")]
+    #[sqlx(rename = "TwodimensionCodeInfoFrAxWgt", default)]
     pub twodimension_code_info_fr_ax_wgt: String,
-    #[sqlx(rename = "TwodimensionCodeInfoRfAxWgt")]
+    #[sqlx(rename = "TwodimensionCodeInfoRfAxWgt", default)]
     pub twodimension_code_info_rf_ax_wgt: String,
-    #[sqlx(rename = "TwodimensionCodeInfoRrAxWgt")]
+    #[sqlx(rename = "TwodimensionCodeInfoRrAxWgt", default)]
     pub twodimension_code_info_rr_ax_wgt: String,
-    #[sqlx(rename = "TwodimensionCodeInfoNoiseReg")]
+    #[sqlx(rename = "TwodimensionCodeInfoNoiseReg", default)]
     pub twodimension_code_info_noise_reg: String,
-    #[sqlx(rename = "TwodimensionCodeInfoNearNoiseReg")]
+    #[sqlx(rename = "TwodimensionCodeInfoNearNoiseReg", default)]
     pub twodimension_code_info_near_noise_reg: String,
-    #[sqlx(rename = "TwodimensionCodeInfoDriveMethod")]
+    #[sqlx(rename = "TwodimensionCodeInfoDriveMethod", default)]
     pub twodimension_code_info_drive_method: String,
-    #[sqlx(rename = "TwodimensionCodeInfoOpacimeterMeasCar")]
+    #[sqlx(rename = "TwodimensionCodeInfoOpacimeterMeasCar", default)]
     pub twodimension_code_info_opacimeter_meas_car: String,
-    #[sqlx(rename = "TwodimensionCodeInfoNoxPmMeasMode")]
+    #[sqlx(rename = "TwodimensionCodeInfoNoxPmMeasMode", default)]
     pub twodimension_code_info_nox_pm_meas_mode: String,
-    #[sqlx(rename = "TwodimensionCodeInfoNoxValue")]
+    #[sqlx(rename = "TwodimensionCodeInfoNoxValue", default)]
     pub twodimension_code_info_nox_value: String,
-    #[sqlx(rename = "TwodimensionCodeInfoPmValue")]
+    #[sqlx(rename = "TwodimensionCodeInfoPmValue", default)]
     pub twodimension_code_info_pm_value: String,
-    #[sqlx(rename = "TwodimensionCodeInfoSafeStdDate")]
+    #[sqlx(rename = "TwodimensionCodeInfoSafeStdDate", default)]
     pub twodimension_code_info_safe_std_date: String,
-    #[sqlx(rename = "TwodimensionCodeInfoFuelClassCode")]
+    #[sqlx(rename = "TwodimensionCodeInfoFuelClassCode", default)]
     pub twodimension_code_info_fuel_class_code: String,
 
-    #[sqlx(rename = "RegistCarLightCar")]
+    #[sqlx(rename = "RegistCarLightCar", default)]
     pub regist_car_light_car: String,
 
     // メタ情報
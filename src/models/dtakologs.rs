@@ -1,6 +1,22 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::db::SortableColumns;
+
+/// ListAll の order_by で指定可能な列（NOT NULL 列のみ）
+pub static DTAKOLOG_SORT_COLUMNS: SortableColumns = SortableColumns {
+    table: "dtakologs",
+    key_columns: &[("data_date_time", "text"), ("vehicle_cd", "int4")],
+    fields: &[
+        ("data_date_time", &["data_date_time"]),
+        ("vehicle_cd", &["vehicle_cd"]),
+        ("vehicle_name", &["vehicle_name"]),
+        ("branch_cd", &["branch_cd"]),
+        ("driver_cd", &["driver_cd"]),
+        ("speed", &["speed"]),
+    ],
+};
+
 /// 運行ログモデル
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct DtakologModel {
@@ -14,6 +14,17 @@ pub struct DvrNotificationModel {
     pub gcs_key: Option<String>,
     pub file_size_bytes: Option<i64>,
     pub download_status: Option<String>,
+    pub id: i64,
+    pub severity: String,
+    pub acknowledged_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub acknowledged_by: Option<String>,
+    pub acknowledgement_note: Option<String>,
+    /// 保存した mp4 の files.uuid
+    pub file_uuid: Option<String>,
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub download_error: Option<String>,
+    pub weather: Option<sqlx::types::Json<crate::weather::WeatherConditions>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl DvrNotificationModel {
@@ -29,4 +40,22 @@ impl DvrNotificationModel {
             mp4_url: self.mp4_url.clone(),
         }
     }
+
+    pub fn to_record_proto(&self) -> crate::proto::dvr_notifications::DvrNotificationRecord {
+        crate::proto::dvr_notifications::DvrNotificationRecord {
+            id: self.id,
+            notification: Some(self.to_proto()),
+            severity: self.severity.clone(),
+            download_status: self.download_status.clone().unwrap_or_else(|| "pending".to_string()),
+            acknowledged_at: self.acknowledged_at.map(|t| t.to_rfc3339()),
+            acknowledged_by: self.acknowledged_by.clone(),
+            acknowledgement_note: self.acknowledgement_note.clone(),
+            video_file_uuid: self.file_uuid.clone(),
+            video_size_bytes: self.file_size_bytes,
+            archived_at: self.archived_at.map(|t| t.to_rfc3339()),
+            download_error: self.download_error.clone(),
+            weather: self.weather.as_ref().map(|w| w.0.to_proto()),
+            created_at: self.created_at.to_rfc3339(),
+        }
+    }
 }
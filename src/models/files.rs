@@ -1,6 +1,19 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::db::SortableColumns;
+
+/// ListFiles の order_by で指定可能な列
+pub static FILE_SORT_COLUMNS: SortableColumns = SortableColumns {
+    table: "files",
+    key_columns: &[("uuid", "uuid")],
+    fields: &[
+        ("created_at", &["created_at"]),
+        ("filename", &["filename"]),
+        ("type", &["type"]),
+    ],
+};
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct FileModel {
     pub uuid: String,
@@ -19,6 +32,20 @@ pub struct FileModel {
     pub promoted_to_standard_at: Option<String>,
 }
 
+/// 分割アップロードの途中状態（file_uploads）
+#[derive(Debug, Clone, FromRow)]
+pub struct FileUploadModel {
+    pub uuid: String,
+    pub filename: String,
+    pub file_type: String,
+    pub total_size: i64,
+    pub received_bytes: i64,
+    pub s3_key: String,
+    /// NULL は署名付き URL への PUT（GetUploadUrl）
+    pub session_id: Option<String>,
+    pub parts: sqlx::types::Json<Vec<crate::storage::UploadedPart>>,
+}
+
 /// Result of recording file access
 #[derive(Debug, Clone, FromRow)]
 pub struct FileAccessResult {
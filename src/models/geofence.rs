@@ -0,0 +1,108 @@
+use sqlx::types::Json;
+use sqlx::FromRow;
+
+use crate::geocoding::GeoPoint;
+use crate::geofencing::GeofenceShape;
+use crate::proto::geofences::{Circle, Geofence, GeofenceEvent, LatLng, Polygon};
+
+/// geofences テーブル
+#[derive(Debug, Clone, FromRow)]
+pub struct GeofenceModel {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub shape: String,
+    pub center_lat: Option<f64>,
+    pub center_lon: Option<f64>,
+    pub radius_m: Option<f64>,
+    /// [[lat, lon], ...]
+    pub polygon: Option<Json<Vec<[f64; 2]>>>,
+    pub notify: bool,
+    pub active: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn lat_lng(point: GeoPoint) -> LatLng {
+    LatLng {
+        latitude: point.lat,
+        longitude: point.lon,
+    }
+}
+
+impl GeofenceModel {
+    /// 判定に使う形（列が壊れていれば None）
+    pub fn geometry(&self) -> Option<GeofenceShape> {
+        match self.shape.as_str() {
+            "circle" => Some(GeofenceShape::Circle {
+                center: GeoPoint::new(self.center_lat?, self.center_lon?)?,
+                radius_m: self.radius_m?,
+            }),
+            "polygon" => {
+                let vertices = self.polygon.as_ref()?;
+                let vertices: Option<Vec<GeoPoint>> =
+                    vertices.iter().map(|[lat, lon]| GeoPoint::new(*lat, *lon)).collect();
+                Some(GeofenceShape::Polygon(vertices?))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_proto(&self) -> Geofence {
+        let (circle, polygon) = match self.geometry() {
+            Some(GeofenceShape::Circle { center, radius_m }) => (
+                Some(Circle {
+                    center: Some(lat_lng(center)),
+                    radius_m,
+                }),
+                None,
+            ),
+            Some(GeofenceShape::Polygon(vertices)) => (
+                None,
+                Some(Polygon {
+                    vertices: vertices.into_iter().map(lat_lng).collect(),
+                }),
+            ),
+            None => (None, None),
+        };
+        Geofence {
+            id: self.id.to_string(),
+            name: self.name.clone(),
+            circle,
+            polygon,
+            notify: self.notify,
+            active: self.active,
+            created_at: self.created_at.to_rfc3339(),
+            updated_at: self.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// geofence_events テーブル（geofences.name を JOIN）
+#[derive(Debug, Clone, FromRow)]
+pub struct GeofenceEventModel {
+    pub id: i64,
+    pub geofence_id: uuid::Uuid,
+    pub geofence_name: String,
+    pub vehicle_cd: i32,
+    pub event_type: String,
+    pub data_date_time: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl GeofenceEventModel {
+    pub fn to_proto(&self) -> GeofenceEvent {
+        GeofenceEvent {
+            id: self.id,
+            geofence_id: self.geofence_id.to_string(),
+            geofence_name: self.geofence_name.clone(),
+            vehicle_cd: self.vehicle_cd,
+            event_type: self.event_type.clone(),
+            data_date_time: self.data_date_time.clone(),
+            latitude: self.latitude,
+            longitude: self.longitude,
+            created_at: self.created_at.to_rfc3339(),
+        }
+    }
+}
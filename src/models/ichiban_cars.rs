@@ -18,6 +18,25 @@ pub struct CarInsSheetIchibanCarsAModel {
     pub grantdate_d: String,
 }
 
+/// ichiban_cars table model（dtako_ids は dtako_cars_ichiban_cars から集約）
+#[derive(Debug, Clone, FromRow)]
+pub struct IchibanCarModel {
+    pub id: String,
+    pub id4: String,
+    pub name: Option<String>,
+    pub name_r: Option<String>,
+    pub shashu: String,
+    pub sekisai: Option<f64>,
+    pub reg_date: Option<String>,
+    pub parch_date: Option<String>,
+    pub scrap_date: Option<String>,
+    pub bumon_code_id: Option<String>,
+    pub driver_id: Option<String>,
+    pub dtako_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub modified_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// dtako_cars_ichiban_cars table model
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct DtakoCarsIchibanCarsModel {
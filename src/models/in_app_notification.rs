@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// notifications テーブル（アプリ内通知）
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InAppNotificationModel {
+    pub id: i64,
+    pub template: String,
+    pub title: String,
+    pub body: String,
+    pub read_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl InAppNotificationModel {
+    pub fn to_proto(&self) -> crate::proto::notifications::InAppNotification {
+        crate::proto::notifications::InAppNotification {
+            id: self.id,
+            template: self.template.clone(),
+            title: self.title.clone(),
+            body: self.body.clone(),
+            read: self.read_at.is_some(),
+            created_at: self.created_at.to_rfc3339(),
+            read_at: self.read_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
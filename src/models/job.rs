@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// jobs テーブル（JobsService 用。ワーカーの claim 結果は `jobs::Job`）
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct JobModel {
+    pub id: i64,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub dedupe_key: Option<String>,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: chrono::DateTime<chrono::Utc>,
+    pub locked_by: Option<String>,
+    pub locked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl JobModel {
+    pub fn to_proto(&self) -> crate::proto::jobs::Job {
+        crate::proto::jobs::Job {
+            id: self.id,
+            kind: self.kind.clone(),
+            status: self.status.clone(),
+            payload: self.payload.to_string(),
+            dedupe_key: self.dedupe_key.clone(),
+            attempts: self.attempts,
+            max_attempts: self.max_attempts,
+            run_at: self.run_at.to_rfc3339(),
+            locked_by: self.locked_by.clone(),
+            locked_at: self.locked_at.map(|t| t.to_rfc3339()),
+            last_error: self.last_error.clone(),
+            created_at: self.created_at.to_rfc3339(),
+            updated_at: self.updated_at.to_rfc3339(),
+            finished_at: self.finished_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
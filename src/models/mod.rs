@@ -12,6 +12,13 @@ pub mod organization_model;
 pub mod password_credential;
 pub mod item;
 pub mod nfc_tag;
+pub mod job;
+pub mod in_app_notification;
+pub mod notification_delivery;
+pub mod notification_webhook;
+pub mod webhook_delivery;
+pub mod webhook_endpoint;
+pub mod geofence;
 
 pub use files::*;
 pub use car_inspection::*;
@@ -27,3 +34,10 @@ pub use organization_model::*;
 pub use password_credential::*;
 pub use item::*;
 pub use nfc_tag::*;
+pub use job::*;
+pub use in_app_notification::*;
+pub use notification_delivery::*;
+pub use notification_webhook::*;
+pub use webhook_delivery::*;
+pub use webhook_endpoint::*;
+pub use geofence::*;
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// notification_deliveries テーブル
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct NotificationDeliveryModel {
+    pub id: i64,
+    pub channel: String,
+    pub template: String,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub sent_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl NotificationDeliveryModel {
+    pub fn to_proto(&self) -> crate::proto::notifications::NotificationDelivery {
+        crate::proto::notifications::NotificationDelivery {
+            id: self.id,
+            channel: self.channel.clone(),
+            template: self.template.clone(),
+            recipient: self.recipient.clone(),
+            subject: self.subject.clone(),
+            body: self.body.clone(),
+            status: self.status.clone(),
+            attempts: self.attempts,
+            last_error: self.last_error.clone(),
+            created_at: self.created_at.to_rfc3339(),
+            sent_at: self.sent_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// notification_webhooks テーブル（URL は暗号化のまま）
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct NotificationWebhookModel {
+    pub id: uuid::Uuid,
+    pub provider: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub url_encrypted: String,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl NotificationWebhookModel {
+    /// url_hint は復号した URL から webhook_url_hint で作る
+    pub fn to_proto(&self, url_hint: String) -> crate::proto::notifications::NotificationWebhook {
+        crate::proto::notifications::NotificationWebhook {
+            id: self.id.to_string(),
+            provider: self.provider.clone(),
+            name: self.name.clone(),
+            url_hint,
+            enabled: self.enabled,
+            created_at: self.created_at.to_rfc3339(),
+            updated_at: self.updated_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// webhook_deliveries テーブル
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WebhookDeliveryModel {
+    pub id: i64,
+    pub endpoint_id: uuid::Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// webhook_delivery_attempts テーブル
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WebhookDeliveryAttemptModel {
+    pub delivery_id: i64,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub response_body: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl WebhookDeliveryModel {
+    pub fn to_proto(&self, attempts: &[WebhookDeliveryAttemptModel]) -> crate::proto::webhooks::WebhookDelivery {
+        crate::proto::webhooks::WebhookDelivery {
+            id: self.id,
+            webhook_id: self.endpoint_id.to_string(),
+            event_type: self.event_type.clone(),
+            payload: self.payload.to_string(),
+            status: self.status.clone(),
+            attempts: self.attempts,
+            last_status_code: self.last_status_code,
+            last_error: self.last_error.clone(),
+            created_at: self.created_at.to_rfc3339(),
+            delivered_at: self.delivered_at.map(|t| t.to_rfc3339()),
+            attempt_log: attempts
+                .iter()
+                .filter(|a| a.delivery_id == self.id)
+                .map(WebhookDeliveryAttemptModel::to_proto)
+                .collect(),
+        }
+    }
+}
+
+impl WebhookDeliveryAttemptModel {
+    pub fn to_proto(&self) -> crate::proto::webhooks::WebhookDeliveryAttempt {
+        crate::proto::webhooks::WebhookDeliveryAttempt {
+            attempt: self.attempt,
+            status_code: self.status_code,
+            error: self.error.clone(),
+            response_body: self.response_body.clone(),
+            duration_ms: self.duration_ms,
+            created_at: self.created_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// webhook_endpoints テーブル（署名キーは暗号化のまま、返さない）
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WebhookEndpointModel {
+    pub id: uuid::Uuid,
+    pub url: String,
+    pub description: String,
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub consecutive_failures: i32,
+    pub disabled_reason: Option<String>,
+    pub last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl WebhookEndpointModel {
+    pub fn to_proto(&self) -> crate::proto::webhooks::Webhook {
+        crate::proto::webhooks::Webhook {
+            id: self.id.to_string(),
+            url: self.url.clone(),
+            description: self.description.clone(),
+            event_types: self.event_types.clone(),
+            enabled: self.enabled,
+            created_at: self.created_at.to_rfc3339(),
+            updated_at: self.updated_at.to_rfc3339(),
+            consecutive_failures: self.consecutive_failures,
+            disabled_reason: self.disabled_reason.clone(),
+            last_success_at: self.last_success_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
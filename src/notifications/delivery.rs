@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+
+use crate::db::OrgScopedPool;
+use crate::jobs::{Job, JobHandler};
+
+/// notification_deliveries 1行を送る job
+pub const NOTIFICATION_DELIVER_JOB: &str = "notifications.deliver";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliverPayload {
+    pub delivery_id: i64,
+}
+
+/// 送信する通知（描画済み）
+#[derive(Debug, Clone, FromRow)]
+pub struct OutgoingMessage {
+    pub id: i64,
+    pub organization_id: String,
+    pub channel: String,
+    /// テンプレートのキー（通知の種類）
+    pub template: String,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// 組織ごとの送信設定（notification_settings、未登録なら全て None）
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct NotificationSettings {
+    pub email_from_address: Option<String>,
+    pub email_from_name: Option<String>,
+    pub email_reply_to: Option<String>,
+    /// 通知に使う LINE WORKS Bot（bot_configs.id）
+    pub lineworks_bot_config_id: Option<String>,
+    /// SMS の月間上限（0 なら SMS は送らない）
+    pub sms_monthly_limit: i32,
+}
+
+/// 送らないことが確定した宛先（月間上限超過など）。チャネルが返すと再試行せず skipped にする
+#[derive(Debug)]
+pub struct DeliverySkipped(pub String);
+
+impl std::fmt::Display for DeliverySkipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "skipped: {}", self.0)
+    }
+}
+
+impl std::error::Error for DeliverySkipped {}
+
+/// 通知チャネル（メール、LINE WORKS 等）
+#[tonic::async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Err を返すと job のバックオフで再送される
+    async fn send(&self, message: &OutgoingMessage, settings: &NotificationSettings) -> anyhow::Result<()>;
+}
+
+/// notification_deliveries を送信して結果を記録する job ハンドラ
+pub struct NotificationJobHandler {
+    pool: PgPool,
+    channels: HashMap<&'static str, Arc<dyn NotificationChannel>>,
+}
+
+impl NotificationJobHandler {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            channels: HashMap::new(),
+        }
+    }
+
+    pub fn channel(mut self, name: &'static str, channel: impl NotificationChannel + 'static) -> Self {
+        self.channels.insert(name, Arc::new(channel));
+        self
+    }
+
+    /// 有効なチャネル（Notifier に渡す）
+    pub fn channel_names(&self) -> Vec<&'static str> {
+        self.channels.keys().copied().collect()
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for NotificationJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: DeliverPayload = job.payload()?;
+
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        let message: Option<OutgoingMessage> = sqlx::query_as(
+            r#"
+            SELECT id, organization_id::text AS organization_id, channel, template, recipient, subject, body
+            FROM notification_deliveries
+            WHERE id = $1 AND status <> 'sent'
+            "#,
+        )
+        .bind(payload.delivery_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some(message) = message else {
+            return Ok(());
+        };
+        let settings: NotificationSettings = sqlx::query_as(
+            r#"
+            SELECT email_from_address, email_from_name, email_reply_to,
+                   lineworks_bot_config_id::text AS lineworks_bot_config_id, sms_monthly_limit
+            FROM notification_settings
+            "#,
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .unwrap_or_default();
+
+        let result = match self.channels.get(message.channel.as_str()) {
+            Some(channel) => channel.send(&message, &settings).await,
+            None => Err(anyhow::anyhow!("Notification channel {} is not configured", message.channel)),
+        };
+
+        match result {
+            Ok(()) => {
+                sqlx::query(
+                    r#"
+                    UPDATE notification_deliveries
+                    SET status = 'sent', attempts = $2, last_error = NULL, updated_at = NOW(), sent_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(message.id)
+                .bind(job.attempts)
+                .execute(&mut *conn)
+                .await?;
+                tracing::debug!("Notification {} sent via {}", message.id, message.channel);
+                Ok(())
+            }
+            Err(err) if err.downcast_ref::<DeliverySkipped>().is_some() => {
+                sqlx::query(
+                    r#"
+                    UPDATE notification_deliveries
+                    SET status = 'skipped', attempts = $2, last_error = $3, updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(message.id)
+                .bind(job.attempts)
+                .bind(err.to_string())
+                .execute(&mut *conn)
+                .await?;
+                tracing::info!("Notification {} via {} {}", message.id, message.channel, err);
+                Ok(())
+            }
+            Err(err) => {
+                // job の再試行が尽きたら failed（以降は RetryJob で再送できる）
+                let status = if job.attempts >= job.max_attempts { "failed" } else { "pending" };
+                sqlx::query(
+                    r#"
+                    UPDATE notification_deliveries
+                    SET status = $2, attempts = $3, last_error = $4, updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(message.id)
+                .bind(status)
+                .bind(job.attempts)
+                .bind(format!("{:#}", err))
+                .execute(&mut *conn)
+                .await?;
+                Err(err)
+            }
+        }
+    }
+}
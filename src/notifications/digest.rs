@@ -0,0 +1,286 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+
+use super::{format_jst, Notification, Notifier, Recipient, DIGEST};
+use crate::db::with_org_transaction;
+use crate::jobs::{Job, JobHandler, ScheduledTaskDef};
+
+/// 日次まとめ（スケジュール実行）
+pub const DAILY_DIGEST_JOB: &str = "notifications.daily_digest";
+/// 週次まとめ（スケジュール実行）
+pub const WEEKLY_DIGEST_JOB: &str = "notifications.weekly_digest";
+
+pub const DAILY_DIGEST_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: DAILY_DIGEST_JOB,
+    description: "前日からの新規車検証・期限間近・失敗した同期・在庫切れ間近を購読者にまとめて通知",
+    default_cron: "0 8 * * *",
+};
+
+pub const WEEKLY_DIGEST_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: WEEKLY_DIGEST_JOB,
+    description: "直近1週間の新規車検証・期限間近・失敗した同期・在庫切れ間近を購読者にまとめて通知",
+    default_cron: "0 8 * * Mon",
+};
+
+/// 失敗を「同期の失敗」としてまとめに載せる job
+const SYNC_JOB_KINDS: &[&str] = &[
+    "cam_files.sync",
+    "cam_files.flickr_upload",
+    "dvr.mp4_download",
+    "files.storage_promotion",
+];
+/// 1セクションに載せる最大行数
+const MAX_SECTION_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    /// notification_digest_subscriptions.frequency の値
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Daily => "日次",
+            Self::Weekly => "週次",
+        }
+    }
+
+    fn period(&self) -> Duration {
+        match self {
+            Self::Daily => Duration::days(1),
+            Self::Weekly => Duration::days(7),
+        }
+    }
+}
+
+/// まとめの内容（各行は表示用に整形済み）
+#[derive(Debug, Default)]
+pub struct DigestSummary {
+    pub new_inspections: Vec<String>,
+    pub expiring: Vec<String>,
+    /// (job の種類, 失敗件数)
+    pub failed_syncs: Vec<(String, i64)>,
+    pub low_stock: Vec<String>,
+}
+
+impl DigestSummary {
+    pub fn is_empty(&self) -> bool {
+        self.new_inspections.is_empty()
+            && self.expiring.is_empty()
+            && self.failed_syncs.is_empty()
+            && self.low_stock.is_empty()
+    }
+
+    pub fn failed_sync_count(&self) -> i64 {
+        self.failed_syncs.iter().map(|(_, count)| count).sum()
+    }
+
+    /// 空でないセクションだけを並べた本文
+    pub fn render(&self) -> String {
+        let failed_syncs: Vec<String> = self
+            .failed_syncs
+            .iter()
+            .map(|(kind, count)| format!("{}: {}件", kind, count))
+            .collect();
+        let sections = [
+            ("新規車検証", "件", self.new_inspections.len() as i64, &self.new_inspections),
+            ("期限切れ・期限間近", "台", self.expiring.len() as i64, &self.expiring),
+            ("失敗した同期", "件", self.failed_sync_count(), &failed_syncs),
+            ("在庫切れ間近の備品", "件", self.low_stock.len() as i64, &self.low_stock),
+        ];
+
+        sections
+            .iter()
+            .filter(|(_, _, _, lines)| !lines.is_empty())
+            .map(|(title, unit, count, lines)| {
+                let mut section = format!("■ {} ({}{})\n", title, count, unit);
+                for line in lines.iter().take(MAX_SECTION_LINES) {
+                    section.push_str(line);
+                    section.push('\n');
+                }
+                if lines.len() > MAX_SECTION_LINES {
+                    section.push_str(&format!("…ほか{}件\n", lines.len() - MAX_SECTION_LINES));
+                }
+                section
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// since 以降のまとめを集める（organization 設定済みのコネクション）
+pub async fn collect_digest(conn: &mut PgConnection, since: DateTime<Utc>) -> Result<DigestSummary, sqlx::Error> {
+    let new_inspections: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT "CarNo", "CarName" FROM car_inspection
+        WHERE created_at >= $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(since)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // ExpiryNotifyJobHandler と同じ対象
+    let expiring: Vec<(String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT "CarNo", "CarName", "TwodimensionCodeInfoValidPeriodExpirdate" FROM car_inspection
+        WHERE "TwodimensionCodeInfoValidPeriodExpirdate" <= to_char(CURRENT_DATE + INTERVAL '30 days', 'YYMMDD')
+        ORDER BY "TwodimensionCodeInfoValidPeriodExpirdate" ASC
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let failed_syncs: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT kind, COUNT(*) FROM jobs
+        WHERE status = 'failed' AND kind = ANY($1) AND finished_at >= $2
+        GROUP BY kind
+        ORDER BY kind
+        "#,
+    )
+    .bind(SYNC_JOB_KINDS)
+    .bind(since)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let low_stock: Vec<(String, i32)> = sqlx::query_as(
+        r#"
+        SELECT name, quantity FROM items
+        WHERE owner_type = 'org' AND item_type = 'item'
+          AND quantity <= COALESCE((SELECT digest_low_stock_threshold FROM notification_settings), 0)
+        ORDER BY quantity, name
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(DigestSummary {
+        new_inspections: new_inspections
+            .into_iter()
+            .map(|(car_no, car_name)| format!("{} {}", car_no, car_name))
+            .collect(),
+        expiring: expiring
+            .into_iter()
+            .map(|(car_no, car_name, expiry)| format!("{} {} 期限: {}", car_no, car_name, expiry))
+            .collect(),
+        failed_syncs,
+        low_stock: low_stock
+            .into_iter()
+            .map(|(name, quantity)| format!("{} 残り{}", name, quantity))
+            .collect(),
+    })
+}
+
+/// 購読者ごとにまとめを1通（メール + アプリ内）送る job ハンドラ
+pub struct DigestJobHandler {
+    pool: PgPool,
+    notifier: Notifier,
+    frequency: DigestFrequency,
+}
+
+impl DigestJobHandler {
+    pub fn new(pool: PgPool, notifier: Notifier, frequency: DigestFrequency) -> Self {
+        Self {
+            pool,
+            notifier,
+            frequency,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for DigestJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let queued = with_org_transaction(&self.pool, &job.organization_id, |mut tx| async move {
+            let subscribers: Vec<(String, Option<String>)> =
+                sqlx::query_as("SELECT user_id, email FROM org_digest_recipients($1::uuid, $2)")
+                    .bind(&job.organization_id)
+                    .bind(self.frequency.as_str())
+                    .fetch_all(&mut *tx)
+                    .await?;
+            if subscribers.is_empty() {
+                return Ok((tx, 0));
+            }
+
+            let since = Utc::now() - self.frequency.period();
+            let summary = collect_digest(&mut tx, since).await?;
+            if summary.is_empty() {
+                tracing::debug!("Nothing to digest for {} ({})", job.organization_id, self.frequency.as_str());
+                return Ok((tx, 0));
+            }
+
+            let mut notification = Notification::new(DIGEST)
+                .var("period", self.frequency.label())
+                .var("since", format_jst(since))
+                .var("summary", summary.render())
+                .var("new_inspection_count", summary.new_inspections.len())
+                .var("expiring_count", summary.expiring.len())
+                .var("failed_sync_count", summary.failed_sync_count())
+                .var("low_stock_count", summary.low_stock.len());
+            for (user_id, email) in &subscribers {
+                notification = notification.to(Recipient::in_app(user_id));
+                if let Some(email) = email {
+                    notification = notification.to(Recipient::email(email));
+                }
+            }
+            self.notifier.send(&mut tx, &job.organization_id, &notification).await?;
+            Ok::<_, anyhow::Error>((tx, subscribers.len()))
+        })
+        .await?;
+        if queued == 0 {
+            return Ok(());
+        }
+
+        tracing::info!(
+            "{} digest queued for {}: {} subscribers",
+            self.frequency.label(),
+            job.organization_id,
+            queued
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_summary_render_skips_empty_sections() {
+        let summary = DigestSummary {
+            new_inspections: vec!["帯広100け201 日野".to_string()],
+            failed_syncs: vec![("cam_files.sync".to_string(), 2), ("dvr.mp4_download".to_string(), 1)],
+            ..Default::default()
+        };
+        assert_eq!(
+            summary.render(),
+            "■ 新規車検証 (1件)\n帯広100け201 日野\n\n■ 失敗した同期 (3件)\ncam_files.sync: 2件\ndvr.mp4_download: 1件"
+        );
+        assert!(DigestSummary::default().is_empty());
+    }
+
+    #[test]
+    fn test_digest_summary_render_truncates_long_sections() {
+        let summary = DigestSummary {
+            low_stock: (0..25).map(|i| format!("備品{} 残り0", i)).collect(),
+            ..Default::default()
+        };
+        let text = summary.render();
+        assert!(text.starts_with("■ 在庫切れ間近の備品 (25件)\n備品0 残り0\n"));
+        assert!(text.ends_with("…ほか5件"));
+        assert_eq!(text.lines().count(), 1 + MAX_SECTION_LINES + 1);
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{NotificationChannel, NotificationSettings, OutgoingMessage};
+use crate::config::{EmailConfig, SmtpConfig};
+use crate::http_client::HttpClient;
+
+pub const EMAIL_CHANNEL: &str = "email";
+
+const SENDGRID_MAIL_SEND_URL: &str = "https://api.sendgrid.com/v3/mail/send";
+
+/// 送信するメール（テキストのみ）
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub from: Mailbox,
+    pub to: Mailbox,
+    pub reply_to: Option<Mailbox>,
+    pub subject: String,
+    pub body: String,
+}
+
+/// メールの送信方法（SMTP / HTTP API）
+#[tonic::async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send(&self, email: &Email) -> anyhow::Result<()>;
+}
+
+/// SMTP（SES は SMTP インターフェースを使う）
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(config: &SmtpConfig) -> anyhow::Result<Self> {
+        // 465 は接続時から TLS、それ以外は STARTTLS
+        let mut builder = if config.port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+        }
+        .port(config.port);
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        let mut builder = Message::builder()
+            .from(email.from.clone())
+            .to(email.to.clone())
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN);
+        if let Some(reply_to) = &email.reply_to {
+            builder = builder.reply_to(reply_to.clone());
+        }
+        self.transport.send(builder.body(email.body.clone())?).await?;
+        Ok(())
+    }
+}
+
+/// SendGrid v3 Mail Send API
+pub struct SendGridTransport {
+    http_client: Arc<HttpClient>,
+    api_key: String,
+}
+
+impl SendGridTransport {
+    pub fn new(http_client: Arc<HttpClient>, api_key: String) -> Self {
+        Self { http_client, api_key }
+    }
+}
+
+fn sendgrid_address(mailbox: &Mailbox) -> serde_json::Value {
+    match &mailbox.name {
+        Some(name) => serde_json::json!({ "email": mailbox.email.to_string(), "name": name }),
+        None => serde_json::json!({ "email": mailbox.email.to_string() }),
+    }
+}
+
+/// Mail Send API の本文
+fn sendgrid_payload(email: &Email) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "personalizations": [{ "to": [sendgrid_address(&email.to)] }],
+        "from": sendgrid_address(&email.from),
+        "subject": email.subject,
+        "content": [{ "type": "text/plain", "value": email.body }],
+    });
+    if let Some(reply_to) = &email.reply_to {
+        payload["reply_to"] = sendgrid_address(reply_to);
+    }
+    payload
+}
+
+#[tonic::async_trait]
+impl EmailTransport for SendGridTransport {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        let response = self
+            .http_client
+            .post_json_with_bearer(SENDGRID_MAIL_SEND_URL, &self.api_key, &sendgrid_payload(email))
+            .await?;
+        // 成功は 202 Accepted
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("SendGrid returned {}: {}", status, text);
+        }
+        Ok(())
+    }
+}
+
+/// 組織の送信元設定でメールを送る（失敗は notification_deliveries の job で再送）
+pub struct EmailChannel {
+    transport: Arc<dyn EmailTransport>,
+    default_from: Mailbox,
+}
+
+impl EmailChannel {
+    /// 設定から送信方法を選ぶ
+    pub fn new(config: &EmailConfig, http_client: Arc<HttpClient>) -> anyhow::Result<Self> {
+        let (transport, default_from): (Arc<dyn EmailTransport>, &str) = match config {
+            EmailConfig::Smtp(smtp) => (Arc::new(SmtpTransport::new(smtp)?), smtp.default_from.as_str()),
+            EmailConfig::SendGrid { api_key, default_from } => (
+                Arc::new(SendGridTransport::new(http_client, api_key.clone())),
+                default_from.as_str(),
+            ),
+        };
+        Ok(Self {
+            transport,
+            default_from: default_from.parse()?,
+        })
+    }
+
+    /// 組織の送信元設定（なければ SMTP_FROM / EMAIL_FROM）
+    fn from_mailbox(&self, settings: &NotificationSettings) -> anyhow::Result<Mailbox> {
+        match &settings.email_from_address {
+            Some(address) if !address.is_empty() => {
+                Ok(Mailbox::new(settings.email_from_name.clone(), address.parse()?))
+            }
+            _ => Ok(self.default_from.clone()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl NotificationChannel for EmailChannel {
+    async fn send(&self, message: &OutgoingMessage, settings: &NotificationSettings) -> anyhow::Result<()> {
+        let reply_to = match settings.email_reply_to.as_deref().filter(|r| !r.is_empty()) {
+            Some(reply_to) => Some(reply_to.parse()?),
+            None => None,
+        };
+        let email = Email {
+            from: self.from_mailbox(settings)?,
+            to: message.recipient.parse()?,
+            reply_to,
+            subject: message.subject.clone(),
+            body: message.body.clone(),
+        };
+        self.transport.send(&email).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sendgrid_payload() {
+        let email = Email {
+            from: Mailbox::new(Some("大石運輸".to_string()), "noreply@example.com".parse().unwrap()),
+            to: "admin@example.com".parse().unwrap(),
+            reply_to: Some("support@example.com".parse().unwrap()),
+            subject: "【車検期限通知】".to_string(),
+            body: "期限切れ: 1台\n".to_string(),
+        };
+        let payload = sendgrid_payload(&email);
+        assert_eq!(payload["personalizations"][0]["to"][0], serde_json::json!({ "email": "admin@example.com" }));
+        assert_eq!(
+            payload["from"],
+            serde_json::json!({ "email": "noreply@example.com", "name": "大石運輸" })
+        );
+        assert_eq!(payload["reply_to"]["email"], "support@example.com");
+        assert_eq!(payload["content"][0]["value"], "期限切れ: 1台\n");
+
+        let payload = sendgrid_payload(&Email { reply_to: None, ..email });
+        assert!(payload.get("reply_to").is_none());
+    }
+}
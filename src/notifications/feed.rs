@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+
+use crate::db::OrgScopedPool;
+use crate::events::{EntityChange, EntityEvent, EventBus};
+use crate::models::InAppNotificationModel;
+use crate::proto::common::ChangeType;
+
+/// アプリ内通知（notifications テーブル）。job を使わず Notifier::send のトランザクションで直接書く
+pub const IN_APP_CHANNEL: &str = "in_app";
+
+/// notifications の INSERT トリガーが pg_notify するチャネル
+const LISTEN_CHANNEL: &str = "in_app_notifications";
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+struct NotifyPayload {
+    id: i64,
+    organization_id: String,
+    user_id: String,
+}
+
+/// コミットされた notifications を LISTEN して EventBus に流す（WatchNotifications 用）
+///
+/// pg_notify はコミット時に全インスタンスへ届くので、別インスタンスの job が書いた通知も配信される。
+pub struct NotificationFeedListener;
+
+impl NotificationFeedListener {
+    pub fn spawn(pool: PgPool, events: EventBus) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = Self::run(&pool, &events).await {
+                    tracing::warn!("Notification feed listener stopped: {}; reconnecting", e);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    async fn run(pool: &PgPool, events: &EventBus) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(LISTEN_CHANNEL).await?;
+        tracing::info!("Listening for in-app notifications");
+
+        loop {
+            let notification = listener.recv().await?;
+            let payload: NotifyPayload = match serde_json::from_str(notification.payload()) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!("Invalid in-app notification payload: {}", e);
+                    continue;
+                }
+            };
+            match Self::load(pool, &payload).await {
+                Ok(Some(model)) => events.publish(EntityEvent {
+                    organization_id: payload.organization_id,
+                    user_id: Some(payload.user_id),
+                    change_type: ChangeType::Created,
+                    change: EntityChange::Notification(model.to_proto()),
+                }),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to load in-app notification {}: {}", payload.id, e),
+            }
+        }
+    }
+
+    async fn load(pool: &PgPool, payload: &NotifyPayload) -> Result<Option<InAppNotificationModel>, sqlx::Error> {
+        let mut conn = pool.acquire_scoped(&payload.organization_id).await?;
+        sqlx::query_as("SELECT id, template, title, body, read_at, created_at FROM notifications WHERE id = $1")
+            .bind(payload.id)
+            .fetch_optional(&mut *conn)
+            .await
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tokio::sync::Mutex;
+
+use super::{NotificationChannel, NotificationSettings, OutgoingMessage};
+use crate::db::OrgScopedPool;
+use crate::http_client::HttpClient;
+use crate::services::lineworks_auth;
+
+pub const LINEWORKS_CHANNEL: &str = "lineworks";
+
+const TOKEN_URL: &str = "https://auth.worksmobile.com/oauth2/v2.0/token";
+const API_BASE_URL: &str = "https://www.worksapis.com/v1.0";
+/// 期限のこの時間前に取り直す
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+/// テキストメッセージの上限（文字数）
+const MAX_TEXT_CHARS: usize = 2000;
+/// トークルーム宛ての宛先アドレスの接頭辞（それ以外は LINE WORKS のユーザー ID）
+pub const CHANNEL_ADDRESS_PREFIX: &str = "channel:";
+
+/// 通知に使う Bot（bot_configs、秘密情報は暗号化のまま）
+#[derive(Debug, Clone, FromRow)]
+struct BotCredentials {
+    id: String,
+    client_id: String,
+    client_secret_encrypted: String,
+    service_account: String,
+    private_key_encrypted: String,
+    bot_id: String,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Service Account 認証の JWT（RS256）
+#[derive(Debug, Serialize)]
+struct AssertionClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+/// LINE WORKS Bot からユーザーへ個別に（`channel:` 宛てはトークルームへ）テキストを送る
+///
+/// Bot は通知の種類のルーティング（bot_routing_rules）、なければ組織の notification_settings.lineworks_bot_config_id、
+/// 未設定なら最初の有効な LINE WORKS Bot。
+/// アクセストークンは Bot ごとにキャッシュし、期限前・401 のときに取り直す。
+pub struct LineWorksChannel {
+    pool: PgPool,
+    http_client: Arc<HttpClient>,
+    /// bot_configs の秘密情報の復号キー（JWT_SECRET）
+    secret_key: String,
+    tokens: Mutex<HashMap<String, CachedToken>>,
+}
+
+impl LineWorksChannel {
+    pub fn new(pool: PgPool, http_client: Arc<HttpClient>, secret_key: String) -> Self {
+        Self {
+            pool,
+            http_client,
+            secret_key,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn load_bot(
+        &self,
+        organization_id: &str,
+        template: &str,
+        default_bot_config_id: Option<&str>,
+    ) -> anyhow::Result<BotCredentials> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+        let routed: Option<String> = sqlx::query_scalar(
+            "SELECT bot_config_id::text FROM bot_routing_rules WHERE event_type = $1",
+        )
+        .bind(template)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let bot_config_id = routed.as_deref().or(default_bot_config_id);
+        let bot: Option<BotCredentials> = sqlx::query_as(
+            r#"
+            SELECT id::text AS id, client_id, client_secret_encrypted, service_account,
+                   private_key_encrypted, bot_id
+            FROM bot_configs
+            WHERE provider = 'lineworks' AND enabled = TRUE
+              AND ($1::uuid IS NULL OR id = $1::uuid)
+            ORDER BY created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(bot_config_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        bot.ok_or_else(|| anyhow::anyhow!("No enabled LINE WORKS bot for organization {}", organization_id))
+    }
+
+    async fn access_token(&self, bot: &BotCredentials) -> anyhow::Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if let Some(cached) = tokens.get(&bot.id) {
+            if cached.expires_at - Duration::seconds(TOKEN_REFRESH_MARGIN_SECS) > Utc::now() {
+                return Ok(cached.access_token.clone());
+            }
+        }
+        let token = self.fetch_token(bot).await?;
+        let access_token = token.access_token.clone();
+        tokens.insert(bot.id.clone(), token);
+        Ok(access_token)
+    }
+
+    async fn invalidate_token(&self, bot_config_id: &str) {
+        self.tokens.lock().await.remove(bot_config_id);
+    }
+
+    async fn fetch_token(&self, bot: &BotCredentials) -> anyhow::Result<CachedToken> {
+        let client_secret = lineworks_auth::decrypt_secret(&bot.client_secret_encrypted, &self.secret_key)
+            .map_err(|e| anyhow::anyhow!("Failed to decrypt client secret: {}", e))?;
+        let private_key = lineworks_auth::decrypt_secret(&bot.private_key_encrypted, &self.secret_key)
+            .map_err(|e| anyhow::anyhow!("Failed to decrypt private key: {}", e))?;
+
+        let (access_token, expires_in) = request_access_token(
+            &self.http_client,
+            &bot.client_id,
+            &client_secret,
+            &bot.service_account,
+            &private_key,
+        )
+        .await?;
+        Ok(CachedToken {
+            access_token,
+            expires_at: Utc::now() + Duration::seconds(expires_in),
+        })
+    }
+}
+
+/// Service Account 認証（JWT）でアクセストークンを取得する（秘密情報は平文）。有効期限（秒）も返す
+///
+/// Bot 設定の保存時の検証にも使う。
+pub async fn request_access_token(
+    http_client: &HttpClient,
+    client_id: &str,
+    client_secret: &str,
+    service_account: &str,
+    private_key: &str,
+) -> anyhow::Result<(String, i64)> {
+    let now = Utc::now().timestamp();
+    let assertion = encode(
+        &Header::new(Algorithm::RS256),
+        &AssertionClaims {
+            iss: client_id,
+            sub: service_account,
+            iat: now,
+            exp: now + 3600,
+        },
+        &EncodingKey::from_rsa_pem(private_key.as_bytes())?,
+    )?;
+
+    let form = [
+        ("assertion", assertion.as_str()),
+        ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+        ("scope", "bot"),
+    ];
+    let response = http_client.post_form(TOKEN_URL, &form).await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("LINE WORKS token request failed: {} - {}", status, body);
+    }
+    let body: serde_json::Value = response.json().await?;
+    let access_token = body
+        .get("access_token")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| anyhow::anyhow!("LINE WORKS token response has no access_token"))?
+        .to_string();
+    // expires_in は文字列で返る（"86400"）
+    let expires_in = match body.get("expires_in") {
+        Some(serde_json::Value::String(s)) => s.parse().unwrap_or(3600),
+        Some(serde_json::Value::Number(n)) => n.as_i64().unwrap_or(3600),
+        _ => 3600,
+    };
+    Ok((access_token, expires_in))
+}
+
+/// 宛先アドレス（ユーザー ID または `channel:<channelId>`）へのメッセージ送信 URL
+pub fn message_url(bot_id: &str, address: &str) -> String {
+    match address.strip_prefix(CHANNEL_ADDRESS_PREFIX) {
+        Some(channel_id) => format!(
+            "{}/bots/{}/channels/{}/messages",
+            API_BASE_URL,
+            urlencoding::encode(bot_id),
+            urlencoding::encode(channel_id)
+        ),
+        None => format!(
+            "{}/bots/{}/users/{}/messages",
+            API_BASE_URL,
+            urlencoding::encode(bot_id),
+            urlencoding::encode(address)
+        ),
+    }
+}
+
+/// 件名と本文から LINE WORKS のテキストメッセージを組み立てる（上限を超える分は切り詰め）
+pub fn lineworks_message(subject: &str, body: &str) -> serde_json::Value {
+    let text = if subject.is_empty() {
+        body.trim_end().to_string()
+    } else {
+        format!("{}\n{}", subject, body.trim_end())
+    };
+    let text = if text.chars().count() > MAX_TEXT_CHARS {
+        let mut truncated: String = text.chars().take(MAX_TEXT_CHARS - 1).collect();
+        truncated.push('…');
+        truncated
+    } else {
+        text
+    };
+    serde_json::json!({
+        "content": {
+            "type": "text",
+            "text": text,
+        }
+    })
+}
+
+#[tonic::async_trait]
+impl NotificationChannel for LineWorksChannel {
+    async fn send(&self, message: &OutgoingMessage, settings: &NotificationSettings) -> anyhow::Result<()> {
+        let bot = self
+            .load_bot(
+                &message.organization_id,
+                &message.template,
+                settings.lineworks_bot_config_id.as_deref(),
+            )
+            .await?;
+        let token = self.access_token(&bot).await?;
+        let url = message_url(&bot.bot_id, &message.recipient);
+
+        let response = self
+            .http_client
+            .post_json_with_bearer(&url, &token, &lineworks_message(&message.subject, &message.body))
+            .await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            // 失効したトークンは次の再試行で取り直す
+            self.invalidate_token(&bot.id).await;
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("LINE WORKS message failed: {} - {}", status, body);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lineworks_message_truncates_long_text() {
+        let message = lineworks_message("【車検期限通知】", "1台\n");
+        assert_eq!(message["content"]["type"], "text");
+        assert_eq!(message["content"]["text"], "【車検期限通知】\n1台");
+
+        let long = "あ".repeat(MAX_TEXT_CHARS + 10);
+        let message = lineworks_message("", &long);
+        let text = message["content"]["text"].as_str().unwrap();
+        assert_eq!(text.chars().count(), MAX_TEXT_CHARS);
+        assert!(text.ends_with('…'));
+    }
+
+    #[test]
+    fn test_message_url_routes_channel_addresses() {
+        assert_eq!(
+            message_url("bot1", "user@example"),
+            format!("{}/bots/bot1/users/user%40example/messages", API_BASE_URL)
+        );
+        assert_eq!(
+            message_url("bot1", "channel:abc-123"),
+            format!("{}/bots/bot1/channels/abc-123/messages", API_BASE_URL)
+        );
+    }
+}
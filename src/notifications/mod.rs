@@ -0,0 +1,350 @@
+// Notification subsystem
+//
+// 車検期限・招待・パスワード再設定などの通知はテンプレートを描画して宛先ごとに notification_deliveries に記録し、
+// job（notifications.deliver）で送る。呼び出し元のトランザクションで記録するので、ロールバックされた処理の通知は
+// 送られない。送信結果（sent / failed、試行回数、最後のエラー）は行に残る。
+// チャネルは NotificationChannel を実装して NotificationJobHandler に登録する（メール、LINE WORKS、Slack / Discord Webhook、SMS）。
+// アプリ内通知（in_app）は送信がないので job を使わず notifications テーブルに直接書く。
+// 日次 / 週次のまとめ（digest）は購読者ごとに1通にまとめて送る。
+
+pub mod delivery;
+pub mod digest;
+pub mod email;
+pub mod feed;
+pub mod lineworks;
+pub mod sms;
+pub mod template;
+pub mod webhook;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use sqlx::PgConnection;
+
+use crate::jobs::{enqueue, NewJob};
+
+pub use delivery::{
+    DeliverPayload, DeliverySkipped, NotificationChannel, NotificationJobHandler,
+    NotificationSettings, OutgoingMessage, NOTIFICATION_DELIVER_JOB,
+};
+pub use digest::{
+    DigestFrequency, DigestJobHandler, DAILY_DIGEST_JOB, DAILY_DIGEST_TASK, WEEKLY_DIGEST_JOB,
+    WEEKLY_DIGEST_TASK,
+};
+pub use email::{EmailChannel, EMAIL_CHANNEL};
+pub use feed::{NotificationFeedListener, IN_APP_CHANNEL};
+pub use lineworks::{
+    lineworks_message, message_url, request_access_token, LineWorksChannel,
+    CHANNEL_ADDRESS_PREFIX, LINEWORKS_CHANNEL,
+};
+pub use sms::{
+    is_valid_phone_number, sms_message, sms_provider, sms_sent_this_month, SmsChannel, SmsGateway,
+    SmsProvider, TwilioSms, SMS_CHANNEL,
+};
+pub use template::{
+    builtin_template, format_jst, render, unknown_variables, NotificationTemplate,
+    ACCESS_REQUEST_REMINDER, BUILTIN_TEMPLATES, DVR_ALERT, EXPIRY_ALERT, INVITATION, PASSWORD_RESET,
+    DIGEST, REPORT_READY, WEBHOOK_DISABLED,
+};
+pub use webhook::{ChatWebhookChannel, DISCORD_CHANNEL, SLACK_CHANNEL};
+
+/// 送信先（チャネル + アドレス）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    pub channel: &'static str,
+    pub address: String,
+}
+
+impl Recipient {
+    pub fn email(address: impl Into<String>) -> Self {
+        Self {
+            channel: EMAIL_CHANNEL,
+            address: address.into(),
+        }
+    }
+
+    /// アプリ内通知（app_users.id 宛て）
+    pub fn in_app(user_id: impl Into<String>) -> Self {
+        Self {
+            channel: IN_APP_CHANNEL,
+            address: user_id.into(),
+        }
+    }
+
+    /// 電話番号（E.164）宛て
+    pub fn sms(number: impl Into<String>) -> Self {
+        Self {
+            channel: SMS_CHANNEL,
+            address: number.into(),
+        }
+    }
+
+    /// LINE WORKS のユーザー ID 宛て
+    pub fn lineworks(user_id: impl Into<String>) -> Self {
+        Self {
+            channel: LINEWORKS_CHANNEL,
+            address: user_id.into(),
+        }
+    }
+
+    /// LINE WORKS のトークルーム（channelId）宛て
+    pub fn lineworks_channel(channel_id: &str) -> Self {
+        Self {
+            channel: LINEWORKS_CHANNEL,
+            address: format!("{}{}", CHANNEL_ADDRESS_PREFIX, channel_id),
+        }
+    }
+}
+
+/// 送る通知（テンプレート + 変数 + 宛先）
+#[derive(Debug, Clone)]
+pub struct Notification {
+    template: NotificationTemplate,
+    vars: BTreeMap<String, String>,
+    recipients: Vec<Recipient>,
+}
+
+impl Notification {
+    pub fn new(template: NotificationTemplate) -> Self {
+        Self {
+            template,
+            vars: BTreeMap::new(),
+            recipients: Vec::new(),
+        }
+    }
+
+    pub fn var(mut self, name: &str, value: impl ToString) -> Self {
+        self.vars.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn to(mut self, recipient: Recipient) -> Self {
+        self.recipients.push(recipient);
+        self
+    }
+
+    pub fn to_all(mut self, recipients: impl IntoIterator<Item = Recipient>) -> Self {
+        self.recipients.extend(recipients);
+        self
+    }
+}
+
+/// 組織の上書き（channel, subject, body）から宛先チャネルに使う件名・本文を選ぶ（チャネル指定 > 共通 > 組み込み）
+pub fn resolve_template<'a>(
+    template: &'a NotificationTemplate,
+    overrides: &'a [(String, String, String)],
+    channel: &str,
+) -> (&'a str, &'a str) {
+    overrides
+        .iter()
+        .find(|(c, _, _)| c == channel)
+        .or_else(|| overrides.iter().find(|(c, _, _)| c.is_empty()))
+        .map(|(_, subject, body)| (subject.as_str(), body.as_str()))
+        .unwrap_or((template.subject, template.body))
+}
+
+/// 有効なチャネルの宛先について通知を記録し、送信 job を登録する
+#[derive(Clone, Default)]
+pub struct Notifier {
+    channels: Arc<Vec<&'static str>>,
+}
+
+impl Notifier {
+    pub fn new(channels: Vec<&'static str>) -> Self {
+        Self {
+            channels: Arc::new(channels),
+        }
+    }
+
+    /// in_app は常に有効
+    pub fn is_enabled(&self, channel: &str) -> bool {
+        channel == IN_APP_CHANNEL || self.channels.iter().any(|c| *c == channel)
+    }
+
+    /// 業務データと同じトランザクション（organization 設定済み）で呼ぶ。記録した件数を返す
+    ///
+    /// 組織のテンプレート（notification_templates）があればそれで描画する。
+    /// 通知の種類にトークルームへのルーティング（bot_routing_rules.channel_id）があればそこにも送る。
+    pub async fn send(
+        &self,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        notification: &Notification,
+    ) -> Result<usize, sqlx::Error> {
+        let mut recipients = notification.recipients.clone();
+        if self.is_enabled(LINEWORKS_CHANNEL) {
+            let channel_ids: Vec<String> = sqlx::query_scalar(
+                "SELECT channel_id FROM bot_routing_rules WHERE event_type = $1 AND channel_id IS NOT NULL",
+            )
+            .bind(notification.template.key)
+            .fetch_all(&mut *conn)
+            .await?;
+            recipients.extend(channel_ids.iter().map(|id| Recipient::lineworks_channel(id)));
+        }
+        if !recipients.iter().any(|r| self.is_enabled(r.channel)) {
+            return Ok(0);
+        }
+        let overrides: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT channel, subject, body FROM notification_templates WHERE template_key = $1",
+        )
+        .bind(notification.template.key)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut recorded = 0;
+        for recipient in &recipients {
+            if !self.is_enabled(recipient.channel) {
+                continue;
+            }
+            let (subject, body) = resolve_template(&notification.template, &overrides, recipient.channel);
+            let subject = render(subject, &notification.vars);
+            let body = render(body, &notification.vars);
+
+            if recipient.channel == IN_APP_CHANNEL {
+                sqlx::query(
+                    r#"
+                    INSERT INTO notifications (organization_id, user_id, template, title, body)
+                    VALUES ($1::uuid, $2::uuid, $3, $4, $5)
+                    "#,
+                )
+                .bind(organization_id)
+                .bind(&recipient.address)
+                .bind(notification.template.key)
+                .bind(&subject)
+                .bind(&body)
+                .execute(&mut *conn)
+                .await?;
+                recorded += 1;
+                continue;
+            }
+            let (delivery_id,): (i64,) = sqlx::query_as(
+                r#"
+                INSERT INTO notification_deliveries (organization_id, channel, template, recipient, subject, body)
+                VALUES ($1::uuid, $2, $3, $4, $5, $6)
+                RETURNING id
+                "#,
+            )
+            .bind(organization_id)
+            .bind(recipient.channel)
+            .bind(notification.template.key)
+            .bind(&recipient.address)
+            .bind(&subject)
+            .bind(&body)
+            .fetch_one(&mut *conn)
+            .await?;
+
+            let job = NewJob::new(NOTIFICATION_DELIVER_JOB, DeliverPayload { delivery_id })
+                .dedupe_key(delivery_id.to_string());
+            enqueue(&mut *conn, organization_id, job).await?;
+            recorded += 1;
+        }
+        Ok(recorded)
+    }
+
+    /// 組織の管理者（メールアドレス登録済み）宛て
+    pub async fn admin_recipients(
+        conn: &mut PgConnection,
+        organization_id: &str,
+    ) -> Result<Vec<Recipient>, sqlx::Error> {
+        let emails: Vec<String> = sqlx::query_scalar("SELECT email FROM org_admin_emails($1::uuid)")
+            .bind(organization_id)
+            .fetch_all(conn)
+            .await?;
+        Ok(emails.into_iter().map(Recipient::email).collect())
+    }
+
+    /// 組織メンバー（admin_only なら管理者のみ）のアプリ内通知宛て
+    pub async fn in_app_recipients(
+        conn: &mut PgConnection,
+        organization_id: &str,
+        admin_only: bool,
+    ) -> Result<Vec<Recipient>, sqlx::Error> {
+        let user_ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT user_id::text FROM user_organizations
+            WHERE organization_id = $1::uuid AND (NOT $2 OR role = 'admin')
+            "#,
+        )
+        .bind(organization_id)
+        .bind(admin_only)
+        .fetch_all(conn)
+        .await?;
+        Ok(user_ids.into_iter().map(Recipient::in_app).collect())
+    }
+
+    /// LINE WORKS でログインしたことのある組織メンバー宛て（有効な LINE WORKS Bot がなければ空）
+    pub async fn lineworks_recipients(
+        conn: &mut PgConnection,
+        organization_id: &str,
+    ) -> Result<Vec<Recipient>, sqlx::Error> {
+        let user_ids: Vec<String> = sqlx::query_scalar("SELECT user_id FROM org_lineworks_user_ids($1::uuid)")
+            .bind(organization_id)
+            .fetch_all(conn)
+            .await?;
+        Ok(user_ids.into_iter().map(Recipient::lineworks).collect())
+    }
+
+    /// 緊急通知の SMS 宛て（notification_settings.sms_recipients、月間上限 0 なら空）
+    pub async fn sms_recipients(
+        conn: &mut PgConnection,
+        organization_id: &str,
+    ) -> Result<Vec<Recipient>, sqlx::Error> {
+        let numbers: Option<Vec<String>> = sqlx::query_scalar(
+            r#"
+            SELECT sms_recipients FROM notification_settings
+            WHERE organization_id = $1::uuid AND sms_monthly_limit > 0
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_optional(conn)
+        .await?;
+        Ok(numbers.unwrap_or_default().into_iter().map(Recipient::sms).collect())
+    }
+
+    /// 組織の有効な Slack / Discord Webhook 宛て（アドレスは notification_webhooks.id）
+    pub async fn webhook_recipients(
+        conn: &mut PgConnection,
+        organization_id: &str,
+    ) -> Result<Vec<Recipient>, sqlx::Error> {
+        let webhooks: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT provider, id::text FROM notification_webhooks
+            WHERE organization_id = $1::uuid AND enabled = TRUE
+            ORDER BY created_at
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(conn)
+        .await?;
+        Ok(webhooks
+            .into_iter()
+            .filter_map(|(provider, id)| {
+                let channel = match provider.as_str() {
+                    SLACK_CHANNEL => SLACK_CHANNEL,
+                    DISCORD_CHANNEL => DISCORD_CHANNEL,
+                    _ => return None,
+                };
+                Some(Recipient { channel, address: id })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_template_prefers_channel_override() {
+        let overrides = vec![
+            ("".to_string(), "共通 {{count}}".to_string(), "共通本文".to_string()),
+            (SLACK_CHANNEL.to_string(), "Slack {{count}}".to_string(), "Slack本文".to_string()),
+        ];
+        assert_eq!(resolve_template(&EXPIRY_ALERT, &overrides, SLACK_CHANNEL), ("Slack {{count}}", "Slack本文"));
+        assert_eq!(resolve_template(&EXPIRY_ALERT, &overrides, EMAIL_CHANNEL), ("共通 {{count}}", "共通本文"));
+        assert_eq!(
+            resolve_template(&EXPIRY_ALERT, &[], EMAIL_CHANNEL),
+            (EXPIRY_ALERT.subject, EXPIRY_ALERT.body)
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use super::{DeliverySkipped, NotificationChannel, NotificationSettings, OutgoingMessage};
+use crate::config::SmsConfig;
+use crate::db::OrgScopedPool;
+use crate::http_client::HttpClient;
+
+pub const SMS_CHANNEL: &str = "sms";
+
+const TWILIO_API_BASE_URL: &str = "https://api.twilio.com/2010-04-01";
+/// 1通の上限（UCS-2 で 10 分割分）
+const MAX_SMS_CHARS: usize = 670;
+
+/// SMS の送信事業者
+#[tonic::async_trait]
+pub trait SmsProvider: Send + Sync {
+    async fn send(&self, to: &str, body: &str) -> anyhow::Result<()>;
+}
+
+/// Twilio Messages API
+pub struct TwilioSms {
+    http_client: Arc<HttpClient>,
+    account_sid: String,
+    auth_token: String,
+    from: String,
+}
+
+impl TwilioSms {
+    pub fn new(http_client: Arc<HttpClient>, account_sid: String, auth_token: String, from: String) -> Self {
+        Self {
+            http_client,
+            account_sid,
+            auth_token,
+            from,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl SmsProvider for TwilioSms {
+    async fn send(&self, to: &str, body: &str) -> anyhow::Result<()> {
+        let url = format!("{}/Accounts/{}/Messages.json", TWILIO_API_BASE_URL, self.account_sid);
+        // MG で始まる場合は Messaging Service から送る
+        let from_key = if self.from.starts_with("MG") { "MessagingServiceSid" } else { "From" };
+        let form = [("To", to), (from_key, self.from.as_str()), ("Body", body)];
+        let response = self
+            .http_client
+            .post_form_with_basic_auth(&url, &self.account_sid, &self.auth_token, &form)
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Twilio returned {}: {}", status, text);
+        }
+        Ok(())
+    }
+}
+
+/// `{"to", "body"}` を POST する汎用ゲートウェイ
+pub struct SmsGateway {
+    http_client: Arc<HttpClient>,
+    url: String,
+    token: Option<String>,
+}
+
+impl SmsGateway {
+    pub fn new(http_client: Arc<HttpClient>, url: String, token: Option<String>) -> Self {
+        Self { http_client, url, token }
+    }
+}
+
+#[tonic::async_trait]
+impl SmsProvider for SmsGateway {
+    async fn send(&self, to: &str, body: &str) -> anyhow::Result<()> {
+        let payload = serde_json::json!({ "to": to, "body": body });
+        let response = match &self.token {
+            Some(token) => self.http_client.post_json_with_bearer(&self.url, token, &payload).await?,
+            None => self.http_client.post_json(&self.url, &payload).await?,
+        };
+        if !response.status().is_success() {
+            anyhow::bail!("SMS gateway returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// 設定から送信事業者を作る
+pub fn sms_provider(config: &SmsConfig, http_client: Arc<HttpClient>) -> Arc<dyn SmsProvider> {
+    match config {
+        SmsConfig::Twilio {
+            account_sid,
+            auth_token,
+            from,
+        } => Arc::new(TwilioSms::new(http_client, account_sid.clone(), auth_token.clone(), from.clone())),
+        SmsConfig::Gateway { url, token } => Arc::new(SmsGateway::new(http_client, url.clone(), token.clone())),
+    }
+}
+
+/// E.164 形式（+ と 8〜15 桁の数字）
+pub fn is_valid_phone_number(number: &str) -> bool {
+    number
+        .strip_prefix('+')
+        .is_some_and(|digits| (8..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit()))
+        && !number.starts_with("+0")
+}
+
+/// 件名 + 本文（長すぎる場合は末尾を省略）
+pub fn sms_message(subject: &str, body: &str) -> String {
+    let text = if subject.is_empty() {
+        body.trim_end().to_string()
+    } else {
+        format!("{}\n{}", subject, body.trim_end())
+    };
+    if text.chars().count() > MAX_SMS_CHARS {
+        let mut truncated: String = text.chars().take(MAX_SMS_CHARS - 1).collect();
+        truncated.push('…');
+        truncated
+    } else {
+        text
+    }
+}
+
+/// 組織の月間上限の範囲で SMS を送る
+pub struct SmsChannel {
+    pool: PgPool,
+    provider: Arc<dyn SmsProvider>,
+}
+
+impl SmsChannel {
+    pub fn new(pool: PgPool, provider: Arc<dyn SmsProvider>) -> Self {
+        Self { pool, provider }
+    }
+
+    /// 今月の送信枠を1つ確保（上限に達していれば false）
+    async fn reserve(&self, organization_id: &str, limit: i32) -> anyhow::Result<bool> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+        let reserved: Option<i32> = sqlx::query_scalar(
+            r#"
+            INSERT INTO sms_usage (organization_id, month, sent_count)
+            SELECT $1::uuid, date_trunc('month', NOW() AT TIME ZONE 'Asia/Tokyo')::date, 1
+            WHERE $2 > 0
+            ON CONFLICT (organization_id, month) DO UPDATE
+            SET sent_count = sms_usage.sent_count + 1, updated_at = NOW()
+            WHERE sms_usage.sent_count < $2
+            RETURNING sent_count
+            "#,
+        )
+        .bind(organization_id)
+        .bind(limit)
+        .fetch_optional(&mut *conn)
+        .await?;
+        Ok(reserved.is_some())
+    }
+
+    /// 送信に失敗した分の枠を戻す
+    async fn release(&self, organization_id: &str) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+        sqlx::query(
+            r#"
+            UPDATE sms_usage SET sent_count = GREATEST(sent_count - 1, 0), updated_at = NOW()
+            WHERE month = date_trunc('month', NOW() AT TIME ZONE 'Asia/Tokyo')::date
+            "#,
+        )
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl NotificationChannel for SmsChannel {
+    async fn send(&self, message: &OutgoingMessage, settings: &NotificationSettings) -> anyhow::Result<()> {
+        if !self.reserve(&message.organization_id, settings.sms_monthly_limit).await? {
+            return Err(DeliverySkipped(format!(
+                "monthly SMS limit reached ({})",
+                settings.sms_monthly_limit
+            ))
+            .into());
+        }
+        let body = sms_message(&message.subject, &message.body);
+        if let Err(e) = self.provider.send(&message.recipient, &body).await {
+            self.release(&message.organization_id).await?;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// 今月の送信数
+pub async fn sms_sent_this_month(conn: &mut sqlx::PgConnection) -> Result<i32, sqlx::Error> {
+    let count: Option<i32> = sqlx::query_scalar(
+        "SELECT sent_count FROM sms_usage WHERE month = date_trunc('month', NOW() AT TIME ZONE 'Asia/Tokyo')::date",
+    )
+    .fetch_optional(conn)
+    .await?;
+    Ok(count.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_phone_number() {
+        assert!(is_valid_phone_number("+819012345678"));
+        assert!(is_valid_phone_number("+15551234567"));
+        assert!(!is_valid_phone_number("09012345678"));
+        assert!(!is_valid_phone_number("+81-90-1234-5678"));
+        assert!(!is_valid_phone_number("+0123456789"));
+        assert!(!is_valid_phone_number("+1234"));
+    }
+
+    #[test]
+    fn test_sms_message_truncates_long_text() {
+        assert_eq!(sms_message("【DVR通知】", "急ブレーキ\n"), "【DVR通知】\n急ブレーキ");
+
+        let long = "あ".repeat(MAX_SMS_CHARS + 10);
+        let text = sms_message("", &long);
+        assert_eq!(text.chars().count(), MAX_SMS_CHARS);
+        assert!(text.ends_with('…'));
+    }
+}
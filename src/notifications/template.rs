@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+/// 通知テンプレート（`{{name}}` を変数で置換）。組織ごとに notification_templates で上書きできる
+#[derive(Debug, Clone, Copy)]
+pub struct NotificationTemplate {
+    pub key: &'static str,
+    pub subject: &'static str,
+    pub body: &'static str,
+    /// 使える変数と、プレビュー用のサンプル値
+    pub variables: &'static [(&'static str, &'static str)],
+}
+
+/// 車検証の期限切れ・期限間近（定期実行）
+pub const EXPIRY_ALERT: NotificationTemplate = NotificationTemplate {
+    key: "car_inspection.expiring",
+    subject: "【車検期限通知】{{count}}台の車検証が期限切れ・期限間近です",
+    body: "期限切れ・30日以内に期限切れの車両: {{count}}台\n\n{{vehicles}}\n",
+    variables: &[
+        ("count", "2"),
+        ("vehicles", "帯広100け201 日野 期限: 261031\n帯広100け202 いすゞ 期限: 261105"),
+        // 最も期限が近い車両
+        ("car_no", "帯広100け201"),
+        ("expiry_date", "261031"),
+    ],
+};
+
+/// DVR のイベント通知
+pub const DVR_ALERT: NotificationTemplate = NotificationTemplate {
+    key: "dvr.alert",
+    subject: "【DVR通知】{{vehicle_name}} {{event_type}}",
+    body: "車両: {{vehicle_name}} ({{vehicle_cd}})\n運転手: {{driver_name}}\nイベント: {{event_type}}\n\
+           日時: {{dvr_datetime}}\n動画URL: {{mp4_url}}\n",
+    variables: &[
+        ("vehicle_name", "1号車"),
+        ("vehicle_cd", "1001"),
+        ("driver_name", "山田太郎"),
+        ("event_type", "急ブレーキ"),
+        ("dvr_datetime", "2026-10-16 09:30:00"),
+        ("mp4_url", "https://example.com/dvr/1001.mp4"),
+    ],
+};
+
+/// 組織への招待
+pub const INVITATION: NotificationTemplate = NotificationTemplate {
+    key: "member.invitation",
+    subject: "{{organization_name}} への招待",
+    body: "{{organization_name}} に招待されました（権限: {{role}}）。\n\n\
+           {{invite_url}}\n\n招待コード: {{token}}\n有効期限: {{expires_at}}\n",
+    variables: &[
+        ("organization_name", "大石運輸"),
+        ("role", "member"),
+        ("invite_url", "https://app.example.com/invite?token=xxxx"),
+        ("token", "xxxx"),
+        ("expires_at", "2026-10-23 09:00"),
+    ],
+};
+
+/// パスワード再設定
+pub const PASSWORD_RESET: NotificationTemplate = NotificationTemplate {
+    key: "auth.password_reset",
+    subject: "パスワード再設定のご案内",
+    body: "ユーザー {{username}} のパスワード再設定が要求されました。\n\n\
+           {{reset_url}}\n\n再設定コード: {{token}}\n有効期限: {{expires_at}}\n\n\
+           心当たりがない場合はこのメールを破棄してください。\n",
+    variables: &[
+        ("username", "yamada"),
+        ("reset_url", "https://app.example.com/reset-password?token=xxxx"),
+        ("token", "xxxx"),
+        ("expires_at", "2026-10-16 10:00"),
+    ],
+};
+
+/// 失敗が続いた Webhook エンドポイントの自動無効化
+pub const WEBHOOK_DISABLED: NotificationTemplate = NotificationTemplate {
+    key: "webhook.disabled",
+    subject: "【Webhook】送信失敗が続いたため無効化しました",
+    body: "送信先: {{url}}\n連続失敗: {{failures}}回\n最後のエラー: {{last_error}}\n\n\
+           受信側を確認のうえ、管理画面から再度有効化してください。\n",
+    variables: &[
+        ("url", "https://example.com/hooks/logi"),
+        ("failures", "50"),
+        ("last_error", "Webhook endpoint returned 500 Internal Server Error"),
+    ],
+};
+
+/// 日次 / 週次のまとめ
+pub const DIGEST: NotificationTemplate = NotificationTemplate {
+    key: "notifications.digest",
+    subject: "【{{period}}まとめ】新規車検証 {{new_inspection_count}}件 / 期限間近 {{expiring_count}}台",
+    body: "{{since}} 以降のまとめです。\n\n{{summary}}\n",
+    variables: &[
+        ("period", "日次"),
+        ("since", "2026-10-15 08:00"),
+        (
+            "summary",
+            "■ 新規車検証 (1件)\n帯広100け201 日野\n\n■ 期限切れ・期限間近 (1台)\n帯広100け202 いすゞ 期限: 261105",
+        ),
+        ("new_inspection_count", "1"),
+        ("expiring_count", "1"),
+        ("failed_sync_count", "0"),
+        ("low_stock_count", "0"),
+    ],
+};
+
+/// レポートの作成完了
+pub const REPORT_READY: NotificationTemplate = NotificationTemplate {
+    key: "reports.ready",
+    subject: "【レポート】{{report_name}}（{{period}}）",
+    body: "{{report_name}}（{{period}}）を作成しました。\n\n{{download_url}}\n\nファイル: {{file_name}}\n件数: {{row_count}}\n",
+    variables: &[
+        ("report_name", "車両稼働状況"),
+        ("period", "2026-09-01 〜 2026-09-30"),
+        ("download_url", "https://app.example.com/reports/xxxx"),
+        ("file_name", "車両稼働状況_20260901_20260930.xlsx"),
+        ("row_count", "24"),
+    ],
+};
+
+/// 承認待ちの参加リクエスト（定期実行で管理者にリマインド）
+pub const ACCESS_REQUEST_REMINDER: NotificationTemplate = NotificationTemplate {
+    key: "access_request.reminder",
+    subject: "【参加リクエスト】{{count}}件が承認待ちです",
+    body: "{{organization_name}} への参加リクエストが承認待ちです（{{count}}件）。\n\n{{requests}}\n\n\
+           期限を過ぎたリクエストは自動的に期限切れになります。\n",
+    variables: &[
+        ("organization_name", "大石運輸"),
+        ("count", "2"),
+        ("requests", "山田太郎 (yamada@example.com) 期限: 2026-10-23 09:00\n佐藤花子 (sato@example.com) 期限: 2026-10-25 18:00"),
+    ],
+};
+
+/// 組織ごとに上書きできるテンプレート
+pub const BUILTIN_TEMPLATES: &[NotificationTemplate] = &[
+    EXPIRY_ALERT,
+    DVR_ALERT,
+    INVITATION,
+    PASSWORD_RESET,
+    WEBHOOK_DISABLED,
+    DIGEST,
+    REPORT_READY,
+    ACCESS_REQUEST_REMINDER,
+];
+
+pub fn builtin_template(key: &str) -> Option<NotificationTemplate> {
+    BUILTIN_TEMPLATES.iter().find(|t| t.key == key).copied()
+}
+
+impl NotificationTemplate {
+    /// プレビュー用のサンプル変数
+    pub fn sample_vars(&self) -> BTreeMap<String, String> {
+        self.variables
+            .iter()
+            .map(|(name, sample)| (name.to_string(), sample.to_string()))
+            .collect()
+    }
+}
+
+/// テンプレート中の `{{name}}` のうち allowed にないもの（重複なし、出現順）
+pub fn unknown_variables(template: &str, allowed: &[&str]) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        if !allowed.contains(&name) && !unknown.iter().any(|u| u == name) {
+            unknown.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    unknown
+}
+
+/// `{{name}}`（前後の空白可）を変数で置換する。未定義の変数はそのまま残す
+pub fn render(template: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        match vars.get(after[..end].trim()) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 通知に載せる日時（JST）
+pub fn format_jst(time: DateTime<Utc>) -> String {
+    let jst = FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    time.with_timezone(&jst).format("%Y-%m-%d %H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_replaces_known_variables() {
+        let vars = BTreeMap::from([
+            ("car_no".to_string(), "帯広100け201".to_string()),
+            ("count".to_string(), "2".to_string()),
+        ]);
+        assert_eq!(
+            render("{{ car_no }} ほか{{count}}台 {{unknown}} {{", &vars),
+            "帯広100け201 ほか2台 {{unknown}} {{"
+        );
+    }
+
+    #[test]
+    fn test_unknown_variables_lists_undefined_names_once() {
+        assert_eq!(
+            unknown_variables("{{count}}台 {{ car_no }} {{foo}} {{foo}} {{bar}}", &["count", "car_no"]),
+            vec!["foo".to_string(), "bar".to_string()]
+        );
+        for template in BUILTIN_TEMPLATES {
+            let names: Vec<&str> = template.variables.iter().map(|(n, _)| *n).collect();
+            assert!(unknown_variables(template.subject, &names).is_empty(), "{}", template.key);
+            assert!(unknown_variables(template.body, &names).is_empty(), "{}", template.key);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use super::{NotificationChannel, NotificationSettings, OutgoingMessage};
+use crate::db::OrgScopedPool;
+use crate::http_client::HttpClient;
+use crate::services::lineworks_auth;
+
+pub const SLACK_CHANNEL: &str = "slack";
+pub const DISCORD_CHANNEL: &str = "discord";
+
+/// Discord の content 上限（文字数）
+const DISCORD_MAX_CHARS: usize = 2000;
+/// Slack の text 推奨上限（文字数）
+const SLACK_MAX_CHARS: usize = 4000;
+
+/// 登録可能な Webhook URL か（provider ごとの公式ホストのみ）
+pub fn is_valid_webhook_url(provider: &str, url: &str) -> bool {
+    match provider {
+        SLACK_CHANNEL => url.starts_with("https://hooks.slack.com/"),
+        DISCORD_CHANNEL => {
+            url.starts_with("https://discord.com/api/webhooks/")
+                || url.starts_with("https://discordapp.com/api/webhooks/")
+        }
+        _ => false,
+    }
+}
+
+/// 一覧表示用に URL のトークン部分を伏せる（末尾4文字のみ）
+pub fn webhook_url_hint(url: &str) -> String {
+    let prefix = url.splitn(4, '/').take(3).collect::<Vec<_>>().join("/");
+    let chars: Vec<char> = url.chars().collect();
+    let tail: String = chars[chars.len().saturating_sub(4)..].iter().collect();
+    format!("{}/…{}", prefix, tail)
+}
+
+fn truncate(text: String, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Slack の incoming webhook 本文（件名は太字、mrkdwn の制御文字はエスケープ）
+pub fn slack_message(subject: &str, body: &str) -> serde_json::Value {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let text = if subject.is_empty() {
+        escape(body.trim_end())
+    } else {
+        format!("*{}*\n{}", escape(subject), escape(body.trim_end()))
+    };
+    serde_json::json!({ "text": truncate(text, SLACK_MAX_CHARS) })
+}
+
+/// Discord の webhook 本文（件名は太字、メンションは無効化）
+pub fn discord_message(subject: &str, body: &str) -> serde_json::Value {
+    let text = if subject.is_empty() {
+        body.trim_end().to_string()
+    } else {
+        format!("**{}**\n{}", subject, body.trim_end())
+    };
+    serde_json::json!({
+        "content": truncate(text, DISCORD_MAX_CHARS),
+        "allowed_mentions": { "parse": [] },
+    })
+}
+
+/// 組織の notification_webhooks に送る（宛先は notification_webhooks.id）
+pub struct ChatWebhookChannel {
+    pool: PgPool,
+    http_client: Arc<HttpClient>,
+    /// URL の復号キー（JWT_SECRET）
+    secret_key: String,
+}
+
+impl ChatWebhookChannel {
+    pub fn new(pool: PgPool, http_client: Arc<HttpClient>, secret_key: String) -> Self {
+        Self {
+            pool,
+            http_client,
+            secret_key,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl NotificationChannel for ChatWebhookChannel {
+    async fn send(&self, message: &OutgoingMessage, _settings: &NotificationSettings) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire_scoped(&message.organization_id).await?;
+        let webhook: Option<(String, String)> = sqlx::query_as(
+            "SELECT provider, url_encrypted FROM notification_webhooks WHERE id = $1::uuid AND enabled = TRUE",
+        )
+        .bind(&message.recipient)
+        .fetch_optional(&mut *conn)
+        .await?;
+        drop(conn);
+        let Some((provider, url_encrypted)) = webhook else {
+            // 削除・無効化された Webhook 宛ては送らない
+            tracing::info!("Skipping notification {}: webhook {} is gone or disabled", message.id, message.recipient);
+            return Ok(());
+        };
+        let url = lineworks_auth::decrypt_secret(&url_encrypted, &self.secret_key)
+            .map_err(|e| anyhow::anyhow!("Failed to decrypt webhook URL: {}", e))?;
+
+        let payload = match provider.as_str() {
+            SLACK_CHANNEL => slack_message(&message.subject, &message.body),
+            _ => discord_message(&message.subject, &message.body),
+        };
+        let response = self.http_client.post_json(&url, &payload).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} webhook failed: {} - {}", provider, status, body);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_messages_are_formatted_per_provider() {
+        assert_eq!(
+            slack_message("【DVR通知】", "a < b & c\n")["text"],
+            "*【DVR通知】*\na &lt; b &amp; c"
+        );
+        let discord = discord_message("【DVR通知】", "@everyone");
+        assert_eq!(discord["content"], "**【DVR通知】**\n@everyone");
+        assert_eq!(discord["allowed_mentions"]["parse"], serde_json::json!([]));
+
+        assert!(is_valid_webhook_url(SLACK_CHANNEL, "https://hooks.slack.com/services/T/B/x"));
+        assert!(!is_valid_webhook_url(DISCORD_CHANNEL, "https://example.com/api/webhooks/1"));
+        assert_eq!(
+            webhook_url_hint("https://hooks.slack.com/services/T0/B0/abcdef"),
+            "https://hooks.slack.com/…cdef"
+        );
+    }
+}
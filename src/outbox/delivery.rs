@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::{FromRow, PgPool};
+use tokio::task::JoinSet;
+
+use crate::http_client::HttpClient;
+use crate::jobs::worker::retry_delay;
+use crate::shutdown;
+
+/// 送信可能なメッセージがないときのポーリング間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// ack されないメッセージを再取得するまでの時間
+const LOCK_TIMEOUT: &str = "5 minutes";
+/// 1回に取得する配送先の数（配送先ごとに先頭1件）
+const CLAIM_BATCH: i32 = 20;
+/// この回数失敗したら failed にして後続を送る（バックオフ上限 1 時間で約半日）
+const MAX_ATTEMPTS: i32 = 20;
+
+/// LINE WORKS（lineworks-bot-rust）へのテキスト通知
+pub const LINEWORKS_TARGET: &str = "lineworks";
+
+/// claim_outbox で取得したメッセージ
+#[derive(Debug, Clone, FromRow)]
+pub struct OutboxMessage {
+    pub id: i64,
+    pub organization_id: String,
+    pub target: String,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    /// 今回の送信を含む試行回数
+    pub attempts: i32,
+}
+
+impl OutboxMessage {
+    /// OutboxEvent::message で設定された本文（なければイベント名 + payload）
+    pub fn text(&self) -> String {
+        self.payload
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}: {}", self.event_type, self.payload))
+    }
+}
+
+/// 配送先ごとの送信処理
+#[tonic::async_trait]
+pub trait OutboxDelivery: Send + Sync {
+    /// Err を返すとバックオフ後に同じメッセージを再送する（後続は待つ）
+    async fn deliver(&self, message: &OutboxMessage) -> anyhow::Result<()>;
+}
+
+/// lineworks-bot-rust の /api/tasks にテキストを送る
+pub struct LineWorksTarget {
+    http_client: Arc<HttpClient>,
+    bot_url: String,
+}
+
+impl LineWorksTarget {
+    pub fn new(http_client: Arc<HttpClient>, bot_url: String) -> Self {
+        Self { http_client, bot_url }
+    }
+}
+
+#[tonic::async_trait]
+impl OutboxDelivery for LineWorksTarget {
+    async fn deliver(&self, message: &OutboxMessage) -> anyhow::Result<()> {
+        let payload = serde_json::json!({
+            "test": "sendTextMessageLine",
+            "message": message.text()
+        });
+        let api_url = format!("{}/api/tasks", self.bot_url.trim_end_matches('/'));
+
+        let response = self.http_client.post_json(&api_url, &payload).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("LINE notification failed: {} - {}", status, body);
+        }
+        Ok(())
+    }
+}
+
+/// outbox を配送先ごとに順番に送るワーカー
+pub struct OutboxWorker {
+    pool: PgPool,
+    targets: HashMap<&'static str, Arc<dyn OutboxDelivery>>,
+}
+
+impl OutboxWorker {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            targets: HashMap::new(),
+        }
+    }
+
+    pub fn target(mut self, name: &'static str, delivery: impl OutboxDelivery + 'static) -> Self {
+        self.targets.insert(name, Arc::new(delivery));
+        self
+    }
+
+    pub fn target_names(&self) -> Vec<&'static str> {
+        self.targets.keys().copied().collect()
+    }
+
+    pub fn spawn(self) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let worker_id = format!("outbox-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        tracing::info!("Starting outbox worker for: {:?}", self.target_names());
+        let worker = Arc::new(self);
+        // drain が始まったら新しく claim せず、送信中のものを終えて止まる
+        shutdown::spawn(async move { worker.run(worker_id).await });
+    }
+
+    async fn run(self: Arc<Self>, worker_id: String) {
+        let names = self.target_names();
+        while !shutdown::is_draining() {
+            let claimed = sqlx::query_as::<_, OutboxMessage>(
+                "SELECT * FROM claim_outbox($1, $2, $3::interval, $4)",
+            )
+            .bind(&worker_id)
+            .bind(&names)
+            .bind(LOCK_TIMEOUT)
+            .bind(CLAIM_BATCH)
+            .fetch_all(&self.pool)
+            .await;
+
+            let messages = match claimed {
+                Ok(messages) if !messages.is_empty() => messages,
+                Ok(_) => {
+                    shutdown::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to claim outbox messages: {}", e);
+                    shutdown::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+
+            // 取得したものは全て別の配送先の先頭なので並行に送ってよい
+            let mut tasks = JoinSet::new();
+            for message in messages {
+                let worker = self.clone();
+                let worker_id = worker_id.clone();
+                tasks.spawn(async move { worker.deliver(message, &worker_id).await });
+            }
+            while tasks.join_next().await.is_some() {}
+        }
+    }
+
+    async fn deliver(&self, message: OutboxMessage, worker_id: &str) {
+        let Some(delivery) = self.targets.get(message.target.as_str()) else {
+            return;
+        };
+
+        match delivery.deliver(&message).await {
+            Ok(()) => {
+                if let Err(e) = sqlx::query("SELECT ack_outbox($1, $2)")
+                    .bind(message.id)
+                    .bind(worker_id)
+                    .execute(&self.pool)
+                    .await
+                {
+                    tracing::error!("Failed to ack outbox message {}: {}", message.id, e);
+                }
+            }
+            Err(err) => {
+                let retry_at = chrono::Utc::now()
+                    + chrono::Duration::from_std(retry_delay(message.attempts)).unwrap_or_default();
+                let status = sqlx::query_scalar::<_, Option<String>>(
+                    "SELECT fail_outbox($1, $2, $3, $4, $5)",
+                )
+                .bind(message.id)
+                .bind(worker_id)
+                .bind(format!("{:#}", err))
+                .bind(retry_at)
+                .bind(MAX_ATTEMPTS)
+                .fetch_one(&self.pool)
+                .await;
+                match status {
+                    Ok(Some(status)) if status == "failed" => tracing::error!(
+                        "Outbox message {} ({} -> {}) dropped after {} attempts: {:#}",
+                        message.id, message.event_type, message.target, message.attempts, err
+                    ),
+                    Ok(_) => tracing::warn!(
+                        "Outbox message {} ({} -> {}) failed (attempt {}), retrying at {}: {:#}",
+                        message.id, message.event_type, message.target, message.attempts, retry_at, err
+                    ),
+                    Err(e) => tracing::error!("Failed to record outbox failure {}: {}", message.id, e),
+                }
+            }
+        }
+    }
+}
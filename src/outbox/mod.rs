@@ -0,0 +1,146 @@
+// Transactional outbox
+//
+// 外部への通知（LINE WORKS など）は送信をリクエスト内で行わず、業務データと同じトランザクションで
+// outbox テーブルに書く。OutboxWorker が配送先ごとに id 順で送り、成功するまで再送する（at-least-once）。
+// コミットされなかった書き込みの通知は送られず、コミットされたものは再起動しても失われない。
+
+pub mod delivery;
+
+use std::sync::Arc;
+
+use serde::Serialize;
+use sqlx::PgConnection;
+
+pub use delivery::{LineWorksTarget, OutboxDelivery, OutboxMessage, OutboxWorker, LINEWORKS_TARGET};
+
+/// 車検証が新規登録された
+pub const CAR_INSPECTION_CREATED: &str = "car_inspection.created";
+/// 車検証の期限切れ・期限間近（定期実行）
+pub const CAR_INSPECTION_EXPIRING: &str = "car_inspection.expiring";
+/// カメラSD同期で新しいファイルを取り込んだ
+pub const CAM_FILES_SYNCED: &str = "cam_files.synced";
+/// DVR 通知（イベント発生）
+pub const DVR_ALERT: &str = "dvr.alert";
+/// job が max_attempts 回失敗して dead_letter になった
+pub const JOB_DEAD_LETTERED: &str = "jobs.dead_lettered";
+/// 車検証ファイル（JSON / PDF）を解析して車検証に紐づけた
+pub const FILE_PARSED: &str = "files.parsed";
+/// 車両がジオフェンスに入った
+pub const GEOFENCE_ENTERED: &str = "geofence.entered";
+/// 車両がジオフェンスを出た
+pub const GEOFENCE_EXITED: &str = "geofence.exited";
+
+/// Webhook で購読できるイベント
+pub const EVENT_TYPES: &[&str] = &[
+    CAR_INSPECTION_CREATED,
+    CAR_INSPECTION_EXPIRING,
+    CAM_FILES_SYNCED,
+    DVR_ALERT,
+    JOB_DEAD_LETTERED,
+    FILE_PARSED,
+    GEOFENCE_ENTERED,
+    GEOFENCE_EXITED,
+];
+
+/// outbox に書くイベント
+#[derive(Debug, Clone)]
+pub struct OutboxEvent {
+    pub event_type: &'static str,
+    pub payload: serde_json::Value,
+    /// 送る配送先を限る（None なら有効な配送先すべて）
+    pub only_targets: Option<&'static [&'static str]>,
+}
+
+impl OutboxEvent {
+    pub fn new(event_type: &'static str, payload: impl Serialize) -> Self {
+        Self {
+            event_type,
+            payload: serde_json::to_value(payload).unwrap_or_default(),
+            only_targets: None,
+        }
+    }
+
+    /// 件数の多いイベントをテキスト配送先（LINE WORKS 等）に流さないときに使う
+    pub fn only(mut self, targets: &'static [&'static str]) -> Self {
+        self.only_targets = Some(targets);
+        self
+    }
+
+    fn sent_to(&self, target: &str) -> bool {
+        match self.only_targets {
+            Some(only) => only.contains(&target),
+            None => true,
+        }
+    }
+
+    /// テキスト配送先（LINE WORKS 等）に送る本文
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        if let Some(payload) = self.payload.as_object_mut() {
+            payload.insert("message".to_string(), message.into().into());
+        }
+        self
+    }
+}
+
+/// 有効な配送先ごとにイベントを outbox に書く
+#[derive(Clone, Default)]
+pub struct Outbox {
+    targets: Arc<Vec<&'static str>>,
+}
+
+impl Outbox {
+    pub fn new(targets: Vec<&'static str>) -> Self {
+        Self {
+            targets: Arc::new(targets),
+        }
+    }
+
+    /// 業務データと同じトランザクション（organization 設定済み）で呼ぶ
+    pub async fn write(
+        &self,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        event: &OutboxEvent,
+    ) -> Result<(), sqlx::Error> {
+        let targets: Vec<&str> = self.targets.iter().copied().filter(|t| event.sent_to(t)).collect();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO outbox (organization_id, target, event_type, payload)
+            SELECT $1::uuid, t.target, $3, $4
+            FROM UNNEST($2::text[]) AS t(target)
+            "#,
+        )
+        .bind(organization_id)
+        .bind(targets.as_slice())
+        .bind(event.event_type)
+        .bind(&event.payload)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_message_is_added_to_payload() {
+        let event = OutboxEvent::new(CAM_FILES_SYNCED, serde_json::json!({ "new_files": 3 }))
+            .message("3 files");
+        assert_eq!(event.payload["new_files"], 3);
+        assert_eq!(event.payload["message"], "3 files");
+    }
+
+    #[test]
+    fn test_event_can_be_limited_to_targets() {
+        let event = OutboxEvent::new(FILE_PARSED, serde_json::json!({}));
+        assert!(event.sent_to("lineworks"));
+        let event = event.only(&["webhooks"]);
+        assert!(event.sent_to("webhooks"));
+        assert!(!event.sent_to("lineworks"));
+    }
+}
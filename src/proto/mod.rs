@@ -1,6 +1,9 @@
 // Generated proto modules will be included here after build
 // Run `cargo build` to generate the proto code
 
+/// File descriptor set (gRPC reflection / REST gateway)
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("logi_descriptor");
+
 pub mod common {
     include!("logi.common.rs");
 }
@@ -60,3 +63,58 @@ pub mod access_request {
 pub mod items {
     include!("logi.items.rs");
 }
+
+pub mod scheduler {
+    include!("logi.scheduler.rs");
+}
+
+pub mod jobs {
+    include!("logi.jobs.rs");
+}
+
+pub mod notifications {
+    include!("logi.notifications.rs");
+}
+
+pub mod webhooks {
+    include!("logi.webhooks.rs");
+}
+
+pub mod etc {
+    include!("logi.etc.rs");
+}
+
+pub mod fuel {
+    include!("logi.fuel.rs");
+}
+
+pub mod ichiban_cars {
+    include!("logi.ichiban_cars.rs");
+}
+
+pub mod reports {
+    include!("logi.reports.rs");
+}
+
+pub mod admin {
+    include!("logi.admin.rs");
+}
+
+pub mod api_keys {
+    include!("logi.api_keys.rs");
+}
+
+pub mod search {
+    include!("logi.search.rs");
+}
+
+pub mod geofences {
+    include!("logi.geofences.rs");
+}
+
+/// v2 packages（logi.v2.*）。v1 は上記の logi.* で凍結
+pub mod v2 {
+    pub mod files {
+        include!("logi.v2.files.rs");
+    }
+}
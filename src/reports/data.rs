@@ -0,0 +1,483 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+
+use super::{ReportKind, ReportTable, ReportValue};
+
+/// 1日の拘束時間の上限（改善基準告示の原則）
+const MAX_DAILY_BINDING_HOURS: f64 = 13.0;
+
+/// JST の日付の範囲 [from, to] を timestamptz の文字列 [start, end) にする
+pub fn jst_range(from: NaiveDate, to: NaiveDate) -> (String, String) {
+    let end = to.succ_opt().unwrap_or(to);
+    (
+        format!("{}T00:00:00+09:00", from.format("%Y-%m-%d")),
+        format!("{}T00:00:00+09:00", end.format("%Y-%m-%d")),
+    )
+}
+
+/// 車検証の有効期限（YYMMDD）
+fn parse_expiry(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("20{}", value.trim()), "%Y%m%d").ok()
+}
+
+/// 和暦の交付日（GrantdateE / Y / M / D、全角数字・空白を含むことがある）
+fn grant_date(era: &str, year: &str, month: &str, day: &str) -> Option<NaiveDate> {
+    let number = |s: &str| -> Option<u32> {
+        let digits: String = s
+            .chars()
+            .filter_map(|c| match c {
+                '0'..='9' => Some(c),
+                '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32),
+                _ => None,
+            })
+            .collect();
+        digits.parse().ok()
+    };
+    let offset = match era.replace([' ', '\u{3000}'], "").as_str() {
+        "令和" => 2018,
+        "平成" => 1988,
+        "昭和" => 1925,
+        _ => return None,
+    };
+    NaiveDate::from_ymd_opt(offset + number(year)? as i32, number(month)?, number(day)?)
+}
+
+fn expiry_status(days_left: i64) -> &'static str {
+    match days_left {
+        d if d < 0 => "期限切れ",
+        d if d <= 30 => "30日以内",
+        d if d <= 90 => "90日以内",
+        _ => "有効",
+    }
+}
+
+/// 車両ごとの稼働（運行ログから集計）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VehicleUtilizationRow {
+    pub vehicle_cd: i32,
+    pub vehicle_name: String,
+    pub days_in_use: i64,
+    /// 期間内の odometer の最大 − 最小（数値の odometer がなければ None）
+    pub distance_km: Option<f64>,
+    pub log_count: i64,
+    /// 停車中（速度 0）の記録の割合
+    pub idle_ratio: Option<f64>,
+}
+
+/// 期間 [from, to]（JST）の車両ごとの稼働（organization 設定済みのコネクション）
+pub async fn vehicle_utilization(
+    conn: &mut PgConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<VehicleUtilizationRow>, sqlx::Error> {
+    let (start, end) = jst_range(from, to);
+    sqlx::query_as(
+        r#"
+        WITH logs AS (
+            SELECT vehicle_cd, vehicle_name, speed,
+                   (data_date_time::timestamptz AT TIME ZONE 'Asia/Tokyo')::date AS day,
+                   CASE WHEN odometer ~ '^\s*[0-9]+(\.[0-9]+)?\s*$' THEN trim(odometer)::float8 END AS odometer_km
+            FROM dtakologs
+            WHERE data_date_time::timestamptz >= $1::timestamptz
+              AND data_date_time::timestamptz < $2::timestamptz
+        )
+        SELECT vehicle_cd, MAX(vehicle_name) AS vehicle_name,
+               COUNT(DISTINCT day) AS days_in_use,
+               NULLIF(MAX(odometer_km) - MIN(odometer_km), 0) AS distance_km,
+               COUNT(*) AS log_count,
+               (COUNT(*) FILTER (WHERE speed < 1))::float8 / NULLIF(COUNT(*), 0) AS idle_ratio
+        FROM logs
+        GROUP BY vehicle_cd
+        ORDER BY vehicle_cd
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(conn)
+    .await
+}
+
+/// 運行ログの間隔がこれを超えたら、その間は走行・停車のどちらにも数えない（電源断・通信途絶）
+pub const MAX_STEP_SECONDS: f64 = 600.0;
+
+/// 日・車両ごとの運行集計（vehicle_total の行が車両の合計、それ以外は乗務員ごと）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DailyVehicleRow {
+    pub day: NaiveDate,
+    pub vehicle_cd: i32,
+    pub vehicle_total: bool,
+    /// 車両の合計の行は None
+    pub driver_cd: Option<i32>,
+    pub vehicle_name: String,
+    pub driver_name: Option<String>,
+    /// odometer の増分（前の数値の odometer との差、減った分は数えない）の合計
+    pub distance_km: Option<f64>,
+    pub driving_seconds: f64,
+    pub idle_seconds: f64,
+    pub max_speed: f64,
+    /// 走行中（速度 1 以上）の記録の平均速度
+    pub avg_speed: Option<f64>,
+    pub log_count: i64,
+}
+
+/// 期間 [from, to]（JST）の日・車両ごと、乗務員ごとの運行集計（organization 設定済みのコネクション）
+///
+/// 距離は odometer の増分、時間は次の記録までの間隔（MAX_STEP_SECONDS まで）をその記録の速度で
+/// 走行・停車に振り分ける。どちらもウィンドウ関数で車両ごとに前後の記録と比べる。
+pub async fn daily_vehicle_report(
+    conn: &mut PgConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+    vehicle_cds: &[i32],
+) -> Result<Vec<DailyVehicleRow>, sqlx::Error> {
+    let (start, end) = jst_range(from, to);
+    sqlx::query_as(
+        r#"
+        WITH logs AS (
+            SELECT vehicle_cd, vehicle_name, driver_cd, driver_name, speed::float8 AS speed,
+                   data_date_time::timestamptz AS ts,
+                   CASE WHEN odometer ~ '^\s*[0-9]+(\.[0-9]+)?\s*$' THEN trim(odometer)::float8 END AS odometer_km
+            FROM dtakologs
+            WHERE data_date_time::timestamptz >= $1::timestamptz
+              AND data_date_time::timestamptz < $2::timestamptz
+              AND (cardinality($3::int[]) = 0 OR vehicle_cd = ANY($3))
+        ),
+        steps AS (
+            SELECT vehicle_cd, vehicle_name, driver_cd, driver_name, speed,
+                   (ts AT TIME ZONE 'Asia/Tokyo')::date AS day,
+                   EXTRACT(EPOCH FROM LEAD(ts) OVER (PARTITION BY vehicle_cd ORDER BY ts) - ts)::float8 AS seconds,
+                   -- odometer のない行を飛ばして前の数値と比べる
+                   odometer_km - LAG(odometer_km) OVER (
+                       PARTITION BY vehicle_cd, odometer_km IS NULL ORDER BY ts
+                   ) AS odometer_delta
+            FROM logs
+        )
+        SELECT day, vehicle_cd,
+               GROUPING(driver_cd) = 1 AS vehicle_total,
+               driver_cd,
+               MAX(vehicle_name) AS vehicle_name,
+               CASE WHEN GROUPING(driver_cd) = 0 THEN MAX(driver_name) END AS driver_name,
+               SUM(GREATEST(odometer_delta, 0)) AS distance_km,
+               COALESCE(SUM(seconds) FILTER (WHERE speed >= 1 AND seconds <= $4), 0) AS driving_seconds,
+               COALESCE(SUM(seconds) FILTER (WHERE speed < 1 AND seconds <= $4), 0) AS idle_seconds,
+               MAX(speed) AS max_speed,
+               AVG(speed) FILTER (WHERE speed >= 1) AS avg_speed,
+               COUNT(*) AS log_count
+        FROM steps
+        GROUP BY GROUPING SETS ((day, vehicle_cd), (day, vehicle_cd, driver_cd))
+        ORDER BY day, vehicle_cd, GROUPING(driver_cd) DESC, driver_cd
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(vehicle_cds)
+    .bind(MAX_STEP_SECONDS)
+    .fetch_all(conn)
+    .await
+}
+
+/// 車両ごとの最新の車検証と書類の有無（月次コンプライアンス用）
+#[derive(Debug, sqlx::FromRow)]
+struct ComplianceRow {
+    branch: Option<String>,
+    car_no: String,
+    car_name: String,
+    expiry: String,
+    grantdate_e: String,
+    grantdate_y: String,
+    grantdate_m: String,
+    grantdate_d: String,
+    /// 同じ CarId の車検証の件数（2件以上なら交付日は更新日）
+    record_count: i64,
+    has_json: bool,
+    has_pdf: bool,
+}
+
+/// 車検の月次コンプライアンス: 期間末時点の期限、書類（車検証 JSON / PDF）の不足、期間内の更新
+async fn monthly_compliance(
+    conn: &mut PgConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+    branch: Option<&str>,
+) -> Result<ReportTable, sqlx::Error> {
+    let vehicles: Vec<ComplianceRow> = sqlx::query_as(
+        r#"
+        WITH latest AS (
+            SELECT DISTINCT ON (ci."CarId")
+                   ci.*, COUNT(*) OVER (PARTITION BY ci."CarId") AS record_count
+            FROM car_inspection ci
+            ORDER BY ci."CarId", ci."TwodimensionCodeInfoValidPeriodExpirdate" DESC, ci.created_at DESC
+        )
+        SELECT ic.bumon_code_id AS branch,
+               l."CarNo" AS car_no, l."CarName" AS car_name,
+               l."TwodimensionCodeInfoValidPeriodExpirdate" AS expiry,
+               l."GrantdateE" AS grantdate_e, l."GrantdateY" AS grantdate_y,
+               l."GrantdateM" AS grantdate_m, l."GrantdateD" AS grantdate_d,
+               l.record_count,
+               EXISTS (
+                   SELECT 1 FROM car_inspection_files_a fa
+                   WHERE fa."ElectCertMgNo" = l."ElectCertMgNo"
+                     AND fa."GrantdateE" = l."GrantdateE" AND fa."GrantdateY" = l."GrantdateY"
+                     AND fa."GrantdateM" = l."GrantdateM" AND fa."GrantdateD" = l."GrantdateD"
+                     AND fa.type = 'application/json' AND fa.deleted_at IS NULL
+               ) AS has_json,
+               EXISTS (
+                   SELECT 1 FROM car_inspection_files_b fb
+                   WHERE fb."ElectCertMgNo" = l."ElectCertMgNo"
+                     AND fb."GrantdateE" = l."GrantdateE" AND fb."GrantdateY" = l."GrantdateY"
+                     AND fb."GrantdateM" = l."GrantdateM" AND fb."GrantdateD" = l."GrantdateD"
+                     AND fb.type = 'application/pdf' AND fb.deleted_at IS NULL
+               ) AS has_pdf
+        FROM latest l
+        LEFT JOIN car_ins_sheet_ichiban_cars_a s
+               ON s."ElectCertMgNo" = l."ElectCertMgNo"
+              AND s."GrantdateE" = l."GrantdateE" AND s."GrantdateY" = l."GrantdateY"
+              AND s."GrantdateM" = l."GrantdateM" AND s."GrantdateD" = l."GrantdateD"
+        LEFT JOIN ichiban_cars ic ON ic.id = s.id_cars
+        WHERE $1::text IS NULL OR ic.bumon_code_id = $1
+        ORDER BY ic.bumon_code_id NULLS LAST, l."TwodimensionCodeInfoValidPeriodExpirdate", l."CarNo"
+        "#,
+    )
+    .bind(branch)
+    .fetch_all(conn)
+    .await?;
+
+    let mut expired = 0;
+    let mut missing_vehicles = 0;
+    let mut renewals_by_month: BTreeMap<String, usize> = BTreeMap::new();
+    let rows: Vec<Vec<ReportValue>> = vehicles
+        .into_iter()
+        .map(|v| {
+            let expiry_date = parse_expiry(&v.expiry);
+            let days_left = expiry_date.map(|d| (d - to).num_days());
+            if days_left.is_some_and(|d| d < 0) {
+                expired += 1;
+            }
+            let missing = [v.has_json, v.has_pdf].iter().filter(|has| !**has).count();
+            if missing > 0 {
+                missing_vehicles += 1;
+            }
+            let granted = grant_date(&v.grantdate_e, &v.grantdate_y, &v.grantdate_m, &v.grantdate_d);
+            let renewed = v.record_count > 1 && granted.is_some_and(|d| from <= d && d <= to);
+            if let Some(d) = granted.filter(|_| renewed) {
+                *renewals_by_month.entry(d.format("%Y-%m").to_string()).or_default() += 1;
+            }
+            let mark = |has: bool| ReportValue::Text(if has { "○" } else { "未登録" }.to_string());
+            vec![
+                ReportValue::Text(v.branch.unwrap_or_else(|| "未設定".to_string())),
+                ReportValue::Text(v.car_no),
+                ReportValue::Text(v.car_name),
+                ReportValue::Text(expiry_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or(v.expiry)),
+                days_left.map(|d| ReportValue::Number(d as f64)).unwrap_or_default(),
+                ReportValue::Text(days_left.map(expiry_status).unwrap_or("不明").to_string()),
+                mark(v.has_json),
+                mark(v.has_pdf),
+                ReportValue::Number(missing as f64),
+                ReportValue::Text(granted.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()),
+                ReportValue::Text(if renewed { "更新" } else { "" }.to_string()),
+            ]
+        })
+        .collect();
+
+    let renewals: usize = renewals_by_month.values().sum();
+    let mut subtitle = format!(
+        "{} 〜 {}{} / 対象 {}台・期限切れ {}台・書類不足 {}台・期間内の更新 {}台",
+        from.format("%Y-%m-%d"),
+        to.format("%Y-%m-%d"),
+        branch.map(|b| format!("（部門 {}）", b)).unwrap_or_default(),
+        rows.len(),
+        expired,
+        missing_vehicles,
+        renewals
+    );
+    if renewals_by_month.len() > 1 {
+        let by_month: Vec<String> = renewals_by_month.iter().map(|(m, n)| format!("{} {}台", m, n)).collect();
+        subtitle.push_str(&format!("（{}）", by_month.join("、")));
+    }
+    Ok(ReportTable {
+        title: ReportKind::MonthlyCompliance.title().to_string(),
+        subtitle,
+        columns: [
+            "部門",
+            "車両番号",
+            "車名",
+            "有効期限",
+            "残日数",
+            "状態",
+            "車検証JSON",
+            "車検証PDF",
+            "不足書類数",
+            "交付日",
+            "期間内更新",
+        ]
+        .map(String::from)
+        .to_vec(),
+        rows,
+    })
+}
+
+/// レポートの表を作る（organization 設定済みのコネクション）
+///
+/// branch（ichiban_cars.bumon_code_id）は `ReportKind::supports_branch` の種類だけが使う
+pub async fn build_report(
+    conn: &mut PgConnection,
+    kind: ReportKind,
+    from: NaiveDate,
+    to: NaiveDate,
+    branch: Option<&str>,
+) -> Result<ReportTable, sqlx::Error> {
+    let period = format!("{} 〜 {}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+    match kind {
+        ReportKind::InspectionCompliance => {
+            let inspections: Vec<(String, String, String)> = sqlx::query_as(
+                r#"
+                SELECT "CarNo", "CarName", "TwodimensionCodeInfoValidPeriodExpirdate" FROM car_inspection
+                ORDER BY "TwodimensionCodeInfoValidPeriodExpirdate" ASC, "CarNo"
+                "#,
+            )
+            .fetch_all(conn)
+            .await?;
+            let rows = inspections
+                .into_iter()
+                .map(|(car_no, car_name, expiry)| {
+                    let expiry_date = parse_expiry(&expiry);
+                    let days_left = expiry_date.map(|d| (d - to).num_days());
+                    vec![
+                        ReportValue::Text(car_no),
+                        ReportValue::Text(car_name),
+                        ReportValue::Text(expiry_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or(expiry)),
+                        days_left.map(|d| ReportValue::Number(d as f64)).unwrap_or_default(),
+                        ReportValue::Text(days_left.map(expiry_status).unwrap_or("不明").to_string()),
+                    ]
+                })
+                .collect();
+            Ok(ReportTable {
+                title: kind.title().to_string(),
+                subtitle: format!("{} 時点", to.format("%Y-%m-%d")),
+                columns: ["車両番号", "車名", "有効期限", "残日数", "状態"].map(String::from).to_vec(),
+                rows,
+            })
+        }
+        ReportKind::DriverHours => {
+            let (start, end) = jst_range(from, to);
+            // 日ごとの拘束時間は、その日の最初と最後の運行ログの間隔で近似する
+            let drivers: Vec<(i32, Option<String>, i64, f64, f64, i64)> = sqlx::query_as(
+                r#"
+                WITH days AS (
+                    SELECT driver_cd, MAX(driver_name) AS driver_name,
+                           (data_date_time::timestamptz AT TIME ZONE 'Asia/Tokyo')::date AS day,
+                           extract(epoch FROM MAX(data_date_time::timestamptz) - MIN(data_date_time::timestamptz))::float8
+                               / 3600.0 AS hours
+                    FROM dtakologs
+                    WHERE driver_cd <> 0
+                      AND data_date_time::timestamptz >= $1::timestamptz
+                      AND data_date_time::timestamptz < $2::timestamptz
+                    GROUP BY driver_cd, day
+                )
+                SELECT driver_cd, MAX(driver_name), COUNT(*), SUM(hours), MAX(hours),
+                       COUNT(*) FILTER (WHERE hours > $3)
+                FROM days
+                GROUP BY driver_cd
+                ORDER BY driver_cd
+                "#,
+            )
+            .bind(start)
+            .bind(end)
+            .bind(MAX_DAILY_BINDING_HOURS)
+            .fetch_all(conn)
+            .await?;
+            let rows = drivers
+                .into_iter()
+                .map(|(driver_cd, driver_name, days, total, max, over)| {
+                    vec![
+                        ReportValue::Number(driver_cd as f64),
+                        ReportValue::Text(driver_name.unwrap_or_default()),
+                        ReportValue::Number(days as f64),
+                        ReportValue::Number(round1(total)),
+                        ReportValue::Number(round1(total / days.max(1) as f64)),
+                        ReportValue::Number(round1(max)),
+                        ReportValue::Number(over as f64),
+                    ]
+                })
+                .collect();
+            Ok(ReportTable {
+                title: kind.title().to_string(),
+                subtitle: period,
+                columns: [
+                    "運転者コード",
+                    "運転者",
+                    "稼働日数",
+                    "拘束時間計(h)",
+                    "1日平均(h)",
+                    "最長(h)",
+                    "13時間超の日数",
+                ]
+                .map(String::from)
+                .to_vec(),
+                rows,
+            })
+        }
+        ReportKind::VehicleUtilization => {
+            let days_in_period = (to - from).num_days() + 1;
+            let rows = vehicle_utilization(conn, from, to)
+                .await?
+                .into_iter()
+                .map(|v| {
+                    vec![
+                        ReportValue::Number(v.vehicle_cd as f64),
+                        ReportValue::Text(v.vehicle_name),
+                        ReportValue::Number(v.days_in_use as f64),
+                        ReportValue::Number(round1(v.days_in_use as f64 * 100.0 / days_in_period as f64)),
+                        v.distance_km.map(|d| ReportValue::Number(round1(d))).unwrap_or_default(),
+                        v.idle_ratio.map(|r| ReportValue::Number(round1(r * 100.0))).unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            Ok(ReportTable {
+                title: kind.title().to_string(),
+                subtitle: period,
+                columns: ["車両コード", "車両", "稼働日数", "稼働率(%)", "走行距離(km)", "停車割合(%)"]
+                    .map(String::from)
+                    .to_vec(),
+                rows,
+            })
+        }
+        ReportKind::MonthlyCompliance => monthly_compliance(conn, from, to, branch).await,
+    }
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_status() {
+        assert_eq!(parse_expiry("261031"), NaiveDate::from_ymd_opt(2026, 10, 31));
+        assert_eq!(parse_expiry(""), None);
+        assert_eq!(expiry_status(-1), "期限切れ");
+        assert_eq!(expiry_status(30), "30日以内");
+        assert_eq!(expiry_status(31), "90日以内");
+        assert_eq!(expiry_status(365), "有効");
+        let (start, end) = jst_range(
+            NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 10, 31).unwrap(),
+        );
+        assert_eq!(start, "2026-10-01T00:00:00+09:00");
+        assert_eq!(end, "2026-11-01T00:00:00+09:00");
+    }
+
+    #[test]
+    fn test_grant_date() {
+        assert_eq!(grant_date("令和", " 8", "2", "13"), NaiveDate::from_ymd_opt(2026, 2, 13));
+        assert_eq!(grant_date("令　和", "０７", "１２", "０１"), NaiveDate::from_ymd_opt(2025, 12, 1));
+        assert_eq!(grant_date("平成", "31", "4", "30"), NaiveDate::from_ymd_opt(2019, 4, 30));
+        assert_eq!(grant_date("", "8", "2", "13"), None);
+        assert_eq!(grant_date("令和", "8", "2", ""), None);
+    }
+}
@@ -0,0 +1,531 @@
+// Reports
+//
+// 定型レポート（車検の期限状況・運転者別の拘束時間・車両稼働）を表にまとめ、PDF / XLSX にして files に保存する。
+// 作成は job（reports.generate）で行い、状態と作成したファイルは report_runs に残る。
+// 定期作成は種類ごとの scheduled_tasks（reports.scheduled.*）で、report_schedules の送信先にリンクをメールで送る。
+
+pub mod data;
+pub mod pdf;
+pub mod xlsx;
+
+use std::sync::Arc;
+
+use chrono::{Datelike, Duration, FixedOffset, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::{with_org_transaction, OrgScopedPool};
+use crate::jobs::{enqueue, Job, JobHandler, NewJob, ScheduledTaskDef};
+use crate::notifications::{Notification, Notifier, Recipient, REPORT_READY};
+use crate::storage::StorageBackend;
+
+pub use data::{
+    build_report, daily_vehicle_report, jst_range, vehicle_utilization, DailyVehicleRow,
+    VehicleUtilizationRow,
+};
+
+pub const REPORT_GENERATE_JOB: &str = "reports.generate";
+pub const SCHEDULED_INSPECTION_COMPLIANCE_JOB: &str = "reports.scheduled.inspection_compliance";
+pub const SCHEDULED_DRIVER_HOURS_JOB: &str = "reports.scheduled.driver_hours";
+pub const SCHEDULED_VEHICLE_UTILIZATION_JOB: &str = "reports.scheduled.vehicle_utilization";
+pub const SCHEDULED_MONTHLY_COMPLIANCE_JOB: &str = "reports.scheduled.monthly_compliance";
+
+pub const SCHEDULED_INSPECTION_COMPLIANCE_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: SCHEDULED_INSPECTION_COMPLIANCE_JOB,
+    description: "車検期限状況レポートを作成して送信",
+    default_cron: "0 7 1 * *",
+};
+
+pub const SCHEDULED_DRIVER_HOURS_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: SCHEDULED_DRIVER_HOURS_JOB,
+    description: "運転者別 拘束時間レポートを作成して送信",
+    default_cron: "0 7 1 * *",
+};
+
+pub const SCHEDULED_VEHICLE_UTILIZATION_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: SCHEDULED_VEHICLE_UTILIZATION_JOB,
+    description: "車両稼働状況レポートを作成して送信",
+    default_cron: "0 7 1 * *",
+};
+
+pub const SCHEDULED_MONTHLY_COMPLIANCE_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: SCHEDULED_MONTHLY_COMPLIANCE_JOB,
+    description: "車検 月次コンプライアンスレポートを作成して送信",
+    default_cron: "0 7 1 * *",
+};
+
+/// レポート作成の試行回数
+const REPORT_MAX_ATTEMPTS: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    InspectionCompliance,
+    DriverHours,
+    VehicleUtilization,
+    MonthlyCompliance,
+}
+
+impl ReportKind {
+    pub const ALL: [ReportKind; 4] = [
+        Self::InspectionCompliance,
+        Self::DriverHours,
+        Self::VehicleUtilization,
+        Self::MonthlyCompliance,
+    ];
+
+    /// report_runs.report_kind の値
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InspectionCompliance => "inspection_compliance",
+            Self::DriverHours => "driver_hours",
+            Self::VehicleUtilization => "vehicle_utilization",
+            Self::MonthlyCompliance => "monthly_compliance",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::InspectionCompliance => "車検期限状況",
+            Self::DriverHours => "運転者別 拘束時間",
+            Self::VehicleUtilization => "車両稼働状況",
+            Self::MonthlyCompliance => "車検 月次コンプライアンス",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::InspectionCompliance => "期間末時点の車検証の有効期限と残日数（期限切れ・30日以内・90日以内）",
+            Self::DriverHours => "運行ログの初回〜最終の間隔による運転者ごとの拘束時間（13時間超の日数付き）",
+            Self::VehicleUtilization => "運行ログによる車両ごとの稼働日数・走行距離・停車割合",
+            Self::MonthlyCompliance => "車両ごとの車検証の期限・書類（JSON / PDF）の不足・期間内の更新（部門ごとに作成可）",
+        }
+    }
+
+    /// 定期作成の job（scheduled_tasks のタスク名）
+    pub fn scheduled_job(&self) -> &'static str {
+        match self {
+            Self::InspectionCompliance => SCHEDULED_INSPECTION_COMPLIANCE_JOB,
+            Self::DriverHours => SCHEDULED_DRIVER_HOURS_JOB,
+            Self::VehicleUtilization => SCHEDULED_VEHICLE_UTILIZATION_JOB,
+            Self::MonthlyCompliance => SCHEDULED_MONTHLY_COMPLIANCE_JOB,
+        }
+    }
+
+    /// 部門（ichiban_cars.bumon_code_id）で絞り込めるか
+    pub fn supports_branch(&self) -> bool {
+        matches!(self, Self::MonthlyCompliance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Pdf,
+    Xlsx,
+}
+
+impl ReportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pdf" => Some(Self::Pdf),
+            "xlsx" => Some(Self::Xlsx),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Pdf => "application/pdf",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+}
+
+/// 定期作成の対象期間（実行日の前日・前週・前月）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportPeriod {
+    PreviousDay,
+    PreviousWeek,
+    PreviousMonth,
+}
+
+impl ReportPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreviousDay => "previous_day",
+            Self::PreviousWeek => "previous_week",
+            Self::PreviousMonth => "previous_month",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "previous_day" => Some(Self::PreviousDay),
+            "previous_week" => Some(Self::PreviousWeek),
+            "previous_month" => Some(Self::PreviousMonth),
+            _ => None,
+        }
+    }
+
+    /// today（JST）から見た期間 [from, to]（週は月曜始まり）
+    pub fn range(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Self::PreviousDay => {
+                let day = today - Duration::days(1);
+                (day, day)
+            }
+            Self::PreviousWeek => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                (monday - Duration::days(7), monday - Duration::days(1))
+            }
+            Self::PreviousMonth => {
+                let first = today.with_day(1).unwrap_or(today);
+                (first.checked_sub_months(Months::new(1)).unwrap_or(first), first - Duration::days(1))
+            }
+        }
+    }
+}
+
+/// 表のセル
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportValue {
+    Text(String),
+    Number(f64),
+}
+
+impl Default for ReportValue {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl ReportValue {
+    pub fn display(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Number(n) if n.fract() == 0.0 => format!("{}", *n as i64),
+            Self::Number(n) => format!("{:.1}", n),
+        }
+    }
+}
+
+/// レポートの中身（表題・期間・見出し・行）
+#[derive(Debug, Clone)]
+pub struct ReportTable {
+    pub title: String,
+    pub subtitle: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<ReportValue>>,
+}
+
+pub fn render(table: &ReportTable, format: ReportFormat) -> anyhow::Result<Vec<u8>> {
+    match format {
+        ReportFormat::Pdf => Ok(pdf::render_pdf(table)),
+        ReportFormat::Xlsx => Ok(xlsx::render_xlsx(table)?),
+    }
+}
+
+/// JST の今日
+pub fn today_jst() -> NaiveDate {
+    let jst = FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    Utc::now().with_timezone(&jst).date_naive()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportGeneratePayload {
+    pub run_id: String,
+}
+
+/// report_runs に登録して作成 job を登録する（organization 設定済みのトランザクションで呼ぶ）
+#[allow(clippy::too_many_arguments)]
+pub async fn create_run(
+    conn: &mut PgConnection,
+    organization_id: &str,
+    kind: ReportKind,
+    format: ReportFormat,
+    from: NaiveDate,
+    to: NaiveDate,
+    branch: Option<&str>,
+    requested_by: Option<&str>,
+    recipients: &[String],
+) -> Result<Uuid, sqlx::Error> {
+    let run_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO report_runs
+            (organization_id, report_kind, format, period_from, period_to, branch, requested_by, recipients)
+        VALUES ($1::uuid, $2, $3, $4, $5, $6, $7::uuid, $8)
+        RETURNING id
+        "#,
+    )
+    .bind(organization_id)
+    .bind(kind.as_str())
+    .bind(format.as_str())
+    .bind(from)
+    .bind(to)
+    .bind(branch)
+    .bind(requested_by)
+    .bind(recipients)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let job = NewJob::new(
+        REPORT_GENERATE_JOB,
+        ReportGeneratePayload {
+            run_id: run_id.to_string(),
+        },
+    )
+    .dedupe_key(run_id.to_string())
+    .max_attempts(REPORT_MAX_ATTEMPTS);
+    enqueue(&mut *conn, organization_id, job).await?;
+    Ok(run_id)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PendingRun {
+    report_kind: String,
+    format: String,
+    period_from: NaiveDate,
+    period_to: NaiveDate,
+    branch: Option<String>,
+    requested_by: Option<String>,
+    recipients: Vec<String>,
+}
+
+/// レポートを作成して files に保存し、送信先に通知する job ハンドラ
+pub struct ReportJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    notifier: Notifier,
+    app_base_url: Option<String>,
+}
+
+impl ReportJobHandler {
+    pub fn new(
+        pool: PgPool,
+        storage: Option<Arc<dyn StorageBackend>>,
+        notifier: Notifier,
+        app_base_url: Option<String>,
+    ) -> Self {
+        Self {
+            pool,
+            storage,
+            notifier,
+            app_base_url,
+        }
+    }
+
+    async fn generate(&self, organization_id: &str, run_id: Uuid) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+        let run: Option<PendingRun> = sqlx::query_as(
+            r#"
+            SELECT report_kind, format, period_from, period_to, branch, requested_by::text AS requested_by, recipients
+            FROM report_runs WHERE id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(run_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some(run) = run else {
+            return Ok(());
+        };
+        let kind = ReportKind::parse(&run.report_kind)
+            .ok_or_else(|| anyhow::anyhow!("Unknown report kind: {}", run.report_kind))?;
+        let format = ReportFormat::parse(&run.format)
+            .ok_or_else(|| anyhow::anyhow!("Unknown report format: {}", run.format))?;
+
+        let table = build_report(&mut conn, kind, run.period_from, run.period_to, run.branch.as_deref()).await?;
+        drop(conn);
+        let data = render(&table, format)?;
+        let file_name = format!(
+            "{}{}_{}_{}.{}",
+            kind.title().replace(' ', "_"),
+            run.branch.as_deref().map(|b| format!("_{}", b)).unwrap_or_default(),
+            run.period_from.format("%Y%m%d"),
+            run.period_to.format("%Y%m%d"),
+            format.as_str()
+        );
+
+        // files に登録（ストレージがなければ DB の blob）
+        let file_uuid = Uuid::new_v4();
+        let s3_key = match &self.storage {
+            Some(storage) => {
+                let key = format!("{}/reports/{}.{}", organization_id, file_uuid, format.as_str());
+                storage
+                    .upload(&key, &data, format.content_type())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Storage upload failed: {}", e.report()))?;
+                Some(key)
+            }
+            None => None,
+        };
+        let blob = s3_key
+            .is_none()
+            .then(|| base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &data));
+
+        let mut tx = self.pool.begin_scoped(organization_id).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO files
+                (uuid, organization_id, filename, type, created_at, blob, s3_key, storage_class, last_accessed_at, size_bytes)
+            VALUES ($1, $2::uuid, $3, $4, NOW(), $5, $6, 'STANDARD', NOW(), $7)
+            "#,
+        )
+        .bind(file_uuid)
+        .bind(organization_id)
+        .bind(&file_name)
+        .bind(format.content_type())
+        .bind(blob)
+        .bind(&s3_key)
+        .bind(data.len() as i64)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE report_runs
+            SET status = 'completed', file_uuid = $2, row_count = $3, error = NULL, completed_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(run_id)
+        .bind(file_uuid)
+        .bind(table.rows.len() as i32)
+        .execute(&mut *tx)
+        .await?;
+
+        let download_url = self
+            .app_base_url
+            .as_deref()
+            .map(|base| format!("{}/reports/{}", base.trim_end_matches('/'), run_id))
+            .unwrap_or_default();
+        let period = format!("{} 〜 {}", run.period_from.format("%Y-%m-%d"), run.period_to.format("%Y-%m-%d"));
+        let notification = Notification::new(REPORT_READY)
+            .var("report_name", kind.title())
+            .var("period", period)
+            .var("file_name", &file_name)
+            .var("row_count", table.rows.len())
+            .var("download_url", download_url)
+            .to_all(run.recipients.iter().map(Recipient::email))
+            .to_all(run.requested_by.iter().map(Recipient::in_app));
+        self.notifier.send(&mut tx, organization_id, &notification).await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "Report {} ({}) created for {}: {} rows, {} bytes",
+            kind.as_str(),
+            format.as_str(),
+            organization_id,
+            table.rows.len(),
+            data.len()
+        );
+        Ok(())
+    }
+
+    async fn mark_failed(&self, organization_id: &str, run_id: Uuid, error: &str) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+        sqlx::query("UPDATE report_runs SET status = 'failed', error = $2, completed_at = NOW() WHERE id = $1")
+            .bind(run_id)
+            .bind(error)
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for ReportJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: ReportGeneratePayload = job.payload()?;
+        let run_id: Uuid = payload.run_id.parse()?;
+        match self.generate(&job.organization_id, run_id).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if job.attempts >= job.max_attempts {
+                    self.mark_failed(&job.organization_id, run_id, &e.to_string()).await?;
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// 定期作成: report_schedules の設定（なければ前月分の XLSX を管理者へ）でレポートを登録する job ハンドラ
+pub struct ScheduledReportJobHandler {
+    pool: PgPool,
+    kind: ReportKind,
+}
+
+impl ScheduledReportJobHandler {
+    pub fn new(pool: PgPool, kind: ReportKind) -> Self {
+        Self { pool, kind }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for ScheduledReportJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let (from, to, run_id) = with_org_transaction(&self.pool, &job.organization_id, |mut tx| async move {
+            let schedule: Option<(String, String, Vec<String>)> =
+                sqlx::query_as("SELECT format, period, recipients FROM report_schedules WHERE report_kind = $1")
+                    .bind(self.kind.as_str())
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            let (format, period, mut recipients) = match schedule {
+                Some((format, period, recipients)) => (
+                    ReportFormat::parse(&format).unwrap_or(ReportFormat::Xlsx),
+                    ReportPeriod::parse(&period).unwrap_or(ReportPeriod::PreviousMonth),
+                    recipients,
+                ),
+                None => (ReportFormat::Xlsx, ReportPeriod::PreviousMonth, Vec::new()),
+            };
+            if recipients.is_empty() {
+                recipients = sqlx::query_scalar("SELECT email FROM org_admin_emails($1::uuid)")
+                    .bind(&job.organization_id)
+                    .fetch_all(&mut *tx)
+                    .await?;
+            }
+
+            let (from, to) = period.range(today_jst());
+            let run_id = create_run(&mut tx, &job.organization_id, self.kind, format, from, to, None, None, &recipients).await?;
+            Ok::<_, anyhow::Error>((tx, (from, to, run_id)))
+        })
+        .await?;
+
+        tracing::info!(
+            "Scheduled report {} queued for {} ({} 〜 {}, run {})",
+            self.kind.as_str(),
+            job.organization_id,
+            from,
+            to,
+            run_id
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_period_range() {
+        // 2026-10-16 は金曜
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+        assert_eq!(ReportPeriod::PreviousDay.range(today), (date(10, 15), date(10, 15)));
+        assert_eq!(ReportPeriod::PreviousWeek.range(today), (date(10, 5), date(10, 11)));
+        assert_eq!(ReportPeriod::PreviousMonth.range(today), (date(9, 1), date(9, 30)));
+        assert_eq!(ReportValue::Number(3.0).display(), "3");
+        assert_eq!(ReportValue::Number(2.46).display(), "2.5");
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt::Write as _;
+
+use super::{ReportTable, ReportValue};
+
+/// A4 横（pt）
+const PAGE_WIDTH: f64 = 842.0;
+const PAGE_HEIGHT: f64 = 595.0;
+const MARGIN: f64 = 36.0;
+const TITLE_SIZE: f64 = 14.0;
+const BODY_SIZE: f64 = 9.0;
+const LINE_HEIGHT: f64 = 14.0;
+const CELL_PADDING: f64 = 6.0;
+/// 1列の最大幅（全角の文字数）
+const MAX_COLUMN_EM: f64 = 24.0;
+
+/// 表示幅（全角 = 1、ASCII・半角カナ = 0.5）
+fn char_em(c: char) -> f64 {
+    if (' '..='~').contains(&c) || ('\u{FF61}'..='\u{FF9F}').contains(&c) {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+fn text_em(text: &str) -> f64 {
+    text.chars().map(char_em).sum()
+}
+
+/// 幅に収まらなければ末尾を「…」にする
+fn truncate_to_em(text: &str, max_em: f64) -> String {
+    if text_em(text) <= max_em {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut width = 0.0;
+    for c in text.chars() {
+        if width + char_em(c) > max_em - 1.0 {
+            break;
+        }
+        width += char_em(c);
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// UniJIS-UCS2-HW-H 用の16進文字列（BMP 外は〓）
+fn ucs2_hex(text: &str) -> String {
+    let mut hex = String::with_capacity(text.len() * 4 + 2);
+    hex.push('<');
+    for c in text.chars() {
+        let code = if (c as u32) <= 0xFFFF { c as u32 } else { 0x3013 };
+        let _ = write!(hex, "{:04X}", code);
+    }
+    hex.push('>');
+    hex
+}
+
+fn text_op(out: &mut String, x: f64, y: f64, size: f64, text: &str) {
+    let _ = writeln!(out, "BT /F1 {} Tf {:.1} {:.1} Td {} Tj ET", size, x, y, ucs2_hex(text));
+}
+
+fn line_op(out: &mut String, y: f64) {
+    let _ = writeln!(out, "0.5 w {:.1} {:.1} m {:.1} {:.1} l S", MARGIN, y, PAGE_WIDTH - MARGIN, y);
+}
+
+/// 列幅（pt）。収まらなければ全体を縮める
+fn column_widths(table: &ReportTable) -> Vec<f64> {
+    let ems: Vec<f64> = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            table
+                .rows
+                .iter()
+                .filter_map(|row| row.get(i))
+                .map(|v| text_em(&v.display()))
+                .fold(text_em(name), f64::max)
+                .min(MAX_COLUMN_EM)
+        })
+        .collect();
+    let widths: Vec<f64> = ems.iter().map(|em| em * BODY_SIZE + CELL_PADDING).collect();
+    let total: f64 = widths.iter().sum();
+    let available = PAGE_WIDTH - MARGIN * 2.0;
+    if total <= available {
+        widths
+    } else {
+        widths.iter().map(|w| w * available / total).collect()
+    }
+}
+
+/// 表を PDF にする
+///
+/// 日本語は非埋め込みの HeiseiKakuGo-W5（Adobe-Japan1、ビューア側のフォント）で描くので、
+/// フォントを同梱せずに済む。各ページに表題と見出し行、ページ番号を入れる。
+pub fn render_pdf(table: &ReportTable) -> Vec<u8> {
+    let widths = column_widths(table);
+    let header_top = PAGE_HEIGHT - MARGIN - TITLE_SIZE - LINE_HEIGHT * 2.0;
+    let rows_per_page = (((header_top - LINE_HEIGHT - MARGIN - LINE_HEIGHT) / LINE_HEIGHT) as usize).max(1);
+    let chunks: Vec<&[Vec<ReportValue>]> = if table.rows.is_empty() {
+        vec![&table.rows[..]]
+    } else {
+        table.rows.chunks(rows_per_page).collect()
+    };
+    let page_count = chunks.len();
+
+    let draw_row = |out: &mut String, y: f64, cells: &mut dyn Iterator<Item = String>| {
+        let mut x = MARGIN;
+        for (cell, width) in cells.zip(&widths) {
+            let max_em = ((width - CELL_PADDING) / BODY_SIZE).max(1.0);
+            text_op(out, x, y, BODY_SIZE, &truncate_to_em(&cell, max_em));
+            x += width;
+        }
+    };
+
+    let mut contents = Vec::with_capacity(page_count);
+    for (page, rows) in chunks.iter().enumerate() {
+        let mut out = String::new();
+        text_op(&mut out, MARGIN, PAGE_HEIGHT - MARGIN - TITLE_SIZE, TITLE_SIZE, &table.title);
+        text_op(
+            &mut out,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN - TITLE_SIZE - LINE_HEIGHT,
+            BODY_SIZE,
+            &table.subtitle,
+        );
+        draw_row(&mut out, header_top, &mut table.columns.iter().cloned());
+        line_op(&mut out, header_top - 4.0);
+        let mut y = header_top - LINE_HEIGHT;
+        for row in rows.iter() {
+            draw_row(&mut out, y, &mut row.iter().map(|v| v.display()));
+            y -= LINE_HEIGHT;
+        }
+        if table.rows.is_empty() {
+            text_op(&mut out, MARGIN, y, BODY_SIZE, "該当するデータはありません");
+        }
+        let footer = format!("{} / {}", page + 1, page_count);
+        text_op(&mut out, PAGE_WIDTH - MARGIN - text_em(&footer) * BODY_SIZE, MARGIN / 2.0, BODY_SIZE, &footer);
+        contents.push(out);
+    }
+
+    // 1: Catalog, 2: Pages, 3-5: フォント, 6 以降: ページと内容を交互に
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..page_count).map(|i| format!("{} 0 R", 6 + i * 2)).collect::<Vec<_>>().join(" "),
+            page_count
+        ),
+        "<< /Type /Font /Subtype /Type0 /BaseFont /HeiseiKakuGo-W5 /Encoding /UniJIS-UCS2-HW-H \
+         /DescendantFonts [4 0 R] >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /HeiseiKakuGo-W5 \
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 2 >> \
+         /FontDescriptor 5 0 R /DW 1000 /W [231 389 500 631 632 500] >>"
+            .to_string(),
+        "<< /Type /FontDescriptor /FontName /HeiseiKakuGo-W5 /Flags 4 /FontBBox [-92 -250 1010 922] \
+         /ItalicAngle 0 /Ascent 752 /Descent -221 /CapHeight 737 /StemV 116 >>"
+            .to_string(),
+    ];
+    for (i, content) in contents.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> \
+             /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            7 + i * 2
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = pdf.len();
+    let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = write!(pdf, "{:010} 00000 n \n", offset);
+    }
+    let _ = write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_to_em() {
+        assert_eq!(text_em("帯広100け201"), 6.0);
+        assert_eq!(truncate_to_em("帯広100け201", 8.0), "帯広100け201");
+        assert_eq!(truncate_to_em("帯広100け201", 4.0), "帯広10…");
+        assert_eq!(ucs2_hex("A車"), "<00418ECA>");
+    }
+
+    #[test]
+    fn test_render_pdf_paginates() {
+        let table = ReportTable {
+            title: "車両稼働状況".to_string(),
+            subtitle: "2026-10-01 〜 2026-10-31".to_string(),
+            columns: vec!["車両".to_string(), "稼働日数".to_string()],
+            rows: (0..80)
+                .map(|i| vec![ReportValue::Text(format!("{}号車", i)), ReportValue::Number(20.0)])
+                .collect(),
+        };
+        let pdf = String::from_utf8(render_pdf(&table)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/Count 3 >>"));
+        // xref の先頭オブジェクトの位置が正しい
+        let first = pdf.find("1 0 obj").unwrap();
+        assert!(pdf.contains(&format!("{:010} 00000 n", first)));
+    }
+}
@@ -0,0 +1,75 @@
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, XlsxError};
+
+use super::{ReportTable, ReportValue};
+
+/// 見出し行（表題・期間の下）
+const HEADER_ROW: u32 = 3;
+
+/// シート名に使えない文字を除いて 31 文字まで
+fn sheet_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(31)
+        .collect();
+    if name.is_empty() {
+        "Report".to_string()
+    } else {
+        name
+    }
+}
+
+/// 表を XLSX にする（数値はセルの数値として書く）
+pub fn render_xlsx(table: &ReportTable) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let title = Format::new().set_bold().set_font_size(14);
+    let header = Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xD9E1F2))
+        .set_border_bottom(FormatBorder::Thin);
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(sheet_name(&table.title))?;
+    sheet.write_string_with_format(0, 0, &table.title, &title)?;
+    sheet.write_string(1, 0, &table.subtitle)?;
+
+    for (col, name) in table.columns.iter().enumerate() {
+        let col = col as u16;
+        sheet.write_string_with_format(HEADER_ROW, col, name, &header)?;
+        // 全角は2文字分で概算
+        let width = table
+            .rows
+            .iter()
+            .filter_map(|row| row.get(col as usize))
+            .map(|v| v.display())
+            .chain(std::iter::once(name.clone()))
+            .map(|s| s.chars().map(|c| if c.is_ascii() { 1.0 } else { 2.0 }).sum::<f64>())
+            .fold(8.0, f64::max)
+            .min(50.0);
+        sheet.set_column_width(col, width + 2.0)?;
+    }
+    for (i, row) in table.rows.iter().enumerate() {
+        let row_num = HEADER_ROW + 1 + i as u32;
+        for (col, value) in row.iter().enumerate() {
+            match value {
+                ReportValue::Text(text) => sheet.write_string(row_num, col as u16, text)?,
+                ReportValue::Number(number) => sheet.write_number(row_num, col as u16, *number)?,
+            };
+        }
+    }
+    sheet.set_freeze_panes(HEADER_ROW + 1, 0)?;
+
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_name() {
+        assert_eq!(sheet_name("車検期限状況 2026/10"), "車検期限状況 202610");
+        assert_eq!(sheet_name("[]"), "Report");
+        assert_eq!(sheet_name(&"あ".repeat(40)).chars().count(), 31);
+    }
+}
@@ -1,12 +1,18 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Duration, Utc};
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
 use crate::config::Config;
-use crate::db::organization::set_current_organization;
+use crate::db::organization::OrgScopedPool;
+use crate::db::{with_org_transaction, AuditEvent};
+use crate::error::AppError;
 use crate::http_client::HttpClient;
+use crate::jobs::{Job, JobHandler, ScheduledTaskDef};
+use crate::shutdown;
 use crate::middleware::AuthenticatedUser;
+use crate::notifications::{format_jst, Notification, Notifier, ACCESS_REQUEST_REMINDER};
 use crate::proto::access_request::access_request_service_server::AccessRequestService;
 use crate::proto::access_request::{
     AccessRequest, ApproveAccessRequestReq, CreateAccessRequestReq, CreateAccessRequestRes,
@@ -14,6 +20,27 @@ use crate::proto::access_request::{
     ListAccessRequestsRes,
 };
 use crate::proto::common::Empty;
+use crate::services::validation;
+
+/// 承認時に付与できるロール
+const GRANTABLE_ROLES: &[&str] = &["admin", "member", "viewer"];
+
+/// 承認時のロール（空なら member）
+fn grant_role(role: &str) -> Result<&str, Status> {
+    match role.trim() {
+        "" => Ok("member"),
+        r if GRANTABLE_ROLES.contains(&r) => Ok(r),
+        r => Err(Status::invalid_argument(format!(
+            "Invalid role: {} (expected one of: {})",
+            r,
+            GRANTABLE_ROLES.join(", ")
+        ))),
+    }
+}
+
+/// 期限切れを反映した状態（定期実行で 'expired' にする前の行も期限切れとして扱う）
+const EFFECTIVE_STATUS: &str =
+    "CASE WHEN status = 'pending' AND expires_at <= NOW() THEN 'expired' ELSE status END";
 
 pub struct AccessRequestServiceImpl {
     pool: PgPool,
@@ -46,7 +73,7 @@ impl AccessRequestServiceImpl {
         .bind(org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match role {
             Some((r,)) if r == "admin" => Ok(()),
@@ -80,7 +107,7 @@ impl AccessRequestServiceImpl {
         let api_url = format!("{}/api/tasks", bot_url.trim_end_matches('/'));
         let http_client = self.http_client.clone();
 
-        tokio::spawn(async move {
+        shutdown::spawn(async move {
             match http_client.post_json(&api_url, &payload).await {
                 Ok(response) => {
                     if response.status().is_success() {
@@ -117,7 +144,7 @@ impl AccessRequestService for AccessRequestServiceImpl {
         .bind(&req.slug)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match row {
             Some((id, name, slug)) => Ok(Response::new(GetOrgBySlugRes {
@@ -153,7 +180,7 @@ impl AccessRequestService for AccessRequestServiceImpl {
         .bind(&req.org_slug)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (org_id, org_name) = match org {
             Some(o) => o,
@@ -168,7 +195,7 @@ impl AccessRequestService for AccessRequestServiceImpl {
         .bind(&org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         if is_member.is_some() {
             return Ok(Response::new(CreateAccessRequestRes {
@@ -178,17 +205,25 @@ impl AccessRequestService for AccessRequestServiceImpl {
             }));
         }
 
+        let mut tx = self.pool.begin_scoped(&org_id).await.map_err(AppError::from)?;
+
+        // 期限切れの承認待ちは締めてから新しく申請させる
+        expire_pending(&mut tx, &org_id, Some(&auth_user.user_id))
+            .await
+            .map_err(AppError::from)?;
+
         // Check if already pending
         let pending: Option<(String,)> = sqlx::query_as(
             "SELECT id::text FROM access_requests WHERE user_id = $1::uuid AND organization_id = $2::uuid AND status = 'pending'",
         )
         .bind(&auth_user.user_id)
         .bind(&org_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         if let Some((existing_id,)) = pending {
+            tx.commit().await.map_err(AppError::from)?;
             return Ok(Response::new(CreateAccessRequestRes {
                 id: existing_id,
                 status: "already_pending".to_string(),
@@ -201,9 +236,9 @@ impl AccessRequestService for AccessRequestServiceImpl {
             "SELECT email, COALESCE(display_name, ''), avatar_url FROM app_users WHERE id = $1::uuid",
         )
         .bind(&auth_user.user_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (email, display_name, avatar_url) = match user_info {
             Some(info) => info,
@@ -213,9 +248,10 @@ impl AccessRequestService for AccessRequestServiceImpl {
         let provider = &auth_user.provider;
 
         // INSERT (RLS is ENABLE not FORCE, INSERT policy is WITH CHECK(true))
+        let expires_at = Utc::now() + Duration::days(self.config.access_request_expiry_days);
         let row: (String,) = sqlx::query_as(
-            "INSERT INTO access_requests (organization_id, user_id, email, display_name, avatar_url, provider) \
-             VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6) RETURNING id::text",
+            "INSERT INTO access_requests (organization_id, user_id, email, display_name, avatar_url, provider, expires_at) \
+             VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6, $7) RETURNING id::text",
         )
         .bind(&org_id)
         .bind(&auth_user.user_id)
@@ -223,9 +259,11 @@ impl AccessRequestService for AccessRequestServiceImpl {
         .bind(&display_name)
         .bind(&avatar_url)
         .bind(provider)
-        .fetch_one(&self.pool)
+        .bind(expires_at)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
+        tx.commit().await.map_err(AppError::from)?;
 
         // Send LINE notification asynchronously
         self.send_line_notification(&org_name, &display_name, &email, provider)
@@ -249,39 +287,27 @@ impl AccessRequestService for AccessRequestServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
+            .map_err(AppError::from)?;
+
+        let rows: Vec<(String, String, String, String, Option<String>, String, String, Option<String>, Option<String>, Option<String>, String, String)> =
+            sqlx::query_as(&format!(
+                "SELECT id::text, user_id::text, email, display_name, avatar_url, \
+                 provider, {status} AS status, role, reviewed_by::text, reviewed_at::text, created_at::text, \
+                 expires_at::text \
+                 FROM access_requests WHERE ($1 = '' OR {status} = $1) ORDER BY created_at DESC",
+                status = EFFECTIVE_STATUS
+            ))
+            .bind(&req.status_filter)
+            .fetch_all(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
-
-        let rows: Vec<(String, String, String, String, Option<String>, String, String, Option<String>, Option<String>, Option<String>, String)> =
-            if req.status_filter.is_empty() {
-                sqlx::query_as(
-                    "SELECT id::text, user_id::text, email, display_name, avatar_url, \
-                     provider, status, role, reviewed_by::text, reviewed_at::text, created_at::text \
-                     FROM access_requests ORDER BY created_at DESC",
-                )
-                .fetch_all(&mut *conn)
-                .await
-                .map_err(|e| Status::internal(format!("Database error: {}", e)))?
-            } else {
-                sqlx::query_as(
-                    "SELECT id::text, user_id::text, email, display_name, avatar_url, \
-                     provider, status, role, reviewed_by::text, reviewed_at::text, created_at::text \
-                     FROM access_requests WHERE status = $1 ORDER BY created_at DESC",
-                )
-                .bind(&req.status_filter)
-                .fetch_all(&mut *conn)
-                .await
-                .map_err(|e| Status::internal(format!("Database error: {}", e)))?
-            };
+            .map_err(AppError::from)?;
 
         let requests: Vec<AccessRequest> = rows
             .into_iter()
             .map(
-                |(id, user_id, email, display_name, avatar_url, provider, status, role, reviewed_by, reviewed_at, created_at)| {
+                |(id, user_id, email, display_name, avatar_url, provider, status, role, reviewed_by, reviewed_at, created_at, expires_at)| {
                     AccessRequest {
                         id,
                         user_id,
@@ -294,6 +320,7 @@ impl AccessRequestService for AccessRequestServiceImpl {
                         reviewed_by: reviewed_by.unwrap_or_default(),
                         reviewed_at: reviewed_at.unwrap_or_default(),
                         created_at,
+                        expires_at,
                     }
                 },
             )
@@ -315,33 +342,25 @@ impl AccessRequestService for AccessRequestServiceImpl {
             return Err(Status::invalid_argument("request_id is required"));
         }
 
-        let role = if req.role.is_empty() {
-            "member"
-        } else {
-            &req.role
-        };
+        let role = grant_role(&req.role)?;
 
-        let mut conn = self
-            .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+        let mut tx = self.pool.begin_scoped(&auth_user.org_id).await.map_err(AppError::from)?;
 
-        // Fetch the pending request
-        let access_req: Option<(String, String)> = sqlx::query_as(
-            "SELECT user_id::text, organization_id::text FROM access_requests \
-             WHERE id = $1::uuid AND status = 'pending'",
+        // Fetch the pending request (期限切れは承認できない)
+        let access_req: Option<(String, String, bool)> = sqlx::query_as(
+            "SELECT user_id::text, organization_id::text, expires_at <= NOW() FROM access_requests \
+             WHERE id = $1::uuid AND status = 'pending' FOR UPDATE",
         )
         .bind(&req.request_id)
-        .fetch_optional(&mut *conn)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (target_user_id, target_org_id) = match access_req {
-            Some(r) => r,
+            Some((_, _, true)) => {
+                return Err(Status::failed_precondition("Access request has expired"))
+            }
+            Some((user_id, org_id, false)) => (user_id, org_id),
             None => return Err(Status::not_found("Pending access request not found")),
         };
 
@@ -354,9 +373,9 @@ impl AccessRequestService for AccessRequestServiceImpl {
         .bind(role)
         .bind(&auth_user.user_id)
         .bind(&req.request_id)
-        .execute(&mut *conn)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         // Add user to organization (ON CONFLICT in case of race)
         sqlx::query(
@@ -367,9 +386,17 @@ impl AccessRequestService for AccessRequestServiceImpl {
         .bind(&target_user_id)
         .bind(&target_org_id)
         .bind(role)
-        .execute(&mut *conn)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
+
+        AuditEvent::new("access_request.approved", "access_request", &req.request_id)
+            .actor(&auth_user.user_id)
+            .details(serde_json::json!({ "user_id": target_user_id, "role": role }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        tx.commit().await.map_err(AppError::from)?;
 
         Ok(Response::new(Empty {}))
     }
@@ -387,31 +414,158 @@ impl AccessRequestService for AccessRequestServiceImpl {
             return Err(Status::invalid_argument("request_id is required"));
         }
 
-        let mut conn = self
-            .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+        let reason = validation::text("reason", &req.reason, validation::TEXT_MAX_CHARS)?;
 
-        let rows_affected = sqlx::query(
+        let mut tx = self.pool.begin_scoped(&auth_user.org_id).await.map_err(AppError::from)?;
+
+        let declined: Option<(String,)> = sqlx::query_as(
             "UPDATE access_requests SET status = 'declined', \
              reviewed_by = $1::uuid, reviewed_at = NOW(), updated_at = NOW() \
-             WHERE id = $2::uuid AND status = 'pending'",
+             WHERE id = $2::uuid AND status = 'pending' AND expires_at > NOW() \
+             RETURNING user_id::text",
         )
         .bind(&auth_user.user_id)
         .bind(&req.request_id)
-        .execute(&mut *conn)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
-        .rows_affected();
+        .map_err(AppError::from)?;
 
-        if rows_affected == 0 {
-            return Err(Status::not_found("Pending access request not found"));
-        }
+        let Some((target_user_id,)) = declined else {
+            return Err(Status::not_found("Pending access request not found or expired"));
+        };
+
+        AuditEvent::new("access_request.declined", "access_request", &req.request_id)
+            .actor(&auth_user.user_id)
+            .details(serde_json::json!({
+                "user_id": target_user_id,
+                "reason": validation::non_empty(reason),
+            }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        tx.commit().await.map_err(AppError::from)?;
 
         Ok(Response::new(Empty {}))
     }
 }
+
+/// 期限を過ぎた承認待ちを 'expired' にして監査ログに残す（user_id 指定でその利用者の分だけ）
+///
+/// organization 設定済みのトランザクションで呼ぶ。期限切れにした件数を返す。
+async fn expire_pending(
+    conn: &mut sqlx::PgConnection,
+    org_id: &str,
+    user_id: Option<&str>,
+) -> Result<usize, sqlx::Error> {
+    let expired: Vec<(String, String)> = sqlx::query_as(
+        "UPDATE access_requests SET status = 'expired', updated_at = NOW() \
+         WHERE status = 'pending' AND expires_at <= NOW() AND ($1::uuid IS NULL OR user_id = $1::uuid) \
+         RETURNING id::text, user_id::text",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (id, user_id) in &expired {
+        AuditEvent::new("access_request.expired", "access_request", id)
+            .details(serde_json::json!({ "user_id": user_id }))
+            .record(conn, org_id)
+            .await?;
+    }
+    Ok(expired.len())
+}
+
+/// 参加リクエストの期限切れ処理と承認者へのリマインド（スケジュール実行）
+pub const ACCESS_REQUEST_MAINTENANCE_JOB: &str = "access_requests.expire_and_remind";
+
+pub const ACCESS_REQUEST_MAINTENANCE_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: ACCESS_REQUEST_MAINTENANCE_JOB,
+    description: "期限切れの参加リクエストを締め、承認待ちを管理者にリマインド",
+    default_cron: "0 9 * * *",
+};
+
+/// 期限切れを 'expired' にし、reminder_days 以上承認待ちのリクエストを管理者に通知する job ハンドラ
+///
+/// 同じリクエストのリマインドは reminder_days に 1 回まで。
+pub struct AccessRequestJobHandler {
+    pool: PgPool,
+    notifier: Notifier,
+    reminder_days: i64,
+}
+
+impl AccessRequestJobHandler {
+    pub fn new(pool: PgPool, notifier: Notifier, reminder_days: i64) -> Self {
+        Self { pool, notifier, reminder_days }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for AccessRequestJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let expired = with_org_transaction(&self.pool, &job.organization_id, |mut tx| async move {
+            let expired = expire_pending(&mut tx, &job.organization_id, None).await?;
+
+            let threshold = Utc::now() - Duration::days(self.reminder_days);
+            let pending: Vec<(String, String, String, DateTime<Utc>)> = sqlx::query_as(
+                "SELECT id::text, display_name, email, expires_at FROM access_requests \
+                 WHERE status = 'pending' AND created_at <= $1 AND (reminded_at IS NULL OR reminded_at <= $1) \
+                 ORDER BY expires_at ASC",
+            )
+            .bind(threshold)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            if !pending.is_empty() {
+                let org_name: String = sqlx::query_scalar("SELECT name FROM organizations WHERE id = $1::uuid")
+                    .bind(&job.organization_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                let requests = pending
+                    .iter()
+                    .map(|(_, name, email, expires_at)| format!("{} ({}) 期限: {}", name, email, format_jst(*expires_at)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                let admins = Notifier::admin_recipients(&mut tx, &job.organization_id).await?;
+                let admin_feed = Notifier::in_app_recipients(&mut tx, &job.organization_id, true).await?;
+                let notification = Notification::new(ACCESS_REQUEST_REMINDER)
+                    .var("organization_name", &org_name)
+                    .var("count", pending.len())
+                    .var("requests", &requests)
+                    .to_all(admins)
+                    .to_all(admin_feed);
+                self.notifier.send(&mut tx, &job.organization_id, &notification).await?;
+
+                let ids: Vec<String> = pending.into_iter().map(|(id, ..)| id).collect();
+                sqlx::query("UPDATE access_requests SET reminded_at = NOW() WHERE id = ANY($1::uuid[])")
+                    .bind(&ids)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            Ok::<_, anyhow::Error>((tx, expired))
+        })
+        .await?;
+
+        tracing::info!(
+            "Access request maintenance for {}: {} expired",
+            job.organization_id,
+            expired
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grant_role_defaults_to_member() {
+        assert_eq!(grant_role("").unwrap(), "member");
+        assert_eq!(grant_role(" admin ").unwrap(), "admin");
+        assert_eq!(
+            grant_role("owner").unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::cost::{estimate, CostUsage};
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::proto::admin::admin_service_server::AdminService;
+use crate::proto::admin::{
+    DailyApiUsage, DeleteUserDataRequest, DeleteUserDataResponse, GetCostBreakdownRequest,
+    GetCostBreakdownResponse, GetTenantApiUsageRequest, GetTenantApiUsageResponse,
+    ListTenantStatsRequest, ListTenantStatsResponse, StorageClassCost, TenantCost, TenantStats,
+    UserDataDeletionStep,
+};
+
+/// API 呼び出しの既定の集計期間（日）
+const DEFAULT_DAYS: i32 = 30;
+const MAX_DAYS: i32 = 366;
+
+#[derive(Debug, sqlx::FromRow)]
+struct TenantStatsRow {
+    organization_id: Uuid,
+    name: String,
+    slug: String,
+    created_at: DateTime<Utc>,
+    member_count: i64,
+    row_counts: serde_json::Value,
+    file_count: i64,
+    storage_bytes: i64,
+    files_without_size: i64,
+    storage_class_bytes: serde_json::Value,
+    api_requests: i64,
+    api_errors: i64,
+    api_server_errors: i64,
+    last_request_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CostUsageRow {
+    organization_id: Uuid,
+    name: String,
+    slug: String,
+    storage_class_bytes: serde_json::Value,
+    downloads: i64,
+    download_bytes: i64,
+    retrieval_class_bytes: serde_json::Value,
+    flickr_uploads: i64,
+    flickr_upload_bytes: i64,
+}
+
+/// jsonb の {"name": 数値} を map にする
+fn json_counts(value: &serde_json::Value) -> HashMap<String, i64> {
+    value
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .filter_map(|(k, v)| v.as_i64().map(|n| (k.clone(), n)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn error_rate(errors: i64, requests: i64) -> f64 {
+    if requests == 0 {
+        0.0
+    } else {
+        errors as f64 / requests as f64
+    }
+}
+
+/// 全組織の合計に対する割合
+fn share(cost: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        cost / total
+    } else {
+        0.0
+    }
+}
+
+fn parse_days(days: Option<i32>) -> Result<i32, Status> {
+    match days {
+        None => Ok(DEFAULT_DAYS),
+        Some(d) if (1..=MAX_DAYS).contains(&d) => Ok(d),
+        Some(_) => Err(Status::invalid_argument(format!("days must be between 1 and {}", MAX_DAYS))),
+    }
+}
+
+/// 運用者向けの組織横断の集計（app_users.is_superadmin のみ）
+///
+/// 集計は SECURITY DEFINER 関数（migration 00063）で RLS をまたいで行うので、
+/// 組織のコンテキストは設定しない。
+pub struct AdminServiceImpl {
+    pool: PgPool,
+}
+
+impl AdminServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 運用者なら実行者の user_id を返す
+    async fn verify_platform_admin<T>(&self, request: &Request<T>) -> Result<String, Status> {
+        let auth_user = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?;
+        let is_admin: bool = sqlx::query_scalar("SELECT is_platform_admin($1::uuid)")
+            .bind(&auth_user.user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        if !is_admin {
+            tracing::warn!("Platform admin RPC denied for user {}", auth_user.user_id);
+            return Err(Status::permission_denied("Platform admin required"));
+        }
+        Ok(auth_user.user_id.clone())
+    }
+}
+
+#[tonic::async_trait]
+impl AdminService for AdminServiceImpl {
+    async fn list_tenant_stats(
+        &self,
+        request: Request<ListTenantStatsRequest>,
+    ) -> Result<Response<ListTenantStatsResponse>, Status> {
+        self.verify_platform_admin(&request).await?;
+        let days = parse_days(request.get_ref().days)?;
+        let since = Utc::now() - Duration::days(days as i64);
+
+        let rows: Vec<TenantStatsRow> = sqlx::query_as("SELECT * FROM platform_tenant_stats($1)")
+            .bind(since)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        let tenants = rows
+            .into_iter()
+            .map(|r| TenantStats {
+                organization_id: r.organization_id.to_string(),
+                name: r.name,
+                slug: r.slug,
+                created_at: r.created_at.to_rfc3339(),
+                member_count: r.member_count,
+                row_counts: json_counts(&r.row_counts),
+                file_count: r.file_count,
+                storage_bytes: r.storage_bytes,
+                files_without_size: r.files_without_size,
+                storage_class_bytes: json_counts(&r.storage_class_bytes),
+                api_requests: r.api_requests,
+                api_errors: r.api_errors,
+                api_server_errors: r.api_server_errors,
+                error_rate: error_rate(r.api_errors, r.api_requests),
+                last_request_at: r.last_request_at.map(|t| t.to_rfc3339()),
+            })
+            .collect();
+
+        Ok(Response::new(ListTenantStatsResponse {
+            tenants,
+            days,
+            generated_at: Utc::now().to_rfc3339(),
+        }))
+    }
+
+    async fn get_tenant_api_usage(
+        &self,
+        request: Request<GetTenantApiUsageRequest>,
+    ) -> Result<Response<GetTenantApiUsageResponse>, Status> {
+        self.verify_platform_admin(&request).await?;
+        let req = request.into_inner();
+        let organization_id: Uuid = req
+            .organization_id
+            .parse()
+            .map_err(|_| Status::invalid_argument("Invalid organization_id"))?;
+        let days = parse_days(req.days)?;
+        let since = Utc::now() - Duration::days(days as i64);
+
+        let rows: Vec<(NaiveDate, i64, i64, i64)> =
+            sqlx::query_as("SELECT * FROM platform_api_usage_daily($1, $2)")
+                .bind(organization_id)
+                .bind(since)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(AppError::from)?;
+
+        Ok(Response::new(GetTenantApiUsageResponse {
+            organization_id: organization_id.to_string(),
+            days: rows
+                .into_iter()
+                .map(|(date, requests, errors, server_errors)| DailyApiUsage {
+                    date: date.to_string(),
+                    requests,
+                    errors,
+                    server_errors,
+                    error_rate: error_rate(errors, requests),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_cost_breakdown(
+        &self,
+        request: Request<GetCostBreakdownRequest>,
+    ) -> Result<Response<GetCostBreakdownResponse>, Status> {
+        self.verify_platform_admin(&request).await?;
+        let days = parse_days(request.get_ref().days)?;
+        let since = Utc::now() - Duration::days(days as i64);
+
+        let rows: Vec<CostUsageRow> = sqlx::query_as("SELECT * FROM platform_cost_usage($1)")
+            .bind(since)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        let mut tenants: Vec<TenantCost> = rows
+            .into_iter()
+            .map(|r| {
+                let usage = CostUsage {
+                    storage_class_bytes: json_counts(&r.storage_class_bytes),
+                    download_bytes: r.download_bytes,
+                    retrieval_class_bytes: json_counts(&r.retrieval_class_bytes),
+                    flickr_upload_bytes: r.flickr_upload_bytes,
+                };
+                let cost = estimate(&usage, days);
+                let mut storage: Vec<StorageClassCost> = usage
+                    .storage_class_bytes
+                    .iter()
+                    .map(|(class, bytes)| StorageClassCost {
+                        storage_class: class.clone(),
+                        bytes: *bytes,
+                        cost: cost.storage_class_cost.get(class).copied().unwrap_or(0.0),
+                    })
+                    .collect();
+                storage.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+                TenantCost {
+                    organization_id: r.organization_id.to_string(),
+                    name: r.name,
+                    slug: r.slug,
+                    storage,
+                    storage_cost: cost.storage_cost,
+                    downloads: r.downloads,
+                    download_bytes: r.download_bytes,
+                    egress_cost: cost.egress_cost,
+                    retrieval_cost: cost.retrieval_cost,
+                    flickr_uploads: r.flickr_uploads,
+                    flickr_upload_bytes: r.flickr_upload_bytes,
+                    flickr_egress_cost: cost.flickr_egress_cost,
+                    total_cost: cost.total_cost,
+                    share: 0.0,
+                }
+            })
+            .collect();
+
+        let total_cost: f64 = tenants.iter().map(|t| t.total_cost).sum();
+        for tenant in &mut tenants {
+            tenant.share = share(tenant.total_cost, total_cost);
+        }
+        tenants.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(Response::new(GetCostBreakdownResponse {
+            tenants,
+            days,
+            currency: "USD".to_string(),
+            total_cost,
+            generated_at: Utc::now().to_rfc3339(),
+        }))
+    }
+
+    async fn delete_user_data(
+        &self,
+        request: Request<DeleteUserDataRequest>,
+    ) -> Result<Response<DeleteUserDataResponse>, Status> {
+        let requested_by = self.verify_platform_admin(&request).await?;
+        let req = request.into_inner();
+        let user_id: Uuid = req
+            .user_id
+            .parse()
+            .map_err(|_| Status::invalid_argument("Invalid user_id"))?;
+
+        let sole_admin_of: Vec<Uuid> =
+            sqlx::query_scalar("SELECT * FROM user_sole_admin_organizations($1)")
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(AppError::from)?;
+        if !sole_admin_of.is_empty() {
+            let ids: Vec<String> = sole_admin_of.iter().map(Uuid::to_string).collect();
+            return Err(Status::failed_precondition(format!(
+                "User is the only admin of organizations with other members: {}",
+                ids.join(", ")
+            )));
+        }
+
+        // 全手順を 1 トランザクションで（dry run はロールバックして件数だけ返す）
+        let mut tx = self.pool.begin().await.map_err(AppError::from)?;
+        let rows: Vec<(i32, String, String, i64)> =
+            sqlx::query_as("SELECT * FROM delete_user_data($1, $2::uuid)")
+                .bind(user_id)
+                .bind(&requested_by)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        if rows.is_empty() {
+            return Err(Status::not_found("User not found"));
+        }
+        if req.dry_run {
+            tx.rollback().await.map_err(AppError::from)?;
+        } else {
+            tx.commit().await.map_err(AppError::from)?;
+            tracing::info!(
+                "User data deleted: user={}, requested_by={}",
+                user_id,
+                requested_by
+            );
+        }
+
+        Ok(Response::new(DeleteUserDataResponse {
+            user_id: user_id.to_string(),
+            dry_run: req.dry_run,
+            steps: rows
+                .into_iter()
+                .map(|(step, table, action, affected)| UserDataDeletionStep {
+                    step,
+                    table,
+                    action,
+                    affected,
+                })
+                .collect(),
+            completed_at: Utc::now().to_rfc3339(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_counts_and_days() {
+        let counts = json_counts(&serde_json::json!({"dtakologs": 120, "files": 3, "bad": "x"}));
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["dtakologs"], 120);
+        assert_eq!(error_rate(0, 0), 0.0);
+        assert_eq!(error_rate(1, 4), 0.25);
+        assert_eq!(share(1.0, 0.0), 0.0);
+        assert_eq!(share(1.0, 4.0), 0.25);
+        assert_eq!(parse_days(None).unwrap(), DEFAULT_DAYS);
+        assert!(parse_days(Some(0)).is_err());
+        assert!(parse_days(Some(367)).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::{DateTime, Utc};
+use prost_reflect::DescriptorPool;
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::db::organization::OrgScopedPool;
+use crate::db::AuditEvent;
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::proto::api_keys::api_keys_service_server::ApiKeysService;
+use crate::proto::api_keys::{
+    ApiKey, CreateApiKeyRequest, CreateApiKeyResponse, ListApiKeysRequest, ListApiKeysResponse,
+    RevokeApiKeyRequest,
+};
+use crate::proto::FILE_DESCRIPTOR_SET;
+use crate::services::validation;
+
+/// 発行するキーの接頭辞（ログやリポジトリに紛れたときに見分けやすくする）
+const KEY_PREFIX: &str = "logi_ak_";
+
+/// API キーに許可できないサービス（ログイン・メンバー・権限の管理。キーは発行者として動くため）
+const NON_DELEGABLE_SERVICES: &[&str] = &[
+    "logi.api_keys.ApiKeysService",
+    "logi.auth.AuthService",
+    "logi.member.MemberService",
+    "logi.organization.OrganizationService",
+    "logi.sso_settings.SsoSettingsService",
+    "logi.access_request.AccessRequestService",
+    "logi.admin.AdminService",
+];
+
+/// 1 つのキーに許可できるサービス数の上限
+const MAX_SERVICES: usize = 50;
+
+const API_KEY_COLUMNS: &str =
+    "id, name, key_hint, services, created_by, last_used_at, revoked_at, created_at";
+
+/// 新しい API キー（32 バイトの乱数）
+pub fn generate_api_key() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|e| format!("RNG error: {}", e))?;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}{}", KEY_PREFIX, hex))
+}
+
+/// キーは SHA-256 だけを保存する
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// api_keys の1行（ハッシュは返さない）
+#[derive(Debug, sqlx::FromRow)]
+struct ApiKeyRow {
+    id: Uuid,
+    name: String,
+    key_hint: String,
+    services: Vec<String>,
+    created_by: Uuid,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl ApiKeyRow {
+    fn to_proto(&self) -> ApiKey {
+        ApiKey {
+            id: self.id.to_string(),
+            name: self.name.clone(),
+            key_hint: self.key_hint.clone(),
+            services: self.services.clone(),
+            created_by: self.created_by.to_string(),
+            last_used_at: self.last_used_at.map(|t| t.to_rfc3339()),
+            revoked_at: self.revoked_at.map(|t| t.to_rfc3339()),
+            created_at: self.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 許可するサービス名を検証（重複は除く）
+fn validate_services(pool: &DescriptorPool, services: &[String]) -> Result<Vec<String>, Status> {
+    let mut result: Vec<String> = Vec::new();
+    for service in services {
+        let service = service.trim().trim_start_matches('/');
+        if service.is_empty() {
+            continue;
+        }
+        if pool.get_service_by_name(service).is_none() {
+            return Err(Status::invalid_argument(format!("Unknown service: {}", service)));
+        }
+        if NON_DELEGABLE_SERVICES.contains(&service) {
+            return Err(Status::invalid_argument(format!(
+                "Service cannot be called with an API key: {}",
+                service
+            )));
+        }
+        if !result.iter().any(|s| s == service) {
+            result.push(service.to_string());
+        }
+    }
+    if result.is_empty() {
+        return Err(Status::invalid_argument("At least one service is required"));
+    }
+    if result.len() > MAX_SERVICES {
+        return Err(Status::invalid_argument(format!(
+            "Too many services (max {})",
+            MAX_SERVICES
+        )));
+    }
+    Ok(result)
+}
+
+pub struct ApiKeysServiceImpl {
+    pool: PgPool,
+    descriptors: DescriptorPool,
+}
+
+impl ApiKeysServiceImpl {
+    pub fn new(pool: PgPool) -> Result<Self, prost_reflect::DescriptorError> {
+        Ok(Self {
+            pool,
+            descriptors: DescriptorPool::decode(FILE_DESCRIPTOR_SET)?,
+        })
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    /// 管理者確認（API キー経由は不可）+ organization 設定済みのトランザクション
+    async fn admin_tx<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(AuthenticatedUser, sqlx::Transaction<'static, sqlx::Postgres>), Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        if auth_user.provider == "api_key" {
+            return Err(Status::permission_denied("API keys cannot manage API keys"));
+        }
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok((auth_user, tx))
+    }
+}
+
+#[tonic::async_trait]
+impl ApiKeysService for ApiKeysServiceImpl {
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let req = request.into_inner();
+        let name = validation::line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        if name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+        let services = validate_services(&self.descriptors, &req.services)?;
+
+        let key = generate_api_key().map_err(Status::internal)?;
+        let hint: String = key.chars().skip(key.chars().count() - 4).collect();
+        let row: ApiKeyRow = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO api_keys (organization_id, name, key_hash, key_hint, services, created_by)
+            VALUES ($1::uuid, $2, $3, $4, $5, $6::uuid)
+            RETURNING {}
+            "#,
+            API_KEY_COLUMNS
+        ))
+        .bind(&auth_user.org_id)
+        .bind(&name)
+        .bind(hash_api_key(&key))
+        .bind(&hint)
+        .bind(&services)
+        .bind(&auth_user.user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        AuditEvent::new("api_key.created", "api_key", row.id.to_string())
+            .actor(&auth_user.user_id)
+            .details(serde_json::json!({ "name": row.name, "services": row.services }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        tracing::info!("API key {} created for {}", row.id, auth_user.org_id);
+        Ok(Response::new(CreateApiKeyResponse {
+            api_key: Some(row.to_proto()),
+            key,
+        }))
+    }
+
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ListApiKeysResponse>, Status> {
+        let (_, mut tx) = self.admin_tx(&request).await?;
+
+        let rows: Vec<ApiKeyRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM api_keys ORDER BY created_at",
+            API_KEY_COLUMNS
+        ))
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListApiKeysResponse {
+            api_keys: rows.iter().map(ApiKeyRow::to_proto).collect(),
+        }))
+    }
+
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<ApiKey>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let id = Uuid::parse_str(&request.into_inner().id)
+            .map_err(|_| Status::invalid_argument("Invalid API key id"))?;
+
+        // 失効済みならそのまま返す
+        let row: Option<ApiKeyRow> = sqlx::query_as(&format!(
+            r#"
+            UPDATE api_keys SET revoked_at = COALESCE(revoked_at, NOW())
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            API_KEY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        let row = row.ok_or_else(|| Status::not_found(format!("API key not found: {}", id)))?;
+
+        AuditEvent::new("api_key.revoked", "api_key", id.to_string())
+            .actor(&auth_user.user_id)
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        tracing::info!("API key {} revoked", id);
+        Ok(Response::new(row.to_proto()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_are_prefixed_and_hashed() {
+        let key = generate_api_key().unwrap();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_eq!(key.len(), KEY_PREFIX.len() + 64);
+        assert_ne!(key, generate_api_key().unwrap());
+        assert_eq!(hash_api_key(&key).len(), 64);
+    }
+
+    #[test]
+    fn validate_services_checks_descriptor_pool() {
+        let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let services = validate_services(
+            &pool,
+            &[
+                "logi.dtakologs.DtakologsService".to_string(),
+                " logi.dtakologs.DtakologsService ".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(services, vec!["logi.dtakologs.DtakologsService"]);
+
+        assert!(validate_services(&pool, &["logi.nope.NopeService".to_string()]).is_err());
+        assert!(validate_services(&pool, &[]).is_err());
+        assert!(validate_services(&pool, &["logi.auth.AuthService".to_string()]).is_err());
+    }
+}
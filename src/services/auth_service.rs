@@ -1,20 +1,35 @@
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
-use chrono::Utc;
+use std::sync::Arc;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use chrono::{DateTime, Utc};
 use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
+use crate::crypto::{SecretBox, OAUTH_ACCESS_TOKEN, SSO_CLIENT_SECRET, SSO_PREVIOUS_CLIENT_SECRET};
+use crate::db::{set_current_organization, OrgScopedPool};
+use crate::error::AppError;
 use crate::google_auth::GoogleTokenVerifier;
+use crate::http_client::HttpClient;
+use crate::notifications::{format_jst, Notification, Notifier, Recipient, PASSWORD_RESET};
 use crate::proto::auth::auth_service_server::AuthService;
 use crate::middleware::AuthenticatedUser;
 use crate::proto::auth::{
     AuthResponse, LoginRequest, LoginWithGoogleRequest, LoginWithSsoProviderRequest,
-    ResolveSsoProviderRequest, ResolveSsoProviderResponse, SignUpWithGoogleRequest,
-    SwitchOrganizationRequest, ValidateTokenRequest, ValidateTokenResponse,
+    RefreshTokenRequest, RequestPasswordResetRequest, ResetPasswordRequest,
+    ResolveSsoProviderRequest, ResolveSsoProviderResponse, RevokeTokenRequest,
+    SignUpWithGoogleRequest, SwitchOrganizationRequest, ValidateTokenRequest,
+    ValidateTokenResponse,
 };
-use crate::services::lineworks_auth;
+use crate::proto::common::Empty;
+use crate::services::password_policy::PasswordPolicy;
 use crate::services::sso_providers;
+use crate::services::validation;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -48,28 +63,88 @@ impl Claims {
     }
 }
 
+/// パスワード再設定トークンの有効期限
+const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
+
+/// リフレッシュトークンの有効期限（使うたびに新しいトークンへ置き換わる）
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+
+/// リフレッシュトークンは SHA-256 だけを保存する
+fn hash_refresh_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn new_refresh_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// ログイン時に新しい系列のリフレッシュトークンを発行する
+pub(crate) async fn issue_refresh_token(
+    pool: &PgPool,
+    user_id: &str,
+    org_id: &str,
+    provider: &str,
+) -> Result<(String, DateTime<Utc>), Status> {
+    let token = new_refresh_token();
+    let expires_at = Utc::now() + chrono::Duration::days(REFRESH_TOKEN_TTL_DAYS);
+    // Query via SECURITY DEFINER function (refresh_tokens has RLS)
+    sqlx::query("SELECT create_refresh_token($1::uuid, $2::uuid, $3, $4, $5)")
+        .bind(user_id)
+        .bind(org_id)
+        .bind(provider)
+        .bind(hash_refresh_token(&token))
+        .bind(expires_at)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+    Ok((token, expires_at))
+}
+
 pub struct AuthServiceImpl {
     pool: PgPool,
     jwt_secret: String,
     google_verifier: Option<GoogleTokenVerifier>,
-    http_client: reqwest::Client,
+    http_client: Arc<HttpClient>,
+    secrets: Arc<SecretBox>,
+    notifier: Notifier,
+    app_base_url: Option<String>,
 }
 
 impl AuthServiceImpl {
-    pub fn new(pool: PgPool, jwt_secret: String, google_client_ids: Vec<String>) -> Self {
+    pub fn new(
+        pool: PgPool,
+        jwt_secret: String,
+        google_client_ids: Vec<String>,
+        http_client: Arc<HttpClient>,
+        secrets: Arc<SecretBox>,
+        notifier: Notifier,
+        app_base_url: Option<String>,
+    ) -> Self {
         let google_verifier = if google_client_ids.is_empty() {
             None
         } else {
-            Some(GoogleTokenVerifier::new(google_client_ids))
+            Some(GoogleTokenVerifier::new(google_client_ids, http_client.clone()))
         };
         Self {
             pool,
             jwt_secret,
             google_verifier,
-            http_client: reqwest::Client::new(),
+            http_client,
+            secrets,
+            notifier,
+            app_base_url,
         }
     }
 
+    /// 再設定トークンは SHA-256 だけを保存する
+    fn hash_reset_token(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
     fn issue_jwt(
         &self,
         user_id: &str,
@@ -101,6 +176,28 @@ impl AuthServiceImpl {
         Ok((token, exp))
     }
 
+    /// JWT とリフレッシュトークンを発行して AuthResponse を作る
+    async fn auth_response(
+        &self,
+        user_id: String,
+        org_id: String,
+        username: &str,
+        provider: &str,
+        org_slug: &str,
+    ) -> Result<AuthResponse, Status> {
+        let (token, exp) = self.issue_jwt(&user_id, &org_id, username, provider, org_slug)?;
+        let (refresh_token, refresh_exp) =
+            issue_refresh_token(&self.pool, &user_id, &org_id, provider).await?;
+        Ok(AuthResponse {
+            token,
+            expires_at: exp.to_rfc3339(),
+            user_id,
+            organization_id: org_id,
+            refresh_token,
+            refresh_token_expires_at: refresh_exp.to_rfc3339(),
+        })
+    }
+
     fn get_google_verifier(&self) -> Result<&GoogleTokenVerifier, Status> {
         self.google_verifier.as_ref().ok_or_else(|| {
             Status::unavailable("Google authentication not configured (GOOGLE_CLIENT_ID not set)")
@@ -130,24 +227,23 @@ impl AuthService for AuthServiceImpl {
         .bind(&google_claims.sub)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         if let Some((existing_user_id, org_id, email, org_slug)) = existing {
             // User already exists — treat as login
-            let (token, exp) = self.issue_jwt(&existing_user_id, &org_id, &email, "google", &org_slug)?;
-            return Ok(Response::new(AuthResponse {
-                token,
-                expires_at: exp.to_rfc3339(),
-                user_id: existing_user_id,
-                organization_id: org_id,
-            }));
+            let response = self
+                .auth_response(existing_user_id, org_id, &email, "google", &org_slug)
+                .await?;
+            return Ok(Response::new(response));
         }
 
         // Validate slug
-        if req.organization_slug.is_empty() {
+        let organization_slug = validation::code("organization_slug", &req.organization_slug, validation::CODE_MAX_CHARS)?;
+        if organization_slug.is_empty() {
             return Err(Status::invalid_argument("Organization slug is required"));
         }
-        if req.organization_name.is_empty() {
+        let organization_name = validation::line("organization_name", &req.organization_name, validation::NAME_MAX_CHARS)?;
+        if organization_name.is_empty() {
             return Err(Status::invalid_argument("Organization name is required"));
         }
 
@@ -159,27 +255,22 @@ impl AuthService for AuthServiceImpl {
         .bind(google_claims.name.as_deref().unwrap_or(&google_claims.email))
         .bind(google_claims.picture.as_deref())
         .bind(&google_claims.sub)
-        .bind(&req.organization_name)
-        .bind(&req.organization_slug)
+        .bind(&organization_name)
+        .bind(&organization_slug)
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| {
-            let msg = e.to_string();
-            if msg.contains("unique") || msg.contains("duplicate") {
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
                 Status::already_exists("Organization slug already taken")
-            } else {
-                Status::internal(format!("Failed to create user: {}", e))
             }
+            e => AppError::from(e).into(),
         })?;
 
-        let (token, exp) = self.issue_jwt(&user_id, &org_id, &google_claims.email, "google", &req.organization_slug)?;
+        let response = self
+            .auth_response(user_id, org_id, &google_claims.email, "google", &organization_slug)
+            .await?;
 
-        Ok(Response::new(AuthResponse {
-            token,
-            expires_at: exp.to_rfc3339(),
-            user_id,
-            organization_id: org_id,
-        }))
+        Ok(Response::new(response))
     }
 
     async fn login_with_google(
@@ -202,7 +293,7 @@ impl AuthService for AuthServiceImpl {
         .bind(&google_claims.sub)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (user_id, org_id, email, org_slug) = if let Some(row) = row {
             row
@@ -219,20 +310,17 @@ impl AuthService for AuthServiceImpl {
             .bind(default_org_id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| Status::internal(format!("Failed to create user: {}", e)))?;
+            .map_err(AppError::from)?;
 
             tracing::info!("Auto-registered Google user {} in default org", &google_claims.email);
             (new_user_id, default_org_id.to_string(), google_claims.email.clone(), default_org_slug)
         };
 
-        let (token, exp) = self.issue_jwt(&user_id, &org_id, &email, "google", &org_slug)?;
+        let response = self
+            .auth_response(user_id, org_id, &email, "google", &org_slug)
+            .await?;
 
-        Ok(Response::new(AuthResponse {
-            token,
-            expires_at: exp.to_rfc3339(),
-            user_id,
-            organization_id: org_id,
-        }))
+        Ok(Response::new(response))
     }
 
     async fn login(
@@ -255,7 +343,7 @@ impl AuthService for AuthServiceImpl {
         .bind(&req.username)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (app_user_id, password_hash, email, org_slug) =
             row.ok_or_else(|| Status::unauthenticated("Invalid credentials"))?;
@@ -267,15 +355,38 @@ impl AuthService for AuthServiceImpl {
             .verify_password(req.password.as_bytes(), &parsed_hash)
             .map_err(|_| Status::unauthenticated("Invalid credentials"))?;
 
+        // 組織のポリシーの有効期限を過ぎたパスワードではログインさせない（再設定してもらう）
+        let mut conn = self
+            .pool
+            .acquire_scoped(&req.organization_id)
+            .await
+            .map_err(AppError::from)?;
+        let policy = PasswordPolicy::load(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        if policy.max_age_days > 0 {
+            let changed_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+                "SELECT password_changed_at FROM password_credentials
+                 WHERE organization_id = $1::uuid AND username = $2",
+            )
+            .bind(&req.organization_id)
+            .bind(&req.username)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+            if changed_at.is_some_and(|changed_at| policy.is_expired(changed_at, Utc::now())) {
+                return Err(Status::failed_precondition(
+                    "Password has expired; reset your password to sign in",
+                ));
+            }
+        }
+
         let username = email.as_deref().unwrap_or(&req.username);
-        let (token, exp) = self.issue_jwt(&app_user_id, &req.organization_id, username, "password", &org_slug)?;
+        let response = self
+            .auth_response(app_user_id, req.organization_id, username, "password", &org_slug)
+            .await?;
 
-        Ok(Response::new(AuthResponse {
-            token,
-            expires_at: exp.to_rfc3339(),
-            user_id: app_user_id,
-            organization_id: req.organization_id,
-        }))
+        Ok(Response::new(response))
     }
 
     async fn validate_token(
@@ -331,7 +442,7 @@ impl AuthService for AuthServiceImpl {
         .bind(&req.external_org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match row {
             Some((client_id, org_name, woff_id)) => {
@@ -382,21 +493,22 @@ impl AuthService for AuthServiceImpl {
         })?;
 
         // 1. Look up SSO config — SECURITY DEFINER function to bypass RLS (pre-auth)
-        let config_row: Option<(String, String, String, String)> = sqlx::query_as(
+        let config_row: Option<(String, String, String, String, Option<String>)> = sqlx::query_as(
             "SELECT * FROM lookup_sso_config_for_login($1, $2)",
         )
         .bind(&req.provider)
         .bind(&req.external_org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
-        let (client_id, client_secret_encrypted, org_id, org_slug) = config_row.ok_or_else(|| {
-            Status::not_found(format!(
-                "SSO config not found for provider={}, external_org_id={}",
-                req.provider, req.external_org_id
-            ))
-        })?;
+        let (client_id, client_secret_encrypted, org_id, org_slug, previous_secret_encrypted) =
+            config_row.ok_or_else(|| {
+                Status::not_found(format!(
+                    "SSO config not found for provider={}, external_org_id={}",
+                    req.provider, req.external_org_id
+                ))
+            })?;
 
         // 2. Get access_token: either from WOFF directly or via code exchange
         let access_token = if use_access_token {
@@ -405,10 +517,8 @@ impl AuthService for AuthServiceImpl {
             req.access_token.clone()
         } else {
             // Standard OAuth flow: exchange code for access_token
-            let client_secret =
-                lineworks_auth::decrypt_secret(&client_secret_encrypted, &self.jwt_secret)
-                    .map_err(|e| Status::internal(format!("Failed to decrypt client secret: {}", e)))?;
-            sso_providers::exchange_code(
+            let client_secret = self.secrets.decrypt(&SSO_CLIENT_SECRET, &client_secret_encrypted)?;
+            let exchanged = sso_providers::exchange_code(
                 &self.http_client,
                 &provider,
                 &client_id,
@@ -416,8 +526,34 @@ impl AuthService for AuthServiceImpl {
                 &req.code,
                 &req.redirect_uri,
             )
-            .await
-            .map_err(|e| Status::unauthenticated(format!("SSO auth failed: {}", e)))?
+            .await;
+            match (exchanged, previous_secret_encrypted) {
+                (Ok(token), _) => token,
+                // ローテーションの猶予期間中はプロバイダー側がまだ古い secret のことがある
+                (Err(e), Some(previous_encrypted)) => {
+                    tracing::warn!(
+                        "SSO code exchange failed with current secret for provider={}, external_org_id={}; retrying with previous secret: {}",
+                        req.provider,
+                        req.external_org_id,
+                        e
+                    );
+                    let previous_secret =
+                        self.secrets.decrypt(&SSO_PREVIOUS_CLIENT_SECRET, &previous_encrypted)?;
+                    sso_providers::exchange_code(
+                        &self.http_client,
+                        &provider,
+                        &client_id,
+                        &previous_secret,
+                        &req.code,
+                        &req.redirect_uri,
+                    )
+                    .await
+                    .map_err(|e| Status::unauthenticated(format!("SSO auth failed: {}", e)))?
+                }
+                (Err(e), None) => {
+                    return Err(Status::unauthenticated(format!("SSO auth failed: {}", e)))
+                }
+            }
         };
 
         // 4. Fetch user profile (generic)
@@ -442,7 +578,7 @@ impl AuthService for AuthServiceImpl {
         .bind(&profile.provider_user_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (user_id, email) = if let Some((uid, email)) = existing {
             // Existing user — ensure they're still a member of this org (SECURITY DEFINER)
@@ -453,7 +589,7 @@ impl AuthService for AuthServiceImpl {
             .bind(&org_id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
             let username = email
                 .clone()
@@ -462,6 +598,7 @@ impl AuthService for AuthServiceImpl {
         } else {
             // 6. Auto-register new user via SECURITY DEFINER function
             let user_email = profile.email.as_deref();
+            let encrypted_access_token = self.secrets.encrypt(&OAUTH_ACCESS_TOKEN, &access_token)?;
 
             let (new_user_id, _org_slug): (String, String) = sqlx::query_as(
                 "SELECT * FROM auto_register_user($1, $2, NULL, $3, $4, $5, $6::uuid)",
@@ -470,11 +607,11 @@ impl AuthService for AuthServiceImpl {
             .bind(&profile.display_name)
             .bind(&req.provider)
             .bind(&profile.provider_user_id)
-            .bind(&access_token)
+            .bind(&encrypted_access_token)
             .bind(&org_id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| Status::internal(format!("Failed to create user: {}", e)))?;
+            .map_err(AppError::from)?;
 
             tracing::info!(
                 "Auto-registered SSO user {} ({}) via provider={} in org {}",
@@ -490,14 +627,11 @@ impl AuthService for AuthServiceImpl {
             (new_user_id, username)
         };
 
-        let (token, exp) = self.issue_jwt(&user_id, &org_id, &email, &req.provider, &org_slug)?;
+        let response = self
+            .auth_response(user_id, org_id, &email, &req.provider, &org_slug)
+            .await?;
 
-        Ok(Response::new(AuthResponse {
-            token,
-            expires_at: exp.to_rfc3339(),
-            user_id,
-            organization_id: org_id,
-        }))
+        Ok(Response::new(response))
     }
 
     async fn switch_organization(
@@ -524,25 +658,199 @@ impl AuthService for AuthServiceImpl {
         .bind(&req.organization_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (username, org_slug, _role) = row.ok_or_else(|| {
             Status::permission_denied("Not a member of the requested organization")
         })?;
 
-        let (token, exp) = self.issue_jwt(
-            &auth_user.user_id,
-            &req.organization_id,
-            &username,
-            &auth_user.provider,
-            &org_slug,
-        )?;
+        let response = self
+            .auth_response(
+                auth_user.user_id,
+                req.organization_id,
+                &username,
+                &auth_user.provider,
+                &org_slug,
+            )
+            .await?;
+
+        Ok(Response::new(response))
+    }
+
+    async fn request_password_reset(
+        &self,
+        request: Request<RequestPasswordResetRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = request.into_inner();
+        if req.organization_id.is_empty() || req.username.is_empty() {
+            return Err(Status::invalid_argument("organization_id and username are required"));
+        }
+
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let expires_at = Utc::now() + chrono::Duration::minutes(PASSWORD_RESET_TTL_MINUTES);
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(AppError::from)?;
+
+        // Query via SECURITY DEFINER function (app_users + password_credentials have RLS)
+        let email: Option<String> =
+            sqlx::query_scalar("SELECT email FROM create_password_reset($1::uuid, $2, $3, $4)")
+                .bind(&req.organization_id)
+                .bind(&req.username)
+                .bind(Self::hash_reset_token(&token))
+                .bind(expires_at)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+
+        // ユーザーの有無を明かさないため、該当なしでも成功を返す
+        let Some(email) = email else {
+            tracing::info!("Password reset requested for unknown user in {}", req.organization_id);
+            return Ok(Response::new(Empty {}));
+        };
+
+        set_current_organization(&mut tx, &req.organization_id)
+            .await
+            .map_err(AppError::from)?;
+        let reset_url = self
+            .app_base_url
+            .as_deref()
+            .map(|base| format!("{}/reset-password?token={}", base.trim_end_matches('/'), token))
+            .unwrap_or_default();
+        let notification = Notification::new(PASSWORD_RESET)
+            .var("username", &req.username)
+            .var("reset_url", reset_url)
+            .var("token", &token)
+            .var("expires_at", format_jst(expires_at))
+            .to(Recipient::email(email));
+        self.notifier
+            .send(&mut tx, &req.organization_id, &notification)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn reset_password(
+        &self,
+        request: Request<ResetPasswordRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = request.into_inner();
+        if req.token.is_empty() {
+            return Err(Status::invalid_argument("token is required"));
+        }
+        let token_hash = Self::hash_reset_token(&req.token);
+
+        // Query via SECURITY DEFINER function (password_reset_tokens has RLS)
+        let target: Option<(String, String)> =
+            sqlx::query_as("SELECT * FROM password_reset_target($1)")
+                .bind(&token_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(AppError::from)?;
+        let (credential_id, target_org_id) =
+            target.ok_or_else(|| Status::invalid_argument("Invalid or expired reset token"))?;
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&target_org_id)
+            .await
+            .map_err(AppError::from)?;
+        let policy = PasswordPolicy::load(&mut tx)
+            .await
+            .map_err(AppError::from)?;
+        policy.check(&req.new_password)?;
+        policy
+            .check_reuse(&mut tx, &credential_id, &req.new_password)
+            .await?;
+
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(req.new_password.as_bytes(), &salt)
+            .map_err(|e| Status::internal(format!("Failed to hash password: {}", e)))?
+            .to_string();
+
+        let organization_id: Option<String> =
+            sqlx::query_scalar("SELECT consume_password_reset($1, $2)")
+                .bind(&token_hash)
+                .bind(&password_hash)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        match organization_id {
+            Some(org) => {
+                tracing::info!("Password reset completed in {}", org);
+                Ok(Response::new(Empty {}))
+            }
+            None => Err(Status::invalid_argument("Invalid or expired reset token")),
+        }
+    }
+
+    async fn refresh_token(
+        &self,
+        request: Request<RefreshTokenRequest>,
+    ) -> Result<Response<AuthResponse>, Status> {
+        let req = request.into_inner();
+        if req.refresh_token.is_empty() {
+            return Err(Status::invalid_argument("refresh_token is required"));
+        }
+
+        let new_token = new_refresh_token();
+        let new_expires_at = Utc::now() + chrono::Duration::days(REFRESH_TOKEN_TTL_DAYS);
+
+        // Query via SECURITY DEFINER function (refresh_tokens has RLS)
+        // 使用済みトークンの再利用は系列ごと失効させる（関数内）
+        let row: Option<(String, String, String, String, String)> =
+            sqlx::query_as("SELECT * FROM rotate_refresh_token($1, $2, $3)")
+                .bind(hash_refresh_token(&req.refresh_token))
+                .bind(hash_refresh_token(&new_token))
+                .bind(new_expires_at)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(AppError::from)?;
+        let (user_id, org_id, provider, username, org_slug) = row
+            .ok_or_else(|| Status::unauthenticated("Invalid or expired refresh token"))?;
+
+        let (token, exp) = self.issue_jwt(&user_id, &org_id, &username, &provider, &org_slug)?;
 
         Ok(Response::new(AuthResponse {
             token,
             expires_at: exp.to_rfc3339(),
-            user_id: auth_user.user_id,
-            organization_id: req.organization_id,
+            user_id,
+            organization_id: org_id,
+            refresh_token: new_token,
+            refresh_token_expires_at: new_expires_at.to_rfc3339(),
         }))
     }
+
+    async fn revoke_token(
+        &self,
+        request: Request<RevokeTokenRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = request.into_inner();
+        if req.refresh_token.is_empty() {
+            return Err(Status::invalid_argument("refresh_token is required"));
+        }
+
+        // 未知・失効済みのトークンでも成功を返す（ログアウトは冪等）
+        let revoked: i64 = sqlx::query_scalar("SELECT revoke_refresh_token($1)")
+            .bind(hash_refresh_token(&req.refresh_token))
+            .fetch_one(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        tracing::info!("Refresh token revoked ({} tokens)", revoked);
+
+        Ok(Response::new(Empty {}))
+    }
 }
@@ -0,0 +1,101 @@
+// Batch RPC helpers (partial-failure semantics)
+//
+// BatchCreate*/BatchDelete* は各エントリを既存の unary ハンドラで処理し、
+// エントリごとの結果を google.rpc.Status で返す。1件の失敗で全体は失敗しない。
+
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Request, Status};
+
+use crate::middleware::AuthenticatedUser;
+use crate::proto::common::BatchDeleteResponse;
+
+/// 1リクエストあたりの最大エントリ数
+pub const MAX_BATCH_ENTRIES: usize = 100;
+
+/// 親リクエストの認証情報・メタデータを各エントリのリクエストに引き継ぐ
+pub struct BatchContext {
+    metadata: MetadataMap,
+    user: Option<AuthenticatedUser>,
+}
+
+impl BatchContext {
+    /// リクエストを分解し、エントリ数を検証する
+    pub fn from_request<T>(
+        request: Request<T>,
+        entries: impl Fn(&T) -> usize,
+    ) -> Result<(Self, T), Status> {
+        let (metadata, extensions, message) = request.into_parts();
+        let count = entries(&message);
+        if count > MAX_BATCH_ENTRIES {
+            return Err(Status::invalid_argument(format!(
+                "Too many entries: {} (max {})",
+                count, MAX_BATCH_ENTRIES
+            )));
+        }
+        let user = extensions.get::<AuthenticatedUser>().cloned();
+        Ok((Self { metadata, user }, message))
+    }
+
+    /// エントリ用の unary リクエストを組み立てる
+    pub fn request<U>(&self, message: U) -> Request<U> {
+        let mut request = Request::new(message);
+        *request.metadata_mut() = self.metadata.clone();
+        if let Some(user) = &self.user {
+            request.extensions_mut().insert(user.clone());
+        }
+        request
+    }
+}
+
+/// tonic::Status → google.rpc.Status
+pub fn rpc_status(status: &Status) -> tonic_types::Status {
+    tonic_types::Status {
+        code: status.code() as i32,
+        message: status.message().to_string(),
+        details: Vec::new(),
+    }
+}
+
+/// 成功エントリ用の google.rpc.Status
+pub fn ok_status() -> tonic_types::Status {
+    tonic_types::Status {
+        code: Code::Ok as i32,
+        message: String::new(),
+        details: Vec::new(),
+    }
+}
+
+/// 削除系の結果をまとめる
+pub fn delete_response<T>(results: Vec<Result<T, Status>>) -> BatchDeleteResponse {
+    BatchDeleteResponse {
+        results: results
+            .iter()
+            .map(|r| match r {
+                Ok(_) => ok_status(),
+                Err(status) => rpc_status(status),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_size_limit() {
+        let ok = Request::new(vec![0u8; MAX_BATCH_ENTRIES]);
+        assert!(BatchContext::from_request(ok, |v| v.len()).is_ok());
+
+        let too_many = Request::new(vec![0u8; MAX_BATCH_ENTRIES + 1]);
+        let err = BatchContext::from_request(too_many, |v| v.len()).err().unwrap();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_delete_response_keeps_order() {
+        let response = delete_response::<()>(vec![Ok(()), Err(Status::not_found("missing")), Ok(())]);
+        let codes: Vec<i32> = response.results.iter().map(|s| s.code).collect();
+        assert_eq!(codes, vec![0, Code::NotFound as i32, 0]);
+    }
+}
@@ -1,24 +1,98 @@
-use sqlx::PgPool;
+use std::sync::Arc;
+
+use sqlx::{PgConnection, PgPool};
 use tonic::{Request, Response, Status};
 
-use crate::db::organization::set_current_organization;
+use crate::db::organization::OrgScopedPool;
+use crate::error::AppError;
+use crate::http_client::HttpClient;
 use crate::middleware::AuthenticatedUser;
+use crate::notifications::{
+    builtin_template, lineworks_message, message_url, request_access_token, BUILTIN_TEMPLATES,
+    CHANNEL_ADDRESS_PREFIX,
+};
 use crate::proto::bot_config::bot_config_service_server::BotConfigService;
 use crate::proto::bot_config::{
-    BotConfigResponse, BotConfigWithSecretsResponse, DeleteBotConfigRequest,
-    DeleteBotConfigResponse, GetBotConfigRequest, ListBotConfigsRequest, ListBotConfigsResponse,
-    UpsertBotConfigRequest,
+    BotConfigResponse, BotConfigWithSecretsResponse, BotRoutingRule, DeleteBotConfigRequest,
+    DeleteBotConfigResponse, DeleteBotRoutingRuleRequest, DeleteBotRoutingRuleResponse,
+    GetBotConfigRequest, ListBotConfigsRequest, ListBotConfigsResponse,
+    ListBotRoutingRulesRequest, ListBotRoutingRulesResponse, SendBotTestMessageRequest,
+    SendBotTestMessageResponse, UpsertBotConfigRequest, UpsertBotRoutingRuleRequest,
 };
 use crate::services::lineworks_auth;
+use crate::services::validation;
+
+/// テスト送信の本文（未指定のとき）
+const DEFAULT_TEST_MESSAGE: &str = "【テスト送信】Bot の設定を確認するためのメッセージです。";
+/// テスト送信の本文の上限（LINE WORKS のテキストメッセージの上限）
+const TEST_MESSAGE_MAX_CHARS: usize = 2000;
+
+const ROUTING_RULE_SELECT: &str =
+    "SELECT r.id::text, r.event_type, r.bot_config_id::text, b.name, COALESCE(r.channel_id, ''),
+            r.created_at::text, r.updated_at::text
+     FROM bot_routing_rules r
+     JOIN bot_configs b ON b.id = r.bot_config_id";
+
+type RoutingRuleRow = (String, String, String, String, String, String, String);
+
+fn routing_rule_from_row(
+    (id, event_type, bot_config_id, bot_name, channel_id, created_at, updated_at): RoutingRuleRow,
+) -> BotRoutingRule {
+    BotRoutingRule {
+        id,
+        event_type,
+        bot_config_id,
+        bot_name,
+        channel_id,
+        created_at,
+        updated_at,
+    }
+}
+
+async fn fetch_routing_rule(
+    conn: &mut PgConnection,
+    event_type: &str,
+) -> Result<Option<RoutingRuleRow>, sqlx::Error> {
+    sqlx::query_as(
+        &format!("{} WHERE r.event_type = $1", ROUTING_RULE_SELECT),
+    )
+    .bind(event_type)
+    .fetch_optional(conn)
+    .await
+}
 
 pub struct BotConfigServiceImpl {
     pool: PgPool,
     jwt_secret: String,
+    http_client: Arc<HttpClient>,
 }
 
 impl BotConfigServiceImpl {
-    pub fn new(pool: PgPool, jwt_secret: String) -> Self {
-        Self { pool, jwt_secret }
+    pub fn new(pool: PgPool, jwt_secret: String, http_client: Arc<HttpClient>) -> Self {
+        Self {
+            pool,
+            jwt_secret,
+            http_client,
+        }
+    }
+
+    fn decrypt(&self, encrypted: &str) -> Result<String, Status> {
+        lineworks_auth::decrypt_secret(encrypted, &self.jwt_secret)
+            .map_err(|e| Status::internal(format!("Decrypt error: {}", e)))
+    }
+
+    /// LINE WORKS のアクセストークンを取得できる認証情報か確かめる
+    async fn verify_credentials(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        service_account: &str,
+        private_key: &str,
+    ) -> Result<String, Status> {
+        request_access_token(&self.http_client, client_id, client_secret, service_account, private_key)
+            .await
+            .map(|(token, _)| token)
+            .map_err(|e| Status::failed_precondition(format!("LINE WORKS credentials rejected: {:#}", e)))
     }
 
     fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
@@ -37,7 +111,7 @@ impl BotConfigServiceImpl {
         .bind(org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match role {
             Some((r,)) if r == "admin" => Ok(()),
@@ -59,12 +133,9 @@ impl BotConfigService for BotConfigServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let rows: Vec<(String, String, String, String, String, String, bool, String, String)> =
             sqlx::query_as(
@@ -77,7 +148,7 @@ impl BotConfigService for BotConfigServiceImpl {
             .bind(&auth_user.org_id)
             .fetch_all(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let configs = rows
             .into_iter()
@@ -115,12 +186,9 @@ impl BotConfigService for BotConfigServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let row: Option<(String, String, String, String, String, String, bool, String, String)> =
             sqlx::query_as(
@@ -133,7 +201,7 @@ impl BotConfigService for BotConfigServiceImpl {
             .bind(&auth_user.org_id)
             .fetch_optional(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         match row {
             Some((id, provider, name, client_id, service_account, bot_id, enabled, created_at, updated_at)) => {
@@ -163,7 +231,11 @@ impl BotConfigService for BotConfigServiceImpl {
         self.verify_admin(&auth_user.user_id, &auth_user.org_id)
             .await?;
 
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        req.name = validation::line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        req.client_id = validation::code("client_id", &req.client_id, validation::CODE_MAX_CHARS)?;
+        req.service_account = validation::code("service_account", &req.service_account, validation::EMAIL_MAX_CHARS)?;
+        req.bot_id = validation::code("bot_id", &req.bot_id, validation::CODE_MAX_CHARS)?;
 
         if req.name.is_empty() || req.client_id.is_empty() || req.bot_id.is_empty() || req.service_account.is_empty() {
             return Err(Status::invalid_argument(
@@ -173,22 +245,42 @@ impl BotConfigService for BotConfigServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
+
+        if req.id.is_empty() && (req.client_secret.is_empty() || req.private_key.is_empty()) {
+            return Err(Status::invalid_argument(
+                "client_secret and private_key are required for new bot config",
+            ));
+        }
+
+        // 有効にする LINE WORKS Bot はトークンを取得できることを確かめてから保存する
+        if req.enabled && (req.provider.is_empty() || req.provider == "lineworks") {
+            let (client_secret, private_key) = if !req.client_secret.is_empty() && !req.private_key.is_empty() {
+                (req.client_secret.clone(), req.private_key.clone())
+            } else {
+                let stored: Option<(String, String)> = sqlx::query_as(
+                    "SELECT client_secret_encrypted, private_key_encrypted
+                     FROM bot_configs
+                     WHERE id = $1::uuid AND organization_id = $2::uuid",
+                )
+                .bind(&req.id)
+                .bind(&auth_user.org_id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+                let (secret_enc, key_enc) = stored.ok_or_else(|| Status::not_found("Bot config not found"))?;
+                (self.decrypt(&secret_enc)?, self.decrypt(&key_enc)?)
+            };
+            self.verify_credentials(&req.client_id, &client_secret, &req.service_account, &private_key)
+                .await?;
+        }
 
         let config_id: String;
 
         if req.id.is_empty() {
             // Create new
-            if req.client_secret.is_empty() || req.private_key.is_empty() {
-                return Err(Status::invalid_argument(
-                    "client_secret and private_key are required for new bot config",
-                ));
-            }
 
             let encrypted_secret =
                 lineworks_auth::encrypt_secret(&req.client_secret, &self.jwt_secret)
@@ -215,12 +307,11 @@ impl BotConfigService for BotConfigServiceImpl {
             .bind(req.enabled)
             .fetch_one(&mut *conn)
             .await
-            .map_err(|e| {
-                if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.is_unique_violation() => {
                     Status::already_exists("This bot_id is already configured")
-                } else {
-                    Status::internal(format!("Insert error: {}", e))
                 }
+                e => AppError::from(e).into(),
             })?;
 
             config_id = row.0;
@@ -256,7 +347,7 @@ impl BotConfigService for BotConfigServiceImpl {
                 .bind(&auth_user.org_id)
                 .execute(&mut *conn)
                 .await
-                .map_err(|e| Status::internal(format!("Update error: {}", e)))?;
+                .map_err(AppError::from)?;
             } else {
                 // Update without changing secrets
                 sqlx::query(
@@ -275,7 +366,7 @@ impl BotConfigService for BotConfigServiceImpl {
                 .bind(&auth_user.org_id)
                 .execute(&mut *conn)
                 .await
-                .map_err(|e| Status::internal(format!("Update error: {}", e)))?;
+                .map_err(AppError::from)?;
             }
         }
 
@@ -292,7 +383,7 @@ impl BotConfigService for BotConfigServiceImpl {
         .bind(&auth_user.org_id)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(BotConfigResponse {
             id,
@@ -321,19 +412,16 @@ impl BotConfigService for BotConfigServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         sqlx::query("DELETE FROM bot_configs WHERE id = $1::uuid AND organization_id = $2::uuid")
             .bind(&req.id)
             .bind(&auth_user.org_id)
             .execute(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Delete error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         Ok(Response::new(DeleteBotConfigResponse {}))
     }
@@ -350,12 +438,9 @@ impl BotConfigService for BotConfigServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let row: Option<(String, String, String, String, String, String, String, String)> =
             sqlx::query_as(
@@ -368,7 +453,7 @@ impl BotConfigService for BotConfigServiceImpl {
             .bind(&auth_user.org_id)
             .fetch_optional(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         match row {
             Some((id, provider, name, client_id, secret_enc, service_account, key_enc, bot_id)) => {
@@ -393,4 +478,185 @@ impl BotConfigService for BotConfigServiceImpl {
             None => Err(Status::not_found("Bot config not found or disabled")),
         }
     }
+
+    async fn send_test_message(
+        &self,
+        request: Request<SendBotTestMessageRequest>,
+    ) -> Result<Response<SendBotTestMessageResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let req = request.into_inner();
+        let user_id = validation::code("user_id", &req.user_id, validation::CODE_MAX_CHARS)?;
+        let channel_id = validation::code("channel_id", &req.channel_id, validation::CODE_MAX_CHARS)?;
+        let text = validation::text("text", &req.text, TEST_MESSAGE_MAX_CHARS)?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        // 無効な Bot も有効にする前に試せるように送る
+        let row: Option<(String, String, String, String, String)> = sqlx::query_as(
+            "SELECT client_id, client_secret_encrypted, service_account, private_key_encrypted, bot_id
+             FROM bot_configs
+             WHERE id = $1::uuid AND organization_id = $2::uuid AND provider = 'lineworks'",
+        )
+        .bind(&req.id)
+        .bind(&auth_user.org_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let Some((client_id, secret_enc, service_account, key_enc, bot_id)) = row else {
+            return Err(Status::not_found("LINE WORKS bot config not found"));
+        };
+
+        let recipient = if !channel_id.is_empty() {
+            format!("{}{}", CHANNEL_ADDRESS_PREFIX, channel_id)
+        } else if !user_id.is_empty() {
+            user_id
+        } else {
+            let own: Option<String> = sqlx::query_scalar("SELECT user_lineworks_user_id($1::uuid)")
+                .bind(&auth_user.user_id)
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+            own.ok_or_else(|| {
+                Status::invalid_argument("user_id or channel_id is required (no LINE WORKS account linked)")
+            })?
+        };
+
+        let token = self
+            .verify_credentials(
+                &client_id,
+                &self.decrypt(&secret_enc)?,
+                &service_account,
+                &self.decrypt(&key_enc)?,
+            )
+            .await?;
+        let text = if text.is_empty() { DEFAULT_TEST_MESSAGE.to_string() } else { text };
+        let response = self
+            .http_client
+            .post_json_with_bearer(&message_url(&bot_id, &recipient), &token, &lineworks_message("", &text))
+            .await
+            .map_err(|e| Status::unavailable(format!("LINE WORKS request failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Status::failed_precondition(format!(
+                "LINE WORKS message failed: {} - {}",
+                status, body
+            )));
+        }
+
+        Ok(Response::new(SendBotTestMessageResponse { recipient }))
+    }
+
+    async fn list_routing_rules(
+        &self,
+        request: Request<ListBotRoutingRulesRequest>,
+    ) -> Result<Response<ListBotRoutingRulesResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows: Vec<RoutingRuleRow> = sqlx::query_as(
+            &format!("{} ORDER BY r.event_type", ROUTING_RULE_SELECT),
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListBotRoutingRulesResponse {
+            rules: rows.into_iter().map(routing_rule_from_row).collect(),
+            event_types: BUILTIN_TEMPLATES.iter().map(|t| t.key.to_string()).collect(),
+        }))
+    }
+
+    async fn upsert_routing_rule(
+        &self,
+        request: Request<UpsertBotRoutingRuleRequest>,
+    ) -> Result<Response<BotRoutingRule>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let req = request.into_inner();
+        if builtin_template(&req.event_type).is_none() {
+            return Err(Status::invalid_argument(format!("Unknown event_type: {}", req.event_type)));
+        }
+        let bot_config_id = uuid::Uuid::parse_str(&req.bot_config_id)
+            .map_err(|_| Status::invalid_argument("Invalid bot_config_id"))?;
+        let channel_id = validation::code("channel_id", &req.channel_id, validation::CODE_MAX_CHARS)?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let bot_exists: Option<(i32,)> = sqlx::query_as(
+            "SELECT 1 FROM bot_configs WHERE id = $1 AND provider = 'lineworks'",
+        )
+        .bind(bot_config_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        if bot_exists.is_none() {
+            return Err(Status::not_found(format!("LINE WORKS bot config not found: {}", bot_config_id)));
+        }
+
+        sqlx::query(
+            "INSERT INTO bot_routing_rules (organization_id, event_type, bot_config_id, channel_id)
+             VALUES ($1::uuid, $2, $3, $4)
+             ON CONFLICT (organization_id, event_type) DO UPDATE
+             SET bot_config_id = EXCLUDED.bot_config_id, channel_id = EXCLUDED.channel_id, updated_at = NOW()",
+        )
+        .bind(&auth_user.org_id)
+        .bind(&req.event_type)
+        .bind(bot_config_id)
+        .bind(validation::non_empty(channel_id))
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let row = fetch_routing_rule(&mut conn, &req.event_type)
+            .await
+            .map_err(AppError::from)?
+            .ok_or_else(|| Status::internal("Routing rule not found after upsert"))?;
+        Ok(Response::new(routing_rule_from_row(row)))
+    }
+
+    async fn delete_routing_rule(
+        &self,
+        request: Request<DeleteBotRoutingRuleRequest>,
+    ) -> Result<Response<DeleteBotRoutingRuleResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let req = request.into_inner();
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        sqlx::query("DELETE FROM bot_routing_rules WHERE event_type = $1")
+            .bind(&req.event_type)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(Response::new(DeleteBotRoutingRuleResponse {}))
+    }
 }
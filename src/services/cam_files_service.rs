@@ -1,13 +1,20 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use md5::{Md5, Digest as Md5Digest};
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
 use tonic::{Request, Response, Status};
 
 use crate::config::CamConfig;
-use crate::db::{get_organization_from_request, set_current_organization};
+use crate::crypto::SecretBox;
+use crate::db::{get_organization_from_request, AdvisoryLock, OrgScopedPool, Paginator};
+use crate::error::AppError;
+use crate::http_client::HttpClient;
+use crate::jobs::{enqueue, Job, JobHandler, NewJob, ScheduledTaskDef};
 use crate::models::{CamFileExeModel, CamFileExeStageModel, CamFileModel};
+use crate::outbox::{Outbox, OutboxEvent, CAM_FILES_SYNCED};
 use crate::proto::cam_files::cam_file_exe_stage_service_server::CamFileExeStageService;
 use crate::proto::cam_files::cam_files_service_server::CamFilesService;
 use crate::proto::cam_files::{
@@ -18,6 +25,8 @@ use crate::proto::cam_files::{
 use crate::proto::common::Empty;
 use crate::proto::flickr::FlickrPhoto;
 use crate::services::flickr_service::{FlickrConfig, FlickrServiceImpl, FlickrTokenRow};
+use crate::services::thumbnails::{self, ThumbnailSource};
+use crate::storage::StorageBackend;
 
 /// cam_files LEFT JOIN flickr_photo の結果行
 #[derive(FromRow)]
@@ -35,18 +44,29 @@ struct CamFileWithFlickrRow {
 
 pub struct CamFilesServiceImpl {
     pool: PgPool,
-    http_client: reqwest::Client,
+    http_client: Arc<HttpClient>,
     cam_config: Option<CamConfig>,
     flickr_config: Option<FlickrConfig>,
+    outbox: Outbox,
+    storage: Option<Arc<dyn StorageBackend>>,
 }
 
 impl CamFilesServiceImpl {
-    pub fn new(pool: PgPool, cam_config: Option<CamConfig>, flickr_config: Option<FlickrConfig>) -> Self {
+    pub fn new(
+        pool: PgPool,
+        http_client: Arc<HttpClient>,
+        cam_config: Option<CamConfig>,
+        flickr_config: Option<FlickrConfig>,
+        outbox: Outbox,
+        storage: Option<Arc<dyn StorageBackend>>,
+    ) -> Self {
         Self {
             pool,
-            http_client: reqwest::Client::new(),
+            http_client,
             cam_config,
             flickr_config,
+            outbox,
+            storage,
         }
     }
 
@@ -66,6 +86,8 @@ impl CamFilesServiceImpl {
             cam: row.cam.clone(),
             flickr_id: row.flickr_id.clone(),
             flickr_photo,
+            thumbnail_small_url: None,
+            thumbnail_medium_url: None,
         }
     }
 
@@ -137,12 +159,12 @@ impl CamFilesServiceImpl {
     }
 
     async fn authenticated_fetch(
-        client: &reqwest::Client,
+        http_client: &HttpClient,
         url: &str,
         cam_config: &CamConfig,
     ) -> Result<reqwest::Response, String> {
-        let response = Self::apply_cf_access_headers(client.get(url), cam_config)
-            .send().await
+        let request = Self::apply_cf_access_headers(http_client.client().get(url), cam_config);
+        let response = http_client.send(request).await
             .map_err(|e| format!("HTTP request failed for {}: {}", url, e))?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
@@ -158,9 +180,9 @@ impl CamFilesServiceImpl {
                     url,
                     www_auth,
                 );
-                return Self::apply_cf_access_headers(client.get(url), cam_config)
-                    .header("Authorization", auth_header)
-                    .send()
+                let request = Self::apply_cf_access_headers(http_client.client().get(url), cam_config)
+                    .header("Authorization", auth_header);
+                return http_client.send(request)
                     .await
                     .map_err(|e| format!("Authenticated request failed for {}: {}", url, e));
             }
@@ -245,39 +267,198 @@ impl CamFilesServiceImpl {
         files
     }
 
-    // ---- Flickr アップロード (バックグラウンド) ----
+    /// カメラSD同期 + Flickrアップロード（RPC とスケジュール実行で共通）
+    ///
+    /// 同じ組織の同期が他のインスタンス・リクエストで実行中なら Aborted を返す（二重アップロード防止）
+    pub async fn sync(&self, organization_id: &str) -> Result<SyncCamFilesResponse, Status> {
+        let lock = AdvisoryLock::try_acquire(&self.pool, format!("{}:{}", CAM_SYNC_JOB, organization_id))
+            .await
+            .map_err(AppError::from)?
+            .ok_or_else(|| Status::aborted("Cam sync is already running for this organization"))?;
+
+        let result = self.sync_locked(organization_id).await;
+        lock.release().await;
+        result
+    }
+
+    /// hono-logi createCam.ts 全体 (L59-500) の移植
+    async fn sync_locked(&self, organization_id: &str) -> Result<SyncCamFilesResponse, Status> {
+        let cam_config = self.cam_config.as_ref().ok_or_else(|| {
+            Status::failed_precondition(
+                "Camera is not configured. Set CAM_DIGEST_USER, CAM_DIGEST_PASS, \
+                 CAM_MACHINE_NAME, CAM_SDCARD_CGI, CAM_MP4_CGI, CAM_JPG_CGI."
+            )
+        })?;
+
+        tracing::info!("SyncCamFiles called for organization: {}", organization_id);
+
+        let mut conn = self.pool.acquire_scoped(organization_id).await
+            .map_err(AppError::from)?;
+
+        // 1. 最終レコード取得 → 開始日決定
+        let last_record: Option<CamFileModel> = sqlx::query_as(
+            "SELECT name, date, hour, type, cam, flickr_id FROM cam_files ORDER BY name DESC LIMIT 1"
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let last_record = last_record.ok_or_else(|| {
+            Status::failed_precondition("No existing cam_files records found. Cannot determine start date.")
+        })?;
+
+        let start_date = last_record.date.clone();
+        let start_hour = last_record.hour.clone();
+        tracing::info!("SyncCamFiles: start_date={}, start_hour={}, last_name={}", start_date, start_hour, last_record.name);
+
+        // 2. カメラからdate一覧取得
+        let dir_path = "/Event";
+        let dates_url = format!("{}{}{}", cam_config.sdcard_cgi, cam_config.machine_name, dir_path);
+        let dates_response = Self::authenticated_fetch(&self.http_client, &dates_url, cam_config).await
+            .map_err(|e| Status::internal(format!("Failed to fetch dates: {}", e)))?;
+        let dates_xml = dates_response.text().await
+            .map_err(|e| Status::internal(format!("Failed to read dates response: {}", e)))?;
+
+        let all_dates = Self::parse_dir_names(&dates_xml);
+        let start_date_int: i64 = start_date.parse().unwrap_or(0);
+        let dates: Vec<&str> = all_dates.iter()
+            .filter(|d| d.parse::<i64>().unwrap_or(0) >= start_date_int)
+            .map(|s| s.as_str())
+            .collect();
+        let processed_dates = dates.len() as i32;
+        tracing::info!("Found {} dates (>= {})", processed_dates, start_date);
+
+        // 3. 各dateからhour一覧取得
+        let mut hours: Vec<(String, String)> = Vec::new();
+        for date in &dates {
+            let hours_url = format!("{}{}{}/{}", cam_config.sdcard_cgi, cam_config.machine_name, dir_path, date);
+            match Self::authenticated_fetch(&self.http_client, &hours_url, cam_config).await {
+                Ok(resp) => {
+                    let xml = resp.text().await.unwrap_or_default();
+                    let hour_dirs = Self::parse_dir_names(&xml);
+                    for hour in hour_dirs {
+                        if *date == start_date.as_str() {
+                            let hour_int: i64 = hour.parse().unwrap_or(0);
+                            let start_hour_int: i64 = start_hour.parse().unwrap_or(0);
+                            if hour_int >= start_hour_int {
+                                hours.push((date.to_string(), hour));
+                            }
+                        } else {
+                            hours.push((date.to_string(), hour));
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch hours for date {}: {}", date, e);
+                }
+            }
+        }
+        let processed_hours = hours.len() as i32;
+        tracing::info!("Found {} hours", processed_hours);
+
+        // 4. 各(date, hour)からファイル一覧取得 → UPSERT
+        let mut new_files_count = 0i32;
+        for (date, hour) in &hours {
+            let files_url = format!(
+                "{}{}{}/{}/{}",
+                cam_config.sdcard_cgi, cam_config.machine_name, dir_path, date, hour
+            );
+            match Self::authenticated_fetch(&self.http_client, &files_url, cam_config).await {
+                Ok(resp) => {
+                    let xml = resp.text().await.unwrap_or_default();
+                    let filenames = Self::parse_file_names(&xml);
+                    for filename in filenames {
+                        let file_type = if filename.contains(".mp4") { "mp4" } else { "jpg" };
+                        match sqlx::query(
+                            r#"
+                            INSERT INTO cam_files (name, organization_id, date, hour, type, cam)
+                            VALUES ($1, $2::uuid, $3, $4, $5, $6)
+                            ON CONFLICT (organization_id, name) DO UPDATE SET
+                                date = EXCLUDED.date, hour = EXCLUDED.hour,
+                                type = EXCLUDED.type, cam = EXCLUDED.cam
+                            "#,
+                        )
+                        .bind(&filename)
+                        .bind(organization_id)
+                        .bind(date)
+                        .bind(hour)
+                        .bind(file_type)
+                        .bind(&cam_config.machine_name)
+                        .execute(&mut *conn)
+                        .await {
+                            Ok(_) => new_files_count += 1,
+                            Err(e) => tracing::warn!("Failed to upsert cam_file {}: {}", filename, e),
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch files for {}/{}: {}", date, hour, e);
+                }
+            }
+        }
+        tracing::info!("Upserted {} files", new_files_count);
+
+        // 5. Flickr アップロード (job queue)
+        let flickr_upload_started = self.enqueue_flickr_uploads(
+            &mut conn,
+            &start_date,
+            organization_id,
+        ).await.unwrap_or(0);
+
+        // 6. 同期完了通知（新しいファイルがあったときのみ）
+        if new_files_count > 0 {
+            let event = OutboxEvent::new(
+                CAM_FILES_SYNCED,
+                serde_json::json!({
+                    "processed_dates": processed_dates,
+                    "processed_hours": processed_hours,
+                    "new_files": new_files_count,
+                }),
+            )
+            .message(format!("【カメラ同期】\n{}日分 {}件のファイルを取り込みました", processed_dates, new_files_count));
+            if let Err(e) = self.outbox.write(&mut conn, organization_id, &event).await {
+                tracing::warn!("Failed to write cam sync event to outbox: {}", e);
+            }
+        }
+
+        Ok(SyncCamFilesResponse {
+            processed_dates,
+            processed_hours,
+            new_files: new_files_count,
+            flickr_upload_started,
+            message: format!(
+                "Synced {} dates, {} hours, {} files. {} Flickr uploads started.",
+                processed_dates, processed_hours, new_files_count, flickr_upload_started
+            ),
+        })
+    }
 
-    /// flickr_id IS NULL のファイルをバックグラウンドで Flickr アップロード
+    // ---- Flickr アップロード (job queue) ----
+
+    /// flickr_id IS NULL のファイルを Flickr アップロード job として登録
     /// hono-logi createCam.ts L430-478 相当
-    async fn spawn_flickr_uploads(
+    async fn enqueue_flickr_uploads(
         &self,
         conn: &mut sqlx::pool::PoolConnection<sqlx::Postgres>,
         start_date: &str,
         organization_id: &str,
-        cam_config: &CamConfig,
     ) -> Result<i32, Status> {
-        let flickr_config = match self.flickr_config.as_ref() {
-            Some(c) => c.clone(),
-            None => {
-                tracing::info!("Flickr not configured, skipping uploads");
-                return Ok(0);
-            }
-        };
+        if self.flickr_config.is_none() {
+            tracing::info!("Flickr not configured, skipping uploads");
+            return Ok(0);
+        }
 
         let token: Option<FlickrTokenRow> = sqlx::query_as(
             "SELECT access_token, access_token_secret FROM flickr_tokens LIMIT 1"
         )
         .fetch_optional(&mut **conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to query flickr_tokens: {}", e)))?;
+        .map_err(AppError::from)?;
 
-        let token = match token {
-            Some(t) => t,
-            None => {
-                tracing::info!("No Flickr access token, skipping uploads");
-                return Ok(0);
-            }
-        };
+        if token.is_none() {
+            tracing::info!("No Flickr access token, skipping uploads");
+            return Ok(0);
+        }
 
         let unuploaded: Vec<CamFileModel> = sqlx::query_as(
             r#"
@@ -290,44 +471,147 @@ impl CamFilesServiceImpl {
         .bind(start_date)
         .fetch_all(&mut **conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to query unuploaded files: {}", e)))?;
+        .map_err(AppError::from)?;
+
+        let mut count = 0;
+        for file in &unuploaded {
+            let job = NewJob::new(FLICKR_UPLOAD_JOB, FlickrUploadPayload { name: file.name.clone() })
+                .dedupe_key(&file.name)
+                .backlog_limit(FLICKR_UPLOAD_BACKLOG_LIMIT);
+            if enqueue(&mut **conn, organization_id, job)
+                .await
+                .map_err(AppError::from)?
+                .is_some()
+            {
+                count += 1;
+            }
+        }
 
-        let count = unuploaded.len() as i32;
-        if count == 0 {
-            tracing::info!("No unuploaded files found");
-            return Ok(0);
+        tracing::info!("Enqueued {} Flickr uploads", count);
+        Ok(count)
+    }
+}
+
+/// cam_files 1件の Flickr アップロード job
+pub const FLICKR_UPLOAD_JOB: &str = "cam_files.flickr_upload";
+/// 組織ごとの実行待ちがこれを超えたら後続を遅らせる
+const FLICKR_UPLOAD_BACKLOG_LIMIT: i64 = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlickrUploadPayload {
+    pub name: String,
+}
+
+/// カメラからダウンロードして Flickr にアップロードする job ハンドラ
+pub struct FlickrUploadJobHandler {
+    pool: PgPool,
+    http_client: Arc<HttpClient>,
+    cam_config: Option<CamConfig>,
+    flickr_config: Option<FlickrConfig>,
+    secrets: Arc<SecretBox>,
+    /// サムネイルの保存先（未設定ならサムネイルを作らない）
+    storage: Option<Arc<dyn StorageBackend>>,
+}
+
+impl FlickrUploadJobHandler {
+    pub fn new(
+        pool: PgPool,
+        http_client: Arc<HttpClient>,
+        cam_config: Option<CamConfig>,
+        flickr_config: Option<FlickrConfig>,
+        secrets: Arc<SecretBox>,
+        storage: Option<Arc<dyn StorageBackend>>,
+    ) -> Self {
+        Self {
+            pool,
+            http_client,
+            cam_config,
+            flickr_config,
+            secrets,
+            storage,
         }
+    }
+}
 
-        tracing::info!("Starting {} Flickr uploads in background", count);
-
-        let pool = self.pool.clone();
-        let http_client = self.http_client.clone();
-        let cam_config = cam_config.clone();
-        let org_id = organization_id.to_string();
-
-        tokio::spawn(async move {
-            for file in unuploaded {
-                match upload_file_to_flickr(
-                    &pool,
-                    &http_client,
-                    &cam_config,
-                    &flickr_config,
-                    &token,
-                    &file,
-                    &org_id,
-                ).await {
-                    Ok(flickr_id) => {
-                        tracing::info!("Flickr upload success: {} -> {}", file.name, flickr_id);
-                    }
-                    Err(e) => {
-                        tracing::warn!("Flickr upload failed for {}: {}", file.name, e);
-                    }
-                }
-            }
-            tracing::info!("Background Flickr uploads completed");
-        });
+#[tonic::async_trait]
+impl JobHandler for FlickrUploadJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: FlickrUploadPayload = job.payload()?;
+        let (Some(cam_config), Some(flickr_config)) = (&self.cam_config, &self.flickr_config) else {
+            anyhow::bail!("CAM/Flickr is not configured");
+        };
 
-        Ok(count)
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        let token: FlickrTokenRow = sqlx::query_as(
+            "SELECT access_token, access_token_secret FROM flickr_tokens LIMIT 1"
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No Flickr access token"))?
+        .decrypt(&self.secrets)?;
+        let file: Option<CamFileModel> = sqlx::query_as(
+            "SELECT name, date, hour, type, cam, flickr_id FROM cam_files WHERE name = $1"
+        )
+        .bind(&payload.name)
+        .fetch_optional(&mut *conn)
+        .await?;
+        drop(conn);
+
+        // 削除済み・アップロード済みなら何もしない（再実行時の二重アップロード防止）
+        let Some(file) = file.filter(|f| f.flickr_id.is_none()) else {
+            return Ok(());
+        };
+
+        let flickr_id = upload_file_to_flickr(
+            &self.pool,
+            &self.http_client,
+            cam_config,
+            flickr_config,
+            &token,
+            &file,
+            &job.organization_id,
+            self.storage.as_deref(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+        tracing::info!("Flickr upload success: {} -> {}", file.name, flickr_id);
+        Ok(())
+    }
+}
+
+/// カメラSD同期（スケジュール実行）
+pub const CAM_SYNC_JOB: &str = "cam_files.sync";
+
+pub const CAM_SYNC_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: CAM_SYNC_JOB,
+    description: "カメラSD同期 + Flickrアップロード",
+    default_cron: "0 * * * *",
+};
+
+/// SyncCamFiles と同じ処理を job として実行するハンドラ
+pub struct CamSyncJobHandler {
+    service: CamFilesServiceImpl,
+}
+
+impl CamSyncJobHandler {
+    pub fn new(service: CamFilesServiceImpl) -> Self {
+        Self { service }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for CamSyncJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let result = match self.service.sync(&job.organization_id).await {
+            Ok(result) => result,
+            Err(status) if status.code() == tonic::Code::Aborted => {
+                tracing::info!("Skipped scheduled cam sync for {}: {}", job.organization_id, status.message());
+                return Ok(());
+            }
+            Err(status) => anyhow::bail!("{}", status.message()),
+        };
+        tracing::info!("Scheduled cam sync for {}: {}", job.organization_id, result.message);
+        Ok(())
     }
 }
 
@@ -335,12 +619,13 @@ impl CamFilesServiceImpl {
 /// hono-logi createCam.ts L446-474 相当
 async fn upload_file_to_flickr(
     pool: &PgPool,
-    http_client: &reqwest::Client,
+    http_client: &HttpClient,
     cam_config: &CamConfig,
     flickr_config: &FlickrConfig,
     token: &FlickrTokenRow,
     file: &CamFileModel,
     organization_id: &str,
+    storage: Option<&dyn StorageBackend>,
 ) -> Result<String, String> {
     let dir_path = "/Event";
     let base_url = if file.name.contains(".mp4") {
@@ -381,28 +666,35 @@ async fn upload_file_to_flickr(
         &data,
     ).await?;
 
-    // RLS用にset_current_organizationが必要
-    let mut conn = pool.acquire().await
+    // RLS 用に組織を設定したコネクション
+    let mut conn = pool.acquire_scoped(organization_id).await
         .map_err(|e| format!("Failed to acquire connection: {}", e))?;
-    set_current_organization(&mut conn, organization_id).await
-        .map_err(|e| format!("Failed to set organization: {}", e))?;
 
     sqlx::query(
-        "UPDATE cam_files SET flickr_id = $1 WHERE name = $2"
+        "UPDATE cam_files SET flickr_id = $1, flickr_uploaded_at = NOW(), flickr_upload_bytes = $3 WHERE name = $2"
     )
     .bind(&flickr_id)
     .bind(&file.name)
+    .bind(data.len() as i64)
     .execute(&mut *conn)
     .await
     .map_err(|e| format!("Failed to update flickr_id for {}: {}", file.name, e))?;
 
+    // ダウンロード済みのデータからサムネイルを作る（失敗しても Flickr アップロードは成功扱い）
+    if let Some(storage) = storage.filter(|_| thumbnails::is_thumbnail_name(&file.name)) {
+        let source = ThumbnailSource::CamFile(&file.name);
+        if let Err(e) = thumbnails::store_thumbnails(&mut conn, storage, organization_id, source, data.to_vec()).await {
+            tracing::warn!("Failed to store thumbnails for {}: {}", file.name, e.report());
+        }
+    }
+
     Ok(flickr_id)
 }
 
 /// OAuth 1.0a 署名付き multipart POST で Flickr にアップロード
 /// エンドポイント: https://up.flickr.com/services/upload/
 async fn upload_to_flickr(
-    http_client: &reqwest::Client,
+    http_client: &HttpClient,
     flickr_config: &FlickrConfig,
     access_token: &str,
     access_token_secret: &str,
@@ -447,10 +739,9 @@ async fn upload_to_flickr(
             .map_err(|e| format!("Failed to set MIME type: {}", e))?
         );
 
+    // multipart 本文は複製できないので再試行されない（二重アップロードしない）
     let response = http_client
-        .post(upload_url)
-        .multipart(form)
-        .send()
+        .send(http_client.client().post(upload_url).multipart(form))
         .await
         .map_err(|e| format!("Flickr upload request failed: {}", e))?;
 
@@ -503,59 +794,64 @@ impl CamFilesService for CamFilesServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
 
-        let base_select = r#"
+        // 日付・カメラ未指定かつページング未指定の場合は従来通り最新100件
+        let limit = match (&req.date, &req.cam, paginator.limit()) {
+            (None, None, None) => Some(100),
+            (_, _, limit) => limit,
+        };
+
+        // キーセット: date DESC, (hour, name) ASC
+        let files = sqlx::query_as::<_, CamFileWithFlickrRow>(
+            r#"
             SELECT cf.name, cf.date, cf.hour, cf.type, cf.cam, cf.flickr_id,
                    fp.secret as fp_secret, fp.server as fp_server
             FROM cam_files cf
             LEFT JOIN flickr_photo fp ON cf.flickr_id = fp.id AND cf.organization_id = fp.organization_id
-        "#;
-
-        let files = match (req.date, req.cam) {
-            (Some(date), Some(cam)) => {
-                sqlx::query_as::<_, CamFileWithFlickrRow>(
-                    &format!("{} WHERE cf.date = $1 AND cf.cam = $2 ORDER BY cf.hour", base_select),
-                )
-                .bind(&date)
-                .bind(&cam)
-                .fetch_all(&mut *conn)
-                .await
-            }
-            (Some(date), None) => {
-                sqlx::query_as::<_, CamFileWithFlickrRow>(
-                    &format!("{} WHERE cf.date = $1 ORDER BY cf.hour", base_select),
-                )
-                .bind(&date)
-                .fetch_all(&mut *conn)
-                .await
-            }
-            (None, Some(cam)) => {
-                sqlx::query_as::<_, CamFileWithFlickrRow>(
-                    &format!("{} WHERE cf.cam = $1 ORDER BY cf.date DESC, cf.hour", base_select),
-                )
-                .bind(&cam)
-                .fetch_all(&mut *conn)
-                .await
-            }
-            (None, None) => {
-                sqlx::query_as::<_, CamFileWithFlickrRow>(
-                    &format!("{} ORDER BY cf.date DESC, cf.hour LIMIT 100", base_select),
-                )
-                .fetch_all(&mut *conn)
-                .await
+            WHERE ($1::text IS NULL OR cf.date = $1)
+              AND ($2::text IS NULL OR cf.cam = $2)
+              AND ($3::text IS NULL
+                   OR cf.date < $3
+                   OR (cf.date = $3 AND (cf.hour, cf.name) > ($4, $5)))
+            ORDER BY cf.date DESC, cf.hour, cf.name
+            LIMIT $6
+            "#,
+        )
+        .bind(&req.date)
+        .bind(&req.cam)
+        .bind(paginator.cursor(0))
+        .bind(paginator.cursor(1))
+        .bind(paginator.cursor(2))
+        .bind(limit)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (files, pagination) =
+            paginator.finish(files, |f| vec![f.date.clone(), f.hour.clone(), f.name.clone()]);
+        let mut proto_files: Vec<CamFile> = files.iter().map(Self::row_to_proto).collect();
+        if let Some(storage) = &self.storage {
+            let names: Vec<String> = proto_files
+                .iter()
+                .filter(|f| thumbnails::is_thumbnail_name(&f.name))
+                .map(|f| f.name.clone())
+                .collect();
+            let mut urls = thumbnails::signed_urls(&mut conn, storage.as_ref(), "cam_file", &names).await?;
+            for file in &mut proto_files {
+                if let Some(urls) = urls.remove(&file.name) {
+                    file.thumbnail_small_url = urls.small;
+                    file.thumbnail_medium_url = urls.medium;
+                }
             }
         }
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
-
-        let proto_files: Vec<CamFile> = files.iter().map(Self::row_to_proto).collect();
 
         Ok(Response::new(ListCamFilesResponse {
             files: proto_files,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -565,16 +861,14 @@ impl CamFilesService for CamFilesServiceImpl {
     ) -> Result<Response<ListCamFileDatesResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let dates: Vec<(String,)> =
             sqlx::query_as("SELECT DISTINCT date FROM cam_files ORDER BY date DESC")
                 .fetch_all(&mut *conn)
                 .await
-                .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+                .map_err(AppError::from)?;
 
         Ok(Response::new(ListCamFileDatesResponse {
             dates: dates.into_iter().map(|(d,)| d).collect(),
@@ -591,10 +885,8 @@ impl CamFilesService for CamFilesServiceImpl {
             .exe
             .ok_or_else(|| Status::invalid_argument("exe is required"))?;
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let result = sqlx::query_as::<_, CamFileExeModel>(
             r#"
@@ -609,7 +901,7 @@ impl CamFilesService for CamFilesServiceImpl {
         .bind(exe.stage)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(CamFileExeResponse {
             exe: Some(CamFileExe {
@@ -621,148 +913,12 @@ impl CamFilesService for CamFilesServiceImpl {
     }
 
     /// カメラSD同期 + Flickrアップロード
-    /// hono-logi createCam.ts 全体 (L59-500) の移植
     async fn sync_cam_files(
         &self,
         request: Request<SyncCamFilesRequest>,
     ) -> Result<Response<SyncCamFilesResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
-
-        let cam_config = self.cam_config.as_ref().ok_or_else(|| {
-            Status::failed_precondition(
-                "Camera is not configured. Set CAM_DIGEST_USER, CAM_DIGEST_PASS, \
-                 CAM_MACHINE_NAME, CAM_SDCARD_CGI, CAM_MP4_CGI, CAM_JPG_CGI."
-            )
-        })?;
-
-        tracing::info!("SyncCamFiles called for organization: {}", organization_id);
-
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
-
-        // 1. 最終レコード取得 → 開始日決定
-        let last_record: Option<CamFileModel> = sqlx::query_as(
-            "SELECT name, date, hour, type, cam, flickr_id FROM cam_files ORDER BY name DESC LIMIT 1"
-        )
-        .fetch_optional(&mut *conn)
-        .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
-
-        let last_record = last_record.ok_or_else(|| {
-            Status::failed_precondition("No existing cam_files records found. Cannot determine start date.")
-        })?;
-
-        let start_date = last_record.date.clone();
-        let start_hour = last_record.hour.clone();
-        tracing::info!("SyncCamFiles: start_date={}, start_hour={}, last_name={}", start_date, start_hour, last_record.name);
-
-        // 2. カメラからdate一覧取得
-        let dir_path = "/Event";
-        let dates_url = format!("{}{}{}", cam_config.sdcard_cgi, cam_config.machine_name, dir_path);
-        let dates_response = Self::authenticated_fetch(&self.http_client, &dates_url, cam_config).await
-            .map_err(|e| Status::internal(format!("Failed to fetch dates: {}", e)))?;
-        let dates_xml = dates_response.text().await
-            .map_err(|e| Status::internal(format!("Failed to read dates response: {}", e)))?;
-
-        let all_dates = Self::parse_dir_names(&dates_xml);
-        let start_date_int: i64 = start_date.parse().unwrap_or(0);
-        let dates: Vec<&str> = all_dates.iter()
-            .filter(|d| d.parse::<i64>().unwrap_or(0) >= start_date_int)
-            .map(|s| s.as_str())
-            .collect();
-        let processed_dates = dates.len() as i32;
-        tracing::info!("Found {} dates (>= {})", processed_dates, start_date);
-
-        // 3. 各dateからhour一覧取得
-        let mut hours: Vec<(String, String)> = Vec::new();
-        for date in &dates {
-            let hours_url = format!("{}{}{}/{}", cam_config.sdcard_cgi, cam_config.machine_name, dir_path, date);
-            match Self::authenticated_fetch(&self.http_client, &hours_url, cam_config).await {
-                Ok(resp) => {
-                    let xml = resp.text().await.unwrap_or_default();
-                    let hour_dirs = Self::parse_dir_names(&xml);
-                    for hour in hour_dirs {
-                        if *date == start_date.as_str() {
-                            let hour_int: i64 = hour.parse().unwrap_or(0);
-                            let start_hour_int: i64 = start_hour.parse().unwrap_or(0);
-                            if hour_int >= start_hour_int {
-                                hours.push((date.to_string(), hour));
-                            }
-                        } else {
-                            hours.push((date.to_string(), hour));
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to fetch hours for date {}: {}", date, e);
-                }
-            }
-        }
-        let processed_hours = hours.len() as i32;
-        tracing::info!("Found {} hours", processed_hours);
-
-        // 4. 各(date, hour)からファイル一覧取得 → UPSERT
-        let mut new_files_count = 0i32;
-        for (date, hour) in &hours {
-            let files_url = format!(
-                "{}{}{}/{}/{}",
-                cam_config.sdcard_cgi, cam_config.machine_name, dir_path, date, hour
-            );
-            match Self::authenticated_fetch(&self.http_client, &files_url, cam_config).await {
-                Ok(resp) => {
-                    let xml = resp.text().await.unwrap_or_default();
-                    let filenames = Self::parse_file_names(&xml);
-                    for filename in filenames {
-                        let file_type = if filename.contains(".mp4") { "mp4" } else { "jpg" };
-                        match sqlx::query(
-                            r#"
-                            INSERT INTO cam_files (name, organization_id, date, hour, type, cam)
-                            VALUES ($1, $2::uuid, $3, $4, $5, $6)
-                            ON CONFLICT (organization_id, name) DO UPDATE SET
-                                date = EXCLUDED.date, hour = EXCLUDED.hour,
-                                type = EXCLUDED.type, cam = EXCLUDED.cam
-                            "#,
-                        )
-                        .bind(&filename)
-                        .bind(&organization_id)
-                        .bind(date)
-                        .bind(hour)
-                        .bind(file_type)
-                        .bind(&cam_config.machine_name)
-                        .execute(&mut *conn)
-                        .await {
-                            Ok(_) => new_files_count += 1,
-                            Err(e) => tracing::warn!("Failed to upsert cam_file {}: {}", filename, e),
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to fetch files for {}/{}: {}", date, hour, e);
-                }
-            }
-        }
-        tracing::info!("Upserted {} files", new_files_count);
-
-        // 5. Flickr アップロード (バックグラウンド)
-        let flickr_upload_started = self.spawn_flickr_uploads(
-            &mut conn,
-            &start_date,
-            &organization_id,
-            cam_config,
-        ).await.unwrap_or(0);
-
-        Ok(Response::new(SyncCamFilesResponse {
-            processed_dates,
-            processed_hours,
-            new_files: new_files_count,
-            flickr_upload_started,
-            message: format!(
-                "Synced {} dates, {} hours, {} files. {} Flickr uploads started.",
-                processed_dates, processed_hours, new_files_count, flickr_upload_started
-            ),
-        }))
+        self.sync(&organization_id).await.map(Response::new)
     }
 }
 
@@ -784,17 +940,15 @@ impl CamFileExeStageService for CamFileExeStageServiceImpl {
     ) -> Result<Response<ListStagesResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let stages = sqlx::query_as::<_, CamFileExeStageModel>(
             "SELECT * FROM cam_file_exe_stage ORDER BY stage",
         )
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let proto_stages: Vec<CamFileExeStage> = stages
             .iter()
@@ -819,10 +973,8 @@ impl CamFileExeStageService for CamFileExeStageServiceImpl {
             .stage
             .ok_or_else(|| Status::invalid_argument("stage is required"))?;
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let result = sqlx::query_as::<_, CamFileExeStageModel>(
             r#"
@@ -836,7 +988,7 @@ impl CamFileExeStageService for CamFileExeStageServiceImpl {
         .bind(&stage.name)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(StageResponse {
             stage: Some(CamFileExeStage {
@@ -1,26 +1,50 @@
 use std::collections::HashSet;
 use std::sync::Arc;
 
-use sqlx::PgPool;
+use chrono::{FixedOffset, NaiveDate};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
 use tonic::{Request, Response, Status};
 
-use crate::db::{get_organization_from_request, set_current_organization};
-use crate::http_client::HttpClient;
-use crate::models::{CarInspectionFileModel, CarInspectionModel, CarInspectionWithRelationsModel, HomeCarEntry};
+use crate::db::kpi_views::fresh_as_of;
+use crate::db::{get_organization_from_request, with_org_transaction, KpiView, OrderBy, OrgScopedPool, Paginator};
+use crate::dtako_api::DtakoApi;
+use crate::error::AppError;
+use crate::jobs::{Job, JobHandler, ScheduledTaskDef};
+use crate::notifications::{Notification, Notifier, EXPIRY_ALERT};
+use crate::outbox::{Outbox, OutboxEvent, CAR_INSPECTION_CREATED, CAR_INSPECTION_EXPIRING};
+use crate::reports::today_jst;
+use crate::models::{
+    CarInspectionFileModel, CarInspectionModel, CarInspectionWithRelationsModel,
+    CAR_INSPECTION_COLUMNS, CAR_INSPECTION_SORT_COLUMNS,
+};
 use crate::proto::car_inspection::car_inspection_files_service_server::CarInspectionFilesService;
 use crate::proto::car_inspection::car_inspection_service_server::CarInspectionService;
 use crate::proto::car_inspection::{
-    CarInspection, CarInspectionFile, CarInspectionFileResponse, CarInspectionResponse,
+    BatchCreateCarInspectionResult, BatchCreateCarInspectionsRequest,
+    BatchCreateCarInspectionsResponse, BatchDeleteCarInspectionsRequest, CarInspection,
+    CarInspectionEvent, CarInspectionFile, CarInspectionFileResponse, CarInspectionResponse,
     CarInspectionWithRelations, CarInsSheetIchibanCar, CreateCarInspectionFileRequest,
-    CreateCarInspectionRequest, DeleteCarInspectionRequest, DtakoCarsIchibanCar,
+    CreateCarInspectionRequest, DeleteCarInspectionRequest, DtakoCarsIchibanCar, ExpirySummary,
     GetCarInspectionRequest, ListCarInspectionFilesRequest, ListCarInspectionFilesResponse,
     ListCarInspectionsRequest, ListCarInspectionsResponse, ListRenewHomeTargetsRequest,
-    ListRenewHomeTargetsResponse,
+    ListRenewHomeTargetsResponse, ExportCarInspectionsRequest, ExportCarInspectionsResponse,
+    StreamCarInspectionsRequest, UploadCarInspectionFileRequest,
+    UploadCarInspectionFileResponse, WatchCarInspectionsRequest,
 };
-use crate::proto::common::Empty;
+use crate::proto::files::File;
+use crate::events::{watch_stream, EntityChange, EntityEvent, EventBus};
+use crate::proto::common::{BatchDeleteResponse, ChangeType, Empty};
+use crate::services::batch::{delete_response, ok_status, rpc_status, BatchContext};
+use crate::services::file_auto_parser::{CertJson, CertKey, FileAutoParser};
+use crate::services::home_car_cache::{HomeCarCache, HomeCarList};
+use crate::services::validation;
+use crate::storage::StorageBackend;
+use crate::text_encoding::{encode_text, TextEncoding};
 
 /// 全角英数字を半角に変換し、スペースを削除する
-fn to_half_width(s: &str) -> String {
+pub(crate) fn to_half_width(s: &str) -> String {
     s.chars()
         .filter_map(|c| match c {
             // スペース削除
@@ -34,15 +58,180 @@ fn to_half_width(s: &str) -> String {
         .collect()
 }
 
+/// 有効期限（YYMMDD）の区分ごとに数える（JST の today 基準）
+fn expiry_summary<'a>(expirdates: impl IntoIterator<Item = &'a str>, today: NaiveDate) -> ExpirySummary {
+    let mut summary = ExpirySummary::default();
+    for value in expirdates {
+        summary.total += 1;
+        let Ok(expiry) = NaiveDate::parse_from_str(&format!("20{}", value.trim()), "%Y%m%d") else {
+            summary.unknown += 1;
+            continue;
+        };
+        match (expiry - today).num_days() {
+            d if d < 0 => summary.expired += 1,
+            d if d <= 30 => summary.within_30_days += 1,
+            d if d <= 60 => summary.within_60_days += 1,
+            d if d <= 90 => summary.within_90_days += 1,
+            _ => summary.later += 1,
+        }
+    }
+    summary
+}
+
+/// 期限通知の区分（期限切れ / 30 日以内、それより先・読めない期限は None）
+fn expiry_notice_window(expirdate: &str, today: NaiveDate) -> Option<&'static str> {
+    let expiry = NaiveDate::parse_from_str(&format!("20{}", expirdate.trim()), "%Y%m%d").ok()?;
+    match (expiry - today).num_days() {
+        d if d < 0 => Some("expired"),
+        d if d <= 30 => Some("within_30_days"),
+        _ => None,
+    }
+}
+
+/// ExportCarInspections の既定の列（proto フィールド名, 見出し）
+const EXPORT_DEFAULT_COLUMNS: &[(&str, &str)] = &[
+    ("entry_no_car_no", "登録番号"),
+    ("car_no", "車台番号"),
+    ("car_name", "車名"),
+    ("model", "型式"),
+    ("car_kind", "自動車の種別"),
+    ("use", "用途"),
+    ("private_business", "自家用・事業用"),
+    ("car_shape", "車体の形状"),
+    ("cap", "乗車定員"),
+    ("maxloadage", "最大積載量"),
+    ("car_wgt", "車両重量"),
+    ("car_total_wgt", "車両総重量"),
+    ("ownername_low_level_char", "所有者の氏名又は名称"),
+    ("owner_address_char", "所有者の住所"),
+    ("username_low_level_char", "使用者の氏名又は名称"),
+    ("user_address_char", "使用者の住所"),
+    ("useheadqrter_char", "使用の本拠の位置"),
+    ("twodimension_code_info_valid_period_expirdate", "有効期間の満了する日"),
+];
+
+/// 1 回の出力行数の上限
+const MAX_EXPORT_ROWS: i64 = 100_000;
+
+/// 出力する (見出し, 列名)。見出しは既定の列にあればそれ、なければフィールド名
+fn export_columns(requested: &[String]) -> Result<Vec<(&'static str, &'static str)>, Status> {
+    let fields: Vec<&str> = if requested.iter().all(|f| f.trim().is_empty()) {
+        EXPORT_DEFAULT_COLUMNS.iter().map(|(field, _)| *field).collect()
+    } else {
+        requested.iter().map(|f| f.trim()).filter(|f| !f.is_empty()).collect()
+    };
+    let mut columns = Vec::with_capacity(fields.len());
+    for field in fields {
+        let &(name, column) = CAR_INSPECTION_COLUMNS
+            .text_columns
+            .iter()
+            .find(|(name, _)| *name == field)
+            .ok_or_else(|| Status::invalid_argument(format!("Unknown column: {}", field)))?;
+        let header = EXPORT_DEFAULT_COLUMNS
+            .iter()
+            .find(|(default, _)| *default == name)
+            .map(|(_, header)| *header)
+            .unwrap_or(name);
+        if !columns.iter().any(|(_, c)| *c == column) {
+            columns.push((header, column));
+        }
+    }
+    Ok(columns)
+}
+
+/// 有効期限の絞り込み（YYYY-MM-DD → 比較用の YYYYMMDD）
+fn parse_expiry_bound(field: &str, value: &str) -> Result<Option<String>, Status> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|d| Some(d.format("%Y%m%d").to_string()))
+        .map_err(|_| Status::invalid_argument(format!("{} must be YYYY-MM-DD", field)))
+}
+
+/// CSV にする（改行は Excel に合わせて CRLF）
+fn render_csv(headers: &[&str], rows: &[Vec<String>], encoding: TextEncoding) -> Result<Vec<u8>, String> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(Vec::new());
+    writer.write_record(headers).map_err(|e| e.to_string())?;
+    for row in rows {
+        writer.write_record(row).map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    let text = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+    Ok(encode_text(&text, encoding))
+}
+
 pub struct CarInspectionServiceImpl {
     pool: PgPool,
-    http_client: Arc<HttpClient>,
-    dtako_api_url: String,
+    storage: Option<Arc<dyn StorageBackend>>,
+    dtako_api: Arc<dyn DtakoApi>,
+    home_cars: HomeCarCache,
+    events: EventBus,
+    outbox: Outbox,
 }
 
 impl CarInspectionServiceImpl {
-    pub fn new(pool: PgPool, http_client: Arc<HttpClient>, dtako_api_url: String) -> Self {
-        Self { pool, http_client, dtako_api_url }
+    pub fn new(
+        pool: PgPool,
+        storage: Option<Arc<dyn StorageBackend>>,
+        dtako_api: Arc<dyn DtakoApi>,
+        home_cars: HomeCarCache,
+        events: EventBus,
+        outbox: Outbox,
+    ) -> Self {
+        Self { pool, storage, dtako_api, home_cars, events, outbox }
+    }
+
+    /// dtako API からホーム車両一覧を取得する（失敗時は期限内の前回の一覧で代替。refresh なら使い回さない）
+    async fn fetch_home_cars(&self, organization_id: &str, refresh: bool) -> Result<HomeCarList, Status> {
+        match self.dtako_api.home_cars(organization_id, refresh).await {
+            Ok(cars) => Ok(self.home_cars.store(cars)),
+            Err(e) => {
+                let error = format!("Failed to fetch home car list: {}", e);
+                match self.home_cars.fallback(error.clone()) {
+                    Some(list) => {
+                        tracing::warn!(
+                            "{}; using cached list fetched at {}",
+                            error,
+                            list.fetched_at.to_rfc3339()
+                        );
+                        Ok(list)
+                    }
+                    None => Err(Status::unavailable(error)),
+                }
+            }
+        }
+    }
+
+    /// 新規登録時に外部へ通知するイベント
+    fn created_event(model: &CarInspectionModel) -> OutboxEvent {
+        OutboxEvent::new(
+            CAR_INSPECTION_CREATED,
+            serde_json::json!({
+                "id": model.id,
+                "elect_cert_mg_no": model.elect_cert_mg_no,
+                "car_id": model.car_id,
+                "car_no": model.car_no,
+                "valid_period_expirdate": model.twodimension_code_info_valid_period_expirdate,
+            }),
+        )
+        .message(format!(
+            "【車検証登録】\n{} {}\n期限: {}",
+            model.car_no, model.car_name, model.twodimension_code_info_valid_period_expirdate
+        ))
+    }
+
+    /// WatchCarInspections の購読者へ変更を通知
+    fn publish(&self, organization_id: &str, change_type: ChangeType, car_inspection: CarInspection) {
+        self.events.publish(EntityEvent {
+            organization_id: organization_id.to_string(),
+            user_id: None,
+            change_type,
+            change: EntityChange::CarInspection(car_inspection),
+        });
     }
 
     pub fn model_to_proto(model: &CarInspectionModel) -> CarInspection {
@@ -183,10 +372,8 @@ impl CarInspectionService for CarInspectionServiceImpl {
             .car_inspection
             .ok_or_else(|| Status::invalid_argument("car_inspection is required"))?;
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut tx = self.pool.begin_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // Use ON CONFLICT DO UPDATE for upsert
         // Note: created_at and modified_at use DB defaults (NOW())
@@ -335,12 +522,25 @@ impl CarInspectionService for CarInspectionServiceImpl {
         .bind(&ci.twodimension_code_info_safe_std_date)
         .bind(&ci.twodimension_code_info_fuel_class_code)
         .bind(&ci.regist_car_light_car)
-        .fetch_one(&mut *conn)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
+
+        // 新規登録のみ通知（既存行の upsert は modified_at だけ更新される）
+        if result.created_at == result.modified_at {
+            self.outbox
+                .write(&mut tx, &organization_id, &Self::created_event(&result))
+                .await
+                .map_err(AppError::from)?;
+        }
+        tx.commit().await
+            .map_err(AppError::from)?;
+
+        let car_inspection = Self::model_to_proto(&result);
+        self.publish(&organization_id, ChangeType::Created, car_inspection.clone());
 
         Ok(Response::new(CarInspectionResponse {
-            car_inspection: Some(Self::model_to_proto(&result)),
+            car_inspection: Some(car_inspection),
         }))
     }
 
@@ -349,25 +549,83 @@ impl CarInspectionService for CarInspectionServiceImpl {
         request: Request<ListCarInspectionsRequest>,
     ) -> Result<Response<ListCarInspectionsResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
+        let paginator = Paginator::from_request(request.get_ref().pagination.as_ref())?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let select_list = CAR_INSPECTION_COLUMNS.select_list(request.get_ref().read_mask.as_ref())?;
+
+        if let Some(order) =
+            OrderBy::parse(&request.get_ref().order_by, &CAR_INSPECTION_SORT_COLUMNS)?
+        {
+            let mut qb = QueryBuilder::<Postgres>::new(format!(
+                "SELECT {} FROM car_inspection",
+                select_list
+            ));
+            order.push_cursor_join(&mut qb, &paginator)?;
+            qb.push(" WHERE TRUE");
+            order.push_page(&mut qb, "car_inspection", &paginator);
+            let inspections = qb
+                .build_query_as::<CarInspectionModel>()
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+
+            // カーソルは一意キー (ElectCertMgNo, GrantdateE, GrantdateY, GrantdateM, GrantdateD)
+            let (inspections, pagination) = paginator.finish(inspections, |ci| {
+                vec![
+                    ci.elect_cert_mg_no.clone(),
+                    ci.grantdate_e.clone(),
+                    ci.grantdate_y.clone(),
+                    ci.grantdate_m.clone(),
+                    ci.grantdate_d.clone(),
+                ]
+            });
+            return Ok(Response::new(ListCarInspectionsResponse {
+                car_inspections: inspections.iter().map(Self::model_to_proto).collect(),
+                pagination: Some(pagination),
+            }));
+        }
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
-
-        let inspections = sqlx::query_as::<_, CarInspectionModel>(
-            r#"SELECT * FROM car_inspection ORDER BY "GrantdateY" DESC, "GrantdateM" DESC, "GrantdateD" DESC"#,
-        )
+        // キーセット: (GrantdateY, GrantdateM, GrantdateD, ElectCertMgNo, GrantdateE) DESC
+        let inspections = sqlx::query_as::<_, CarInspectionModel>(&format!(
+            r#"
+            SELECT {} FROM car_inspection
+            WHERE ($1::text IS NULL
+                   OR ("GrantdateY", "GrantdateM", "GrantdateD", "ElectCertMgNo", "GrantdateE")
+                      < ($1, $2, $3, $4, $5))
+            ORDER BY "GrantdateY" DESC, "GrantdateM" DESC, "GrantdateD" DESC,
+                     "ElectCertMgNo" DESC, "GrantdateE" DESC
+            LIMIT $6
+            "#,
+            select_list
+        ))
+        .bind(paginator.cursor(0))
+        .bind(paginator.cursor(1))
+        .bind(paginator.cursor(2))
+        .bind(paginator.cursor(3))
+        .bind(paginator.cursor(4))
+        .bind(paginator.limit())
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
-
+        .map_err(AppError::from)?;
+
+        let (inspections, pagination) = paginator.finish(inspections, |ci| {
+            vec![
+                ci.grantdate_y.clone(),
+                ci.grantdate_m.clone(),
+                ci.grantdate_d.clone(),
+                ci.elect_cert_mg_no.clone(),
+                ci.grantdate_e.clone(),
+            ]
+        });
         let proto_inspections: Vec<CarInspection> =
             inspections.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListCarInspectionsResponse {
             car_inspections: proto_inspections,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -379,10 +637,8 @@ impl CarInspectionService for CarInspectionServiceImpl {
         let organization_id = get_organization_from_request(&request);
 
         // Acquire DB connection and set organization context
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // Get car inspections with latest record per CarId and file UUIDs
         let inspections = sqlx::query_as::<_, CarInspectionModel>(
@@ -417,14 +673,16 @@ impl CarInspectionService for CarInspectionServiceImpl {
         )
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (inspections, pagination) =
+            Paginator::default().finish(inspections, |ci| vec![ci.elect_cert_mg_no.clone()]);
         let proto_inspections: Vec<CarInspection> =
             inspections.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListCarInspectionsResponse {
             car_inspections: proto_inspections,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -435,21 +693,22 @@ impl CarInspectionService for CarInspectionServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let select_list = CAR_INSPECTION_COLUMNS.select_list(req.read_mask.as_ref())?;
 
-        let inspection = sqlx::query_as::<_, CarInspectionModel>(
+        let inspection = sqlx::query_as::<_, CarInspectionModel>(&format!(
             r#"
-            SELECT * FROM car_inspection
+            SELECT {} FROM car_inspection
             WHERE "ElectCertMgNo" = $1
               AND "GrantdateE" = $2
               AND "GrantdateY" = $3
               AND "GrantdateM" = $4
               AND "GrantdateD" = $5
             "#,
-        )
+            select_list
+        ))
         .bind(&req.elect_cert_mg_no)
         .bind(&req.grantdate_e)
         .bind(&req.grantdate_y)
@@ -457,7 +716,7 @@ impl CarInspectionService for CarInspectionServiceImpl {
         .bind(&req.grantdate_d)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+        .map_err(AppError::from)?
         .ok_or_else(|| Status::not_found("Car inspection not found"))?;
 
         Ok(Response::new(CarInspectionResponse {
@@ -472,10 +731,8 @@ impl CarInspectionService for CarInspectionServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         sqlx::query(
             r#"
@@ -494,21 +751,263 @@ impl CarInspectionService for CarInspectionServiceImpl {
         .bind(&req.grantdate_d)
         .execute(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
+
+        self.publish(
+            &organization_id,
+            ChangeType::Deleted,
+            CarInspection {
+                elect_cert_mg_no: req.elect_cert_mg_no,
+                grantdate_e: req.grantdate_e,
+                grantdate_y: req.grantdate_y,
+                grantdate_m: req.grantdate_m,
+                grantdate_d: req.grantdate_d,
+                ..Default::default()
+            },
+        );
 
         Ok(Response::new(Empty {}))
     }
 
+    type WatchCarInspectionsStream = ReceiverStream<Result<CarInspectionEvent, Status>>;
+
+    async fn watch_car_inspections(
+        &self,
+        request: Request<WatchCarInspectionsRequest>,
+    ) -> Result<Response<Self::WatchCarInspectionsStream>, Status> {
+        let organization_id = get_organization_from_request(&request);
+
+        Ok(Response::new(watch_stream(
+            &self.events,
+            organization_id,
+            None,
+            |event| match &event.change {
+                EntityChange::CarInspection(car_inspection) => Some(CarInspectionEvent {
+                    change_type: event.change_type as i32,
+                    car_inspection: Some(car_inspection.clone()),
+                }),
+                _ => None,
+            },
+        )))
+    }
+
+    type StreamCarInspectionsStream = ReceiverStream<Result<CarInspection, Status>>;
+
+    /// 車検証をストリーミング取得（結果全体をメモリに載せない）
+    async fn stream_car_inspections(
+        &self,
+        request: Request<StreamCarInspectionsRequest>,
+    ) -> Result<Response<Self::StreamCarInspectionsStream>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let select_list = CAR_INSPECTION_COLUMNS.select_list(request.get_ref().read_mask.as_ref())?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        // 受信側が遅い場合は送信を待つ（バックプレッシャー）
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+        tokio::spawn(async move {
+            let sql = format!(
+                r#"
+                SELECT {} FROM car_inspection
+                ORDER BY "GrantdateY" DESC, "GrantdateM" DESC, "GrantdateD" DESC,
+                         "ElectCertMgNo" DESC, "GrantdateE" DESC
+                "#,
+                select_list
+            );
+            let mut rows = sqlx::query_as::<_, CarInspectionModel>(&sql).fetch(&mut *conn);
+
+            while let Some(row) = rows.next().await {
+                let item = row
+                    .map(|m| Self::model_to_proto(&m))
+                    .map_err(|e| Status::from(AppError::from(e)));
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn batch_create_car_inspections(
+        &self,
+        request: Request<BatchCreateCarInspectionsRequest>,
+    ) -> Result<Response<BatchCreateCarInspectionsResponse>, Status> {
+        let (ctx, req) = BatchContext::from_request(request, |r| r.requests.len())?;
+
+        let mut results = Vec::with_capacity(req.requests.len());
+        for entry in req.requests {
+            let result = match self.create_car_inspection(ctx.request(entry)).await {
+                Ok(response) => BatchCreateCarInspectionResult {
+                    status: Some(ok_status()),
+                    car_inspection: response.into_inner().car_inspection,
+                },
+                Err(status) => BatchCreateCarInspectionResult {
+                    status: Some(rpc_status(&status)),
+                    car_inspection: None,
+                },
+            };
+            results.push(result);
+        }
+
+        Ok(Response::new(BatchCreateCarInspectionsResponse { results }))
+    }
+
+    async fn batch_delete_car_inspections(
+        &self,
+        request: Request<BatchDeleteCarInspectionsRequest>,
+    ) -> Result<Response<BatchDeleteResponse>, Status> {
+        let (ctx, req) = BatchContext::from_request(request, |r| r.requests.len())?;
+
+        let mut results = Vec::with_capacity(req.requests.len());
+        for entry in req.requests {
+            results.push(self.delete_car_inspection(ctx.request(entry)).await);
+        }
+
+        Ok(Response::new(delete_response(results)))
+    }
+
+    async fn get_expiry_summary(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<ExpirySummary>, Status> {
+        let organization_id = get_organization_from_request(&request);
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        // materialized view が古ければ car_inspection を直接集計
+        let (source, as_of) = match fresh_as_of(&mut conn, KpiView::CarInspectionCurrent).await {
+            Some(at) => ("SELECT expirdate FROM car_inspection_current", at),
+            None => (
+                r#"SELECT DISTINCT ON ("CarId") "TwodimensionCodeInfoValidPeriodExpirdate" AS expirdate
+                   FROM car_inspection
+                   ORDER BY "CarId", "TwodimensionCodeInfoValidPeriodExpirdate" DESC, created_at DESC"#,
+                chrono::Utc::now(),
+            ),
+        };
+        let expirdates: Vec<String> = sqlx::query_scalar(source)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+
+        let jst = FixedOffset::east_opt(9 * 3600).expect("valid offset");
+        let today = chrono::Utc::now().with_timezone(&jst).date_naive();
+        let mut summary = expiry_summary(expirdates.iter().map(String::as_str), today);
+        summary.as_of = as_of.to_rfc3339();
+
+        Ok(Response::new(summary))
+    }
+
+    async fn export_car_inspections(
+        &self,
+        request: Request<ExportCarInspectionsRequest>,
+    ) -> Result<Response<ExportCarInspectionsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let columns = export_columns(&req.columns)?;
+        let expiry_from = parse_expiry_bound("expiry_from", &req.expiry_from)?;
+        let expiry_to = parse_expiry_bound("expiry_to", &req.expiry_to)?;
+        let encoding = TextEncoding::parse(&req.encoding).map_err(Status::invalid_argument)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        // 列名は CAR_INSPECTION_COLUMNS にあるものだけ（すべて NOT NULL の text）
+        let values = columns
+            .iter()
+            .map(|(_, column)| format!("\"{}\"", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let source = if req.current_only {
+            r#"(SELECT DISTINCT ON ("CarId") * FROM car_inspection
+                ORDER BY "CarId", "TwodimensionCodeInfoValidPeriodExpirdate" DESC, created_at DESC) ci"#
+        } else {
+            "car_inspection ci"
+        };
+        let rows: Vec<Vec<String>> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT ARRAY[{values}]::text[] FROM {source}
+            WHERE ($1::text IS NULL OR '20' || "TwodimensionCodeInfoValidPeriodExpirdate" >= $1)
+              AND ($2::text IS NULL OR '20' || "TwodimensionCodeInfoValidPeriodExpirdate" <= $2)
+            ORDER BY "TwodimensionCodeInfoValidPeriodExpirdate", "EntryNoCarNo"
+            LIMIT $3
+            "#,
+            values = values,
+            source = source,
+        ))
+        .bind(&expiry_from)
+        .bind(&expiry_to)
+        .bind(MAX_EXPORT_ROWS + 1)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        if rows.len() as i64 > MAX_EXPORT_ROWS {
+            return Err(Status::failed_precondition(format!(
+                "Too many rows to export (max {}); narrow the expiry range",
+                MAX_EXPORT_ROWS
+            )));
+        }
+
+        let headers: Vec<&str> = columns.iter().map(|(header, _)| *header).collect();
+        let data = render_csv(&headers, &rows, encoding).map_err(Status::internal)?;
+        let filename = format!("車検証_{}.csv", today_jst().format("%Y%m%d"));
+
+        // files に登録（ストレージがなければ DB の blob）
+        let file_uuid = uuid::Uuid::new_v4();
+        let s3_key = match &self.storage {
+            Some(storage) => {
+                let key = format!("{}/exports/{}.csv", organization_id, file_uuid);
+                storage.upload(&key, &data, "text/csv").await?;
+                Some(key)
+            }
+            None => None,
+        };
+        let blob = s3_key
+            .is_none()
+            .then(|| base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &data));
+        sqlx::query(
+            r#"
+            INSERT INTO files
+                (uuid, organization_id, filename, type, created_at, blob, s3_key, storage_class, last_accessed_at, size_bytes)
+            VALUES ($1, $2::uuid, $3, 'text/csv', NOW(), $4, $5, 'STANDARD', NOW(), $6)
+            "#,
+        )
+        .bind(file_uuid)
+        .bind(&organization_id)
+        .bind(&filename)
+        .bind(blob)
+        .bind(&s3_key)
+        .bind(data.len() as i64)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!(
+            "Exported {} car inspections for {} ({} bytes)",
+            rows.len(),
+            organization_id,
+            data.len()
+        );
+        Ok(Response::new(ExportCarInspectionsResponse {
+            file_uuid: file_uuid.to_string(),
+            filename,
+            row_count: rows.len() as i32,
+            size_bytes: data.len() as i64,
+        }))
+    }
+
     async fn list_expired_or_about_to_expire(
         &self,
         request: Request<Empty>,
     ) -> Result<Response<ListCarInspectionsResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // Expired or expiring within 30 days
         let inspections = sqlx::query_as::<_, CarInspectionModel>(
@@ -520,14 +1019,16 @@ impl CarInspectionService for CarInspectionServiceImpl {
         )
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (inspections, pagination) =
+            Paginator::default().finish(inspections, |ci| vec![ci.elect_cert_mg_no.clone()]);
         let proto_inspections: Vec<CarInspection> =
             inspections.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListCarInspectionsResponse {
             car_inspections: proto_inspections,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -537,10 +1038,8 @@ impl CarInspectionService for CarInspectionServiceImpl {
     ) -> Result<Response<ListCarInspectionsResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // Vehicles that need renewal (expiring within 60 days)
         let inspections = sqlx::query_as::<_, CarInspectionModel>(
@@ -553,14 +1052,16 @@ impl CarInspectionService for CarInspectionServiceImpl {
         )
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (inspections, pagination) =
+            Paginator::default().finish(inspections, |ci| vec![ci.elect_cert_mg_no.clone()]);
         let proto_inspections: Vec<CarInspection> =
             inspections.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListCarInspectionsResponse {
             car_inspections: proto_inspections,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -574,6 +1075,7 @@ impl CarInspectionService for CarInspectionServiceImpl {
         let organization_id = get_organization_from_request(&request);
         tracing::info!("organization_id: {}", organization_id);
         let req = request.into_inner();
+        let force_refresh = req.force_refresh.unwrap_or(false);
 
         // Parse date parameter or use today (no DB needed)
         let search_date = req.date.unwrap_or_else(|| {
@@ -595,32 +1097,27 @@ impl CarInspectionService for CarInspectionServiceImpl {
         tracing::info!("search_date_yymmdd: {}", search_date_yymmdd);
 
         // Fetch home car list from external API BEFORE acquiring DB connection
-        // This minimizes the time between set_current_organization and query execution
-        let home_cars: Vec<HomeCarEntry> = self
-            .http_client
-            .get_json(&self.dtako_api_url)
-            .await
-            .map_err(|e| Status::unavailable(format!("Failed to fetch home car list: {}", e)))?;
-        tracing::info!("home_cars count: {}", home_cars.len());
+        // This minimizes the time the organization-scoped connection is held
+        let home_cars = self.fetch_home_cars(&organization_id, force_refresh).await?;
+        tracing::info!("home_cars count: {} (stale: {})", home_cars.cars.len(), home_cars.stale);
 
         // Create a set of home car VehicleCDs for fast lookup
         let home_vehicle_cds: HashSet<String> = home_cars
+            .cars
             .iter()
             .map(|c| c.vehicle_cd.to_string())
             .collect();
         tracing::info!("home_vehicle_cds count: {}", home_vehicle_cds.len());
 
         // Acquire DB connection and set organization context
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // Verify organization context was set correctly
         let verified_org: Option<String> = sqlx::query_scalar("SELECT get_current_organization()")
             .fetch_one(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Failed to verify organization context: {}", e)))?;
+            .map_err(AppError::from)?;
         tracing::info!("Verified organization context: {:?}", verified_org);
 
         if verified_org.as_deref() != Some(&organization_id) {
@@ -798,7 +1295,7 @@ impl CarInspectionService for CarInspectionServiceImpl {
         .bind(&search_date_yymmdd)
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         tracing::info!("inspections count from DB: {}", inspections.len());
 
@@ -966,18 +1463,121 @@ impl CarInspectionService for CarInspectionServiceImpl {
 
         Ok(Response::new(ListRenewHomeTargetsResponse {
             car_inspections: filtered,
+            home_cars_stale: home_cars.stale,
+            home_cars_fetched_at: Some(home_cars.fetched_at.to_rfc3339()),
+            degraded_reason: home_cars.error,
         }))
     }
 }
 
 // CarInspectionFilesService implementation
+/// UploadCarInspectionFile で解析した車検証
+enum UploadedCert {
+    Json(CertJson),
+    Pdf(CertKey),
+}
+
+/// 紐づけ処理のエラー（DB エラーはそのまま分類する）
+fn link_error(e: anyhow::Error) -> Status {
+    match e.downcast::<sqlx::Error>() {
+        Ok(db) => AppError::from(db).into(),
+        Err(e) => Status::internal(format!("Failed to link car inspection file: {}", e)),
+    }
+}
+
 pub struct CarInspectionFilesServiceImpl {
     pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    events: EventBus,
+    outbox: Outbox,
 }
 
 impl CarInspectionFilesServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>, events: EventBus, outbox: Outbox) -> Self {
+        Self { pool, storage, events, outbox }
+    }
+
+    /// files の行と車検証への紐づけを 1 トランザクションで作る（PDF が JSON 待ちなら pending = true）
+    #[allow(clippy::too_many_arguments)]
+    async fn store_and_link(
+        &self,
+        organization_id: &str,
+        uuid: &str,
+        filename: &str,
+        mime_type: &str,
+        content: &[u8],
+        s3_key: Option<&str>,
+        created: chrono::DateTime<chrono::Utc>,
+        cert: &UploadedCert,
+    ) -> Result<(CarInspectionFile, bool), Status> {
+        with_org_transaction(&self.pool, organization_id, |mut tx| async move {
+            // ストレージ有効時はメタデータのみ、無効時は blob を DB に保存（FilesService.CreateFile と同じ）
+            let blob = match s3_key {
+                Some(_) => None,
+                None => Some(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, content)),
+            };
+            sqlx::query(
+                r#"
+                INSERT INTO files (uuid, organization_id, filename, type, created_at, blob, s3_key, last_accessed_at, size_bytes)
+                VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+            )
+            .bind(uuid)
+            .bind(organization_id)
+            .bind(filename)
+            .bind(mime_type)
+            .bind(created)
+            .bind(&blob)
+            .bind(s3_key)
+            .bind(s3_key.map(|_| created))
+            .bind(content.len() as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+            let (table, key, pending) = match cert {
+                UploadedCert::Json(cert) => {
+                    FileAutoParser::link_json(&mut tx, uuid, cert).await.map_err(link_error)?;
+                    ("car_inspection_files_a", &cert.key, false)
+                }
+                UploadedCert::Pdf(key) => {
+                    let linked = FileAutoParser::link_pdf(&mut tx, uuid, key).await.map_err(link_error)?;
+                    ("car_inspection_files_b", key, !linked)
+                }
+            };
+
+            let file = if pending {
+                CarInspectionFile {
+                    uuid: uuid.to_string(),
+                    r#type: mime_type.to_string(),
+                    elect_cert_mg_no: key.elect_cert_mg_no.clone(),
+                    grantdate_e: key.grantdate_e.clone(),
+                    grantdate_y: key.grantdate_y.clone(),
+                    grantdate_m: key.grantdate_m.clone(),
+                    grantdate_d: key.grantdate_d.clone(),
+                    created: created.to_rfc3339(),
+                    modified: None,
+                    deleted: None,
+                }
+            } else {
+                let model = sqlx::query_as::<_, CarInspectionFileModel>(&format!(
+                    "SELECT * FROM {} WHERE uuid = $1::uuid",
+                    table
+                ))
+                .bind(uuid)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+                Self::model_to_proto(&model)
+            };
+
+            self.outbox
+                .write(&mut tx, organization_id, &FileAutoParser::parsed_event(uuid, mime_type, key, pending))
+                .await
+                .map_err(AppError::from)?;
+            Ok::<_, Status>((tx, (file, pending)))
+        })
+        .await
     }
 
     fn model_to_proto(model: &CarInspectionFileModel) -> CarInspectionFile {
@@ -1008,10 +1608,8 @@ impl CarInspectionFilesService for CarInspectionFilesServiceImpl {
             .file
             .ok_or_else(|| Status::invalid_argument("file is required"))?;
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // hono-logi準拠: JSON→car_inspection_files_a、PDF→car_inspection_files_b
         let table = if file.r#type == "application/pdf" {
@@ -1040,13 +1638,104 @@ impl CarInspectionFilesService for CarInspectionFilesServiceImpl {
         .bind(&file.grantdate_d)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(CarInspectionFileResponse {
             file: Some(Self::model_to_proto(&result)),
         }))
     }
 
+    async fn upload_car_inspection_file(
+        &self,
+        request: Request<UploadCarInspectionFileRequest>,
+    ) -> Result<Response<UploadCarInspectionFileResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let filename = validation::required_line("filename", &req.filename, validation::FILENAME_MAX_CHARS)?;
+        if req.content.is_empty() {
+            return Err(Status::invalid_argument("content is required"));
+        }
+
+        // 解析は保存より先に行い、車検証でなければ何も残さない
+        let parsed = match req.r#type.as_str() {
+            "application/json" => FileAutoParser::parse_json(&req.content).map(|c| c.map(UploadedCert::Json)),
+            "application/pdf" => FileAutoParser::parse_pdf(&req.content).map(|k| k.map(UploadedCert::Pdf)),
+            _ => {
+                return Err(Status::invalid_argument(
+                    "type must be application/json or application/pdf",
+                ))
+            }
+        };
+        let cert = parsed
+            .map_err(|e| Status::invalid_argument(format!("Failed to parse file: {}", e)))?
+            .ok_or_else(|| Status::invalid_argument("File is not a car inspection certificate"))?;
+
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let created = chrono::Utc::now();
+        let s3_key = match &self.storage {
+            Some(storage) => {
+                let key = format!("{}/{}", organization_id, uuid);
+                storage.upload(&key, &req.content, &req.r#type).await.map_err(Status::from)?;
+                Some(key)
+            }
+            None => None,
+        };
+
+        let result = self
+            .store_and_link(
+                &organization_id,
+                &uuid,
+                &filename,
+                &req.r#type,
+                &req.content,
+                s3_key.as_deref(),
+                created,
+                &cert,
+            )
+            .await;
+        // ロールバックした場合はアップロード済みのオブジェクトも消す
+        if result.is_err() {
+            if let (Some(storage), Some(key)) = (&self.storage, &s3_key) {
+                if let Err(e) = storage.delete(key).await {
+                    tracing::warn!("Failed to delete orphaned upload {}: {}", key, e);
+                }
+            }
+        }
+        let (file, pending) = result?;
+
+        tracing::info!(
+            "Uploaded car inspection file: uuid={}, ElectCertMgNo={}, pending={}, org={}",
+            uuid,
+            file.elect_cert_mg_no,
+            pending,
+            organization_id
+        );
+        self.events.publish(EntityEvent {
+            organization_id: organization_id.clone(),
+            user_id: None,
+            change_type: ChangeType::Created,
+            change: EntityChange::File(File {
+                uuid: uuid.clone(),
+                filename,
+                r#type: req.r#type,
+                created: created.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                deleted: None,
+                blob: None,
+                storage_class: s3_key.as_ref().map(|_| "STANDARD".to_string()),
+                last_accessed_at: s3_key.as_ref().map(|_| created.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+                s3_key,
+                thumbnail_small_url: None,
+                thumbnail_medium_url: None,
+                purge_at: None,
+            }),
+        });
+
+        Ok(Response::new(UploadCarInspectionFileResponse {
+            file: Some(file),
+            pending,
+        }))
+    }
+
     async fn list_car_inspection_files(
         &self,
         request: Request<ListCarInspectionFilesRequest>,
@@ -1054,32 +1743,36 @@ impl CarInspectionFilesService for CarInspectionFilesServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
-        let files = if let Some(elect_cert_mg_no) = req.elect_cert_mg_no {
-            sqlx::query_as::<_, CarInspectionFileModel>(
-                r#"SELECT * FROM car_inspection_files_a WHERE "ElectCertMgNo" = $1 AND deleted_at IS NULL ORDER BY created_at DESC"#,
-            )
-            .bind(&elect_cert_mg_no)
-            .fetch_all(&mut *conn)
-            .await
-        } else {
-            sqlx::query_as::<_, CarInspectionFileModel>(
-                r#"SELECT * FROM car_inspection_files_a WHERE deleted_at IS NULL ORDER BY created_at DESC"#,
-            )
-            .fetch_all(&mut *conn)
-            .await
-        }
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
 
+        // キーセット: (created_at, uuid) DESC、カーソルは前ページ最終行の uuid
+        let files = sqlx::query_as::<_, CarInspectionFileModel>(
+            r#"
+            SELECT * FROM car_inspection_files_a
+            WHERE deleted_at IS NULL
+              AND ($1::text IS NULL OR "ElectCertMgNo" = $1)
+              AND ($2::uuid IS NULL OR (created_at, uuid) <
+                   (SELECT created_at, uuid FROM car_inspection_files_a WHERE uuid = $2::uuid))
+            ORDER BY created_at DESC, uuid DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(&req.elect_cert_mg_no)
+        .bind(paginator.cursor(0))
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (files, pagination) = paginator.finish(files, |f| vec![f.uuid.to_string()]);
         let proto_files: Vec<CarInspectionFile> = files.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListCarInspectionFilesResponse {
             files: proto_files,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -1089,10 +1782,8 @@ impl CarInspectionFilesService for CarInspectionFilesServiceImpl {
     ) -> Result<Response<ListCarInspectionFilesResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let files = sqlx::query_as::<_, CarInspectionFileModel>(
             r#"
@@ -1111,13 +1802,239 @@ impl CarInspectionFilesService for CarInspectionFilesServiceImpl {
         )
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (files, pagination) = Paginator::default().finish(files, |f| vec![f.uuid.to_string()]);
         let proto_files: Vec<CarInspectionFile> = files.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListCarInspectionFilesResponse {
             files: proto_files,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 }
+
+/// 車検期限通知（スケジュール実行）
+pub const EXPIRY_NOTIFY_JOB: &str = "car_inspection.expiry_notify";
+
+pub const EXPIRY_NOTIFY_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: EXPIRY_NOTIFY_JOB,
+    description: "期限切れ・30日以内に期限切れの車検証を通知（車両ごとに区分が変わったときだけ）",
+    default_cron: "0 9 * * *",
+};
+
+/// ListExpiredOrAboutToExpire と同じ対象を outbox 経由で通知する job ハンドラ
+///
+/// 通知済みの車両・有効期限・区分は car_inspection_expiry_notices に記録し、同じ組み合わせは2回送らない。
+pub struct ExpiryNotifyJobHandler {
+    pool: PgPool,
+    outbox: Outbox,
+    notifier: Notifier,
+}
+
+impl ExpiryNotifyJobHandler {
+    pub fn new(pool: PgPool, outbox: Outbox, notifier: Notifier) -> Self {
+        Self { pool, outbox, notifier }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for ExpiryNotifyJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let queued = with_org_transaction(&self.pool, &job.organization_id, |mut tx| async move {
+            let inspections = sqlx::query_as::<_, CarInspectionModel>(
+                r#"
+                SELECT * FROM car_inspection
+                WHERE "TwodimensionCodeInfoValidPeriodExpirdate" <= to_char(CURRENT_DATE + INTERVAL '30 days', 'YYMMDD')
+                ORDER BY "TwodimensionCodeInfoValidPeriodExpirdate" ASC
+                "#,
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            // 区分ごとに未通知の車両だけ（同じ車両の同じ期限は1件に）
+            let today = today_jst();
+            let mut keys = HashSet::new();
+            let candidates: Vec<(&CarInspectionModel, &'static str)> = inspections
+                .iter()
+                .filter_map(|ci| {
+                    let window = expiry_notice_window(&ci.twodimension_code_info_valid_period_expirdate, today)?;
+                    keys.insert((ci.car_id.as_str(), ci.twodimension_code_info_valid_period_expirdate.as_str(), window))
+                        .then_some((ci, window))
+                })
+                .collect();
+            if candidates.is_empty() {
+                return Ok((tx, 0));
+            }
+
+            let recorded: Vec<(String, String, String)> = sqlx::query_as(
+                r#"
+                INSERT INTO car_inspection_expiry_notices (organization_id, car_id, expirdate, notice_window)
+                SELECT $1::uuid, * FROM UNNEST($2::text[], $3::text[], $4::text[])
+                ON CONFLICT DO NOTHING
+                RETURNING car_id, expirdate, notice_window
+                "#,
+            )
+            .bind(&job.organization_id)
+            .bind(candidates.iter().map(|(ci, _)| ci.car_id.clone()).collect::<Vec<_>>())
+            .bind(candidates.iter().map(|(ci, _)| ci.twodimension_code_info_valid_period_expirdate.clone()).collect::<Vec<_>>())
+            .bind(candidates.iter().map(|(_, window)| window.to_string()).collect::<Vec<_>>())
+            .fetch_all(&mut *tx)
+            .await?;
+            let recorded: HashSet<(String, String, String)> = recorded.into_iter().collect();
+            let inspections: Vec<&CarInspectionModel> = candidates
+                .into_iter()
+                .filter(|(ci, window)| {
+                    recorded.contains(&(
+                        ci.car_id.clone(),
+                        ci.twodimension_code_info_valid_period_expirdate.clone(),
+                        window.to_string(),
+                    ))
+                })
+                .map(|(ci, _)| ci)
+                .collect();
+            if inspections.is_empty() {
+                return Ok((tx, 0));
+            }
+
+            let vehicles = inspections
+                .iter()
+                .map(|ci| format!("{} {} 期限: {}", ci.car_no, ci.car_name, ci.twodimension_code_info_valid_period_expirdate))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let message = format!("【車検期限通知】\n期限切れ・30日以内に期限切れ: {}台\n{}", inspections.len(), vehicles);
+            let event = OutboxEvent::new(
+                CAR_INSPECTION_EXPIRING,
+                serde_json::json!({
+                    "elect_cert_mg_nos": inspections.iter().map(|ci| &ci.elect_cert_mg_no).collect::<Vec<_>>(),
+                }),
+            )
+            .message(message);
+            self.outbox.write(&mut tx, &job.organization_id, &event).await?;
+
+            // 管理者へのメールとアプリ内通知、LINE WORKS 連携済みメンバーへの個別通知、Slack / Discord Webhook
+            let admins = Notifier::admin_recipients(&mut tx, &job.organization_id).await?;
+            let admin_feed = Notifier::in_app_recipients(&mut tx, &job.organization_id, true).await?;
+            let members = Notifier::lineworks_recipients(&mut tx, &job.organization_id).await?;
+            let webhooks = Notifier::webhook_recipients(&mut tx, &job.organization_id).await?;
+            // car_no / expiry_date は最も期限が近い車両（組織のテンプレートで使う）
+            let nearest = inspections[0];
+            let notification = Notification::new(EXPIRY_ALERT)
+                .var("count", inspections.len())
+                .var("vehicles", &vehicles)
+                .var("car_no", &nearest.car_no)
+                .var("expiry_date", &nearest.twodimension_code_info_valid_period_expirdate)
+                .to_all(admins)
+                .to_all(admin_feed)
+                .to_all(members)
+                .to_all(webhooks);
+            self.notifier.send(&mut tx, &job.organization_id, &notification).await?;
+            Ok::<_, anyhow::Error>((tx, inspections.len()))
+        })
+        .await?;
+        if queued == 0 {
+            return Ok(());
+        }
+
+        tracing::info!(
+            "Expiry notification queued for {}: {} vehicles",
+            job.organization_id,
+            queued
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::db::DEFAULT_ORGANIZATION_ID;
+    use crate::models::HomeCarEntry;
+
+    /// 1 回目だけ成功する dtako API
+    struct FlakyDtakoApi {
+        failed: AtomicBool,
+    }
+
+    #[tonic::async_trait]
+    impl DtakoApi for FlakyDtakoApi {
+        async fn home_cars(&self, _organization_id: &str, _refresh: bool) -> anyhow::Result<Vec<HomeCarEntry>> {
+            if self.failed.swap(true, Ordering::SeqCst) {
+                anyhow::bail!("connection refused");
+            }
+            Ok(vec![HomeCarEntry { vehicle_cd: 101, vehicle_name: None, all_state: None }])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_home_cars_falls_back_to_last_list() {
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let service = CarInspectionServiceImpl::new(
+            pool,
+            None,
+            Arc::new(FlakyDtakoApi { failed: AtomicBool::new(false) }),
+            HomeCarCache::new(3600),
+            EventBus::new(),
+            Outbox::default(),
+        );
+
+        let fresh = service.fetch_home_cars(DEFAULT_ORGANIZATION_ID, false).await.unwrap();
+        assert!(!fresh.stale);
+        let stale = service.fetch_home_cars(DEFAULT_ORGANIZATION_ID, false).await.unwrap();
+        assert!(stale.stale);
+        assert_eq!(stale.cars[0].vehicle_cd, 101);
+        assert!(stale.error.unwrap().contains("connection refused"));
+    }
+
+    #[test]
+    fn test_expiry_summary() {
+        let today = NaiveDate::from_ymd_opt(2026, 4, 1).unwrap();
+        let summary = expiry_summary(
+            ["260331", "260401", "260501", "260531", "260630", "261231", ""],
+            today,
+        );
+        assert_eq!(summary.total, 7);
+        assert_eq!(summary.expired, 1);
+        assert_eq!(summary.within_30_days, 2);
+        assert_eq!(summary.within_60_days, 1);
+        assert_eq!(summary.within_90_days, 1);
+        assert_eq!(summary.later, 1);
+        assert_eq!(summary.unknown, 1);
+    }
+
+    #[test]
+    fn test_expiry_notice_window() {
+        let today = NaiveDate::from_ymd_opt(2026, 4, 1).unwrap();
+        assert_eq!(expiry_notice_window("260331", today), Some("expired"));
+        assert_eq!(expiry_notice_window("260401", today), Some("within_30_days"));
+        assert_eq!(expiry_notice_window("260501", today), Some("within_30_days"));
+        assert_eq!(expiry_notice_window("260502", today), None);
+        assert_eq!(expiry_notice_window("", today), None);
+    }
+
+    #[test]
+    fn test_export_columns_and_csv() {
+        let columns = export_columns(&[]).unwrap();
+        assert_eq!(columns[0], ("登録番号", "EntryNoCarNo"));
+        assert_eq!(columns.len(), EXPORT_DEFAULT_COLUMNS.len());
+
+        let columns = export_columns(&["car_no".to_string(), "model_specify_no".to_string(), "car_no".to_string()]).unwrap();
+        assert_eq!(columns, vec![("車台番号", "CarNo"), ("model_specify_no", "ModelSpecifyNo")]);
+        assert!(export_columns(&["id; DROP TABLE files".to_string()]).is_err());
+
+        assert_eq!(parse_expiry_bound("expiry_from", "2026-04-01").unwrap().as_deref(), Some("20260401"));
+        assert_eq!(parse_expiry_bound("expiry_from", "").unwrap(), None);
+        assert!(parse_expiry_bound("expiry_from", "2026/04/01").is_err());
+
+        let rows = vec![vec!["品川500あ1234".to_string(), "株式会社\"大石\", 本社".to_string()]];
+        let csv = render_csv(&["登録番号", "所有者"], &rows, TextEncoding::Utf8).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "\u{FEFF}登録番号,所有者\r\n品川500あ1234,\"株式会社\"\"大石\"\", 本社\"\r\n"
+        );
+        let sjis = render_csv(&["登録番号"], &[], TextEncoding::ShiftJis).unwrap();
+        assert_eq!(crate::text_encoding::decode_text(&sjis, TextEncoding::ShiftJis).unwrap(), "登録番号\r\n");
+    }
+}
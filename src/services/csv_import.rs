@@ -0,0 +1,156 @@
+use chrono::{NaiveDate, NaiveTime};
+
+/// 1回に取り込める CSV の上限
+pub const MAX_CSV_BYTES: usize = 10 * 1024 * 1024;
+/// レスポンスに載せる読み飛ばし行の上限
+pub const MAX_REPORTED_ERRORS: usize = 50;
+
+/// 見出しの揺れ（全角英数・括弧・空白・ヵ/ケ）をそろえる
+pub fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '（' => '(',
+            '）' => ')',
+            'ヵ' | 'ケ' | 'ｹ' => 'ヶ',
+            'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '０'..='９' => {
+                char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)
+            }
+            c => c,
+        })
+        .collect()
+}
+
+/// 見出し行（normalize_header 済み）から列を探す
+pub struct CsvHeaders(Vec<String>);
+
+impl CsvHeaders {
+    pub fn new(headers: &csv::StringRecord) -> Self {
+        Self(headers.iter().map(normalize_header).collect())
+    }
+
+    pub fn find(&self, pred: impl Fn(&str) -> bool) -> Option<usize> {
+        self.0.iter().position(|h| pred(h))
+    }
+
+    /// 候補の名前のうち、最初に見つかった列
+    pub fn find_any(&self, names: &[&str]) -> Option<usize> {
+        names.iter().find_map(|name| self.find(|h| h == *name))
+    }
+}
+
+/// "2026/10/15" / "26/10/15" / "2026-10-15"
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let parts: Vec<&str> = value.trim().split(['/', '-']).collect();
+    let [year, month, day] = parts.as_slice() else {
+        return None;
+    };
+    let year: i32 = year.parse().ok()?;
+    let year = if year < 100 { 2000 + year } else { year };
+    NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)
+}
+
+/// "8:05" / "08:05" / "08:05:00"（空なら 0:00）
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    if value.is_empty() {
+        return NaiveTime::from_hms_opt(0, 0, 0);
+    }
+    let mut parts = value.split(':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn clean_number(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, ',' | '¥' | '￥' | '円' | ' ' | 'L' | 'ℓ' | 'ｌ'))
+        .collect()
+}
+
+/// "1,230" / "-500" / "￥1,230"（空なら None）
+pub fn parse_amount(value: &str) -> Result<Option<i32>, String> {
+    let cleaned = clean_number(value);
+    if cleaned.is_empty() {
+        return Ok(None);
+    }
+    cleaned
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("金額が不正です: {}", value))
+}
+
+/// "45.20" / "1,234.5L"（空なら None）
+pub fn parse_decimal(value: &str) -> Result<Option<f64>, String> {
+    let cleaned = clean_number(value);
+    if cleaned.is_empty() {
+        return Ok(None);
+    }
+    cleaned
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .map(Some)
+        .ok_or_else(|| format!("数値が不正です: {}", value))
+}
+
+/// 見出し付き CSV を1行ずつ読む（読めた行, 読み飛ばした行の理由 "N行目: ..."）
+pub fn read_csv<C, T>(
+    text: &str,
+    columns: impl FnOnce(&CsvHeaders) -> Result<C, String>,
+    parse_row: impl Fn(&csv::StringRecord, &C) -> Result<T, String>,
+) -> Result<(Vec<T>, Vec<String>), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("CSV の見出しを読めません: {}", e))?;
+    let columns = columns(&CsvHeaders::new(headers))?;
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (i, row) in reader.records().enumerate() {
+        // 見出しが1行目
+        let line = i + 2;
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                errors.push(format!("{}行目: {}", line, e));
+                continue;
+            }
+        };
+        if row.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+        match parse_row(&row, &columns) {
+            Ok(record) => records.push(record),
+            Err(e) => errors.push(format!("{}行目: {}", line, e)),
+        }
+    }
+    Ok((records, errors))
+}
+
+/// 行の idx 列（列がなければ空文字）
+pub fn field(row: &csv::StringRecord, idx: Option<usize>) -> String {
+    idx.and_then(|i| row.get(i)).unwrap_or("").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(normalize_header("ＥＴＣ割引額 "), "ETC割引額");
+        assert_eq!(parse_date("26/10/15"), NaiveDate::from_ymd_opt(2026, 10, 15));
+        assert_eq!(parse_date("2026-13-01"), None);
+        assert_eq!(parse_time("8:05"), NaiveTime::from_hms_opt(8, 5, 0));
+        assert_eq!(parse_amount("￥1,230").unwrap(), Some(1230));
+        assert_eq!(parse_amount("").unwrap(), None);
+        assert!(parse_amount("abc").is_err());
+        assert_eq!(parse_decimal("45.20L").unwrap(), Some(45.2));
+    }
+}
@@ -1,29 +1,510 @@
-use sqlx::PgPool;
-use tonic::{Request, Response, Status};
-
-use crate::db::{get_organization_from_request, set_current_organization};
-use crate::models::DtakologModel;
-use crate::proto::common::Empty;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use sqlx::query_builder::Separated;
+use sqlx::{Connection, PgPool, Postgres, QueryBuilder};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tokio::time::MissedTickBehavior;
+use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
+
+use crate::db::kpi_views::is_fresh;
+use crate::db::{get_organization_from_request, KpiView, OrderBy, OrgScopedPool, Paginator};
+use crate::error::{AppError, ResultExt};
+use crate::events::{watch_stream, EntityChange, EntityEvent, EventBus};
+use crate::geocoding::track::{path_length_m, simplify};
+use crate::geocoding::{backfill_job, GeoPoint, Geocoder};
+use crate::geofencing::{GeofenceEvaluator, Position};
+use crate::jobs::enqueue;
+use crate::models::{DtakologModel, DTAKOLOG_SORT_COLUMNS};
+use crate::outbox::Outbox;
+use crate::proto::common::{ChangeType, Empty};
 use crate::proto::dtakologs::dtakologs_service_server::DtakologsService;
 use crate::proto::dtakologs::{
-    BulkCreateDtakologsRequest, BulkCreateDtakologsResponse, CreateDtakologRequest,
-    CreateDtakologResponse, CurrentListSelectRequest, DeleteResponse, Dtakolog, GetDateRangeRequest,
-    GetDateRequest, ListDtakologsResponse,
+    BackfillAddressesResponse, BulkCreateDtakologsRequest, BulkCreateDtakologsResponse,
+    CreateDtakologBatchRequest, CreateDtakologBatchResponse, CreateDtakologRequest, CreateDtakologResponse, CurrentListSelectRequest, DeleteResponse, Dtakolog,
+    DtakologEvent,
+    ExportDtakologsParquetRequest, ExportDtakologsParquetResponse, GetDateRangeRequest, GetDateRequest,
+    GetVehicleTrackRequest, GetVehicleTrackResponse, GetVehicleUtilizationRequest, GetVehicleUtilizationResponse, IngestDtakologsResponse, ListDtakologsRequest,
+    ListDtakologsResponse, ParquetFile, ReverseGeocodeRequest, ReverseGeocodeResponse,
+    StreamDtakologsRequest, TrackPoint, VehicleUtilization, WatchDtakologsRequest,
 };
+use crate::reports::data::{jst_range, vehicle_utilization};
+use crate::services::batch::{ok_status, rpc_status};
+use crate::services::report_service::parse_period;
+use crate::storage::StorageBackend;
+use crate::warehouse::DtakologParquetWriter;
+
+/// Parquet の row group の行数
+const PARQUET_ROW_GROUP_ROWS: usize = 50_000;
+/// 1ファイルの行数の上限（超えたら次のファイル）
+const PARQUET_FILE_ROWS: usize = 1_000_000;
+/// 署名付き URL の有効期間（分）
+const DEFAULT_EXPORT_URL_MINUTES: i32 = 60;
+const MAX_EXPORT_URL_MINUTES: i32 = 7 * 24 * 60;
+/// CreateBatch の上限行数
+const MAX_CREATE_BATCH_ROWS: usize = 10_000;
+/// IngestDtakologs の書き込み間隔（行数・時間のどちらかに達したら書く）
+const INGEST_FLUSH_ROWS: usize = 500;
+const INGEST_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// IngestDtakologs の応答に含める失敗行の上限
+const MAX_INGEST_ERRORS: usize = 100;
+/// CreateBatch の 1 文あたりの行数（bind パラメータの上限 65535 / 57 列）
+const CREATE_BATCH_STATEMENT_ROWS: usize = 1_000;
+
+/// GetVehicleTrack の期間の上限（日）
+const MAX_TRACK_DAYS: i64 = 31;
+/// GetVehicleTrack の間引きの許容誤差の上限（m）
+const MAX_TRACK_TOLERANCE_M: f64 = 1000.0;
+
+/// dtakologs に INSERT する列（organization_id を除く。push_dtakolog の bind 順）
+const DTAKOLOG_INSERT_COLUMNS: &str = "data_date_time, vehicle_cd, type, \
+    all_state_font_color_index, all_state_ryout_color, branch_cd, branch_name, \
+    current_work_cd, data_filter_type, disp_flag, driver_cd, \
+    gps_direction, gps_enable, gps_latitude, gps_longitude, gps_satellite_num, \
+    operation_state, recive_event_type, recive_packet_type, recive_work_cd, revo, \
+    setting_temp, setting_temp1, setting_temp3, setting_temp4, speed, \
+    sub_driver_cd, temp_state, vehicle_name, \
+    address_disp_c, address_disp_p, all_state, all_state_ex, all_state_font_color, \
+    comu_date_time, current_work_name, driver_name, event_val, gps_lati_and_long, \
+    odometer, recive_type_color_name, recive_type_name, start_work_date_time, \
+    state, state1, state2, state3, state_flag, \
+    temp1, temp2, temp3, temp4, \
+    vehicle_icon_color, vehicle_icon_label_for_datetime, \
+    vehicle_icon_label_for_driver, vehicle_icon_label_for_vehicle";
 
 pub struct DtakologsServiceImpl {
     pool: PgPool,
+    geocoder: Option<Arc<Geocoder>>,
+    storage: Option<Arc<dyn StorageBackend>>,
+    events: EventBus,
+    geofences: GeofenceEvaluator,
+}
+
+/// 走行軌跡の 1 行
+#[derive(Debug, sqlx::FromRow)]
+struct TrackRow {
+    data_date_time: String,
+    gps_latitude: i32,
+    gps_longitude: i32,
+    speed: f32,
+    gps_direction: i32,
+}
+
+fn parse_track_time(value: &str, field: &str) -> Result<DateTime<FixedOffset>, Status> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map_err(|_| Status::invalid_argument(format!("{} must be ISO8601: {:?}", field, value)))
+}
+
+/// 軌跡を組み立てる（無効な座標は除き、距離・平均速度は間引く前の点で計算）
+fn build_track(vehicle_cd: i32, rows: Vec<TrackRow>, tolerance_m: f64) -> GetVehicleTrackResponse {
+    let rows: Vec<(TrackRow, GeoPoint)> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let point = GeoPoint::from_dtako(row.gps_latitude, row.gps_longitude)?;
+            Some((row, point))
+        })
+        .collect();
+    let points: Vec<GeoPoint> = rows.iter().map(|(_, p)| *p).collect();
+    let distance_km = path_length_m(&points) / 1000.0;
+
+    let elapsed_hours = match (rows.first(), rows.last()) {
+        (Some((first, _)), Some((last, _))) if rows.len() >= 2 => {
+            match (
+                DateTime::parse_from_rfc3339(&first.data_date_time),
+                DateTime::parse_from_rfc3339(&last.data_date_time),
+            ) {
+                (Ok(from), Ok(to)) => Some((to - from).num_seconds() as f64 / 3600.0),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let average_speed_kmh = elapsed_hours
+        .filter(|hours| *hours > 0.0)
+        .map(|hours| distance_km / hours);
+
+    let track_points = simplify(&points, tolerance_m)
+        .into_iter()
+        .map(|i| {
+            let (row, point) = &rows[i];
+            TrackPoint {
+                data_date_time: row.data_date_time.clone(),
+                latitude: point.lat,
+                longitude: point.lon,
+                speed: row.speed,
+                gps_direction: row.gps_direction,
+            }
+        })
+        .collect();
+
+    GetVehicleTrackResponse {
+        vehicle_cd,
+        points: track_points,
+        raw_point_count: rows.len() as i32,
+        distance_km,
+        average_speed_kmh,
+    }
+}
+
+/// 書き込んだ行のうち車両ごとに最新（data_date_time が最大）の行
+fn latest_per_vehicle<'a>(
+    written: impl IntoIterator<Item = (&'a Dtakolog, ChangeType)>,
+) -> Vec<(&'a Dtakolog, ChangeType)> {
+    let mut latest: HashMap<i32, (&Dtakolog, ChangeType)> = HashMap::new();
+    for (dtakolog, change_type) in written {
+        match latest.get(&dtakolog.vehicle_cd) {
+            Some((current, _)) if current.data_date_time >= dtakolog.data_date_time => {}
+            _ => {
+                latest.insert(dtakolog.vehicle_cd, (dtakolog, change_type));
+            }
+        }
+    }
+    let mut latest: Vec<_> = latest.into_values().collect();
+    latest.sort_by_key(|(d, _)| d.vehicle_cd);
+    latest
 }
 
 impl DtakologsServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(
+        pool: PgPool,
+        geocoder: Option<Arc<Geocoder>>,
+        storage: Option<Arc<dyn StorageBackend>>,
+        events: EventBus,
+        outbox: Outbox,
+    ) -> Self {
+        Self {
+            pool,
+            geocoder,
+            storage,
+            events,
+            geofences: GeofenceEvaluator::new(outbox),
+        }
+    }
+
+    /// 書き込んだ行でジオフェンスの出入りを判定（失敗しても書き込みは成功のまま）
+    async fn evaluate_geofences<'a>(
+        &self,
+        organization_id: &str,
+        written: impl IntoIterator<Item = &'a Dtakolog>,
+    ) {
+        let positions: Vec<Position> = written.into_iter().filter_map(Position::from_dtakolog).collect();
+        if positions.is_empty() {
+            return;
+        }
+        let result = async {
+            let mut tx = self.pool.begin_scoped(organization_id).await?;
+            let recorded = self.geofences.evaluate(&mut tx, organization_id, &positions).await?;
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(recorded)
+        }
+        .await;
+        match result {
+            Ok(0) => {}
+            Ok(recorded) => tracing::info!("Recorded {} geofence events for {}", recorded, organization_id),
+            Err(e) => tracing::warn!("Failed to evaluate geofences: {}", e),
+        }
+    }
+
+    /// WatchDtakologs の購読者へ書き込んだ行を通知（車両ごとに最新の 1 行）
+    fn publish<'a>(
+        &self,
+        organization_id: &str,
+        written: impl IntoIterator<Item = (&'a Dtakolog, ChangeType)>,
+    ) {
+        for (dtakolog, change_type) in latest_per_vehicle(written) {
+            self.events.publish(EntityEvent {
+                organization_id: organization_id.to_string(),
+                user_id: None,
+                change_type,
+                change: EntityChange::Dtakolog(dtakolog.clone()),
+            });
+        }
+    }
+
+    fn change_type(inserted: bool) -> ChangeType {
+        if inserted {
+            ChangeType::Created
+        } else {
+            ChangeType::Updated
+        }
+    }
+
+    fn geocoder(&self) -> Result<&Arc<Geocoder>, Status> {
+        self.geocoder
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Reverse geocoding is not configured"))
+    }
+
+    /// 住所がなく GPS がある（逆ジオコーディングで埋められる）行
+    fn needs_address(dtakolog: &Dtakolog) -> bool {
+        dtakolog.address_disp_c.as_deref().map_or(true, str::is_empty)
+            && GeoPoint::from_dtako(dtakolog.gps_latitude, dtakolog.gps_longitude).is_some()
     }
 
     fn model_to_proto(model: &DtakologModel) -> Dtakolog {
         model.to_proto()
     }
 
+    /// 車両ごとの最新運行ログの FROM 句（materialized view が古ければ dtakologs を直接集計）
+    async fn latest_source(conn: &mut sqlx::PgConnection) -> &'static str {
+        if is_fresh(&mut *conn, KpiView::DtakologsLatest).await {
+            "dtakologs_latest"
+        } else {
+            "(SELECT DISTINCT ON (vehicle_cd) * FROM dtakologs ORDER BY vehicle_cd, data_date_time DESC)"
+        }
+    }
+
+    /// 車両ごとの最新運行ログ
+    async fn fetch_latest(
+        conn: &mut sqlx::PgConnection,
+        filter: &str,
+    ) -> Result<Vec<DtakologModel>, Status> {
+        let source = Self::latest_source(&mut *conn).await;
+        sqlx::query_as::<_, DtakologModel>(&format!(
+            "SELECT d.* FROM {} d {} ORDER BY d.vehicle_cd ASC",
+            source, filter
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| Status::from(AppError::from(e)))
+    }
+
+    /// Parquet ファイルを書き、署名付き URL を付けて返す
+    async fn upload_parquet(
+        storage: &Arc<dyn StorageBackend>,
+        key: String,
+        writer: DtakologParquetWriter,
+        expires_in: Duration,
+    ) -> Result<ParquetFile, Status> {
+        let row_count = writer.rows() as i64;
+        let data = writer
+            .finish()
+            .map_err(|e| Status::internal(format!("Failed to write Parquet: {}", e)))?;
+        storage
+            .upload(&key, &data, "application/vnd.apache.parquet")
+            .await
+            .with_context(|| format!("exporting Parquet {}", key))
+            .map_err(Status::from)?;
+        let url = storage
+            .signed_url(&key, expires_in)
+            .await
+            .with_context(|| format!("signing Parquet {}", key))
+            .map_err(Status::from)?;
+        Ok(ParquetFile {
+            key,
+            url,
+            row_count,
+            size_bytes: data.len() as i64,
+        })
+    }
+
+    /// CreateBatch の行の検証
+    fn validate_batch_row(dtakolog: &Dtakolog) -> Result<(), Status> {
+        chrono::DateTime::parse_from_rfc3339(&dtakolog.data_date_time).map_err(|_| {
+            Status::invalid_argument(format!(
+                "data_date_time must be ISO8601: {:?}",
+                dtakolog.data_date_time
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// DTAKOLOG_INSERT_COLUMNS の順に値を bind
+    fn push_dtakolog<'args>(b: &mut Separated<'_, 'args, Postgres, &'static str>, d: &'args Dtakolog) {
+        b.push_bind(&d.data_date_time)
+            .push_bind(d.vehicle_cd)
+            .push_bind(&d.r#type)
+            .push_bind(d.all_state_font_color_index)
+            .push_bind(&d.all_state_ryout_color)
+            .push_bind(d.branch_cd)
+            .push_bind(&d.branch_name)
+            .push_bind(d.current_work_cd)
+            .push_bind(d.data_filter_type)
+            .push_bind(d.disp_flag)
+            .push_bind(d.driver_cd)
+            .push_bind(d.gps_direction)
+            .push_bind(d.gps_enable)
+            .push_bind(d.gps_latitude)
+            .push_bind(d.gps_longitude)
+            .push_bind(d.gps_satellite_num)
+            .push_bind(d.operation_state)
+            .push_bind(d.recive_event_type)
+            .push_bind(d.recive_packet_type)
+            .push_bind(d.recive_work_cd)
+            .push_bind(d.revo)
+            .push_bind(&d.setting_temp)
+            .push_bind(&d.setting_temp1)
+            .push_bind(&d.setting_temp3)
+            .push_bind(&d.setting_temp4)
+            .push_bind(d.speed)
+            .push_bind(d.sub_driver_cd)
+            .push_bind(d.temp_state)
+            .push_bind(&d.vehicle_name)
+            .push_bind(&d.address_disp_c)
+            .push_bind(&d.address_disp_p)
+            .push_bind(&d.all_state)
+            .push_bind(&d.all_state_ex)
+            .push_bind(&d.all_state_font_color)
+            .push_bind(&d.comu_date_time)
+            .push_bind(&d.current_work_name)
+            .push_bind(&d.driver_name)
+            .push_bind(&d.event_val)
+            .push_bind(&d.gps_lati_and_long)
+            .push_bind(&d.odometer)
+            .push_bind(&d.recive_type_color_name)
+            .push_bind(&d.recive_type_name)
+            .push_bind(&d.start_work_date_time)
+            .push_bind(&d.state)
+            .push_bind(&d.state1)
+            .push_bind(&d.state2)
+            .push_bind(&d.state3)
+            .push_bind(&d.state_flag)
+            .push_bind(&d.temp1)
+            .push_bind(&d.temp2)
+            .push_bind(&d.temp3)
+            .push_bind(&d.temp4)
+            .push_bind(&d.vehicle_icon_color)
+            .push_bind(&d.vehicle_icon_label_for_datetime)
+            .push_bind(&d.vehicle_icon_label_for_driver)
+            .push_bind(&d.vehicle_icon_label_for_vehicle);
+    }
+
+    /// 運行ログをまとめて UPSERT（1 トランザクション）。不正な行は読み飛ばして行ごとの結果を返す
+    async fn write_batch(
+        &self,
+        organization_id: &str,
+        dtakologs: &[Dtakolog],
+    ) -> Result<CreateDtakologBatchResponse, Status> {
+        // 不正な行は読み飛ばす。同じキーの行が複数あれば後の行を登録する（1 文で同じ行は 2 回更新できない）
+        let mut results: Vec<Option<tonic_types::Status>> = vec![None; dtakologs.len()];
+        let mut rows: HashMap<(&str, i32), usize> = HashMap::new();
+        for (i, dtakolog) in dtakologs.iter().enumerate() {
+            if let Err(e) = Self::validate_batch_row(dtakolog) {
+                results[i] = Some(rpc_status(&e));
+                continue;
+            }
+            if let Some(prev) = rows.insert((dtakolog.data_date_time.as_str(), dtakolog.vehicle_cd), i) {
+                results[prev] = Some(rpc_status(&Status::invalid_argument(format!(
+                    "Duplicate of row {} (vehicle_cd={}, data_date_time={})",
+                    i, dtakolog.vehicle_cd, dtakolog.data_date_time
+                ))));
+            }
+        }
+        let mut valid: Vec<usize> = rows.values().copied().collect();
+        valid.sort_unstable();
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let mut tx = conn.begin().await.map_err(AppError::from)?;
+        let mut inserted = 0;
+        let mut updated = 0;
+        let mut written: Vec<(usize, ChangeType)> = Vec::with_capacity(valid.len());
+        for chunk in valid.chunks(CREATE_BATCH_STATEMENT_ROWS) {
+            let mut qb = QueryBuilder::<Postgres>::new(format!(
+                "INSERT INTO dtakologs (organization_id, {}) ",
+                DTAKOLOG_INSERT_COLUMNS
+            ));
+            qb.push_values(chunk, |mut b, &i| {
+                b.push_bind(organization_id).push_unseparated("::uuid");
+                Self::push_dtakolog(&mut b, &dtakologs[i]);
+            });
+            qb.push(
+                " ON CONFLICT (organization_id, data_date_time, vehicle_cd) DO UPDATE SET ",
+            );
+            let mut set = qb.separated(", ");
+            for column in DTAKOLOG_INSERT_COLUMNS
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !matches!(*c, "data_date_time" | "vehicle_cd"))
+            {
+                set.push(format!("{c} = EXCLUDED.{c}", c = column));
+            }
+            qb.push(" RETURNING data_date_time, vehicle_cd, (xmax = 0) AS inserted");
+
+            let returned: Vec<(String, i32, bool)> = qb
+                .build_query_as()
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+            for (data_date_time, vehicle_cd, is_insert) in returned {
+                if let Some(&i) = rows.get(&(data_date_time.as_str(), vehicle_cd)) {
+                    results[i] = Some(ok_status());
+                    written.push((i, Self::change_type(is_insert)));
+                }
+                if is_insert {
+                    inserted += 1;
+                } else {
+                    updated += 1;
+                }
+            }
+        }
+
+        // 住所のない行は逆ジオコーディングで埋める（登録済みなら何もしない）
+        let missing_address = valid.iter().any(|&i| Self::needs_address(&dtakologs[i]));
+        if missing_address && self.geocoder.is_some() {
+            if let Err(e) = enqueue(&mut *tx, organization_id, backfill_job()).await {
+                tracing::warn!("Failed to enqueue geocode backfill: {}", e);
+            }
+        }
+        tx.commit().await.map_err(AppError::from)?;
+        self.evaluate_geofences(organization_id, written.iter().map(|&(i, _)| &dtakologs[i]))
+            .await;
+        self.publish(
+            organization_id,
+            written.into_iter().map(|(i, change_type)| (&dtakologs[i], change_type)),
+        );
+
+        let results: Vec<tonic_types::Status> = results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| rpc_status(&Status::internal("Row was not written"))))
+            .collect();
+        let failed = results.iter().filter(|r| r.code != tonic::Code::Ok as i32).count() as i32;
+
+        Ok(CreateDtakologBatchResponse {
+            inserted,
+            updated,
+            failed,
+            results,
+        })
+    }
+
+    /// IngestDtakologs のバッファを書き込み、集計に加える
+    async fn flush_ingest(
+        &self,
+        organization_id: &str,
+        buffer: &mut Vec<Dtakolog>,
+        summary: &mut IngestDtakologsResponse,
+    ) -> Result<(), Status> {
+        let rows = std::mem::take(buffer);
+        let response = self.write_batch(organization_id, &rows).await?;
+        summary.batches += 1;
+        summary.inserted += response.inserted;
+        summary.updated += response.updated;
+        summary.failed += response.failed;
+        for (dtakolog, status) in rows.iter().zip(&response.results) {
+            if status.code != tonic::Code::Ok as i32 && summary.errors.len() < MAX_INGEST_ERRORS {
+                summary.errors.push(format!(
+                    "vehicle_cd={}, date={}: {}",
+                    dtakolog.vehicle_cd, dtakolog.data_date_time, status.message
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// キーセット用ソートキー (data_date_time, vehicle_cd)
+    fn page_key(model: &DtakologModel) -> Vec<String> {
+        vec![model.data_date_time.clone(), model.vehicle_cd.to_string()]
+    }
+
     /// Convert YY/MM/DD HH:MM format to ISO 8601 format (2026-01-24T21:06:00+09:00)
     fn convert_to_iso8601(date_time: &str) -> Result<String, String> {
         // Expected format: "26/01/24 21:06"
@@ -61,20 +542,35 @@ impl DtakologsService for DtakologsServiceImpl {
     /// 全運行ログ取得
     async fn list_all(
         &self,
-        request: Request<Empty>,
+        request: Request<ListDtakologsRequest>,
     ) -> Result<Response<ListDtakologsResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
         tracing::info!("ListAll called for organization: {}", organization_id);
+        let paginator = Paginator::from_request(request.get_ref().pagination.as_ref())?;
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
+
+        if let Some(order) = OrderBy::parse(&request.get_ref().order_by, &DTAKOLOG_SORT_COLUMNS)? {
+            let mut qb = QueryBuilder::<Postgres>::new("SELECT dtakologs.* FROM dtakologs");
+            order.push_cursor_join(&mut qb, &paginator)?;
+            qb.push(" WHERE TRUE");
+            order.push_page(&mut qb, "dtakologs", &paginator);
+            let dtakologs = qb
+                .build_query_as::<DtakologModel>()
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+
+            let (dtakologs, pagination) = paginator.finish(dtakologs, Self::page_key);
+            return Ok(Response::new(ListDtakologsResponse {
+                dtakologs: dtakologs.iter().map(Self::model_to_proto).collect(),
+                pagination: Some(pagination),
+            }));
+        }
 
         let dtakologs = sqlx::query_as::<_, DtakologModel>(
             r#"
@@ -93,19 +589,25 @@ impl DtakologsService for DtakologsServiceImpl {
                 vehicle_icon_color, vehicle_icon_label_for_datetime,
                 vehicle_icon_label_for_driver, vehicle_icon_label_for_vehicle
             FROM dtakologs
-            ORDER BY data_date_time DESC
+            WHERE ($1::text IS NULL OR (data_date_time, vehicle_cd) < ($1, $2))
+            ORDER BY data_date_time DESC, vehicle_cd DESC
+            LIMIT $3
             "#,
         )
+        .bind(paginator.cursor(0))
+        .bind(paginator.cursor_as::<i32>(1)?)
+        .bind(paginator.limit())
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to fetch dtakologs: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (dtakologs, pagination) = paginator.finish(dtakologs, Self::page_key);
         let proto_dtakologs: Vec<Dtakolog> =
             dtakologs.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListDtakologsResponse {
             dtakologs: proto_dtakologs,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -119,38 +621,20 @@ impl DtakologsService for DtakologsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
+            .map_err(AppError::from)?;
 
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
-
-        // サブクエリでVehicleCD毎の最新DataDateTimeを取得してJOIN
-        let dtakologs = sqlx::query_as::<_, DtakologModel>(
-            r#"
-            SELECT d.*
-            FROM dtakologs d
-            INNER JOIN (
-                SELECT vehicle_cd, MAX(data_date_time) as max_data_date_time
-                FROM dtakologs
-                GROUP BY vehicle_cd
-            ) latest ON d.vehicle_cd = latest.vehicle_cd
-                     AND d.data_date_time = latest.max_data_date_time
-            ORDER BY d.vehicle_cd ASC
-            "#,
-        )
-        .fetch_all(&mut *conn)
-        .await
-        .map_err(|e| Status::internal(format!("Failed to fetch dtakologs: {}", e)))?;
+        let dtakologs = Self::fetch_latest(&mut conn, "").await?;
 
+        // 車両ごと最新1件のため件数は車両数で上限あり（ページングなし）
+        let (dtakologs, pagination) = Paginator::default().finish(dtakologs, Self::page_key);
         let proto_dtakologs: Vec<Dtakolog> =
             dtakologs.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListDtakologsResponse {
             dtakologs: proto_dtakologs,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -167,40 +651,21 @@ impl DtakologsService for DtakologsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
+            .map_err(AppError::from)?;
 
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
-
-        // サブクエリでVehicleCD毎の最新DataDateTimeを取得してJOIN
         // AddressDispPでフィルタ
-        let dtakologs = sqlx::query_as::<_, DtakologModel>(
-            r#"
-            SELECT d.*
-            FROM dtakologs d
-            INNER JOIN (
-                SELECT vehicle_cd, MAX(data_date_time) as max_data_date_time
-                FROM dtakologs
-                GROUP BY vehicle_cd
-            ) latest ON d.vehicle_cd = latest.vehicle_cd
-                     AND d.data_date_time = latest.max_data_date_time
-            WHERE d.address_disp_p LIKE '%本社営業所%'
-            ORDER BY d.vehicle_cd ASC
-            "#,
-        )
-        .fetch_all(&mut *conn)
-        .await
-        .map_err(|e| Status::internal(format!("Failed to fetch dtakologs: {}", e)))?;
+        let dtakologs = Self::fetch_latest(&mut conn, "WHERE d.address_disp_p LIKE '%本社営業所%'").await?;
 
+        // 車両ごと最新1件のため件数は車両数で上限あり（ページングなし）
+        let (dtakologs, pagination) = Paginator::default().finish(dtakologs, Self::page_key);
         let proto_dtakologs: Vec<Dtakolog> =
             dtakologs.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListDtakologsResponse {
             dtakologs: proto_dtakologs,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -212,113 +677,68 @@ impl DtakologsService for DtakologsServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
         tracing::info!(
-            "CurrentListSelect called for organization: {}, address_disp_p: {:?}, branch_cd: {:?}, vehicle_cds: {:?}",
+            "CurrentListSelect called for organization: {}, address_disp_p: {:?}, branch_cd: {:?}, driver_cd: {:?}, vehicle_cds: {:?}",
             organization_id,
             req.address_disp_p,
             req.branch_cd,
+            req.driver_cd,
             req.vehicle_cds
         );
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
-
-        // 動的クエリ構築
-        let mut conditions = Vec::new();
-        let mut params: Vec<String> = Vec::new();
-
-        if let Some(ref address) = req.address_disp_p {
-            conditions.push(format!("d.address_disp_p LIKE '%' || ${} || '%'", params.len() + 1));
-            params.push(address.clone());
+            .map_err(AppError::from)?;
+
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+        // 既定は車両CD順
+        let order_by = if req.order_by.trim().is_empty() { "vehicle_cd" } else { req.order_by.as_str() };
+        let order = OrderBy::parse(order_by, &DTAKOLOG_SORT_COLUMNS)?
+            .ok_or_else(|| Status::invalid_argument("Empty order_by"))?;
+
+        let source = Self::latest_source(&mut conn).await;
+        let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT d.* FROM {} d", source));
+        order.push_cursor_join(&mut qb, &paginator)?;
+        qb.push(" WHERE TRUE");
+        if let Some(address) = &req.address_disp_p {
+            qb.push(" AND strpos(d.address_disp_p, ").push_bind(address.clone()).push(") > 0");
         }
-
         if let Some(branch_cd) = req.branch_cd {
-            conditions.push(format!("d.branch_cd = ${}", params.len() + 1));
-            params.push(branch_cd.to_string());
+            qb.push(" AND d.branch_cd = ").push_bind(branch_cd);
+        }
+        if let Some(driver_cd) = req.driver_cd {
+            qb.push(" AND d.driver_cd = ").push_bind(driver_cd);
         }
-
         if !req.vehicle_cds.is_empty() {
-            let placeholders: Vec<String> = req
-                .vehicle_cds
-                .iter()
-                .enumerate()
-                .map(|(i, _)| format!("${}", params.len() + i + 1))
-                .collect();
-            conditions.push(format!("d.vehicle_cd IN ({})", placeholders.join(", ")));
-            for cd in &req.vehicle_cds {
-                params.push(cd.to_string());
-            }
+            qb.push(" AND d.vehicle_cd = ANY(").push_bind(req.vehicle_cds.clone()).push(")");
         }
+        // 最新運行ログの日時で絞り込む（ISO8601、空なら制限なし）
+        if !req.start_date_time.is_empty() {
+            qb.push(" AND d.data_date_time::timestamptz >= ")
+                .push_bind(req.start_date_time.clone())
+                .push("::timestamptz");
+        }
+        if !req.end_date_time.is_empty() {
+            qb.push(" AND d.data_date_time::timestamptz <= ")
+                .push_bind(req.end_date_time.clone())
+                .push("::timestamptz");
+        }
+        order.push_page(&mut qb, "d", &paginator);
 
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
-
-        // 動的クエリは将来的に使用予定
-        let _query = format!(
-            r#"
-            SELECT d.*
-            FROM dtakologs d
-            INNER JOIN (
-                SELECT vehicle_cd, MAX(data_date_time) as max_data_date_time
-                FROM dtakologs
-                GROUP BY vehicle_cd
-            ) latest ON d.vehicle_cd = latest.vehicle_cd
-                     AND d.data_date_time = latest.max_data_date_time
-            {}
-            ORDER BY d.vehicle_cd ASC
-            "#,
-            where_clause
-        );
-
-        // 動的バインドが複雑なため、シンプルにフィルタなしで取得してからフィルタ
-        let dtakologs = sqlx::query_as::<_, DtakologModel>(
-            r#"
-            SELECT d.*
-            FROM dtakologs d
-            INNER JOIN (
-                SELECT vehicle_cd, MAX(data_date_time) as max_data_date_time
-                FROM dtakologs
-                GROUP BY vehicle_cd
-            ) latest ON d.vehicle_cd = latest.vehicle_cd
-                     AND d.data_date_time = latest.max_data_date_time
-            ORDER BY d.vehicle_cd ASC
-            "#,
-        )
-        .fetch_all(&mut *conn)
-        .await
-        .map_err(|e| Status::internal(format!("Failed to fetch dtakologs: {}", e)))?;
-
-        // アプリケーション側でフィルタ
-        let filtered: Vec<DtakologModel> = dtakologs
-            .into_iter()
-            .filter(|d| {
-                let address_ok = req.address_disp_p.as_ref().map_or(true, |addr| {
-                    d.address_disp_p
-                        .as_ref()
-                        .map_or(false, |a| a.contains(addr))
-                });
-                let branch_ok = req.branch_cd.map_or(true, |b| d.branch_cd == b);
-                let vehicle_ok = req.vehicle_cds.is_empty()
-                    || req.vehicle_cds.contains(&d.vehicle_cd);
-                address_ok && branch_ok && vehicle_ok
-            })
-            .collect();
+        let dtakologs = qb
+            .build_query_as::<DtakologModel>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
 
+        let (dtakologs, pagination) = paginator.finish(dtakologs, Self::page_key);
         let proto_dtakologs: Vec<Dtakolog> =
-            filtered.iter().map(Self::model_to_proto).collect();
+            dtakologs.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListDtakologsResponse {
             dtakologs: proto_dtakologs,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -341,16 +761,13 @@ impl DtakologsService for DtakologsServiceImpl {
             Status::invalid_argument(format!("Invalid date_time format: {}. Expected YY/MM/DD HH:MM", e))
         })?;
         tracing::info!("Converted date_time: {} -> {}", req.date_time, iso_date_time);
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let dtakologs = if let Some(vehicle_cd) = req.vehicle_cd {
             sqlx::query_as::<_, DtakologModel>(
@@ -396,21 +813,26 @@ impl DtakologsService for DtakologsServiceImpl {
                     vehicle_icon_label_for_driver, vehicle_icon_label_for_vehicle
                 FROM dtakologs
                 WHERE data_date_time = $1
+                  AND ($2::int IS NULL OR vehicle_cd > $2)
                 ORDER BY vehicle_cd ASC
+                LIMIT $3
                 "#,
             )
             .bind(&iso_date_time)
+            .bind(paginator.cursor_as::<i32>(1)?)
+            .bind(paginator.limit())
             .fetch_all(&mut *conn)
             .await
         }
-        .map_err(|e| Status::internal(format!("Failed to fetch dtakologs: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (dtakologs, pagination) = paginator.finish(dtakologs, Self::page_key);
         let proto_dtakologs: Vec<Dtakolog> =
             dtakologs.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListDtakologsResponse {
             dtakologs: proto_dtakologs,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -428,16 +850,13 @@ impl DtakologsService for DtakologsServiceImpl {
             req.end_date_time,
             req.vehicle_cd
         );
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
 
         // Use TIMESTAMPTZ cast for proper timezone-aware comparison
         let dtakologs = if let Some(vehicle_cd) = req.vehicle_cd {
@@ -461,12 +880,16 @@ impl DtakologsService for DtakologsServiceImpl {
                 WHERE data_date_time::timestamptz >= $1::timestamptz
                   AND data_date_time::timestamptz <= $2::timestamptz
                   AND vehicle_cd = $3
+                  AND ($4::text IS NULL OR data_date_time < $4)
                 ORDER BY data_date_time DESC
+                LIMIT $5
                 "#,
             )
             .bind(&req.start_date_time)
             .bind(&req.end_date_time)
             .bind(vehicle_cd)
+            .bind(paginator.cursor(0))
+            .bind(paginator.limit())
             .fetch_all(&mut *conn)
             .await
         } else {
@@ -489,22 +912,28 @@ impl DtakologsService for DtakologsServiceImpl {
                 FROM dtakologs
                 WHERE data_date_time::timestamptz >= $1::timestamptz
                   AND data_date_time::timestamptz <= $2::timestamptz
-                ORDER BY data_date_time DESC
+                  AND ($3::text IS NULL OR (data_date_time, vehicle_cd) < ($3, $4))
+                ORDER BY data_date_time DESC, vehicle_cd DESC
+                LIMIT $5
                 "#,
             )
             .bind(&req.start_date_time)
             .bind(&req.end_date_time)
+            .bind(paginator.cursor(0))
+            .bind(paginator.cursor_as::<i32>(1)?)
+            .bind(paginator.limit())
             .fetch_all(&mut *conn)
             .await
         }
-        .map_err(|e| Status::internal(format!("Failed to fetch dtakologs: {}", e)))?;
+        .map_err(AppError::from)?;
 
+        let (dtakologs, pagination) = paginator.finish(dtakologs, Self::page_key);
         let proto_dtakologs: Vec<Dtakolog> =
             dtakologs.iter().map(Self::model_to_proto).collect();
 
         Ok(Response::new(ListDtakologsResponse {
             dtakologs: proto_dtakologs,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -528,15 +957,11 @@ impl DtakologsService for DtakologsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
 
-        sqlx::query(
+        let inserted: bool = sqlx::query_scalar(
             r#"
             INSERT INTO dtakologs (
                 organization_id, data_date_time, vehicle_cd, type,
@@ -623,6 +1048,7 @@ impl DtakologsService for DtakologsServiceImpl {
                 vehicle_icon_label_for_datetime = EXCLUDED.vehicle_icon_label_for_datetime,
                 vehicle_icon_label_for_driver = EXCLUDED.vehicle_icon_label_for_driver,
                 vehicle_icon_label_for_vehicle = EXCLUDED.vehicle_icon_label_for_vehicle
+            RETURNING (xmax = 0) AS inserted
             "#,
         )
         .bind(&organization_id)
@@ -682,15 +1108,97 @@ impl DtakologsService for DtakologsServiceImpl {
         .bind(&dtakolog.vehicle_icon_label_for_datetime)
         .bind(&dtakolog.vehicle_icon_label_for_driver)
         .bind(&dtakolog.vehicle_icon_label_for_vehicle)
-        .execute(&mut *conn)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to create dtakolog: {}", e)))?;
+        .map_err(AppError::from)?;
+        self.evaluate_geofences(&organization_id, [&dtakolog]).await;
+        self.publish(&organization_id, [(&dtakolog, Self::change_type(inserted))]);
 
         Ok(Response::new(CreateDtakologResponse {
             dtakolog: Some(dtakolog),
         }))
     }
 
+    /// 運行ログをまとめて登録（複数行 UPSERT、1 トランザクション）
+    async fn create_batch(
+        &self,
+        request: Request<CreateDtakologBatchRequest>,
+    ) -> Result<Response<CreateDtakologBatchResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        if req.dtakologs.len() > MAX_CREATE_BATCH_ROWS {
+            return Err(Status::invalid_argument(format!(
+                "Too many rows: {} (max {})",
+                req.dtakologs.len(),
+                MAX_CREATE_BATCH_ROWS
+            )));
+        }
+        tracing::info!(
+            "CreateBatch called for organization: {}, records: {}",
+            organization_id,
+            req.dtakologs.len()
+        );
+
+        let response = self.write_batch(&organization_id, &req.dtakologs).await?;
+        tracing::info!(
+            "CreateBatch completed: inserted={}, updated={}, failed={}",
+            response.inserted,
+            response.updated,
+            response.failed
+        );
+        Ok(Response::new(response))
+    }
+
+    /// 車載ゲートウェイからの連続取り込み（INGEST_FLUSH_ROWS 行または INGEST_FLUSH_INTERVAL ごとに書き込む）
+    async fn ingest_dtakologs(
+        &self,
+        request: Request<Streaming<Dtakolog>>,
+    ) -> Result<Response<IngestDtakologsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let mut stream = request.into_inner();
+        tracing::info!("IngestDtakologs started for organization: {}", organization_id);
+
+        let mut summary = IngestDtakologsResponse::default();
+        let mut buffer: Vec<Dtakolog> = Vec::with_capacity(INGEST_FLUSH_ROWS);
+        let mut ticker = tokio::time::interval(INGEST_FLUSH_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await; // 最初の tick はすぐ返る
+
+        loop {
+            tokio::select! {
+                message = stream.message() => match message? {
+                    Some(dtakolog) => {
+                        summary.received += 1;
+                        buffer.push(dtakolog);
+                        if buffer.len() >= INGEST_FLUSH_ROWS {
+                            self.flush_ingest(&organization_id, &mut buffer, &mut summary).await?;
+                            ticker.reset();
+                        }
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    if !buffer.is_empty() {
+                        self.flush_ingest(&organization_id, &mut buffer, &mut summary).await?;
+                    }
+                }
+            }
+        }
+        if !buffer.is_empty() {
+            self.flush_ingest(&organization_id, &mut buffer, &mut summary).await?;
+        }
+
+        tracing::info!(
+            "IngestDtakologs completed: received={}, inserted={}, updated={}, failed={}, batches={}",
+            summary.received,
+            summary.inserted,
+            summary.updated,
+            summary.failed,
+            summary.batches
+        );
+        Ok(Response::new(summary))
+    }
+
     /// 全運行ログ削除
     async fn delete_all(
         &self,
@@ -701,18 +1209,14 @@ impl DtakologsService for DtakologsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let result = sqlx::query("DELETE FROM dtakologs")
             .execute(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Failed to delete dtakologs: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let deleted_count = result.rows_affected() as i32;
 
@@ -748,19 +1252,17 @@ impl DtakologsService for DtakologsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let mut records_added = 0;
         let mut errors = Vec::new();
+        let mut missing_address = false;
+        let mut written: Vec<(Dtakolog, ChangeType)> = Vec::new();
 
         for dtakolog in req.dtakologs {
-            let result = sqlx::query(
+            let result: Result<bool, _> = sqlx::query_scalar(
                 r#"
                 INSERT INTO dtakologs (
                     organization_id, data_date_time, vehicle_cd, type,
@@ -847,6 +1349,7 @@ impl DtakologsService for DtakologsServiceImpl {
                     vehicle_icon_label_for_datetime = EXCLUDED.vehicle_icon_label_for_datetime,
                     vehicle_icon_label_for_driver = EXCLUDED.vehicle_icon_label_for_driver,
                     vehicle_icon_label_for_vehicle = EXCLUDED.vehicle_icon_label_for_vehicle
+                RETURNING (xmax = 0) AS inserted
                 "#,
             )
             .bind(&organization_id)
@@ -906,11 +1409,15 @@ impl DtakologsService for DtakologsServiceImpl {
             .bind(&dtakolog.vehicle_icon_label_for_datetime)
             .bind(&dtakolog.vehicle_icon_label_for_driver)
             .bind(&dtakolog.vehicle_icon_label_for_vehicle)
-            .execute(&mut *conn)
+            .fetch_one(&mut *conn)
             .await;
 
             match result {
-                Ok(_) => records_added += 1,
+                Ok(inserted) => {
+                    records_added += 1;
+                    missing_address |= Self::needs_address(&dtakolog);
+                    written.push((dtakolog, Self::change_type(inserted)));
+                }
                 Err(e) => {
                     errors.push(format!(
                         "vehicle_cd={}, date={}: {}",
@@ -920,6 +1427,15 @@ impl DtakologsService for DtakologsServiceImpl {
             }
         }
 
+        // 住所のない行は逆ジオコーディングで埋める（登録済みなら何もしない）
+        if missing_address && self.geocoder.is_some() {
+            if let Err(e) = enqueue(&mut conn, &organization_id, backfill_job()).await {
+                tracing::warn!("Failed to enqueue geocode backfill: {}", e);
+            }
+        }
+        self.evaluate_geofences(&organization_id, written.iter().map(|(d, _)| d)).await;
+        self.publish(&organization_id, written.iter().map(|(d, c)| (d, *c)));
+
         let success = errors.is_empty();
         let message = if success {
             format!("Successfully inserted {} records", records_added)
@@ -946,4 +1462,436 @@ impl DtakologsService for DtakologsServiceImpl {
             message,
         }))
     }
+
+    type StreamDtakologsStream = ReceiverStream<Result<Dtakolog, Status>>;
+
+    /// 運行ログをストリーミング取得
+    ///
+    /// 行をカーソルで読みながら送信するため、結果全体をメモリに載せない。
+    async fn stream_dtakologs(
+        &self,
+        request: Request<StreamDtakologsRequest>,
+    ) -> Result<Response<Self::StreamDtakologsStream>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        tracing::info!("StreamDtakologs called for organization: {}", organization_id);
+
+        let start = Some(req.start_date_time).filter(|s| !s.is_empty());
+        let end = Some(req.end_date_time).filter(|s| !s.is_empty());
+        let vehicle_cd = req.vehicle_cd;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        // 受信側が遅い場合は送信を待つ（バックプレッシャー）
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+        tokio::spawn(async move {
+            let mut rows = sqlx::query_as::<_, DtakologModel>(
+                r#"
+                SELECT
+                    data_date_time, vehicle_cd, type, all_state_font_color_index,
+                    all_state_ryout_color, branch_cd, branch_name, current_work_cd,
+                    data_filter_type, disp_flag, driver_cd, gps_direction, gps_enable,
+                    gps_latitude, gps_longitude, gps_satellite_num, operation_state,
+                    recive_event_type, recive_packet_type, recive_work_cd, revo,
+                    setting_temp, setting_temp1, setting_temp3, setting_temp4, speed,
+                    sub_driver_cd, temp_state, vehicle_name, address_disp_c, address_disp_p,
+                    all_state, all_state_ex, all_state_font_color, comu_date_time,
+                    current_work_name, driver_name, event_val, gps_lati_and_long, odometer,
+                    recive_type_color_name, recive_type_name, start_work_date_time, state,
+                    state1, state2, state3, state_flag, temp1, temp2, temp3, temp4,
+                    vehicle_icon_color, vehicle_icon_label_for_datetime,
+                    vehicle_icon_label_for_driver, vehicle_icon_label_for_vehicle
+                FROM dtakologs
+                WHERE ($1::text IS NULL OR data_date_time::timestamptz >= $1::timestamptz)
+                  AND ($2::text IS NULL OR data_date_time::timestamptz <= $2::timestamptz)
+                  AND ($3::int IS NULL OR vehicle_cd = $3)
+                ORDER BY data_date_time DESC, vehicle_cd DESC
+                "#,
+            )
+            .bind(start.as_deref())
+            .bind(end.as_deref())
+            .bind(vehicle_cd)
+            .fetch(&mut *conn);
+
+            while let Some(row) = rows.next().await {
+                let item = row
+                    .map(|m| Self::model_to_proto(&m))
+                    .map_err(|e| Status::from(AppError::from(e)));
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type WatchDtakologsStream = ReceiverStream<Result<DtakologEvent, Status>>;
+
+    /// 運行ログの書き込みを購読（vehicle_cds 指定時はその車両のみ）
+    async fn watch_dtakologs(
+        &self,
+        request: Request<WatchDtakologsRequest>,
+    ) -> Result<Response<Self::WatchDtakologsStream>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let vehicle_cds = request.into_inner().vehicle_cds;
+        tracing::info!("WatchDtakologs called for organization: {}", organization_id);
+
+        Ok(Response::new(watch_stream(
+            &self.events,
+            organization_id,
+            None,
+            move |event| match &event.change {
+                EntityChange::Dtakolog(dtakolog)
+                    if vehicle_cds.is_empty() || vehicle_cds.contains(&dtakolog.vehicle_cd) =>
+                {
+                    Some(DtakologEvent {
+                        change_type: event.change_type as i32,
+                        dtakolog: Some(dtakolog.clone()),
+                    })
+                }
+                _ => None,
+            },
+        )))
+    }
+
+    async fn reverse_geocode(
+        &self,
+        request: Request<ReverseGeocodeRequest>,
+    ) -> Result<Response<ReverseGeocodeResponse>, Status> {
+        let geocoder = self.geocoder()?;
+        let req = request.into_inner();
+        let point = match (req.gps_latitude, req.gps_longitude) {
+            (Some(lat_ms), Some(lon_ms)) => GeoPoint::from_dtako(lat_ms, lon_ms),
+            _ => GeoPoint::new(req.latitude, req.longitude),
+        }
+        .ok_or_else(|| Status::invalid_argument("Invalid GPS coordinates"))?;
+
+        let resolved = geocoder
+            .reverse(point)
+            .await
+            .map_err(|e| Status::unavailable(format!("Reverse geocoding failed: {}", e)))?;
+        Ok(Response::new(ReverseGeocodeResponse {
+            address: resolved.address,
+            cached: resolved.cached,
+        }))
+    }
+
+    async fn backfill_addresses(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<BackfillAddressesResponse>, Status> {
+        self.geocoder()?;
+        let organization_id = get_organization_from_request(&request);
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let queued = enqueue(&mut conn, &organization_id, backfill_job())
+            .await
+            .map_err(AppError::from)?
+            .is_some();
+        Ok(Response::new(BackfillAddressesResponse {
+            queued,
+            message: if queued {
+                "Address backfill queued".to_string()
+            } else {
+                "Address backfill is already queued".to_string()
+            },
+        }))
+    }
+
+    async fn get_vehicle_utilization(
+        &self,
+        request: Request<GetVehicleUtilizationRequest>,
+    ) -> Result<Response<GetVehicleUtilizationResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let (from, to) = parse_period(&req.from_date, &req.to_date)?;
+        let days_in_period = (to - from).num_days() + 1;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows = vehicle_utilization(&mut conn, from, to)
+            .await
+            .map_err(AppError::from)?;
+
+        let vehicles: Vec<VehicleUtilization> = rows
+            .into_iter()
+            .map(|v| VehicleUtilization {
+                vehicle_cd: v.vehicle_cd,
+                vehicle_name: v.vehicle_name,
+                days_in_use: v.days_in_use as i32,
+                utilization_rate: v.days_in_use as f64 / days_in_period as f64,
+                distance_km: v.distance_km,
+                idle_ratio: v.idle_ratio,
+                log_count: v.log_count,
+            })
+            .collect();
+        let total_distance_km = vehicles.iter().filter_map(|v| v.distance_km).sum();
+        let average_utilization_rate = if vehicles.is_empty() {
+            0.0
+        } else {
+            vehicles.iter().map(|v| v.utilization_rate).sum::<f64>() / vehicles.len() as f64
+        };
+
+        Ok(Response::new(GetVehicleUtilizationResponse {
+            vehicles,
+            days_in_period: days_in_period as i32,
+            total_distance_km,
+            average_utilization_rate,
+        }))
+    }
+
+    /// 車両の走行軌跡（期間内の GPS 点を時刻順、tolerance_m 指定時は間引く）
+    async fn get_vehicle_track(
+        &self,
+        request: Request<GetVehicleTrackRequest>,
+    ) -> Result<Response<GetVehicleTrackResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let start = parse_track_time(&req.start_date_time, "start_date_time")?;
+        let end = parse_track_time(&req.end_date_time, "end_date_time")?;
+        if start > end {
+            return Err(Status::invalid_argument("start_date_time must not be after end_date_time"));
+        }
+        if end - start > chrono::Duration::days(MAX_TRACK_DAYS) {
+            return Err(Status::invalid_argument(format!(
+                "Period must be at most {} days",
+                MAX_TRACK_DAYS
+            )));
+        }
+        let tolerance_m = req.tolerance_m.unwrap_or(0.0);
+        if !(0.0..=MAX_TRACK_TOLERANCE_M).contains(&tolerance_m) {
+            return Err(Status::invalid_argument(format!(
+                "tolerance_m must be between 0 and {}",
+                MAX_TRACK_TOLERANCE_M
+            )));
+        }
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows: Vec<TrackRow> = sqlx::query_as(
+            r#"
+            SELECT data_date_time, gps_latitude, gps_longitude, speed, gps_direction
+            FROM dtakologs
+            WHERE vehicle_cd = $1
+              AND data_date_time::timestamptz >= $2::timestamptz
+              AND data_date_time::timestamptz <= $3::timestamptz
+            ORDER BY data_date_time::timestamptz
+            "#,
+        )
+        .bind(req.vehicle_cd)
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let track = build_track(req.vehicle_cd, rows, tolerance_m);
+        tracing::info!(
+            "GetVehicleTrack: vehicle_cd={}, points={}/{}",
+            req.vehicle_cd,
+            track.points.len(),
+            track.raw_point_count
+        );
+        Ok(Response::new(track))
+    }
+
+    /// 行をカーソルで読みながら row group ごとに書くため、結果全体をメモリに載せない（ファイル単位では載る）。
+    async fn export_dtakologs_parquet(
+        &self,
+        request: Request<ExportDtakologsParquetRequest>,
+    ) -> Result<Response<ExportDtakologsParquetResponse>, Status> {
+        let storage = self
+            .storage
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Storage backend is not configured"))?;
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let (from, to) = parse_period(&req.from_date, &req.to_date)?;
+        let minutes = req.expires_in_minutes.unwrap_or(DEFAULT_EXPORT_URL_MINUTES);
+        if !(1..=MAX_EXPORT_URL_MINUTES).contains(&minutes) {
+            return Err(Status::invalid_argument(format!(
+                "expires_in_minutes must be between 1 and {}",
+                MAX_EXPORT_URL_MINUTES
+            )));
+        }
+        let expires_in = Duration::from_secs(minutes as u64 * 60);
+        let (start, end) = jst_range(from, to);
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let export_id = Uuid::new_v4().to_string();
+        let file_key = |part: usize| {
+            format!(
+                "{}/exports/dtakologs/{}/part-{:04}.parquet",
+                organization_id, export_id, part
+            )
+        };
+        let new_writer = || {
+            DtakologParquetWriter::new().map_err(|e| Status::internal(format!("Failed to write Parquet: {}", e)))
+        };
+
+        let mut rows = sqlx::query_as::<_, DtakologModel>(
+            r#"
+            SELECT
+                data_date_time, vehicle_cd, type, all_state_font_color_index,
+                all_state_ryout_color, branch_cd, branch_name, current_work_cd,
+                data_filter_type, disp_flag, driver_cd, gps_direction, gps_enable,
+                gps_latitude, gps_longitude, gps_satellite_num, operation_state,
+                recive_event_type, recive_packet_type, recive_work_cd, revo,
+                setting_temp, setting_temp1, setting_temp3, setting_temp4, speed,
+                sub_driver_cd, temp_state, vehicle_name, address_disp_c, address_disp_p,
+                all_state, all_state_ex, all_state_font_color, comu_date_time,
+                current_work_name, driver_name, event_val, gps_lati_and_long, odometer,
+                recive_type_color_name, recive_type_name, start_work_date_time, state,
+                state1, state2, state3, state_flag, temp1, temp2, temp3, temp4,
+                vehicle_icon_color, vehicle_icon_label_for_datetime,
+                vehicle_icon_label_for_driver, vehicle_icon_label_for_vehicle
+            FROM dtakologs
+            WHERE data_date_time::timestamptz >= $1::timestamptz
+              AND data_date_time::timestamptz < $2::timestamptz
+              AND (cardinality($3::int[]) = 0 OR vehicle_cd = ANY($3))
+            ORDER BY data_date_time, vehicle_cd
+            "#,
+        )
+        .bind(&start)
+        .bind(&end)
+        .bind(&req.vehicle_cds)
+        .fetch(&mut *conn);
+
+        let mut files = Vec::new();
+        let mut writer = new_writer()?;
+        let mut buffer = Vec::with_capacity(PARQUET_ROW_GROUP_ROWS);
+        let mut total_rows = 0i64;
+        while let Some(row) = rows.next().await {
+            let row = row.map_err(AppError::from)?;
+            buffer.push(row);
+            total_rows += 1;
+            if buffer.len() < PARQUET_ROW_GROUP_ROWS {
+                continue;
+            }
+            writer
+                .write(&buffer)
+                .map_err(|e| Status::internal(format!("Failed to write Parquet: {}", e)))?;
+            buffer.clear();
+            if writer.rows() >= PARQUET_FILE_ROWS {
+                let full = std::mem::replace(&mut writer, new_writer()?);
+                let key = file_key(files.len());
+                files.push(Self::upload_parquet(storage, key, full, expires_in).await?);
+            }
+        }
+        drop(rows);
+        writer
+            .write(&buffer)
+            .map_err(|e| Status::internal(format!("Failed to write Parquet: {}", e)))?;
+        // 0 行でもスキーマだけのファイルを1つ返す（glob で読む側がエラーにならないように）
+        if writer.rows() > 0 || files.is_empty() {
+            let key = file_key(files.len());
+            files.push(Self::upload_parquet(storage, key, writer, expires_in).await?);
+        }
+
+        tracing::info!(
+            "Exported {} dtakologs to {} Parquet files (organization {}, export {})",
+            total_rows,
+            files.len(),
+            organization_id,
+            export_id
+        );
+        Ok(Response::new(ExportDtakologsParquetResponse {
+            export_id,
+            files,
+            total_rows,
+            expires_at: (Utc::now() + chrono::Duration::minutes(minutes as i64)).to_rfc3339(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dtakolog(vehicle_cd: i32, data_date_time: &str) -> Dtakolog {
+        Dtakolog {
+            vehicle_cd,
+            data_date_time: data_date_time.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn track_row(data_date_time: &str, lat: f64, lon: f64) -> TrackRow {
+        TrackRow {
+            data_date_time: data_date_time.to_string(),
+            gps_latitude: (lat * 3_600_000.0).round() as i32,
+            gps_longitude: (lon * 3_600_000.0).round() as i32,
+            speed: 0.0,
+            gps_direction: 0,
+        }
+    }
+
+    #[test]
+    fn build_track_skips_invalid_points_and_computes_aggregates() {
+        let rows = vec![
+            track_row("2026-01-24T10:00:00+09:00", 35.0, 135.0),
+            track_row("2026-01-24T10:15:00+09:00", 0.0, 0.0),
+            track_row("2026-01-24T10:30:00+09:00", 35.05, 135.0),
+            track_row("2026-01-24T11:00:00+09:00", 35.1, 135.0),
+        ];
+        let track = build_track(7, rows, 10.0);
+        assert_eq!(track.raw_point_count, 3);
+        // 直線上の中間点は間引かれる
+        assert_eq!(track.points.len(), 2);
+        assert_eq!(track.points[1].data_date_time, "2026-01-24T11:00:00+09:00");
+        assert!((track.distance_km - 11.12).abs() < 0.05, "{}", track.distance_km);
+        let speed = track.average_speed_kmh.unwrap();
+        assert!((speed - track.distance_km).abs() < 1e-9);
+
+        let single = build_track(7, vec![track_row("2026-01-24T10:00:00+09:00", 35.0, 135.0)], 0.0);
+        assert_eq!(single.points.len(), 1);
+        assert_eq!(single.distance_km, 0.0);
+        assert!(single.average_speed_kmh.is_none());
+    }
+
+    #[test]
+    fn latest_per_vehicle_keeps_newest_row() {
+        let rows = [
+            dtakolog(2, "2026-01-24T21:06:00+09:00"),
+            dtakolog(1, "2026-01-24T21:07:00+09:00"),
+            dtakolog(2, "2026-01-24T21:08:00+09:00"),
+            dtakolog(2, "2026-01-24T21:07:00+09:00"),
+        ];
+        let latest = latest_per_vehicle([
+            (&rows[0], ChangeType::Created),
+            (&rows[1], ChangeType::Updated),
+            (&rows[2], ChangeType::Created),
+            (&rows[3], ChangeType::Updated),
+        ]);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].0.vehicle_cd, 1);
+        assert_eq!(latest[0].1, ChangeType::Updated);
+        assert_eq!(latest[1].0.data_date_time, "2026-01-24T21:08:00+09:00");
+        assert_eq!(latest[1].1, ChangeType::Created);
+    }
 }
@@ -1,57 +1,128 @@
 use std::sync::Arc;
 
-use sqlx::PgPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, PgConnection, PgPool};
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
 use crate::config::Config;
-use crate::db::{get_organization_from_request, set_current_organization};
+use crate::db::{get_organization_from_request, OrgScopedPool, Paginator};
+use crate::error::AppError;
 use crate::http_client::HttpClient;
+use crate::ingest::{generate_ingest_token, hash_ingest_token};
+use crate::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::middleware::AuthenticatedUser;
+use crate::models::DvrNotificationModel;
+use crate::notifications::{Notification, Notifier, DVR_ALERT};
+use crate::outbox::{self, Outbox, OutboxEvent};
+use crate::proto::common::Empty;
 use crate::proto::dvr_notifications::dvr_notifications_service_server::DvrNotificationsService;
 use crate::proto::dvr_notifications::{
-    BulkCreateDvrNotificationsRequest, BulkCreateDvrNotificationsResponse, DvrNotification,
-    RetryPendingDownloadsRequest, RetryPendingDownloadsResponse,
+    AcknowledgeDvrNotificationsRequest, AcknowledgeDvrNotificationsResponse,
+    BulkCreateDvrNotificationsRequest, BulkCreateDvrNotificationsResponse,
+    CreateDvrIngestTokenRequest, CreateDvrIngestTokenResponse, DeleteDvrIngestTokenRequest,
+    DvrIngestToken, DvrNotification, ListDvrIngestTokensRequest, ListDvrIngestTokensResponse,
+    ListDvrNotificationsRequest, ListDvrNotificationsResponse, RetryPendingDownloadsRequest,
+    RetryPendingDownloadsResponse,
 };
 use crate::storage::StorageBackend;
+use crate::weather::DvrWeatherPayload;
+
+/// dvr_event_severity() が返す重要度
+const SEVERITIES: &[&str] = &["critical", "warning", "info"];
+
+const DVR_COLUMNS: &str = "mp4_url, vehicle_cd, vehicle_name, serial_no, file_name, event_type, dvr_datetime, \
+     driver_name, gcs_key, file_size_bytes, download_status, id, severity, acknowledged_at, \
+     acknowledged_by::text AS acknowledged_by, acknowledgement_note, file_uuid::text AS file_uuid, archived_at, \
+     download_error, weather, created_at";
+
+const INGEST_TOKEN_COLUMNS: &str = "id, name, token_hint, last_used_at, created_at";
+
+/// dvr_ingest_tokens の1行（ハッシュは返さない）
+#[derive(Debug, sqlx::FromRow)]
+struct IngestTokenRow {
+    id: Uuid,
+    name: String,
+    token_hint: String,
+    last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl IngestTokenRow {
+    fn to_proto(&self) -> DvrIngestToken {
+        DvrIngestToken {
+            id: self.id.to_string(),
+            name: self.name.clone(),
+            token_hint: self.token_hint.clone(),
+            last_used_at: self.last_used_at.map(|t| t.to_rfc3339()),
+            created_at: self.created_at.to_rfc3339(),
+        }
+    }
+}
 
 pub struct DvrNotificationsServiceImpl {
     pool: PgPool,
     config: Config,
-    http_client: Arc<HttpClient>,
     storage: Option<Arc<dyn StorageBackend>>,
+    outbox: Outbox,
+    notifier: Notifier,
 }
 
 impl DvrNotificationsServiceImpl {
     pub fn new(
         pool: PgPool,
         config: Config,
-        http_client: Arc<HttpClient>,
         storage: Option<Arc<dyn StorageBackend>>,
+        outbox: Outbox,
+        notifier: Notifier,
     ) -> Self {
         Self {
             pool,
             config,
-            http_client,
             storage,
+            outbox,
+            notifier,
         }
     }
 
-    /// Send LINE WORKS notification via lineworks-bot-rust
-    async fn send_line_notification(&self, notification: &DvrNotification) -> Result<(), String> {
-        let bot_url = match &self.config.dvr_lineworks_bot_url {
-            Some(url) => url,
-            None => {
-                tracing::debug!("LINE WORKS bot URL not configured, skipping notification");
-                return Ok(());
-            }
-        };
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
 
-        if !self.config.dvr_notification_enabled {
-            tracing::debug!("DVR notifications disabled, skipping LINE notification");
-            return Ok(());
+    /// 管理者確認 + organization 設定済みのコネクション
+    async fn admin_conn<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(AuthenticatedUser, sqlx::pool::PoolConnection<sqlx::Postgres>), Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(&auth_user.user_id)
+        .bind(&auth_user.org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+        match role {
+            Some((r,)) if r == "admin" => {}
+            Some(_) => return Err(Status::permission_denied("Admin role required")),
+            None => return Err(Status::permission_denied("Not a member of this organization")),
         }
 
-        // Build message text for LINE WORKS
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok((auth_user, conn))
+    }
+
+    /// LINE WORKS 通知イベント（outbox 経由で lineworks-bot-rust に送る）
+    fn alert_event(notification: &DvrNotification) -> OutboxEvent {
         let message = format!(
             "【DVR通知】\n車両: {} ({})\n運転手: {}\nイベント: {}\n日時: {}\nシリアル: {}\nファイル: {}\n動画URL: {}",
             notification.vehicle_name,
@@ -63,35 +134,172 @@ impl DvrNotificationsServiceImpl {
             notification.file_name,
             notification.mp4_url
         );
+        OutboxEvent::new(
+            outbox::DVR_ALERT,
+            serde_json::json!({
+                "mp4_url": notification.mp4_url,
+                "vehicle_cd": notification.vehicle_cd,
+                "event_type": notification.event_type,
+                "dvr_datetime": notification.dvr_datetime,
+            }),
+        )
+        .message(message)
+    }
+
+    /// LINE WORKS 連携済みメンバー、Slack / Discord Webhook、アプリ内通知（全メンバー）
+    fn alert_notification(notification: &DvrNotification) -> Notification {
+        Notification::new(DVR_ALERT)
+            .var("vehicle_name", &notification.vehicle_name)
+            .var("vehicle_cd", notification.vehicle_cd)
+            .var("driver_name", &notification.driver_name)
+            .var("event_type", &notification.event_type)
+            .var("dvr_datetime", &notification.dvr_datetime)
+            .var("mp4_url", &notification.mp4_url)
+    }
+
+    /// 通知レコードと LINE WORKS 通知（outbox / メンバー個別・Webhook・アプリ内・SMS）、mp4 ダウンロード・気象 job を同じトランザクションで書く
+    async fn insert_with_alert(
+        &self,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        notification: &DvrNotification,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = conn.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO dvr_notifications (
+                organization_id, mp4_url, vehicle_cd, vehicle_name,
+                serial_no, file_name, event_type, dvr_datetime, driver_name
+            ) VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(organization_id)
+        .bind(&notification.mp4_url)
+        .bind(notification.vehicle_cd)
+        .bind(&notification.vehicle_name)
+        .bind(&notification.serial_no)
+        .bind(&notification.file_name)
+        .bind(&notification.event_type)
+        .bind(&notification.dvr_datetime)
+        .bind(&notification.driver_name)
+        .execute(&mut *tx)
+        .await?;
 
-        // Call lineworks-bot-rust API
-        let payload = serde_json::json!({
-            "test": "sendTextMessageLine",
-            "message": message
-        });
+        if self.config.dvr_notification_enabled {
+            self.outbox
+                .write(&mut tx, organization_id, &Self::alert_event(notification))
+                .await?;
+            let members = Notifier::lineworks_recipients(&mut tx, organization_id).await?;
+            let webhooks = Notifier::webhook_recipients(&mut tx, organization_id).await?;
+            let feed = Notifier::in_app_recipients(&mut tx, organization_id, false).await?;
+            // 緊急通知として SMS も（組織の月間上限の範囲で）
+            let sms = Notifier::sms_recipients(&mut tx, organization_id).await?;
+            let alert = Self::alert_notification(notification)
+                .to_all(members)
+                .to_all(webhooks)
+                .to_all(feed)
+                .to_all(sms);
+            self.notifier.send(&mut tx, organization_id, &alert).await?;
+        }
+        if self.storage.is_some() {
+            enqueue(&mut tx, organization_id, Mp4DownloadPayload::job(&notification.mp4_url)).await?;
+        }
+        if self.config.weather.is_some() {
+            enqueue(&mut tx, organization_id, DvrWeatherPayload::job(&notification.mp4_url)).await?;
+        }
+        tx.commit().await
+    }
 
-        let api_url = format!("{}/api/tasks", bot_url.trim_end_matches('/'));
+    /// 重複（mp4_url）を除いて保存し、通知・mp4 ダウンロードを登録する（BulkCreate と Webhook 取り込みで共通）
+    pub async fn create_notifications(
+        &self,
+        organization_id: &str,
+        notifications: Vec<DvrNotification>,
+    ) -> Result<BulkCreateDvrNotificationsResponse, Status> {
+        let total_records = notifications.len() as i32;
 
-        match self.http_client.post_json(&api_url, &payload).await {
-            Ok(response) => {
-                if response.status().is_success() {
+        tracing::info!(
+            "Creating DVR notifications for organization: {}, records: {}",
+            organization_id,
+            total_records
+        );
+
+        if notifications.is_empty() {
+            return Ok(BulkCreateDvrNotificationsResponse {
+                success: true,
+                records_added: 0,
+                total_records: 0,
+                message: "No records to insert".to_string(),
+            });
+        }
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let mut records_added = 0;
+        let mut errors = Vec::new();
+
+        for notification in notifications {
+            // Check if mp4_url already exists
+            let exists = self
+                .exists(&mut conn, &notification.mp4_url)
+                .await
+                .map_err(AppError::from)?;
+
+            if exists {
+                tracing::debug!(
+                    "DVR notification already exists, skipping: mp4_url={}",
+                    notification.mp4_url
+                );
+                continue;
+            }
+
+            // Insert new record (+ LINE WORKS notification via outbox, mp4 download job)
+            let result = self
+                .insert_with_alert(&mut conn, organization_id, &notification)
+                .await;
+
+            match result {
+                Ok(_) => {
+                    records_added += 1;
                     tracing::info!(
-                        "LINE notification sent for DVR: mp4_url={}",
-                        notification.mp4_url
+                        "DVR notification created: mp4_url={}, vehicle={}",
+                        notification.mp4_url,
+                        notification.vehicle_name
                     );
-                    Ok(())
-                } else {
-                    let status = response.status();
-                    let body = response.text().await.unwrap_or_default();
-                    tracing::error!("LINE notification failed: {} - {}", status, body);
-                    Err(format!("LINE notification failed: {}", status))
                 }
-            }
-            Err(e) => {
-                tracing::error!("Failed to send LINE notification: {}", e);
-                Err(format!("Failed to send LINE notification: {}", e))
+                Err(e) => {
+                    let error_msg = format!("mp4_url={}: {}", notification.mp4_url, e);
+                    tracing::error!("Failed to insert DVR notification: {}", error_msg);
+                    errors.push(error_msg);
+                }
             }
         }
+
+        let success = errors.is_empty();
+        let message = if success {
+            format!(
+                "Inserted {} new records out of {} total",
+                records_added, total_records
+            )
+        } else {
+            format!(
+                "Inserted {} records with {} errors: {}",
+                records_added,
+                errors.len(),
+                errors.join("; ")
+            )
+        };
+
+        Ok(BulkCreateDvrNotificationsResponse {
+            success,
+            records_added,
+            total_records,
+            message,
+        })
     }
 
     /// Check if a notification with the given mp4_url already exists
@@ -105,57 +313,135 @@ impl DvrNotificationsServiceImpl {
 
         Ok(result.is_some())
     }
+}
+
+/// DVR 動画（mp4）をダウンロードしてストレージに保存する job
+pub const MP4_DOWNLOAD_JOB: &str = "dvr.mp4_download";
+/// 組織ごとの実行待ちがこれを超えたら後続を遅らせる
+const MP4_DOWNLOAD_BACKLOG_LIMIT: i64 = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Mp4DownloadPayload {
+    pub mp4_url: String,
+}
+
+impl Mp4DownloadPayload {
+    pub fn job(mp4_url: &str) -> NewJob {
+        NewJob::new(MP4_DOWNLOAD_JOB, Self { mp4_url: mp4_url.to_string() })
+            .dedupe_key(mp4_url)
+            .backlog_limit(MP4_DOWNLOAD_BACKLOG_LIMIT)
+    }
+}
+
+/// mp4 ダウンロード job ハンドラ
+pub struct Mp4DownloadJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    http_client: Arc<HttpClient>,
+}
+
+impl Mp4DownloadJobHandler {
+    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>, http_client: Arc<HttpClient>) -> Self {
+        Self { pool, storage, http_client }
+    }
+}
 
-    /// Spawn background task to download mp4 and store to object storage
-    fn spawn_mp4_download(&self, mp4_url: String, organization_id: String) {
+#[tonic::async_trait]
+impl JobHandler for Mp4DownloadJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: Mp4DownloadPayload = job.payload()?;
         let Some(storage) = self.storage.clone() else {
             tracing::debug!("Storage backend not configured, skipping mp4 download");
-            return;
+            return Ok(());
         };
-
-        let pool = self.pool.clone();
-        let http_client = self.http_client.clone();
-
-        tokio::spawn(async move {
-            if let Err(e) = download_and_store_mp4(
-                pool,
-                storage,
-                http_client,
-                mp4_url.clone(),
-                organization_id,
-            )
-            .await
-            {
-                tracing::warn!("Failed to download/store mp4 {}: {}", mp4_url, e);
+        let result = archive_mp4(
+            &self.pool,
+            storage,
+            &self.http_client,
+            &payload.mp4_url,
+            &job.organization_id,
+        )
+        .await;
+
+        if let Err(e) = &result {
+            // 最終試行の失敗だけ記録する（途中の失敗は worker が再試行する）
+            if job.attempts >= job.max_attempts {
+                if let Err(db_err) =
+                    mark_download_failed(&self.pool, &job.organization_id, &payload.mp4_url, e).await
+                {
+                    tracing::error!("Failed to mark mp4 download failed {}: {}", payload.mp4_url, db_err);
+                }
             }
-        });
+        }
+        result.map_err(|e| anyhow::anyhow!("Failed to download/store mp4 {}: {}", payload.mp4_url, e))
     }
 }
 
-/// Download mp4 from external URL and store to object storage
-async fn download_and_store_mp4(
-    pool: PgPool,
+/// 通知が参照する mp4 を保存済みかどうか（再実行時に二重保存しない）
+async fn is_archived(conn: &mut PgConnection, organization_id: &str, mp4_url: &str) -> Result<bool, sqlx::Error> {
+    let row: Option<(bool,)> = sqlx::query_as(
+        r#"
+        SELECT file_uuid IS NOT NULL FROM dvr_notifications
+        WHERE organization_id = $1::uuid AND mp4_url = $2
+        "#,
+    )
+    .bind(organization_id)
+    .bind(mp4_url)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.map(|(archived,)| archived).unwrap_or(false))
+}
+
+/// mp4 を DVR の URL から取得してストレージに保存し、files に登録して通知に紐づける
+async fn archive_mp4(
+    pool: &PgPool,
     storage: Arc<dyn StorageBackend>,
-    http_client: Arc<HttpClient>,
-    mp4_url: String,
-    organization_id: String,
+    http_client: &HttpClient,
+    mp4_url: &str,
+    organization_id: &str,
 ) -> Result<(), String> {
+    let mut conn = pool
+        .acquire_scoped(organization_id)
+        .await
+        .map_err(|e| format!("DB connection failed: {}", e))?;
+    if is_archived(&mut conn, organization_id, mp4_url)
+        .await
+        .map_err(|e| format!("DB query failed: {}", e))?
+    {
+        tracing::debug!("mp4 already archived: {}", mp4_url);
+        return Ok(());
+    }
+    drop(conn);
+
     tracing::info!("Starting mp4 download: {}", mp4_url);
 
     // 1. Download mp4 from external URL
     let response = http_client
-        .get(&mp4_url)
+        .get(mp4_url)
         .await
         .map_err(|e| format!("HTTP request failed: {}", e))?;
 
     if !response.status().is_success() {
         return Err(format!("HTTP error: {}", response.status()));
     }
+    // 期限切れの URL はエラーページ（HTML）を 200 で返すことがある
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("video/mp4")
+        .to_string();
+    if content_type.starts_with("text/") {
+        return Err(format!("Unexpected content type: {}", content_type));
+    }
 
     let data = response
         .bytes()
         .await
         .map_err(|e| format!("Failed to read response body: {}", e))?;
+    if data.is_empty() {
+        return Err("Empty response body".to_string());
+    }
 
     let file_size = data.len() as i64;
     tracing::info!("Downloaded mp4: {} bytes from {}", file_size, mp4_url);
@@ -168,36 +454,87 @@ async fn download_and_store_mp4(
     storage
         .upload(&gcs_key, &data, "video/mp4")
         .await
-        .map_err(|e| format!("Storage upload failed: {}", e))?;
+        .map_err(|e| format!("Storage upload failed: {}", e.report()))?;
 
     tracing::info!("Uploaded to storage: {}", gcs_key);
 
-    // 4. Update DB with gcs_key, file_size, and status
+    // 4. files に登録して通知に紐づける
+    let mut tx = pool
+        .begin_scoped(organization_id)
+        .await
+        .map_err(|e| format!("DB connection failed: {}", e))?;
+    sqlx::query(
+        r#"
+        INSERT INTO files (uuid, organization_id, filename, type, created_at, s3_key, storage_class, last_accessed_at, size_bytes)
+        SELECT $1, organization_id,
+               COALESCE(NULLIF(file_name, ''), $2),
+               'video/mp4', NOW(), $3, 'STANDARD', NOW(), $6
+        FROM dvr_notifications
+        WHERE organization_id = $4::uuid AND mp4_url = $5
+        "#,
+    )
+    .bind(uuid)
+    .bind(format!("{}.mp4", uuid))
+    .bind(&gcs_key)
+    .bind(organization_id)
+    .bind(mp4_url)
+    .bind(file_size)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("DB insert failed: {}", e))?;
     sqlx::query(
         r#"
         UPDATE dvr_notifications
-        SET gcs_key = $1, file_size_bytes = $2, download_status = 'completed'
-        WHERE organization_id = $3::uuid AND mp4_url = $4
+        SET gcs_key = $1, file_size_bytes = $2, download_status = 'completed',
+            file_uuid = $3, archived_at = NOW(), download_error = NULL
+        WHERE organization_id = $4::uuid AND mp4_url = $5
         "#,
     )
     .bind(&gcs_key)
     .bind(file_size)
-    .bind(&organization_id)
-    .bind(&mp4_url)
-    .execute(&pool)
+    .bind(uuid)
+    .bind(organization_id)
+    .bind(mp4_url)
+    .execute(&mut *tx)
     .await
     .map_err(|e| format!("DB update failed: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("DB commit failed: {}", e))?;
 
     tracing::info!(
-        "DVR mp4 stored: mp4_url={}, gcs_key={}, size={}",
+        "DVR mp4 stored: mp4_url={}, gcs_key={}, file_uuid={}, size={}",
         mp4_url,
         gcs_key,
+        uuid,
         file_size
     );
 
     Ok(())
 }
 
+async fn mark_download_failed(
+    pool: &PgPool,
+    organization_id: &str,
+    mp4_url: &str,
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    let mut conn = pool.acquire_scoped(organization_id).await?;
+    sqlx::query(
+        r#"
+        UPDATE dvr_notifications
+        SET download_status = 'failed', download_error = $1
+        WHERE organization_id = $2::uuid AND mp4_url = $3 AND file_uuid IS NULL
+        "#,
+    )
+    .bind(error)
+    .bind(organization_id)
+    .bind(mp4_url)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
 #[tonic::async_trait]
 impl DvrNotificationsService for DvrNotificationsServiceImpl {
     /// DVR通知一括作成
@@ -207,121 +544,8 @@ impl DvrNotificationsService for DvrNotificationsServiceImpl {
     ) -> Result<Response<BulkCreateDvrNotificationsResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
-        let total_records = req.notifications.len() as i32;
-
-        tracing::info!(
-            "BulkCreate DVR notifications called for organization: {}, records: {}",
-            organization_id,
-            total_records
-        );
-
-        if req.notifications.is_empty() {
-            return Ok(Response::new(BulkCreateDvrNotificationsResponse {
-                success: true,
-                records_added: 0,
-                total_records: 0,
-                message: "No records to insert".to_string(),
-            }));
-        }
-
-        let mut conn = self
-            .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
-
-        let mut records_added = 0;
-        let mut errors = Vec::new();
-
-        for notification in req.notifications {
-            // Check if mp4_url already exists
-            let exists = self
-                .exists(&mut conn, &notification.mp4_url)
-                .await
-                .map_err(|e| Status::internal(format!("Failed to check existence: {}", e)))?;
-
-            if exists {
-                tracing::debug!(
-                    "DVR notification already exists, skipping: mp4_url={}",
-                    notification.mp4_url
-                );
-                continue;
-            }
-
-            // Insert new record
-            let result = sqlx::query(
-                r#"
-                INSERT INTO dvr_notifications (
-                    organization_id, mp4_url, vehicle_cd, vehicle_name,
-                    serial_no, file_name, event_type, dvr_datetime, driver_name
-                ) VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8, $9)
-                "#,
-            )
-            .bind(&organization_id)
-            .bind(&notification.mp4_url)
-            .bind(notification.vehicle_cd)
-            .bind(&notification.vehicle_name)
-            .bind(&notification.serial_no)
-            .bind(&notification.file_name)
-            .bind(&notification.event_type)
-            .bind(&notification.dvr_datetime)
-            .bind(&notification.driver_name)
-            .execute(&mut *conn)
-            .await;
-
-            match result {
-                Ok(_) => {
-                    records_added += 1;
-                    tracing::info!(
-                        "DVR notification created: mp4_url={}, vehicle={}",
-                        notification.mp4_url,
-                        notification.vehicle_name
-                    );
-
-                    // Send LINE WORKS notification for the new record
-                    if let Err(e) = self.send_line_notification(&notification).await {
-                        tracing::warn!("LINE notification failed but record was saved: {}", e);
-                    }
-
-                    // Spawn background task to download mp4 and store to GCS
-                    self.spawn_mp4_download(
-                        notification.mp4_url.clone(),
-                        organization_id.clone(),
-                    );
-                }
-                Err(e) => {
-                    let error_msg = format!("mp4_url={}: {}", notification.mp4_url, e);
-                    tracing::error!("Failed to insert DVR notification: {}", error_msg);
-                    errors.push(error_msg);
-                }
-            }
-        }
-
-        let success = errors.is_empty();
-        let message = if success {
-            format!(
-                "Inserted {} new records out of {} total",
-                records_added, total_records
-            )
-        } else {
-            format!(
-                "Inserted {} records with {} errors: {}",
-                records_added,
-                errors.len(),
-                errors.join("; ")
-            )
-        };
-
-        Ok(Response::new(BulkCreateDvrNotificationsResponse {
-            success,
-            records_added,
-            total_records,
-            message,
-        }))
+        let response = self.create_notifications(&organization_id, req.notifications).await?;
+        Ok(Response::new(response))
     }
 
     /// ペンディング状態のmp4ダウンロードを再試行
@@ -346,25 +570,23 @@ impl DvrNotificationsService for DvrNotificationsServiceImpl {
         }
 
         // Set RLS context for this organization
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
-        // Fetch all pending records for this organization
-        let pending_records: Vec<(String, String)> = sqlx::query_as(
+        // Fetch all pending / failed records for this organization
+        let pending_records: Vec<(String,)> = sqlx::query_as(
             r#"
-            SELECT mp4_url, organization_id::text
+            SELECT mp4_url
             FROM dvr_notifications
             WHERE organization_id = $1::uuid
-              AND (download_status = 'pending' OR download_status IS NULL)
+              AND (download_status IN ('pending', 'failed') OR download_status IS NULL)
               AND mp4_url NOT LIKE 'https://example.com%'
             "#,
         )
         .bind(&organization_id)
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to fetch pending records: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let pending_count = pending_records.len() as i32;
 
@@ -376,16 +598,198 @@ impl DvrNotificationsService for DvrNotificationsServiceImpl {
             }));
         }
 
-        // Spawn download tasks for each pending record
-        for (mp4_url, org_id) in pending_records {
-            tracing::info!("Spawning download for pending mp4: {}", mp4_url);
-            self.spawn_mp4_download(mp4_url, org_id);
+        // Enqueue download jobs for each pending record (already queued ones are skipped)
+        let mut enqueued = 0;
+        for (mp4_url,) in pending_records {
+            if enqueue(&mut conn, &organization_id, Mp4DownloadPayload::job(&mp4_url))
+                .await
+                .map_err(AppError::from)?
+                .is_some()
+            {
+                tracing::info!("Enqueued download for pending mp4: {}", mp4_url);
+                enqueued += 1;
+            }
         }
 
         Ok(Response::new(RetryPendingDownloadsResponse {
             success: true,
             pending_count,
-            message: format!("Started {} pending downloads", pending_count),
+            message: format!("Queued {} of {} pending downloads", enqueued, pending_count),
+        }))
+    }
+
+    /// DVR通知一覧
+    async fn list_dvr_notifications(
+        &self,
+        request: Request<ListDvrNotificationsRequest>,
+    ) -> Result<Response<ListDvrNotificationsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        if let Some(unknown) = req.severities.iter().find(|s| !SEVERITIES.contains(&s.as_str())) {
+            return Err(Status::invalid_argument(format!(
+                "Unknown severity: {} (available: {})",
+                unknown,
+                SEVERITIES.join(", ")
+            )));
+        }
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let notifications: Vec<DvrNotificationModel> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM dvr_notifications
+            WHERE ($1::bigint IS NULL OR vehicle_cd = $1)
+              AND (cardinality($2::text[]) = 0 OR severity = ANY($2))
+              AND ($3 = '' OR dvr_datetime >= $3)
+              AND ($4 = '' OR dvr_datetime < $4)
+              AND ($5::boolean IS NULL OR (acknowledged_at IS NOT NULL) = $5)
+              AND ($6::text IS NULL OR (dvr_datetime, id) < ($6, $7))
+            ORDER BY dvr_datetime DESC, id DESC
+            LIMIT $8
+            "#,
+            DVR_COLUMNS
+        ))
+        .bind(req.vehicle_cd)
+        .bind(&req.severities)
+        .bind(req.from.trim())
+        .bind(req.to.trim())
+        .bind(req.acknowledged)
+        .bind(paginator.cursor(0))
+        .bind(paginator.cursor_as::<i64>(1)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let unacknowledged_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM dvr_notifications WHERE acknowledged_at IS NULL")
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+
+        let (notifications, pagination) =
+            paginator.finish(notifications, |n| vec![n.dvr_datetime.clone(), n.id.to_string()]);
+
+        Ok(Response::new(ListDvrNotificationsResponse {
+            notifications: notifications.iter().map(DvrNotificationModel::to_record_proto).collect(),
+            pagination: Some(pagination),
+            unacknowledged_count,
+        }))
+    }
+
+    /// 対応済みにする
+    async fn acknowledge(
+        &self,
+        request: Request<AcknowledgeDvrNotificationsRequest>,
+    ) -> Result<Response<AcknowledgeDvrNotificationsResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let req = request.into_inner();
+        if req.ids.is_empty() {
+            return Err(Status::invalid_argument("ids is required"));
+        }
+        let note = req.note.trim();
+
+        let mut conn = self.pool.acquire_scoped(&auth_user.org_id).await
+            .map_err(AppError::from)?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE dvr_notifications
+            SET acknowledged_at = NOW(), acknowledged_by = $2::uuid, acknowledgement_note = NULLIF($3, '')
+            WHERE id = ANY($1) AND acknowledged_at IS NULL
+            "#,
+        )
+        .bind(&req.ids)
+        .bind(&auth_user.user_id)
+        .bind(note)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!(
+            "DVR notifications acknowledged by {}: {} of {}",
+            auth_user.user_id,
+            result.rows_affected(),
+            req.ids.len()
+        );
+        Ok(Response::new(AcknowledgeDvrNotificationsResponse {
+            acknowledged: result.rows_affected() as i32,
+        }))
+    }
+
+    async fn create_dvr_ingest_token(
+        &self,
+        request: Request<CreateDvrIngestTokenRequest>,
+    ) -> Result<Response<CreateDvrIngestTokenResponse>, Status> {
+        let (auth_user, mut conn) = self.admin_conn(&request).await?;
+        let name = request.into_inner().name.trim().to_string();
+        if name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+
+        let token = generate_ingest_token().map_err(Status::internal)?;
+        let hint: String = token.chars().skip(token.chars().count() - 4).collect();
+        let row: IngestTokenRow = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO dvr_ingest_tokens (organization_id, name, token_hash, token_hint)
+            VALUES ($1::uuid, $2, $3, $4)
+            RETURNING {}
+            "#,
+            INGEST_TOKEN_COLUMNS
+        ))
+        .bind(&auth_user.org_id)
+        .bind(&name)
+        .bind(hash_ingest_token(&token))
+        .bind(&hint)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!("DVR ingest token {} created for {}", row.id, auth_user.org_id);
+        Ok(Response::new(CreateDvrIngestTokenResponse {
+            ingest_token: Some(row.to_proto()),
+            token,
+        }))
+    }
+
+    async fn list_dvr_ingest_tokens(
+        &self,
+        request: Request<ListDvrIngestTokensRequest>,
+    ) -> Result<Response<ListDvrIngestTokensResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+
+        let rows: Vec<IngestTokenRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM dvr_ingest_tokens ORDER BY created_at",
+            INGEST_TOKEN_COLUMNS
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListDvrIngestTokensResponse {
+            tokens: rows.iter().map(IngestTokenRow::to_proto).collect(),
         }))
     }
+
+    async fn delete_dvr_ingest_token(
+        &self,
+        request: Request<DeleteDvrIngestTokenRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let id = Uuid::parse_str(&request.into_inner().id)
+            .map_err(|_| Status::invalid_argument("Invalid ingest token id"))?;
+
+        let result = sqlx::query("DELETE FROM dvr_ingest_tokens WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::not_found(format!("Ingest token not found: {}", id)));
+        }
+        tracing::info!("DVR ingest token {} deleted", id);
+        Ok(Response::new(Empty {}))
+    }
 }
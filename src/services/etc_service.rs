@@ -0,0 +1,445 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::db::{get_organization_from_request, OrgScopedPool, Paginator};
+use crate::error::AppError;
+use crate::proto::etc::etc_service_server::EtcService;
+use crate::proto::etc::{
+    EtcUsage, GetMonthlyTollCostsRequest, GetMonthlyTollCostsResponse, ImportEtcCsvRequest,
+    ImportEtcCsvResponse, ListEtcUsagesRequest, ListEtcUsagesResponse, MonthlyTollCost,
+};
+use crate::services::csv_import::{
+    field, parse_amount, parse_date, parse_time, read_csv, CsvHeaders, MAX_CSV_BYTES,
+    MAX_REPORTED_ERRORS,
+};
+use crate::services::validation;
+use crate::services::vehicle_matcher::VehicleMatcher;
+use crate::text_encoding::{decode_text, TextEncoding};
+
+/// ETC 利用明細の1行
+#[derive(Debug, Clone, PartialEq)]
+pub struct EtcRecord {
+    pub entry_at: Option<NaiveDateTime>,
+    pub exit_at: NaiveDateTime,
+    pub entry_ic: String,
+    pub exit_ic: String,
+    pub toll_before_discount: Option<i32>,
+    pub discount: Option<i32>,
+    pub toll: i32,
+    pub vehicle_class: String,
+    pub plate_number: String,
+    pub card_number: String,
+    pub note: String,
+}
+
+/// 列の位置（ETC 利用照会サービスの CSV）
+#[derive(Debug, Default)]
+struct EtcColumns {
+    entry_date: Option<usize>,
+    entry_time: Option<usize>,
+    exit_date: usize,
+    exit_time: Option<usize>,
+    entry_ic: Option<usize>,
+    exit_ic: Option<usize>,
+    toll_before_discount: Option<usize>,
+    discount: Option<usize>,
+    toll: usize,
+    vehicle_class: Option<usize>,
+    plate_number: Option<usize>,
+    card_number: Option<usize>,
+    note: Option<usize>,
+}
+
+impl EtcColumns {
+    fn from_headers(headers: &CsvHeaders) -> Result<Self, String> {
+        let exit_date = headers
+            .find(|h| h.contains("年月日") && h.contains("(至)"))
+            .or_else(|| headers.find_any(&["利用年月日"]))
+            .ok_or("利用年月日（至）の列がありません")?;
+        let toll = headers
+            .find_any(&["通行料金", "料金"])
+            .ok_or("通行料金の列がありません")?;
+        Ok(Self {
+            entry_date: headers.find(|h| h.contains("年月日") && h.contains("(自)")),
+            entry_time: headers.find(|h| h.contains("時分") && h.contains("(自)")),
+            exit_date,
+            exit_time: headers
+                .find(|h| h.contains("時分") && h.contains("(至)"))
+                .or_else(|| headers.find_any(&["時分"])),
+            entry_ic: headers.find(|h| h.contains("ヶ所名") && h.contains("(自)")),
+            exit_ic: headers.find(|h| h.contains("ヶ所名") && h.contains("(至)")),
+            toll_before_discount: headers.find_any(&["割引前料金"]),
+            discount: headers.find(|h| h.contains("割引額")),
+            toll,
+            vehicle_class: headers.find_any(&["車種"]),
+            plate_number: headers.find_any(&["車両番号"]),
+            card_number: headers.find(|h| h.contains("カード番号")),
+            note: headers.find_any(&["備考"]),
+        })
+    }
+}
+
+/// ETC 利用明細 CSV を読む（読めた行, 読み飛ばした行の理由）
+pub fn parse_etc_csv(text: &str) -> Result<(Vec<EtcRecord>, Vec<String>), String> {
+    read_csv(text, EtcColumns::from_headers, parse_etc_row)
+}
+
+fn parse_etc_row(row: &csv::StringRecord, columns: &EtcColumns) -> Result<EtcRecord, String> {
+    let get = |idx: Option<usize>| field(row, idx);
+    let datetime = |date: Option<usize>, time: Option<usize>| -> Result<Option<NaiveDateTime>, String> {
+        let date_value = get(date);
+        if date_value.is_empty() {
+            return Ok(None);
+        }
+        let date = parse_date(&date_value).ok_or_else(|| format!("日付が不正です: {}", date_value))?;
+        let time_value = get(time);
+        let time = parse_time(&time_value).ok_or_else(|| format!("時刻が不正です: {}", time_value))?;
+        Ok(Some(date.and_time(time)))
+    };
+
+    let exit_at = datetime(Some(columns.exit_date), columns.exit_time)?.ok_or("利用年月日（至）が空です")?;
+    let toll = parse_amount(&get(Some(columns.toll)))?.ok_or("通行料金が空です")?;
+    Ok(EtcRecord {
+        entry_at: datetime(columns.entry_date, columns.entry_time)?,
+        exit_at,
+        entry_ic: get(columns.entry_ic),
+        exit_ic: get(columns.exit_ic),
+        toll_before_discount: parse_amount(&get(columns.toll_before_discount))?,
+        discount: parse_amount(&get(columns.discount))?,
+        toll,
+        vehicle_class: get(columns.vehicle_class),
+        plate_number: get(columns.plate_number),
+        card_number: get(columns.card_number),
+        note: get(columns.note),
+    })
+}
+
+/// "YYYY-MM" の月初
+pub(crate) fn parse_month(value: &str) -> Result<NaiveDate, Status> {
+    NaiveDate::parse_from_str(&format!("{}-01", value.trim()), "%Y-%m-%d")
+        .map_err(|_| Status::invalid_argument(format!("Invalid month (expected YYYY-MM): {}", value)))
+}
+
+pub(crate) fn next_month(month: NaiveDate) -> NaiveDate {
+    month.checked_add_months(chrono::Months::new(1)).unwrap_or(month)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct EtcUsageRow {
+    id: i64,
+    entry_at: Option<NaiveDateTime>,
+    exit_at: NaiveDateTime,
+    entry_ic: String,
+    exit_ic: String,
+    toll_before_discount: Option<i32>,
+    discount: Option<i32>,
+    toll: i32,
+    vehicle_class: String,
+    plate_number: String,
+    card_number: String,
+    ichiban_car_id: Option<String>,
+    car_name: Option<String>,
+    note: String,
+}
+
+impl EtcUsageRow {
+    fn to_proto(&self) -> EtcUsage {
+        let format = |t: &NaiveDateTime| t.format("%Y-%m-%d %H:%M").to_string();
+        EtcUsage {
+            id: self.id,
+            entry_at: self.entry_at.as_ref().map(format),
+            exit_at: format(&self.exit_at),
+            entry_ic: self.entry_ic.clone(),
+            exit_ic: self.exit_ic.clone(),
+            toll_before_discount: self.toll_before_discount,
+            discount: self.discount,
+            toll: self.toll,
+            vehicle_class: self.vehicle_class.clone(),
+            plate_number: self.plate_number.clone(),
+            card_number: self.card_number.clone(),
+            ichiban_car_id: self.ichiban_car_id.clone(),
+            car_name: self.car_name.clone(),
+            note: self.note.clone(),
+        }
+    }
+}
+
+pub struct EtcServiceImpl {
+    pool: PgPool,
+}
+
+impl EtcServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl EtcService for EtcServiceImpl {
+    async fn import_etc_csv(
+        &self,
+        request: Request<ImportEtcCsvRequest>,
+    ) -> Result<Response<ImportEtcCsvResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let mut req = request.into_inner();
+        if req.content.is_empty() {
+            return Err(Status::invalid_argument("content is required"));
+        }
+        req.filename = validation::line("filename", &req.filename, validation::FILENAME_MAX_CHARS)?;
+        if req.content.len() > MAX_CSV_BYTES {
+            return Err(Status::invalid_argument(format!(
+                "CSV is too large (max {} bytes)",
+                MAX_CSV_BYTES
+            )));
+        }
+        let encoding = TextEncoding::parse(&req.encoding).map_err(Status::invalid_argument)?;
+        let text = decode_text(&req.content, encoding).map_err(Status::invalid_argument)?;
+        let (records, mut errors) = parse_etc_csv(&text).map_err(Status::invalid_argument)?;
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let matcher = VehicleMatcher::load(&mut tx)
+            .await
+            .map_err(AppError::from)?;
+
+        let mut imported = 0;
+        let mut duplicates = 0;
+        let mut imported_ids = Vec::new();
+        for record in &records {
+            let id: Option<i64> = sqlx::query_scalar(
+                r#"
+                INSERT INTO etc_usages (
+                    organization_id, entry_at, exit_at, entry_ic, exit_ic, toll_before_discount,
+                    discount, toll, vehicle_class, plate_number, card_number, note, ichiban_car_id,
+                    source_filename
+                ) VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                ON CONFLICT ON CONSTRAINT etc_usages_unique DO NOTHING
+                RETURNING id
+                "#,
+            )
+            .bind(&organization_id)
+            .bind(record.entry_at)
+            .bind(record.exit_at)
+            .bind(&record.entry_ic)
+            .bind(&record.exit_ic)
+            .bind(record.toll_before_discount)
+            .bind(record.discount)
+            .bind(record.toll)
+            .bind(&record.vehicle_class)
+            .bind(&record.plate_number)
+            .bind(&record.card_number)
+            .bind(&record.note)
+            .bind(matcher.match_plate(&record.plate_number))
+            .bind(&req.filename)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+            match id {
+                Some(id) => {
+                    imported += 1;
+                    imported_ids.push(id);
+                }
+                None => duplicates += 1,
+            }
+        }
+
+        // 車両番号で紐づかなかった明細は、同じカードの直近の明細の車両に
+        sqlx::query(
+            r#"
+            UPDATE etc_usages u SET ichiban_car_id = m.ichiban_car_id
+            FROM (
+                SELECT DISTINCT ON (card_number) card_number, ichiban_car_id
+                FROM etc_usages
+                WHERE ichiban_car_id IS NOT NULL AND card_number <> ''
+                ORDER BY card_number, exit_at DESC
+            ) m
+            WHERE u.ichiban_car_id IS NULL AND u.card_number = m.card_number
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        let unmatched: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM etc_usages WHERE id = ANY($1) AND ichiban_car_id IS NULL")
+                .bind(&imported_ids)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        tracing::info!(
+            "ETC CSV {} imported for {}: {} new, {} duplicates, {} unmatched, {} skipped rows",
+            req.filename,
+            organization_id,
+            imported,
+            duplicates,
+            unmatched,
+            errors.len()
+        );
+        errors.truncate(MAX_REPORTED_ERRORS);
+        Ok(Response::new(ImportEtcCsvResponse {
+            imported,
+            duplicates,
+            unmatched: unmatched as i32,
+            errors,
+        }))
+    }
+
+    async fn list_etc_usages(
+        &self,
+        request: Request<ListEtcUsagesRequest>,
+    ) -> Result<Response<ListEtcUsagesResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+        let (from, to) = if req.month.trim().is_empty() {
+            (None, None)
+        } else {
+            let month = parse_month(&req.month)?;
+            (Some(month), Some(next_month(month)))
+        };
+        let cursor_exit_at = paginator
+            .cursor(0)
+            .map(|c| NaiveDateTime::parse_from_str(c, "%Y-%m-%dT%H:%M:%S"))
+            .transpose()
+            .map_err(|_| Status::invalid_argument("Invalid page_token"))?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows: Vec<EtcUsageRow> = sqlx::query_as(
+            r#"
+            SELECT u.id, u.entry_at, u.exit_at, u.entry_ic, u.exit_ic, u.toll_before_discount,
+                   u.discount, u.toll, u.vehicle_class, u.plate_number, u.card_number,
+                   u.ichiban_car_id, c.name AS car_name, u.note
+            FROM etc_usages u
+            LEFT JOIN ichiban_cars c ON c.organization_id = u.organization_id AND c.id = u.ichiban_car_id
+            WHERE ($1::text IS NULL OR u.ichiban_car_id = $1)
+              AND ($2::date IS NULL OR u.exit_at >= $2)
+              AND ($3::date IS NULL OR u.exit_at < $3)
+              AND (NOT $4 OR u.ichiban_car_id IS NULL)
+              AND ($5::timestamp IS NULL OR (u.exit_at, u.id) < ($5, $6))
+            ORDER BY u.exit_at DESC, u.id DESC
+            LIMIT $7
+            "#,
+        )
+        .bind(&req.ichiban_car_id)
+        .bind(from)
+        .bind(to)
+        .bind(req.unmatched_only)
+        .bind(cursor_exit_at)
+        .bind(paginator.cursor_as::<i64>(1)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (rows, pagination) = paginator.finish(rows, |r| {
+            vec![r.exit_at.format("%Y-%m-%dT%H:%M:%S").to_string(), r.id.to_string()]
+        });
+        Ok(Response::new(ListEtcUsagesResponse {
+            usages: rows.iter().map(EtcUsageRow::to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn get_monthly_toll_costs(
+        &self,
+        request: Request<GetMonthlyTollCostsRequest>,
+    ) -> Result<Response<GetMonthlyTollCostsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let from = parse_month(&req.from_month)?;
+        let to = next_month(parse_month(&req.to_month)?);
+        if from >= to {
+            return Err(Status::invalid_argument("from_month must not be after to_month"));
+        }
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows: Vec<(Option<String>, Option<String>, String, i64, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT u.ichiban_car_id, MAX(c.name), to_char(u.exit_at, 'YYYY-MM') AS month,
+                   COUNT(*), COALESCE(SUM(u.toll), 0)::bigint, COALESCE(SUM(u.discount), 0)::bigint
+            FROM etc_usages u
+            LEFT JOIN ichiban_cars c ON c.organization_id = u.organization_id AND c.id = u.ichiban_car_id
+            WHERE u.exit_at >= $1 AND u.exit_at < $2
+              AND ($3::text IS NULL OR u.ichiban_car_id = $3)
+            GROUP BY u.ichiban_car_id, month
+            ORDER BY month, u.ichiban_car_id NULLS LAST
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .bind(&req.ichiban_car_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let toll_total = rows.iter().map(|r| r.4).sum();
+        let costs = rows
+            .into_iter()
+            .map(|(ichiban_car_id, car_name, month, count, toll, discount)| MonthlyTollCost {
+                ichiban_car_id,
+                car_name,
+                month,
+                usage_count: count as i32,
+                toll_total: toll,
+                discount_total: discount,
+            })
+            .collect();
+        Ok(Response::new(GetMonthlyTollCostsResponse { costs, toll_total }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_etc_csv() {
+        let text = "利用年月日（自）,時分（自）,利用年月日（至）,時分（至）,利用ヶ所名（自）,利用ヶ所名（至）,割引前料金,ＥＴＣ割引額,通行料金,通行区分,車種,車両番号,ＥＴＣカード番号,備考\n\
+                    26/10/14,08:05,26/10/14,09:40,帯広・広尾道 帯広川西,道東道 トマム,\"2,350\",-700,\"1,650\",,中型,帯広100け201,************1234,\n\
+                    ,,2026/10/15,7:10,,札樽道 札幌西,,,-500,,中型,帯広100け201,************1234,払戻\n\
+                    26/10/16,,26/13/16,10:00,,,,,800,,,,,\n";
+        let (records, errors) = parse_etc_csv(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("4行目"));
+
+        let first = &records[0];
+        assert_eq!(first.entry_at.unwrap().to_string(), "2026-10-14 08:05:00");
+        assert_eq!(first.exit_at.to_string(), "2026-10-14 09:40:00");
+        assert_eq!(first.exit_ic, "道東道 トマム");
+        assert_eq!(first.toll_before_discount, Some(2350));
+        assert_eq!(first.discount, Some(-700));
+        assert_eq!(first.toll, 1650);
+        assert_eq!(first.card_number, "************1234");
+
+        let refund = &records[1];
+        assert_eq!(refund.entry_at, None);
+        assert_eq!(refund.toll, -500);
+        assert_eq!(refund.note, "払戻");
+    }
+
+    #[test]
+    fn test_parse_etc_csv_requires_columns() {
+        assert!(parse_etc_csv("日付,金額\n").is_err());
+    }
+}
@@ -1,8 +1,13 @@
 use regex::Regex;
-use sqlx::PgPool;
-use std::sync::LazyLock;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::sync::{Arc, LazyLock};
 
-use crate::db::set_current_organization;
+use crate::db::with_org_transaction;
+use crate::jobs::{Job, JobHandler, NewJob};
+use crate::outbox::{Outbox, OutboxEvent, FILE_PARSED};
+use crate::storage::StorageBackend;
+use crate::webhooks::WEBHOOK_TARGET;
 
 // === PDF解析用の正規表現パターン ===
 
@@ -36,6 +41,7 @@ static RE_GRANTDATE_BIKO: LazyLock<Regex> = LazyLock::new(|| {
 /// hono-logiのcreateFiles.ts相当の処理をRustで実装
 pub struct FileAutoParser {
     pool: PgPool,
+    outbox: Outbox,
 }
 
 /// Grantdate文字列からスペース（半角+全角）を除去
@@ -53,18 +59,76 @@ fn get_str<'a>(cert_info: &'a serde_json::Value, key: &str) -> String {
 }
 
 impl FileAutoParser {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, outbox: Outbox) -> Self {
+        Self { pool, outbox }
+    }
+
+    /// 解析・紐づけ完了の outbox イベント（件数が多いので Webhook にのみ送る）
+    pub fn parsed_event(file_uuid: &str, mime_type: &str, key: &CertKey, pending: bool) -> OutboxEvent {
+        OutboxEvent::new(
+            FILE_PARSED,
+            serde_json::json!({
+                "file_uuid": file_uuid,
+                "type": mime_type,
+                "elect_cert_mg_no": key.elect_cert_mg_no,
+                "grantdate_e": key.grantdate_e,
+                "grantdate_y": key.grantdate_y,
+                "grantdate_m": key.grantdate_m,
+                "grantdate_d": key.grantdate_d,
+                // PDF が JSON 待ち（JSON が届いたときに紐づく）
+                "pending": pending,
+            }),
+        )
+        .only(&[WEBHOOK_TARGET])
     }
 
     /// JSONファイルアップロード後に呼ばれる自動解析処理
     /// hono-logi createFiles.ts L186-287 相当
+    ///
+    /// 紐づけと outbox への書き込みは `with_org_transaction` で 1 トランザクション
+    /// （途中で失敗したら何も残らず、job の再実行でやり直す）。
     pub async fn process_json_upload(
         &self,
         file_uuid: &str,
         file_data: &[u8],
         organization_id: &str,
     ) -> Result<(), anyhow::Error> {
+        let Some(cert) = Self::parse_json(file_data)? else {
+            return Ok(());
+        };
+        with_org_transaction(&self.pool, organization_id, |mut tx| async move {
+            Self::link_json(&mut tx, file_uuid, &cert).await?;
+            let event = Self::parsed_event(file_uuid, "application/json", &cert.key, false);
+            self.outbox.write(&mut tx, organization_id, &event).await?;
+            Ok::<_, anyhow::Error>((tx, ()))
+        })
+        .await
+    }
+
+    /// PDFファイルアップロード後に呼ばれる自動解析処理
+    /// hono-logi createFiles.ts L291-365 + pdfCategory.ts 相当
+    ///
+    /// JSON と同じく紐づけと outbox への書き込みは 1 トランザクション。
+    pub async fn process_pdf_upload(
+        &self,
+        file_uuid: &str,
+        file_data: &[u8],
+        organization_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        let Some(key) = Self::parse_pdf(file_data)? else {
+            return Ok(());
+        };
+        with_org_transaction(&self.pool, organization_id, |mut tx| async move {
+            let linked = Self::link_pdf(&mut tx, file_uuid, &key).await?;
+            let event = Self::parsed_event(file_uuid, "application/pdf", &key, !linked);
+            self.outbox.write(&mut tx, organization_id, &event).await?;
+            Ok::<_, anyhow::Error>((tx, ()))
+        })
+        .await
+    }
+
+    /// 車検証 JSON（CertInfo）を読む。車検証でなければ None
+    pub fn parse_json(file_data: &[u8]) -> Result<Option<CertJson>, anyhow::Error> {
         // 1. JSONパース
         let json: serde_json::Value = serde_json::from_slice(file_data)?;
 
@@ -72,14 +136,14 @@ impl FileAutoParser {
             Some(ci) => ci,
             None => {
                 tracing::debug!("JSON does not contain CertInfo, skipping auto-parse");
-                return Ok(());
+                return Ok(None);
             }
         };
 
         let elect_cert_mg_no = get_str(cert_info, "ElectCertMgNo");
         if elect_cert_mg_no.is_empty() {
             tracing::debug!("CertInfo.ElectCertMgNo is empty, skipping auto-parse");
-            return Ok(());
+            return Ok(None);
         }
 
         // 2. Grantdateのスペース除去（hono-logi createCarInspection.ts L88-91）
@@ -103,9 +167,29 @@ impl FileAutoParser {
             grantdate_d
         );
 
-        // 3. DB接続取得 + RLS設定
-        let mut conn = self.pool.acquire().await?;
-        set_current_organization(&mut conn, organization_id).await?;
+        Ok(Some(CertJson {
+            key: CertKey {
+                elect_cert_mg_no,
+                grantdate_e,
+                grantdate_y,
+                grantdate_m,
+                grantdate_d,
+            },
+            cert_info_import_file_version,
+            cert_info: cert_info.clone(),
+        }))
+    }
+
+    /// 車検証 JSON を car_inspection に登録し、car_inspection_files_a に紐づける
+    /// （organization 設定済みのトランザクションで呼ぶ。JSON 待ちの PDF があれば一緒に紐づける）
+    pub async fn link_json(
+        conn: &mut PgConnection,
+        file_uuid: &str,
+        cert: &CertJson,
+    ) -> Result<(), anyhow::Error> {
+        let CertKey { elect_cert_mg_no, grantdate_e, grantdate_y, grantdate_m, grantdate_d } = &cert.key;
+        let cert_info = &cert.cert_info;
+        let cert_info_import_file_version = &cert.cert_info_import_file_version;
 
         // 4. car_inspection UPSERT（car_inspection_service.rs L192-338と同じSQL）
         sqlx::query(
@@ -377,28 +461,22 @@ impl FileAutoParser {
         Ok(())
     }
 
-    /// PDFファイルアップロード後に呼ばれる自動解析処理
-    /// hono-logi createFiles.ts L291-365 + pdfCategory.ts 相当
-    pub async fn process_pdf_upload(
-        &self,
-        file_uuid: &str,
-        file_data: &[u8],
-        organization_id: &str,
-    ) -> Result<(), anyhow::Error> {
+    /// 車検証 PDF の 1 ページ目から ElectCertMgNo と Grantdate を読む。車検証でなければ None
+    pub fn parse_pdf(file_data: &[u8]) -> Result<Option<CertKey>, anyhow::Error> {
         // 1. PDFテキスト抽出（1ページ目のみ）
         let pages = pdf_extract::extract_text_from_mem_by_pages(file_data)?;
         let page1_text = match pages.first() {
             Some(text) if !text.is_empty() => text,
             _ => {
                 tracing::debug!("PDF has no extractable text on page 1, skipping auto-parse");
-                return Ok(());
+                return Ok(None);
             }
         };
 
         // 2. 車検証PDF判定
         if !RE_CAR_INSPECTION.is_match(page1_text) {
             tracing::debug!("PDF is not a car inspection certificate, skipping auto-parse");
-            return Ok(());
+            return Ok(None);
         }
 
         // 3. ElectCertMgNo抽出（12桁数字）
@@ -406,7 +484,7 @@ impl FileAutoParser {
             Some(m) => m.as_str().to_string(),
             None => {
                 tracing::warn!("Car inspection PDF but no ElectCertMgNo found");
-                return Ok(());
+                return Ok(None);
             }
         };
 
@@ -428,7 +506,7 @@ impl FileAutoParser {
                     "Car inspection PDF but Grantdate not found: ElectCertMgNo={}",
                     elect_cert_mg_no
                 );
-                return Ok(());
+                return Ok(None);
             }
         };
 
@@ -441,9 +519,24 @@ impl FileAutoParser {
             grantdate_d
         );
 
-        // 5. DB接続取得 + RLS設定
-        let mut conn = self.pool.acquire().await?;
-        set_current_organization(&mut conn, organization_id).await?;
+        Ok(Some(CertKey {
+            elect_cert_mg_no,
+            grantdate_e,
+            grantdate_y,
+            grantdate_m,
+            grantdate_d,
+        }))
+    }
+
+    /// 車検証 PDF を car_inspection_files_b に紐づける（organization 設定済みのトランザクションで呼ぶ）
+    ///
+    /// JSON がまだなければ pending_car_inspection_pdfs に入れて false を返す。
+    pub async fn link_pdf(
+        conn: &mut PgConnection,
+        file_uuid: &str,
+        key: &CertKey,
+    ) -> Result<bool, anyhow::Error> {
+        let CertKey { elect_cert_mg_no, grantdate_e, grantdate_y, grantdate_m, grantdate_d } = key;
 
         // 6. car_inspection_files_aでJSON存在確認（ElectCertMgNo + Grantdate一致）
         let json_exists = sqlx::query_scalar::<_, bool>(
@@ -522,10 +615,28 @@ impl FileAutoParser {
             );
         }
 
-        Ok(())
+        Ok(json_exists)
     }
 }
 
+/// 車検証の識別キー（car_inspection_files_a/b の紐づけに使う）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertKey {
+    pub elect_cert_mg_no: String,
+    pub grantdate_e: String,
+    pub grantdate_y: String,
+    pub grantdate_m: String,
+    pub grantdate_d: String,
+}
+
+/// 解析済みの車検証 JSON
+#[derive(Debug, Clone)]
+pub struct CertJson {
+    pub key: CertKey,
+    pub cert_info_import_file_version: String,
+    pub cert_info: serde_json::Value,
+}
+
 /// car_ins_sheet_ichiban_cars_a JOINクエリ用
 #[derive(sqlx::FromRow)]
 struct IchibanCarsLink {
@@ -541,10 +652,116 @@ struct PendingPdf {
     file_uuid: String,
 }
 
+/// アップロード後の自動解析 job
+pub const AUTO_PARSE_JOB: &str = "files.auto_parse";
+/// 組織ごとの実行待ちがこれを超えたら後続を遅らせる
+const AUTO_PARSE_BACKLOG_LIMIT: i64 = 50;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AutoParsePayload {
+    pub file_uuid: String,
+    pub mime_type: String,
+}
+
+impl AutoParsePayload {
+    /// 自動解析対象の MIME タイプなら job を作る
+    pub fn job(file_uuid: &str, mime_type: &str) -> Option<NewJob> {
+        if mime_type != "application/json" && mime_type != "application/pdf" {
+            return None;
+        }
+        let payload = Self {
+            file_uuid: file_uuid.to_string(),
+            mime_type: mime_type.to_string(),
+        };
+        Some(
+            NewJob::new(AUTO_PARSE_JOB, payload)
+                .dedupe_key(file_uuid)
+                .backlog_limit(AUTO_PARSE_BACKLOG_LIMIT),
+        )
+    }
+}
+
+/// files の内容（ストレージ or DB blob）を読み込んで自動解析する
+pub struct AutoParseJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    parser: Arc<FileAutoParser>,
+}
+
+impl AutoParseJobHandler {
+    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>, parser: Arc<FileAutoParser>) -> Self {
+        Self { pool, storage, parser }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for AutoParseJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: AutoParsePayload = job.payload()?;
+
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        let row: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT s3_key, blob FROM files WHERE uuid = $1::uuid AND deleted_at IS NULL",
+        )
+        .bind(&payload.file_uuid)
+        .fetch_optional(&mut *conn)
+        .await?;
+        drop(conn);
+
+        let Some((s3_key, blob)) = row else {
+            tracing::debug!("File {} was deleted, skipping auto-parse", payload.file_uuid);
+            return Ok(());
+        };
+        let data = match (s3_key, &self.storage, blob) {
+            (Some(key), Some(storage), _) => storage.download(&key).await?,
+            (_, _, Some(blob)) => {
+                base64::Engine::decode(&base64::engine::general_purpose::STANDARD, blob)?
+            }
+            _ => anyhow::bail!("No content available for file {}", payload.file_uuid),
+        };
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        match payload.mime_type.as_str() {
+            "application/json" => {
+                self.parser
+                    .process_json_upload(&payload.file_uuid, &data, &job.organization_id)
+                    .await
+            }
+            "application/pdf" => {
+                self.parser
+                    .process_pdf_upload(&payload.file_uuid, &data, &job.organization_id)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_json_strips_grantdate_spaces() {
+        let data = r#"{"CertInfoImportFileVersion":"1","CertInfo":{"ElectCertMgNo":"141230033850","GrantdateE":"令 和","GrantdateY":" 8","GrantdateM":"2","GrantdateD":"　13"}}"#;
+        let cert = FileAutoParser::parse_json(data.as_bytes()).unwrap().expect("CertInfo");
+        assert_eq!(
+            cert.key,
+            CertKey {
+                elect_cert_mg_no: "141230033850".to_string(),
+                grantdate_e: "令和".to_string(),
+                grantdate_y: "8".to_string(),
+                grantdate_m: "2".to_string(),
+                grantdate_d: "13".to_string(),
+            }
+        );
+        assert_eq!(cert.cert_info_import_file_version, "1");
+        assert!(FileAutoParser::parse_json(br#"{"other":1}"#).unwrap().is_none());
+        assert!(FileAutoParser::parse_json(b"not json").is_err());
+    }
+
     #[test]
     fn test_pdf_text_extraction() {
         let pdf_data = include_bytes!("../../20260218141909_帯広１００け２０１.pdf");
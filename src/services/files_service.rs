@@ -1,29 +1,178 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use sqlx::PgPool;
-use tonic::{Request, Response, Status};
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, FromRow, PgConnection, PgPool, Postgres, QueryBuilder};
+use tokio_stream::StreamExt;
+use tonic::{Request, Response, Status, Streaming};
 use uuid::Uuid;
 
-use crate::db::{get_organization_from_request, set_current_organization, DEFAULT_ORGANIZATION_ID};
-use crate::models::FileModel;
-use crate::proto::common::Empty;
+use crate::db::{get_organization_from_request, OrderBy, OrgScopedPool, Paginator, DEFAULT_ORGANIZATION_ID};
+use crate::error::{AppError, ResultExt};
+use crate::middleware::AuthenticatedUser;
+use crate::models::{FileModel, FileUploadModel, FILE_SORT_COLUMNS};
+use crate::events::{watch_stream, EntityChange, EntityEvent, EventBus};
+use crate::proto::common::{BatchDeleteResponse, ChangeType, Empty};
 use crate::proto::files::files_service_server::FilesService;
 use crate::proto::files::{
-    CreateFileRequest, DeleteFileRequest, DownloadFileRequest, File, FileChunk, FileResponse,
-    GetFileRequest, ListFilesRequest, ListFilesResponse, RestoreFileRequest, RestoreFileResponse,
+    BatchCreateFileResult, BatchCreateFilesRequest, BatchCreateFilesResponse,
+    BatchDeleteFilesRequest, BatchGetFileResult, BatchGetFilesRequest, BatchGetFilesResponse,
+    CompleteUploadRequest, CreateFileRequest, DeleteFileRequest,
+    DownloadFileRequest, File, FileChunk, FileEvent, FileResponse, FileRetention,
+    GetDownloadUrlRequest, GetFileRequest, GetFileRetentionRequest, GetUploadStatusRequest,
+    GetUploadUrlRequest, GetUploadUrlResponse, GetThumbnailRequest, ListDeletedFilesRequest,
+    ListFilesRequest, ListFilesResponse, PurgeFileRequest, RestoreDeletedFileRequest,
+    RestoreFileRequest, RestoreFileResponse, SignedUrlResponse, ThumbnailResponse,
+    UploadFileRequest, UploadFileResponse, WatchFilesRequest,
 };
-use crate::services::file_auto_parser::FileAutoParser;
-use crate::storage::{StorageBackend, RestoreStatus};
+use crate::services::batch::{delete_response, ok_status, rpc_status, BatchContext};
+use crate::jobs::{enqueue, Job, JobHandler, NewJob, ScheduledTaskDef};
+use crate::services::file_auto_parser::AutoParsePayload;
+use crate::services::thumbnails::{self, ThumbnailPayload, ThumbnailSize, ThumbnailSource};
+use crate::services::validation;
+use crate::shutdown;
+use crate::storage::{StorageBackend, RestoreStatus, MULTIPART_PART_SIZE};
+use crate::usage::ensure_storage_quota;
+
+/// base64 の blob を復号したときのバイト数（files.size_bytes 用）
+fn base64_decoded_len(blob: &str) -> i64 {
+    let len = blob.trim_end().len();
+    let padding = blob.trim_end().bytes().rev().take_while(|b| *b == b'=').count();
+    (len / 4 * 3) as i64 - padding as i64
+}
+
+/// 分割アップロードを完了したファイル（GetUploadStatus 用）
+#[derive(FromRow)]
+struct UploadedFileRow {
+    #[sqlx(flatten)]
+    file: FileModel,
+    size_bytes: Option<i64>,
+}
+
+/// ゴミ箱のファイル（ListDeletedFiles 用）
+#[derive(FromRow)]
+struct DeletedFileRow {
+    #[sqlx(flatten)]
+    file: FileModel,
+    purge_at: Option<String>,
+}
+
+/// 署名付き URL の有効期間（分）
+const DEFAULT_SIGNED_URL_MINUTES: i32 = 15;
+const MAX_SIGNED_URL_MINUTES: i32 = 7 * 24 * 60;
+
+fn signed_url_expiry(expires_in_minutes: Option<i32>) -> Result<Duration, Status> {
+    let minutes = expires_in_minutes.unwrap_or(DEFAULT_SIGNED_URL_MINUTES);
+    if !(1..=MAX_SIGNED_URL_MINUTES).contains(&minutes) {
+        return Err(Status::invalid_argument(format!(
+            "expires_in_minutes must be between 1 and {}",
+            MAX_SIGNED_URL_MINUTES
+        )));
+    }
+    Ok(Duration::from_secs(minutes as u64 * 60))
+}
+
+fn expires_at(expires_in: Duration) -> String {
+    (chrono::Utc::now() + expires_in).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+const FILE_UPLOAD_COLUMNS: &str =
+    "uuid::text, filename, type as file_type, total_size, received_bytes, s3_key, session_id, parts";
 
 pub struct FilesServiceImpl {
     pool: PgPool,
     storage: Option<Arc<dyn StorageBackend>>,
-    file_auto_parser: Arc<FileAutoParser>,
+    events: EventBus,
+    /// 組織ごとの保存量の既定の上限（STORAGE_QUOTA_BYTES）
+    storage_quota: Option<i64>,
 }
 
 impl FilesServiceImpl {
-    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>, file_auto_parser: Arc<FileAutoParser>) -> Self {
-        Self { pool, storage, file_auto_parser }
+    pub fn new(
+        pool: PgPool,
+        storage: Option<Arc<dyn StorageBackend>>,
+        events: EventBus,
+        storage_quota: Option<i64>,
+    ) -> Self {
+        Self { pool, storage, events, storage_quota }
+    }
+
+    /// 自動解析・サムネイル生成の job を登録（対象外の MIME タイプは何もしない。登録失敗でアップロードは失敗させない）
+    async fn enqueue_upload_jobs(conn: &mut PgConnection, organization_id: &str, uuid: &str, mime_type: &str) {
+        let jobs = [
+            AutoParsePayload::job(uuid, mime_type),
+            ThumbnailPayload::job(uuid, mime_type),
+        ];
+        for job in jobs.into_iter().flatten() {
+            if let Err(e) = enqueue(conn, organization_id, job).await {
+                tracing::error!("Failed to enqueue upload job for {}: {}", uuid, e);
+            }
+        }
+    }
+
+    /// サムネイルの署名付き URL を付ける（ストレージ未設定なら何もしない）
+    async fn attach_thumbnail_urls(&self, conn: &mut PgConnection, files: &mut [File]) -> Result<(), Status> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        let ids: Vec<String> = files
+            .iter()
+            .filter(|f| thumbnails::is_thumbnail_type(&f.r#type))
+            .map(|f| f.uuid.clone())
+            .collect();
+        let mut urls = thumbnails::signed_urls(conn, storage.as_ref(), "file", &ids).await?;
+        for file in files {
+            if let Some(urls) = urls.remove(&file.uuid) {
+                file.thumbnail_small_url = urls.small;
+                file.thumbnail_medium_url = urls.medium;
+            }
+        }
+        Ok(())
+    }
+
+    /// order_by 指定時の一覧クエリ（`WHERE {alias}.deleted_at IS NULL` まで組み立てる）
+    fn ordered_select<'a>(
+        from: &str,
+        alias: &str,
+        order: &OrderBy,
+        paginator: &Paginator,
+    ) -> Result<QueryBuilder<'a, Postgres>, Status> {
+        let mut qb = QueryBuilder::new(format!(
+            r#"
+            SELECT {a}.uuid::text, {a}.filename, {a}.type as file_type,
+                   to_char({a}.created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                   to_char({a}.deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                   NULL as blob, {a}.s3_key, {a}.storage_class,
+                   to_char({a}.last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                   {a}.access_count_weekly, {a}.access_count_total,
+                   to_char({a}.promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
+            FROM {from}"#,
+            a = alias,
+            from = from,
+        ));
+        order.push_cursor_join(&mut qb, paginator)?;
+        qb.push(format!(" WHERE {}.deleted_at IS NULL", alias));
+        Ok(qb)
+    }
+
+    fn list_response(paginator: &Paginator, files: Vec<FileModel>) -> ListFilesResponse {
+        let (files, pagination) = paginator.finish(files, |f| vec![f.uuid.clone()]);
+        ListFilesResponse {
+            files: files.iter().map(Self::model_to_proto).collect(),
+            pagination: Some(pagination),
+        }
+    }
+
+    /// WatchFiles の購読者へ変更を通知（blob は送らない）
+    fn publish(&self, organization_id: &str, change_type: ChangeType, mut file: File) {
+        file.blob = None;
+        self.events.publish(EntityEvent {
+            organization_id: organization_id.to_string(),
+            user_id: None,
+            change_type,
+            change: EntityChange::File(file),
+        });
     }
 
     fn model_to_proto(model: &FileModel) -> File {
@@ -38,6 +187,10 @@ impl FilesServiceImpl {
             s3_key: model.s3_key.clone(),
             storage_class: model.storage_class.clone(),
             last_accessed_at: model.last_accessed_at.clone(),
+            // attach_thumbnail_urls で付ける
+            thumbnail_small_url: None,
+            thumbnail_medium_url: None,
+            purge_at: None,
         }
     }
 
@@ -46,23 +199,266 @@ impl FilesServiceImpl {
         format!("{}/{}", organization_id, uuid)
     }
 
-    /// アクセスを記録し、条件を満たせばSTANDARDに昇格
+    fn upload_status(upload: &FileUploadModel) -> UploadFileResponse {
+        UploadFileResponse {
+            uuid: upload.uuid.clone(),
+            received_bytes: upload.received_bytes,
+            total_size: upload.total_size,
+            completed: false,
+            file: None,
+        }
+    }
+
+    fn parse_upload_uuid(uuid: &str) -> Result<String, Status> {
+        Uuid::parse_str(uuid)
+            .map(|u| u.to_string())
+            .map_err(|_| Status::invalid_argument(format!("Invalid upload uuid: {}", uuid)))
+    }
+
+    async fn find_upload(conn: &mut PgConnection, uuid: &str) -> Result<Option<FileUploadModel>, Status> {
+        let upload = sqlx::query_as::<_, FileUploadModel>(&format!(
+            "SELECT {} FROM file_uploads WHERE uuid = $1::uuid",
+            FILE_UPLOAD_COLUMNS
+        ))
+        .bind(uuid)
+        .fetch_optional(conn)
+        .await
+        .map_err(AppError::from)?;
+        Ok(upload)
+    }
+
+    /// 完了済みのアップロード（files にある）の状態
+    async fn completed_status(conn: &mut PgConnection, uuid: &str) -> Result<Option<UploadFileResponse>, Status> {
+        let row = sqlx::query_as::<_, UploadedFileRow>(
+            r#"
+            SELECT uuid::text, filename, type as file_type,
+                   to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                   to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                   NULL as blob, s3_key, storage_class,
+                   to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                   access_count_weekly, access_count_total,
+                   to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at,
+                   size_bytes
+            FROM files WHERE uuid = $1::uuid
+            "#,
+        )
+        .bind(uuid)
+        .fetch_optional(conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(row.map(|row| {
+            let size = row.size_bytes.unwrap_or_default();
+            UploadFileResponse {
+                uuid: row.file.uuid.clone(),
+                received_bytes: size,
+                total_size: size,
+                completed: true,
+                file: Some(Self::model_to_proto(&row.file)),
+            }
+        }))
+    }
+
+    /// 次の chunk を読む（chunk のないメッセージは読み飛ばす）
+    async fn next_chunk(stream: &mut Streaming<UploadFileRequest>) -> Result<Option<FileChunk>, Status> {
+        while let Some(message) = stream.message().await? {
+            if let Some(chunk) = message.chunk {
+                return Ok(Some(chunk));
+            }
+        }
+        Ok(None)
+    }
+
+    /// マルチパートアップロードを開始して file_uploads に記録
+    async fn begin_upload(
+        storage: &dyn StorageBackend,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        filename: &str,
+        file_type: &str,
+        total_size: i64,
+    ) -> Result<FileUploadModel, Status> {
+        if total_size <= 0 {
+            return Err(Status::invalid_argument("total_size must be positive"));
+        }
+        let uuid = Uuid::new_v4().to_string();
+        let s3_key = Self::generate_gcs_key(organization_id, &uuid);
+        let session_id = storage
+            .create_multipart_upload(&s3_key, file_type)
+            .await
+            .with_context(|| format!("starting upload {} for org {}", uuid, organization_id))
+            .map_err(Status::from)?;
+
+        let result = sqlx::query_as::<_, FileUploadModel>(&format!(
+            r#"
+            INSERT INTO file_uploads (uuid, organization_id, filename, type, total_size, s3_key, session_id)
+            VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            FILE_UPLOAD_COLUMNS
+        ))
+        .bind(&uuid)
+        .bind(organization_id)
+        .bind(filename)
+        .bind(file_type)
+        .bind(total_size)
+        .bind(&s3_key)
+        .bind(&session_id)
+        .fetch_one(conn)
+        .await;
+
+        match result {
+            Ok(upload) => Ok(upload),
+            Err(e) => {
+                if let Err(abort_err) = storage.abort_multipart_upload(&s3_key, &session_id).await {
+                    tracing::warn!("Failed to abort upload {}: {}", uuid, abort_err.report());
+                }
+                Err(AppError::from(e).into())
+            }
+        }
+    }
+
+    /// パートを送り、送り終えた位置を記録する
+    async fn send_part(
+        storage: &dyn StorageBackend,
+        conn: &mut PgConnection,
+        upload: &mut FileUploadModel,
+        data: Vec<u8>,
+    ) -> Result<(), Status> {
+        let Some(session_id) = upload.session_id.clone() else {
+            return Err(Status::failed_precondition(format!(
+                "Upload {} uses a presigned URL; call CompleteUpload",
+                upload.uuid
+            )));
+        };
+        let len = data.len() as i64;
+        let part_number = upload.parts.len() as u32 + 1;
+        let part = storage
+            .upload_part(
+                &upload.s3_key,
+                &session_id,
+                part_number,
+                upload.received_bytes as u64,
+                data,
+                Some(upload.total_size as u64),
+            )
+            .await
+            .with_context(|| format!("uploading part {} of {}", part_number, upload.uuid))
+            .map_err(Status::from)?;
+        upload.parts.push(part);
+        upload.received_bytes += len;
+
+        sqlx::query(
+            "UPDATE file_uploads SET received_bytes = $2, parts = $3, updated_at = NOW() WHERE uuid = $1::uuid",
+        )
+        .bind(&upload.uuid)
+        .bind(upload.received_bytes)
+        .bind(&upload.parts)
+        .execute(conn)
+        .await
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// アップロードしたオブジェクトを files に登録（マルチパートは全パートを 1 つのオブジェクトにしてから）
+    async fn finish_upload(
+        storage: &dyn StorageBackend,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        upload: &FileUploadModel,
+    ) -> Result<File, Status> {
+        match &upload.session_id {
+            Some(session_id) => {
+                if let Err(e) = storage
+                    .complete_multipart_upload(&upload.s3_key, session_id, &upload.parts)
+                    .await
+                {
+                    // 前回の完了後に DB の更新だけ失敗していれば、オブジェクトはできている
+                    if storage.get_object_info(&upload.s3_key).await.is_err() {
+                        return Err(e
+                            .context(format!("completing upload {} for org {}", upload.uuid, organization_id))
+                            .into());
+                    }
+                }
+            }
+            // 署名付き URL への PUT は、オブジェクトがあり大きさが合っていれば完了
+            None => {
+                let info = storage.get_object_info(&upload.s3_key).await.map_err(|e| {
+                    tracing::debug!("Upload {} not found in storage: {}", upload.uuid, e.report());
+                    Status::failed_precondition(format!("Upload {} has not been uploaded yet", upload.uuid))
+                })?;
+                if info.size.is_some_and(|size| size != upload.total_size) {
+                    return Err(Status::invalid_argument(format!(
+                        "Uploaded size {} does not match size_bytes {}",
+                        info.size.unwrap_or_default(),
+                        upload.total_size
+                    )));
+                }
+            }
+        }
+
+        let mut tx = conn.begin().await.map_err(AppError::from)?;
+        let result = sqlx::query_as::<_, FileModel>(
+            r#"
+            INSERT INTO files (uuid, organization_id, filename, type, created_at, s3_key, storage_class, last_accessed_at, size_bytes)
+            VALUES ($1::uuid, $2::uuid, $3, $4, NOW(), $5, 'STANDARD', NOW(), $6)
+            RETURNING uuid::text, filename, type as file_type,
+                      to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                      to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                      NULL as blob, s3_key, storage_class,
+                      to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                      access_count_weekly, access_count_total,
+                      to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
+            "#,
+        )
+        .bind(&upload.uuid)
+        .bind(organization_id)
+        .bind(&upload.filename)
+        .bind(&upload.file_type)
+        .bind(&upload.s3_key)
+        .bind(upload.total_size)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        sqlx::query("DELETE FROM file_uploads WHERE uuid = $1::uuid")
+            .bind(&upload.uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+        // 自動解析（job queue）— JSON or PDF
+        Self::enqueue_upload_jobs(&mut *tx, organization_id, &upload.uuid, &upload.file_type).await;
+        tx.commit().await.map_err(AppError::from)?;
+
+        Ok(Self::model_to_proto(&result))
+    }
+
+    /// アクセスを記録し、条件を満たせばSTANDARDへの昇格 job を登録
     /// - 直近7日で3回以上アクセス → STANDARDにrewrite
     async fn record_access_and_maybe_promote(
         &self,
-        gcs_key: &str,
         uuid: &str,
         organization_id: &str,
         current_storage_class: Option<&str>,
     ) {
+        if self.storage.is_none() {
+            return;
+        }
         let pool = self.pool.clone();
-        let storage = self.storage.clone();
-        let gcs_key = gcs_key.to_string();
         let uuid = uuid.to_string();
         let organization_id = organization_id.to_string();
         let storage_class = current_storage_class.map(|s| s.to_string());
 
-        tokio::spawn(async move {
+        shutdown::spawn(async move {
+            let mut conn = match pool.acquire_scoped(&organization_id).await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to record file access: uuid={}, error={}", uuid, e);
+                    return;
+                }
+            };
+
             // アクセスを記録し、カウントを取得
             let access_result = sqlx::query_as::<_, crate::models::FileAccessResult>(
                 "SELECT * FROM record_file_access($1::uuid, $2::uuid, $3)",
@@ -70,7 +466,7 @@ impl FilesServiceImpl {
             .bind(&uuid)
             .bind(&organization_id)
             .bind(&storage_class)
-            .fetch_one(&pool)
+            .fetch_one(&mut *conn)
             .await;
 
             match access_result {
@@ -83,45 +479,15 @@ impl FilesServiceImpl {
                         result.recent_7day_count
                     );
 
-                    // 直近7日で3回以上 && STANDARDでない場合は昇格
+                    // 直近7日で3回以上 && STANDARDでない場合は昇格（job で実行し、再起動しても失われない）
                     let should_promote = result.recent_7day_count >= 3
                         && storage_class.as_deref() != Some("STANDARD");
 
                     if should_promote {
-                        if let Some(storage) = storage {
-                            match storage.rewrite_to_standard(&gcs_key).await {
-                                Ok(_) => {
-                                    tracing::info!(
-                                        "Promoted to STANDARD: uuid={}, access_count_7day={}",
-                                        uuid,
-                                        result.recent_7day_count
-                                    );
-
-                                    // DB更新
-                                    let now = chrono::Utc::now();
-                                    if let Err(e) = sqlx::query(
-                                        "UPDATE files SET storage_class = 'STANDARD', promoted_to_standard_at = $1 WHERE uuid = $2::uuid",
-                                    )
-                                    .bind(&now)
-                                    .bind(&uuid)
-                                    .execute(&pool)
-                                    .await
-                                    {
-                                        tracing::error!(
-                                            "Failed to update storage_class: uuid={}, error={}",
-                                            uuid,
-                                            e
-                                        );
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!(
-                                        "Failed to promote to STANDARD: uuid={}, error={}",
-                                        uuid,
-                                        e
-                                    );
-                                }
-                            }
+                        let job = NewJob::new(STORAGE_PROMOTION_JOB, StoragePromotionPayload { file_uuid: uuid.clone() })
+                            .dedupe_key(&uuid);
+                        if let Err(e) = enqueue(&mut conn, &organization_id, job).await {
+                            tracing::error!("Failed to enqueue promotion: uuid={}, error={}", uuid, e);
                         }
                     }
                 }
@@ -145,14 +511,14 @@ impl FilesService for FilesServiceImpl {
         if organization_id == DEFAULT_ORGANIZATION_ID {
             tracing::debug!("Using default organization_id for file upload");
         }
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        req.filename = validation::line("filename", &req.filename, validation::FILENAME_MAX_CHARS)?;
+        req.r#type = validation::line("type", &req.r#type, validation::CODE_MAX_CHARS)?;
         let uuid = Uuid::new_v4().to_string();
         let created = chrono::Utc::now();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         tracing::info!(
             "Creating file: uuid={}, filename={}, org={}",
@@ -174,18 +540,20 @@ impl FilesService for FilesServiceImpl {
             } else {
                 return Err(Status::invalid_argument("No content or blob_base64 provided"));
             };
+            ensure_storage_quota(&mut conn, self.storage_quota, data.len() as i64).await?;
 
             // ストレージにアップロード
             storage
                 .upload(&gcs_key, &data, &req.r#type)
                 .await
-                .map_err(|e| Status::internal(format!("GCS upload failed: {}", e)))?;
+                .with_context(|| format!("creating file {} for org {}", uuid, organization_id))
+                .map_err(Status::from)?;
 
             // DBにメタデータのみ保存（blobはNULL）
             let result = sqlx::query_as::<_, FileModel>(
                 r#"
-                INSERT INTO files (uuid, organization_id, filename, type, created_at, s3_key, storage_class, last_accessed_at)
-                VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6, 'STANDARD', $5)
+                INSERT INTO files (uuid, organization_id, filename, type, created_at, s3_key, storage_class, last_accessed_at, size_bytes)
+                VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6, 'STANDARD', $5, $7)
                 RETURNING uuid::text, filename, type as file_type,
                           to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
                           to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
@@ -201,34 +569,17 @@ impl FilesService for FilesServiceImpl {
             .bind(&req.r#type)
             .bind(&created)
             .bind(&gcs_key)
+            .bind(data.len() as i64)
             .fetch_one(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
-
-            // 自動解析（バックグラウンド）— JSON or PDF
-            if req.r#type == "application/json" {
-                let parser = self.file_auto_parser.clone();
-                let uuid_clone = uuid.clone();
-                let org_clone = organization_id.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = parser.process_json_upload(&uuid_clone, &data, &org_clone).await {
-                        tracing::error!("JSON auto-parse failed for {}: {}", uuid_clone, e);
-                    }
-                });
-            } else if req.r#type == "application/pdf" {
-                let parser = self.file_auto_parser.clone();
-                let uuid_clone = uuid.clone();
-                let org_clone = organization_id.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = parser.process_pdf_upload(&uuid_clone, &data, &org_clone).await {
-                        tracing::error!("PDF auto-parse failed for {}: {}", uuid_clone, e);
-                    }
-                });
-            }
+            .map_err(AppError::from)?;
+
+            // 自動解析（job queue）— JSON or PDF
+            Self::enqueue_upload_jobs(&mut conn, &organization_id, &uuid, &req.r#type).await;
 
-            return Ok(Response::new(FileResponse {
-                file: Some(Self::model_to_proto(&result)),
-            }));
+            let file = Self::model_to_proto(&result);
+            self.publish(&organization_id, ChangeType::Created, file.clone());
+            return Ok(Response::new(FileResponse { file: Some(file) }));
         }
 
         // GCSが無効な場合は従来通りDBにblobを保存
@@ -241,11 +592,13 @@ impl FilesService for FilesServiceImpl {
         } else {
             req.blob_base64
         };
+        let size_bytes = blob.as_deref().map(base64_decoded_len);
+        ensure_storage_quota(&mut conn, self.storage_quota, size_bytes.unwrap_or_default()).await?;
 
         let result = sqlx::query_as::<_, FileModel>(
             r#"
-            INSERT INTO files (uuid, organization_id, filename, type, created_at, blob)
-            VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6)
+            INSERT INTO files (uuid, organization_id, filename, type, created_at, blob, size_bytes)
+            VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6, $7)
             RETURNING uuid::text, filename, type as file_type,
                       to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
                       to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
@@ -261,36 +614,170 @@ impl FilesService for FilesServiceImpl {
         .bind(&req.r#type)
         .bind(&created)
         .bind(&blob)
+        .bind(size_bytes)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
-        // 自動解析（バックグラウンド）— JSON or PDF
-        if req.r#type == "application/json" && !raw_content.is_empty() {
-            let parser = self.file_auto_parser.clone();
-            let uuid_clone = uuid.clone();
-            let org_clone = organization_id.clone();
-            tokio::spawn(async move {
-                if let Err(e) = parser.process_json_upload(&uuid_clone, &raw_content, &org_clone).await {
-                    tracing::error!("JSON auto-parse failed for {}: {}", uuid_clone, e);
+        // 自動解析（job queue）— JSON or PDF
+        if !raw_content.is_empty() {
+            Self::enqueue_upload_jobs(&mut conn, &organization_id, &uuid, &req.r#type).await;
+        }
+
+        let file = Self::model_to_proto(&result);
+        self.publish(&organization_id, ChangeType::Created, file.clone());
+        Ok(Response::new(FileResponse { file: Some(file) }))
+    }
+
+    async fn upload_file(
+        &self,
+        request: Request<Streaming<UploadFileRequest>>,
+    ) -> Result<Response<UploadFileResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let storage = self
+            .storage
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("UploadFile requires object storage (GCS / R2)"))?;
+        let mut stream = request.into_inner();
+
+        let first = stream
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("Empty upload stream"))?;
+        let metadata = first
+            .metadata
+            .ok_or_else(|| Status::invalid_argument("First message must contain metadata"))?;
+        let filename = validation::line("filename", &metadata.filename, validation::FILENAME_MAX_CHARS)?;
+        let file_type = validation::line("type", &metadata.r#type, validation::CODE_MAX_CHARS)?;
+        let mut pending = first.chunk;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let mut upload = match &metadata.uuid {
+            Some(uuid) => {
+                let uuid = Self::parse_upload_uuid(uuid)?;
+                match Self::find_upload(&mut conn, &uuid).await? {
+                    Some(upload) if upload.session_id.is_none() => {
+                        return Err(Status::failed_precondition(format!(
+                            "Upload {} uses a presigned URL; call CompleteUpload",
+                            uuid
+                        )));
+                    }
+                    Some(upload) => upload,
+                    // 完了済みならその結果を返す（完了の応答を受け取れなかった再送）
+                    None => {
+                        return Self::completed_status(&mut conn, &uuid)
+                            .await?
+                            .map(Response::new)
+                            .ok_or_else(|| Status::not_found(format!("Upload not found: {}", uuid)));
+                    }
                 }
-            });
-        } else if req.r#type == "application/pdf" && !raw_content.is_empty() {
-            let parser = self.file_auto_parser.clone();
-            let uuid_clone = uuid.clone();
-            let org_clone = organization_id.clone();
-            tokio::spawn(async move {
-                if let Err(e) = parser.process_pdf_upload(&uuid_clone, &raw_content, &org_clone).await {
-                    tracing::error!("PDF auto-parse failed for {}: {}", uuid_clone, e);
+            }
+            None => {
+                // 新規は最初の chunk の total_size で開始
+                if pending.is_none() {
+                    pending = Self::next_chunk(&mut stream).await?;
                 }
-            });
+                let total_size = pending
+                    .as_ref()
+                    .map(|chunk| chunk.total_size)
+                    .ok_or_else(|| Status::invalid_argument("No chunk received"))?;
+                Self::begin_upload(storage.as_ref(), &mut conn, &organization_id, &filename, &file_type, total_size)
+                    .await?
+            }
+        };
+
+        tracing::info!(
+            "Uploading file: uuid={}, filename={}, received={}/{}, org={}",
+            upload.uuid,
+            upload.filename,
+            upload.received_bytes,
+            upload.total_size,
+            organization_id
+        );
+
+        // パートの大きさに満たない分はここに溜める（途中で切れたら捨て、received_bytes から再送してもらう）
+        let mut buffer: Vec<u8> = Vec::new();
+        while upload.received_bytes + (buffer.len() as i64) < upload.total_size {
+            let chunk = match pending.take() {
+                Some(chunk) => chunk,
+                None => match Self::next_chunk(&mut stream).await? {
+                    Some(chunk) => chunk,
+                    None => break,
+                },
+            };
+            if chunk.total_size != upload.total_size {
+                return Err(Status::invalid_argument(format!(
+                    "total_size mismatch: expected {}, got {}",
+                    upload.total_size, chunk.total_size
+                )));
+            }
+            let expected = upload.received_bytes + buffer.len() as i64;
+            if chunk.offset < 0 || chunk.offset > expected {
+                return Err(Status::out_of_range(format!(
+                    "Expected offset {}, got {}",
+                    expected, chunk.offset
+                )));
+            }
+            // 受け取り済みの範囲（再送）は読み飛ばす
+            let skip = (expected - chunk.offset) as usize;
+            if skip < chunk.data.len() {
+                buffer.extend_from_slice(&chunk.data[skip..]);
+            }
+            if upload.received_bytes + buffer.len() as i64 > upload.total_size {
+                return Err(Status::invalid_argument(format!(
+                    "Data exceeds total_size {}",
+                    upload.total_size
+                )));
+            }
+
+            while buffer.len() >= MULTIPART_PART_SIZE {
+                let rest = buffer.split_off(MULTIPART_PART_SIZE);
+                let part = std::mem::replace(&mut buffer, rest);
+                Self::send_part(storage.as_ref(), &mut conn, &mut upload, part).await?;
+            }
+        }
+
+        // 最後のパート
+        if !buffer.is_empty() && upload.received_bytes + buffer.len() as i64 == upload.total_size {
+            Self::send_part(storage.as_ref(), &mut conn, &mut upload, buffer).await?;
         }
 
-        Ok(Response::new(FileResponse {
-            file: Some(Self::model_to_proto(&result)),
+        if upload.received_bytes < upload.total_size {
+            return Ok(Response::new(Self::upload_status(&upload)));
+        }
+
+        let file = Self::finish_upload(storage.as_ref(), &mut conn, &organization_id, &upload).await?;
+        self.publish(&organization_id, ChangeType::Created, file.clone());
+        Ok(Response::new(UploadFileResponse {
+            uuid: upload.uuid,
+            received_bytes: upload.total_size,
+            total_size: upload.total_size,
+            completed: true,
+            file: Some(file),
         }))
     }
 
+    async fn get_upload_status(
+        &self,
+        request: Request<GetUploadStatusRequest>,
+    ) -> Result<Response<UploadFileResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let uuid = Self::parse_upload_uuid(&request.into_inner().uuid)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        if let Some(upload) = Self::find_upload(&mut conn, &uuid).await? {
+            return Ok(Response::new(Self::upload_status(&upload)));
+        }
+        Self::completed_status(&mut conn, &uuid)
+            .await?
+            .map(Response::new)
+            .ok_or_else(|| Status::not_found(format!("Upload not found: {}", uuid)))
+    }
+
     async fn list_files(
         &self,
         request: Request<ListFilesRequest>,
@@ -298,55 +785,55 @@ impl FilesService for FilesServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
-        let files = if let Some(type_filter) = req.type_filter {
-            sqlx::query_as::<_, FileModel>(
-                r#"
-                SELECT uuid::text, filename, type as file_type,
-                       to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
-                       to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
-                       NULL as blob, s3_key, storage_class,
-                       to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
-                       access_count_weekly, access_count_total,
-                       to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
-                FROM files
-                WHERE deleted_at IS NULL AND type = $1
-                ORDER BY created_at DESC
-                "#,
-            )
-            .bind(&type_filter)
-            .fetch_all(&mut *conn)
-            .await
-        } else {
-            sqlx::query_as::<_, FileModel>(
-                r#"
-                SELECT uuid::text, filename, type as file_type,
-                       to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
-                       to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
-                       NULL as blob, s3_key, storage_class,
-                       to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
-                       access_count_weekly, access_count_total,
-                       to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
-                FROM files
-                WHERE deleted_at IS NULL
-                ORDER BY created_at DESC
-                "#,
-            )
-            .fetch_all(&mut *conn)
-            .await
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        if let Some(order) = OrderBy::parse(&req.order_by, &FILE_SORT_COLUMNS)? {
+            let mut qb = Self::ordered_select("files", "files", &order, &paginator)?;
+            if let Some(type_filter) = &req.type_filter {
+                qb.push(" AND files.type = ").push_bind(type_filter.clone());
+            }
+            order.push_page(&mut qb, "files", &paginator);
+            let files = qb
+                .build_query_as::<FileModel>()
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+            let mut response = Self::list_response(&paginator, files);
+            self.attach_thumbnail_urls(&mut conn, &mut response.files).await?;
+            return Ok(Response::new(response));
         }
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
 
-        let proto_files: Vec<File> = files.iter().map(Self::model_to_proto).collect();
+        // キーセット: (created_at, uuid) DESC、カーソルは前ページ最終行の uuid
+        let files = sqlx::query_as::<_, FileModel>(
+            r#"
+            SELECT uuid::text, filename, type as file_type,
+                   to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                   to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                   NULL as blob, s3_key, storage_class,
+                   to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                   access_count_weekly, access_count_total,
+                   to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
+            FROM files
+            WHERE deleted_at IS NULL
+              AND ($1::text IS NULL OR type = $1)
+              AND ($2::uuid IS NULL OR (created_at, uuid) < (SELECT created_at, uuid FROM files WHERE uuid = $2::uuid))
+            ORDER BY created_at DESC, uuid DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(&req.type_filter)
+        .bind(paginator.cursor(0))
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
 
-        Ok(Response::new(ListFilesResponse {
-            files: proto_files,
-            pagination: None,
-        }))
+        let mut response = Self::list_response(&paginator, files);
+        self.attach_thumbnail_urls(&mut conn, &mut response.files).await?;
+        Ok(Response::new(response))
     }
 
     async fn get_file(
@@ -356,10 +843,8 @@ impl FilesService for FilesServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let query = if req.include_blob {
             r#"
@@ -389,12 +874,12 @@ impl FilesService for FilesServiceImpl {
             .bind(&req.uuid)
             .fetch_optional(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+            .map_err(AppError::from)?
             .ok_or_else(|| Status::not_found(format!("File not found: {}", req.uuid)))?;
 
-        Ok(Response::new(FileResponse {
-            file: Some(Self::model_to_proto(&file)),
-        }))
+        let mut file = Self::model_to_proto(&file);
+        self.attach_thumbnail_urls(&mut conn, std::slice::from_mut(&mut file)).await?;
+        Ok(Response::new(FileResponse { file: Some(file) }))
     }
 
     type DownloadFileStream = tokio_stream::wrappers::ReceiverStream<Result<FileChunk, Status>>;
@@ -407,10 +892,8 @@ impl FilesService for FilesServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         let file = sqlx::query_as::<_, FileModel>(
             r#"
@@ -427,7 +910,7 @@ impl FilesService for FilesServiceImpl {
         .bind(&req.uuid)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+        .map_err(AppError::from)?
         .ok_or_else(|| Status::not_found(format!("File not found: {}", req.uuid)))?;
 
         let (tx, rx) = tokio::sync::mpsc::channel(4);
@@ -438,38 +921,50 @@ impl FilesService for FilesServiceImpl {
             let info = storage
                 .get_object_info(gcs_key)
                 .await
-                .map_err(|e| Status::internal(format!("Storage error: {}", e)))?;
+                .with_context(|| format!("downloading file {}", req.uuid))
+                .map_err(Status::from)?;
 
-            // ストレージからダウンロード
-            let data = storage
-                .download(gcs_key)
+            // ストレージから読みながら送る（全体をメモリに載せない）
+            let mut data = storage
+                .download_stream(gcs_key)
                 .await
-                .map_err(|e| Status::internal(format!("Storage download failed: {}", e)))?;
+                .with_context(|| format!("downloading file {}", req.uuid))
+                .map_err(Status::from)?;
 
-            let total_size = data.len() as i64;
+            let total_size = info.size.unwrap_or_default();
             let chunk_size = 64 * 1024; // 64KB chunks
 
             // アクセスを記録し、条件を満たせばSTANDARDに昇格
             self.record_access_and_maybe_promote(
-                gcs_key,
                 &file.uuid,
                 &organization_id,
                 info.storage_class.as_deref(),
             )
             .await;
 
+            let uuid = file.uuid.clone();
             tokio::spawn(async move {
                 let mut offset = 0i64;
-                for chunk in data.chunks(chunk_size) {
-                    let file_chunk = FileChunk {
-                        data: chunk.to_vec(),
-                        offset,
-                        total_size,
+                while let Some(bytes) = data.next().await {
+                    let bytes = match bytes {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            tracing::error!("Failed to stream file {}: {}", uuid, e.report());
+                            let _ = tx.send(Err(Status::from(e))).await;
+                            return;
+                        }
                     };
-                    if tx.send(Ok(file_chunk)).await.is_err() {
-                        break;
+                    for chunk in bytes.chunks(chunk_size) {
+                        let file_chunk = FileChunk {
+                            data: chunk.to_vec(),
+                            offset,
+                            total_size,
+                        };
+                        if tx.send(Ok(file_chunk)).await.is_err() {
+                            return;
+                        }
+                        offset += chunk.len() as i64;
                     }
-                    offset += chunk.len() as i64;
                 }
             });
 
@@ -507,6 +1002,180 @@ impl FilesService for FilesServiceImpl {
         )))
     }
 
+    async fn get_upload_url(
+        &self,
+        request: Request<GetUploadUrlRequest>,
+    ) -> Result<Response<GetUploadUrlResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let storage = self
+            .storage
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("GetUploadUrl requires object storage (GCS / R2)"))?;
+        let req = request.into_inner();
+        let filename = validation::line("filename", &req.filename, validation::FILENAME_MAX_CHARS)?;
+        let file_type = validation::line("type", &req.r#type, validation::CODE_MAX_CHARS)?;
+        if req.size_bytes <= 0 {
+            return Err(Status::invalid_argument("size_bytes must be positive"));
+        }
+        let expires_in = signed_url_expiry(req.expires_in_minutes)?;
+
+        let uuid = Uuid::new_v4().to_string();
+        let s3_key = Self::generate_gcs_key(&organization_id, &uuid);
+        let url = storage
+            .signed_upload_url(&s3_key, &file_type, expires_in)
+            .await
+            .with_context(|| format!("signing upload {} for org {}", uuid, organization_id))
+            .map_err(Status::from)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+        ensure_storage_quota(&mut conn, self.storage_quota, req.size_bytes).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO file_uploads (uuid, organization_id, filename, type, total_size, s3_key)
+            VALUES ($1::uuid, $2::uuid, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(&uuid)
+        .bind(&organization_id)
+        .bind(&filename)
+        .bind(&file_type)
+        .bind(req.size_bytes)
+        .bind(&s3_key)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(GetUploadUrlResponse {
+            uuid,
+            url,
+            expires_at: expires_at(expires_in),
+        }))
+    }
+
+    async fn complete_upload(
+        &self,
+        request: Request<CompleteUploadRequest>,
+    ) -> Result<Response<FileResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let storage = self
+            .storage
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("CompleteUpload requires object storage (GCS / R2)"))?;
+        let uuid = Self::parse_upload_uuid(&request.into_inner().uuid)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let upload = match Self::find_upload(&mut conn, &uuid).await? {
+            Some(upload) if upload.session_id.is_some() => {
+                return Err(Status::failed_precondition(format!(
+                    "Upload {} is a chunked upload; resume it with UploadFile",
+                    uuid
+                )));
+            }
+            Some(upload) => upload,
+            // 完了済みならその結果を返す
+            None => {
+                let file = Self::completed_status(&mut conn, &uuid)
+                    .await?
+                    .and_then(|status| status.file)
+                    .ok_or_else(|| Status::not_found(format!("Upload not found: {}", uuid)))?;
+                return Ok(Response::new(FileResponse { file: Some(file) }));
+            }
+        };
+
+        let file = Self::finish_upload(storage.as_ref(), &mut conn, &organization_id, &upload).await?;
+        self.publish(&organization_id, ChangeType::Created, file.clone());
+        Ok(Response::new(FileResponse { file: Some(file) }))
+    }
+
+    async fn get_download_url(
+        &self,
+        request: Request<GetDownloadUrlRequest>,
+    ) -> Result<Response<SignedUrlResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let storage = self
+            .storage
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("GetDownloadUrl requires object storage (GCS / R2)"))?;
+        let req = request.into_inner();
+        let expires_in = signed_url_expiry(req.expires_in_minutes)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let (s3_key, storage_class): (Option<String>, Option<String>) = sqlx::query_as(
+            "SELECT s3_key, storage_class FROM files WHERE uuid = $1::uuid",
+        )
+        .bind(&req.uuid)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| Status::not_found(format!("File not found: {}", req.uuid)))?;
+        drop(conn);
+
+        let s3_key = s3_key.ok_or_else(|| {
+            Status::failed_precondition(format!(
+                "File {} is stored in the database; use DownloadFile",
+                req.uuid
+            ))
+        })?;
+        let url = storage
+            .signed_url(&s3_key, expires_in)
+            .await
+            .with_context(|| format!("signing download of file {}", req.uuid))
+            .map_err(Status::from)?;
+
+        // アクセスを記録し、条件を満たせばSTANDARDに昇格
+        self.record_access_and_maybe_promote(&req.uuid, &organization_id, storage_class.as_deref())
+            .await;
+
+        Ok(Response::new(SignedUrlResponse {
+            url,
+            expires_at: expires_at(expires_in),
+        }))
+    }
+
+    async fn get_thumbnail(
+        &self,
+        request: Request<GetThumbnailRequest>,
+    ) -> Result<Response<ThumbnailResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let storage = self
+            .storage
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("GetThumbnail requires object storage (GCS / R2)"))?;
+        let req = request.into_inner();
+        let size = match req.size.as_str() {
+            "" => ThumbnailSize::Small,
+            size => ThumbnailSize::parse(size).ok_or_else(|| {
+                Status::invalid_argument(format!("size must be small or medium: {:?}", size))
+            })?,
+        };
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+        let (s3_key, width, height) = thumbnails::find_thumbnail(&mut conn, ThumbnailSource::File(&req.uuid), size)
+            .await?
+            .ok_or_else(|| Status::not_found(format!("Thumbnail not found: {}", req.uuid)))?;
+        drop(conn);
+
+        let data = storage
+            .download(&s3_key)
+            .await
+            .with_context(|| format!("downloading thumbnail of file {}", req.uuid))
+            .map_err(Status::from)?;
+
+        Ok(Response::new(ThumbnailResponse {
+            data,
+            content_type: "image/jpeg".to_string(),
+            width,
+            height,
+        }))
+    }
+
     async fn delete_file(
         &self,
         request: Request<DeleteFileRequest>,
@@ -515,10 +1184,8 @@ impl FilesService for FilesServiceImpl {
         let req = request.into_inner();
         let deleted = chrono::Utc::now();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // ソフトデリート（GCSからは削除しない）
         sqlx::query("UPDATE files SET deleted_at = $1 WHERE uuid = $2::uuid")
@@ -526,21 +1193,249 @@ impl FilesService for FilesServiceImpl {
             .bind(&req.uuid)
             .execute(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
+
+        self.publish(
+            &organization_id,
+            ChangeType::Deleted,
+            File {
+                uuid: req.uuid,
+                ..Default::default()
+            },
+        );
 
         Ok(Response::new(Empty {}))
     }
 
+    async fn list_deleted_files(
+        &self,
+        request: Request<ListDeletedFilesRequest>,
+    ) -> Result<Response<ListFilesResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let paginator = Paginator::from_request(request.get_ref().pagination.as_ref())?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+        let retention_days = file_retention_days(&mut conn).await
+            .map_err(AppError::from)?;
+
+        // キーセット: (deleted_at, uuid) DESC、カーソルは前ページ最終行の uuid
+        let rows = sqlx::query_as::<_, DeletedFileRow>(
+            r#"
+            SELECT uuid::text, filename, type as file_type,
+                   to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                   to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                   NULL as blob, s3_key, storage_class,
+                   to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                   access_count_weekly, access_count_total,
+                   to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at,
+                   to_char(deleted_at + make_interval(days => $1), 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as purge_at
+            FROM files
+            WHERE deleted_at IS NOT NULL
+              AND ($2::uuid IS NULL OR (deleted_at, uuid) < (SELECT deleted_at, uuid FROM files WHERE uuid = $2::uuid))
+            ORDER BY deleted_at DESC, uuid DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(retention_days)
+        .bind(paginator.cursor(0))
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (rows, pagination) = paginator.finish(rows, |r| vec![r.file.uuid.clone()]);
+        let mut files: Vec<File> = rows
+            .iter()
+            .map(|row| File {
+                purge_at: row.purge_at.clone(),
+                ..Self::model_to_proto(&row.file)
+            })
+            .collect();
+        self.attach_thumbnail_urls(&mut conn, &mut files).await?;
+        Ok(Response::new(ListFilesResponse {
+            files,
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn restore_deleted_file(
+        &self,
+        request: Request<RestoreDeletedFileRequest>,
+    ) -> Result<Response<FileResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let uuid = Uuid::parse_str(&request.into_inner().uuid)
+            .map_err(|_| Status::invalid_argument("Invalid file uuid"))?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let restored = sqlx::query_as::<_, FileModel>(
+            r#"
+            UPDATE files SET deleted_at = NULL
+            WHERE uuid = $1 AND deleted_at IS NOT NULL
+            RETURNING uuid::text, filename, type as file_type,
+                   to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                   to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                   NULL as blob, s3_key, storage_class,
+                   to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                   access_count_weekly, access_count_total,
+                   to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
+            "#,
+        )
+        .bind(&uuid)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let Some(restored) = restored else {
+            let exists: Option<i32> = sqlx::query_scalar("SELECT 1 FROM files WHERE uuid = $1")
+                .bind(&uuid)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+            return Err(match exists {
+                Some(_) => Status::failed_precondition(format!("File is not deleted: {}", uuid)),
+                None => Status::not_found(format!("File not found: {}", uuid)),
+            });
+        };
+
+        let mut file = Self::model_to_proto(&restored);
+        self.attach_thumbnail_urls(&mut conn, std::slice::from_mut(&mut file)).await?;
+        tracing::info!("File {} restored from trash in {}", uuid, organization_id);
+        self.publish(&organization_id, ChangeType::Created, file.clone());
+        Ok(Response::new(FileResponse { file: Some(file) }))
+    }
+
+    async fn purge_file(
+        &self,
+        request: Request<PurgeFileRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let uuid = Uuid::parse_str(&request.into_inner().uuid)
+            .map_err(|_| Status::invalid_argument("Invalid file uuid"))?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let row: Option<(Option<String>, bool)> =
+            sqlx::query_as("SELECT s3_key, deleted_at IS NOT NULL FROM files WHERE uuid = $1")
+                .bind(uuid)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+        let (s3_key, deleted) =
+            row.ok_or_else(|| Status::not_found(format!("File not found: {}", uuid)))?;
+        if !deleted {
+            return Err(Status::failed_precondition(format!(
+                "File is not deleted; call DeleteFile first: {}",
+                uuid
+            )));
+        }
+
+        if !delete_file_permanently(&mut conn, self.storage.as_deref(), uuid, s3_key)
+            .await
+            .map_err(AppError::from)?
+        {
+            return Err(Status::failed_precondition(format!(
+                "File is still referenced (e.g. by a car inspection) and cannot be purged: {}",
+                uuid
+            )));
+        }
+
+        tracing::info!("File {} purged in {}", uuid, organization_id);
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn get_file_retention(
+        &self,
+        request: Request<GetFileRetentionRequest>,
+    ) -> Result<Response<FileRetention>, Status> {
+        let organization_id = get_organization_from_request(&request);
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let retention_days = file_retention_days(&mut conn).await
+            .map_err(AppError::from)?;
+        Ok(Response::new(FileRetention { retention_days }))
+    }
+
+    async fn update_file_retention(
+        &self,
+        request: Request<FileRetention>,
+    ) -> Result<Response<FileRetention>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let updated_by = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .map(|user| user.user_id.clone());
+        let req = request.into_inner();
+        if !(1..=MAX_FILE_RETENTION_DAYS).contains(&req.retention_days) {
+            return Err(Status::invalid_argument(format!(
+                "retention_days must be between 1 and {}",
+                MAX_FILE_RETENTION_DAYS
+            )));
+        }
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO file_retention_settings (organization_id, retention_days, updated_by)
+            VALUES ($1::uuid, $2, $3::uuid)
+            ON CONFLICT (organization_id)
+            DO UPDATE SET retention_days = EXCLUDED.retention_days,
+                          updated_by = EXCLUDED.updated_by,
+                          updated_at = NOW()
+            "#,
+        )
+        .bind(&organization_id)
+        .bind(req.retention_days)
+        .bind(&updated_by)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!(
+            "File retention for {} set to {} days",
+            organization_id,
+            req.retention_days
+        );
+        Ok(Response::new(FileRetention {
+            retention_days: req.retention_days,
+        }))
+    }
+
     async fn list_not_attached_files(
         &self,
         request: Request<ListFilesRequest>,
     ) -> Result<Response<ListFilesResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
-
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let paginator = Paginator::from_request(request.get_ref().pagination.as_ref())?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        if let Some(order) = OrderBy::parse(&request.get_ref().order_by, &FILE_SORT_COLUMNS)? {
+            let mut qb = Self::ordered_select(
+                "files f LEFT JOIN car_inspection_files_a cif ON f.uuid = cif.uuid",
+                "f",
+                &order,
+                &paginator,
+            )?;
+            qb.push(" AND cif.uuid IS NULL");
+            order.push_page(&mut qb, "f", &paginator);
+            let files = qb
+                .build_query_as::<FileModel>()
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+            let mut response = Self::list_response(&paginator, files);
+            self.attach_thumbnail_urls(&mut conn, &mut response.files).await?;
+            return Ok(Response::new(response));
+        }
 
         // Files that are not attached to any car inspection
         let files = sqlx::query_as::<_, FileModel>(
@@ -555,19 +1450,20 @@ impl FilesService for FilesServiceImpl {
             FROM files f
             LEFT JOIN car_inspection_files_a cif ON f.uuid = cif.uuid
             WHERE f.deleted_at IS NULL AND cif.uuid IS NULL
-            ORDER BY f.created_at DESC
+              AND ($1::uuid IS NULL OR (f.created_at, f.uuid) < (SELECT created_at, uuid FROM files WHERE uuid = $1::uuid))
+            ORDER BY f.created_at DESC, f.uuid DESC
+            LIMIT $2
             "#,
         )
+        .bind(paginator.cursor(0))
+        .bind(paginator.limit())
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
-
-        let proto_files: Vec<File> = files.iter().map(Self::model_to_proto).collect();
+        .map_err(AppError::from)?;
 
-        Ok(Response::new(ListFilesResponse {
-            files: proto_files,
-            pagination: None,
-        }))
+        let mut response = Self::list_response(&paginator, files);
+        self.attach_thumbnail_urls(&mut conn, &mut response.files).await?;
+        Ok(Response::new(response))
     }
 
     async fn list_recent_uploaded_files(
@@ -575,12 +1471,12 @@ impl FilesService for FilesServiceImpl {
         request: Request<ListFilesRequest>,
     ) -> Result<Response<ListFilesResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
+        let paginator = Paginator::from_request(request.get_ref().pagination.as_ref())?;
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
+        // 直近50件の範囲内でページング
         let files = sqlx::query_as::<_, FileModel>(
             r#"
             SELECT uuid::text, filename, type as file_type,
@@ -592,19 +1488,21 @@ impl FilesService for FilesServiceImpl {
                    to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
             FROM files
             WHERE deleted_at IS NULL
-            ORDER BY created_at DESC
+            ORDER BY created_at DESC, uuid DESC
             LIMIT 50
             "#,
         )
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
-        let proto_files: Vec<File> = files.iter().map(Self::model_to_proto).collect();
+        let (files, pagination) = paginator.paginate(files, |f| vec![f.uuid.clone()]);
+        let mut proto_files: Vec<File> = files.iter().map(Self::model_to_proto).collect();
+        self.attach_thumbnail_urls(&mut conn, &mut proto_files).await?;
 
         Ok(Response::new(ListFilesResponse {
             files: proto_files,
-            pagination: None,
+            pagination: Some(pagination),
         }))
     }
 
@@ -616,10 +1514,8 @@ impl FilesService for FilesServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // ファイル情報を取得
         let file = sqlx::query_as::<_, FileModel>(
@@ -637,7 +1533,7 @@ impl FilesService for FilesServiceImpl {
         .bind(&req.uuid)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+        .map_err(AppError::from)?
         .ok_or_else(|| Status::not_found(format!("File not found: {}", req.uuid)))?;
 
         let Some(storage) = &self.storage else {
@@ -654,7 +1550,7 @@ impl FilesService for FilesServiceImpl {
         let info = storage
             .get_object_info(gcs_key)
             .await
-            .map_err(|e| Status::internal(format!("Storage error: {}", e)))?;
+            .map_err(Status::from)?;
 
         // GCSではすべてのストレージクラスが即座にアクセス可能
         let (restore_status, message) = match info.restore_status {
@@ -673,4 +1569,433 @@ impl FilesService for FilesServiceImpl {
             storage_class: info.storage_class,
         }))
     }
+
+    async fn batch_create_files(
+        &self,
+        request: Request<BatchCreateFilesRequest>,
+    ) -> Result<Response<BatchCreateFilesResponse>, Status> {
+        let (ctx, req) = BatchContext::from_request(request, |r| r.requests.len())?;
+
+        let mut results = Vec::with_capacity(req.requests.len());
+        for entry in req.requests {
+            let result = match self.create_file(ctx.request(entry)).await {
+                Ok(response) => BatchCreateFileResult {
+                    status: Some(ok_status()),
+                    file: response.into_inner().file,
+                },
+                Err(status) => BatchCreateFileResult {
+                    status: Some(rpc_status(&status)),
+                    file: None,
+                },
+            };
+            results.push(result);
+        }
+
+        Ok(Response::new(BatchCreateFilesResponse { results }))
+    }
+
+    async fn batch_delete_files(
+        &self,
+        request: Request<BatchDeleteFilesRequest>,
+    ) -> Result<Response<BatchDeleteResponse>, Status> {
+        let (ctx, req) = BatchContext::from_request(request, |r| r.uuids.len())?;
+
+        let mut results = Vec::with_capacity(req.uuids.len());
+        for uuid in req.uuids {
+            results.push(self.delete_file(ctx.request(DeleteFileRequest { uuid })).await);
+        }
+
+        Ok(Response::new(delete_response(results)))
+    }
+
+    async fn batch_get_files(
+        &self,
+        request: Request<BatchGetFilesRequest>,
+    ) -> Result<Response<BatchGetFilesResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let (_, req) = BatchContext::from_request(request, |r| r.uuids.len())?;
+        let expires_in = signed_url_expiry(req.expires_in_minutes)?;
+
+        let ids: Vec<Uuid> = req.uuids.iter().filter_map(|u| Uuid::parse_str(u).ok()).collect();
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+        let models = sqlx::query_as::<_, FileModel>(
+            r#"
+            SELECT uuid::text, filename, type as file_type,
+                   to_char(created_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created,
+                   to_char(deleted_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as deleted,
+                   NULL as blob, s3_key, storage_class,
+                   to_char(last_accessed_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as last_accessed_at,
+                   access_count_weekly, access_count_total,
+                   to_char(promoted_to_standard_at, 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as promoted_to_standard_at
+            FROM files WHERE uuid = ANY($1)
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let mut files: Vec<File> = models.iter().map(Self::model_to_proto).collect();
+        self.attach_thumbnail_urls(&mut conn, &mut files).await?;
+        drop(conn);
+        let found: HashMap<Uuid, File> = files
+            .into_iter()
+            .filter_map(|f| Some((Uuid::parse_str(&f.uuid).ok()?, f)))
+            .collect();
+
+        // 一覧の表示用なので、URL を発行してもアクセスとしては記録しない
+        let storage = self.storage.as_ref().filter(|_| req.include_download_urls);
+        let mut results = Vec::with_capacity(req.uuids.len());
+        for uuid in &req.uuids {
+            let file = match Uuid::parse_str(uuid) {
+                Ok(id) => found
+                    .get(&id)
+                    .cloned()
+                    .ok_or_else(|| Status::not_found(format!("File not found: {}", uuid))),
+                Err(_) => Err(Status::invalid_argument(format!("Invalid file uuid: {}", uuid))),
+            };
+            let download_url = match (&file, storage) {
+                (Ok(File { s3_key: Some(s3_key), .. }), Some(storage)) => storage
+                    .signed_url(s3_key, expires_in)
+                    .await
+                    .with_context(|| format!("signing download of file {}", uuid))
+                    .map_err(Status::from)
+                    .map(|url| Some(SignedUrlResponse { url, expires_at: expires_at(expires_in) })),
+                _ => Ok(None),
+            };
+            let result = match file {
+                Ok(file) => download_url.map(|download_url| (file, download_url)),
+                Err(status) => Err(status),
+            };
+            results.push(match result {
+                Ok((file, download_url)) => BatchGetFileResult {
+                    status: Some(ok_status()),
+                    file: Some(file),
+                    download_url,
+                },
+                Err(status) => BatchGetFileResult {
+                    status: Some(rpc_status(&status)),
+                    file: None,
+                    download_url: None,
+                },
+            });
+        }
+
+        Ok(Response::new(BatchGetFilesResponse { results }))
+    }
+
+    type WatchFilesStream = tokio_stream::wrappers::ReceiverStream<Result<FileEvent, Status>>;
+
+    async fn watch_files(
+        &self,
+        request: Request<WatchFilesRequest>,
+    ) -> Result<Response<Self::WatchFilesStream>, Status> {
+        let organization_id = get_organization_from_request(&request);
+
+        Ok(Response::new(watch_stream(
+            &self.events,
+            organization_id,
+            None,
+            |event| match &event.change {
+                EntityChange::File(file) => Some(FileEvent {
+                    change_type: event.change_type as i32,
+                    file: Some(file.clone()),
+                }),
+                _ => None,
+            },
+        )))
+    }
+}
+
+/// 削除済みファイルの完全削除（スケジュール実行）
+pub const FILE_PURGE_JOB: &str = "files.retention_purge";
+
+pub const FILE_PURGE_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: FILE_PURGE_JOB,
+    description: "削除から保持日数（既定 30 日）を過ぎたファイルを DB とストレージから完全削除し、放置された分割アップロードを中止",
+    default_cron: "0 3 * * *",
+};
+
+/// DeleteFile（ソフトデリート）後の保持日数（file_retention_settings がない組織）
+const FILE_RETENTION_DAYS: i32 = 30;
+const MAX_FILE_RETENTION_DAYS: i32 = 3650;
+/// 1回の job で削除する最大件数
+const FILE_PURGE_BATCH: i64 = 500;
+/// 更新が止まった分割アップロードを中止するまでの日数
+const STALE_UPLOAD_DAYS: i32 = 7;
+
+/// 組織の削除済みファイルの保持日数（organization 設定済みの接続で呼ぶ）
+async fn file_retention_days(conn: &mut PgConnection) -> Result<i32, sqlx::Error> {
+    let days: Option<i32> = sqlx::query_scalar("SELECT retention_days FROM file_retention_settings")
+        .fetch_optional(conn)
+        .await?;
+    Ok(days.unwrap_or(FILE_RETENTION_DAYS))
+}
+
+/// ファイルを DB とストレージから完全削除する（サムネイルも）。
+/// 車検証ファイル等から参照されていて消せなければ false
+async fn delete_file_permanently(
+    conn: &mut PgConnection,
+    storage: Option<&dyn StorageBackend>,
+    uuid: Uuid,
+    s3_key: Option<String>,
+) -> Result<bool, sqlx::Error> {
+    match sqlx::query("DELETE FROM files WHERE uuid = $1")
+        .bind(uuid)
+        .execute(&mut *conn)
+        .await
+    {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    if let (Some(storage), Some(key)) = (storage, s3_key) {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("Failed to delete purged file object {}: {}", key, e.report());
+        }
+    }
+    let uuid = uuid.to_string();
+    if let Err(e) = thumbnails::delete_thumbnails(conn, storage, ThumbnailSource::File(&uuid)).await {
+        tracing::warn!("Failed to delete thumbnails of purged file {}: {}", uuid, e.report());
+    }
+    Ok(true)
+}
+
+/// 保持期間を過ぎた削除済みファイルを完全削除する job ハンドラ
+pub struct FilePurgeJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+}
+
+impl FilePurgeJobHandler {
+    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>) -> Self {
+        Self { pool, storage }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for FilePurgeJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+
+        let retention_days = file_retention_days(&mut conn).await?;
+        let expired: Vec<(Uuid, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT uuid, s3_key FROM files
+            WHERE deleted_at < NOW() - make_interval(days => $1)
+            ORDER BY deleted_at
+            LIMIT $2
+            "#,
+        )
+        .bind(retention_days)
+        .bind(FILE_PURGE_BATCH)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut purged = 0;
+        for (uuid, s3_key) in expired {
+            // 車検証ファイル等から参照されているものは外部キー違反になるので残す
+            if delete_file_permanently(&mut conn, self.storage.as_deref(), uuid, s3_key).await? {
+                purged += 1;
+            } else {
+                tracing::debug!("Skipping purge of referenced file {}", uuid);
+            }
+        }
+
+        tracing::info!("Purged {} deleted files for {}", purged, job.organization_id);
+
+        let stale: Vec<(Uuid, String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT uuid, s3_key, session_id FROM file_uploads
+            WHERE updated_at < NOW() - make_interval(days => $1)
+            ORDER BY updated_at
+            LIMIT $2
+            "#,
+        )
+        .bind(STALE_UPLOAD_DAYS)
+        .bind(FILE_PURGE_BATCH)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let aborted = stale.len();
+        for (uuid, s3_key, session_id) in stale {
+            if let Some(storage) = &self.storage {
+                // 署名付き URL への PUT は、置かれたオブジェクトを消す（無ければ失敗するだけ）
+                let result = match &session_id {
+                    Some(session_id) => storage.abort_multipart_upload(&s3_key, session_id).await,
+                    None => storage.delete(&s3_key).await,
+                };
+                if let Err(e) = result {
+                    tracing::warn!("Failed to abort stale upload {}: {}", uuid, e.report());
+                }
+            }
+            sqlx::query("DELETE FROM file_uploads WHERE uuid = $1")
+                .bind(uuid)
+                .execute(&mut *conn)
+                .await?;
+        }
+        if aborted > 0 {
+            tracing::info!("Aborted {} stale uploads for {}", aborted, job.organization_id);
+        }
+        Ok(())
+    }
+}
+
+/// アクセスの多いファイルを STANDARD に昇格する job
+pub const STORAGE_PROMOTION_JOB: &str = "files.storage_promotion";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoragePromotionPayload {
+    pub file_uuid: String,
+}
+
+/// ストレージの rewrite と DB 更新を行う job ハンドラ（rewrite は冪等なので途中で落ちても再実行してよい）
+pub struct StoragePromotionJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+}
+
+impl StoragePromotionJobHandler {
+    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>) -> Self {
+        Self { pool, storage }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for StoragePromotionJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: StoragePromotionPayload = job.payload()?;
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        let file: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT s3_key, storage_class FROM files WHERE uuid = $1::uuid AND deleted_at IS NULL",
+        )
+        .bind(&payload.file_uuid)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some((Some(gcs_key), storage_class)) = file else {
+            return Ok(());
+        };
+        if storage_class.as_deref() == Some("STANDARD") {
+            return Ok(());
+        }
+
+        // 降格したファイルは実際にクラスを戻す（Autoclass のバケットでは降格しないので rewrite_to_standard の no-op でよい）
+        let promoted = match storage_class.as_deref() {
+            Some(_) => storage.rewrite_storage_class(&gcs_key, "STANDARD").await,
+            None => storage.rewrite_to_standard(&gcs_key).await,
+        };
+        promoted.with_context(|| format!("promoting file {} to STANDARD", payload.file_uuid))?;
+        sqlx::query(
+            "UPDATE files SET storage_class = 'STANDARD', promoted_to_standard_at = NOW() WHERE uuid = $1::uuid",
+        )
+        .bind(&payload.file_uuid)
+        .execute(&mut *conn)
+        .await?;
+
+        tracing::info!("Promoted to STANDARD: uuid={}", payload.file_uuid);
+        Ok(())
+    }
+}
+
+/// 長くアクセスされていないファイルを低頻度クラスに降格する job（スケジュール実行、STORAGE_DEMOTION_DAYS 設定時のみ）
+pub const STORAGE_DEMOTION_JOB: &str = "files.storage_demotion";
+
+pub const STORAGE_DEMOTION_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: STORAGE_DEMOTION_JOB,
+    description: "最終アクセスから一定日数が過ぎたファイルを NEARLINE / STANDARD_IA に降格",
+    default_cron: "0 4 * * *",
+};
+
+/// 1回の job で降格する最大件数
+const STORAGE_DEMOTION_BATCH: i64 = 500;
+
+/// ストレージの rewrite と files.storage_class の更新を行う job ハンドラ
+pub struct StorageDemotionJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    days: i64,
+    storage_class: Option<String>,
+}
+
+impl StorageDemotionJobHandler {
+    pub fn new(
+        pool: PgPool,
+        storage: Option<Arc<dyn StorageBackend>>,
+        days: i64,
+        storage_class: Option<String>,
+    ) -> Self {
+        Self {
+            pool,
+            storage,
+            days,
+            storage_class,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for StorageDemotionJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        let target = self
+            .storage_class
+            .as_deref()
+            .unwrap_or_else(|| storage.cold_storage_class());
+
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+
+        let cold: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT uuid, s3_key FROM files
+            WHERE s3_key IS NOT NULL
+              AND deleted_at IS NULL
+              AND COALESCE(storage_class, 'STANDARD') = 'STANDARD'
+              AND COALESCE(last_accessed_at, created_at) < NOW() - make_interval(days => $1)
+            ORDER BY COALESCE(last_accessed_at, created_at)
+            LIMIT $2
+            "#,
+        )
+        .bind(self.days as i32)
+        .bind(STORAGE_DEMOTION_BATCH)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut demoted = 0;
+        for (uuid, s3_key) in cold {
+            if let Err(e) = storage.rewrite_storage_class(&s3_key, target).await {
+                tracing::warn!("Failed to demote file {} to {}: {}", uuid, target, e.report());
+                continue;
+            }
+            sqlx::query("UPDATE files SET storage_class = $2 WHERE uuid = $1")
+                .bind(uuid)
+                .bind(target)
+                .execute(&mut *conn)
+                .await?;
+            demoted += 1;
+        }
+
+        tracing::info!("Demoted {} files to {} for {}", demoted, target, job.organization_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_decoded_len() {
+        assert_eq!(base64_decoded_len("YWJj"), 3);
+        assert_eq!(base64_decoded_len("YWI="), 2);
+        assert_eq!(base64_decoded_len("YQ=="), 1);
+        assert_eq!(base64_decoded_len(""), 0);
+    }
 }
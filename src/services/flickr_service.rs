@@ -2,9 +2,13 @@ use serde::Deserialize;
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 use std::collections::HashMap;
+use std::sync::Arc;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
-use crate::db::{get_organization_from_request, set_current_organization};
+use crate::crypto::{SecretBox, FLICKR_ACCESS_TOKEN, FLICKR_ACCESS_TOKEN_SECRET};
+use crate::db::{get_organization_from_request, OrgScopedPool};
+use crate::error::{AppError, AppResult};
+use crate::http_client::HttpClient;
 use crate::proto::common::Empty;
 use crate::proto::flickr::flickr_service_server::FlickrService;
 use crate::proto::flickr::{
@@ -33,6 +37,16 @@ pub(crate) struct FlickrTokenRow {
     pub(crate) access_token_secret: String,
 }
 
+impl FlickrTokenRow {
+    /// 暗号化して保存された行を平文に戻す
+    pub(crate) fn decrypt(self, secrets: &SecretBox) -> AppResult<Self> {
+        Ok(Self {
+            access_token: secrets.decrypt(&FLICKR_ACCESS_TOKEN, &self.access_token)?,
+            access_token_secret: secrets.decrypt(&FLICKR_ACCESS_TOKEN_SECRET, &self.access_token_secret)?,
+        })
+    }
+}
+
 /// Flickr OAuth 1.0a 設定
 #[derive(Clone)]
 pub struct FlickrConfig {
@@ -56,18 +70,23 @@ impl FlickrConfig {
     }
 }
 
+/// flickr.photos.getInfo の応答を使い回す時間（写真の情報はほとんど変わらない）
+const PHOTO_INFO_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(600);
+
 pub struct FlickrServiceImpl {
     pool: PgPool,
     config: Option<FlickrConfig>,
-    http_client: reqwest::Client,
+    http_client: Arc<HttpClient>,
+    secrets: Arc<SecretBox>,
 }
 
 impl FlickrServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
+    pub fn new(pool: PgPool, http_client: Arc<HttpClient>, secrets: Arc<SecretBox>) -> Self {
         Self {
             pool,
             config: FlickrConfig::from_env(),
-            http_client: reqwest::Client::new(),
+            http_client,
+            secrets,
         }
     }
 
@@ -189,21 +208,21 @@ impl FlickrServiceImpl {
             .map(|(k, v)| (k.as_str(), v.as_str()))
             .collect();
 
-        let response = self.http_client
+        let request = self.http_client.client()
             .get(api_url)
             .header("Authorization", format!("OAuth {}", auth_header))
-            .query(&query_params)
-            .send()
+            .query(&query_params);
+        // Authorization は nonce で毎回変わるので、キーにはアクセストークンを使う
+        let response = self.http_client.send_cached(request, PHOTO_INFO_CACHE_TTL, Some(access_token))
             .await
             .map_err(|e| format!("HTTP request failed for photo {}: {}", photo_id, e))?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!("Flickr API error for photo {}: {} - {}", photo_id, status, body));
+        if !response.status.is_success() {
+            let body = String::from_utf8_lossy(&response.body);
+            return Err(format!("Flickr API error for photo {}: {} - {}", photo_id, response.status, body));
         }
 
-        let api_response: FlickrApiResponse = response.json().await
+        let api_response: FlickrApiResponse = response.json()
             .map_err(|e| format!("Failed to parse Flickr response for photo {}: {}", photo_id, e))?;
 
         if api_response.stat != "ok" {
@@ -260,11 +279,14 @@ impl FlickrService for FlickrServiceImpl {
             .join(", ");
 
         // リクエスト送信
-        let response = self
+        let request = self
             .http_client
+            .client()
             .get(request_token_url)
-            .header("Authorization", format!("OAuth {}", auth_header))
-            .send()
+            .header("Authorization", format!("OAuth {}", auth_header));
+        let response = self
+            .http_client
+            .send(request)
             .await
             .map_err(|e| Status::internal(format!("Failed to request token: {}", e)))?;
 
@@ -302,13 +324,9 @@ impl FlickrService for FlickrServiceImpl {
         // セッションをDBに保存
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+            .map_err(AppError::from)?;
 
         sqlx::query(
             r#"
@@ -321,7 +339,7 @@ impl FlickrService for FlickrServiceImpl {
         .bind(oauth_token_secret)
         .execute(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to save OAuth session: {}", e)))?;
+        .map_err(AppError::from)?;
 
         // 認可URL
         let authorization_url = format!(
@@ -382,11 +400,14 @@ impl FlickrService for FlickrServiceImpl {
             .join(", ");
 
         // リクエスト送信
-        let response = self
+        let request = self
             .http_client
+            .client()
             .get(access_token_url)
-            .header("Authorization", format!("OAuth {}", auth_header))
-            .send()
+            .header("Authorization", format!("OAuth {}", auth_header));
+        let response = self
+            .http_client
+            .send(request)
             .await
             .map_err(|e| Status::internal(format!("Failed to get access token: {}", e)))?;
 
@@ -426,13 +447,12 @@ impl FlickrService for FlickrServiceImpl {
         // トークンをDBに保存
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
+            .map_err(AppError::from)?;
 
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+        let encrypted_token = self.secrets.encrypt(&FLICKR_ACCESS_TOKEN, access_token)?;
+        let encrypted_secret = self.secrets.encrypt(&FLICKR_ACCESS_TOKEN_SECRET, access_token_secret)?;
 
         // UPSERT
         sqlx::query(
@@ -448,13 +468,13 @@ impl FlickrService for FlickrServiceImpl {
             "#,
         )
         .bind(&organization_id)
-        .bind(access_token)
-        .bind(access_token_secret)
+        .bind(&encrypted_token)
+        .bind(&encrypted_secret)
         .bind(&user_nsid)
         .bind(&username)
         .execute(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to save access token: {}", e)))?;
+        .map_err(AppError::from)?;
 
         // 古いセッションを削除
         sqlx::query("DELETE FROM flickr_oauth_sessions WHERE request_token = $1")
@@ -489,10 +509,8 @@ impl FlickrService for FlickrServiceImpl {
             Status::failed_precondition("Flickr OAuth is not configured. Set FLICKR_CONSUMER_KEY and FLICKR_CONSUMER_SECRET.")
         })?;
 
-        let mut conn = self.pool.acquire().await
-            .map_err(|e| Status::internal(format!("Failed to acquire connection: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id).await
-            .map_err(|e| Status::internal(format!("Failed to set organization: {}", e)))?;
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
 
         // アクセストークン取得
         let token = sqlx::query_as::<_, FlickrTokenRow>(
@@ -500,10 +518,11 @@ impl FlickrService for FlickrServiceImpl {
         )
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to query flickr_tokens: {}", e)))?
+        .map_err(AppError::from)?
         .ok_or_else(|| Status::failed_precondition(
             "No Flickr access token found. Please authorize via GetAuthorizationUrl first."
-        ))?;
+        ))?
+        .decrypt(&self.secrets)?;
 
         // 未検証写真を取得 (cam_files LEFT JOIN flickr_photo)
         let unverified: Vec<(String,)> = sqlx::query_as(
@@ -518,7 +537,7 @@ impl FlickrService for FlickrServiceImpl {
         .bind(limit)
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to query unverified photos: {}", e)))?;
+        .map_err(AppError::from)?;
 
         if unverified.is_empty() {
             tracing::info!("No unverified Flickr photos found");
@@ -589,7 +608,7 @@ impl FlickrService for FlickrServiceImpl {
         )
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Failed to count remaining: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let imported_count = imported.len() as i32;
         tracing::info!(
@@ -0,0 +1,535 @@
+use std::collections::BTreeMap;
+
+use chrono::{Months, NaiveDate, NaiveDateTime};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::db::{get_organization_from_request, OrgScopedPool, Paginator};
+use crate::error::AppError;
+use crate::proto::fuel::fuel_service_server::FuelService;
+use crate::proto::fuel::{
+    FuelEfficiency, FuelTransaction, ImportFuelCsvRequest, ImportFuelCsvResponse,
+    ListFuelEfficiencyRequest, ListFuelEfficiencyResponse, ListFuelTransactionsRequest,
+    ListFuelTransactionsResponse,
+};
+use crate::services::csv_import::{
+    field, parse_amount, parse_date, parse_decimal, parse_time, read_csv, CsvHeaders,
+    MAX_CSV_BYTES, MAX_REPORTED_ERRORS,
+};
+use crate::services::etc_service::{next_month, parse_month};
+use crate::services::validation;
+use crate::services::vehicle_matcher::VehicleMatcher;
+use crate::text_encoding::{decode_text, TextEncoding};
+
+/// 給油時刻の前後この範囲の運行ログから odometer を引く
+const ODOMETER_WINDOW_HOURS: i32 = 6;
+
+/// 給油カード明細の1行
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelRecord {
+    pub fueled_at: NaiveDateTime,
+    pub station: String,
+    pub product: String,
+    pub quantity: f64,
+    pub unit_price: Option<f64>,
+    pub amount: i32,
+    pub plate_number: String,
+    pub card_number: String,
+    pub odometer_km: Option<f64>,
+}
+
+/// 列の位置（カード会社ごとに見出しが違うので候補から探す）
+struct FuelColumns {
+    date: usize,
+    time: Option<usize>,
+    station: Option<usize>,
+    product: Option<usize>,
+    quantity: usize,
+    unit_price: Option<usize>,
+    amount: usize,
+    plate_number: Option<usize>,
+    card_number: Option<usize>,
+    odometer: Option<usize>,
+}
+
+impl FuelColumns {
+    fn from_headers(headers: &CsvHeaders) -> Result<Self, String> {
+        Ok(Self {
+            date: headers
+                .find_any(&["給油日", "利用日", "利用年月日", "売上日", "取引日", "日付"])
+                .ok_or("給油日（利用日）の列がありません")?,
+            time: headers.find_any(&["給油時刻", "利用時刻", "時刻", "時間"]),
+            station: headers.find_any(&["給油所", "給油所名", "SS名", "利用店舗", "店舗名", "利用場所", "スタンド名"]),
+            product: headers.find_any(&["商品名", "油種", "品名", "商品"]),
+            quantity: headers
+                .find_any(&["数量", "給油量", "数量(L)", "給油量(L)"])
+                .ok_or("数量（給油量）の列がありません")?,
+            unit_price: headers.find_any(&["単価"]),
+            amount: headers
+                .find_any(&["金額", "利用金額", "請求金額", "合計金額", "税込金額"])
+                .ok_or("金額の列がありません")?,
+            plate_number: headers.find_any(&["車番", "車両番号", "車両No", "車両NO"]),
+            card_number: headers.find(|h| h.contains("カード番号")),
+            odometer: headers.find_any(&["走行距離", "メーター", "オドメーター", "ODO"]),
+        })
+    }
+}
+
+/// 給油カード明細 CSV を読む（読めた行, 読み飛ばした行の理由）
+pub fn parse_fuel_csv(text: &str) -> Result<(Vec<FuelRecord>, Vec<String>), String> {
+    read_csv(text, FuelColumns::from_headers, parse_fuel_row)
+}
+
+fn parse_fuel_row(row: &csv::StringRecord, columns: &FuelColumns) -> Result<FuelRecord, String> {
+    let get = |idx: Option<usize>| field(row, idx);
+
+    // "2026/10/15 08:05" のように日付の列に時刻が入っていることもある
+    let date_value = get(Some(columns.date));
+    let (date_part, time_part) = match date_value.split_once(char::is_whitespace) {
+        Some((date, time)) => (date.to_string(), time.trim().to_string()),
+        None => (date_value.clone(), get(columns.time)),
+    };
+    if date_part.is_empty() {
+        return Err("給油日が空です".to_string());
+    }
+    let date = parse_date(&date_part).ok_or_else(|| format!("日付が不正です: {}", date_part))?;
+    let time = parse_time(&time_part).ok_or_else(|| format!("時刻が不正です: {}", time_part))?;
+
+    let quantity = parse_decimal(&get(Some(columns.quantity)))?.ok_or("数量が空です（燃料以外の明細）")?;
+    let amount = parse_amount(&get(Some(columns.amount)))?.ok_or("金額が空です")?;
+    Ok(FuelRecord {
+        fueled_at: date.and_time(time),
+        station: get(columns.station),
+        product: get(columns.product),
+        quantity,
+        unit_price: parse_decimal(&get(columns.unit_price))?,
+        amount,
+        plate_number: get(columns.plate_number),
+        card_number: get(columns.card_number),
+        odometer_km: parse_decimal(&get(columns.odometer))?.filter(|km| *km > 0.0),
+    })
+}
+
+/// 燃費の計算に使う給油1回分（車両ごとに給油日時順）
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct FillPoint {
+    ichiban_car_id: String,
+    car_name: Option<String>,
+    fueled_at: NaiveDateTime,
+    quantity: f64,
+    amount: i64,
+    odometer_km: Option<f64>,
+}
+
+/// 満タン法で車両・月別に集計する
+///
+/// 走行距離の分かる給油から次に分かる給油までの距離を、その間の給油量の合計で割る。
+/// メーター値が戻っている（車両の付け替え・誤入力）ときは区間を切る。
+/// from より前の給油は起点のメーター値にだけ使う。
+fn monthly_efficiency(fills: &[FillPoint], from: NaiveDate, to: NaiveDate) -> Vec<FuelEfficiency> {
+    let mut rows: BTreeMap<(String, String), FuelEfficiency> = BTreeMap::new();
+    let mut current_car: Option<&str> = None;
+    let mut last_odometer: Option<f64> = None;
+    let mut pending_liters = 0.0;
+
+    for fill in fills {
+        if current_car != Some(fill.ichiban_car_id.as_str()) {
+            current_car = Some(fill.ichiban_car_id.as_str());
+            last_odometer = None;
+            pending_liters = 0.0;
+        }
+        let date = fill.fueled_at.date();
+        let in_range = date >= from && date < to;
+        let month = fill.fueled_at.format("%Y-%m").to_string();
+
+        let mut segment = None;
+        match (fill.odometer_km, last_odometer) {
+            (Some(odometer), Some(last)) if odometer > last => {
+                segment = Some((odometer - last, pending_liters + fill.quantity));
+                last_odometer = Some(odometer);
+                pending_liters = 0.0;
+            }
+            (Some(odometer), _) => {
+                last_odometer = Some(odometer);
+                pending_liters = 0.0;
+            }
+            (None, Some(_)) => pending_liters += fill.quantity,
+            (None, None) => {}
+        }
+
+        if !in_range {
+            continue;
+        }
+        let row = rows
+            .entry((fill.ichiban_car_id.clone(), month.clone()))
+            .or_insert_with(|| FuelEfficiency {
+                ichiban_car_id: fill.ichiban_car_id.clone(),
+                car_name: fill.car_name.clone(),
+                month,
+                ..Default::default()
+            });
+        row.fill_count += 1;
+        row.liters += fill.quantity;
+        row.amount += fill.amount;
+        if let Some((distance, liters)) = segment {
+            row.distance_km += distance;
+            row.measured_liters += liters;
+        }
+    }
+
+    rows.into_values()
+        .map(|mut row| {
+            row.km_per_liter = (row.measured_liters > 0.0).then(|| row.distance_km / row.measured_liters);
+            row
+        })
+        .collect()
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct FuelTransactionRow {
+    id: i64,
+    fueled_at: NaiveDateTime,
+    station: String,
+    product: String,
+    quantity: f64,
+    unit_price: Option<f64>,
+    amount: i32,
+    plate_number: String,
+    card_number: String,
+    odometer_km: Option<f64>,
+    ichiban_car_id: Option<String>,
+    car_name: Option<String>,
+}
+
+impl FuelTransactionRow {
+    fn to_proto(&self) -> FuelTransaction {
+        FuelTransaction {
+            id: self.id,
+            fueled_at: self.fueled_at.format("%Y-%m-%d %H:%M").to_string(),
+            station: self.station.clone(),
+            product: self.product.clone(),
+            quantity: self.quantity,
+            unit_price: self.unit_price,
+            amount: self.amount,
+            plate_number: self.plate_number.clone(),
+            card_number: self.card_number.clone(),
+            odometer_km: self.odometer_km,
+            ichiban_car_id: self.ichiban_car_id.clone(),
+            car_name: self.car_name.clone(),
+        }
+    }
+}
+
+pub struct FuelServiceImpl {
+    pool: PgPool,
+}
+
+impl FuelServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl FuelService for FuelServiceImpl {
+    async fn import_fuel_csv(
+        &self,
+        request: Request<ImportFuelCsvRequest>,
+    ) -> Result<Response<ImportFuelCsvResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let mut req = request.into_inner();
+        if req.content.is_empty() {
+            return Err(Status::invalid_argument("content is required"));
+        }
+        req.filename = validation::line("filename", &req.filename, validation::FILENAME_MAX_CHARS)?;
+        if req.content.len() > MAX_CSV_BYTES {
+            return Err(Status::invalid_argument(format!(
+                "CSV is too large (max {} bytes)",
+                MAX_CSV_BYTES
+            )));
+        }
+        let encoding = TextEncoding::parse(&req.encoding).map_err(Status::invalid_argument)?;
+        let text = decode_text(&req.content, encoding).map_err(Status::invalid_argument)?;
+        let (records, mut errors) = parse_fuel_csv(&text).map_err(Status::invalid_argument)?;
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let matcher = VehicleMatcher::load(&mut tx)
+            .await
+            .map_err(AppError::from)?;
+
+        let mut imported = 0;
+        let mut duplicates = 0;
+        let mut imported_ids = Vec::new();
+        for record in &records {
+            let id: Option<i64> = sqlx::query_scalar(
+                r#"
+                INSERT INTO fuel_transactions (
+                    organization_id, fueled_at, station, product, quantity, unit_price, amount,
+                    plate_number, card_number, odometer_km, ichiban_car_id, source_filename
+                ) VALUES ($1::uuid, $2, $3, $4, $5::numeric, $6::numeric, $7, $8, $9, $10::numeric, $11, $12)
+                ON CONFLICT ON CONSTRAINT fuel_transactions_unique DO NOTHING
+                RETURNING id
+                "#,
+            )
+            .bind(&organization_id)
+            .bind(record.fueled_at)
+            .bind(&record.station)
+            .bind(&record.product)
+            .bind(record.quantity)
+            .bind(record.unit_price)
+            .bind(record.amount)
+            .bind(&record.plate_number)
+            .bind(&record.card_number)
+            .bind(record.odometer_km)
+            .bind(matcher.match_plate(&record.plate_number))
+            .bind(&req.filename)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+            match id {
+                Some(id) => {
+                    imported += 1;
+                    imported_ids.push(id);
+                }
+                None => duplicates += 1,
+            }
+        }
+
+        // 車両番号で紐づかなかった明細は、同じカードの直近の明細の車両に
+        sqlx::query(
+            r#"
+            UPDATE fuel_transactions f SET ichiban_car_id = m.ichiban_car_id
+            FROM (
+                SELECT DISTINCT ON (card_number) card_number, ichiban_car_id
+                FROM fuel_transactions
+                WHERE ichiban_car_id IS NOT NULL AND card_number <> ''
+                ORDER BY card_number, fueled_at DESC
+            ) m
+            WHERE f.ichiban_car_id IS NULL AND f.card_number = m.card_number
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        let unmatched: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM fuel_transactions WHERE id = ANY($1) AND ichiban_car_id IS NULL",
+        )
+        .bind(&imported_ids)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        tracing::info!(
+            "Fuel CSV {} imported for {}: {} new, {} duplicates, {} unmatched, {} skipped rows",
+            req.filename,
+            organization_id,
+            imported,
+            duplicates,
+            unmatched,
+            errors.len()
+        );
+        errors.truncate(MAX_REPORTED_ERRORS);
+        Ok(Response::new(ImportFuelCsvResponse {
+            imported,
+            duplicates,
+            unmatched: unmatched as i32,
+            errors,
+        }))
+    }
+
+    async fn list_fuel_transactions(
+        &self,
+        request: Request<ListFuelTransactionsRequest>,
+    ) -> Result<Response<ListFuelTransactionsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+        let (from, to) = if req.month.trim().is_empty() {
+            (None, None)
+        } else {
+            let month = parse_month(&req.month)?;
+            (Some(month), Some(next_month(month)))
+        };
+        let cursor_fueled_at = paginator
+            .cursor(0)
+            .map(|c| NaiveDateTime::parse_from_str(c, "%Y-%m-%dT%H:%M:%S"))
+            .transpose()
+            .map_err(|_| Status::invalid_argument("Invalid page_token"))?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows: Vec<FuelTransactionRow> = sqlx::query_as(
+            r#"
+            SELECT f.id, f.fueled_at, f.station, f.product, f.quantity::float8 AS quantity,
+                   f.unit_price::float8 AS unit_price, f.amount, f.plate_number, f.card_number,
+                   f.odometer_km::float8 AS odometer_km, f.ichiban_car_id, c.name AS car_name
+            FROM fuel_transactions f
+            LEFT JOIN ichiban_cars c ON c.organization_id = f.organization_id AND c.id = f.ichiban_car_id
+            WHERE ($1::text IS NULL OR f.ichiban_car_id = $1)
+              AND ($2::date IS NULL OR f.fueled_at >= $2)
+              AND ($3::date IS NULL OR f.fueled_at < $3)
+              AND (NOT $4 OR f.ichiban_car_id IS NULL)
+              AND ($5::timestamp IS NULL OR (f.fueled_at, f.id) < ($5, $6))
+            ORDER BY f.fueled_at DESC, f.id DESC
+            LIMIT $7
+            "#,
+        )
+        .bind(&req.ichiban_car_id)
+        .bind(from)
+        .bind(to)
+        .bind(req.unmatched_only)
+        .bind(cursor_fueled_at)
+        .bind(paginator.cursor_as::<i64>(1)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (rows, pagination) = paginator.finish(rows, |r| {
+            vec![r.fueled_at.format("%Y-%m-%dT%H:%M:%S").to_string(), r.id.to_string()]
+        });
+        Ok(Response::new(ListFuelTransactionsResponse {
+            transactions: rows.iter().map(FuelTransactionRow::to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn list_fuel_efficiency(
+        &self,
+        request: Request<ListFuelEfficiencyRequest>,
+    ) -> Result<Response<ListFuelEfficiencyResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let from = parse_month(&req.from_month)?;
+        let to = next_month(parse_month(&req.to_month)?);
+        if from >= to {
+            return Err(Status::invalid_argument("from_month must not be after to_month"));
+        }
+        // 月初の区間の起点に前月の給油を使う
+        let lookback = from.checked_sub_months(Months::new(1)).unwrap_or(from);
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        // メーター値は CSV の値、なければ給油時刻に最も近い運行ログの odometer
+        let fills: Vec<FillPoint> = sqlx::query_as(
+            r#"
+            SELECT f.ichiban_car_id, c.name AS car_name, f.fueled_at, f.quantity::float8 AS quantity,
+                   f.amount::bigint AS amount,
+                   COALESCE(f.odometer_km::float8, o.km) AS odometer_km
+            FROM fuel_transactions f
+            LEFT JOIN ichiban_cars c ON c.organization_id = f.organization_id AND c.id = f.ichiban_car_id
+            LEFT JOIN LATERAL (
+                SELECT trim(d.odometer)::float8 AS km
+                FROM dtako_cars_ichiban_cars m
+                JOIN dtakologs d ON d.vehicle_cd::text = m.id_dtako
+                WHERE m.id = f.ichiban_car_id
+                  AND d.odometer ~ '^\s*[0-9]+(\.[0-9]+)?\s*$'
+                  AND d.data_date_time::timestamptz
+                      BETWEEN (f.fueled_at AT TIME ZONE 'Asia/Tokyo') - make_interval(hours => $4)
+                          AND (f.fueled_at AT TIME ZONE 'Asia/Tokyo') + make_interval(hours => $4)
+                ORDER BY abs(extract(epoch FROM d.data_date_time::timestamptz - (f.fueled_at AT TIME ZONE 'Asia/Tokyo')))
+                LIMIT 1
+            ) o ON f.odometer_km IS NULL
+            WHERE f.ichiban_car_id IS NOT NULL
+              AND f.fueled_at >= $1 AND f.fueled_at < $2
+              AND ($3::text IS NULL OR f.ichiban_car_id = $3)
+            ORDER BY f.ichiban_car_id, f.fueled_at, f.id
+            "#,
+        )
+        .bind(lookback)
+        .bind(to)
+        .bind(&req.ichiban_car_id)
+        .bind(ODOMETER_WINDOW_HOURS)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListFuelEfficiencyResponse {
+            rows: monthly_efficiency(&fills, from, to),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fuel_csv() {
+        let text = "利用日,時刻,ＳＳ名,商品名,数量,単価,金額,車番,カード番号\n\
+                    2026/10/01 07:30,,帯広西SS,軽油,120.50,150.2,\"18,099\",帯広100け201,1111\n\
+                    26/10/15,18:05,帯広西SS,洗車,,,800,帯広100け201,1111\n";
+        let (records, errors) = parse_fuel_csv(text).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("3行目"));
+        assert_eq!(records[0].fueled_at.to_string(), "2026-10-01 07:30:00");
+        assert_eq!(records[0].station, "帯広西SS");
+        assert_eq!(records[0].quantity, 120.5);
+        assert_eq!(records[0].amount, 18099);
+        assert_eq!(records[0].odometer_km, None);
+    }
+
+    fn fill(car: &str, at: &str, quantity: f64, odometer_km: Option<f64>) -> FillPoint {
+        FillPoint {
+            ichiban_car_id: car.to_string(),
+            car_name: None,
+            fueled_at: NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M").unwrap(),
+            quantity,
+            amount: (quantity * 150.0) as i64,
+            odometer_km,
+        }
+    }
+
+    #[test]
+    fn test_monthly_efficiency() {
+        let fills = vec![
+            // 前月の給油は起点のメーター値だけに使う
+            fill("C1", "2026-09-28 08:00", 100.0, Some(10_000.0)),
+            fill("C1", "2026-10-05 08:00", 50.0, None),
+            fill("C1", "2026-10-10 08:00", 50.0, Some(10_400.0)),
+            // メーター値が戻ったら区間を切る
+            fill("C1", "2026-10-20 08:00", 80.0, Some(500.0)),
+            fill("C1", "2026-11-02 08:00", 40.0, Some(700.0)),
+            fill("C2", "2026-10-03 08:00", 30.0, Some(5_000.0)),
+        ];
+        let from = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2026, 12, 1).unwrap();
+        let rows = monthly_efficiency(&fills, from, to);
+        assert_eq!(rows.len(), 3);
+
+        let oct = &rows[0];
+        assert_eq!((oct.ichiban_car_id.as_str(), oct.month.as_str()), ("C1", "2026-10"));
+        assert_eq!(oct.fill_count, 3);
+        assert_eq!(oct.liters, 180.0);
+        assert_eq!(oct.distance_km, 400.0);
+        assert_eq!(oct.measured_liters, 100.0);
+        assert_eq!(oct.km_per_liter, Some(4.0));
+
+        let nov = &rows[1];
+        assert_eq!(nov.month, "2026-11");
+        assert_eq!(nov.km_per_liter, Some(5.0));
+
+        let c2 = &rows[2];
+        assert_eq!(c2.ichiban_car_id, "C2");
+        assert_eq!(c2.km_per_liter, None);
+    }
+}
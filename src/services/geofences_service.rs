@@ -0,0 +1,361 @@
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgPool};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::db::{get_organization_from_request, OrgScopedPool, Paginator};
+use crate::error::AppError;
+use crate::geocoding::GeoPoint;
+use crate::models::{GeofenceEventModel, GeofenceModel};
+use crate::proto::common::Empty;
+use crate::proto::geofences::geofences_service_server::GeofencesService;
+use crate::proto::geofences::{
+    Circle, CreateGeofenceRequest, DeleteGeofenceRequest, Geofence, GetGeofenceRequest, LatLng,
+    ListGeofenceEventsRequest, ListGeofenceEventsResponse, ListGeofencesRequest, ListGeofencesResponse,
+    Polygon, UpdateGeofenceRequest,
+};
+use crate::services::validation;
+
+const GEOFENCE_COLUMNS: &str = "id, name, shape, center_lat, center_lon, radius_m, polygon, notify, active, \
+     created_at, updated_at";
+
+const MAX_RADIUS_M: f64 = 50_000.0;
+const MAX_POLYGON_VERTICES: usize = 500;
+
+/// 検証済みの形（geofences の列の値）
+#[derive(Debug, PartialEq)]
+enum ShapeColumns {
+    Circle { lat: f64, lon: f64, radius_m: f64 },
+    Polygon(Vec<[f64; 2]>),
+}
+
+impl ShapeColumns {
+    fn shape(&self) -> &'static str {
+        match self {
+            Self::Circle { .. } => "circle",
+            Self::Polygon(_) => "polygon",
+        }
+    }
+
+    fn circle(&self) -> (Option<f64>, Option<f64>, Option<f64>) {
+        match self {
+            Self::Circle { lat, lon, radius_m } => (Some(*lat), Some(*lon), Some(*radius_m)),
+            Self::Polygon(_) => (None, None, None),
+        }
+    }
+
+    fn polygon(&self) -> Option<Json<Vec<[f64; 2]>>> {
+        match self {
+            Self::Circle { .. } => None,
+            Self::Polygon(vertices) => Some(Json(vertices.clone())),
+        }
+    }
+}
+
+fn point(value: Option<&LatLng>, field: &str) -> Result<[f64; 2], Status> {
+    let value = value.ok_or_else(|| Status::invalid_argument(format!("{} is required", field)))?;
+    GeoPoint::new(value.latitude, value.longitude)
+        .map(|p| [p.lat, p.lon])
+        .ok_or_else(|| Status::invalid_argument(format!("{} is not a valid coordinate", field)))
+}
+
+/// circle / polygon のどちらか一方を検証（どちらもなければ None）
+fn parse_shape(circle: Option<&Circle>, polygon: Option<&Polygon>) -> Result<Option<ShapeColumns>, Status> {
+    match (circle, polygon) {
+        (Some(_), Some(_)) => Err(Status::invalid_argument("Specify either circle or polygon, not both")),
+        (Some(circle), None) => {
+            let [lat, lon] = point(circle.center.as_ref(), "circle.center")?;
+            if !(1.0..=MAX_RADIUS_M).contains(&circle.radius_m) {
+                return Err(Status::invalid_argument(format!(
+                    "circle.radius_m must be between 1 and {}",
+                    MAX_RADIUS_M
+                )));
+            }
+            Ok(Some(ShapeColumns::Circle { lat, lon, radius_m: circle.radius_m }))
+        }
+        (None, Some(polygon)) => {
+            if !(3..=MAX_POLYGON_VERTICES).contains(&polygon.vertices.len()) {
+                return Err(Status::invalid_argument(format!(
+                    "polygon must have 3 to {} vertices",
+                    MAX_POLYGON_VERTICES
+                )));
+            }
+            let vertices = polygon
+                .vertices
+                .iter()
+                .map(|v| point(Some(v), "polygon.vertices"))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some(ShapeColumns::Polygon(vertices)))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+fn parse_id(id: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(id).map_err(|_| Status::invalid_argument("Invalid geofence id"))
+}
+
+pub struct GeofencesServiceImpl {
+    pool: PgPool,
+}
+
+impl GeofencesServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn fetch_geofence(conn: &mut PgConnection, id: Uuid) -> Result<GeofenceModel, Status> {
+        let geofence: Option<GeofenceModel> = sqlx::query_as(&format!(
+            "SELECT {} FROM geofences WHERE id = $1",
+            GEOFENCE_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+        .map_err(AppError::from)?;
+        geofence.ok_or_else(|| Status::not_found(format!("Geofence not found: {}", id)))
+    }
+}
+
+#[tonic::async_trait]
+impl GeofencesService for GeofencesServiceImpl {
+    async fn create_geofence(
+        &self,
+        request: Request<CreateGeofenceRequest>,
+    ) -> Result<Response<Geofence>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let name = validation::required_line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        let shape = parse_shape(req.circle.as_ref(), req.polygon.as_ref())?
+            .ok_or_else(|| Status::invalid_argument("circle or polygon is required"))?;
+        let (center_lat, center_lon, radius_m) = shape.circle();
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await.map_err(AppError::from)?;
+
+        let geofence: GeofenceModel = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO geofences
+                (organization_id, name, shape, center_lat, center_lon, radius_m, polygon, notify, active)
+            VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING {}
+            "#,
+            GEOFENCE_COLUMNS
+        ))
+        .bind(&organization_id)
+        .bind(&name)
+        .bind(shape.shape())
+        .bind(center_lat)
+        .bind(center_lon)
+        .bind(radius_m)
+        .bind(shape.polygon())
+        .bind(req.notify)
+        .bind(req.active.unwrap_or(true))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!("Geofence {} created ({})", geofence.id, geofence.shape);
+        Ok(Response::new(geofence.to_proto()))
+    }
+
+    async fn list_geofences(
+        &self,
+        request: Request<ListGeofencesRequest>,
+    ) -> Result<Response<ListGeofencesResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let include_inactive = request.into_inner().include_inactive;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await.map_err(AppError::from)?;
+
+        let geofences: Vec<GeofenceModel> = sqlx::query_as(&format!(
+            "SELECT {} FROM geofences WHERE active OR $1 ORDER BY name, created_at",
+            GEOFENCE_COLUMNS
+        ))
+        .bind(include_inactive)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListGeofencesResponse {
+            geofences: geofences.iter().map(GeofenceModel::to_proto).collect(),
+        }))
+    }
+
+    async fn get_geofence(
+        &self,
+        request: Request<GetGeofenceRequest>,
+    ) -> Result<Response<Geofence>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let id = parse_id(&request.into_inner().id)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await.map_err(AppError::from)?;
+
+        let geofence = Self::fetch_geofence(&mut conn, id).await?;
+        Ok(Response::new(geofence.to_proto()))
+    }
+
+    async fn update_geofence(
+        &self,
+        request: Request<UpdateGeofenceRequest>,
+    ) -> Result<Response<Geofence>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let id = parse_id(&req.id)?;
+        let name = req
+            .name
+            .as_deref()
+            .map(|name| validation::required_line("name", name, validation::NAME_MAX_CHARS))
+            .transpose()?;
+        let shape = parse_shape(req.circle.as_ref(), req.polygon.as_ref())?;
+
+        let mut tx = self.pool.begin_scoped(&organization_id).await.map_err(AppError::from)?;
+
+        let current = Self::fetch_geofence(&mut tx, id).await?;
+        let (center_lat, center_lon, radius_m, polygon) = match &shape {
+            Some(shape) => {
+                let (lat, lon, radius_m) = shape.circle();
+                (lat, lon, radius_m, shape.polygon())
+            }
+            None => (current.center_lat, current.center_lon, current.radius_m, current.polygon.clone()),
+        };
+
+        let geofence: GeofenceModel = sqlx::query_as(&format!(
+            r#"
+            UPDATE geofences SET
+                name = $2, shape = $3, center_lat = $4, center_lon = $5, radius_m = $6, polygon = $7,
+                notify = $8, active = $9, updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            GEOFENCE_COLUMNS
+        ))
+        .bind(id)
+        .bind(name.as_ref().unwrap_or(&current.name))
+        .bind(shape.as_ref().map_or(current.shape.as_str(), ShapeColumns::shape))
+        .bind(center_lat)
+        .bind(center_lon)
+        .bind(radius_m)
+        .bind(polygon)
+        .bind(req.notify.unwrap_or(current.notify))
+        .bind(req.active.unwrap_or(current.active))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        // 形が変わったら内外は次の運行ログから取り直す
+        if shape.is_some() {
+            sqlx::query("DELETE FROM geofence_states WHERE geofence_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        }
+        tx.commit().await.map_err(AppError::from)?;
+
+        tracing::info!("Geofence {} updated", id);
+        Ok(Response::new(geofence.to_proto()))
+    }
+
+    async fn delete_geofence(
+        &self,
+        request: Request<DeleteGeofenceRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let id = parse_id(&request.into_inner().id)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await.map_err(AppError::from)?;
+
+        let result = sqlx::query("DELETE FROM geofences WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::not_found(format!("Geofence not found: {}", id)));
+        }
+        tracing::info!("Geofence {} deleted", id);
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn list_geofence_events(
+        &self,
+        request: Request<ListGeofenceEventsRequest>,
+    ) -> Result<Response<ListGeofenceEventsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let geofence_id = if req.geofence_id.is_empty() {
+            None
+        } else {
+            Some(parse_id(&req.geofence_id)?)
+        };
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await.map_err(AppError::from)?;
+
+        let events: Vec<GeofenceEventModel> = sqlx::query_as(
+            r#"
+            SELECT e.id, e.geofence_id, g.name AS geofence_name, e.vehicle_cd, e.event_type,
+                   e.data_date_time, e.latitude, e.longitude, e.created_at
+            FROM geofence_events e
+            JOIN geofences g ON g.id = e.geofence_id
+            WHERE ($1::uuid IS NULL OR e.geofence_id = $1)
+              AND ($2::int IS NULL OR e.vehicle_cd = $2)
+              AND ($3::bigint IS NULL OR e.id < $3)
+            ORDER BY e.id DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(geofence_id)
+        .bind(req.vehicle_cd)
+        .bind(paginator.cursor_as::<i64>(0)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (events, pagination) = paginator.finish(events, |e| vec![e.id.to_string()]);
+        Ok(Response::new(ListGeofenceEventsResponse {
+            events: events.iter().map(GeofenceEventModel::to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lat_lng(latitude: f64, longitude: f64) -> LatLng {
+        LatLng { latitude, longitude }
+    }
+
+    #[test]
+    fn parse_shape_requires_exactly_one_valid_shape() {
+        let circle = Circle {
+            center: Some(lat_lng(35.0, 135.0)),
+            radius_m: 300.0,
+        };
+        let triangle = Polygon {
+            vertices: vec![lat_lng(35.0, 135.0), lat_lng(35.1, 135.0), lat_lng(35.0, 135.1)],
+        };
+
+        assert_eq!(
+            parse_shape(Some(&circle), None).unwrap(),
+            Some(ShapeColumns::Circle { lat: 35.0, lon: 135.0, radius_m: 300.0 })
+        );
+        assert_eq!(parse_shape(None, Some(&triangle)).unwrap().unwrap().shape(), "polygon");
+        assert_eq!(parse_shape(None, None).unwrap(), None);
+        assert!(parse_shape(Some(&circle), Some(&triangle)).is_err());
+
+        let too_large = Circle { radius_m: MAX_RADIUS_M + 1.0, ..circle.clone() };
+        assert!(parse_shape(Some(&too_large), None).is_err());
+        let no_center = Circle { center: None, ..circle };
+        assert!(parse_shape(Some(&no_center), None).is_err());
+        let line = Polygon { vertices: triangle.vertices[..2].to_vec() };
+        assert!(parse_shape(None, Some(&line)).is_err());
+        let off_earth = Polygon {
+            vertices: vec![lat_lng(95.0, 135.0), lat_lng(35.1, 135.0), lat_lng(35.0, 135.1)],
+        };
+        assert!(parse_shape(None, Some(&off_earth)).is_err());
+    }
+}
@@ -1,16 +1,234 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tonic::server::NamedService;
 use tonic::{Request, Response, Status};
 
+use crate::db::DEFAULT_ORGANIZATION_ID;
+use crate::dtako_api::DtakoApi;
 use crate::proto::health::{
     health_server::Health, HealthCheckRequest, HealthCheckResponse,
     health_check_response::ServingStatus,
 };
+use crate::storage::StorageBackend;
 
-#[derive(Debug, Default)]
-pub struct HealthServiceImpl;
+/// 依存関係の再チェック間隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// 1つの確認の上限（応答しない依存先でチェック全体を止めない）
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
 
-impl HealthServiceImpl {
+/// サービスごとのステータス（"" はサーバー全体）
+#[derive(Clone)]
+pub struct HealthRegistry {
+    statuses: watch::Sender<HashMap<String, ServingStatus>>,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthRegistry {
     pub fn new() -> Self {
-        Self
+        let mut statuses = HashMap::new();
+        statuses.insert(String::new(), ServingStatus::Serving);
+        let (statuses, _) = watch::channel(statuses);
+        Self { statuses }
+    }
+
+    pub fn set(&self, service: &str, status: ServingStatus) {
+        self.statuses.send_if_modified(|statuses| {
+            let previous = statuses.insert(service.to_string(), status);
+            if previous != Some(status) {
+                tracing::info!("Health: {:?} -> {:?}", service, status);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    pub fn get(&self, service: &str) -> Option<ServingStatus> {
+        self.statuses.borrow().get(service).copied()
+    }
+
+    fn subscribe(&self) -> watch::Receiver<HashMap<String, ServingStatus>> {
+        self.statuses.subscribe()
+    }
+}
+
+/// サービスが依存するもの
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependency {
+    /// PostgreSQL に接続できること
+    Database,
+    /// CAM_* 環境変数（CamConfig）が設定されていること
+    CamConfig,
+    /// FLICKR_CONSUMER_KEY/SECRET が設定され、いずれかの組織が認可済みであること
+    Flickr,
+    /// 設定されたストレージ（GCS / R2 / ローカル）のバケットに到達できること（未設定なら DB の blob なので満たす）
+    Storage,
+    /// dtako API からホーム車両一覧を取得できること（HEALTH_CHECK_DTAKO_API=true のときだけ確かめる）
+    DtakoApi,
+}
+
+/// 1回のチェックで確かめた依存関係
+#[derive(Debug, Clone, Copy, Default)]
+struct Probes {
+    database: bool,
+    cam_config: bool,
+    flickr: bool,
+    storage: bool,
+    dtako_api: bool,
+}
+
+impl Probes {
+    fn satisfied(&self, dependency: &Dependency) -> bool {
+        match dependency {
+            Dependency::Database => self.database,
+            Dependency::CamConfig => self.cam_config,
+            Dependency::Flickr => self.flickr,
+            Dependency::Storage => self.storage,
+            Dependency::DtakoApi => self.dtako_api,
+        }
+    }
+
+    fn status(&self, dependencies: &[Dependency]) -> ServingStatus {
+        if dependencies.iter().all(|d| self.satisfied(d)) {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        }
+    }
+}
+
+/// PROBE_TIMEOUT 以内に成功したか（失敗は warn ログ）
+async fn probe<E: std::fmt::Display>(name: &str, check: impl Future<Output = Result<(), E>>) -> bool {
+    match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            tracing::warn!("Health: {} check failed: {}", name, e);
+            false
+        }
+        Err(_) => {
+            tracing::warn!("Health: {} check timed out after {:?}", name, PROBE_TIMEOUT);
+            false
+        }
+    }
+}
+
+/// 依存関係を定期チェックして HealthRegistry を更新する
+pub struct HealthChecker {
+    pool: PgPool,
+    registry: HealthRegistry,
+    cam_configured: bool,
+    flickr_configured: bool,
+    storage: Option<Arc<dyn StorageBackend>>,
+    dtako_api: Option<Arc<dyn DtakoApi>>,
+    services: Vec<(&'static str, &'static [Dependency])>,
+}
+
+impl HealthChecker {
+    pub fn new(pool: PgPool, registry: HealthRegistry, cam_configured: bool, flickr_configured: bool) -> Self {
+        Self {
+            pool,
+            registry,
+            cam_configured,
+            flickr_configured,
+            storage: None,
+            dtako_api: None,
+            services: Vec::new(),
+        }
+    }
+
+    /// ストレージのバケットも確かめる
+    pub fn storage(mut self, storage: Option<Arc<dyn StorageBackend>>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// dtako API も確かめる（指定しなければ Dependency::DtakoApi は常に満たす）
+    pub fn dtako_api(mut self, dtako_api: Arc<dyn DtakoApi>) -> Self {
+        self.dtako_api = Some(dtako_api);
+        self
+    }
+
+    /// 登録済みサービスとその依存関係を追加
+    pub fn service<S: NamedService>(mut self, dependencies: &'static [Dependency]) -> Self {
+        self.services.push((S::NAME, dependencies));
+        self
+    }
+
+    /// 1回チェックしてステータスを更新
+    pub async fn check_once(&self) {
+        let database = probe("database", async {
+            sqlx::query("SELECT 1").execute(&self.pool).await.map(|_| ())
+        })
+        .await;
+
+        let flickr = self.flickr_configured
+            && database
+            && probe("flickr token", async {
+                match sqlx::query_scalar::<_, bool>("SELECT flickr_tokens_exist()")
+                    .fetch_one(&self.pool)
+                    .await?
+                {
+                    true => Ok(()),
+                    false => Err(sqlx::Error::RowNotFound),
+                }
+            })
+            .await;
+
+        let storage = match &self.storage {
+            Some(storage) => probe("storage", storage.probe()).await,
+            None => true,
+        };
+        let dtako_api = match &self.dtako_api {
+            Some(dtako_api) => probe("dtako API", async { dtako_api.home_cars(DEFAULT_ORGANIZATION_ID, false).await.map(|_| ()) }).await,
+            None => true,
+        };
+
+        let probes = Probes {
+            database,
+            cam_config: self.cam_configured,
+            flickr,
+            storage,
+            dtako_api,
+        };
+        for (service, dependencies) in &self.services {
+            self.registry.set(service, probes.status(dependencies));
+        }
+        // サーバー全体は DB のみで判断する
+        self.registry.set("", probes.status(&[Dependency::Database]));
+    }
+
+    /// 初回チェック後、CHECK_INTERVAL ごとに再チェックするタスクを起動
+    pub async fn spawn(self) {
+        self.check_once().await;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                self.check_once().await;
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct HealthServiceImpl {
+    registry: HealthRegistry,
+}
+
+impl HealthServiceImpl {
+    pub fn new(registry: HealthRegistry) -> Self {
+        Self { registry }
     }
 }
 
@@ -18,10 +236,16 @@ impl HealthServiceImpl {
 impl Health for HealthServiceImpl {
     async fn check(
         &self,
-        _request: Request<HealthCheckRequest>,
+        request: Request<HealthCheckRequest>,
     ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service = request.into_inner().service;
+        let status = self
+            .registry
+            .get(&service)
+            .ok_or_else(|| Status::not_found(format!("Unknown service: {}", service)))?;
+
         Ok(Response::new(HealthCheckResponse {
-            status: ServingStatus::Serving.into(),
+            status: status.into(),
         }))
     }
 
@@ -29,16 +253,96 @@ impl Health for HealthServiceImpl {
 
     async fn watch(
         &self,
-        _request: Request<HealthCheckRequest>,
+        request: Request<HealthCheckRequest>,
     ) -> Result<Response<Self::WatchStream>, Status> {
-        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let service = request.into_inner().service;
+        let mut statuses = self.registry.subscribe();
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
 
+        // 現在値を送り、以降は変化したときだけ送る
         tokio::spawn(async move {
-            let _ = tx.send(Ok(HealthCheckResponse {
-                status: ServingStatus::Serving.into(),
-            })).await;
+            let mut last = None;
+            loop {
+                let status = statuses
+                    .borrow_and_update()
+                    .get(&service)
+                    .copied()
+                    .unwrap_or(ServingStatus::ServiceUnknown);
+                if last != Some(status) {
+                    last = Some(status);
+                    let response = HealthCheckResponse { status: status.into() };
+                    if tx.send(Ok(response)).await.is_err() {
+                        break;
+                    }
+                }
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    changed = statuses.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
         });
 
         Ok(Response::new(tokio_stream::wrappers::ReceiverStream::new(rx)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probes_status_per_service() {
+        let probes = Probes {
+            database: true,
+            cam_config: false,
+            flickr: true,
+            storage: false,
+            dtako_api: true,
+        };
+        assert_eq!(probes.status(&[Dependency::Database]), ServingStatus::Serving);
+        assert_eq!(
+            probes.status(&[Dependency::Database, Dependency::Storage]),
+            ServingStatus::NotServing
+        );
+        assert_eq!(
+            probes.status(&[Dependency::Database, Dependency::CamConfig]),
+            ServingStatus::NotServing
+        );
+        assert_eq!(
+            probes.status(&[Dependency::Database, Dependency::DtakoApi]),
+            ServingStatus::Serving
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe() {
+        assert!(probe("ok", async { Ok::<(), String>(()) }).await);
+        assert!(!probe("err", async { Err("boom".to_string()) }).await);
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_registered_status() {
+        let registry = HealthRegistry::new();
+        registry.set("logi.cam_files.CamFilesService", ServingStatus::NotServing);
+        let service = HealthServiceImpl::new(registry);
+
+        let check = |name: &str| {
+            service.check(Request::new(HealthCheckRequest {
+                service: name.to_string(),
+            }))
+        };
+        assert_eq!(
+            check("").await.unwrap().into_inner().status,
+            ServingStatus::Serving as i32
+        );
+        assert_eq!(
+            check("logi.cam_files.CamFilesService").await.unwrap().into_inner().status,
+            ServingStatus::NotServing as i32
+        );
+        assert_eq!(check("nope").await.unwrap_err().code(), tonic::Code::NotFound);
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::HomeCarEntry;
+
+/// dtako API の前回取得できたホーム車両一覧（取得失敗時の代替に使う）
+pub struct HomeCarCache {
+    last: RwLock<Option<(Arc<Vec<HomeCarEntry>>, DateTime<Utc>)>>,
+    /// これより古い一覧は代替に使わない
+    max_stale: Duration,
+}
+
+/// ListRenewHomeTargets に渡すホーム車両一覧
+#[derive(Debug, Clone)]
+pub struct HomeCarList {
+    pub cars: Arc<Vec<HomeCarEntry>>,
+    pub fetched_at: DateTime<Utc>,
+    /// 取得に失敗して前回の一覧を使った
+    pub stale: bool,
+    pub error: Option<String>,
+}
+
+impl HomeCarCache {
+    pub fn new(max_stale_secs: u64) -> Self {
+        Self {
+            last: RwLock::new(None),
+            max_stale: Duration::seconds(max_stale_secs as i64),
+        }
+    }
+
+    /// 取得に成功した一覧を保存する
+    pub fn store(&self, cars: Vec<HomeCarEntry>) -> HomeCarList {
+        self.store_at(cars, Utc::now())
+    }
+
+    /// 取得失敗時、期限内の前回の一覧があれば返す
+    pub fn fallback(&self, error: String) -> Option<HomeCarList> {
+        self.fallback_at(error, Utc::now())
+    }
+
+    fn store_at(&self, cars: Vec<HomeCarEntry>, now: DateTime<Utc>) -> HomeCarList {
+        let cars = Arc::new(cars);
+        *self.last.write().unwrap_or_else(|e| e.into_inner()) = Some((cars.clone(), now));
+        HomeCarList {
+            cars,
+            fetched_at: now,
+            stale: false,
+            error: None,
+        }
+    }
+
+    fn fallback_at(&self, error: String, now: DateTime<Utc>) -> Option<HomeCarList> {
+        let last = self.last.read().unwrap_or_else(|e| e.into_inner());
+        let (cars, fetched_at) = last.as_ref()?;
+        if now - *fetched_at > self.max_stale {
+            return None;
+        }
+        Some(HomeCarList {
+            cars: cars.clone(),
+            fetched_at: *fetched_at,
+            stale: true,
+            error: Some(error),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car(vehicle_cd: i64) -> HomeCarEntry {
+        HomeCarEntry {
+            vehicle_cd,
+            vehicle_name: None,
+            all_state: None,
+        }
+    }
+
+    #[test]
+    fn test_fallback_serves_last_list_until_max_stale() {
+        let cache = HomeCarCache::new(3600);
+        let now = Utc::now();
+        assert!(cache.fallback_at("down".to_string(), now).is_none());
+
+        cache.store_at(vec![car(1), car(2)], now);
+        let list = cache
+            .fallback_at("down".to_string(), now + Duration::minutes(30))
+            .unwrap();
+        assert!(list.stale);
+        assert_eq!(list.cars.len(), 2);
+        assert_eq!(list.fetched_at, now);
+        assert_eq!(list.error.as_deref(), Some("down"));
+
+        assert!(cache
+            .fallback_at("down".to_string(), now + Duration::hours(2))
+            .is_none());
+    }
+}
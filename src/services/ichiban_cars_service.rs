@@ -0,0 +1,598 @@
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use tonic::{Request, Response, Status};
+
+use crate::db::{get_organization_from_request, AuditEvent, OrgScopedPool, Paginator};
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::IchibanCarModel;
+use crate::proto::common::Empty;
+use crate::proto::ichiban_cars::ichiban_cars_service_server::IchibanCarsService;
+use crate::proto::ichiban_cars::{
+    DeleteDtakoCarMappingRequest, DeleteIchibanCarRequest, DtakoCarMapping, GetIchibanCarRequest,
+    IchibanCar, ImportIchibanCarsCsvRequest, ImportIchibanCarsCsvResponse, ListIchibanCarsRequest,
+    ListIchibanCarsResponse, SetDtakoCarMappingRequest,
+};
+use crate::services::csv_import::{
+    field, parse_decimal, read_csv, CsvHeaders, MAX_CSV_BYTES, MAX_REPORTED_ERRORS,
+};
+use crate::services::validation;
+use crate::text_encoding::{decode_text, TextEncoding};
+
+const CAR_COLUMNS: &str = r#"
+    c.id, c.id4, c.name, c.name_r, c.shashu, c.sekisai::float8 AS sekisai,
+    c.reg_date, c.parch_date, c.scrap_date, c.bumon_code_id, c.driver_id,
+    ARRAY(
+        SELECT m.id_dtako FROM dtako_cars_ichiban_cars m
+        WHERE m.organization_id = c.organization_id AND m.id = c.id
+        ORDER BY m.id_dtako
+    ) AS dtako_ids,
+    c.created_at, c.modified_at
+"#;
+
+/// 車両マスタの1件（RPC・CSV 共通、検証済み）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IchibanCarRecord {
+    pub id: String,
+    pub id4: String,
+    pub name: Option<String>,
+    pub name_r: Option<String>,
+    pub shashu: String,
+    pub sekisai: Option<f64>,
+    pub reg_date: Option<String>,
+    pub parch_date: Option<String>,
+    pub scrap_date: Option<String>,
+    pub bumon_code_id: Option<String>,
+    pub driver_id: Option<String>,
+    /// CSV のデジタコ車両の列（RPC では使わない）
+    pub dtako_ids: Vec<String>,
+}
+
+fn optional_line(field: &str, value: Option<&str>) -> Result<Option<String>, Status> {
+    Ok(value
+        .map(|v| validation::line(field, v, validation::NAME_MAX_CHARS))
+        .transpose()?
+        .and_then(validation::non_empty))
+}
+
+fn optional_code(field: &str, value: Option<&str>) -> Result<Option<String>, Status> {
+    Ok(value
+        .map(|v| validation::code(field, v, validation::CODE_MAX_CHARS))
+        .transpose()?
+        .and_then(validation::non_empty))
+}
+
+fn required_code(field: &str, value: &str) -> Result<String, Status> {
+    let value = validation::code(field, value, validation::CODE_MAX_CHARS)?;
+    if value.is_empty() {
+        return Err(Status::invalid_argument(format!("{} is required", field)));
+    }
+    Ok(value)
+}
+
+impl IchibanCarRecord {
+    pub fn from_proto(car: &IchibanCar) -> Result<Self, Status> {
+        if car.sekisai.is_some_and(|v| !v.is_finite() || v < 0.0) {
+            return Err(Status::invalid_argument("sekisai must be a non-negative number"));
+        }
+        Ok(Self {
+            id: required_code("id", &car.id)?,
+            id4: required_code("id4", &car.id4)?,
+            name: optional_line("name", car.name.as_deref())?,
+            name_r: optional_line("name_r", car.name_r.as_deref())?,
+            shashu: validation::line("shashu", &car.shashu, validation::NAME_MAX_CHARS)?,
+            sekisai: car.sekisai,
+            reg_date: optional_code("reg_date", car.reg_date.as_deref())?,
+            parch_date: optional_code("parch_date", car.parch_date.as_deref())?,
+            scrap_date: optional_code("scrap_date", car.scrap_date.as_deref())?,
+            bumon_code_id: optional_code("bumon_code_id", car.bumon_code_id.as_deref())?,
+            driver_id: optional_code("driver_id", car.driver_id.as_deref())?,
+            dtako_ids: Vec::new(),
+        })
+    }
+}
+
+fn model_to_proto(model: &IchibanCarModel) -> IchibanCar {
+    IchibanCar {
+        id: model.id.clone(),
+        id4: model.id4.clone(),
+        name: model.name.clone(),
+        name_r: model.name_r.clone(),
+        shashu: model.shashu.clone(),
+        sekisai: model.sekisai,
+        reg_date: model.reg_date.clone(),
+        parch_date: model.parch_date.clone(),
+        scrap_date: model.scrap_date.clone(),
+        bumon_code_id: model.bumon_code_id.clone(),
+        driver_id: model.driver_id.clone(),
+        dtako_ids: model.dtako_ids.clone(),
+        created_at: model.created_at.to_rfc3339(),
+        modified_at: model.modified_at.to_rfc3339(),
+    }
+}
+
+/// 列の位置（見出しの候補から探す）
+struct IchibanCarColumns {
+    id: usize,
+    id4: usize,
+    name: Option<usize>,
+    name_r: Option<usize>,
+    shashu: Option<usize>,
+    sekisai: Option<usize>,
+    reg_date: Option<usize>,
+    parch_date: Option<usize>,
+    scrap_date: Option<usize>,
+    bumon_code_id: Option<usize>,
+    driver_id: Option<usize>,
+    dtako_ids: Option<usize>,
+}
+
+impl IchibanCarColumns {
+    fn from_headers(headers: &CsvHeaders) -> Result<Self, String> {
+        Ok(Self {
+            id: headers
+                .find_any(&["id", "ID", "車両ID", "車輌ID", "車両コード"])
+                .ok_or("車両ID（id）の列がありません")?,
+            id4: headers
+                .find_any(&["id4", "ID4", "車番", "下4桁", "車両番号下4桁"])
+                .ok_or("車番（id4）の列がありません")?,
+            name: headers.find_any(&["name", "車両番号", "登録番号", "車両名"]),
+            name_r: headers.find_any(&["name_r", "車両番号(略)", "略称"]),
+            shashu: headers.find_any(&["shashu", "車種"]),
+            sekisai: headers.find_any(&["sekisai", "積載量", "最大積載量"]),
+            reg_date: headers.find_any(&["reg_date", "登録日", "登録年月日"]),
+            parch_date: headers.find_any(&["parch_date", "購入日", "購入年月日"]),
+            scrap_date: headers.find_any(&["scrap_date", "廃車日", "廃車年月日"]),
+            bumon_code_id: headers.find_any(&["bumon_code_id", "部門コード", "部門"]),
+            driver_id: headers.find_any(&["driver_id", "運転者ID", "乗務員コード"]),
+            dtako_ids: headers.find_any(&["id_dtako", "デジタコID", "デジタコ車両ID"]),
+        })
+    }
+}
+
+/// 車両マスタ CSV を読む（読めた行, 読み飛ばした行の理由）
+pub fn parse_ichiban_cars_csv(text: &str) -> Result<(Vec<IchibanCarRecord>, Vec<String>), String> {
+    read_csv(text, IchibanCarColumns::from_headers, parse_ichiban_car_row)
+}
+
+fn parse_ichiban_car_row(
+    row: &csv::StringRecord,
+    columns: &IchibanCarColumns,
+) -> Result<IchibanCarRecord, String> {
+    let get = |idx: Option<usize>| field(row, idx);
+    let sekisai = parse_decimal(&get(columns.sekisai))?;
+    let car = IchibanCar {
+        id: get(Some(columns.id)),
+        id4: get(Some(columns.id4)),
+        name: Some(get(columns.name)),
+        name_r: Some(get(columns.name_r)),
+        shashu: get(columns.shashu),
+        sekisai,
+        reg_date: Some(get(columns.reg_date)),
+        parch_date: Some(get(columns.parch_date)),
+        scrap_date: Some(get(columns.scrap_date)),
+        bumon_code_id: Some(get(columns.bumon_code_id)),
+        driver_id: Some(get(columns.driver_id)),
+        ..Default::default()
+    };
+    let mut record = IchibanCarRecord::from_proto(&car).map_err(|e| e.message().to_string())?;
+    // "101/102" のように複数のデジタコ車両を並べてもよい
+    for id_dtako in get(columns.dtako_ids).split(['/', ';', '、', ' ']) {
+        let id_dtako = validation::code("id_dtako", id_dtako, validation::CODE_MAX_CHARS)
+            .map_err(|e| e.message().to_string())?;
+        if !id_dtako.is_empty() && !record.dtako_ids.contains(&id_dtako) {
+            record.dtako_ids.push(id_dtako);
+        }
+    }
+    Ok(record)
+}
+
+async fn fetch_car(conn: &mut PgConnection, id: &str) -> Result<IchibanCarModel, Status> {
+    let model = sqlx::query_as::<_, IchibanCarModel>(&format!(
+        "SELECT {} FROM ichiban_cars c WHERE c.id = $1",
+        CAR_COLUMNS
+    ))
+    .bind(id)
+    .fetch_one(conn)
+    .await
+    .map_err(AppError::from)?;
+    Ok(model)
+}
+
+/// id が同じ車両があれば更新（戻り値は新規登録なら true）
+async fn upsert_car(conn: &mut PgConnection, car: &IchibanCarRecord) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO ichiban_cars (
+            id, organization_id, id4, name, name_r, shashu, sekisai,
+            reg_date, parch_date, scrap_date, bumon_code_id, driver_id
+        ) VALUES (
+            $1, current_setting('app.current_organization_id')::uuid, $2, $3, $4, $5, $6::numeric,
+            $7, $8, $9, $10, $11
+        )
+        ON CONFLICT (organization_id, id) DO UPDATE SET
+            id4 = EXCLUDED.id4, name = EXCLUDED.name, name_r = EXCLUDED.name_r,
+            shashu = EXCLUDED.shashu, sekisai = EXCLUDED.sekisai,
+            reg_date = EXCLUDED.reg_date, parch_date = EXCLUDED.parch_date,
+            scrap_date = EXCLUDED.scrap_date, bumon_code_id = EXCLUDED.bumon_code_id,
+            driver_id = EXCLUDED.driver_id, modified_at = NOW()
+        RETURNING (xmax = 0)
+        "#,
+    )
+    .bind(&car.id)
+    .bind(&car.id4)
+    .bind(&car.name)
+    .bind(&car.name_r)
+    .bind(&car.shashu)
+    .bind(car.sekisai)
+    .bind(&car.reg_date)
+    .bind(&car.parch_date)
+    .bind(&car.scrap_date)
+    .bind(&car.bumon_code_id)
+    .bind(&car.driver_id)
+    .fetch_one(conn)
+    .await
+}
+
+/// デジタコ車両の対応を登録（既存の対応は付け替え）
+async fn upsert_mapping(conn: &mut PgConnection, id_dtako: &str, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO dtako_cars_ichiban_cars (id_dtako, organization_id, id)
+        VALUES ($1, current_setting('app.current_organization_id')::uuid, $2)
+        ON CONFLICT (organization_id, id_dtako) DO UPDATE SET id = EXCLUDED.id
+        "#,
+    )
+    .bind(id_dtako)
+    .bind(id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub struct IchibanCarsServiceImpl {
+    pool: PgPool,
+}
+
+impl IchibanCarsServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    /// 管理者確認 + organization 設定済みのトランザクション
+    async fn admin_tx<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(AuthenticatedUser, sqlx::Transaction<'static, sqlx::Postgres>), Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok((auth_user, tx))
+    }
+}
+
+#[tonic::async_trait]
+impl IchibanCarsService for IchibanCarsServiceImpl {
+    async fn list_ichiban_cars(
+        &self,
+        request: Request<ListIchibanCarsRequest>,
+    ) -> Result<Response<ListIchibanCarsResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+        let query = validation::code("query", &req.query, validation::NAME_MAX_CHARS)?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let cars = sqlx::query_as::<_, IchibanCarModel>(&format!(
+            r#"
+            SELECT {}
+            FROM ichiban_cars c
+            WHERE ($1 = '' OR c.id ILIKE '%' || $1 || '%' OR c.id4 ILIKE '%' || $1 || '%'
+                   OR c.name ILIKE '%' || $1 || '%' OR c.name_r ILIKE '%' || $1 || '%')
+              AND (NOT $2 OR COALESCE(c.scrap_date, '') = '')
+              AND ($3::text IS NULL OR c.id > $3)
+            ORDER BY c.id
+            LIMIT $4
+            "#,
+            CAR_COLUMNS
+        ))
+        .bind(&query)
+        .bind(req.exclude_scrapped)
+        .bind(paginator.cursor(0))
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (cars, pagination) = paginator.finish(cars, |c| vec![c.id.clone()]);
+        Ok(Response::new(ListIchibanCarsResponse {
+            cars: cars.iter().map(model_to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn get_ichiban_car(
+        &self,
+        request: Request<GetIchibanCarRequest>,
+    ) -> Result<Response<IchibanCar>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&organization_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let car = fetch_car(&mut conn, &req.id).await?;
+        Ok(Response::new(model_to_proto(&car)))
+    }
+
+    async fn create_ichiban_car(
+        &self,
+        request: Request<IchibanCar>,
+    ) -> Result<Response<IchibanCar>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let car = IchibanCarRecord::from_proto(request.get_ref())?;
+
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM ichiban_cars WHERE id = $1)")
+            .bind(&car.id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        if exists {
+            return Err(Status::already_exists(format!("Car {} already exists", car.id)));
+        }
+        upsert_car(&mut tx, &car).await.map_err(AppError::from)?;
+        AuditEvent::new("ichiban_car.created", "ichiban_car", &car.id)
+            .actor(&auth_user.user_id)
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let model = fetch_car(&mut tx, &car.id).await?;
+        tx.commit().await.map_err(AppError::from)?;
+        Ok(Response::new(model_to_proto(&model)))
+    }
+
+    async fn update_ichiban_car(
+        &self,
+        request: Request<IchibanCar>,
+    ) -> Result<Response<IchibanCar>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let car = IchibanCarRecord::from_proto(request.get_ref())?;
+
+        // 存在しなければ NOT_FOUND（更新で新規登録はしない）
+        fetch_car(&mut tx, &car.id).await?;
+        upsert_car(&mut tx, &car).await.map_err(AppError::from)?;
+        AuditEvent::new("ichiban_car.updated", "ichiban_car", &car.id)
+            .actor(&auth_user.user_id)
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let model = fetch_car(&mut tx, &car.id).await?;
+        tx.commit().await.map_err(AppError::from)?;
+        Ok(Response::new(model_to_proto(&model)))
+    }
+
+    async fn delete_ichiban_car(
+        &self,
+        request: Request<DeleteIchibanCarRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let id = request.into_inner().id;
+
+        let deleted = sqlx::query("DELETE FROM ichiban_cars WHERE id = $1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        if deleted.rows_affected() == 0 {
+            return Err(Status::not_found(format!("Car {} not found", id)));
+        }
+        let mappings = sqlx::query("DELETE FROM dtako_cars_ichiban_cars WHERE id = $1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        AuditEvent::new("ichiban_car.deleted", "ichiban_car", &id)
+            .actor(&auth_user.user_id)
+            .details(json!({ "dtako_mappings": mappings.rows_affected() }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit().await.map_err(AppError::from)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn set_dtako_car_mapping(
+        &self,
+        request: Request<SetDtakoCarMappingRequest>,
+    ) -> Result<Response<DtakoCarMapping>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let req = request.get_ref();
+        let id_dtako = required_code("id_dtako", &req.id_dtako)?;
+        let id = required_code("id", &req.id)?;
+
+        fetch_car(&mut tx, &id).await?;
+        upsert_mapping(&mut tx, &id_dtako, &id)
+            .await
+            .map_err(AppError::from)?;
+        AuditEvent::new("ichiban_car.dtako_mapped", "ichiban_car", &id)
+            .actor(&auth_user.user_id)
+            .details(json!({ "id_dtako": id_dtako }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit().await.map_err(AppError::from)?;
+        Ok(Response::new(DtakoCarMapping { id_dtako, id }))
+    }
+
+    async fn delete_dtako_car_mapping(
+        &self,
+        request: Request<DeleteDtakoCarMappingRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let id_dtako = request.into_inner().id_dtako;
+
+        let id: Option<Option<String>> =
+            sqlx::query_scalar("DELETE FROM dtako_cars_ichiban_cars WHERE id_dtako = $1 RETURNING id")
+                .bind(&id_dtako)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        let Some(id) = id else {
+            return Err(Status::not_found(format!("Mapping for {} not found", id_dtako)));
+        };
+        AuditEvent::new("ichiban_car.dtako_unmapped", "ichiban_car", id.unwrap_or_default())
+            .actor(&auth_user.user_id)
+            .details(json!({ "id_dtako": id_dtako }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit().await.map_err(AppError::from)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn import_ichiban_cars_csv(
+        &self,
+        request: Request<ImportIchibanCarsCsvRequest>,
+    ) -> Result<Response<ImportIchibanCarsCsvResponse>, Status> {
+        let (auth_user, mut tx) = self.admin_tx(&request).await?;
+        let mut req = request.into_inner();
+        if req.content.is_empty() {
+            return Err(Status::invalid_argument("content is required"));
+        }
+        req.filename = validation::line("filename", &req.filename, validation::FILENAME_MAX_CHARS)?;
+        if req.content.len() > MAX_CSV_BYTES {
+            return Err(Status::invalid_argument(format!(
+                "CSV is too large (max {} bytes)",
+                MAX_CSV_BYTES
+            )));
+        }
+        let encoding = TextEncoding::parse(&req.encoding).map_err(Status::invalid_argument)?;
+        let text = decode_text(&req.content, encoding).map_err(Status::invalid_argument)?;
+        let (records, mut errors) = parse_ichiban_cars_csv(&text).map_err(Status::invalid_argument)?;
+
+        let mut created = 0;
+        let mut updated = 0;
+        let mut mappings = 0;
+        for record in &records {
+            if upsert_car(&mut tx, record).await.map_err(AppError::from)? {
+                created += 1;
+            } else {
+                updated += 1;
+            }
+            for id_dtako in &record.dtako_ids {
+                upsert_mapping(&mut tx, id_dtako, &record.id)
+                    .await
+                    .map_err(AppError::from)?;
+                mappings += 1;
+            }
+        }
+
+        if req.dry_run {
+            tx.rollback().await.map_err(AppError::from)?;
+        } else {
+            AuditEvent::new("ichiban_car.imported", "ichiban_car", "")
+                .actor(&auth_user.user_id)
+                .details(json!({
+                    "filename": req.filename,
+                    "created": created,
+                    "updated": updated,
+                    "mappings": mappings,
+                    "skipped": errors.len(),
+                }))
+                .record(&mut tx, &auth_user.org_id)
+                .await
+                .map_err(AppError::from)?;
+            tx.commit().await.map_err(AppError::from)?;
+        }
+
+        tracing::info!(
+            "Ichiban cars CSV {} {} for {}: {} created, {} updated, {} mappings, {} skipped rows",
+            req.filename,
+            if req.dry_run { "checked" } else { "imported" },
+            auth_user.org_id,
+            created,
+            updated,
+            mappings,
+            errors.len()
+        );
+        errors.truncate(MAX_REPORTED_ERRORS);
+        Ok(Response::new(ImportIchibanCarsCsvResponse {
+            created,
+            updated,
+            mappings,
+            errors,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ichiban_cars_csv() {
+        let text = "車両ID,車番,車両番号,車種,積載量,廃車日,デジタコID\n\
+                    ＣＡＲ１,０２０１,帯広100け201,大型,\"13,500\",,101/102\n\
+                    ,0202,帯広100け202,中型,,,\n\
+                    C3,0203,帯広100け203,中型,abc,,\n";
+        let (records, errors) = parse_ichiban_cars_csv(text).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("3行目"));
+        assert!(errors[1].starts_with("4行目"));
+
+        let car = &records[0];
+        assert_eq!(car.id, "CAR1");
+        assert_eq!(car.id4, "0201");
+        assert_eq!(car.name.as_deref(), Some("帯広100け201"));
+        assert_eq!(car.sekisai, Some(13_500.0));
+        assert_eq!(car.scrap_date, None);
+        assert_eq!(car.dtako_ids, vec!["101", "102"]);
+
+        assert!(parse_ichiban_cars_csv("name,車種\nx,y\n").is_err());
+    }
+}
@@ -1,24 +1,75 @@
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
-use crate::db::organization::{get_organization_from_request, set_current_organization, set_current_user};
+use crate::db::organization::{get_organization_from_request, set_current_user, OrgScopedPool};
+use crate::error::AppError;
 use crate::middleware::AuthenticatedUser;
 use crate::models::ItemModel;
-use crate::proto::common::Empty;
+use crate::events::{watch_stream, EntityChange, EntityEvent, EventBus};
+use crate::proto::common::{BatchDeleteResponse, ChangeType, Empty};
 use crate::proto::items::items_service_server::ItemsService;
 use crate::proto::items::{
-    ChangeItemOwnershipReq, ConvertItemTypeReq, ConvertItemTypeRes, CreateItemReq, CreateItemRes,
+    BatchCreateItemResult, BatchCreateItemsReq, BatchCreateItemsRes, BatchDeleteItemsReq,
+    ChangeItemOwnershipReq, ItemEvent, WatchItemsReq, ConvertItemTypeReq, ConvertItemTypeRes, CreateItemReq, CreateItemRes,
     DeleteItemReq, GetItemReq, GetItemRes, Item, ListItemsReq, ListItemsRes, MoveItemReq,
     SearchByBarcodeReq, UpdateItemReq, UpdateItemRes,
 };
+use crate::services::batch::{delete_response, ok_status, rpc_status, BatchContext};
+use crate::services::validation;
+
+/// CreateItem / UpdateItem の任意項目（サニタイズ済み、空は NULL）
+struct ItemFields {
+    barcode: Option<String>,
+    category: Option<String>,
+    description: Option<String>,
+    image_url: Option<String>,
+    url: Option<String>,
+}
+
+impl ItemFields {
+    fn sanitize(barcode: &str, category: &str, description: &str, image_url: &str, url: &str) -> Result<Self, Status> {
+        Ok(Self {
+            barcode: validation::non_empty(validation::code("barcode", barcode, validation::CODE_MAX_CHARS)?),
+            category: validation::non_empty(validation::line("category", category, validation::NAME_MAX_CHARS)?),
+            description: validation::non_empty(validation::text(
+                "description",
+                description,
+                validation::TEXT_MAX_CHARS,
+            )?),
+            image_url: validation::non_empty(validation::line("image_url", image_url, validation::URL_MAX_CHARS)?),
+            url: validation::non_empty(validation::line("url", url, validation::URL_MAX_CHARS)?),
+        })
+    }
+}
 
 pub struct ItemsServiceImpl {
     pool: PgPool,
+    events: EventBus,
 }
 
 impl ItemsServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, events: EventBus) -> Self {
+        Self { pool, events }
+    }
+
+    /// WatchItems の購読者へ変更を通知（個人アイテムは所有者にのみ配信）
+    fn publish(
+        &self,
+        auth_user: &AuthenticatedUser,
+        change_type: ChangeType,
+        id: String,
+        item: Option<Item>,
+    ) {
+        let user_id = item
+            .as_ref()
+            .filter(|i| i.owner_type == "personal")
+            .map(|i| i.user_id.clone());
+        self.events.publish(EntityEvent {
+            organization_id: auth_user.org_id.clone(),
+            user_id,
+            change_type,
+            change: EntityChange::Item { id, item },
+        });
     }
 
     fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
@@ -55,15 +106,12 @@ impl ItemsServiceImpl {
     ) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>, Status> {
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+            .map_err(AppError::from)?;
         set_current_user(&mut conn, &auth_user.user_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set user context: {}", e)))?;
+            .map_err(AppError::from)?;
         Ok(conn)
     }
 }
@@ -78,9 +126,8 @@ impl ItemsService for ItemsServiceImpl {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
 
-        if req.name.is_empty() {
-            return Err(Status::invalid_argument("name is required"));
-        }
+        let name = validation::required_line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        let fields = ItemFields::sanitize(&req.barcode, &req.category, &req.description, &req.image_url, &req.url)?;
 
         let owner_type = if req.owner_type.is_empty() {
             "org"
@@ -100,31 +147,6 @@ impl ItemsService for ItemsServiceImpl {
         } else {
             Some(&req.parent_id)
         };
-        let barcode: Option<&str> = if req.barcode.is_empty() {
-            None
-        } else {
-            Some(&req.barcode)
-        };
-        let category: Option<&str> = if req.category.is_empty() {
-            None
-        } else {
-            Some(&req.category)
-        };
-        let description: Option<&str> = if req.description.is_empty() {
-            None
-        } else {
-            Some(&req.description)
-        };
-        let image_url: Option<&str> = if req.image_url.is_empty() {
-            None
-        } else {
-            Some(&req.image_url)
-        };
-        let url: Option<&str> = if req.url.is_empty() {
-            None
-        } else {
-            Some(&req.url)
-        };
         let item_type = if req.item_type.is_empty() {
             "item"
         } else {
@@ -155,21 +177,22 @@ impl ItemsService for ItemsServiceImpl {
         .bind(owner_type)
         .bind(org_id_val)
         .bind(user_id_val)
-        .bind(&req.name)
-        .bind(barcode)
-        .bind(category)
-        .bind(description)
-        .bind(image_url)
-        .bind(url)
+        .bind(&name)
+        .bind(&fields.barcode)
+        .bind(&fields.category)
+        .bind(&fields.description)
+        .bind(&fields.image_url)
+        .bind(&fields.url)
         .bind(item_type)
         .bind(quantity)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
+
+        let item = Self::model_to_proto(&model);
+        self.publish(&auth_user, ChangeType::Created, item.id.clone(), Some(item.clone()));
 
-        Ok(Response::new(CreateItemRes {
-            item: Some(Self::model_to_proto(&model)),
-        }))
+        Ok(Response::new(CreateItemRes { item: Some(item) }))
     }
 
     async fn get_item(
@@ -194,7 +217,7 @@ impl ItemsService for ItemsServiceImpl {
         .bind(&req.id)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match model {
             Some(m) => Ok(Response::new(GetItemRes {
@@ -215,33 +238,10 @@ impl ItemsService for ItemsServiceImpl {
             return Err(Status::invalid_argument("id is required"));
         }
 
-        let mut conn = self.setup_dual_rls(&auth_user).await?;
+        let name = validation::line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        let fields = ItemFields::sanitize(&req.barcode, &req.category, &req.description, &req.image_url, &req.url)?;
 
-        let barcode: Option<&str> = if req.barcode.is_empty() {
-            None
-        } else {
-            Some(&req.barcode)
-        };
-        let category: Option<&str> = if req.category.is_empty() {
-            None
-        } else {
-            Some(&req.category)
-        };
-        let description: Option<&str> = if req.description.is_empty() {
-            None
-        } else {
-            Some(&req.description)
-        };
-        let image_url: Option<&str> = if req.image_url.is_empty() {
-            None
-        } else {
-            Some(&req.image_url)
-        };
-        let url: Option<&str> = if req.url.is_empty() {
-            None
-        } else {
-            Some(&req.url)
-        };
+        let mut conn = self.setup_dual_rls(&auth_user).await?;
 
         let model: Option<ItemModel> = sqlx::query_as(
             "UPDATE items SET name = $1, barcode = $2, category = $3, description = $4, \
@@ -251,22 +251,24 @@ impl ItemsService for ItemsServiceImpl {
              name, barcode, category, description, image_url, url, item_type, quantity, \
              created_at::text, updated_at::text",
         )
-        .bind(&req.name)
-        .bind(barcode)
-        .bind(category)
-        .bind(description)
-        .bind(image_url)
-        .bind(url)
+        .bind(&name)
+        .bind(&fields.barcode)
+        .bind(&fields.category)
+        .bind(&fields.description)
+        .bind(&fields.image_url)
+        .bind(&fields.url)
         .bind(req.quantity)
         .bind(&req.id)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match model {
-            Some(m) => Ok(Response::new(UpdateItemRes {
-                item: Some(Self::model_to_proto(&m)),
-            })),
+            Some(m) => {
+                let item = Self::model_to_proto(&m);
+                self.publish(&auth_user, ChangeType::Updated, item.id.clone(), Some(item.clone()));
+                Ok(Response::new(UpdateItemRes { item: Some(item) }))
+            }
             None => Err(Status::not_found("Item not found")),
         }
     }
@@ -288,13 +290,15 @@ impl ItemsService for ItemsServiceImpl {
             .bind(&req.id)
             .execute(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+            .map_err(AppError::from)?
             .rows_affected();
 
         if rows_affected == 0 {
             return Err(Status::not_found("Item not found"));
         }
 
+        self.publish(&auth_user, ChangeType::Deleted, req.id, None);
+
         Ok(Response::new(Empty {}))
     }
 
@@ -367,7 +371,7 @@ impl ItemsService for ItemsServiceImpl {
         let models: Vec<ItemModel> = query
             .fetch_all(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let items: Vec<Item> = models.iter().map(Self::model_to_proto).collect();
         Ok(Response::new(ListItemsRes { items }))
@@ -399,13 +403,15 @@ impl ItemsService for ItemsServiceImpl {
         .bind(&req.id)
         .execute(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+        .map_err(AppError::from)?
         .rows_affected();
 
         if rows_affected == 0 {
             return Err(Status::not_found("Item not found"));
         }
 
+        self.publish(&auth_user, ChangeType::Updated, req.id, None);
+
         Ok(Response::new(Empty {}))
     }
 
@@ -457,7 +463,7 @@ impl ItemsService for ItemsServiceImpl {
         .bind(new_user_id)
         .execute(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+        .map_err(AppError::from)?
         .rows_affected();
 
         tracing::info!(
@@ -469,6 +475,8 @@ impl ItemsService for ItemsServiceImpl {
             return Err(Status::not_found("Item not found"));
         }
 
+        self.publish(&auth_user, ChangeType::Updated, req.id, None);
+
         Ok(Response::new(Empty {}))
     }
 
@@ -479,7 +487,8 @@ impl ItemsService for ItemsServiceImpl {
         let auth_user = Self::get_authenticated_user(&request)?;
         let req = request.into_inner();
 
-        if req.barcode.is_empty() {
+        let barcode = validation::code("barcode", &req.barcode, validation::CODE_MAX_CHARS)?;
+        if barcode.is_empty() {
             return Err(Status::invalid_argument("barcode is required"));
         }
 
@@ -491,10 +500,10 @@ impl ItemsService for ItemsServiceImpl {
              created_at::text, updated_at::text \
              FROM items WHERE barcode = $1 ORDER BY name ASC",
         )
-        .bind(&req.barcode)
+        .bind(&barcode)
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let items: Vec<Item> = models.iter().map(Self::model_to_proto).collect();
         Ok(Response::new(ListItemsRes { items }))
@@ -528,7 +537,7 @@ impl ItemsService for ItemsServiceImpl {
         .bind(&req.id)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let current = current.ok_or_else(|| Status::not_found("Item not found"))?;
 
@@ -554,7 +563,7 @@ impl ItemsService for ItemsServiceImpl {
             .bind(&req.id)
             .execute(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
             children_moved = result.rows_affected() as i32;
         }
@@ -571,14 +580,79 @@ impl ItemsService for ItemsServiceImpl {
         .bind(&req.id)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match model {
-            Some(m) => Ok(Response::new(ConvertItemTypeRes {
-                item: Some(Self::model_to_proto(&m)),
-                children_moved,
-            })),
+            Some(m) => {
+                let item = Self::model_to_proto(&m);
+                self.publish(&auth_user, ChangeType::Updated, item.id.clone(), Some(item.clone()));
+                Ok(Response::new(ConvertItemTypeRes {
+                    item: Some(item),
+                    children_moved,
+                }))
+            }
             None => Err(Status::internal("Update failed unexpectedly")),
         }
     }
+
+    async fn batch_create_items(
+        &self,
+        request: Request<BatchCreateItemsReq>,
+    ) -> Result<Response<BatchCreateItemsRes>, Status> {
+        let (ctx, req) = BatchContext::from_request(request, |r| r.requests.len())?;
+
+        let mut results = Vec::with_capacity(req.requests.len());
+        for entry in req.requests {
+            let result = match self.create_item(ctx.request(entry)).await {
+                Ok(response) => BatchCreateItemResult {
+                    status: Some(ok_status()),
+                    item: response.into_inner().item,
+                },
+                Err(status) => BatchCreateItemResult {
+                    status: Some(rpc_status(&status)),
+                    item: None,
+                },
+            };
+            results.push(result);
+        }
+
+        Ok(Response::new(BatchCreateItemsRes { results }))
+    }
+
+    async fn batch_delete_items(
+        &self,
+        request: Request<BatchDeleteItemsReq>,
+    ) -> Result<Response<BatchDeleteResponse>, Status> {
+        let (ctx, req) = BatchContext::from_request(request, |r| r.ids.len())?;
+
+        let mut results = Vec::with_capacity(req.ids.len());
+        for id in req.ids {
+            results.push(self.delete_item(ctx.request(DeleteItemReq { id })).await);
+        }
+
+        Ok(Response::new(delete_response(results)))
+    }
+
+    type WatchItemsStream = tokio_stream::wrappers::ReceiverStream<Result<ItemEvent, Status>>;
+
+    async fn watch_items(
+        &self,
+        request: Request<WatchItemsReq>,
+    ) -> Result<Response<Self::WatchItemsStream>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+
+        Ok(Response::new(watch_stream(
+            &self.events,
+            auth_user.org_id,
+            Some(auth_user.user_id),
+            |event| match &event.change {
+                EntityChange::Item { id, item } => Some(ItemEvent {
+                    change_type: event.change_type as i32,
+                    id: id.clone(),
+                    item: item.clone(),
+                }),
+                _ => None,
+            },
+        )))
+    }
 }
@@ -0,0 +1,243 @@
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::db::organization::OrgScopedPool;
+use crate::db::Paginator;
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::JobModel;
+use crate::proto::jobs::jobs_service_server::JobsService;
+use crate::proto::jobs::{
+    CancelJobRequest, GetJobRequest, Job, ListJobsRequest, ListJobsResponse,
+    RequeueDeadLettersRequest, RequeueDeadLettersResponse, RetryJobRequest,
+};
+
+const JOB_COLUMNS: &str = "id, kind, payload, dedupe_key, status, attempts, max_attempts, run_at, \
+     locked_by, locked_at, last_error, created_at, updated_at, finished_at";
+
+pub struct JobsServiceImpl {
+    pool: PgPool,
+}
+
+impl JobsServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    /// 管理者確認 + organization 設定済みのコネクション
+    async fn admin_conn<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>, Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok(conn)
+    }
+
+    /// 状態遷移の UPDATE が 0 件だったときのエラー（存在しない / 状態が合わない）
+    async fn transition_error(conn: &mut sqlx::PgConnection, id: i64, action: &str) -> Status {
+        let status: Result<Option<(String,)>, _> = sqlx::query_as("SELECT status FROM jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(conn)
+            .await;
+        match status {
+            Ok(Some((status,))) => Status::failed_precondition(format!("Cannot {} a {} job", action, status)),
+            Ok(None) => Status::not_found(format!("Job not found: {}", id)),
+            Err(e) => AppError::from(e).into(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl JobsService for JobsServiceImpl {
+    async fn get_job(
+        &self,
+        request: Request<GetJobRequest>,
+    ) -> Result<Response<Job>, Status> {
+        let mut conn = self.admin_conn(&request).await?;
+        let id = request.into_inner().id;
+
+        let job: JobModel = sqlx::query_as(&format!("SELECT {} FROM jobs WHERE id = $1", JOB_COLUMNS))
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(AppError::from)?
+            .ok_or_else(|| Status::not_found(format!("Job not found: {}", id)))?;
+
+        Ok(Response::new(job.to_proto()))
+    }
+
+    async fn list_jobs(
+        &self,
+        request: Request<ListJobsRequest>,
+    ) -> Result<Response<ListJobsResponse>, Status> {
+        let mut conn = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        let jobs: Vec<JobModel> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM jobs
+            WHERE ($1 = '' OR status = $1)
+              AND ($2 = '' OR kind = $2)
+              AND ($3::bigint IS NULL OR id < $3)
+            ORDER BY id DESC
+            LIMIT $4
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(&req.status)
+        .bind(&req.kind)
+        .bind(paginator.cursor_as::<i64>(0)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (jobs, pagination) = paginator.finish(jobs, |j| vec![j.id.to_string()]);
+
+        Ok(Response::new(ListJobsResponse {
+            jobs: jobs.iter().map(JobModel::to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn cancel_job(
+        &self,
+        request: Request<CancelJobRequest>,
+    ) -> Result<Response<Job>, Status> {
+        let mut conn = self.admin_conn(&request).await?;
+        let id = request.into_inner().id;
+
+        // running の job はワーカーの処理自体は止まらないが、結果（ack / 再試行）は反映されない
+        let job: Option<JobModel> = sqlx::query_as(&format!(
+            r#"
+            UPDATE jobs
+            SET status = 'cancelled', locked_by = NULL, locked_at = NULL,
+                updated_at = NOW(), finished_at = NOW()
+            WHERE id = $1 AND status IN ('pending', 'running')
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        match job {
+            Some(job) => {
+                tracing::info!("Job {} ({}) cancelled", job.id, job.kind);
+                Ok(Response::new(job.to_proto()))
+            }
+            None => Err(Self::transition_error(&mut conn, id, "cancel").await),
+        }
+    }
+
+    async fn retry_job(
+        &self,
+        request: Request<RetryJobRequest>,
+    ) -> Result<Response<Job>, Status> {
+        let mut conn = self.admin_conn(&request).await?;
+        let id = request.into_inner().id;
+
+        let job: Option<JobModel> = sqlx::query_as(&format!(
+            r#"
+            UPDATE jobs
+            SET status = 'pending', run_at = NOW(),
+                attempts = CASE WHEN status = 'pending' THEN attempts ELSE 0 END,
+                updated_at = NOW(), finished_at = NULL
+            WHERE id = $1 AND status IN ('pending', 'dead_letter', 'cancelled')
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| match e {
+            // 同じ dedupe_key の job が既に pending/running
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Status::already_exists("An equivalent job is already pending or running")
+            }
+            e => AppError::from(e).into(),
+        })?;
+
+        match job {
+            Some(job) => {
+                tracing::info!("Job {} ({}) requeued", job.id, job.kind);
+                Ok(Response::new(job.to_proto()))
+            }
+            None => Err(Self::transition_error(&mut conn, id, "retry").await),
+        }
+    }
+
+    async fn requeue_dead_letters(
+        &self,
+        request: Request<RequeueDeadLettersRequest>,
+    ) -> Result<Response<RequeueDeadLettersResponse>, Status> {
+        let mut conn = self.admin_conn(&request).await?;
+        let kind = request.into_inner().kind;
+
+        // 同じ dedupe_key の dead_letter は最新の1件だけ、既に pending/running のものがあれば対象外
+        let job_ids: Vec<i64> = sqlx::query_scalar(
+            r#"
+            UPDATE jobs
+            SET status = 'pending', run_at = NOW(), attempts = 0,
+                updated_at = NOW(), finished_at = NULL
+            WHERE id IN (
+                SELECT DISTINCT ON (d.kind, COALESCE(d.dedupe_key, d.id::text)) d.id
+                FROM jobs d
+                WHERE d.status = 'dead_letter'
+                  AND ($1 = '' OR d.kind = $1)
+                  AND (d.dedupe_key IS NULL OR NOT EXISTS (
+                      SELECT 1 FROM jobs o
+                      WHERE o.kind = d.kind AND o.dedupe_key = d.dedupe_key
+                        AND o.status IN ('pending', 'running')
+                  ))
+                ORDER BY d.kind, COALESCE(d.dedupe_key, d.id::text), d.id DESC
+            )
+            RETURNING id
+            "#,
+        )
+        .bind(&kind)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!("Requeued {} dead-letter jobs (kind: {:?})", job_ids.len(), kind);
+        Ok(Response::new(RequeueDeadLettersResponse { job_ids }))
+    }
+}
@@ -2,29 +2,87 @@ use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHasher,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chrono::Utc;
 use jsonwebtoken::{encode, EncodingKey, Header};
+use ring::hmac;
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
+use crate::db::OrgScopedPool;
+use crate::error::AppError;
 use crate::middleware::AuthenticatedUser;
+use crate::notifications::{format_jst, Notification, Notifier, Recipient, INVITATION};
 use crate::proto::auth::AuthResponse;
 use crate::proto::common::Empty;
 use crate::proto::member::member_service_server::MemberService;
 use crate::proto::member::{
-    AcceptInvitationRequest, InviteUserRequest, InviteUserResponse, ListMembersResponse, Member,
-    MemberIdRequest, MemberResponse, RemoveMemberRequest, TransferAdminRequest,
+    AcceptInvitationRequest, GetPasswordPolicyRequest, InviteUserRequest, InviteUserResponse,
+    ListMembersResponse, Member, MemberIdRequest, MemberResponse,
+    PasswordPolicy as PasswordPolicyMessage, RemoveMemberRequest, TransferAdminRequest,
 };
-use crate::services::auth_service::Claims;
+use crate::services::auth_service::{issue_refresh_token, Claims};
+use crate::services::password_policy::PasswordPolicy;
+use crate::services::validation;
+
+/// 招待の有効期限
+const INVITATION_TTL_DAYS: i64 = 7;
+
+/// 招待トークン `{招待 id}.{有効期限（unix 秒）}.{署名}`（JWT_SECRET の HMAC-SHA256、base64url）
+fn sign_invitation_token(secret: &str, invitation_id: &uuid::Uuid, expires_at: i64) -> String {
+    let payload = format!("{}.{}", invitation_id.simple(), expires_at);
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let signature = hmac::sign(&key, payload.as_bytes());
+    format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(signature.as_ref()))
+}
+
+/// 署名と有効期限を確かめて招待 id を返す（DB を引く前に改ざん・期限切れを弾く）
+fn verify_invitation_token(secret: &str, token: &str, now: i64) -> Option<uuid::Uuid> {
+    let (payload, signature) = token.rsplit_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, payload.as_bytes(), &signature).ok()?;
+    let (id, expires_at) = payload.split_once('.')?;
+    if expires_at.parse::<i64>().ok()? <= now {
+        return None;
+    }
+    uuid::Uuid::parse_str(id).ok()
+}
+
+/// 招待トークンは SHA-256 だけを保存する
+fn hash_invitation_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn policy_to_message(policy: &PasswordPolicy) -> PasswordPolicyMessage {
+    PasswordPolicyMessage {
+        min_length: policy.min_length,
+        require_uppercase: policy.require_uppercase,
+        require_lowercase: policy.require_lowercase,
+        require_digit: policy.require_digit,
+        require_symbol: policy.require_symbol,
+        history_count: policy.history_count,
+        max_age_days: policy.max_age_days,
+        requirements: policy.requirements(),
+    }
+}
 
 pub struct MemberServiceImpl {
     pool: PgPool,
     jwt_secret: String,
+    notifier: Notifier,
+    app_base_url: Option<String>,
 }
 
 impl MemberServiceImpl {
-    pub fn new(pool: PgPool, jwt_secret: String) -> Self {
-        Self { pool, jwt_secret }
+    pub fn new(pool: PgPool, jwt_secret: String, notifier: Notifier, app_base_url: Option<String>) -> Self {
+        Self {
+            pool,
+            jwt_secret,
+            notifier,
+            app_base_url,
+        }
     }
 
     fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
@@ -43,7 +101,7 @@ impl MemberServiceImpl {
         .bind(org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match role {
             Some((r,)) if r == "admin" => Ok(()),
@@ -54,6 +112,11 @@ impl MemberServiceImpl {
         }
     }
 
+    async fn password_policy(&self, org_id: &str) -> Result<PasswordPolicy, Status> {
+        let mut conn = self.pool.acquire_scoped(org_id).await.map_err(AppError::from)?;
+        Ok(PasswordPolicy::load(&mut conn).await.map_err(AppError::from)?)
+    }
+
     async fn count_admins(&self, org_id: &str) -> Result<i64, Status> {
         let (count,): (i64,) = sqlx::query_as(
             "SELECT COUNT(*) FROM user_organizations WHERE organization_id = $1::uuid AND role = 'admin'",
@@ -61,7 +124,7 @@ impl MemberServiceImpl {
         .bind(org_id)
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(count)
     }
@@ -109,7 +172,7 @@ impl MemberServiceImpl {
             .bind(org_id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         Ok(Member {
             user_id: row.0,
@@ -134,7 +197,8 @@ impl MemberService for MemberServiceImpl {
 
         self.verify_admin(&invited_by, &org_id).await?;
 
-        if req.email.is_empty() {
+        let email = validation::code("email", &req.email, validation::EMAIL_MAX_CHARS)?;
+        if email.is_empty() {
             return Err(Status::invalid_argument("Email is required"));
         }
 
@@ -143,34 +207,70 @@ impl MemberService for MemberServiceImpl {
         } else {
             &req.role
         };
-        if role != "admin" && role != "member" {
+        if role != "admin" && role != "member" && role != "viewer" {
             return Err(Status::invalid_argument(
-                "Role must be 'admin' or 'member'",
+                "Role must be 'admin', 'member' or 'viewer'",
             ));
         }
 
-        let token = uuid::Uuid::new_v4().to_string();
-        let expires_at = Utc::now() + chrono::Duration::days(7);
+        let invitation_id = uuid::Uuid::new_v4();
+        let expires_at = Utc::now() + chrono::Duration::days(INVITATION_TTL_DAYS);
+        let token = sign_invitation_token(&self.jwt_secret, &invitation_id, expires_at.timestamp());
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&org_id)
+            .await
+            .map_err(AppError::from)?;
 
-        let (invitation_id,): (String,) = sqlx::query_as(
-            "INSERT INTO invitations (organization_id, email, role, token, invited_by, expires_at)
-             VALUES ($1::uuid, $2, $3, $4, $5::uuid, $6)
-             RETURNING id::text",
+        sqlx::query(
+            "INSERT INTO invitations (id, organization_id, email, role, token, invited_by, expires_at)
+             VALUES ($1, $2::uuid, $3, $4, $5, $6::uuid, $7)",
         )
+        .bind(invitation_id)
         .bind(&org_id)
-        .bind(&req.email)
+        .bind(&email)
         .bind(role)
-        .bind(&token)
+        .bind(hash_invitation_token(&token))
         .bind(&invited_by)
         .bind(expires_at)
-        .fetch_one(&self.pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Failed to create invitation: {}", e)))?;
+        .map_err(AppError::from)?;
+
+        // 招待メール（招待と同じトランザクションで記録）
+        let organization_name: Option<String> =
+            sqlx::query_scalar("SELECT name FROM organizations WHERE id = $1::uuid")
+                .bind(&org_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        let invite_url = self
+            .app_base_url
+            .as_deref()
+            .map(|base| format!("{}/invite?token={}", base.trim_end_matches('/'), token))
+            .unwrap_or_default();
+        let notification = Notification::new(INVITATION)
+            .var("organization_name", organization_name.unwrap_or_default())
+            .var("role", role)
+            .var("invite_url", &invite_url)
+            .var("token", &token)
+            .var("expires_at", format_jst(expires_at))
+            .to(Recipient::email(&email));
+        self.notifier
+            .send(&mut tx, &org_id, &notification)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
 
         Ok(Response::new(InviteUserResponse {
-            invitation_id,
+            invitation_id: invitation_id.to_string(),
             token,
             expires_at: expires_at.to_rfc3339(),
+            invite_url,
         }))
     }
 
@@ -180,40 +280,48 @@ impl MemberService for MemberServiceImpl {
     ) -> Result<Response<AuthResponse>, Status> {
         let req = request.into_inner();
 
-        if req.token.is_empty() || req.username.is_empty() || req.password.is_empty() {
+        let username = validation::line("username", &req.username, validation::NAME_MAX_CHARS)?;
+        if req.token.is_empty() || username.is_empty() || req.password.is_empty() {
             return Err(Status::invalid_argument(
                 "token, username, and password are required",
             ));
         }
 
-        // 1. Find valid invitation
-        let inv: Option<(String, String, String, String, String)> = sqlx::query_as(
-            "SELECT i.id::text, i.organization_id::text, i.email, i.role, o.slug
+        // 1. Verify the signature, then lock the invitation (accepted only once)
+        let inv_id = verify_invitation_token(&self.jwt_secret, &req.token, Utc::now().timestamp())
+            .ok_or_else(|| Status::not_found("Invalid or expired invitation"))?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(AppError::from)?;
+
+        let inv: Option<(String, String, String, String)> = sqlx::query_as(
+            "SELECT i.organization_id::text, i.email, i.role, o.slug
              FROM invitations i
              JOIN organizations o ON o.id = i.organization_id
-             WHERE i.token = $1 AND i.accepted_at IS NULL AND i.expires_at > NOW()",
+             WHERE i.id = $1 AND i.token = $2 AND i.accepted_at IS NULL AND i.expires_at > NOW()
+             FOR UPDATE OF i",
         )
-        .bind(&req.token)
-        .fetch_optional(&self.pool)
+        .bind(inv_id)
+        .bind(hash_invitation_token(&req.token))
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
-        let (inv_id, org_id, inv_email, inv_role, org_slug) =
+        let (org_id, inv_email, inv_role, org_slug) =
             inv.ok_or_else(|| Status::not_found("Invalid or expired invitation"))?;
 
-        // 2. Hash password
+        // 2. Check the organization's password policy, then hash
+        self.password_policy(&org_id).await?.check(&req.password)?;
         let salt = SaltString::generate(&mut OsRng);
         let password_hash = Argon2::default()
             .hash_password(req.password.as_bytes(), &salt)
             .map_err(|e| Status::internal(format!("Password hash error: {}", e)))?
             .to_string();
 
-        // 3. Transaction: create user + credentials + membership
-        let mut tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(|e| Status::internal(format!("Transaction error: {}", e)))?;
+        // 3. Create user + credentials + membership in the same transaction
 
         let display_name = if req.display_name.is_empty() {
             inv_email.clone()
@@ -228,7 +336,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&inv_email)
         .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let user_id = if let Some((id,)) = existing_user {
             id
@@ -241,7 +349,7 @@ impl MemberService for MemberServiceImpl {
             .bind(&display_name)
             .fetch_one(&mut *tx)
             .await
-            .map_err(|e| Status::internal(format!("Failed to create user: {}", e)))?;
+            .map_err(AppError::from)?;
             id
         };
 
@@ -252,16 +360,15 @@ impl MemberService for MemberServiceImpl {
         )
         .bind(&user_id)
         .bind(&org_id)
-        .bind(&req.username)
+        .bind(&username)
         .bind(&password_hash)
         .execute(&mut *tx)
         .await
-        .map_err(|e| {
-            if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
                 Status::already_exists("Username already taken in this organization")
-            } else {
-                Status::internal(format!("Failed to create credentials: {}", e))
             }
+            e => AppError::from(e).into(),
         })?;
 
         // Create user_organizations (or update if already exists)
@@ -276,30 +383,34 @@ impl MemberService for MemberServiceImpl {
         .bind(&inv_role)
         .execute(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Failed to create membership: {}", e)))?;
+        .map_err(AppError::from)?;
 
         // Mark invitation as accepted
         sqlx::query(
-            "UPDATE invitations SET accepted_by = $1::uuid, accepted_at = NOW() WHERE id = $2::uuid",
+            "UPDATE invitations SET accepted_by = $1::uuid, accepted_at = NOW() WHERE id = $2",
         )
         .bind(&user_id)
-        .bind(&inv_id)
+        .bind(inv_id)
         .execute(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Failed to update invitation: {}", e)))?;
+        .map_err(AppError::from)?;
 
         tx.commit()
             .await
-            .map_err(|e| Status::internal(format!("Transaction commit error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         // Issue JWT (auto-login)
         let (token, exp) = self.issue_jwt(&user_id, &org_id, &inv_email, "password", &org_slug)?;
+        let (refresh_token, refresh_exp) =
+            issue_refresh_token(&self.pool, &user_id, &org_id, "password").await?;
 
         Ok(Response::new(AuthResponse {
             token,
             expires_at: exp.to_rfc3339(),
             user_id,
             organization_id: org_id,
+            refresh_token,
+            refresh_token_expires_at: refresh_exp.to_rfc3339(),
         }))
     }
 
@@ -327,7 +438,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let members = rows
             .into_iter()
@@ -368,7 +479,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (target_role_str,) = target_role
             .ok_or_else(|| Status::not_found("User is not a member of this organization"))?;
@@ -390,7 +501,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .execute(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         // Remove password_credentials for this org
         sqlx::query(
@@ -400,7 +511,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .execute(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(Empty {}))
     }
@@ -424,7 +535,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .execute(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         if result.rows_affected() == 0 {
             return Err(Status::not_found(
@@ -465,7 +576,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .execute(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         if result.rows_affected() == 0 {
             return Err(Status::not_found(
@@ -504,7 +615,7 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let (target_role,) = target_exists.ok_or_else(|| {
             Status::not_found("Target user is not a member of this organization")
@@ -514,7 +625,7 @@ impl MemberService for MemberServiceImpl {
             .pool
             .begin()
             .await
-            .map_err(|e| Status::internal(format!("Transaction error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         // Promote target to admin (if not already)
         if target_role != "admin" {
@@ -526,7 +637,7 @@ impl MemberService for MemberServiceImpl {
             .bind(&org_id)
             .execute(&mut *tx)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
         }
 
         // Demote caller to member
@@ -538,12 +649,121 @@ impl MemberService for MemberServiceImpl {
         .bind(&org_id)
         .execute(&mut *tx)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         tx.commit()
             .await
-            .map_err(|e| Status::internal(format!("Transaction commit error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         Ok(Response::new(Empty {}))
     }
+
+    async fn get_password_policy(
+        &self,
+        request: Request<GetPasswordPolicyRequest>,
+    ) -> Result<Response<PasswordPolicyMessage>, Status> {
+        let auth_user = request.extensions().get::<AuthenticatedUser>().cloned();
+        let req = request.into_inner();
+
+        let org_id = if !req.organization_id.is_empty() {
+            uuid::Uuid::parse_str(&req.organization_id)
+                .map_err(|_| Status::invalid_argument("Invalid organization_id"))?
+                .to_string()
+        } else if !req.invitation_token.is_empty() {
+            let inv_id = verify_invitation_token(&self.jwt_secret, &req.invitation_token, Utc::now().timestamp())
+                .ok_or_else(|| Status::not_found("Invalid or expired invitation"))?;
+            let org: Option<(String,)> = sqlx::query_as(
+                "SELECT organization_id::text FROM invitations
+                 WHERE id = $1 AND token = $2 AND accepted_at IS NULL AND expires_at > NOW()",
+            )
+            .bind(inv_id)
+            .bind(hash_invitation_token(&req.invitation_token))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+            org.ok_or_else(|| Status::not_found("Invalid or expired invitation"))?.0
+        } else if let Some(user) = auth_user {
+            user.org_id
+        } else {
+            return Err(Status::invalid_argument("organization_id or invitation_token is required"));
+        };
+
+        let policy = self.password_policy(&org_id).await?;
+        Ok(Response::new(policy_to_message(&policy)))
+    }
+
+    async fn update_password_policy(
+        &self,
+        request: Request<PasswordPolicyMessage>,
+    ) -> Result<Response<PasswordPolicyMessage>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id).await?;
+        let req = request.into_inner();
+
+        let policy = PasswordPolicy {
+            min_length: req.min_length,
+            require_uppercase: req.require_uppercase,
+            require_lowercase: req.require_lowercase,
+            require_digit: req.require_digit,
+            require_symbol: req.require_symbol,
+            history_count: req.history_count,
+            max_age_days: req.max_age_days,
+        };
+        policy.validate()?;
+
+        let mut conn = self.pool.acquire_scoped(&auth_user.org_id).await.map_err(AppError::from)?;
+        sqlx::query(
+            "INSERT INTO password_policies
+                 (organization_id, min_length, require_uppercase, require_lowercase, require_digit,
+                  require_symbol, history_count, max_age_days, updated_by)
+             VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8, $9::uuid)
+             ON CONFLICT (organization_id) DO UPDATE
+             SET min_length = EXCLUDED.min_length,
+                 require_uppercase = EXCLUDED.require_uppercase,
+                 require_lowercase = EXCLUDED.require_lowercase,
+                 require_digit = EXCLUDED.require_digit,
+                 require_symbol = EXCLUDED.require_symbol,
+                 history_count = EXCLUDED.history_count,
+                 max_age_days = EXCLUDED.max_age_days,
+                 updated_by = EXCLUDED.updated_by,
+                 updated_at = NOW()",
+        )
+        .bind(&auth_user.org_id)
+        .bind(policy.min_length)
+        .bind(policy.require_uppercase)
+        .bind(policy.require_lowercase)
+        .bind(policy.require_digit)
+        .bind(policy.require_symbol)
+        .bind(policy.history_count)
+        .bind(policy.max_age_days)
+        .bind(&auth_user.user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(policy_to_message(&policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invitation_token_roundtrip() {
+        let id = uuid::Uuid::new_v4();
+        let now = Utc::now().timestamp();
+        let token = sign_invitation_token("secret", &id, now + 60);
+        assert_eq!(verify_invitation_token("secret", &token, now), Some(id));
+
+        // 期限切れ・別の鍵・改ざん
+        assert_eq!(verify_invitation_token("secret", &token, now + 60), None);
+        assert_eq!(verify_invitation_token("other", &token, now), None);
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}.{}", id.simple(), now + 3600, signature);
+        assert_eq!(verify_invitation_token("secret", &forged, now), None);
+        assert_eq!(verify_invitation_token("secret", &uuid::Uuid::new_v4().to_string(), now), None);
+
+        assert_ne!(hash_invitation_token(&token), token);
+    }
 }
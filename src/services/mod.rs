@@ -1,21 +1,42 @@
+pub mod batch;
 pub mod file_auto_parser;
 pub mod files_service;
 pub mod car_inspection_service;
 pub mod cam_files_service;
 pub mod health_service;
+pub mod home_car_cache;
 pub mod dtakologs_service;
 pub mod flickr_service;
 pub mod dvr_notifications_service;
 pub mod auth_service;
 pub mod organization_service;
 pub mod member_service;
+pub mod password_policy;
 pub mod lineworks_auth;
 pub mod sso_providers;
 pub mod sso_settings_service;
 pub mod bot_config_service;
 pub mod access_request_service;
 pub mod items_service;
+pub mod jobs_service;
 pub mod nfc_tag_service;
+pub mod notification_feed_service;
+pub mod notification_service;
+pub mod scheduler_service;
+pub mod webhook_service;
+pub mod csv_import;
+pub mod etc_service;
+pub mod fuel_service;
+pub mod ichiban_cars_service;
+pub mod report_service;
+pub mod thumbnails;
+pub mod admin_service;
+pub mod api_keys_service;
+pub mod search_service;
+pub mod geofences_service;
+pub mod validation;
+pub mod vehicle_matcher;
+pub mod v2;
 
 pub use file_auto_parser::FileAutoParser;
 pub use files_service::FilesServiceImpl;
@@ -33,4 +54,17 @@ pub use sso_settings_service::SsoSettingsServiceImpl;
 pub use bot_config_service::BotConfigServiceImpl;
 pub use access_request_service::AccessRequestServiceImpl;
 pub use items_service::ItemsServiceImpl;
+pub use jobs_service::JobsServiceImpl;
 pub use nfc_tag_service::NfcTagServiceImpl;
+pub use notification_feed_service::NotificationFeedServiceImpl;
+pub use notification_service::NotificationServiceImpl;
+pub use scheduler_service::SchedulerServiceImpl;
+pub use webhook_service::WebhookServiceImpl;
+pub use etc_service::EtcServiceImpl;
+pub use fuel_service::FuelServiceImpl;
+pub use ichiban_cars_service::IchibanCarsServiceImpl;
+pub use report_service::ReportServiceImpl;
+pub use admin_service::AdminServiceImpl;
+pub use api_keys_service::ApiKeysServiceImpl;
+pub use search_service::SearchServiceImpl;
+pub use geofences_service::GeofencesServiceImpl;
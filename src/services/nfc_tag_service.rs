@@ -1,7 +1,8 @@
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
-use crate::db::{get_organization_from_request, set_current_organization};
+use crate::db::{get_organization_from_request, OrgScopedPool};
+use crate::error::AppError;
 use crate::models::{CarInspectionModel, NfcTagModel};
 use crate::proto::car_inspection::nfc_tag_service_server::NfcTagService;
 use crate::proto::car_inspection::{
@@ -10,10 +11,11 @@ use crate::proto::car_inspection::{
 };
 use crate::proto::common::Empty;
 use crate::services::car_inspection_service::CarInspectionServiceImpl;
+use crate::services::validation;
 
-/// NFC UUID を正規化: 小文字、コロン除去
+/// NFC UUID を正規化: 全角→半角、小文字、コロン除去
 fn normalize_nfc_uuid(uuid: &str) -> String {
-    uuid.to_lowercase().replace(':', "")
+    validation::normalize_width(uuid).trim().to_lowercase().replace(':', "")
 }
 
 fn model_to_proto(model: &NfcTagModel) -> NfcTag {
@@ -43,16 +45,13 @@ impl NfcTagService for NfcTagServiceImpl {
     ) -> Result<Response<SearchByNfcUuidResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
-        let nfc_uuid = normalize_nfc_uuid(&req.nfc_uuid);
+        let nfc_uuid = normalize_nfc_uuid(&validation::code("nfc_uuid", &req.nfc_uuid, validation::CODE_MAX_CHARS)?);
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+            .map_err(AppError::from)?;
 
         // JOIN nfc_tags with car_inspection
         let tag_row = sqlx::query_as::<_, NfcTagModel>(
@@ -61,7 +60,7 @@ impl NfcTagService for NfcTagServiceImpl {
         .bind(&nfc_uuid)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match tag_row {
             Some(tag) => {
@@ -72,7 +71,7 @@ impl NfcTagService for NfcTagServiceImpl {
                 .bind(tag.car_inspection_id)
                 .fetch_optional(&mut *conn)
                 .await
-                .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+                .map_err(AppError::from)?;
 
                 Ok(Response::new(SearchByNfcUuidResponse {
                     car_inspection: ci.map(|m| CarInspectionServiceImpl::model_to_proto(&m)),
@@ -92,16 +91,13 @@ impl NfcTagService for NfcTagServiceImpl {
     ) -> Result<Response<NfcTagResponse>, Status> {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
-        let nfc_uuid = normalize_nfc_uuid(&req.nfc_uuid);
+        let nfc_uuid = normalize_nfc_uuid(&validation::code("nfc_uuid", &req.nfc_uuid, validation::CODE_MAX_CHARS)?);
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let tag = sqlx::query_as::<_, NfcTagModel>(
             r#"
@@ -117,7 +113,7 @@ impl NfcTagService for NfcTagServiceImpl {
         .bind(req.car_inspection_id)
         .fetch_one(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(NfcTagResponse {
             nfc_tag: Some(model_to_proto(&tag)),
@@ -133,12 +129,9 @@ impl NfcTagService for NfcTagServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let tags = if let Some(car_inspection_id) = req.car_inspection_id {
             sqlx::query_as::<_, NfcTagModel>(
@@ -154,7 +147,7 @@ impl NfcTagService for NfcTagServiceImpl {
             .fetch_all(&mut *conn)
             .await
         }
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(ListNfcTagsResponse {
             nfc_tags: tags.iter().map(model_to_proto).collect(),
@@ -167,22 +160,19 @@ impl NfcTagService for NfcTagServiceImpl {
     ) -> Result<Response<Empty>, Status> {
         let organization_id = get_organization_from_request(&request);
         let req = request.into_inner();
-        let nfc_uuid = normalize_nfc_uuid(&req.nfc_uuid);
+        let nfc_uuid = normalize_nfc_uuid(&validation::code("nfc_uuid", &req.nfc_uuid, validation::CODE_MAX_CHARS)?);
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Database connection error: {}", e)))?;
-        set_current_organization(&mut conn, &organization_id)
+            .acquire_scoped(&organization_id)
             .await
-            .map_err(|e| Status::internal(format!("Failed to set organization context: {}", e)))?;
+            .map_err(AppError::from)?;
 
         sqlx::query("DELETE FROM car_inspection_nfc_tags WHERE nfc_uuid = $1")
             .bind(&nfc_uuid)
             .execute(&mut *conn)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         Ok(Response::new(Empty {}))
     }
@@ -0,0 +1,213 @@
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::db::organization::OrgScopedPool;
+use crate::db::Paginator;
+use crate::error::AppError;
+use crate::events::{watch_stream, EntityChange, EventBus};
+use crate::middleware::AuthenticatedUser;
+use crate::models::InAppNotificationModel;
+use crate::notifications::DigestFrequency;
+use crate::proto::notifications::notification_feed_service_server::NotificationFeedService;
+use crate::proto::notifications::{
+    DigestPreference, GetDigestPreferenceRequest, InAppNotificationEvent, ListNotificationsRequest, ListNotificationsResponse,
+    MarkNotificationsReadRequest, MarkNotificationsReadResponse, WatchNotificationsRequest,
+};
+
+/// まとめ通知を購読しない（notification_digest_subscriptions に行がない）
+const DIGEST_OFF: &str = "off";
+
+pub struct NotificationFeedServiceImpl {
+    pool: PgPool,
+    events: EventBus,
+}
+
+impl NotificationFeedServiceImpl {
+    pub fn new(pool: PgPool, events: EventBus) -> Self {
+        Self { pool, events }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn get_conn(
+        &self,
+        auth_user: &AuthenticatedUser,
+    ) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>, Status> {
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok(conn)
+    }
+
+    async fn unread_count(conn: &mut sqlx::PgConnection, user_id: &str) -> Result<i64, Status> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1::uuid AND read_at IS NULL")
+            .bind(user_id)
+            .fetch_one(conn)
+            .await
+            .map_err(|e| Status::from(AppError::from(e)))
+    }
+}
+
+#[tonic::async_trait]
+impl NotificationFeedService for NotificationFeedServiceImpl {
+    async fn list_notifications(
+        &self,
+        request: Request<ListNotificationsRequest>,
+    ) -> Result<Response<ListNotificationsResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let mut conn = self.get_conn(&auth_user).await?;
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        let notifications: Vec<InAppNotificationModel> = sqlx::query_as(
+            r#"
+            SELECT id, template, title, body, read_at, created_at FROM notifications
+            WHERE user_id = $1::uuid
+              AND (NOT $2 OR read_at IS NULL)
+              AND ($3::bigint IS NULL OR id < $3)
+            ORDER BY id DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(&auth_user.user_id)
+        .bind(req.unread_only)
+        .bind(paginator.cursor_as::<i64>(0)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (notifications, pagination) = paginator.finish(notifications, |n| vec![n.id.to_string()]);
+        let unread_count = Self::unread_count(&mut conn, &auth_user.user_id).await?;
+
+        Ok(Response::new(ListNotificationsResponse {
+            notifications: notifications.iter().map(InAppNotificationModel::to_proto).collect(),
+            pagination: Some(pagination),
+            unread_count,
+        }))
+    }
+
+    async fn mark_notifications_read(
+        &self,
+        request: Request<MarkNotificationsReadRequest>,
+    ) -> Result<Response<MarkNotificationsReadResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let req = request.into_inner();
+        if !req.all && req.ids.is_empty() {
+            return Err(Status::invalid_argument("ids or all is required"));
+        }
+        let mut conn = self.get_conn(&auth_user).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE notifications SET read_at = NOW()
+            WHERE user_id = $1::uuid AND read_at IS NULL AND ($2 OR id = ANY($3))
+            "#,
+        )
+        .bind(&auth_user.user_id)
+        .bind(req.all)
+        .bind(&req.ids)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let unread_count = Self::unread_count(&mut conn, &auth_user.user_id).await?;
+
+        Ok(Response::new(MarkNotificationsReadResponse {
+            updated: result.rows_affected() as i64,
+            unread_count,
+        }))
+    }
+
+    type WatchNotificationsStream =
+        tokio_stream::wrappers::ReceiverStream<Result<InAppNotificationEvent, Status>>;
+
+    async fn watch_notifications(
+        &self,
+        request: Request<WatchNotificationsRequest>,
+    ) -> Result<Response<Self::WatchNotificationsStream>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let org_id = auth_user.org_id.clone();
+
+        Ok(Response::new(watch_stream(
+            &self.events,
+            auth_user.org_id,
+            Some(auth_user.user_id),
+            move |event| match &event.change {
+                // user_id 宛てのイベントは組織をまたぐので、ログイン中の組織のものだけ
+                EntityChange::Notification(notification) if event.organization_id == org_id => {
+                    Some(InAppNotificationEvent {
+                        change_type: event.change_type as i32,
+                        notification: Some(notification.clone()),
+                    })
+                }
+                _ => None,
+            },
+        )))
+    }
+
+    async fn get_digest_preference(
+        &self,
+        request: Request<GetDigestPreferenceRequest>,
+    ) -> Result<Response<DigestPreference>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let mut conn = self.get_conn(&auth_user).await?;
+
+        let frequency: Option<String> = sqlx::query_scalar(
+            "SELECT frequency FROM notification_digest_subscriptions WHERE user_id = $1::uuid",
+        )
+        .bind(&auth_user.user_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(DigestPreference {
+            frequency: frequency.unwrap_or_else(|| DIGEST_OFF.to_string()),
+        }))
+    }
+
+    async fn update_digest_preference(
+        &self,
+        request: Request<DigestPreference>,
+    ) -> Result<Response<DigestPreference>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let frequency = request.into_inner().frequency;
+        let mut conn = self.get_conn(&auth_user).await?;
+
+        let result = if frequency == DIGEST_OFF {
+            sqlx::query("DELETE FROM notification_digest_subscriptions WHERE user_id = $1::uuid")
+                .bind(&auth_user.user_id)
+                .execute(&mut *conn)
+                .await
+        } else if [DigestFrequency::Daily, DigestFrequency::Weekly]
+            .iter()
+            .any(|f| f.as_str() == frequency)
+        {
+            sqlx::query(
+                r#"
+                INSERT INTO notification_digest_subscriptions (organization_id, user_id, frequency)
+                VALUES ($1::uuid, $2::uuid, $3)
+                ON CONFLICT (organization_id, user_id) DO UPDATE
+                SET frequency = EXCLUDED.frequency, updated_at = NOW()
+                "#,
+            )
+            .bind(&auth_user.org_id)
+            .bind(&auth_user.user_id)
+            .bind(&frequency)
+            .execute(&mut *conn)
+            .await
+        } else {
+            return Err(Status::invalid_argument("frequency must be off, daily or weekly"));
+        };
+        result.map_err(AppError::from)?;
+
+        Ok(Response::new(DigestPreference { frequency }))
+    }
+}
@@ -0,0 +1,574 @@
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::db::organization::OrgScopedPool;
+use crate::db::Paginator;
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::{NotificationDeliveryModel, NotificationWebhookModel};
+use crate::notifications::webhook::{is_valid_webhook_url, webhook_url_hint};
+use crate::notifications::{
+    builtin_template, is_valid_phone_number, render, resolve_template, sms_sent_this_month,
+    unknown_variables, BUILTIN_TEMPLATES,
+    DISCORD_CHANNEL, EMAIL_CHANNEL, LINEWORKS_CHANNEL, SLACK_CHANNEL, SMS_CHANNEL,
+};
+use crate::proto::common::Empty;
+use crate::proto::notifications::notification_service_server::NotificationService;
+use crate::proto::notifications::{
+    DeleteNotificationTemplateRequest, DeleteNotificationWebhookRequest,
+    GetNotificationSettingsRequest, ListNotificationDeliveriesRequest,
+    ListNotificationDeliveriesResponse, ListNotificationTemplatesRequest,
+    ListNotificationTemplatesResponse, ListNotificationWebhooksRequest,
+    ListNotificationWebhooksResponse, NotificationSettings, NotificationTemplate,
+    NotificationTemplateVariable, NotificationWebhook, PreviewNotificationTemplateRequest,
+    PreviewNotificationTemplateResponse, UpsertNotificationTemplateRequest,
+    UpsertNotificationWebhookRequest,
+};
+use crate::services::lineworks_auth;
+use crate::services::validation;
+
+const WEBHOOK_COLUMNS: &str = "id, provider, name, url_encrypted, enabled, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "id, channel, template, recipient, subject, body, status, attempts, \
+     last_error, created_at, sent_at";
+
+pub struct NotificationServiceImpl {
+    pool: PgPool,
+    /// Webhook URL の暗号化キー（JWT_SECRET、bot_configs と同じ）
+    jwt_secret: String,
+}
+
+impl NotificationServiceImpl {
+    pub fn new(pool: PgPool, jwt_secret: String) -> Self {
+        Self { pool, jwt_secret }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    /// 管理者確認 + organization 設定済みのコネクション
+    async fn admin_conn<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(AuthenticatedUser, sqlx::pool::PoolConnection<sqlx::Postgres>), Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok((auth_user, conn))
+    }
+
+    fn webhook_to_proto(&self, webhook: &NotificationWebhookModel) -> NotificationWebhook {
+        let url_hint = lineworks_auth::decrypt_secret(&webhook.url_encrypted, &self.jwt_secret)
+            .map(|url| webhook_url_hint(&url))
+            .unwrap_or_default();
+        webhook.to_proto(url_hint)
+    }
+}
+
+/// テンプレートを上書きできるチャネル（空文字は全チャネル共通）
+const TEMPLATE_CHANNELS: &[&str] = &["", EMAIL_CHANNEL, LINEWORKS_CHANNEL, SLACK_CHANNEL, DISCORD_CHANNEL, SMS_CHANNEL];
+
+/// notification_templates の1行
+#[derive(Debug, sqlx::FromRow)]
+struct TemplateOverride {
+    template_key: String,
+    channel: String,
+    subject: String,
+    body: String,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn template_to_proto(
+    builtin: &crate::notifications::NotificationTemplate,
+    row: Option<&TemplateOverride>,
+) -> NotificationTemplate {
+    NotificationTemplate {
+        template_key: builtin.key.to_string(),
+        channel: row.map(|r| r.channel.clone()).unwrap_or_default(),
+        subject: row.map_or(builtin.subject, |r| r.subject.as_str()).to_string(),
+        body: row.map_or(builtin.body, |r| r.body.as_str()).to_string(),
+        customized: row.is_some(),
+        variables: builtin
+            .variables
+            .iter()
+            .map(|(name, sample)| NotificationTemplateVariable {
+                name: name.to_string(),
+                sample: sample.to_string(),
+            })
+            .collect(),
+        updated_at: row.map(|r| r.updated_at.to_rfc3339()),
+    }
+}
+
+/// template_key と channel の検証
+fn validate_template_target(
+    template_key: &str,
+    channel: &str,
+) -> Result<crate::notifications::NotificationTemplate, Status> {
+    let builtin = builtin_template(template_key)
+        .ok_or_else(|| Status::invalid_argument(format!("Unknown template: {}", template_key)))?;
+    if !TEMPLATE_CHANNELS.contains(&channel) {
+        return Err(Status::invalid_argument(format!("Unknown channel: {}", channel)));
+    }
+    Ok(builtin)
+}
+
+/// 空文字は未設定（NULL）として保存する
+/// notification_settings（未登録なら全て空）
+#[derive(Debug, Default, sqlx::FromRow)]
+struct SettingsRow {
+    email_from_address: Option<String>,
+    email_from_name: Option<String>,
+    email_reply_to: Option<String>,
+    lineworks_bot_config_id: Option<String>,
+    sms_recipients: Vec<String>,
+    sms_monthly_limit: i32,
+    digest_low_stock_threshold: i32,
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+    let value = value.trim();
+    (!value.is_empty()).then_some(value)
+}
+
+#[tonic::async_trait]
+impl NotificationService for NotificationServiceImpl {
+    async fn get_notification_settings(
+        &self,
+        request: Request<GetNotificationSettingsRequest>,
+    ) -> Result<Response<NotificationSettings>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+
+        let row: Option<SettingsRow> = sqlx::query_as(
+            r#"
+            SELECT email_from_address, email_from_name, email_reply_to,
+                   lineworks_bot_config_id::text AS lineworks_bot_config_id, sms_recipients, sms_monthly_limit,
+                   digest_low_stock_threshold
+            FROM notification_settings
+            "#,
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let sms_sent_this_month = sms_sent_this_month(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+
+        let row = row.unwrap_or_default();
+        Ok(Response::new(NotificationSettings {
+            email_from_address: row.email_from_address.unwrap_or_default(),
+            email_from_name: row.email_from_name.unwrap_or_default(),
+            email_reply_to: row.email_reply_to.unwrap_or_default(),
+            lineworks_bot_config_id: row.lineworks_bot_config_id.unwrap_or_default(),
+            sms_recipients: row.sms_recipients,
+            sms_monthly_limit: row.sms_monthly_limit,
+            sms_sent_this_month,
+            digest_low_stock_threshold: row.digest_low_stock_threshold,
+        }))
+    }
+
+    async fn update_notification_settings(
+        &self,
+        request: Request<NotificationSettings>,
+    ) -> Result<Response<NotificationSettings>, Status> {
+        let (auth_user, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+
+        for address in [&req.email_from_address, &req.email_reply_to] {
+            if let Some(address) = non_empty(address) {
+                address
+                    .parse::<lettre::Address>()
+                    .map_err(|_| Status::invalid_argument(format!("Invalid email address: {}", address)))?;
+            }
+        }
+
+        if req.sms_monthly_limit < 0 {
+            return Err(Status::invalid_argument("sms_monthly_limit must not be negative"));
+        }
+        let mut sms_recipients: Vec<String> = req
+            .sms_recipients
+            .iter()
+            .filter_map(|n| non_empty(n))
+            .map(|n| n.replace([' ', '-'], ""))
+            .collect();
+        if let Some(invalid) = sms_recipients.iter().find(|n| !is_valid_phone_number(n)) {
+            return Err(Status::invalid_argument(format!(
+                "Invalid phone number (E.164, e.g. +819012345678): {}",
+                invalid
+            )));
+        }
+        sms_recipients.sort();
+        sms_recipients.dedup();
+
+        let bot_config_id = non_empty(&req.lineworks_bot_config_id);
+        if let Some(id) = bot_config_id {
+            let id = uuid::Uuid::parse_str(id)
+                .map_err(|_| Status::invalid_argument("Invalid lineworks_bot_config_id"))?;
+            let bot_exists: Option<(i32,)> = sqlx::query_as(
+                "SELECT 1 FROM bot_configs WHERE id = $1 AND provider = 'lineworks'",
+            )
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+            if bot_exists.is_none() {
+                return Err(Status::not_found(format!("LINE WORKS bot config not found: {}", id)));
+            }
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO notification_settings
+                (organization_id, email_from_address, email_from_name, email_reply_to, lineworks_bot_config_id,
+                 sms_recipients, sms_monthly_limit, digest_low_stock_threshold)
+            VALUES ($1::uuid, $2, $3, $4, $5::uuid, $6, $7, $8)
+            ON CONFLICT (organization_id) DO UPDATE
+            SET email_from_address = EXCLUDED.email_from_address,
+                email_from_name = EXCLUDED.email_from_name,
+                email_reply_to = EXCLUDED.email_reply_to,
+                lineworks_bot_config_id = EXCLUDED.lineworks_bot_config_id,
+                sms_recipients = EXCLUDED.sms_recipients,
+                sms_monthly_limit = EXCLUDED.sms_monthly_limit,
+                digest_low_stock_threshold = EXCLUDED.digest_low_stock_threshold,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(&auth_user.org_id)
+        .bind(non_empty(&req.email_from_address))
+        .bind(non_empty(&req.email_from_name))
+        .bind(non_empty(&req.email_reply_to))
+        .bind(bot_config_id)
+        .bind(&sms_recipients)
+        .bind(req.sms_monthly_limit)
+        .bind(req.digest_low_stock_threshold)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let sms_sent_this_month = sms_sent_this_month(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(Response::new(NotificationSettings {
+            email_from_address: non_empty(&req.email_from_address).unwrap_or_default().to_string(),
+            email_from_name: non_empty(&req.email_from_name).unwrap_or_default().to_string(),
+            email_reply_to: non_empty(&req.email_reply_to).unwrap_or_default().to_string(),
+            lineworks_bot_config_id: bot_config_id.unwrap_or_default().to_string(),
+            sms_recipients,
+            sms_monthly_limit: req.sms_monthly_limit,
+            sms_sent_this_month,
+            digest_low_stock_threshold: req.digest_low_stock_threshold,
+        }))
+    }
+
+    async fn list_notification_deliveries(
+        &self,
+        request: Request<ListNotificationDeliveriesRequest>,
+    ) -> Result<Response<ListNotificationDeliveriesResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        let deliveries: Vec<NotificationDeliveryModel> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM notification_deliveries
+            WHERE ($1 = '' OR status = $1)
+              AND ($2 = '' OR channel = $2)
+              AND ($3::bigint IS NULL OR id < $3)
+            ORDER BY id DESC
+            LIMIT $4
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(&req.status)
+        .bind(&req.channel)
+        .bind(paginator.cursor_as::<i64>(0)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (deliveries, pagination) = paginator.finish(deliveries, |d| vec![d.id.to_string()]);
+
+        Ok(Response::new(ListNotificationDeliveriesResponse {
+            deliveries: deliveries.iter().map(NotificationDeliveryModel::to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn list_notification_webhooks(
+        &self,
+        request: Request<ListNotificationWebhooksRequest>,
+    ) -> Result<Response<ListNotificationWebhooksResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+
+        let webhooks: Vec<NotificationWebhookModel> = sqlx::query_as(&format!(
+            "SELECT {} FROM notification_webhooks ORDER BY created_at",
+            WEBHOOK_COLUMNS
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListNotificationWebhooksResponse {
+            webhooks: webhooks.iter().map(|w| self.webhook_to_proto(w)).collect(),
+        }))
+    }
+
+    async fn upsert_notification_webhook(
+        &self,
+        request: Request<UpsertNotificationWebhookRequest>,
+    ) -> Result<Response<NotificationWebhook>, Status> {
+        let (auth_user, mut conn) = self.admin_conn(&request).await?;
+        let mut req = request.into_inner();
+        req.name = validation::line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        req.url = validation::line("url", &req.url, validation::URL_MAX_CHARS)?;
+
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+        if req.provider != "slack" && req.provider != "discord" {
+            return Err(Status::invalid_argument("provider must be slack or discord"));
+        }
+        let url_encrypted = match non_empty(&req.url) {
+            Some(url) => {
+                if !is_valid_webhook_url(&req.provider, url) {
+                    return Err(Status::invalid_argument(format!("Invalid {} webhook URL", req.provider)));
+                }
+                Some(
+                    lineworks_auth::encrypt_secret(url, &self.jwt_secret)
+                        .map_err(|e| Status::internal(format!("Encrypt error: {}", e)))?,
+                )
+            }
+            None => None,
+        };
+
+        let webhook: Option<NotificationWebhookModel> = if req.id.is_empty() {
+            let url_encrypted = url_encrypted
+                .ok_or_else(|| Status::invalid_argument("url is required for new webhook"))?;
+            sqlx::query_as(&format!(
+                r#"
+                INSERT INTO notification_webhooks (organization_id, provider, name, url_encrypted, enabled)
+                VALUES ($1::uuid, $2, $3, $4, $5)
+                RETURNING {}
+                "#,
+                WEBHOOK_COLUMNS
+            ))
+            .bind(&auth_user.org_id)
+            .bind(&req.provider)
+            .bind(req.name.trim())
+            .bind(&url_encrypted)
+            .bind(req.enabled)
+            .fetch_optional(&mut *conn)
+            .await
+        } else {
+            let id = uuid::Uuid::parse_str(&req.id)
+                .map_err(|_| Status::invalid_argument("Invalid webhook id"))?;
+            // provider を変える場合は URL も入れ直す
+            sqlx::query_as(&format!(
+                r#"
+                UPDATE notification_webhooks
+                SET provider = $2, name = $3, url_encrypted = COALESCE($4, url_encrypted),
+                    enabled = $5, updated_at = NOW()
+                WHERE id = $1 AND ($4 IS NOT NULL OR provider = $2)
+                RETURNING {}
+                "#,
+                WEBHOOK_COLUMNS
+            ))
+            .bind(id)
+            .bind(&req.provider)
+            .bind(req.name.trim())
+            .bind(&url_encrypted)
+            .bind(req.enabled)
+            .fetch_optional(&mut *conn)
+            .await
+        }
+        .map_err(AppError::from)?;
+
+        let webhook = webhook.ok_or_else(|| {
+            Status::not_found(format!("Webhook not found (or url required to change provider): {}", req.id))
+        })?;
+        tracing::info!("Notification webhook {} ({}) saved", webhook.id, webhook.provider);
+        Ok(Response::new(self.webhook_to_proto(&webhook)))
+    }
+
+    async fn delete_notification_webhook(
+        &self,
+        request: Request<DeleteNotificationWebhookRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let id = uuid::Uuid::parse_str(&request.into_inner().id)
+            .map_err(|_| Status::invalid_argument("Invalid webhook id"))?;
+
+        let result = sqlx::query("DELETE FROM notification_webhooks WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::not_found(format!("Webhook not found: {}", id)));
+        }
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn list_notification_templates(
+        &self,
+        request: Request<ListNotificationTemplatesRequest>,
+    ) -> Result<Response<ListNotificationTemplatesResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+
+        let overrides: Vec<TemplateOverride> = sqlx::query_as(
+            r#"
+            SELECT template_key, channel, subject, body, updated_at
+            FROM notification_templates
+            ORDER BY template_key, channel
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        // 組み込みごとに共通（channel = ''）の上書きかデフォルト、続けてチャネル別の上書き
+        let mut templates = Vec::new();
+        for builtin in BUILTIN_TEMPLATES {
+            let rows: Vec<&TemplateOverride> =
+                overrides.iter().filter(|o| o.template_key == builtin.key).collect();
+            let common = rows.iter().find(|o| o.channel.is_empty()).copied();
+            templates.push(template_to_proto(builtin, common));
+            templates.extend(
+                rows.iter()
+                    .filter(|o| !o.channel.is_empty())
+                    .map(|o| template_to_proto(builtin, Some(*o))),
+            );
+        }
+
+        Ok(Response::new(ListNotificationTemplatesResponse { templates }))
+    }
+
+    async fn upsert_notification_template(
+        &self,
+        request: Request<UpsertNotificationTemplateRequest>,
+    ) -> Result<Response<NotificationTemplate>, Status> {
+        let (auth_user, mut conn) = self.admin_conn(&request).await?;
+        let mut req = request.into_inner();
+        let builtin = validate_template_target(&req.template_key, &req.channel)?;
+        req.subject = validation::line("subject", &req.subject, validation::NAME_MAX_CHARS)?;
+        req.body = validation::text("body", &req.body, validation::TEXT_MAX_CHARS)?;
+
+        if req.subject.is_empty() || req.body.is_empty() {
+            return Err(Status::invalid_argument("subject and body are required"));
+        }
+        let allowed: Vec<&str> = builtin.variables.iter().map(|(name, _)| *name).collect();
+        let unknown = unknown_variables(&format!("{}\n{}", req.subject, req.body), &allowed);
+        if !unknown.is_empty() {
+            return Err(Status::invalid_argument(format!(
+                "Unknown variables: {} (available: {})",
+                unknown.join(", "),
+                allowed.join(", ")
+            )));
+        }
+
+        let row: TemplateOverride = sqlx::query_as(
+            r#"
+            INSERT INTO notification_templates (organization_id, template_key, channel, subject, body)
+            VALUES ($1::uuid, $2, $3, $4, $5)
+            ON CONFLICT (organization_id, template_key, channel) DO UPDATE
+            SET subject = EXCLUDED.subject, body = EXCLUDED.body, updated_at = NOW()
+            RETURNING template_key, channel, subject, body, updated_at
+            "#,
+        )
+        .bind(&auth_user.org_id)
+        .bind(&req.template_key)
+        .bind(&req.channel)
+        .bind(&req.subject)
+        .bind(&req.body)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!("Notification template {} (channel: {:?}) updated", row.template_key, row.channel);
+        Ok(Response::new(template_to_proto(&builtin, Some(&row))))
+    }
+
+    async fn delete_notification_template(
+        &self,
+        request: Request<DeleteNotificationTemplateRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        validate_template_target(&req.template_key, &req.channel)?;
+
+        let result = sqlx::query("DELETE FROM notification_templates WHERE template_key = $1 AND channel = $2")
+            .bind(&req.template_key)
+            .bind(&req.channel)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::not_found(format!(
+                "Template is not customized: {} (channel: {:?})",
+                req.template_key, req.channel
+            )));
+        }
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn preview_notification_template(
+        &self,
+        request: Request<PreviewNotificationTemplateRequest>,
+    ) -> Result<Response<PreviewNotificationTemplateResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        let builtin = validate_template_target(&req.template_key, &req.channel)?;
+
+        // 件名・本文が空なら実際の送信と同じ優先順位で保存済み / 組み込みを使う
+        let overrides: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT channel, subject, body FROM notification_templates WHERE template_key = $1",
+        )
+        .bind(&req.template_key)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let (saved_subject, saved_body) = resolve_template(&builtin, &overrides, &req.channel);
+        let subject = if req.subject.is_empty() { saved_subject } else { req.subject.as_str() };
+        let body = if req.body.is_empty() { saved_body } else { req.body.as_str() };
+
+        let allowed: Vec<&str> = builtin.variables.iter().map(|(name, _)| *name).collect();
+        let unknown = unknown_variables(&format!("{}\n{}", subject, body), &allowed);
+
+        let mut vars = builtin.sample_vars();
+        vars.extend(req.variables);
+        Ok(Response::new(PreviewNotificationTemplateResponse {
+            subject: render(subject, &vars),
+            body: render(body, &vars),
+            unknown_variables: unknown,
+        }))
+    }
+}
@@ -1,20 +1,27 @@
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
+use crate::db::OrgScopedPool;
+use crate::error::AppError;
 use crate::middleware::AuthenticatedUser;
 use crate::proto::common::Empty;
 use crate::proto::organization::organization_service_server::OrganizationService;
 use crate::proto::organization::{
-    ListOrganizationsResponse, Organization, OrganizationResponse, UpdateOrganizationRequest,
+    ListOrganizationsResponse, Organization, OrganizationResponse, OrganizationUsage,
+    UpdateOrganizationRequest,
 };
+use crate::services::validation;
+use crate::usage::storage_usage;
 
 pub struct OrganizationServiceImpl {
     pool: PgPool,
+    /// 組織ごとの保存量の既定の上限（STORAGE_QUOTA_BYTES）
+    storage_quota: Option<i64>,
 }
 
 impl OrganizationServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, storage_quota: Option<i64>) -> Self {
+        Self { pool, storage_quota }
     }
 
     fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
@@ -41,7 +48,7 @@ impl OrganizationService for OrganizationServiceImpl {
             .bind(&user.user_id)
             .fetch_all(&self.pool)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         let organizations = rows
             .into_iter()
@@ -67,6 +74,11 @@ impl OrganizationService for OrganizationServiceImpl {
         if req.organization_id.is_empty() {
             return Err(Status::invalid_argument("organization_id is required"));
         }
+        let name = validation::required_line("name", &req.name, validation::NAME_MAX_CHARS)?;
+        let slug = validation::code("slug", &req.slug, validation::CODE_MAX_CHARS)?;
+        if slug.is_empty() {
+            return Err(Status::invalid_argument("slug is required"));
+        }
 
         // Verify caller is admin of this organization
         let role: Option<(String,)> = sqlx::query_as(
@@ -76,7 +88,7 @@ impl OrganizationService for OrganizationServiceImpl {
         .bind(&req.organization_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match role {
             Some((r,)) if r == "admin" => {}
@@ -94,17 +106,16 @@ impl OrganizationService for OrganizationServiceImpl {
              WHERE id = $3::uuid AND deleted_at IS NULL
              RETURNING id::text, name, slug, created_at",
         )
-        .bind(&req.name)
-        .bind(&req.slug)
+        .bind(&name)
+        .bind(&slug)
         .bind(&req.organization_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| {
-            if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
                 Status::already_exists("Organization slug already taken")
-            } else {
-                Status::internal(format!("Database error: {}", e))
             }
+            e => AppError::from(e).into(),
         })?;
 
         let (id, name, slug, created_at) =
@@ -120,4 +131,23 @@ impl OrganizationService for OrganizationServiceImpl {
             }),
         }))
     }
+
+    async fn get_usage(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<OrganizationUsage>, Status> {
+        let user = Self::get_authenticated_user(&request)?;
+
+        let mut conn = self.pool.acquire_scoped(&user.org_id).await.map_err(AppError::from)?;
+        let usage = storage_usage(&mut conn, self.storage_quota)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(Response::new(OrganizationUsage {
+            organization_id: user.org_id,
+            storage_bytes: usage.storage_bytes,
+            file_count: usage.file_count,
+            quota_bytes: usage.quota_bytes,
+        }))
+    }
 }
@@ -0,0 +1,217 @@
+// 組織ごとのパスワードポリシー（password_policies）
+//
+// - 作成・変更時（招待の受諾、再設定、CLI）に `check` で長さ・文字種を、`check_reuse` で直近のパスワードとの重複を確かめる
+// - ログイン時に `is_expired` で有効期限を確かめる（期限切れは再設定してもらう）
+// - 行がない組織は既定（8 文字以上のみ）
+
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{FromRow, PgConnection};
+use tonic::Status;
+
+/// 最小文字数の下限（ポリシーでこれより短くはできない）
+pub const MIN_PASSWORD_LENGTH: i32 = 8;
+pub const MAX_PASSWORD_LENGTH: i32 = 128;
+/// 再利用禁止にできる過去のパスワードの数の上限
+pub const MAX_HISTORY_COUNT: i32 = 24;
+pub const MAX_AGE_DAYS_LIMIT: i32 = 3650;
+
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct PasswordPolicy {
+    pub min_length: i32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub history_count: i32,
+    pub max_age_days: i32,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: MIN_PASSWORD_LENGTH,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            history_count: 0,
+            max_age_days: 0,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// 組織のポリシー（organization 設定済みの接続で呼ぶ）
+    pub async fn load(conn: &mut PgConnection) -> Result<Self, sqlx::Error> {
+        let policy: Option<Self> = sqlx::query_as(
+            "SELECT min_length, require_uppercase, require_lowercase, require_digit, require_symbol,
+                    history_count, max_age_days
+             FROM password_policies",
+        )
+        .fetch_optional(conn)
+        .await?;
+        Ok(policy.unwrap_or_default())
+    }
+
+    /// 保存できる値か（範囲外は INVALID_ARGUMENT）
+    pub fn validate(&self) -> Result<(), Status> {
+        if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&self.min_length) {
+            return Err(Status::invalid_argument(format!(
+                "min_length must be between {} and {}",
+                MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+            )));
+        }
+        if !(0..=MAX_HISTORY_COUNT).contains(&self.history_count) {
+            return Err(Status::invalid_argument(format!(
+                "history_count must be between 0 and {}",
+                MAX_HISTORY_COUNT
+            )));
+        }
+        if !(0..=MAX_AGE_DAYS_LIMIT).contains(&self.max_age_days) {
+            return Err(Status::invalid_argument(format!(
+                "max_age_days must be between 0 and {}",
+                MAX_AGE_DAYS_LIMIT
+            )));
+        }
+        Ok(())
+    }
+
+    /// 画面に出す要件（日本語、ポリシーの順）
+    pub fn requirements(&self) -> Vec<String> {
+        let mut requirements = vec![format!("{}文字以上", self.min_length)];
+        if self.require_uppercase {
+            requirements.push("英大文字を含む".to_string());
+        }
+        if self.require_lowercase {
+            requirements.push("英小文字を含む".to_string());
+        }
+        if self.require_digit {
+            requirements.push("数字を含む".to_string());
+        }
+        if self.require_symbol {
+            requirements.push("記号を含む".to_string());
+        }
+        if self.history_count > 0 {
+            requirements.push(format!("直近{}回と同じパスワードは使えません", self.history_count));
+        }
+        if self.max_age_days > 0 {
+            requirements.push(format!("{}日ごとに変更が必要です", self.max_age_days));
+        }
+        requirements
+    }
+
+    /// 満たしていない要件（空なら OK）
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length as usize {
+            violations.push(format!("at least {} characters", self.min_length));
+        }
+        if length > MAX_PASSWORD_LENGTH as usize {
+            violations.push(format!("at most {} characters", MAX_PASSWORD_LENGTH));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_ascii_uppercase()) {
+            violations.push("an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_ascii_lowercase()) {
+            violations.push("a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("a digit".to_string());
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push("a symbol".to_string());
+        }
+        violations
+    }
+
+    /// 長さ・文字種（満たさなければ INVALID_ARGUMENT、足りない要件を列挙）
+    pub fn check(&self, password: &str) -> Result<(), Status> {
+        let violations = self.violations(password);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Status::invalid_argument(format!(
+                "Password does not meet the policy: requires {}",
+                violations.join(", ")
+            )))
+        }
+    }
+
+    /// 現在と直近 history_count 個のパスワードと同じなら INVALID_ARGUMENT
+    ///
+    /// organization 設定済みの接続で、パスワードを更新する前に呼ぶ。
+    pub async fn check_reuse(
+        &self,
+        conn: &mut PgConnection,
+        credential_id: &str,
+        password: &str,
+    ) -> Result<(), Status> {
+        if self.history_count == 0 {
+            return Ok(());
+        }
+        let hashes: Vec<String> = sqlx::query_scalar(
+            "(SELECT password_hash FROM password_credentials WHERE id = $1::uuid)
+             UNION ALL
+             (SELECT password_hash FROM password_history WHERE credential_id = $1::uuid
+              ORDER BY created_at DESC LIMIT $2)",
+        )
+        .bind(credential_id)
+        // 現在のパスワードも 1 個と数える
+        .bind(i64::from(self.history_count - 1))
+        .fetch_all(conn)
+        .await
+        .map_err(crate::error::AppError::from)?;
+
+        let reused = hashes.iter().any(|hash| {
+            PasswordHash::new(hash)
+                .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+                .unwrap_or(false)
+        });
+        if reused {
+            return Err(Status::invalid_argument(format!(
+                "Password must differ from the last {} passwords",
+                self.history_count
+            )));
+        }
+        Ok(())
+    }
+
+    /// 最後に変えてから max_age_days を過ぎたか
+    pub fn is_expired(&self, changed_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.max_age_days > 0 && changed_at + Duration::days(i64::from(self.max_age_days)) <= now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_lists_missing_requirements() {
+        let policy = PasswordPolicy {
+            min_length: 10,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.violations("short"),
+            vec!["at least 10 characters", "an uppercase letter", "a digit", "a symbol"]
+        );
+        assert!(policy.violations("Longer-pass1").is_empty());
+        assert!(PasswordPolicy::default().check("12345678").is_ok());
+        assert!(PasswordPolicy::default().check("1234567").is_err());
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = Utc::now();
+        let policy = PasswordPolicy { max_age_days: 90, ..Default::default() };
+        assert!(!policy.is_expired(now - Duration::days(89), now));
+        assert!(policy.is_expired(now - Duration::days(90), now));
+        assert!(!PasswordPolicy::default().is_expired(now - Duration::days(10_000), now));
+    }
+}
@@ -0,0 +1,454 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::db::{OrgScopedPool, Paginator};
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::proto::common::Empty;
+use crate::proto::reports::report_service_server::ReportService;
+use crate::proto::reports::{
+    DailyVehicleReport, DeleteReportScheduleRequest, DriverOperation, GenerateReportRequest,
+    GetDailyVehicleReportRequest, GetDailyVehicleReportResponse, GetReportRunRequest,
+    ListReportDefinitionsResponse, ListReportRunsRequest, ListReportRunsResponse,
+    ListReportSchedulesResponse, OperationSummary, ReportDefinition, ReportRun, ReportSchedule,
+};
+use crate::reports::{
+    create_run, daily_vehicle_report, DailyVehicleRow, ReportFormat, ReportKind, ReportPeriod,
+};
+
+/// 1回に作成できる期間の上限
+const MAX_REPORT_DAYS: i64 = 366;
+/// GetDailyVehicleReport の期間の上限
+const MAX_DAILY_REPORT_DAYS: i64 = 31;
+
+const RUN_COLUMNS: &str = "id, report_kind, format, period_from, period_to, branch, status, \
+     file_uuid::text AS file_uuid, row_count, error, requested_by IS NULL AS scheduled, created_at, completed_at";
+
+#[derive(Debug, sqlx::FromRow)]
+struct ReportRunRow {
+    id: Uuid,
+    report_kind: String,
+    format: String,
+    period_from: NaiveDate,
+    period_to: NaiveDate,
+    branch: Option<String>,
+    status: String,
+    file_uuid: Option<String>,
+    row_count: Option<i32>,
+    error: Option<String>,
+    scheduled: bool,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl ReportRunRow {
+    fn to_proto(&self) -> ReportRun {
+        ReportRun {
+            id: self.id.to_string(),
+            kind: self.report_kind.clone(),
+            format: self.format.clone(),
+            from_date: self.period_from.to_string(),
+            to_date: self.period_to.to_string(),
+            branch: self.branch.clone(),
+            status: self.status.clone(),
+            file_uuid: self.file_uuid.clone(),
+            row_count: self.row_count,
+            error: self.error.clone(),
+            scheduled: self.scheduled,
+            created_at: self.created_at.to_rfc3339(),
+            completed_at: self.completed_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+fn parse_kind(kind: &str) -> Result<ReportKind, Status> {
+    ReportKind::parse(kind).ok_or_else(|| Status::invalid_argument(format!("Unknown report kind: {}", kind)))
+}
+
+fn parse_format(format: &str) -> Result<ReportFormat, Status> {
+    ReportFormat::parse(format)
+        .ok_or_else(|| Status::invalid_argument(format!("Unknown format: {} (available: pdf, xlsx)", format)))
+}
+
+fn parse_date(value: &str, field: &str) -> Result<NaiveDate, Status> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| Status::invalid_argument(format!("Invalid {} (expected YYYY-MM-DD): {}", field, value)))
+}
+
+/// from_date / to_date（YYYY-MM-DD、両端を含む）を検証する
+pub(crate) fn parse_period(from_date: &str, to_date: &str) -> Result<(NaiveDate, NaiveDate), Status> {
+    let from = parse_date(from_date, "from_date")?;
+    let to = parse_date(to_date, "to_date")?;
+    if from > to {
+        return Err(Status::invalid_argument("from_date must not be after to_date"));
+    }
+    if (to - from).num_days() >= MAX_REPORT_DAYS {
+        return Err(Status::invalid_argument(format!(
+            "Period must be at most {} days",
+            MAX_REPORT_DAYS
+        )));
+    }
+    Ok((from, to))
+}
+
+fn operation_summary(row: &DailyVehicleRow) -> OperationSummary {
+    OperationSummary {
+        distance_km: row.distance_km,
+        driving_minutes: row.driving_seconds / 60.0,
+        idle_minutes: row.idle_seconds / 60.0,
+        max_speed: row.max_speed,
+        avg_speed: row.avg_speed,
+        log_count: row.log_count,
+    }
+}
+
+/// 車両の合計の行ごとに、続く乗務員の行をまとめる（行は日・車両順で合計が先）
+fn group_daily_rows(rows: Vec<DailyVehicleRow>) -> Vec<DailyVehicleReport> {
+    let mut reports: Vec<DailyVehicleReport> = Vec::new();
+    for row in rows {
+        if row.vehicle_total {
+            reports.push(DailyVehicleReport {
+                date: row.day.to_string(),
+                vehicle_cd: row.vehicle_cd,
+                vehicle_name: row.vehicle_name.clone(),
+                summary: Some(operation_summary(&row)),
+                drivers: Vec::new(),
+            });
+        } else if let Some(report) = reports.last_mut() {
+            report.drivers.push(DriverOperation {
+                driver_cd: row.driver_cd.unwrap_or_default(),
+                driver_name: row.driver_name.clone().unwrap_or_default(),
+                summary: Some(operation_summary(&row)),
+            });
+        }
+    }
+    reports
+}
+
+pub struct ReportServiceImpl {
+    pool: PgPool,
+}
+
+impl ReportServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    /// organization 設定済みのコネクション（admin_only なら管理者確認も）
+    async fn conn<T>(
+        &self,
+        request: &Request<T>,
+        admin_only: bool,
+    ) -> Result<(AuthenticatedUser, sqlx::pool::PoolConnection<sqlx::Postgres>), Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        if admin_only {
+            self.verify_admin(&auth_user.user_id, &auth_user.org_id).await?;
+        }
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok((auth_user, conn))
+    }
+}
+
+#[tonic::async_trait]
+impl ReportService for ReportServiceImpl {
+    async fn list_report_definitions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<ListReportDefinitionsResponse>, Status> {
+        let definitions = ReportKind::ALL
+            .iter()
+            .map(|kind| ReportDefinition {
+                kind: kind.as_str().to_string(),
+                title: kind.title().to_string(),
+                description: kind.description().to_string(),
+                scheduled_task: kind.scheduled_job().to_string(),
+                supports_branch: kind.supports_branch(),
+            })
+            .collect();
+        Ok(Response::new(ListReportDefinitionsResponse { definitions }))
+    }
+
+    async fn generate_report(
+        &self,
+        request: Request<GenerateReportRequest>,
+    ) -> Result<Response<ReportRun>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        let req = request.into_inner();
+        let kind = parse_kind(&req.kind)?;
+        let format = parse_format(&req.format)?;
+        let (from, to) = parse_period(&req.from_date, &req.to_date)?;
+        let branch = req.branch.as_deref().map(str::trim).filter(|b| !b.is_empty());
+        if branch.is_some() && !kind.supports_branch() {
+            return Err(Status::invalid_argument(format!(
+                "Report kind {} cannot be filtered by branch",
+                kind.as_str()
+            )));
+        }
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        let run_id = create_run(
+            &mut tx,
+            &auth_user.org_id,
+            kind,
+            format,
+            from,
+            to,
+            branch,
+            Some(&auth_user.user_id),
+            &[],
+        )
+            .await
+            .map_err(AppError::from)?;
+        let run: ReportRunRow = sqlx::query_as(&format!("SELECT {} FROM report_runs WHERE id = $1", RUN_COLUMNS))
+            .bind(run_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(Response::new(run.to_proto()))
+    }
+
+    async fn list_report_runs(
+        &self,
+        request: Request<ListReportRunsRequest>,
+    ) -> Result<Response<ListReportRunsResponse>, Status> {
+        let (_, mut conn) = self.conn(&request, false).await?;
+        let req = request.into_inner();
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+        let cursor_created_at = paginator.cursor_as::<DateTime<Utc>>(0)?;
+        let cursor_id = paginator.cursor_as::<Uuid>(1)?;
+
+        let rows: Vec<ReportRunRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM report_runs
+            WHERE ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(cursor_created_at)
+        .bind(cursor_id)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (rows, pagination) =
+            paginator.finish(rows, |r| vec![r.created_at.to_rfc3339(), r.id.to_string()]);
+        Ok(Response::new(ListReportRunsResponse {
+            runs: rows.iter().map(ReportRunRow::to_proto).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn get_report_run(
+        &self,
+        request: Request<GetReportRunRequest>,
+    ) -> Result<Response<ReportRun>, Status> {
+        let (_, mut conn) = self.conn(&request, false).await?;
+        let id: Uuid = request
+            .get_ref()
+            .id
+            .parse()
+            .map_err(|_| Status::invalid_argument("Invalid report id"))?;
+        let run: Option<ReportRunRow> =
+            sqlx::query_as(&format!("SELECT {} FROM report_runs WHERE id = $1", RUN_COLUMNS))
+                .bind(id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(AppError::from)?;
+        run.map(|r| Response::new(r.to_proto()))
+            .ok_or_else(|| Status::not_found("Report not found"))
+    }
+
+    async fn get_daily_vehicle_report(
+        &self,
+        request: Request<GetDailyVehicleReportRequest>,
+    ) -> Result<Response<GetDailyVehicleReportResponse>, Status> {
+        let (_, mut conn) = self.conn(&request, false).await?;
+        let req = request.into_inner();
+        let (from, to) = parse_period(&req.from_date, &req.to_date)?;
+        if (to - from).num_days() >= MAX_DAILY_REPORT_DAYS {
+            return Err(Status::invalid_argument(format!(
+                "Period must be at most {} days",
+                MAX_DAILY_REPORT_DAYS
+            )));
+        }
+
+        let rows = daily_vehicle_report(&mut conn, from, to, &req.vehicle_cds)
+            .await
+            .map_err(AppError::from)?;
+        Ok(Response::new(GetDailyVehicleReportResponse {
+            reports: group_daily_rows(rows),
+        }))
+    }
+
+    async fn list_report_schedules(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<ListReportSchedulesResponse>, Status> {
+        let (_, mut conn) = self.conn(&request, true).await?;
+        let rows: Vec<(String, String, String, Vec<String>)> = sqlx::query_as(
+            "SELECT report_kind, format, period, recipients FROM report_schedules ORDER BY report_kind",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListReportSchedulesResponse {
+            schedules: rows
+                .into_iter()
+                .map(|(kind, format, period, recipients)| ReportSchedule {
+                    kind,
+                    format,
+                    period,
+                    recipients,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn upsert_report_schedule(
+        &self,
+        request: Request<ReportSchedule>,
+    ) -> Result<Response<ReportSchedule>, Status> {
+        let (auth_user, mut conn) = self.conn(&request, true).await?;
+        let req = request.into_inner();
+        let kind = parse_kind(&req.kind)?;
+        let format = parse_format(&req.format)?;
+        let period = ReportPeriod::parse(&req.period).ok_or_else(|| {
+            Status::invalid_argument(format!(
+                "Unknown period: {} (available: previous_day, previous_week, previous_month)",
+                req.period
+            ))
+        })?;
+        let recipients: Vec<String> = req
+            .recipients
+            .iter()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect();
+        if let Some(invalid) = recipients.iter().find(|r| !r.contains('@')) {
+            return Err(Status::invalid_argument(format!("Invalid email address: {}", invalid)));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO report_schedules (organization_id, report_kind, format, period, recipients)
+            VALUES ($1::uuid, $2, $3, $4, $5)
+            ON CONFLICT (organization_id, report_kind) DO UPDATE
+            SET format = EXCLUDED.format, period = EXCLUDED.period, recipients = EXCLUDED.recipients,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(&auth_user.org_id)
+        .bind(kind.as_str())
+        .bind(format.as_str())
+        .bind(period.as_str())
+        .bind(&recipients)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ReportSchedule {
+            kind: kind.as_str().to_string(),
+            format: format.as_str().to_string(),
+            period: period.as_str().to_string(),
+            recipients,
+        }))
+    }
+
+    async fn delete_report_schedule(
+        &self,
+        request: Request<DeleteReportScheduleRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (_, mut conn) = self.conn(&request, true).await?;
+        let kind = parse_kind(&request.get_ref().kind)?;
+        sqlx::query("DELETE FROM report_schedules WHERE report_kind = $1")
+            .bind(kind.as_str())
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(Response::new(Empty {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(vehicle_cd: i32, driver_cd: Option<i32>, driving_seconds: f64) -> DailyVehicleRow {
+        DailyVehicleRow {
+            day: NaiveDate::from_ymd_opt(2026, 1, 24).unwrap(),
+            vehicle_cd,
+            vehicle_total: driver_cd.is_none(),
+            driver_cd,
+            vehicle_name: format!("車両{}", vehicle_cd),
+            driver_name: driver_cd.map(|cd| format!("乗務員{}", cd)),
+            distance_km: Some(10.0),
+            driving_seconds,
+            idle_seconds: 0.0,
+            max_speed: 60.0,
+            avg_speed: Some(40.0),
+            log_count: 1,
+        }
+    }
+
+    #[test]
+    fn group_daily_rows_nests_drivers_under_vehicle_total() {
+        let reports = group_daily_rows(vec![
+            row(1, None, 5400.0),
+            row(1, Some(10), 3600.0),
+            row(1, Some(11), 1800.0),
+            row(2, None, 600.0),
+            row(2, Some(10), 600.0),
+        ]);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].date, "2026-01-24");
+        assert_eq!(reports[0].summary.as_ref().unwrap().driving_minutes, 90.0);
+        let drivers: Vec<i32> = reports[0].drivers.iter().map(|d| d.driver_cd).collect();
+        assert_eq!(drivers, vec![10, 11]);
+        assert_eq!(reports[1].drivers.len(), 1);
+        assert_eq!(reports[1].drivers[0].driver_name, "乗務員10");
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use tonic::{Request, Response, Status};
+
+use crate::db::organization::OrgScopedPool;
+use crate::error::AppError;
+use crate::jobs::scheduler::{next_run_after, parse_cron};
+use crate::jobs::ScheduledTaskDef;
+use crate::middleware::AuthenticatedUser;
+use crate::proto::scheduler::scheduler_service_server::SchedulerService;
+use crate::proto::scheduler::{
+    ListScheduledTasksRequest, ListScheduledTasksResponse, ScheduledTask,
+    UpdateScheduledTaskRequest,
+};
+
+/// scheduled_tasks LEFT JOIN jobs（直近の実行）の結果行
+#[derive(FromRow)]
+struct ScheduledTaskRow {
+    task: String,
+    cron_expression: String,
+    enabled: bool,
+    next_run_at: Option<DateTime<Utc>>,
+    last_run_at: Option<DateTime<Utc>>,
+    last_job_id: Option<i64>,
+    last_job_status: Option<String>,
+}
+
+pub struct SchedulerServiceImpl {
+    pool: PgPool,
+    tasks: Vec<ScheduledTaskDef>,
+}
+
+impl SchedulerServiceImpl {
+    pub fn new(pool: PgPool, tasks: Vec<ScheduledTaskDef>) -> Self {
+        Self { pool, tasks }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    fn to_proto(def: &ScheduledTaskDef, row: Option<&ScheduledTaskRow>) -> ScheduledTask {
+        let default = ScheduledTask {
+            task: def.name.to_string(),
+            description: def.description.to_string(),
+            cron_expression: def.default_cron.to_string(),
+            default_cron_expression: def.default_cron.to_string(),
+            ..Default::default()
+        };
+        match row {
+            Some(row) => ScheduledTask {
+                cron_expression: row.cron_expression.clone(),
+                enabled: row.enabled,
+                configured: true,
+                next_run_at: row.next_run_at.map(|t| t.to_rfc3339()),
+                last_run_at: row.last_run_at.map(|t| t.to_rfc3339()),
+                last_job_id: row.last_job_id,
+                last_job_status: row.last_job_status.clone(),
+                ..default
+            },
+            None => default,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl SchedulerService for SchedulerServiceImpl {
+    async fn list_scheduled_tasks(
+        &self,
+        request: Request<ListScheduledTasksRequest>,
+    ) -> Result<Response<ListScheduledTasksResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let rows: Vec<ScheduledTaskRow> = sqlx::query_as(
+            r#"
+            SELECT s.task, s.cron_expression, s.enabled, s.next_run_at, s.last_run_at,
+                   s.last_job_id, j.status AS last_job_status
+            FROM scheduled_tasks s
+            LEFT JOIN jobs j ON j.id = s.last_job_id
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        // 登録済みタスクの順に、未設定のものも推奨値で返す
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|def| Self::to_proto(def, rows.iter().find(|r| r.task == def.name)))
+            .collect();
+
+        Ok(Response::new(ListScheduledTasksResponse { tasks }))
+    }
+
+    async fn update_scheduled_task(
+        &self,
+        request: Request<UpdateScheduledTaskRequest>,
+    ) -> Result<Response<ScheduledTask>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+        let req = request.into_inner();
+
+        let def = self
+            .tasks
+            .iter()
+            .find(|t| t.name == req.task)
+            .ok_or_else(|| Status::not_found(format!("Unknown task: {}", req.task)))?;
+        parse_cron(&req.cron_expression).map_err(Status::invalid_argument)?;
+        let next_run_at = if req.enabled {
+            next_run_after(&req.cron_expression, Utc::now()).map_err(Status::invalid_argument)?
+        } else {
+            None
+        };
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let row: ScheduledTaskRow = sqlx::query_as(
+            r#"
+            WITH upserted AS (
+                INSERT INTO scheduled_tasks (organization_id, task, cron_expression, enabled, next_run_at)
+                VALUES ($1::uuid, $2, $3, $4, $5)
+                ON CONFLICT (organization_id, task) DO UPDATE SET
+                    cron_expression = EXCLUDED.cron_expression,
+                    enabled = EXCLUDED.enabled,
+                    next_run_at = EXCLUDED.next_run_at,
+                    updated_at = NOW()
+                RETURNING *
+            )
+            SELECT u.task, u.cron_expression, u.enabled, u.next_run_at, u.last_run_at,
+                   u.last_job_id, j.status AS last_job_status
+            FROM upserted u
+            LEFT JOIN jobs j ON j.id = u.last_job_id
+            "#,
+        )
+        .bind(&auth_user.org_id)
+        .bind(def.name)
+        .bind(req.cron_expression.trim())
+        .bind(req.enabled)
+        .bind(next_run_at)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(Self::to_proto(def, Some(&row))))
+    }
+}
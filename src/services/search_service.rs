@@ -0,0 +1,201 @@
+use sqlx::{FromRow, PgConnection, PgPool};
+use tonic::{Request, Response, Status};
+
+use crate::db::{get_organization_from_request, OrgScopedPool};
+use crate::error::AppError;
+use crate::proto::search::search_service_server::SearchService;
+use crate::proto::search::{SearchHit, SearchRequest, SearchResponse, SearchTarget};
+use crate::services::validation;
+
+/// 検索語の上限（文字数）
+const MAX_QUERY_CHARS: usize = 100;
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+/// 並び順（0: 完全一致, 1: 前方一致, 2: 部分一致）。key は search_key() 済みの列
+const RANK_SQL: &str = "CASE WHEN {key} = search_key($1) THEN 0 \
+     WHEN {key} LIKE substr(search_like_pattern($1), 2) THEN 1 ELSE 2 END";
+
+#[derive(Debug, FromRow)]
+struct SearchRow {
+    id: String,
+    title: String,
+    subtitle: String,
+    matched_field: String,
+}
+
+impl SearchRow {
+    fn into_hit(self, target: SearchTarget) -> SearchHit {
+        SearchHit {
+            target: target as i32,
+            id: self.id,
+            title: self.title,
+            subtitle: self.subtitle,
+            matched_field: self.matched_field,
+        }
+    }
+}
+
+fn rank_sql(key: &str) -> String {
+    RANK_SQL.replace("{key}", key)
+}
+
+/// 検索対象（未指定なら全て、重複は除く）
+fn resolve_targets(targets: &[i32]) -> Result<Vec<SearchTarget>, Status> {
+    let mut result = Vec::new();
+    for value in targets {
+        let target = SearchTarget::try_from(*value)
+            .ok()
+            .filter(|t| *t != SearchTarget::Unspecified)
+            .ok_or_else(|| Status::invalid_argument(format!("Unknown search target: {}", value)))?;
+        if !result.contains(&target) {
+            result.push(target);
+        }
+    }
+    if result.is_empty() {
+        result = vec![SearchTarget::File, SearchTarget::CarInspection];
+    }
+    Ok(result)
+}
+
+fn resolve_limit(limit: i32) -> Result<i64, Status> {
+    match i64::from(limit) {
+        0 => Ok(DEFAULT_LIMIT),
+        n if (1..=MAX_LIMIT).contains(&n) => Ok(n),
+        _ => Err(Status::invalid_argument(format!(
+            "limit must be between 1 and {}",
+            MAX_LIMIT
+        ))),
+    }
+}
+
+pub struct SearchServiceImpl {
+    pool: PgPool,
+}
+
+impl SearchServiceImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn search_files(conn: &mut PgConnection, query: &str, limit: i64) -> Result<Vec<SearchRow>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT uuid::text AS id, filename AS title, type AS subtitle, 'filename' AS matched_field
+            FROM files
+            WHERE deleted_at IS NULL
+              AND search_key(filename) LIKE search_like_pattern($1)
+            ORDER BY {rank}, created_at DESC
+            LIMIT $2
+            "#,
+            rank = rank_sql("search_key(filename)"),
+        ))
+        .bind(query)
+        .bind(limit)
+        .fetch_all(conn)
+        .await
+    }
+
+    /// 車両（CarId）ごとに一致した最新の車検証。どの項目で一致したかは優先順（登録番号 → 車台番号 → 所有者 → 使用者）で1つ
+    async fn search_car_inspections(
+        conn: &mut PgConnection,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SearchRow>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            r#"
+            WITH matched AS (
+                SELECT DISTINCT ON ("CarId")
+                       "ElectCertMgNo" || '/' || "GrantdateE" || '/' || "GrantdateY" || '/' ||
+                           "GrantdateM" || '/' || "GrantdateD" AS id,
+                       "EntryNoCarNo", "CarNo", "CarName", "OwnernameLowLevelChar", "OwnernameHighLevelChar",
+                       "UsernameLowLevelChar", "UsernameHighLevelChar"
+                FROM car_inspection
+                WHERE search_key(
+                        "EntryNoCarNo" || '|' || "CarNo" || '|' ||
+                        "OwnernameLowLevelChar" || '|' || "OwnernameHighLevelChar" || '|' ||
+                        "UsernameLowLevelChar" || '|' || "UsernameHighLevelChar"
+                      ) LIKE search_like_pattern($1)
+                ORDER BY "CarId", "TwodimensionCodeInfoValidPeriodExpirdate" DESC, created_at DESC
+            )
+            SELECT m.id, m."EntryNoCarNo" AS title,
+                   concat_ws(' / ', NULLIF(m."CarName", ''), NULLIF(m."OwnernameLowLevelChar", '')) AS subtitle,
+                   f.field AS matched_field
+            FROM matched m
+            CROSS JOIN LATERAL (
+                SELECT v.field, {rank} AS match_rank
+                FROM (VALUES
+                    (1, 'EntryNoCarNo', search_key(m."EntryNoCarNo")),
+                    (2, 'CarNo', search_key(m."CarNo")),
+                    (3, 'Ownername', search_key(m."OwnernameLowLevelChar" || '|' || m."OwnernameHighLevelChar")),
+                    (4, 'Username', search_key(m."UsernameLowLevelChar" || '|' || m."UsernameHighLevelChar"))
+                ) AS v(ord, field, key)
+                WHERE v.key LIKE search_like_pattern($1)
+                ORDER BY match_rank, v.ord
+                LIMIT 1
+            ) f
+            ORDER BY f.match_rank, m."EntryNoCarNo"
+            LIMIT $2
+            "#,
+            rank = rank_sql("v.key"),
+        ))
+        .bind(query)
+        .bind(limit)
+        .fetch_all(conn)
+        .await
+    }
+}
+
+#[tonic::async_trait]
+impl SearchService for SearchServiceImpl {
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchResponse>, Status> {
+        let organization_id = get_organization_from_request(&request);
+        let req = request.into_inner();
+        let query = validation::required_line("query", &req.query, MAX_QUERY_CHARS)?;
+        let targets = resolve_targets(&req.targets)?;
+        let limit = resolve_limit(req.limit)?;
+
+        let mut conn = self.pool.acquire_scoped(&organization_id).await
+            .map_err(AppError::from)?;
+
+        let mut hits = Vec::new();
+        for target in targets {
+            let rows = match target {
+                SearchTarget::File => Self::search_files(&mut conn, &query, limit).await,
+                SearchTarget::CarInspection => Self::search_car_inspections(&mut conn, &query, limit).await,
+                SearchTarget::Unspecified => continue,
+            }
+            .map_err(AppError::from)?;
+            hits.extend(rows.into_iter().map(|row| row.into_hit(target)));
+        }
+
+        Ok(Response::new(SearchResponse { hits }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_default_to_all_and_reject_unknown() {
+        assert_eq!(
+            resolve_targets(&[]).unwrap(),
+            vec![SearchTarget::File, SearchTarget::CarInspection]
+        );
+        assert_eq!(
+            resolve_targets(&[2, 2]).unwrap(),
+            vec![SearchTarget::CarInspection]
+        );
+        assert!(resolve_targets(&[0]).is_err());
+        assert!(resolve_targets(&[9]).is_err());
+
+        assert_eq!(resolve_limit(0).unwrap(), DEFAULT_LIMIT);
+        assert_eq!(resolve_limit(5).unwrap(), 5);
+        assert!(resolve_limit(MAX_LIMIT as i32 + 1).is_err());
+        assert!(resolve_limit(-1).is_err());
+    }
+}
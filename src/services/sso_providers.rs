@@ -5,6 +5,8 @@
 
 use serde::Deserialize;
 
+use crate::http_client::HttpClient;
+
 /// Supported SSO providers
 pub enum Provider {
     Lineworks,
@@ -62,7 +64,7 @@ pub struct SsoUserProfile {
 
 /// Exchange authorization code for access token (standard OAuth2)
 pub async fn exchange_code(
-    http_client: &reqwest::Client,
+    http_client: &HttpClient,
     provider: &Provider,
     client_id: &str,
     client_secret: &str,
@@ -78,9 +80,7 @@ pub async fn exchange_code(
     ];
 
     let response = http_client
-        .post(provider.token_url())
-        .form(&params)
-        .send()
+        .send(http_client.client().post(provider.token_url()).form(&params))
         .await
         .map_err(|e| format!("Token request failed: {}", e))?;
 
@@ -103,16 +103,68 @@ pub async fn exchange_code(
     Ok(token.access_token)
 }
 
+/// Authorization code used by `check_client_credentials` (never valid at the provider)
+const PROBE_CODE: &str = "rust-logi-connection-test";
+
+/// Dry-run the token endpoint with the client credentials (TestSsoConnection)
+///
+/// Sends a code exchange with an invalid code: a rejected code means the provider
+/// authenticated the client, a rejected client means the credentials are wrong.
+pub async fn check_client_credentials(
+    http_client: &HttpClient,
+    provider: &Provider,
+    client_id: &str,
+    client_secret: &str,
+    redirect_uri: &str,
+) -> Result<String, String> {
+    let params = [
+        ("grant_type", "authorization_code"),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+        ("code", PROBE_CODE),
+        ("redirect_uri", redirect_uri),
+    ];
+
+    let response = http_client
+        .send(http_client.client().post(provider.token_url()).form(&params))
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    classify_probe_response(status, &body)
+}
+
+/// Interpret the token endpoint's answer to the dry-run exchange
+fn classify_probe_response(status: u16, body: &str) -> Result<String, String> {
+    let error = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+        .unwrap_or_default();
+    match error.as_str() {
+        "invalid_grant" | "invalid_request" => {
+            Ok(format!("Client credentials accepted (test code rejected: {})", error))
+        }
+        "invalid_client" | "unauthorized_client" => {
+            Err(format!("Client credentials rejected: status={}, body={}", status, body))
+        }
+        _ if status == 401 => Err(format!("Client credentials rejected: status={}, body={}", status, body)),
+        _ if (200..300).contains(&status) => Ok("Token endpoint accepted the request".to_string()),
+        _ => Err(format!("Unexpected token endpoint response: status={}, body={}", status, body)),
+    }
+}
+
 /// Fetch user profile from provider's userinfo endpoint
 pub async fn fetch_user_profile(
-    http_client: &reqwest::Client,
+    http_client: &HttpClient,
     provider: &Provider,
     access_token: &str,
 ) -> Result<SsoUserProfile, String> {
-    let response = http_client
+    let request = http_client
+        .client()
         .get(provider.userinfo_url())
-        .header("Authorization", format!("Bearer {}", access_token))
-        .send()
+        .header("Authorization", format!("Bearer {}", access_token));
+    let response = http_client
+        .send(request)
         .await
         .map_err(|e| format!("Profile request failed: {}", e))?;
 
@@ -191,3 +243,16 @@ pub fn build_authorize_url(
         urlencoding::encode(state),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_probe_response() {
+        assert!(classify_probe_response(400, r#"{"error":"invalid_grant"}"#).is_ok());
+        assert!(classify_probe_response(400, r#"{"error":"invalid_client"}"#).is_err());
+        assert!(classify_probe_response(401, "Unauthorized").is_err());
+        assert!(classify_probe_response(500, "oops").is_err());
+    }
+}
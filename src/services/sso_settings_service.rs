@@ -1,23 +1,108 @@
-use sqlx::PgPool;
+use std::sync::Arc;
+
+use sqlx::{FromRow, PgConnection, PgPool};
 use tonic::{Request, Response, Status};
 
-use crate::db::organization::set_current_organization;
+use crate::crypto::{SecretBox, SSO_CLIENT_SECRET, SSO_PREVIOUS_CLIENT_SECRET};
+use crate::db::organization::OrgScopedPool;
+use crate::db::AuditEvent;
+use crate::error::AppError;
+use crate::http_client::HttpClient;
 use crate::middleware::AuthenticatedUser;
 use crate::proto::sso_settings::sso_settings_service_server::SsoSettingsService;
 use crate::proto::sso_settings::{
     DeleteSsoConfigRequest, DeleteSsoConfigResponse, GetSsoConfigRequest, ListSsoConfigsRequest,
-    ListSsoConfigsResponse, SsoConfigResponse, UpsertSsoConfigRequest,
+    ListSsoConfigsResponse, RotateSsoClientSecretRequest, SsoConfigResponse,
+    TestSsoConnectionRequest, TestSsoConnectionResponse, UpsertSsoConfigRequest,
 };
-use crate::services::lineworks_auth;
+use crate::services::sso_providers;
+use crate::services::validation;
+
+/// ローテーション後に古い secret を受け付ける期間（既定 / 上限）
+const DEFAULT_GRACE_PERIOD_HOURS: i32 = 24;
+const MAX_GRACE_PERIOD_HOURS: i32 = 24 * 30;
+/// TestSsoConnection で redirect_uri が未指定のときに送る値（コードは必ず拒否される）
+const TEST_REDIRECT_URI: &str = "https://localhost/sso/callback";
+
+/// 猶予期間を過ぎた古い secret の期限は返さない
+const CONFIG_COLUMNS: &str = "provider, client_id, external_org_id, enabled,
+     created_at::text AS created_at, updated_at::text AS updated_at, woff_id, client_secret_version,
+     CASE WHEN previous_secret_expires_at > NOW() THEN previous_secret_expires_at::text END
+         AS previous_secret_expires_at,
+     secret_rotated_at::text AS secret_rotated_at";
+
+#[derive(Debug, FromRow)]
+struct SsoConfigRow {
+    provider: String,
+    client_id: String,
+    external_org_id: String,
+    enabled: bool,
+    created_at: String,
+    updated_at: String,
+    woff_id: Option<String>,
+    client_secret_version: i32,
+    previous_secret_expires_at: Option<String>,
+    secret_rotated_at: Option<String>,
+}
+
+impl From<SsoConfigRow> for SsoConfigResponse {
+    fn from(row: SsoConfigRow) -> Self {
+        Self {
+            provider: row.provider,
+            client_id: row.client_id,
+            has_client_secret: true,
+            external_org_id: row.external_org_id,
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            woff_id: row.woff_id.unwrap_or_default(),
+            client_secret_version: row.client_secret_version,
+            previous_secret_expires_at: row.previous_secret_expires_at.unwrap_or_default(),
+            secret_rotated_at: row.secret_rotated_at.unwrap_or_default(),
+        }
+    }
+}
+
+async fn fetch_config(
+    conn: &mut PgConnection,
+    org_id: &str,
+    provider: &str,
+) -> Result<Option<SsoConfigRow>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM sso_provider_configs WHERE organization_id = $1::uuid AND provider = $2",
+        CONFIG_COLUMNS
+    ))
+    .bind(org_id)
+    .bind(provider)
+    .fetch_optional(conn)
+    .await
+}
+
+/// 猶予期間（時間）。0 なら既定
+fn grace_period_hours(requested: i32) -> Result<i32, Status> {
+    match requested {
+        0 => Ok(DEFAULT_GRACE_PERIOD_HOURS),
+        h if (1..=MAX_GRACE_PERIOD_HOURS).contains(&h) => Ok(h),
+        h => Err(Status::invalid_argument(format!(
+            "grace_period_hours must be between 0 and {} (got {})",
+            MAX_GRACE_PERIOD_HOURS, h
+        ))),
+    }
+}
 
 pub struct SsoSettingsServiceImpl {
     pool: PgPool,
-    jwt_secret: String,
+    secrets: Arc<SecretBox>,
+    http_client: Arc<HttpClient>,
 }
 
 impl SsoSettingsServiceImpl {
-    pub fn new(pool: PgPool, jwt_secret: String) -> Self {
-        Self { pool, jwt_secret }
+    pub fn new(pool: PgPool, secrets: Arc<SecretBox>, http_client: Arc<HttpClient>) -> Self {
+        Self {
+            pool,
+            secrets,
+            http_client,
+        }
     }
 
     fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
@@ -36,7 +121,7 @@ impl SsoSettingsServiceImpl {
         .bind(org_id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         match role {
             Some((r,)) if r == "admin" => Ok(()),
@@ -62,38 +147,16 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
-        let row: Option<(String, String, String, bool, String, String, Option<String>)> = sqlx::query_as(
-            "SELECT provider, client_id, external_org_id, enabled,
-                    created_at::text, updated_at::text, woff_id
-             FROM sso_provider_configs
-             WHERE organization_id = $1::uuid AND provider = $2",
-        )
-        .bind(&auth_user.org_id)
-        .bind(&req.provider)
-        .fetch_optional(&mut *conn)
-        .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        let row = fetch_config(&mut conn, &auth_user.org_id, &req.provider)
+            .await
+            .map_err(AppError::from)?;
 
         match row {
-            Some((provider, client_id, external_org_id, enabled, created_at, updated_at, woff_id)) => {
-                Ok(Response::new(SsoConfigResponse {
-                    provider,
-                    client_id,
-                    has_client_secret: true,
-                    external_org_id,
-                    enabled,
-                    created_at,
-                    updated_at,
-                    woff_id: woff_id.unwrap_or_default(),
-                }))
-            }
+            Some(row) => Ok(Response::new(row.into())),
             None => Ok(Response::new(SsoConfigResponse {
                 provider: req.provider,
                 client_id: String::new(),
@@ -103,6 +166,9 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
                 created_at: String::new(),
                 updated_at: String::new(),
                 woff_id: String::new(),
+                client_secret_version: 0,
+                previous_secret_expires_at: String::new(),
+                secret_rotated_at: String::new(),
             })),
         }
     }
@@ -115,7 +181,9 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
         self.verify_admin(&auth_user.user_id, &auth_user.org_id)
             .await?;
 
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        req.client_id = validation::code("client_id", &req.client_id, validation::CODE_MAX_CHARS)?;
+        req.external_org_id = validation::code("external_org_id", &req.external_org_id, validation::CODE_MAX_CHARS)?;
 
         if req.client_id.is_empty() || req.external_org_id.is_empty() || req.provider.is_empty() {
             return Err(Status::invalid_argument(
@@ -125,12 +193,9 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         // Check if config already exists for this provider
         let existing: Option<(String,)> = sqlx::query_as(
@@ -140,7 +205,7 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
         .bind(&req.provider)
         .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         let woff_id_val = if req.woff_id.is_empty() { None } else { Some(&req.woff_id) };
 
@@ -161,18 +226,16 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
                 .bind(&req.provider)
                 .execute(&mut *conn)
                 .await
-                .map_err(|e| Status::internal(format!("Update error: {}", e)))?;
+                .map_err(AppError::from)?;
             } else {
-                // Update with new secret
-                let encrypted =
-                    lineworks_auth::encrypt_secret(&req.client_secret, &self.jwt_secret)
-                        .map_err(|e| {
-                            Status::internal(format!("Failed to encrypt secret: {}", e))
-                        })?;
+                // Update with new secret (古い secret は直ちに無効。猶予を残すなら RotateSsoClientSecret)
+                let encrypted = self.secrets.encrypt(&SSO_CLIENT_SECRET, &req.client_secret)?;
                 sqlx::query(
                     "UPDATE sso_provider_configs
                      SET client_id = $1, client_secret_encrypted = $2, external_org_id = $3,
-                         enabled = $4, woff_id = $5, updated_at = NOW()
+                         enabled = $4, woff_id = $5, updated_at = NOW(),
+                         client_secret_version = client_secret_version + 1,
+                         previous_client_secret_encrypted = NULL, previous_secret_expires_at = NULL
                      WHERE organization_id = $6::uuid AND provider = $7",
                 )
                 .bind(&req.client_id)
@@ -184,7 +247,7 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
                 .bind(&req.provider)
                 .execute(&mut *conn)
                 .await
-                .map_err(|e| Status::internal(format!("Update error: {}", e)))?;
+                .map_err(AppError::from)?;
             }
         } else {
             // Create new config (client_secret required)
@@ -194,10 +257,7 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
                 ));
             }
 
-            let encrypted =
-                lineworks_auth::encrypt_secret(&req.client_secret, &self.jwt_secret).map_err(
-                    |e| Status::internal(format!("Failed to encrypt secret: {}", e)),
-                )?;
+            let encrypted = self.secrets.encrypt(&SSO_CLIENT_SECRET, &req.client_secret)?;
 
             sqlx::query(
                 "INSERT INTO sso_provider_configs
@@ -213,48 +273,23 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
             .bind(woff_id_val)
             .execute(&mut *conn)
             .await
-            .map_err(|e| {
-                if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.is_unique_violation() => {
                     Status::already_exists(
                         "This external_org_id or client_id is already configured for another organization",
                     )
-                } else {
-                    Status::internal(format!("Insert error: {}", e))
                 }
+                e => AppError::from(e).into(),
             })?;
         }
 
         // Return updated config
-        let (provider, client_id, external_org_id, enabled, created_at, updated_at, woff_id): (
-            String,
-            String,
-            String,
-            bool,
-            String,
-            String,
-            Option<String>,
-        ) = sqlx::query_as(
-            "SELECT provider, client_id, external_org_id, enabled,
-                    created_at::text, updated_at::text, woff_id
-             FROM sso_provider_configs
-             WHERE organization_id = $1::uuid AND provider = $2",
-        )
-        .bind(&auth_user.org_id)
-        .bind(&req.provider)
-        .fetch_one(&mut *conn)
-        .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        let row = fetch_config(&mut conn, &auth_user.org_id, &req.provider)
+            .await
+            .map_err(AppError::from)?
+            .ok_or_else(|| Status::internal("SSO config not found after upsert"))?;
 
-        Ok(Response::new(SsoConfigResponse {
-            provider,
-            client_id,
-            has_client_secret: true,
-            external_org_id,
-            enabled,
-            created_at,
-            updated_at,
-            woff_id: woff_id.unwrap_or_default(),
-        }))
+        Ok(Response::new(row.into()))
     }
 
     async fn delete_config(
@@ -269,12 +304,9 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
-            .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
+            .map_err(AppError::from)?;
 
         sqlx::query(
             "DELETE FROM sso_provider_configs WHERE organization_id = $1::uuid AND provider = $2",
@@ -283,7 +315,7 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
         .bind(&req.provider)
         .execute(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Delete error: {}", e)))?;
+        .map_err(AppError::from)?;
 
         Ok(Response::new(DeleteSsoConfigResponse {}))
     }
@@ -298,43 +330,191 @@ impl SsoSettingsService for SsoSettingsServiceImpl {
 
         let mut conn = self
             .pool
-            .acquire()
+            .acquire_scoped(&auth_user.org_id)
             .await
-            .map_err(|e| Status::internal(format!("Pool error: {}", e)))?;
-        set_current_organization(&mut conn, &auth_user.org_id)
-            .await
-            .map_err(|e| Status::internal(format!("RLS error: {}", e)))?;
-
-        let rows: Vec<(String, String, String, bool, String, String, Option<String>)> = sqlx::query_as(
-            "SELECT provider, client_id, external_org_id, enabled,
-                    created_at::text, updated_at::text, woff_id
-             FROM sso_provider_configs
-             WHERE organization_id = $1::uuid
-             ORDER BY provider",
-        )
+            .map_err(AppError::from)?;
+
+        let rows: Vec<SsoConfigRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM sso_provider_configs WHERE organization_id = $1::uuid ORDER BY provider",
+            CONFIG_COLUMNS
+        ))
         .bind(&auth_user.org_id)
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
-
-        let configs = rows
-            .into_iter()
-            .map(
-                |(provider, client_id, external_org_id, enabled, created_at, updated_at, woff_id)| {
-                    SsoConfigResponse {
-                        provider,
-                        client_id,
-                        has_client_secret: true,
-                        external_org_id,
-                        enabled,
-                        created_at,
-                        updated_at,
-                        woff_id: woff_id.unwrap_or_default(),
-                    }
-                },
-            )
-            .collect();
+        .map_err(AppError::from)?;
+
+        let configs = rows.into_iter().map(SsoConfigResponse::from).collect();
 
         Ok(Response::new(ListSsoConfigsResponse { configs }))
     }
+
+    async fn rotate_sso_client_secret(
+        &self,
+        request: Request<RotateSsoClientSecretRequest>,
+    ) -> Result<Response<SsoConfigResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let req = request.into_inner();
+        if req.new_client_secret.is_empty() {
+            return Err(Status::invalid_argument("new_client_secret is required"));
+        }
+        let grace_hours = grace_period_hours(req.grace_period_hours)?;
+
+        let mut tx = self.pool.begin_scoped(&auth_user.org_id).await.map_err(AppError::from)?;
+
+        let current: Option<(String, String)> = sqlx::query_as(
+            "SELECT id::text, client_secret_encrypted FROM sso_provider_configs
+             WHERE organization_id = $1::uuid AND provider = $2
+             FOR UPDATE",
+        )
+        .bind(&auth_user.org_id)
+        .bind(&req.provider)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        let Some((config_id, current_encrypted)) = current else {
+            return Err(Status::not_found(format!("SSO config not found for provider={}", req.provider)));
+        };
+
+        // 古い secret は列（AAD）が変わるので復号して現在の鍵で暗号化し直す
+        let previous = self.secrets.decrypt(&SSO_CLIENT_SECRET, &current_encrypted)?;
+        let previous_encrypted = self.secrets.encrypt(&SSO_PREVIOUS_CLIENT_SECRET, &previous)?;
+        let new_encrypted = self.secrets.encrypt(&SSO_CLIENT_SECRET, &req.new_client_secret)?;
+
+        let (version,): (i32,) = sqlx::query_as(
+            "UPDATE sso_provider_configs
+             SET client_secret_encrypted = $1,
+                 previous_client_secret_encrypted = $2,
+                 previous_secret_expires_at = NOW() + make_interval(hours => $3),
+                 client_secret_version = client_secret_version + 1,
+                 secret_rotated_at = NOW(), updated_at = NOW()
+             WHERE id = $4::uuid
+             RETURNING client_secret_version",
+        )
+        .bind(&new_encrypted)
+        .bind(&previous_encrypted)
+        .bind(grace_hours)
+        .bind(&config_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        AuditEvent::new("sso.client_secret_rotated", "sso_provider_config", &config_id)
+            .actor(&auth_user.user_id)
+            .details(serde_json::json!({
+                "provider": req.provider,
+                "client_secret_version": version,
+                "grace_period_hours": grace_hours,
+            }))
+            .record(&mut tx, &auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let row = fetch_config(&mut tx, &auth_user.org_id, &req.provider)
+            .await
+            .map_err(AppError::from)?
+            .ok_or_else(|| Status::internal("SSO config not found after rotation"))?;
+        tx.commit().await.map_err(AppError::from)?;
+
+        tracing::info!(
+            "Rotated SSO client secret for org {} provider={} (version {}, grace {}h)",
+            auth_user.org_id,
+            req.provider,
+            version,
+            grace_hours
+        );
+        Ok(Response::new(row.into()))
+    }
+
+    async fn test_sso_connection(
+        &self,
+        request: Request<TestSsoConnectionRequest>,
+    ) -> Result<Response<TestSsoConnectionResponse>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let req = request.into_inner();
+        let provider = sso_providers::Provider::from_str(&req.provider).ok_or_else(|| {
+            Status::invalid_argument(format!("Unknown provider: {}", req.provider))
+        })?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        let stored: Option<(String, String, i32)> = sqlx::query_as(
+            "SELECT client_id, client_secret_encrypted, client_secret_version FROM sso_provider_configs
+             WHERE organization_id = $1::uuid AND provider = $2",
+        )
+        .bind(&auth_user.org_id)
+        .bind(&req.provider)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let Some((client_id, secret_encrypted, version)) = stored else {
+            return Err(Status::not_found(format!("SSO config not found for provider={}", req.provider)));
+        };
+
+        let (client_secret, tested_version) = if req.client_secret.is_empty() {
+            (self.secrets.decrypt(&SSO_CLIENT_SECRET, &secret_encrypted)?, version)
+        } else {
+            (req.client_secret.clone(), 0)
+        };
+        let redirect_uri = if req.redirect_uri.is_empty() { TEST_REDIRECT_URI } else { req.redirect_uri.as_str() };
+
+        let credentials = sso_providers::check_client_credentials(
+            &self.http_client,
+            &provider,
+            &client_id,
+            &client_secret,
+            redirect_uri,
+        )
+        .await;
+        let (client_credentials_ok, client_credentials_message) = match credentials {
+            Ok(message) => (true, message),
+            Err(message) => (false, message),
+        };
+
+        let mut response = TestSsoConnectionResponse {
+            ok: client_credentials_ok,
+            client_credentials_ok,
+            client_credentials_message,
+            client_secret_version: tested_version,
+            ..Default::default()
+        };
+        if !req.access_token.is_empty() {
+            response.userinfo_checked = true;
+            match sso_providers::fetch_user_profile(&self.http_client, &provider, &req.access_token).await {
+                Ok(profile) => {
+                    response.userinfo_ok = true;
+                    response.userinfo_message = format!("Profile fetched for {}", profile.provider_user_id);
+                    response.user_display_name = profile.display_name;
+                }
+                Err(message) => {
+                    response.ok = false;
+                    response.userinfo_message = message;
+                }
+            }
+        }
+
+        Ok(Response::new(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grace_period_hours() {
+        assert_eq!(grace_period_hours(0).unwrap(), DEFAULT_GRACE_PERIOD_HOURS);
+        assert_eq!(grace_period_hours(48).unwrap(), 48);
+        assert!(grace_period_hours(-1).is_err());
+        assert!(grace_period_hours(MAX_GRACE_PERIOD_HOURS + 1).is_err());
+    }
 }
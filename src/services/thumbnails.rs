@@ -0,0 +1,386 @@
+// Server-side thumbnails for files and cam_files (jpg / png)
+//
+// small / medium の JPEG を生成してストレージの {org}/thumbnails/... に置き、thumbnails テーブルに記録する。
+// files はアップロード後の files.thumbnail job、cam_files は Flickr アップロード job で生成する。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use image::codecs::jpeg::JpegEncoder;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+use crate::db::OrgScopedPool;
+use crate::error::{AppError, AppResult};
+use crate::jobs::{Job, JobHandler, NewJob};
+use crate::storage::StorageBackend;
+
+/// サムネイルの署名付き URL の有効期間
+pub const THUMBNAIL_URL_EXPIRY: Duration = Duration::from_secs(60 * 60);
+/// 生成する JPEG の品質
+const JPEG_QUALITY: u8 = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailSize {
+    /// 一覧・ギャラリー用（長辺 160px）
+    Small,
+    /// プレビュー用（長辺 640px）
+    Medium,
+}
+
+impl ThumbnailSize {
+    pub const ALL: [ThumbnailSize; 2] = [ThumbnailSize::Small, ThumbnailSize::Medium];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThumbnailSize::Small => "small",
+            ThumbnailSize::Medium => "medium",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "small" => Some(ThumbnailSize::Small),
+            "medium" => Some(ThumbnailSize::Medium),
+            _ => None,
+        }
+    }
+
+    fn max_dimension(self) -> u32 {
+        match self {
+            ThumbnailSize::Small => 160,
+            ThumbnailSize::Medium => 640,
+        }
+    }
+}
+
+/// サムネイルの元（thumbnails.source_type / source_id）
+#[derive(Debug, Clone, Copy)]
+pub enum ThumbnailSource<'a> {
+    /// files.uuid
+    File(&'a str),
+    /// cam_files.name
+    CamFile(&'a str),
+}
+
+impl ThumbnailSource<'_> {
+    pub fn source_type(&self) -> &'static str {
+        match self {
+            ThumbnailSource::File(_) => "file",
+            ThumbnailSource::CamFile(_) => "cam_file",
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            ThumbnailSource::File(id) | ThumbnailSource::CamFile(id) => id,
+        }
+    }
+
+    /// 元のキーから導いたストレージキー
+    fn storage_key(&self, organization_id: &str, size: ThumbnailSize) -> String {
+        format!(
+            "{}/thumbnails/{}/{}/{}.jpg",
+            organization_id,
+            self.source_type(),
+            self.id(),
+            size.as_str()
+        )
+    }
+}
+
+/// サムネイルを作る MIME タイプか
+pub fn is_thumbnail_type(mime_type: &str) -> bool {
+    matches!(mime_type, "image/jpeg" | "image/png")
+}
+
+/// サムネイルを作るファイル名か（cam_files は MIME タイプを持たない）
+pub fn is_thumbnail_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".jpg") || name.ends_with(".jpeg") || name.ends_with(".png")
+}
+
+#[derive(Debug)]
+pub struct Thumbnail {
+    pub size: ThumbnailSize,
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 長辺を size に収まるよう縮小して JPEG にする（元より大きくはしない）
+pub fn generate(data: &[u8], size: ThumbnailSize) -> AppResult<Thumbnail> {
+    let image = image::load_from_memory(data)
+        .map_err(|e| AppError::InvalidInput(format!("Unsupported image: {}", e)))?;
+    let max = size.max_dimension();
+    let image = if image.width() > max || image.height() > max {
+        image.thumbnail(max, max)
+    } else {
+        image
+    };
+
+    // JPEG はアルファを持たない
+    let rgb = image.to_rgb8();
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+        .encode_image(&rgb)
+        .map_err(|e| AppError::internal_with("Thumbnail encoding failed", e))?;
+    Ok(Thumbnail {
+        size,
+        width: rgb.width(),
+        height: rgb.height(),
+        data: out,
+    })
+}
+
+/// 全サイズを生成してストレージと thumbnails に保存（conn は組織を設定済みであること）
+pub async fn store_thumbnails(
+    conn: &mut PgConnection,
+    storage: &dyn StorageBackend,
+    organization_id: &str,
+    source: ThumbnailSource<'_>,
+    data: Vec<u8>,
+) -> AppResult<()> {
+    // デコードと縮小は CPU を使うので async のワーカーを止めない
+    let thumbnails = tokio::task::spawn_blocking(move || {
+        ThumbnailSize::ALL
+            .into_iter()
+            .map(|size| generate(&data, size))
+            .collect::<AppResult<Vec<_>>>()
+    })
+    .await
+    .map_err(|e| AppError::internal_with("Thumbnail task failed", e))??;
+
+    for thumbnail in thumbnails {
+        let key = source.storage_key(organization_id, thumbnail.size);
+        storage.upload(&key, &thumbnail.data, "image/jpeg").await?;
+        sqlx::query(
+            r#"
+            INSERT INTO thumbnails (organization_id, source_type, source_id, size, s3_key, width, height)
+            VALUES ($1::uuid, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (organization_id, source_type, source_id, size)
+            DO UPDATE SET s3_key = EXCLUDED.s3_key, width = EXCLUDED.width,
+                          height = EXCLUDED.height, created_at = NOW()
+            "#,
+        )
+        .bind(organization_id)
+        .bind(source.source_type())
+        .bind(source.id())
+        .bind(thumbnail.size.as_str())
+        .bind(&key)
+        .bind(thumbnail.width as i32)
+        .bind(thumbnail.height as i32)
+        .execute(&mut *conn)
+        .await?;
+    }
+    tracing::info!("Stored thumbnails for {} {}", source.source_type(), source.id());
+    Ok(())
+}
+
+/// 保存済みのサムネイル（s3_key, width, height）
+pub async fn find_thumbnail(
+    conn: &mut PgConnection,
+    source: ThumbnailSource<'_>,
+    size: ThumbnailSize,
+) -> AppResult<Option<(String, i32, i32)>> {
+    Ok(sqlx::query_as(
+        "SELECT s3_key, width, height FROM thumbnails WHERE source_type = $1 AND source_id = $2 AND size = $3",
+    )
+    .bind(source.source_type())
+    .bind(source.id())
+    .bind(size.as_str())
+    .fetch_optional(&mut *conn)
+    .await?)
+}
+
+/// 一覧に載せるサムネイルの署名付き URL
+#[derive(Debug, Clone, Default)]
+pub struct ThumbnailUrls {
+    pub small: Option<String>,
+    pub medium: Option<String>,
+}
+
+/// source_id → 署名付き URL（署名に失敗したものは載せない。一覧自体は失敗させない）
+pub async fn signed_urls(
+    conn: &mut PgConnection,
+    storage: &dyn StorageBackend,
+    source_type: &str,
+    ids: &[String],
+) -> AppResult<HashMap<String, ThumbnailUrls>> {
+    let mut urls: HashMap<String, ThumbnailUrls> = HashMap::new();
+    if ids.is_empty() {
+        return Ok(urls);
+    }
+
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT source_id, size, s3_key FROM thumbnails WHERE source_type = $1 AND source_id = ANY($2)",
+    )
+    .bind(source_type)
+    .bind(ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (source_id, size, key) in rows {
+        let url = match storage.signed_url(&key, THUMBNAIL_URL_EXPIRY).await {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::warn!("Failed to sign thumbnail URL for {}: {}", key, e.report());
+                continue;
+            }
+        };
+        let entry = urls.entry(source_id).or_default();
+        match ThumbnailSize::parse(&size) {
+            Some(ThumbnailSize::Small) => entry.small = Some(url),
+            Some(ThumbnailSize::Medium) => entry.medium = Some(url),
+            None => {}
+        }
+    }
+    Ok(urls)
+}
+
+/// サムネイルをストレージと thumbnails から削除（元のファイルを完全削除するとき）
+pub async fn delete_thumbnails(
+    conn: &mut PgConnection,
+    storage: Option<&dyn StorageBackend>,
+    source: ThumbnailSource<'_>,
+) -> AppResult<()> {
+    let keys: Vec<(String,)> = sqlx::query_as(
+        "DELETE FROM thumbnails WHERE source_type = $1 AND source_id = $2 RETURNING s3_key",
+    )
+    .bind(source.source_type())
+    .bind(source.id())
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if let Some(storage) = storage {
+        for (key,) in keys {
+            if let Err(e) = storage.delete(&key).await {
+                tracing::warn!("Failed to delete thumbnail {}: {}", key, e.report());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// アップロードされた画像ファイルのサムネイル生成 job
+pub const THUMBNAIL_JOB: &str = "files.thumbnail";
+/// 組織ごとの実行待ちがこれを超えたら後続を遅らせる
+const THUMBNAIL_BACKLOG_LIMIT: i64 = 50;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThumbnailPayload {
+    pub file_uuid: String,
+}
+
+impl ThumbnailPayload {
+    /// サムネイルを作る MIME タイプなら job を作る
+    pub fn job(file_uuid: &str, mime_type: &str) -> Option<NewJob> {
+        if !is_thumbnail_type(mime_type) {
+            return None;
+        }
+        let payload = Self {
+            file_uuid: file_uuid.to_string(),
+        };
+        Some(
+            NewJob::new(THUMBNAIL_JOB, payload)
+                .dedupe_key(file_uuid)
+                .backlog_limit(THUMBNAIL_BACKLOG_LIMIT),
+        )
+    }
+}
+
+/// files の内容（ストレージ or DB blob）からサムネイルを作る job ハンドラ（保存先のストレージ必須）
+pub struct ThumbnailJobHandler {
+    pool: PgPool,
+    storage: Option<Arc<dyn StorageBackend>>,
+}
+
+impl ThumbnailJobHandler {
+    pub fn new(pool: PgPool, storage: Option<Arc<dyn StorageBackend>>) -> Self {
+        Self { pool, storage }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for ThumbnailJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: ThumbnailPayload = job.payload()?;
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        let row: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT s3_key, blob FROM files WHERE uuid = $1::uuid AND deleted_at IS NULL",
+        )
+        .bind(&payload.file_uuid)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some((s3_key, blob)) = row else {
+            tracing::debug!("File {} was deleted, skipping thumbnail", payload.file_uuid);
+            return Ok(());
+        };
+        let data = match (s3_key, blob) {
+            (Some(key), _) => storage.download(&key).await?,
+            (_, Some(blob)) => base64::Engine::decode(&base64::engine::general_purpose::STANDARD, blob)?,
+            _ => anyhow::bail!("No content available for file {}", payload.file_uuid),
+        };
+
+        let source = ThumbnailSource::File(&payload.file_uuid);
+        match store_thumbnails(&mut conn, storage.as_ref(), &job.organization_id, source, data).await {
+            // 壊れた画像は再実行しても同じなので諦める
+            Err(AppError::InvalidInput(msg)) => {
+                tracing::warn!("Skipping thumbnail for {}: {}", payload.file_uuid, msg);
+                Ok(())
+            }
+            result => result.map_err(anyhow::Error::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([200, 10, 10, 128]));
+        let mut out = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_generate_keeps_aspect_ratio() {
+        let data = png(1280, 720);
+        let small = generate(&data, ThumbnailSize::Small).unwrap();
+        assert_eq!((small.width, small.height), (160, 90));
+        let medium = generate(&data, ThumbnailSize::Medium).unwrap();
+        assert_eq!((medium.width, medium.height), (640, 360));
+        assert!(image::load_from_memory_with_format(&small.data, image::ImageFormat::Jpeg).is_ok());
+    }
+
+    #[test]
+    fn test_generate_does_not_upscale() {
+        let small = generate(&png(100, 50), ThumbnailSize::Medium).unwrap();
+        assert_eq!((small.width, small.height), (100, 50));
+    }
+
+    #[test]
+    fn test_generate_rejects_non_images() {
+        assert!(matches!(
+            generate(b"not an image", ThumbnailSize::Small),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_storage_key_and_names() {
+        let key = ThumbnailSource::CamFile("Event20250323_005902.jpg").storage_key("org", ThumbnailSize::Small);
+        assert_eq!(key, "org/thumbnails/cam_file/Event20250323_005902.jpg/small.jpg");
+        assert!(is_thumbnail_name("A.JPG"));
+        assert!(!is_thumbnail_name("a.mp4"));
+        assert!(ThumbnailPayload::job("u", "application/pdf").is_none());
+    }
+}
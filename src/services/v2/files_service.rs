@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use super::parse_timestamp;
+use crate::proto::common::{Empty, PaginationRequest};
+use crate::proto::files as v1;
+use crate::proto::files::files_service_server::FilesService as FilesServiceV1;
+use crate::proto::v2::files::files_service_server::FilesService;
+use crate::proto::v2::files::{
+    CreateFileRequest, DeleteFileRequest, File, GetFileRequest, ListFilesRequest,
+    ListFilesResponse,
+};
+use crate::services::FilesServiceImpl;
+
+/// logi.v2.files.FilesService（v1 の FilesServiceImpl に委譲）
+pub struct FilesV2ServiceImpl {
+    v1: Arc<FilesServiceImpl>,
+}
+
+impl FilesV2ServiceImpl {
+    pub fn new(v1: Arc<FilesServiceImpl>) -> Self {
+        Self { v1 }
+    }
+
+    fn to_v2(file: v1::File) -> File {
+        File {
+            uuid: file.uuid,
+            filename: file.filename,
+            mime_type: file.r#type,
+            create_time: parse_timestamp(&file.created),
+            delete_time: file.deleted.as_deref().and_then(parse_timestamp),
+            storage_class: file.storage_class.unwrap_or_default(),
+            last_access_time: file.last_accessed_at.as_deref().and_then(parse_timestamp),
+        }
+    }
+
+    /// v2 のフィールド名で書かれた order_by を v1 のフィールド名に置き換える
+    fn to_v1_order_by(order_by: &str) -> String {
+        order_by
+            .split(',')
+            .map(|term| {
+                let term = term.trim();
+                let (field, direction) = term.split_once(' ').unwrap_or((term, ""));
+                let field = match field {
+                    "create_time" => "created_at",
+                    "mime_type" => "type",
+                    other => other,
+                };
+                format!("{} {}", field, direction).trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn unwrap_file(response: Response<v1::FileResponse>) -> Result<File, Status> {
+        response
+            .into_inner()
+            .file
+            .map(Self::to_v2)
+            .ok_or_else(|| Status::internal("Missing file in response"))
+    }
+}
+
+#[tonic::async_trait]
+impl FilesService for FilesV2ServiceImpl {
+    async fn create_file(
+        &self,
+        request: Request<CreateFileRequest>,
+    ) -> Result<Response<File>, Status> {
+        let request = request.map(|req| v1::CreateFileRequest {
+            filename: req.filename,
+            r#type: req.mime_type,
+            content: req.content,
+            blob_base64: None,
+        });
+        let response = self.v1.create_file(request).await?;
+        Ok(Response::new(Self::unwrap_file(response)?))
+    }
+
+    async fn list_files(
+        &self,
+        request: Request<ListFilesRequest>,
+    ) -> Result<Response<ListFilesResponse>, Status> {
+        // v2 は常にページング（page_size 0 → デフォルト件数）
+        let request = request.map(|req| v1::ListFilesRequest {
+            pagination: Some(PaginationRequest {
+                page: 0,
+                per_page: req.page_size,
+                page_token: req.page_token,
+            }),
+            type_filter: Some(req.mime_type).filter(|t| !t.is_empty()),
+            order_by: Self::to_v1_order_by(&req.order_by),
+        });
+        let response = self.v1.list_files(request).await?.into_inner();
+
+        Ok(Response::new(ListFilesResponse {
+            files: response.files.into_iter().map(Self::to_v2).collect(),
+            next_page_token: response
+                .pagination
+                .map(|p| p.next_page_token)
+                .unwrap_or_default(),
+        }))
+    }
+
+    async fn get_file(
+        &self,
+        request: Request<GetFileRequest>,
+    ) -> Result<Response<File>, Status> {
+        let request = request.map(|req| v1::GetFileRequest {
+            uuid: req.uuid,
+            include_blob: false,
+        });
+        let response = self.v1.get_file(request).await?;
+        Ok(Response::new(Self::unwrap_file(response)?))
+    }
+
+    async fn delete_file(
+        &self,
+        request: Request<DeleteFileRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let request = request.map(|req| v1::DeleteFileRequest { uuid: req.uuid });
+        self.v1.delete_file(request).await
+    }
+}
@@ -0,0 +1,30 @@
+// v2 API services
+//
+// v2 は v1 のサービス実装（DB アクセス・RLS・ストレージ）を共有し、
+// メッセージ形式の変換だけを行う。
+
+pub mod files_service;
+
+pub use files_service::FilesV2ServiceImpl;
+
+/// v1 の ISO 8601 文字列を google.protobuf.Timestamp に変換
+pub fn parse_timestamp(value: &str) -> Option<prost_types::Timestamp> {
+    let dt = chrono::DateTime::parse_from_rfc3339(value).ok()?;
+    Some(prost_types::Timestamp {
+        seconds: dt.timestamp(),
+        nanos: dt.timestamp_subsec_nanos() as i32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let ts = parse_timestamp("2026-01-24T12:00:00Z").unwrap();
+        assert_eq!(ts.seconds, 1769256000);
+        assert_eq!(ts.nanos, 0);
+        assert!(parse_timestamp("").is_none());
+    }
+}
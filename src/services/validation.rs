@@ -0,0 +1,103 @@
+// 文字列入力の共通チェック（INSERT / UPDATE の前に通す）
+//
+// - 制御文字を除く（複数行の項目だけ改行・タブを残す）
+// - 前後の空白を落とす
+// - 上限（文字数）を超えたら INVALID_ARGUMENT
+// - コード類（バーコード・slug・メールアドレス・ID など）は全角の英数字・記号を半角にそろえる
+
+use tonic::Status;
+
+/// 名前・件名など 1 行の項目
+pub const NAME_MAX_CHARS: usize = 200;
+/// 説明・本文など複数行の項目
+pub const TEXT_MAX_CHARS: usize = 10_000;
+/// バーコード・slug・外部 ID などのコード類
+pub const CODE_MAX_CHARS: usize = 128;
+pub const URL_MAX_CHARS: usize = 2048;
+/// RFC 5321 のアドレス長
+pub const EMAIL_MAX_CHARS: usize = 254;
+pub const FILENAME_MAX_CHARS: usize = 255;
+
+/// 全角の英数字・記号（！〜～）と全角空白を半角にする。マイナス記号（−）はハイフンに
+pub fn normalize_width(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{2212}' => '-',
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .collect()
+}
+
+fn check_length(field: &str, value: String, max_chars: usize) -> Result<String, Status> {
+    let chars = value.chars().count();
+    if chars > max_chars {
+        return Err(Status::invalid_argument(format!(
+            "{} is too long ({} characters, max {})",
+            field, chars, max_chars
+        )));
+    }
+    Ok(value)
+}
+
+/// 1 行の項目: 制御文字（改行・タブを含む）を除く
+pub fn line(field: &str, value: &str, max_chars: usize) -> Result<String, Status> {
+    let cleaned: String = value.chars().filter(|c| !c.is_control()).collect();
+    check_length(field, cleaned.trim().to_string(), max_chars)
+}
+
+/// 複数行の項目: 改行とタブ以外の制御文字を除き、改行は \n にそろえる
+pub fn text(field: &str, value: &str, max_chars: usize) -> Result<String, Status> {
+    let cleaned: String = value
+        .replace("\r\n", "\n")
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .collect();
+    check_length(field, cleaned.trim().to_string(), max_chars)
+}
+
+/// コード類: 1 行の項目に加えて全角英数字・記号を半角にする
+pub fn code(field: &str, value: &str, max_chars: usize) -> Result<String, Status> {
+    line(field, &normalize_width(value), max_chars)
+}
+
+/// 必須の 1 行の項目（空なら INVALID_ARGUMENT）
+pub fn required_line(field: &str, value: &str, max_chars: usize) -> Result<String, Status> {
+    let value = line(field, value, max_chars)?;
+    if value.is_empty() {
+        return Err(Status::invalid_argument(format!("{} is required", field)));
+    }
+    Ok(value)
+}
+
+/// 空文字は None（proto3 の未指定）
+pub fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn test_sanitize_strings() {
+        assert_eq!(line("name", "  工具\u{0000}箱\n ", NAME_MAX_CHARS).unwrap(), "工具箱");
+        assert_eq!(text("body", "1行目\r\n\t2行目\u{0007}", TEXT_MAX_CHARS).unwrap(), "1行目\n\t2行目");
+        assert_eq!(code("barcode", "４９０１２３４－ＡＢ", CODE_MAX_CHARS).unwrap(), "4901234-AB");
+        assert_eq!(code("email", "ｔａｒｏ＠ｅｘａｍｐｌｅ．ｊｐ", EMAIL_MAX_CHARS).unwrap(), "taro@example.jp");
+
+        let err = line("name", &"あ".repeat(NAME_MAX_CHARS + 1), NAME_MAX_CHARS).unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert!(line("name", &"あ".repeat(NAME_MAX_CHARS), NAME_MAX_CHARS).is_ok());
+
+        assert_eq!(required_line("name", " \u{0001} ", NAME_MAX_CHARS).unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(non_empty(String::new()), None);
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use sqlx::PgConnection;
+
+use crate::services::car_inspection_service::to_half_width;
+
+/// 明細（ETC・給油）の車両番号を ichiban_cars の車両に紐づける
+///
+/// name / name_r の完全一致（全角・空白の揺れは吸収）を優先し、なければ末尾の番号と id4 が
+/// 一致する車両が1台だけのときに紐づける。
+#[derive(Debug, Default)]
+pub struct VehicleMatcher {
+    by_name: HashMap<String, String>,
+    by_number: HashMap<u32, Vec<String>>,
+}
+
+/// 車両番号の末尾の数字（"帯広100け2-01" → 201）
+fn plate_serial(plate: &str) -> Option<u32> {
+    let normalized: String = to_half_width(plate).chars().filter(|c| *c != '-' && *c != '・').collect();
+    let digits: String = normalized
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+impl VehicleMatcher {
+    /// (id, id4, name, name_r) から作る
+    pub fn new(cars: &[(String, String, Option<String>, Option<String>)]) -> Self {
+        let mut matcher = Self::default();
+        for (id, id4, name, name_r) in cars {
+            for name in [name, name_r].into_iter().flatten() {
+                let key = to_half_width(name);
+                if !key.is_empty() {
+                    matcher.by_name.entry(key).or_insert_with(|| id.clone());
+                }
+            }
+            if let Some(number) = plate_serial(id4) {
+                let ids = matcher.by_number.entry(number).or_default();
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+        }
+        matcher
+    }
+
+    /// 組織の ichiban_cars から作る（organization 設定済みのコネクション）
+    pub async fn load(conn: &mut PgConnection) -> Result<Self, sqlx::Error> {
+        let cars: Vec<(String, String, Option<String>, Option<String>)> =
+            sqlx::query_as("SELECT id, id4, name, name_r FROM ichiban_cars")
+                .fetch_all(&mut *conn)
+                .await?;
+        Ok(Self::new(&cars))
+    }
+
+    pub fn match_plate(&self, plate: &str) -> Option<&str> {
+        let key = to_half_width(plate);
+        if key.is_empty() {
+            return None;
+        }
+        if let Some(id) = self.by_name.get(&key) {
+            return Some(id);
+        }
+        match self.by_number.get(&plate_serial(&key)?)?.as_slice() {
+            [id] => Some(id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car(id: &str, id4: &str, name: Option<&str>) -> (String, String, Option<String>, Option<String>) {
+        (id.to_string(), id4.to_string(), name.map(str::to_string), None)
+    }
+
+    #[test]
+    fn test_match_plate() {
+        let matcher = VehicleMatcher::new(&[
+            car("C1", "0201", Some("帯広100け201")),
+            car("C2", "1234", None),
+            car("C3", "5678", None),
+            car("C4", "5678", None),
+        ]);
+        assert_eq!(matcher.match_plate("帯広 １００ け ２０１"), Some("C1"));
+        assert_eq!(matcher.match_plate("帯広100あ12-34"), Some("C2"));
+        assert_eq!(matcher.match_plate("201"), Some("C1"));
+        // 番号が重複する車両は紐づけない
+        assert_eq!(matcher.match_plate("5678"), None);
+        assert_eq!(matcher.match_plate(""), None);
+        assert_eq!(matcher.match_plate("9999"), None);
+    }
+}
@@ -0,0 +1,332 @@
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::db::organization::OrgScopedPool;
+use crate::db::Paginator;
+use crate::error::AppError;
+use crate::jobs::enqueue;
+use crate::middleware::AuthenticatedUser;
+use crate::models::{WebhookDeliveryAttemptModel, WebhookDeliveryModel, WebhookEndpointModel};
+use crate::outbox::EVENT_TYPES;
+use crate::proto::common::Empty;
+use crate::proto::webhooks::webhook_service_server::WebhookService;
+use crate::proto::webhooks::{
+    DeleteWebhookRequest, ListWebhookDeliveriesRequest, ListWebhookDeliveriesResponse,
+    ListWebhooksRequest, ListWebhooksResponse, RedeliverWebhookEventRequest, RegisterWebhookRequest,
+    RegisterWebhookResponse, SetWebhookEnabledRequest, Webhook, WebhookDelivery,
+};
+use crate::services::lineworks_auth;
+use crate::services::validation;
+use crate::webhooks::{generate_secret, WebhookDeliverPayload};
+
+const ENDPOINT_COLUMNS: &str = "id, url, description, event_types, enabled, created_at, updated_at, \
+     consecutive_failures, disabled_reason, last_success_at";
+
+const DELIVERY_COLUMNS: &str = "id, endpoint_id, event_type, payload, status, attempts, last_status_code, \
+     last_error, created_at, delivered_at";
+
+pub struct WebhookServiceImpl {
+    pool: PgPool,
+    /// 署名キーの暗号化キー（JWT_SECRET、bot_configs と同じ）
+    jwt_secret: String,
+}
+
+impl WebhookServiceImpl {
+    pub fn new(pool: PgPool, jwt_secret: String) -> Self {
+        Self { pool, jwt_secret }
+    }
+
+    fn get_authenticated_user<T>(request: &Request<T>) -> Result<AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    async fn verify_admin(&self, user_id: &str, org_id: &str) -> Result<(), Status> {
+        let role: Option<(String,)> = sqlx::query_as(
+            "SELECT role FROM user_organizations WHERE user_id = $1::uuid AND organization_id = $2::uuid",
+        )
+        .bind(user_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        match role {
+            Some((r,)) if r == "admin" => Ok(()),
+            Some(_) => Err(Status::permission_denied("Admin role required")),
+            None => Err(Status::permission_denied("Not a member of this organization")),
+        }
+    }
+
+    /// 管理者確認 + organization 設定済みのコネクション
+    async fn admin_conn<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(AuthenticatedUser, sqlx::pool::PoolConnection<sqlx::Postgres>), Status> {
+        let auth_user = Self::get_authenticated_user(request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id)
+            .await?;
+
+        let mut conn = self
+            .pool
+            .acquire_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+        Ok((auth_user, conn))
+    }
+}
+
+/// 登録可能な URL（https、ホストあり）
+fn validate_endpoint_url(url: &str) -> Result<(), Status> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| Status::invalid_argument("Invalid webhook URL"))?;
+    if parsed.scheme() != "https" || !matches!(parsed.host_str(), Some(host) if !host.is_empty()) {
+        return Err(Status::invalid_argument("Webhook URL must be https"));
+    }
+    Ok(())
+}
+
+/// 配送ごとの送信履歴（attempt 順）
+async fn load_attempts(
+    conn: &mut sqlx::PgConnection,
+    delivery_ids: &[i64],
+) -> Result<Vec<WebhookDeliveryAttemptModel>, Status> {
+    sqlx::query_as(
+        r#"
+        SELECT delivery_id, attempt, status_code, error, response_body, duration_ms, created_at
+        FROM webhook_delivery_attempts
+        WHERE delivery_id = ANY($1)
+        ORDER BY delivery_id, id
+        "#,
+    )
+    .bind(delivery_ids)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| Status::from(AppError::from(e)))
+}
+
+#[tonic::async_trait]
+impl WebhookService for WebhookServiceImpl {
+    async fn register_webhook(
+        &self,
+        request: Request<RegisterWebhookRequest>,
+    ) -> Result<Response<RegisterWebhookResponse>, Status> {
+        let (auth_user, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        let url = validation::line("url", &req.url, validation::URL_MAX_CHARS)?;
+        let description = validation::text("description", &req.description, validation::TEXT_MAX_CHARS)?;
+
+        validate_endpoint_url(&url)?;
+        let mut event_types = req.event_types;
+        event_types.sort();
+        event_types.dedup();
+        if let Some(unknown) = event_types.iter().find(|t| !EVENT_TYPES.contains(&t.as_str())) {
+            return Err(Status::invalid_argument(format!(
+                "Unknown event type: {} (available: {})",
+                unknown,
+                EVENT_TYPES.join(", ")
+            )));
+        }
+
+        let secret = generate_secret().map_err(Status::internal)?;
+        let secret_encrypted = lineworks_auth::encrypt_secret(&secret, &self.jwt_secret)
+            .map_err(|e| Status::internal(format!("Encrypt error: {}", e)))?;
+
+        let webhook: WebhookEndpointModel = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO webhook_endpoints (organization_id, url, description, event_types, secret_encrypted)
+            VALUES ($1::uuid, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            ENDPOINT_COLUMNS
+        ))
+        .bind(&auth_user.org_id)
+        .bind(&url)
+        .bind(&description)
+        .bind(&event_types)
+        .bind(&secret_encrypted)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        tracing::info!("Webhook {} registered for {:?}", webhook.id, webhook.event_types);
+        Ok(Response::new(RegisterWebhookResponse {
+            webhook: Some(webhook.to_proto()),
+            secret,
+        }))
+    }
+
+    async fn list_webhooks(
+        &self,
+        request: Request<ListWebhooksRequest>,
+    ) -> Result<Response<ListWebhooksResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+
+        let webhooks: Vec<WebhookEndpointModel> = sqlx::query_as(&format!(
+            "SELECT {} FROM webhook_endpoints ORDER BY created_at",
+            ENDPOINT_COLUMNS
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(Response::new(ListWebhooksResponse {
+            webhooks: webhooks.iter().map(WebhookEndpointModel::to_proto).collect(),
+        }))
+    }
+
+    async fn delete_webhook(
+        &self,
+        request: Request<DeleteWebhookRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let id = uuid::Uuid::parse_str(&request.into_inner().id)
+            .map_err(|_| Status::invalid_argument("Invalid webhook id"))?;
+
+        let result = sqlx::query("DELETE FROM webhook_endpoints WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::from)?;
+        if result.rows_affected() == 0 {
+            return Err(Status::not_found(format!("Webhook not found: {}", id)));
+        }
+        tracing::info!("Webhook {} deleted", id);
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn set_webhook_enabled(
+        &self,
+        request: Request<SetWebhookEnabledRequest>,
+    ) -> Result<Response<Webhook>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        let id = uuid::Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid webhook id"))?;
+
+        // 有効化したら連続失敗をリセット（すぐに再度無効化されないように）
+        let webhook: Option<WebhookEndpointModel> = sqlx::query_as(&format!(
+            r#"
+            UPDATE webhook_endpoints
+            SET enabled = $2,
+                consecutive_failures = CASE WHEN $2 THEN 0 ELSE consecutive_failures END,
+                disabled_reason = CASE WHEN $2 THEN NULL ELSE disabled_reason END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            ENDPOINT_COLUMNS
+        ))
+        .bind(id)
+        .bind(req.enabled)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+        let webhook = webhook.ok_or_else(|| Status::not_found(format!("Webhook not found: {}", id)))?;
+
+        tracing::info!("Webhook {} {}", id, if req.enabled { "enabled" } else { "disabled" });
+        Ok(Response::new(webhook.to_proto()))
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        request: Request<ListWebhookDeliveriesRequest>,
+    ) -> Result<Response<ListWebhookDeliveriesResponse>, Status> {
+        let (_, mut conn) = self.admin_conn(&request).await?;
+        let req = request.into_inner();
+        let webhook_id = if req.webhook_id.is_empty() {
+            None
+        } else {
+            Some(uuid::Uuid::parse_str(&req.webhook_id).map_err(|_| Status::invalid_argument("Invalid webhook id"))?)
+        };
+        let paginator = Paginator::from_request(req.pagination.as_ref())?;
+
+        let deliveries: Vec<WebhookDeliveryModel> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM webhook_deliveries
+            WHERE ($1::uuid IS NULL OR endpoint_id = $1)
+              AND ($2 = '' OR status = $2)
+              AND ($3::bigint IS NULL OR id < $3)
+            ORDER BY id DESC
+            LIMIT $4
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(webhook_id)
+        .bind(&req.status)
+        .bind(paginator.cursor_as::<i64>(0)?)
+        .bind(paginator.limit())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+        let (deliveries, pagination) = paginator.finish(deliveries, |d| vec![d.id.to_string()]);
+        let ids: Vec<i64> = deliveries.iter().map(|d| d.id).collect();
+        let attempts = load_attempts(&mut conn, &ids).await?;
+
+        Ok(Response::new(ListWebhookDeliveriesResponse {
+            deliveries: deliveries.iter().map(|d| d.to_proto(&attempts)).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn redeliver_webhook_event(
+        &self,
+        request: Request<RedeliverWebhookEventRequest>,
+    ) -> Result<Response<WebhookDelivery>, Status> {
+        let auth_user = Self::get_authenticated_user(&request)?;
+        self.verify_admin(&auth_user.user_id, &auth_user.org_id).await?;
+        let delivery_id = request.into_inner().delivery_id;
+
+        let mut tx = self
+            .pool
+            .begin_scoped(&auth_user.org_id)
+            .await
+            .map_err(AppError::from)?;
+
+        // 無効化されたエンドポイントには送らない（先に SetWebhookEnabled で有効化する）
+        let enabled: Option<bool> = sqlx::query_scalar(
+            r#"
+            SELECT e.enabled FROM webhook_deliveries d
+            JOIN webhook_endpoints e ON e.id = d.endpoint_id
+            WHERE d.id = $1
+            "#,
+        )
+        .bind(delivery_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        match enabled {
+            None => return Err(Status::not_found(format!("Webhook delivery not found: {}", delivery_id))),
+            Some(false) => return Err(Status::failed_precondition("Webhook is disabled")),
+            Some(true) => {}
+        }
+
+        // 本文は作成時のまま。attempts は履歴の通し番号として残す
+        let delivery: WebhookDeliveryModel = sqlx::query_as(&format!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending', delivered_at = NULL, updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(delivery_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        // 再試行待ちの job がすでにあれば、それがそのまま送る
+        enqueue(&mut tx, &auth_user.org_id, WebhookDeliverPayload::job(delivery_id))
+            .await
+            .map_err(AppError::from)?;
+        let attempts = load_attempts(&mut tx, &[delivery_id]).await?;
+        tx.commit()
+            .await
+            .map_err(AppError::from)?;
+
+        tracing::info!("Webhook delivery {} queued for redelivery", delivery_id);
+        Ok(Response::new(delivery.to_proto(&attempts)))
+    }
+}
@@ -0,0 +1,123 @@
+// Graceful shutdown
+//
+// SIGTERM / SIGINT を受けたら drain を始める: 新しい RPC は DrainLayer が UNAVAILABLE で断り、
+// job / outbox のワーカーは新しく claim せず、実行中の RPC とバックグラウンドのタスクの終了を待つ（SHUTDOWN_TIMEOUT_SECS まで）。
+// 待ちきれなかった job は running のまま残り、ロックタイムアウト後に他のインスタンスが再取得する。
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+struct Shutdown {
+    token: CancellationToken,
+    tracker: TaskTracker,
+    started_at: OnceLock<Instant>,
+}
+
+static SHUTDOWN: OnceLock<Shutdown> = OnceLock::new();
+
+fn state() -> &'static Shutdown {
+    SHUTDOWN.get_or_init(|| Shutdown {
+        token: CancellationToken::new(),
+        tracker: TaskTracker::new(),
+        started_at: OnceLock::new(),
+    })
+}
+
+/// 終了時に待つバックグラウンドのタスクを起動（tokio::spawn の代わり）
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    state().tracker.spawn(future)
+}
+
+/// drain を始める（2 回目以降は何もしない）
+pub fn begin() {
+    let state = state();
+    state.started_at.get_or_init(Instant::now);
+    state.token.cancel();
+}
+
+/// drain 中か（新しい処理を始めない）
+pub fn is_draining() -> bool {
+    state().token.is_cancelled()
+}
+
+/// drain が始まるまで待つ
+pub async fn cancelled() {
+    state().token.cancelled().await
+}
+
+/// duration だけ待つ（drain が始まったらすぐ戻る。ワーカーのポーリング用）
+pub async fn sleep(duration: Duration) {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {}
+        _ = cancelled() => {}
+    }
+}
+
+/// SIGTERM / SIGINT を待って drain を始める（serve_with_shutdown に渡す）
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+        // シグナル以外で drain が始まった場合
+        _ = cancelled() => {}
+    }
+    begin();
+}
+
+/// drain 開始から timeout 経過するまで待つ（drain 前は待ち続ける）
+pub async fn deadline(timeout: Duration) {
+    cancelled().await;
+    let started_at = *state().started_at.get_or_init(Instant::now);
+    tokio::time::sleep_until(started_at + timeout).await;
+}
+
+/// spawn したタスクの終了を期限まで待つ（drain 前なら始める）。全て終わったら true
+pub async fn drain(timeout: Duration) -> bool {
+    begin();
+    let state = state();
+    state.tracker.close();
+    tokio::select! {
+        _ = state.tracker.wait() => {
+            tracing::info!("All background tasks finished");
+            true
+        }
+        _ = deadline(timeout) => {
+            tracing::warn!(
+                "Timed out waiting for {} background tasks; they will be retried after restart",
+                state.tracker.len()
+            );
+            false
+        }
+    }
+}
@@ -1,16 +1,25 @@
+use std::time::Duration;
+
 use google_cloud_storage::{
     client::{Client, ClientConfig},
     http::objects::{
         delete::DeleteObjectRequest,
         download::Range,
         get::GetObjectRequest,
+        list::ListObjectsRequest,
+        rewrite::RewriteObjectRequest,
         upload::{Media, UploadObjectRequest, UploadType},
+        Object,
     },
+    http::resumable_upload_client::{ChunkSize, UploadStatus},
+    sign::{SignedURLMethod, SignedURLOptions},
 };
 
+use tokio_stream::StreamExt;
+
 use crate::error::{AppError, AppResult};
 
-use super::{ObjectInfo, RestoreStatus, StorageBackend};
+use super::{ByteStream, ObjectInfo, RestoreStatus, StorageBackend, UploadedPart};
 
 pub struct GcsBackend {
     client: Client,
@@ -22,7 +31,7 @@ impl GcsBackend {
         let config = ClientConfig::default()
             .with_auth()
             .await
-            .map_err(|e| AppError::Storage(format!("GCS auth failed: {}", e)))?;
+            .map_err(|e| AppError::storage("GCS auth failed", e))?;
         let client = Client::new(config);
         Ok(Self { client, bucket })
     }
@@ -45,7 +54,7 @@ impl StorageBackend for GcsBackend {
                 &upload_type,
             )
             .await
-            .map_err(|e| AppError::Storage(format!("GCS upload failed: {}", e)))?;
+            .map_err(|e| AppError::storage("GCS upload failed", e))?;
 
         tracing::info!("GCS upload: bucket={}, key={}", self.bucket, key);
         Ok(format!("gs://{}/{}", self.bucket, key))
@@ -63,7 +72,7 @@ impl StorageBackend for GcsBackend {
                 &Range::default(),
             )
             .await
-            .map_err(|e| AppError::Storage(format!("GCS download failed: {}", e)))?;
+            .map_err(|e| AppError::storage("GCS download failed", e))?;
 
         tracing::info!(
             "GCS download: bucket={}, key={}, size={}",
@@ -74,6 +83,26 @@ impl StorageBackend for GcsBackend {
         Ok(data)
     }
 
+    async fn download_stream(&self, key: &str) -> AppResult<ByteStream> {
+        let stream = self
+            .client
+            .download_streamed_object(
+                &GetObjectRequest {
+                    bucket: self.bucket.clone(),
+                    object: key.to_string(),
+                    ..Default::default()
+                },
+                &Range::default(),
+            )
+            .await
+            .map_err(|e| AppError::storage("GCS download failed", e))?;
+
+        tracing::info!("GCS download stream: bucket={}, key={}", self.bucket, key);
+        Ok(Box::pin(
+            stream.map(|chunk| chunk.map_err(|e| AppError::storage("GCS download failed", e))),
+        ))
+    }
+
     async fn delete(&self, key: &str) -> AppResult<()> {
         self.client
             .delete_object(&DeleteObjectRequest {
@@ -82,12 +111,24 @@ impl StorageBackend for GcsBackend {
                 ..Default::default()
             })
             .await
-            .map_err(|e| AppError::Storage(format!("GCS delete failed: {}", e)))?;
+            .map_err(|e| AppError::storage("GCS delete failed", e))?;
 
         tracing::info!("GCS delete: bucket={}, key={}", self.bucket, key);
         Ok(())
     }
 
+    async fn probe(&self) -> AppResult<()> {
+        self.client
+            .list_objects(&ListObjectsRequest {
+                bucket: self.bucket.clone(),
+                max_results: Some(1),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| AppError::storage("GCS list objects failed", e))?;
+        Ok(())
+    }
+
     async fn get_object_info(&self, key: &str) -> AppResult<ObjectInfo> {
         let obj = self
             .client
@@ -97,7 +138,7 @@ impl StorageBackend for GcsBackend {
                 ..Default::default()
             })
             .await
-            .map_err(|e| AppError::Storage(format!("GCS get object failed: {}", e)))?;
+            .map_err(|e| AppError::storage("GCS get object failed", e))?;
 
         Ok(ObjectInfo {
             storage_class: obj.storage_class.clone(),
@@ -116,6 +157,168 @@ impl StorageBackend for GcsBackend {
         Ok(())
     }
 
+    async fn rewrite_storage_class(&self, key: &str, storage_class: &str) -> AppResult<()> {
+        // destination_metadata は置き換えなので、元のメタデータにクラスだけ変えて渡す
+        let mut metadata = self
+            .client
+            .get_object(&GetObjectRequest {
+                bucket: self.bucket.clone(),
+                object: key.to_string(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| AppError::storage("GCS get object failed", e))?;
+        metadata.storage_class = Some(storage_class.to_string());
+
+        // 大きなオブジェクトは 1 回で終わらないので rewrite_token で続ける
+        let mut rewrite_token = None;
+        loop {
+            let response = self
+                .client
+                .rewrite_object(&RewriteObjectRequest {
+                    source_bucket: self.bucket.clone(),
+                    source_object: key.to_string(),
+                    destination_bucket: self.bucket.clone(),
+                    destination_object: key.to_string(),
+                    destination_metadata: Some(metadata.clone()),
+                    rewrite_token: rewrite_token.take(),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| AppError::storage("GCS storage class change failed", e))?;
+            if response.done {
+                break;
+            }
+            rewrite_token = response.rewrite_token;
+        }
+
+        tracing::info!(
+            "GCS storage class changed: bucket={}, key={}, class={}",
+            self.bucket,
+            key,
+            storage_class
+        );
+        Ok(())
+    }
+
+    fn cold_storage_class(&self) -> &'static str {
+        "NEARLINE"
+    }
+
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> AppResult<String> {
+        // Cloud Run では秘密鍵がないので IAM signBlob で署名する（roles/iam.serviceAccountTokenCreator が必要）
+        self.client
+            .signed_url(
+                &self.bucket,
+                key,
+                None,
+                None,
+                SignedURLOptions {
+                    method: SignedURLMethod::GET,
+                    expires: expires_in,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| AppError::storage("GCS signed URL failed", e))
+    }
+
+    async fn signed_upload_url(
+        &self,
+        key: &str,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> AppResult<String> {
+        self.client
+            .signed_url(
+                &self.bucket,
+                key,
+                None,
+                None,
+                SignedURLOptions {
+                    method: SignedURLMethod::PUT,
+                    expires: expires_in,
+                    content_type: Some(content_type.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| AppError::storage("GCS signed upload URL failed", e))
+    }
+
+    async fn create_multipart_upload(&self, key: &str, content_type: &str) -> AppResult<String> {
+        // resumable upload の session URL（1 週間有効）をセッション ID にする
+        let uploader = self
+            .client
+            .prepare_resumable_upload(
+                &UploadObjectRequest {
+                    bucket: self.bucket.clone(),
+                    ..Default::default()
+                },
+                &UploadType::Multipart(Box::new(Object {
+                    name: key.to_string(),
+                    content_type: Some(content_type.to_string()),
+                    ..Default::default()
+                })),
+            )
+            .await
+            .map_err(|e| AppError::storage("GCS resumable upload failed", e))?;
+
+        tracing::info!("GCS resumable upload started: bucket={}, key={}", self.bucket, key);
+        Ok(uploader.url().to_string())
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        session_id: &str,
+        part_number: u32,
+        offset: u64,
+        data: Vec<u8>,
+        total_size: Option<u64>,
+    ) -> AppResult<UploadedPart> {
+        let last_byte = offset + data.len() as u64 - 1;
+        let status = self
+            .client
+            .get_resumable_upload(session_id.to_string())
+            .upload_multiple_chunk(data, &ChunkSize::new(offset, last_byte, total_size))
+            .await
+            .map_err(|e| AppError::storage("GCS upload chunk failed", e))?;
+
+        match status {
+            UploadStatus::Ok(_) | UploadStatus::ResumeIncomplete(_) => Ok(UploadedPart {
+                part_number,
+                etag: String::new(),
+            }),
+            UploadStatus::NotStarted => Err(AppError::storage(
+                "GCS upload chunk failed",
+                format!("chunk {} of {} was not accepted", part_number, key),
+            )),
+        }
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        _session_id: &str,
+        _parts: &[UploadedPart],
+    ) -> AppResult<()> {
+        // resumable upload は最後のチャンクで確定する
+        tracing::info!("GCS resumable upload completed: bucket={}, key={}", self.bucket, key);
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(&self, key: &str, session_id: &str) -> AppResult<()> {
+        self.client
+            .get_resumable_upload(session_id.to_string())
+            .cancel()
+            .await
+            .map_err(|e| AppError::storage("GCS cancel resumable upload failed", e))?;
+
+        tracing::info!("GCS resumable upload aborted: bucket={}, key={}", self.bucket, key);
+        Ok(())
+    }
+
     fn bucket(&self) -> &str {
         &self.bucket
     }
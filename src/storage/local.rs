@@ -0,0 +1,367 @@
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::error::{AppError, AppResult};
+
+use super::{ByteStream, ObjectInfo, RestoreStatus, StorageBackend, UploadedPart};
+
+/// Content-Type を保存するディレクトリ（FS_ROOT 直下、キーと同じ階層）
+const META_DIR: &str = ".meta";
+/// マルチパートアップロード中のパートを置くディレクトリ
+const UPLOADS_DIR: &str = ".uploads";
+/// download_stream で 1 回に読む大きさ
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// ローカルファイルシステムに保存するバックエンド（クラウドの認証情報がない開発環境・結合テスト用）
+pub struct LocalFsBackend {
+    root: PathBuf,
+    bucket_name: String,
+}
+
+impl LocalFsBackend {
+    pub async fn new(root: impl Into<PathBuf>) -> AppResult<Self> {
+        let root = root.into();
+        tokio::fs::create_dir_all(&root)
+            .await
+            .map_err(|e| AppError::storage("Local storage root error", e))?;
+        let root = tokio::fs::canonicalize(&root)
+            .await
+            .map_err(|e| AppError::storage("Local storage root error", e))?;
+        let bucket_name = root.display().to_string();
+        Ok(Self { root, bucket_name })
+    }
+
+    /// キーをファイルパスにする（FS_ROOT の外を指すキーは拒否）
+    fn object_path(&self, base: &Path, key: &str) -> AppResult<PathBuf> {
+        let relative = Path::new(key);
+        let valid = !key.is_empty()
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(name) if name != META_DIR && name != UPLOADS_DIR));
+        if !valid {
+            return Err(AppError::InvalidInput(format!("Invalid storage key: '{}'", key)));
+        }
+        Ok(base.join(relative))
+    }
+
+    fn data_path(&self, key: &str) -> AppResult<PathBuf> {
+        self.object_path(&self.root, key)
+    }
+
+    fn meta_path(&self, key: &str) -> AppResult<PathBuf> {
+        self.object_path(&self.root.join(META_DIR), key)
+    }
+
+    fn session_dir(&self, session_id: &str) -> AppResult<PathBuf> {
+        uuid::Uuid::parse_str(session_id).map_err(|_| {
+            AppError::InvalidInput(format!("Invalid multipart session: '{}'", session_id))
+        })?;
+        Ok(self.root.join(UPLOADS_DIR).join(session_id))
+    }
+
+    fn file_url(&self, key: &str) -> AppResult<String> {
+        Ok(format!("file://{}", self.data_path(key)?.display()))
+    }
+
+    async fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, data).await
+    }
+
+    async fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+        match tokio::fs::remove_file(path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl StorageBackend for LocalFsBackend {
+    async fn upload(&self, key: &str, data: &[u8], content_type: &str) -> AppResult<String> {
+        let path = self.data_path(key)?;
+        Self::write_file(&path, data)
+            .await
+            .map_err(|e| AppError::storage("Local upload failed", e))?;
+        Self::write_file(&self.meta_path(key)?, content_type.as_bytes())
+            .await
+            .map_err(|e| AppError::storage("Local upload failed", e))?;
+
+        tracing::info!("Local upload: root={}, key={}", self.bucket_name, key);
+        Ok(format!("file://{}", path.display()))
+    }
+
+    async fn download(&self, key: &str) -> AppResult<Vec<u8>> {
+        let data = tokio::fs::read(self.data_path(key)?)
+            .await
+            .map_err(|e| AppError::storage("Local download failed", e))?;
+
+        tracing::info!(
+            "Local download: root={}, key={}, size={}",
+            self.bucket_name,
+            key,
+            data.len()
+        );
+        Ok(data)
+    }
+
+    async fn download_stream(&self, key: &str) -> AppResult<ByteStream> {
+        let mut file = tokio::fs::File::open(self.data_path(key)?)
+            .await
+            .map_err(|e| AppError::storage("Local download failed", e))?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tokio::spawn(async move {
+            loop {
+                let mut buf = vec![0u8; READ_CHUNK_SIZE];
+                let item = match file.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        buf.truncate(n);
+                        Ok(bytes::Bytes::from(buf))
+                    }
+                    Err(e) => Err(AppError::storage("Local download failed", e)),
+                };
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        tracing::info!("Local download stream: root={}, key={}", self.bucket_name, key);
+        Ok(Box::pin(ReceiverStream::new(rx)))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        tokio::fs::remove_file(self.data_path(key)?)
+            .await
+            .map_err(|e| AppError::storage("Local delete failed", e))?;
+        Self::remove_if_exists(&self.meta_path(key)?)
+            .await
+            .map_err(|e| AppError::storage("Local delete failed", e))?;
+
+        tracing::info!("Local delete: root={}, key={}", self.bucket_name, key);
+        Ok(())
+    }
+
+    async fn probe(&self) -> AppResult<()> {
+        let metadata = tokio::fs::metadata(&self.root)
+            .await
+            .map_err(|e| AppError::storage("Local storage root error", e))?;
+        if !metadata.is_dir() {
+            return Err(AppError::Unavailable(format!("{} is not a directory", self.root.display())));
+        }
+        Ok(())
+    }
+
+    async fn get_object_info(&self, key: &str) -> AppResult<ObjectInfo> {
+        let metadata = tokio::fs::metadata(self.data_path(key)?)
+            .await
+            .map_err(|e| AppError::storage("Local head object failed", e))?;
+        let content_type = tokio::fs::read_to_string(self.meta_path(key)?).await.ok();
+
+        Ok(ObjectInfo {
+            storage_class: Some("STANDARD".to_string()),
+            restore_status: RestoreStatus::NotNeeded,
+            content_type,
+            size: Some(metadata.len() as i64),
+        })
+    }
+
+    async fn rewrite_to_standard(&self, key: &str) -> AppResult<()> {
+        tracing::info!(
+            "Local rewrite_to_standard called (no-op): root={}, key={}",
+            self.bucket_name,
+            key
+        );
+        Ok(())
+    }
+
+    async fn rewrite_storage_class(&self, key: &str, storage_class: &str) -> AppResult<()> {
+        tracing::info!(
+            "Local rewrite_storage_class called (no-op): root={}, key={}, class={}",
+            self.bucket_name,
+            key,
+            storage_class
+        );
+        Ok(())
+    }
+
+    fn cold_storage_class(&self) -> &'static str {
+        "NEARLINE"
+    }
+
+    async fn signed_url(&self, key: &str, _expires_in: Duration) -> AppResult<String> {
+        // 署名の仕組みはないので、同じホストから読める file:// URL を返す
+        self.file_url(key)
+    }
+
+    async fn signed_upload_url(
+        &self,
+        key: &str,
+        _content_type: &str,
+        _expires_in: Duration,
+    ) -> AppResult<String> {
+        self.file_url(key)
+    }
+
+    async fn create_multipart_upload(&self, key: &str, content_type: &str) -> AppResult<String> {
+        self.data_path(key)?;
+        let session_id = uuid::Uuid::new_v4().to_string();
+        Self::write_file(
+            &self.session_dir(&session_id)?.join("content_type"),
+            content_type.as_bytes(),
+        )
+        .await
+        .map_err(|e| AppError::storage("Local multipart upload failed", e))?;
+
+        tracing::info!("Local multipart upload started: root={}, key={}", self.bucket_name, key);
+        Ok(session_id)
+    }
+
+    async fn upload_part(
+        &self,
+        _key: &str,
+        session_id: &str,
+        part_number: u32,
+        _offset: u64,
+        data: Vec<u8>,
+        _total_size: Option<u64>,
+    ) -> AppResult<UploadedPart> {
+        let dir = self.session_dir(session_id)?;
+        if !tokio::fs::try_exists(&dir).await.unwrap_or(false) {
+            return Err(AppError::NotFound(format!(
+                "Multipart session not found: {}",
+                session_id
+            )));
+        }
+        tokio::fs::write(dir.join(format!("part-{:05}", part_number)), &data)
+            .await
+            .map_err(|e| AppError::storage("Local upload part failed", e))?;
+
+        Ok(UploadedPart {
+            part_number,
+            etag: String::new(),
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        session_id: &str,
+        parts: &[UploadedPart],
+    ) -> AppResult<()> {
+        let dir = self.session_dir(session_id)?;
+        let content_type = tokio::fs::read_to_string(dir.join("content_type"))
+            .await
+            .map_err(|e| AppError::storage("Local complete multipart upload failed", e))?;
+
+        let path = self.data_path(key)?;
+        let meta_path = self.meta_path(key)?;
+        let result: std::io::Result<()> = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let mut out = tokio::fs::File::create(&path).await?;
+            for part in parts {
+                let data = tokio::fs::read(dir.join(format!("part-{:05}", part.part_number))).await?;
+                out.write_all(&data).await?;
+            }
+            out.flush().await?;
+            Self::write_file(&meta_path, content_type.as_bytes()).await
+        }
+        .await;
+        result.map_err(|e| AppError::storage("Local complete multipart upload failed", e))?;
+        tokio::fs::remove_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::storage("Local complete multipart upload failed", e))?;
+
+        tracing::info!("Local multipart upload completed: root={}, key={}", self.bucket_name, key);
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(&self, key: &str, session_id: &str) -> AppResult<()> {
+        match tokio::fs::remove_dir_all(self.session_dir(session_id)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(AppError::storage("Local abort multipart upload failed", e));
+            }
+            _ => {}
+        }
+
+        tracing::info!("Local multipart upload aborted: root={}, key={}", self.bucket_name, key);
+        Ok(())
+    }
+
+    fn bucket(&self) -> &str {
+        &self.bucket_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    async fn backend() -> LocalFsBackend {
+        let root = std::env::temp_dir().join(format!("rust-logi-storage-{}", uuid::Uuid::new_v4()));
+        LocalFsBackend::new(root).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upload_download_delete() {
+        let storage = backend().await;
+        storage.upload("org/files/a.json", b"{}", "application/json").await.unwrap();
+
+        assert_eq!(storage.download("org/files/a.json").await.unwrap(), b"{}");
+        let info = storage.get_object_info("org/files/a.json").await.unwrap();
+        assert_eq!(info.content_type.as_deref(), Some("application/json"));
+        assert_eq!(info.size, Some(2));
+
+        storage.delete("org/files/a.json").await.unwrap();
+        assert!(storage.download("org/files/a.json").await.is_err());
+        tokio::fs::remove_dir_all(&storage.root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejects_keys_outside_root() {
+        let storage = backend().await;
+        for key in ["../escape", "/etc/passwd", "", ".meta/a"] {
+            assert!(
+                matches!(storage.upload(key, b"x", "text/plain").await, Err(AppError::InvalidInput(_))),
+                "key {:?} should be rejected",
+                key
+            );
+        }
+        tokio::fs::remove_dir_all(&storage.root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_upload_stream_and_download_stream() {
+        let storage = backend().await;
+        let data: Vec<u8> = (0..crate::storage::MULTIPART_PART_SIZE + 1000).map(|i| i as u8).collect();
+        let chunks: Vec<AppResult<bytes::Bytes>> = data
+            .chunks(1 << 20)
+            .map(|c| Ok(bytes::Bytes::copy_from_slice(c)))
+            .collect();
+
+        let size = storage
+            .upload_stream("big.bin", Box::pin(tokio_stream::iter(chunks)), "application/octet-stream")
+            .await
+            .unwrap();
+        assert_eq!(size, data.len() as u64);
+
+        let mut stream = storage.download_stream("big.bin").await.unwrap();
+        let mut read = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            read.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(read, data);
+        tokio::fs::remove_dir_all(&storage.root).await.unwrap();
+    }
+}
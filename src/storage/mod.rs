@@ -1,15 +1,28 @@
-// Storage abstraction for GCS and R2 backends
+// Storage abstraction for GCS, R2 and local filesystem backends
 
 pub mod gcs;
+pub mod local;
 pub mod r2;
+pub mod traced;
 
 pub use gcs::GcsBackend;
+pub use local::LocalFsBackend;
 pub use r2::R2Backend;
+pub use traced::TracedStorage;
 
 // Backward compatibility alias
 pub type GcsClient = GcsBackend;
 
-use crate::error::AppResult;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
 
 /// オブジェクトの復元状態
 #[derive(Debug, Clone, PartialEq)]
@@ -33,6 +46,21 @@ pub struct ObjectInfo {
     pub size: Option<i64>,
 }
 
+/// マルチパートアップロードのパートの大きさ（最後のパート以外）
+///
+/// S3 / R2 の下限（5 MiB）以上、GCS resumable upload の単位（256 KiB）の倍数。
+pub const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// ストレージとやり取りするバイト列のストリーム
+pub type ByteStream = Pin<Box<dyn Stream<Item = AppResult<Bytes>> + Send>>;
+
+/// 送り終えたパート（complete_multipart_upload に渡す。GCS では etag は空）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadedPart {
+    pub part_number: u32,
+    pub etag: String,
+}
+
 /// ストレージバックエンド抽象化（GCS / R2 共通インタフェース）
 #[tonic::async_trait]
 pub trait StorageBackend: Send + Sync {
@@ -48,13 +76,170 @@ pub trait StorageBackend: Send + Sync {
     /// オブジェクトメタデータを取得
     async fn get_object_info(&self, key: &str) -> AppResult<ObjectInfo>;
 
-    /// STANDARD ストレージクラスへの書き換え（GCS Autoclass / R2 では no-op）
+    /// STANDARD ストレージクラスへの書き換え（GCS Autoclass では no-op）
     async fn rewrite_to_standard(&self, key: &str) -> AppResult<()>;
 
+    /// ストレージクラスを書き換える（GCS: rewrite、R2: 同じキーへの copy。GCS は Autoclass 無効のバケットのみ）
+    async fn rewrite_storage_class(&self, key: &str, storage_class: &str) -> AppResult<()>;
+
+    /// アクセスされないファイルの降格先（GCS: NEARLINE、R2: STANDARD_IA）
+    fn cold_storage_class(&self) -> &'static str;
+
+    /// バケットに到達でき、認証が通るか（ヘルスチェック用。オブジェクトを 1 件だけ一覧する）
+    async fn probe(&self) -> AppResult<()>;
+
+    /// 期限付きでダウンロードできる署名付き URL（GET）
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> AppResult<String>;
+
+    /// 期限付きでアップロードできる署名付き URL（PUT。GCS では Content-Type も署名に含む）
+    async fn signed_upload_url(
+        &self,
+        key: &str,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> AppResult<String>;
+
+    /// マルチパートアップロードを開始し、再開に使うセッション ID を返す
+    async fn create_multipart_upload(&self, key: &str, content_type: &str) -> AppResult<String>;
+
+    /// offset からのパートを送る（part_number は 1 から。最後以外は MULTIPART_PART_SIZE ちょうど）
+    ///
+    /// total_size は全体の大きさ。まだ分からない途中のパートでは None でよい（最後のパートでは必須）。
+    async fn upload_part(
+        &self,
+        key: &str,
+        session_id: &str,
+        part_number: u32,
+        offset: u64,
+        data: Vec<u8>,
+        total_size: Option<u64>,
+    ) -> AppResult<UploadedPart>;
+
+    /// 送り終えたパートを 1 つのオブジェクトにする
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        session_id: &str,
+        parts: &[UploadedPart],
+    ) -> AppResult<()>;
+
+    /// マルチパートアップロードを中止し、送ったパートを破棄する
+    async fn abort_multipart_upload(&self, key: &str, session_id: &str) -> AppResult<()>;
+
+    /// ストリームを読みながらマルチパートアップロードで保存する（全体をメモリに載せない）。保存したバイト数を返す
+    async fn upload_stream(
+        &self,
+        key: &str,
+        mut data: ByteStream,
+        content_type: &str,
+    ) -> AppResult<u64> {
+        let Some(first) = data.next().await.transpose()? else {
+            // 空のパートだけのマルチパートは作れないので通常のアップロード
+            self.upload(key, &[], content_type).await?;
+            return Ok(0);
+        };
+
+        let session_id = self.create_multipart_upload(key, content_type).await?;
+        let result: AppResult<(Vec<UploadedPart>, u64)> = async {
+            let mut parts = Vec::new();
+            let mut offset = 0u64;
+            let mut buffer = first.to_vec();
+            loop {
+                // 最後のパートかは次のデータを読むまで分からないので、1 パート分を超えてから送る
+                while buffer.len() > MULTIPART_PART_SIZE {
+                    let rest = buffer.split_off(MULTIPART_PART_SIZE);
+                    let part = std::mem::replace(&mut buffer, rest);
+                    let part_number = parts.len() as u32 + 1;
+                    parts.push(self.upload_part(key, &session_id, part_number, offset, part, None).await?);
+                    offset += MULTIPART_PART_SIZE as u64;
+                }
+                match data.next().await.transpose()? {
+                    Some(bytes) => buffer.extend_from_slice(&bytes),
+                    None => break,
+                }
+            }
+            let total_size = offset + buffer.len() as u64;
+            let part_number = parts.len() as u32 + 1;
+            parts.push(
+                self.upload_part(key, &session_id, part_number, offset, buffer, Some(total_size))
+                    .await?,
+            );
+            Ok((parts, total_size))
+        }
+        .await;
+
+        let completed = match result {
+            Ok((parts, total_size)) => self
+                .complete_multipart_upload(key, &session_id, &parts)
+                .await
+                .map(|_| total_size),
+            Err(e) => Err(e),
+        };
+        if completed.is_err() {
+            if let Err(e) = self.abort_multipart_upload(key, &session_id).await {
+                tracing::warn!("Failed to abort multipart upload of {}: {}", key, e.report());
+            }
+        }
+        completed
+    }
+
+    /// オブジェクトをストリームで読む（全体をメモリに載せない）
+    async fn download_stream(&self, key: &str) -> AppResult<ByteStream>;
+
     /// バケット名を取得
     fn bucket(&self) -> &str;
 }
 
+/// STORAGE_BACKEND 設定からバックエンドを生成
+///
+/// `None` / `"gcs"` で GCS_BUCKET 未設定の場合は `Ok(None)`（DB blob 保存）。
+/// 各操作は `TracedStorage` で span に囲む。
+pub async fn create_backend(
+    config: &Config,
+    backend: Option<&str>,
+) -> AppResult<Option<Arc<dyn StorageBackend>>> {
+    match backend {
+        Some("r2") => {
+            let require = |value: &Option<String>, name: &str| {
+                value.clone().ok_or_else(|| {
+                    AppError::InvalidInput(format!("{} required when STORAGE_BACKEND=r2", name))
+                })
+            };
+            let bucket = require(&config.r2_bucket, "R2_BUCKET")?;
+            let account_id = require(&config.r2_account_id, "R2_ACCOUNT_ID")?;
+            let access_key = require(&config.r2_access_key, "R2_ACCESS_KEY")?;
+            let secret_key = require(&config.r2_secret_key, "R2_SECRET_KEY")?;
+
+            tracing::info!("R2 storage enabled: bucket={}", bucket);
+            let backend = R2Backend::new(bucket, account_id, access_key, secret_key)?;
+            Ok(Some(Arc::new(TracedStorage::new(backend))))
+        }
+        Some("local") => {
+            let root = config.fs_root.clone().ok_or_else(|| {
+                AppError::InvalidInput("FS_ROOT required when STORAGE_BACKEND=local".to_string())
+            })?;
+
+            let backend = LocalFsBackend::new(root).await?;
+            tracing::info!("Local filesystem storage enabled: root={}", backend.bucket());
+            Ok(Some(Arc::new(TracedStorage::new(backend))))
+        }
+        Some("gcs") | None => {
+            if let Some(bucket) = &config.gcs_bucket {
+                tracing::info!("GCS storage enabled: bucket={}", bucket);
+                let backend = GcsBackend::new(bucket.clone()).await?;
+                Ok(Some(Arc::new(TracedStorage::new(backend))))
+            } else {
+                tracing::info!("No storage backend configured, using database blob storage");
+                Ok(None)
+            }
+        }
+        Some(other) => Err(AppError::InvalidInput(format!(
+            "Unknown STORAGE_BACKEND: '{}'. Expected 'gcs', 'r2' or 'local'",
+            other
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
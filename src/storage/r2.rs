@@ -1,10 +1,15 @@
+use std::time::Duration;
+
 use s3::bucket::Bucket;
 use s3::creds::Credentials;
+use s3::serde_types::Part;
 use s3::Region;
 
+use tokio_stream::StreamExt;
+
 use crate::error::{AppError, AppResult};
 
-use super::{ObjectInfo, RestoreStatus, StorageBackend};
+use super::{ByteStream, ObjectInfo, RestoreStatus, StorageBackend, UploadedPart};
 
 pub struct R2Backend {
     bucket: Box<Bucket>,
@@ -30,10 +35,10 @@ impl R2Backend {
             None, // session token
             None, // profile
         )
-        .map_err(|e| AppError::Storage(format!("R2 credentials error: {}", e)))?;
+        .map_err(|e| AppError::storage("R2 credentials error", e))?;
 
         let bucket = Bucket::new(&bucket_name, region, credentials)
-            .map_err(|e| AppError::Storage(format!("R2 bucket error: {}", e)))?;
+            .map_err(|e| AppError::storage("R2 bucket error", e))?;
 
         Ok(Self {
             bucket,
@@ -48,7 +53,7 @@ impl StorageBackend for R2Backend {
         self.bucket
             .put_object_with_content_type(key, data, content_type)
             .await
-            .map_err(|e| AppError::Storage(format!("R2 upload failed: {}", e)))?;
+            .map_err(|e| AppError::storage("R2 upload failed", e))?;
 
         tracing::info!("R2 upload: bucket={}, key={}", self.bucket_name, key);
         Ok(format!("r2://{}/{}", self.bucket_name, key))
@@ -59,7 +64,7 @@ impl StorageBackend for R2Backend {
             .bucket
             .get_object(key)
             .await
-            .map_err(|e| AppError::Storage(format!("R2 download failed: {}", e)))?;
+            .map_err(|e| AppError::storage("R2 download failed", e))?;
 
         tracing::info!(
             "R2 download: bucket={}, key={}, size={}",
@@ -70,25 +75,54 @@ impl StorageBackend for R2Backend {
         Ok(response.bytes().to_vec())
     }
 
+    async fn download_stream(&self, key: &str) -> AppResult<ByteStream> {
+        let response = self
+            .bucket
+            .get_object_stream(key)
+            .await
+            .map_err(|e| AppError::storage("R2 download failed", e))?;
+        if !(200..300).contains(&response.status_code) {
+            return Err(AppError::storage(
+                "R2 download failed",
+                format!("status {} for {}", response.status_code, key),
+            ));
+        }
+
+        tracing::info!("R2 download stream: bucket={}, key={}", self.bucket_name, key);
+        Ok(Box::pin(
+            response
+                .bytes
+                .map(|chunk| chunk.map_err(|e| AppError::storage("R2 download failed", e))),
+        ))
+    }
+
     async fn delete(&self, key: &str) -> AppResult<()> {
         self.bucket
             .delete_object(key)
             .await
-            .map_err(|e| AppError::Storage(format!("R2 delete failed: {}", e)))?;
+            .map_err(|e| AppError::storage("R2 delete failed", e))?;
 
         tracing::info!("R2 delete: bucket={}, key={}", self.bucket_name, key);
         Ok(())
     }
 
+    async fn probe(&self) -> AppResult<()> {
+        self.bucket
+            .list_page(String::new(), None, None, None, Some(1))
+            .await
+            .map_err(|e| AppError::storage("R2 list objects failed", e))?;
+        Ok(())
+    }
+
     async fn get_object_info(&self, key: &str) -> AppResult<ObjectInfo> {
         let (head, _status) = self
             .bucket
             .head_object(key)
             .await
-            .map_err(|e| AppError::Storage(format!("R2 head object failed: {}", e)))?;
+            .map_err(|e| AppError::storage("R2 head object failed", e))?;
 
         Ok(ObjectInfo {
-            storage_class: Some("STANDARD".to_string()),
+            storage_class: Some(head.storage_class.unwrap_or_else(|| "STANDARD".to_string())),
             restore_status: RestoreStatus::NotNeeded,
             content_type: head.content_type,
             size: head.content_length.map(|l| l as i64),
@@ -96,14 +130,117 @@ impl StorageBackend for R2Backend {
     }
 
     async fn rewrite_to_standard(&self, key: &str) -> AppResult<()> {
+        // 降格（STANDARD_IA）したオブジェクトのみ対象になる
+        self.rewrite_storage_class(key, "STANDARD").await
+    }
+
+    async fn rewrite_storage_class(&self, key: &str, storage_class: &str) -> AppResult<()> {
+        // 同じキーへのコピーでクラスだけを変える（メタデータは引き継ぐ）
+        let mut bucket = (*self.bucket).clone();
+        bucket.add_header("x-amz-storage-class", storage_class);
+        bucket.add_header("x-amz-metadata-directive", "COPY");
+        bucket
+            .copy_object_internal(key, key)
+            .await
+            .map_err(|e| AppError::storage("R2 storage class change failed", e))?;
+
         tracing::info!(
-            "R2 rewrite_to_standard called (no-op): bucket={}, key={}",
+            "R2 storage class changed: bucket={}, key={}, class={}",
             self.bucket_name,
-            key
+            key,
+            storage_class
         );
         Ok(())
     }
 
+    fn cold_storage_class(&self) -> &'static str {
+        "STANDARD_IA"
+    }
+
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> AppResult<String> {
+        self.bucket
+            .presign_get(key, expires_in.as_secs() as u32, None)
+            .await
+            .map_err(|e| AppError::storage("R2 signed URL failed", e))
+    }
+
+    async fn signed_upload_url(
+        &self,
+        key: &str,
+        _content_type: &str,
+        expires_in: Duration,
+    ) -> AppResult<String> {
+        self.bucket
+            .presign_put(key, expires_in.as_secs() as u32, None, None)
+            .await
+            .map_err(|e| AppError::storage("R2 signed upload URL failed", e))
+    }
+
+    async fn create_multipart_upload(&self, key: &str, content_type: &str) -> AppResult<String> {
+        let response = self
+            .bucket
+            .initiate_multipart_upload(key, content_type)
+            .await
+            .map_err(|e| AppError::storage("R2 multipart upload failed", e))?;
+
+        tracing::info!("R2 multipart upload started: bucket={}, key={}", self.bucket_name, key);
+        Ok(response.upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        session_id: &str,
+        part_number: u32,
+        _offset: u64,
+        data: Vec<u8>,
+        _total_size: Option<u64>,
+    ) -> AppResult<UploadedPart> {
+        // Content-Type は initiate 時のものが使われる
+        let part = self
+            .bucket
+            .put_multipart_chunk(data, key, part_number, session_id, "application/octet-stream")
+            .await
+            .map_err(|e| AppError::storage("R2 upload part failed", e))?;
+
+        Ok(UploadedPart {
+            part_number: part.part_number,
+            etag: part.etag,
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        session_id: &str,
+        parts: &[UploadedPart],
+    ) -> AppResult<()> {
+        let parts = parts
+            .iter()
+            .map(|p| Part {
+                part_number: p.part_number,
+                etag: p.etag.clone(),
+            })
+            .collect();
+        self.bucket
+            .complete_multipart_upload(key, session_id, parts)
+            .await
+            .map_err(|e| AppError::storage("R2 complete multipart upload failed", e))?;
+
+        tracing::info!("R2 multipart upload completed: bucket={}, key={}", self.bucket_name, key);
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(&self, key: &str, session_id: &str) -> AppResult<()> {
+        self.bucket
+            .abort_upload(key, session_id)
+            .await
+            .map_err(|e| AppError::storage("R2 abort multipart upload failed", e))?;
+
+        tracing::info!("R2 multipart upload aborted: bucket={}, key={}", self.bucket_name, key);
+        Ok(())
+    }
+
     fn bucket(&self) -> &str {
         &self.bucket_name
     }
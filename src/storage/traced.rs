@@ -0,0 +1,162 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tracing::Instrument;
+
+use crate::error::AppResult;
+
+use super::{ByteStream, ObjectInfo, StorageBackend, UploadedPart};
+
+/// 各操作を span で囲むバックエンド（create_backend が包む。OTLP exporter があれば送られる）
+pub struct TracedStorage<B> {
+    inner: B,
+}
+
+impl<B: StorageBackend> TracedStorage<B> {
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    fn span(&self, operation: &'static str, key: &str) -> tracing::Span {
+        tracing::info_span!(
+            "storage",
+            otel.name = operation,
+            otel.kind = "client",
+            otel.status_code = tracing::field::Empty,
+            storage.bucket = %self.inner.bucket(),
+            storage.key = %key,
+            storage.bytes = tracing::field::Empty,
+        )
+    }
+}
+
+/// span の中で実行し、失敗したら span をエラーにする
+async fn traced<T>(span: tracing::Span, future: impl Future<Output = AppResult<T>>) -> AppResult<T> {
+    let result = future.instrument(span.clone()).await;
+    if result.is_err() {
+        span.record("otel.status_code", "ERROR");
+    }
+    result
+}
+
+#[tonic::async_trait]
+impl<B: StorageBackend> StorageBackend for TracedStorage<B> {
+    async fn upload(&self, key: &str, data: &[u8], content_type: &str) -> AppResult<String> {
+        let span = self.span("storage.upload", key);
+        span.record("storage.bytes", data.len());
+        traced(span, self.inner.upload(key, data, content_type)).await
+    }
+
+    async fn download(&self, key: &str) -> AppResult<Vec<u8>> {
+        traced(self.span("storage.download", key), self.inner.download(key)).await
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        traced(self.span("storage.delete", key), self.inner.delete(key)).await
+    }
+
+    async fn get_object_info(&self, key: &str) -> AppResult<ObjectInfo> {
+        traced(self.span("storage.get_object_info", key), self.inner.get_object_info(key)).await
+    }
+
+    async fn rewrite_to_standard(&self, key: &str) -> AppResult<()> {
+        traced(self.span("storage.rewrite_to_standard", key), self.inner.rewrite_to_standard(key)).await
+    }
+
+    async fn rewrite_storage_class(&self, key: &str, storage_class: &str) -> AppResult<()> {
+        traced(
+            self.span("storage.rewrite_storage_class", key),
+            self.inner.rewrite_storage_class(key, storage_class),
+        )
+        .await
+    }
+
+    fn cold_storage_class(&self) -> &'static str {
+        self.inner.cold_storage_class()
+    }
+
+    async fn probe(&self) -> AppResult<()> {
+        traced(self.span("storage.probe", ""), self.inner.probe()).await
+    }
+
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> AppResult<String> {
+        traced(self.span("storage.signed_url", key), self.inner.signed_url(key, expires_in)).await
+    }
+
+    async fn signed_upload_url(
+        &self,
+        key: &str,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> AppResult<String> {
+        traced(
+            self.span("storage.signed_upload_url", key),
+            self.inner.signed_upload_url(key, content_type, expires_in),
+        )
+        .await
+    }
+
+    async fn create_multipart_upload(&self, key: &str, content_type: &str) -> AppResult<String> {
+        traced(
+            self.span("storage.create_multipart_upload", key),
+            self.inner.create_multipart_upload(key, content_type),
+        )
+        .await
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        session_id: &str,
+        part_number: u32,
+        offset: u64,
+        data: Vec<u8>,
+        total_size: Option<u64>,
+    ) -> AppResult<UploadedPart> {
+        let span = self.span("storage.upload_part", key);
+        span.record("storage.bytes", data.len());
+        traced(
+            span,
+            self.inner.upload_part(key, session_id, part_number, offset, data, total_size),
+        )
+        .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        session_id: &str,
+        parts: &[UploadedPart],
+    ) -> AppResult<()> {
+        traced(
+            self.span("storage.complete_multipart_upload", key),
+            self.inner.complete_multipart_upload(key, session_id, parts),
+        )
+        .await
+    }
+
+    async fn abort_multipart_upload(&self, key: &str, session_id: &str) -> AppResult<()> {
+        traced(
+            self.span("storage.abort_multipart_upload", key),
+            self.inner.abort_multipart_upload(key, session_id),
+        )
+        .await
+    }
+
+    async fn upload_stream(&self, key: &str, data: ByteStream, content_type: &str) -> AppResult<u64> {
+        traced(
+            self.span("storage.upload_stream", key),
+            self.inner.upload_stream(key, data, content_type),
+        )
+        .await
+    }
+
+    async fn download_stream(&self, key: &str) -> AppResult<ByteStream> {
+        // 本文を読む時間は含まない（開くまで）
+        traced(self.span("storage.download_stream", key), self.inner.download_stream(key)).await
+    }
+
+    fn bucket(&self) -> &str {
+        self.inner.bucket()
+    }
+}
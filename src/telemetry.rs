@@ -0,0 +1,109 @@
+// OpenTelemetry trace export (OTLP/gRPC)
+//
+// OTEL_EXPORTER_OTLP_ENDPOINT を設定したときだけ tracing の span を collector に送る。
+// リクエストの span は受信した traceparent の呼び出し元を親にし（middleware::trace_context）、
+// 外部呼び出しの traceparent は実行中の span を親にする。SQL は sqlx::query の event として各 span に付く。
+
+use opentelemetry::trace::{
+    SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState, TracerProvider as _,
+};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{Config as TraceConfig, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+const DEFAULT_SERVICE_NAME: &str = "rust-logi";
+
+/// collector に送る span / event（OTEL_TRACES_FILTER で変更。sqlx::query は SQL 文と所要時間）
+const DEFAULT_TRACES_FILTER: &str = "rust_logi=info,sqlx::query=debug";
+
+/// OTLP exporter（drop 前に shutdown で送り残しを送る）
+pub struct Telemetry {
+    provider: TracerProvider,
+}
+
+impl Telemetry {
+    /// OTEL_EXPORTER_OTLP_ENDPOINT があれば exporter を作る（サービス名は OTEL_SERVICE_NAME、既定 rust-logi）
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Some(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .filter(|v| !v.is_empty())
+        else {
+            return Ok(None);
+        };
+        let service_name = std::env::var("OTEL_SERVICE_NAME")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string());
+
+        let provider = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+            .with_trace_config(
+                TraceConfig::default()
+                    .with_resource(Resource::new(vec![KeyValue::new("service.name", service_name)])),
+            )
+            .install_batch(runtime::Tokio)?;
+        Ok(Some(Self { provider }))
+    }
+
+    /// tracing に足す layer
+    pub fn layer<S>(&self) -> impl Layer<S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let filter = EnvFilter::try_from_env("OTEL_TRACES_FILTER")
+            .unwrap_or_else(|_| DEFAULT_TRACES_FILTER.into());
+        tracing_opentelemetry::layer()
+            .with_tracer(self.provider.tracer(DEFAULT_SERVICE_NAME))
+            .with_filter(filter)
+    }
+
+    /// 送り残した span を送って止める
+    pub fn shutdown(self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!("Failed to shut down OpenTelemetry exporter: {}", e);
+        }
+    }
+}
+
+/// span の (trace_id, span_id, sampled)（exporter がないときや span が無効なら None）
+fn span_ids(span: &tracing::Span) -> Option<(u128, u64, bool)> {
+    let cx = span.context();
+    let span_context = cx.span().span_context().clone();
+    span_context.is_valid().then(|| {
+        (
+            u128::from_be_bytes(span_context.trace_id().to_bytes()),
+            u64::from_be_bytes(span_context.span_id().to_bytes()),
+            span_context.is_sampled(),
+        )
+    })
+}
+
+/// 受信した traceparent（trace_id, 呼び出し元の span_id, sampled）を span の親にし、span の ID を返す
+pub fn link_remote_parent(
+    span: &tracing::Span,
+    parent: Option<(u128, u64, bool)>,
+) -> Option<(u128, u64, bool)> {
+    if let Some((trace_id, span_id, sampled)) = parent {
+        let flags = if sampled { TraceFlags::SAMPLED } else { TraceFlags::default() };
+        let parent_context = SpanContext::new(
+            TraceId::from_bytes(trace_id.to_be_bytes()),
+            SpanId::from_bytes(span_id.to_be_bytes()),
+            flags,
+            true,
+            TraceState::default(),
+        );
+        span.set_parent(opentelemetry::Context::new().with_remote_span_context(parent_context));
+    }
+    span_ids(span)
+}
+
+/// 実行中の span の ID（外部呼び出しの traceparent の親）
+pub fn current_span_ids() -> Option<(u128, u64, bool)> {
+    span_ids(&tracing::Span::current())
+}
@@ -0,0 +1,75 @@
+use encoding_rs::SHIFT_JIS;
+
+/// CSV 等の文字コード（Excel / 業務システムの出力は Shift_JIS が多い）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// BOM 付き UTF-8 → UTF-8 → Shift_JIS の順に判定
+    Auto,
+    Utf8,
+    ShiftJis,
+}
+
+impl TextEncoding {
+    /// リクエストの指定（"" / "auto" / "utf-8" / "shift_jis"）
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "" | "auto" => Ok(Self::Auto),
+            "utf8" | "utf_8" => Ok(Self::Utf8),
+            "shift_jis" | "sjis" | "cp932" | "windows_31j" => Ok(Self::ShiftJis),
+            other => Err(format!("Unknown encoding: {} (available: auto, utf-8, shift_jis)", other)),
+        }
+    }
+}
+
+/// バイト列を文字列にする（BOM は除く）
+pub fn decode_text(bytes: &[u8], encoding: TextEncoding) -> Result<String, String> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match encoding {
+        TextEncoding::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|e| format!("Invalid UTF-8: {}", e)),
+        TextEncoding::ShiftJis => {
+            let (text, _, had_errors) = SHIFT_JIS.decode(bytes);
+            if had_errors {
+                return Err("Invalid Shift_JIS text".to_string());
+            }
+            Ok(text.into_owned())
+        }
+        TextEncoding::Auto => match std::str::from_utf8(bytes) {
+            Ok(text) => Ok(text.to_string()),
+            Err(_) => decode_text(bytes, TextEncoding::ShiftJis),
+        },
+    }
+}
+
+/// 文字列をバイト列にする（UTF-8 は Excel 向けに BOM を付ける）。Shift_JIS にない文字は "?" になる
+pub fn encode_text(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    match encoding {
+        TextEncoding::ShiftJis => {
+            let (bytes, _, _) = SHIFT_JIS.encode(text);
+            bytes.into_owned()
+        }
+        TextEncoding::Utf8 | TextEncoding::Auto => {
+            let mut bytes = b"\xEF\xBB\xBF".to_vec();
+            bytes.extend_from_slice(text.as_bytes());
+            bytes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text_detects_encoding() {
+        let sjis = encode_text("通行料金,車両番号", TextEncoding::ShiftJis);
+        assert_eq!(decode_text(&sjis, TextEncoding::Auto).unwrap(), "通行料金,車両番号");
+
+        let utf8 = encode_text("通行料金", TextEncoding::Utf8);
+        assert!(utf8.starts_with(b"\xEF\xBB\xBF"));
+        assert_eq!(decode_text(&utf8, TextEncoding::Auto).unwrap(), "通行料金");
+        assert!(decode_text(&sjis, TextEncoding::Utf8).is_err());
+
+        assert_eq!(TextEncoding::parse("Shift-JIS").unwrap(), TextEncoding::ShiftJis);
+        assert!(TextEncoding::parse("euc-jp").is_err());
+    }
+}
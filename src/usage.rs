@@ -0,0 +1,75 @@
+// Per-organization storage usage and quota (OrganizationService.GetUsage, FilesService の上限確認)
+//
+// 保存量は organization_storage_usage（migration 00082）を files のトリガーが増減する。
+// 上限の確認はアップロード前の概算で、同時に複数アップロードすると少し超えることがある。
+
+use sqlx::PgConnection;
+
+use crate::error::AppError;
+
+/// 組織の保存量と上限
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    pub storage_bytes: i64,
+    pub file_count: i64,
+    /// None なら無制限
+    pub quota_bytes: Option<i64>,
+}
+
+impl StorageUsage {
+    /// additional_bytes を追加すると上限を超えるか
+    pub fn exceeds_quota(&self, additional_bytes: i64) -> bool {
+        self.quota_bytes
+            .is_some_and(|quota| self.storage_bytes.saturating_add(additional_bytes) > quota)
+    }
+}
+
+/// 現在の組織の保存量（組織を設定済みの接続。組織の上限がなければ default_quota）
+pub async fn storage_usage(
+    conn: &mut PgConnection,
+    default_quota: Option<i64>,
+) -> Result<StorageUsage, sqlx::Error> {
+    let row: Option<(i64, i64, Option<i64>)> = sqlx::query_as(
+        "SELECT storage_bytes, file_count, quota_bytes FROM organization_storage_usage",
+    )
+    .fetch_optional(conn)
+    .await?;
+    let (storage_bytes, file_count, quota_bytes) = row.unwrap_or_default();
+    Ok(StorageUsage {
+        storage_bytes,
+        file_count,
+        quota_bytes: quota_bytes.or(default_quota),
+    })
+}
+
+/// additional_bytes を保存できるか確かめる（超えるなら RESOURCE_EXHAUSTED）
+pub async fn ensure_storage_quota(
+    conn: &mut PgConnection,
+    default_quota: Option<i64>,
+    additional_bytes: i64,
+) -> Result<(), AppError> {
+    let usage = storage_usage(conn, default_quota).await?;
+    if usage.exceeds_quota(additional_bytes) {
+        return Err(AppError::ResourceExhausted(format!(
+            "Storage quota exceeded: {} + {} bytes > {} bytes",
+            usage.storage_bytes,
+            additional_bytes,
+            usage.quota_bytes.unwrap_or_default()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exceeds_quota() {
+        let usage = StorageUsage { storage_bytes: 900, file_count: 3, quota_bytes: Some(1000) };
+        assert!(!usage.exceeds_quota(100));
+        assert!(usage.exceeds_quota(101));
+        assert!(!StorageUsage { quota_bytes: None, ..usage }.exceeds_quota(i64::MAX));
+        assert!(usage.exceeds_quota(i64::MAX));
+    }
+}
@@ -0,0 +1,156 @@
+// BigQuery streaming insert (tabledata.insertAll)
+//
+// テーブルは {dataset}.{table_prefix}{source}（事前に作成しておく）。列は
+// organization_id STRING, row_key STRING, ts TIMESTAMP, exported_at TIMESTAMP, data JSON（または STRING）。
+// アクセストークンは Cloud Run / GCE のメタデータサーバーから取得し、期限まで使い回す。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use super::{Source, WarehouseRow, WarehouseSink};
+use crate::http_client::HttpClient;
+
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const BIGQUERY_API_URL: &str = "https://bigquery.googleapis.com/bigquery/v2";
+/// insertAll 1リクエストあたりの行数（推奨は 500 行まで）
+const INSERT_CHUNK: usize = 500;
+/// 期限切れ直前のトークンは使わない
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InsertAllResponse {
+    #[serde(default)]
+    insert_errors: Vec<serde_json::Value>,
+}
+
+pub struct BigQuerySink {
+    http_client: Arc<HttpClient>,
+    project: String,
+    dataset: String,
+    table_prefix: String,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl BigQuerySink {
+    pub fn new(http_client: Arc<HttpClient>, project: String, dataset: String, table_prefix: String) -> Self {
+        Self {
+            http_client,
+            project,
+            dataset,
+            table_prefix,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn access_token(&self) -> anyhow::Result<String> {
+        let mut cached = self.token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + TOKEN_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+        let fetched: MetadataToken = self
+            .http_client
+            .get_json_with_headers(METADATA_TOKEN_URL, &[("Metadata-Flavor", "Google")])
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get access token from metadata server: {}", e))?;
+        let expires_at = Instant::now() + Duration::from_secs(fetched.expires_in);
+        *cached = Some((fetched.access_token.clone(), expires_at));
+        Ok(fetched.access_token)
+    }
+
+    fn insert_all_url(&self, source: Source) -> String {
+        format!(
+            "{}/projects/{}/datasets/{}/tables/{}{}/insertAll",
+            BIGQUERY_API_URL,
+            self.project,
+            self.dataset,
+            self.table_prefix,
+            source.as_str()
+        )
+    }
+}
+
+/// insertAll の本文（insertId で BigQuery 側の重複排除をさせる）
+fn insert_all_body(organization_id: &str, source: Source, rows: &[WarehouseRow]) -> serde_json::Value {
+    let exported_at = Utc::now().to_rfc3339();
+    let rows: Vec<serde_json::Value> = rows
+        .iter()
+        .map(|row| {
+            serde_json::json!({
+                "insertId": row.insert_id(organization_id, source),
+                "json": {
+                    "organization_id": organization_id,
+                    "row_key": row.row_key,
+                    "ts": row.ts.to_rfc3339(),
+                    "exported_at": exported_at,
+                    "data": row.data.to_string(),
+                },
+            })
+        })
+        .collect();
+    serde_json::json!({ "skipInvalidRows": false, "ignoreUnknownValues": false, "rows": rows })
+}
+
+#[tonic::async_trait]
+impl WarehouseSink for BigQuerySink {
+    fn name(&self) -> &'static str {
+        "bigquery"
+    }
+
+    async fn write(&self, organization_id: &str, source: Source, rows: &[WarehouseRow]) -> anyhow::Result<()> {
+        let url = self.insert_all_url(source);
+        for chunk in rows.chunks(INSERT_CHUNK) {
+            let token = self.access_token().await?;
+            let body = insert_all_body(organization_id, source, chunk);
+            let response = self.http_client.post_json_with_bearer(&url, &token, &body).await?;
+            let status = response.status();
+            if !status.is_success() {
+                let text = response.text().await.unwrap_or_default();
+                anyhow::bail!("BigQuery insertAll returned {}: {}", status, text);
+            }
+            let result: InsertAllResponse = response.json().await?;
+            if let Some(first) = result.insert_errors.first() {
+                anyhow::bail!(
+                    "BigQuery insertAll rejected {} rows (first: {})",
+                    result.insert_errors.len(),
+                    first
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_insert_all_body() {
+        let rows = vec![WarehouseRow {
+            ts: Utc.with_ymd_and_hms(2026, 4, 1, 3, 0, 0).unwrap(),
+            row_key: "2026-04-01T12:00:00#101".to_string(),
+            data: serde_json::json!({"vehicle_cd": 101}),
+        }];
+        let body = insert_all_body("org-1", Source::Dtakologs, &rows);
+        let row = &body["rows"][0];
+        assert_eq!(row["insertId"], rows[0].insert_id("org-1", Source::Dtakologs));
+        assert_eq!(row["json"]["organization_id"], "org-1");
+        assert_eq!(row["json"]["ts"], "2026-04-01T03:00:00+00:00");
+        assert_eq!(row["json"]["data"], r#"{"vehicle_cd":101}"#);
+    }
+}
@@ -0,0 +1,346 @@
+// Data warehouse export
+//
+// 運行ログ・車検証・イベント（outbox）を分析用のデータウェアハウスへ差分エクスポートする。
+// 出力先は WAREHOUSE_SINK で切り替え（bigquery: streaming insert / avro: ストレージに Avro ファイル）。
+// 位置は warehouse_export_state に組織 × ソースごとに保持し、書き込み成功後にバッチごとに進める（at-least-once）。
+// 分析のクエリを本番の Postgres で流さないためのもの。
+
+pub mod avro;
+pub mod bigquery;
+pub mod parquet_file;
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+
+pub use avro::AvroSink;
+pub use bigquery::BigQuerySink;
+pub use parquet_file::DtakologParquetWriter;
+
+use crate::config::WarehouseConfig;
+use crate::db::OrgScopedPool;
+use crate::http_client::HttpClient;
+use crate::jobs::{Job, JobHandler, ScheduledTaskDef};
+use crate::storage::StorageBackend;
+
+/// 前回の続きからエクスポートする
+pub const WAREHOUSE_EXPORT_JOB: &str = "warehouse.export";
+
+pub const WAREHOUSE_EXPORT_TASK: ScheduledTaskDef = ScheduledTaskDef {
+    name: WAREHOUSE_EXPORT_JOB,
+    description: "運行ログ・車検証・イベントの前回以降の差分をデータウェアハウス（BigQuery / Avro）に書き出す",
+    default_cron: "*/15 * * * *",
+};
+
+/// 1回に読み出す行数
+const BATCH_ROWS: i64 = 5000;
+/// 1回の job でソースごとに処理するバッチ数の上限（残りは次回）
+const MAX_BATCHES_PER_RUN: usize = 20;
+/// 書き込み中のトランザクションを取りこぼさないよう、直近の行は次回に回す
+const SETTLE_SECONDS: i64 = 60;
+
+/// エクスポート対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// 運行ログ（作成順）
+    Dtakologs,
+    /// 車検証（更新順、更新された行は再度出力する）
+    CarInspection,
+    /// outbox のイベント（作成順）
+    Events,
+}
+
+impl Source {
+    pub const ALL: [Source; 3] = [Source::Dtakologs, Source::CarInspection, Source::Events];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Dtakologs => "dtakologs",
+            Source::CarInspection => "car_inspection",
+            Source::Events => "events",
+        }
+    }
+
+    /// (ts, row_key) が cursor より後の行を古い順に読む
+    ///
+    /// $1 = cursor_ts（NULL なら先頭から）, $2 = cursor_key, $3 = 件数, $4 = 直近の除外秒数
+    fn query(&self) -> &'static str {
+        match self {
+            Source::Dtakologs => {
+                r#"
+                SELECT t.ts, t.row_key, t.data FROM (
+                    SELECT d.created_at AS ts,
+                           d.data_date_time || '#' || d.vehicle_cd::text AS row_key,
+                           to_jsonb(d) - 'organization_id' AS data
+                    FROM dtakologs d
+                ) t
+                WHERE t.ts < NOW() - make_interval(secs => $4)
+                  AND ($1::timestamptz IS NULL OR (t.ts, t.row_key) > ($1, $2))
+                ORDER BY t.ts, t.row_key
+                LIMIT $3
+                "#
+            }
+            Source::CarInspection => {
+                r#"
+                SELECT t.ts, t.row_key, t.data FROM (
+                    SELECT c.modified_at AS ts,
+                           lpad(c.id::text, 12, '0') AS row_key,
+                           to_jsonb(c) - 'organization_id' AS data
+                    FROM car_inspection c
+                ) t
+                WHERE t.ts < NOW() - make_interval(secs => $4)
+                  AND ($1::timestamptz IS NULL OR (t.ts, t.row_key) > ($1, $2))
+                ORDER BY t.ts, t.row_key
+                LIMIT $3
+                "#
+            }
+            Source::Events => {
+                r#"
+                SELECT t.ts, t.row_key, t.data FROM (
+                    SELECT o.created_at AS ts,
+                           lpad(o.id::text, 20, '0') AS row_key,
+                           jsonb_build_object(
+                               'id', o.id,
+                               'target', o.target,
+                               'event_type', o.event_type,
+                               'payload', o.payload
+                           ) AS data
+                    FROM outbox o
+                ) t
+                WHERE t.ts < NOW() - make_interval(secs => $4)
+                  AND ($1::timestamptz IS NULL OR (t.ts, t.row_key) > ($1, $2))
+                ORDER BY t.ts, t.row_key
+                LIMIT $3
+                "#
+            }
+        }
+    }
+}
+
+/// ウェアハウスに書く1行（ソースの行全体は data に JSON で入れる）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WarehouseRow {
+    pub ts: DateTime<Utc>,
+    pub row_key: String,
+    pub data: serde_json::Value,
+}
+
+impl WarehouseRow {
+    /// 出力先での重複排除キー（更新された車検証は ts が変わるので別の行になる）
+    pub fn insert_id(&self, organization_id: &str, source: Source) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            source.as_str(),
+            organization_id,
+            self.row_key,
+            self.ts.timestamp_micros()
+        )
+    }
+}
+
+/// エクスポート先
+#[tonic::async_trait]
+pub trait WarehouseSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 全行を書き込めたときだけ Ok（失敗したら同じ行を次回もう一度渡す）
+    async fn write(&self, organization_id: &str, source: Source, rows: &[WarehouseRow]) -> anyhow::Result<()>;
+}
+
+/// WAREHOUSE_SINK からエクスポート先を作る（avro はストレージが必要）
+pub fn create_sink(
+    config: &WarehouseConfig,
+    http_client: Arc<HttpClient>,
+    storage: Option<Arc<dyn StorageBackend>>,
+) -> anyhow::Result<Arc<dyn WarehouseSink>> {
+    match config {
+        WarehouseConfig::BigQuery {
+            project,
+            dataset,
+            table_prefix,
+        } => Ok(Arc::new(BigQuerySink::new(
+            http_client,
+            project.clone(),
+            dataset.clone(),
+            table_prefix.clone(),
+        ))),
+        WarehouseConfig::Avro { prefix } => {
+            let storage = storage
+                .ok_or_else(|| anyhow::anyhow!("WAREHOUSE_SINK=avro requires STORAGE_BACKEND (GCS / R2)"))?;
+            Ok(Arc::new(AvroSink::new(storage, prefix.clone())?))
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ExportCursor {
+    cursor_ts: Option<DateTime<Utc>>,
+    cursor_key: String,
+}
+
+pub struct WarehouseExporter {
+    pool: PgPool,
+    sink: Arc<dyn WarehouseSink>,
+}
+
+impl WarehouseExporter {
+    pub fn new(pool: PgPool, sink: Arc<dyn WarehouseSink>) -> Self {
+        Self { pool, sink }
+    }
+
+    /// 組織の全ソースを前回の続きから書き出す（書き出した行数）
+    pub async fn export(&self, organization_id: &str) -> anyhow::Result<u64> {
+        let mut conn = self.pool.acquire_scoped(organization_id).await?;
+
+        let mut total = 0;
+        let mut first_error = None;
+        for source in Source::ALL {
+            match self.export_source(&mut conn, organization_id, source).await {
+                Ok(exported) => total += exported,
+                Err(e) => {
+                    tracing::warn!(
+                        "Warehouse export of {} failed for organization {}: {}",
+                        source.as_str(),
+                        organization_id,
+                        e
+                    );
+                    let _ = sqlx::query(
+                        r#"
+                        INSERT INTO warehouse_export_state (organization_id, source, last_error)
+                        VALUES ($1::uuid, $2, $3)
+                        ON CONFLICT (organization_id, source) DO UPDATE
+                            SET last_error = EXCLUDED.last_error, updated_at = NOW()
+                        "#,
+                    )
+                    .bind(organization_id)
+                    .bind(source.as_str())
+                    .bind(e.to_string())
+                    .execute(&mut *conn)
+                    .await;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            // job を失敗にしてリトライさせる（成功したソースの位置は進んでいる）
+            Some(e) => Err(e),
+            None => Ok(total),
+        }
+    }
+
+    async fn export_source(
+        &self,
+        conn: &mut PgConnection,
+        organization_id: &str,
+        source: Source,
+    ) -> anyhow::Result<u64> {
+        let cursor: Option<ExportCursor> = sqlx::query_as(
+            "SELECT cursor_ts, cursor_key FROM warehouse_export_state WHERE source = $1",
+        )
+        .bind(source.as_str())
+        .fetch_optional(&mut *conn)
+        .await?;
+        let (mut cursor_ts, mut cursor_key) = match cursor {
+            Some(c) => (c.cursor_ts, c.cursor_key),
+            None => (None, String::new()),
+        };
+
+        let mut exported = 0;
+        for _ in 0..MAX_BATCHES_PER_RUN {
+            let rows: Vec<WarehouseRow> = sqlx::query_as(source.query())
+                .bind(cursor_ts)
+                .bind(&cursor_key)
+                .bind(BATCH_ROWS)
+                .bind(SETTLE_SECONDS as f64)
+                .fetch_all(&mut *conn)
+                .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+
+            self.sink.write(organization_id, source, &rows).await?;
+            cursor_ts = Some(last.ts);
+            cursor_key = last.row_key.clone();
+            exported += rows.len() as u64;
+
+            sqlx::query(
+                r#"
+                INSERT INTO warehouse_export_state
+                    (organization_id, source, cursor_ts, cursor_key, exported_rows, last_exported_at)
+                VALUES ($1::uuid, $2, $3, $4, $5, NOW())
+                ON CONFLICT (organization_id, source) DO UPDATE
+                    SET cursor_ts = EXCLUDED.cursor_ts,
+                        cursor_key = EXCLUDED.cursor_key,
+                        exported_rows = warehouse_export_state.exported_rows + EXCLUDED.exported_rows,
+                        last_exported_at = NOW(),
+                        last_error = NULL,
+                        updated_at = NOW()
+                "#,
+            )
+            .bind(organization_id)
+            .bind(source.as_str())
+            .bind(cursor_ts)
+            .bind(&cursor_key)
+            .bind(rows.len() as i64)
+            .execute(&mut *conn)
+            .await?;
+
+            if (rows.len() as i64) < BATCH_ROWS {
+                break;
+            }
+        }
+        Ok(exported)
+    }
+}
+
+pub struct WarehouseExportJobHandler {
+    exporter: Arc<WarehouseExporter>,
+}
+
+impl WarehouseExportJobHandler {
+    pub fn new(exporter: Arc<WarehouseExporter>) -> Self {
+        Self { exporter }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for WarehouseExportJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let exported = self.exporter.export(&job.organization_id).await?;
+        if exported > 0 {
+            tracing::info!(
+                "Exported {} rows to {} ({})",
+                exported,
+                self.exporter.sink.name(),
+                job.organization_id
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_insert_id_changes_with_ts() {
+        let row = WarehouseRow {
+            ts: Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap(),
+            row_key: "000000000042".to_string(),
+            data: serde_json::json!({"id": 42}),
+        };
+        let org = "00000000-0000-0000-0000-000000000001";
+        let first = row.insert_id(org, Source::CarInspection);
+        assert!(first.starts_with("car_inspection:00000000-0000-0000-0000-000000000001:000000000042:"));
+
+        let updated = WarehouseRow {
+            ts: row.ts + chrono::Duration::seconds(1),
+            ..row.clone()
+        };
+        assert_ne!(first, updated.insert_id(org, Source::CarInspection));
+        assert_ne!(first, row.insert_id(org, Source::Events));
+    }
+}
@@ -0,0 +1,194 @@
+// Parquet files of dtakologs (ExportDtakologsParquet)
+//
+// 列は dtakologs と同じ名前・型（type は dtako_type ではなく type）に、data_date_time を
+// 解釈した recorded_at（TIMESTAMP、UTC）を先頭に加える。Snappy 圧縮。
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float32Array, Int32Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use chrono::DateTime;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::Result;
+use parquet::file::properties::WriterProperties;
+
+use crate::models::DtakologModel;
+
+/// data_date_time（ISO 8601、+09:00 付き）を UTC のマイクロ秒に（解釈できなければ NULL）
+fn recorded_at(data_date_time: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(data_date_time)
+        .ok()
+        .map(|t| t.timestamp_micros())
+}
+
+fn record_batch(rows: &[DtakologModel]) -> Result<RecordBatch> {
+    let int = |f: fn(&DtakologModel) -> i32| -> ArrayRef {
+        Arc::new(Int32Array::from_iter_values(rows.iter().map(f)))
+    };
+    let text = |f: fn(&DtakologModel) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(rows.iter().map(f)))
+    };
+    let opt = |f: fn(&DtakologModel) -> Option<&str>| -> ArrayRef {
+        Arc::new(StringArray::from_iter(rows.iter().map(f)))
+    };
+    let recorded_at: ArrayRef = Arc::new(
+        TimestampMicrosecondArray::from_iter(rows.iter().map(|r| recorded_at(&r.data_date_time)))
+            .with_timezone("UTC"),
+    );
+    let speed: ArrayRef = Arc::new(Float32Array::from_iter_values(rows.iter().map(|r| r.speed)));
+
+    Ok(RecordBatch::try_from_iter_with_nullable(vec![
+        ("recorded_at", recorded_at, true),
+        ("data_date_time", text(|r| r.data_date_time.as_str()), false),
+        ("vehicle_cd", int(|r| r.vehicle_cd), false),
+        ("type", text(|r| r.dtako_type.as_str()), false),
+        ("all_state_font_color_index", int(|r| r.all_state_font_color_index), false),
+        ("all_state_ryout_color", text(|r| r.all_state_ryout_color.as_str()), false),
+        ("branch_cd", int(|r| r.branch_cd), false),
+        ("branch_name", text(|r| r.branch_name.as_str()), false),
+        ("current_work_cd", int(|r| r.current_work_cd), false),
+        ("data_filter_type", int(|r| r.data_filter_type), false),
+        ("disp_flag", int(|r| r.disp_flag), false),
+        ("driver_cd", int(|r| r.driver_cd), false),
+        ("gps_direction", int(|r| r.gps_direction), false),
+        ("gps_enable", int(|r| r.gps_enable), false),
+        ("gps_latitude", int(|r| r.gps_latitude), false),
+        ("gps_longitude", int(|r| r.gps_longitude), false),
+        ("gps_satellite_num", int(|r| r.gps_satellite_num), false),
+        ("operation_state", int(|r| r.operation_state), false),
+        ("recive_event_type", int(|r| r.recive_event_type), false),
+        ("recive_packet_type", int(|r| r.recive_packet_type), false),
+        ("recive_work_cd", int(|r| r.recive_work_cd), false),
+        ("revo", int(|r| r.revo), false),
+        ("setting_temp", text(|r| r.setting_temp.as_str()), false),
+        ("setting_temp1", text(|r| r.setting_temp1.as_str()), false),
+        ("setting_temp3", text(|r| r.setting_temp3.as_str()), false),
+        ("setting_temp4", text(|r| r.setting_temp4.as_str()), false),
+        ("speed", speed, false),
+        ("sub_driver_cd", int(|r| r.sub_driver_cd), false),
+        ("temp_state", int(|r| r.temp_state), false),
+        ("vehicle_name", text(|r| r.vehicle_name.as_str()), false),
+        ("address_disp_c", opt(|r| r.address_disp_c.as_deref()), true),
+        ("address_disp_p", opt(|r| r.address_disp_p.as_deref()), true),
+        ("all_state", opt(|r| r.all_state.as_deref()), true),
+        ("all_state_ex", opt(|r| r.all_state_ex.as_deref()), true),
+        ("all_state_font_color", opt(|r| r.all_state_font_color.as_deref()), true),
+        ("comu_date_time", opt(|r| r.comu_date_time.as_deref()), true),
+        ("current_work_name", opt(|r| r.current_work_name.as_deref()), true),
+        ("driver_name", opt(|r| r.driver_name.as_deref()), true),
+        ("event_val", opt(|r| r.event_val.as_deref()), true),
+        ("gps_lati_and_long", opt(|r| r.gps_lati_and_long.as_deref()), true),
+        ("odometer", opt(|r| r.odometer.as_deref()), true),
+        ("recive_type_color_name", opt(|r| r.recive_type_color_name.as_deref()), true),
+        ("recive_type_name", opt(|r| r.recive_type_name.as_deref()), true),
+        ("start_work_date_time", opt(|r| r.start_work_date_time.as_deref()), true),
+        ("state", opt(|r| r.state.as_deref()), true),
+        ("state1", opt(|r| r.state1.as_deref()), true),
+        ("state2", opt(|r| r.state2.as_deref()), true),
+        ("state3", opt(|r| r.state3.as_deref()), true),
+        ("state_flag", opt(|r| r.state_flag.as_deref()), true),
+        ("temp1", opt(|r| r.temp1.as_deref()), true),
+        ("temp2", opt(|r| r.temp2.as_deref()), true),
+        ("temp3", opt(|r| r.temp3.as_deref()), true),
+        ("temp4", opt(|r| r.temp4.as_deref()), true),
+        ("vehicle_icon_color", opt(|r| r.vehicle_icon_color.as_deref()), true),
+        (
+            "vehicle_icon_label_for_datetime",
+            opt(|r| r.vehicle_icon_label_for_datetime.as_deref()),
+            true,
+        ),
+        (
+            "vehicle_icon_label_for_driver",
+            opt(|r| r.vehicle_icon_label_for_driver.as_deref()),
+            true,
+        ),
+        (
+            "vehicle_icon_label_for_vehicle",
+            opt(|r| r.vehicle_icon_label_for_vehicle.as_deref()),
+            true,
+        ),
+    ])?)
+}
+
+/// 1ファイル分の Parquet をメモリ上に書く（write のたびに1つの row group）
+pub struct DtakologParquetWriter {
+    writer: ArrowWriter<Vec<u8>>,
+    rows: usize,
+}
+
+impl DtakologParquetWriter {
+    pub fn new() -> Result<Self> {
+        let schema = record_batch(&[])?.schema();
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        Ok(Self {
+            writer: ArrowWriter::try_new(Vec::new(), schema, Some(properties))?,
+            rows: 0,
+        })
+    }
+
+    pub fn write(&mut self, rows: &[DtakologModel]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.writer.write(&record_batch(rows)?)?;
+        self.rows += rows.len();
+        Ok(())
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// フッターを書いてファイルの内容を返す
+    pub fn finish(self) -> Result<Vec<u8>> {
+        self.writer.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn dtakolog(data_date_time: &str, vehicle_cd: i32) -> DtakologModel {
+        serde_json::from_value(serde_json::json!({
+            "data_date_time": data_date_time, "vehicle_cd": vehicle_cd, "dtako_type": "t",
+            "all_state_font_color_index": 0, "all_state_ryout_color": "Transparent",
+            "branch_cd": 1, "branch_name": "本社", "current_work_cd": 0, "data_filter_type": 0,
+            "disp_flag": 0, "driver_cd": 0, "gps_direction": 0, "gps_enable": 1,
+            "gps_latitude": 0, "gps_longitude": 0, "gps_satellite_num": 0, "operation_state": 0,
+            "recive_event_type": 0, "recive_packet_type": 0, "recive_work_cd": 0, "revo": 0,
+            "setting_temp": "", "setting_temp1": "", "setting_temp3": "", "setting_temp4": "",
+            "speed": 42.5, "sub_driver_cd": 0, "temp_state": 0, "vehicle_name": "1号車",
+            "address_disp_c": null, "address_disp_p": null, "all_state": null, "all_state_ex": null,
+            "all_state_font_color": null, "comu_date_time": null, "current_work_name": null,
+            "driver_name": null, "event_val": null, "gps_lati_and_long": null, "odometer": "12345",
+            "recive_type_color_name": null, "recive_type_name": null, "start_work_date_time": null,
+            "state": null, "state1": null, "state2": null, "state3": null, "state_flag": null,
+            "temp1": null, "temp2": null, "temp3": null, "temp4": null, "vehicle_icon_color": null,
+            "vehicle_icon_label_for_datetime": null, "vehicle_icon_label_for_driver": null,
+            "vehicle_icon_label_for_vehicle": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let mut writer = DtakologParquetWriter::new().unwrap();
+        writer
+            .write(&[dtakolog("2026-04-01T09:00:00+09:00", 1), dtakolog("invalid", 2)])
+            .unwrap();
+        writer.write(&[dtakolog("2026-04-01T09:01:00+09:00", 1)]).unwrap();
+        assert_eq!(writer.rows(), 3);
+
+        let data = writer.finish().unwrap();
+        let reader = SerializedFileReader::new(bytes::Bytes::from(data)).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.file_metadata().schema_descr().column(0).name(), "recorded_at");
+        assert_eq!(recorded_at("2026-04-01T09:00:00+09:00"), Some(1_775_001_600_000_000));
+    }
+}
@@ -0,0 +1,326 @@
+// Weather enrichment
+//
+// 事故分析用に、地点・時刻の気象（天気・気温・降水量・風速・視程）を Open-Meteo から取得する。
+// 1 日分の毎時データを weather_cache（約1km 単位 × JST の日付）に保存し、同じ地点・日付は再取得しない
+// （当日分は 1 時間で取り直す）。DVR 通知は dvr.weather_enrich で車両の運行ログの位置から付ける。
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+use crate::config::WeatherConfig;
+use crate::db::OrgScopedPool;
+use crate::geocoding::GeoPoint;
+use crate::http_client::HttpClient;
+use crate::jobs::{Job, JobHandler, NewJob};
+
+/// DVR 通知に発生時刻の気象を付ける
+pub const DVR_WEATHER_JOB: &str = "dvr.weather_enrich";
+
+const HOURLY_VARIABLES: &str = "weather_code,temperature_2m,precipitation,wind_speed_10m,visibility";
+/// これより古い日付は archive API（forecast API は過去 92 日まで）
+const FORECAST_PAST_DAYS: i64 = 90;
+/// 当日分のキャッシュの有効期間
+const TODAY_CACHE_TTL_MINUTES: i64 = 60;
+/// DVR 通知の時刻と運行ログの位置の許容差
+const POSITION_WINDOW_MINUTES: i64 = 60;
+
+/// ある時刻の気象
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherConditions {
+    /// JST（YYYY-MM-DDTHH:00）
+    pub time: String,
+    pub weather_code: i32,
+    pub description: String,
+    pub temperature_c: Option<f64>,
+    pub precipitation_mm: Option<f64>,
+    pub wind_speed_kmh: Option<f64>,
+    pub visibility_m: Option<f64>,
+}
+
+impl WeatherConditions {
+    pub fn to_proto(&self) -> crate::proto::common::WeatherConditions {
+        crate::proto::common::WeatherConditions {
+            time: self.time.clone(),
+            weather_code: self.weather_code,
+            description: self.description.clone(),
+            temperature_c: self.temperature_c,
+            precipitation_mm: self.precipitation_mm,
+            wind_speed_kmh: self.wind_speed_kmh,
+            visibility_m: self.visibility_m,
+        }
+    }
+}
+
+/// WMO 天気コードの表示名
+pub fn describe_weather_code(code: i32) -> &'static str {
+    match code {
+        0 => "快晴",
+        1 => "晴れ",
+        2 => "一部曇り",
+        3 => "曇り",
+        45 | 48 => "霧",
+        51..=57 => "霧雨",
+        61..=67 => "雨",
+        71..=77 => "雪",
+        80..=82 => "にわか雨",
+        85 | 86 => "にわか雪",
+        95..=99 => "雷雨",
+        _ => "不明",
+    }
+}
+
+/// Open-Meteo の hourly（配列はすべて time と同じ長さ）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourlyWeather {
+    pub time: Vec<String>,
+    #[serde(default)]
+    pub weather_code: Vec<Option<i32>>,
+    #[serde(default)]
+    pub temperature_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    pub precipitation: Vec<Option<f64>>,
+    #[serde(default)]
+    pub wind_speed_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    pub visibility: Vec<Option<f64>>,
+}
+
+impl HourlyWeather {
+    /// 指定時刻（JST）を含む 1 時間の気象
+    pub fn at(&self, local: NaiveDateTime) -> Option<WeatherConditions> {
+        let hour = local.with_minute(0)?.with_second(0)?;
+        let key = hour.format("%Y-%m-%dT%H:%M").to_string();
+        let i = self.time.iter().position(|t| *t == key)?;
+        let weather_code = self.weather_code.get(i).copied().flatten()?;
+        let value = |values: &Vec<Option<f64>>| values.get(i).copied().flatten();
+        Some(WeatherConditions {
+            time: key,
+            weather_code,
+            description: describe_weather_code(weather_code).to_string(),
+            temperature_c: value(&self.temperature_2m),
+            precipitation_mm: value(&self.precipitation),
+            wind_speed_kmh: value(&self.wind_speed_10m),
+            visibility_m: value(&self.visibility),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoResponse {
+    hourly: HourlyWeather,
+}
+
+fn jst_today() -> NaiveDate {
+    (Utc::now() + Duration::hours(9)).date_naive()
+}
+
+/// 気象の取得（Open-Meteo + weather_cache）
+pub struct WeatherService {
+    pool: PgPool,
+    http_client: Arc<HttpClient>,
+    config: WeatherConfig,
+}
+
+impl WeatherService {
+    pub fn new(pool: PgPool, http_client: Arc<HttpClient>, config: WeatherConfig) -> Self {
+        Self {
+            pool,
+            http_client,
+            config,
+        }
+    }
+
+    /// 地点の 1 日分（JST）の毎時データ
+    pub async fn hourly(&self, point: GeoPoint, date: NaiveDate) -> anyhow::Result<HourlyWeather> {
+        // 約 1km 単位（1/100 度）で共有する
+        let lat_e2 = (point.lat * 100.0).round() as i32;
+        let lon_e2 = (point.lon * 100.0).round() as i32;
+        let today = jst_today();
+
+        let cached: Option<(sqlx::types::Json<HourlyWeather>, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT hourly, fetched_at FROM weather_cache WHERE lat_e2 = $1 AND lon_e2 = $2 AND date = $3",
+        )
+        .bind(lat_e2)
+        .bind(lon_e2)
+        .bind(date)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some((hourly, fetched_at)) = cached {
+            if date < today || Utc::now() - fetched_at < Duration::minutes(TODAY_CACHE_TTL_MINUTES) {
+                return Ok(hourly.0);
+            }
+        }
+
+        let base_url = if today - date > Duration::days(FORECAST_PAST_DAYS) {
+            &self.config.archive_url
+        } else {
+            &self.config.forecast_url
+        };
+        let url = format!(
+            "{}?latitude={:.2}&longitude={:.2}&hourly={}&start_date={}&end_date={}&timezone=Asia%2FTokyo&wind_speed_unit=kmh",
+            base_url,
+            lat_e2 as f64 / 100.0,
+            lon_e2 as f64 / 100.0,
+            HOURLY_VARIABLES,
+            date,
+            date
+        );
+        let response: OpenMeteoResponse = self.http_client.get_json_with_headers(&url, &[]).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO weather_cache (lat_e2, lon_e2, date, hourly)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (lat_e2, lon_e2, date) DO UPDATE
+                SET hourly = EXCLUDED.hourly, fetched_at = NOW()
+            "#,
+        )
+        .bind(lat_e2)
+        .bind(lon_e2)
+        .bind(date)
+        .bind(sqlx::types::Json(&response.hourly))
+        .execute(&self.pool)
+        .await?;
+        Ok(response.hourly)
+    }
+
+    /// 地点・時刻（JST）の気象。未来やデータ欠損なら None
+    pub async fn conditions_at(
+        &self,
+        point: GeoPoint,
+        local: NaiveDateTime,
+    ) -> anyhow::Result<Option<WeatherConditions>> {
+        if local.date() > jst_today() {
+            return Ok(None);
+        }
+        Ok(self.hourly(point, local.date()).await?.at(local))
+    }
+}
+
+/// 車両の時刻（JST）前後の運行ログから位置を引く
+pub async fn vehicle_position_at(
+    conn: &mut PgConnection,
+    vehicle_cd: i64,
+    local: NaiveDateTime,
+) -> Result<Option<GeoPoint>, sqlx::Error> {
+    let at = format!("{}+09:00", local.format("%Y-%m-%dT%H:%M:%S"));
+    let row: Option<(i32, i32)> = sqlx::query_as(
+        r#"
+        SELECT gps_latitude, gps_longitude FROM dtakologs
+        WHERE vehicle_cd = $1 AND gps_latitude <> 0 AND gps_longitude <> 0
+          AND data_date_time::timestamptz BETWEEN $2::timestamptz - make_interval(mins => $3)
+                                              AND $2::timestamptz + make_interval(mins => $3)
+        ORDER BY abs(extract(epoch FROM data_date_time::timestamptz - $2::timestamptz))
+        LIMIT 1
+        "#,
+    )
+    .bind(vehicle_cd as i32)
+    .bind(&at)
+    .bind(POSITION_WINDOW_MINUTES as i32)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.and_then(|(lat, lon)| GeoPoint::from_dtako(lat, lon)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DvrWeatherPayload {
+    pub mp4_url: String,
+}
+
+impl DvrWeatherPayload {
+    pub fn job(mp4_url: &str) -> NewJob {
+        NewJob::new(DVR_WEATHER_JOB, Self { mp4_url: mp4_url.to_string() })
+            .dedupe_key(mp4_url)
+            .max_attempts(3)
+    }
+}
+
+/// DVR 通知（事故・ヒヤリハット）に発生時刻・地点の気象を付ける
+pub struct DvrWeatherJobHandler {
+    pool: PgPool,
+    weather: Arc<WeatherService>,
+}
+
+impl DvrWeatherJobHandler {
+    pub fn new(pool: PgPool, weather: Arc<WeatherService>) -> Self {
+        Self { pool, weather }
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for DvrWeatherJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: DvrWeatherPayload = job.payload()?;
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+
+        let row: Option<(i64, String)> = sqlx::query_as(
+            "SELECT vehicle_cd, dvr_datetime FROM dvr_notifications WHERE mp4_url = $1 AND weather IS NULL",
+        )
+        .bind(&payload.mp4_url)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some((vehicle_cd, dvr_datetime)) = row else {
+            return Ok(());
+        };
+        let Ok(local) = NaiveDateTime::parse_from_str(&dvr_datetime, "%Y-%m-%d %H:%M:%S") else {
+            tracing::debug!("Unparseable dvr_datetime for weather: {}", dvr_datetime);
+            return Ok(());
+        };
+        let Some(point) = vehicle_position_at(&mut conn, vehicle_cd, local).await? else {
+            tracing::debug!("No position for vehicle {} at {}, skipping weather", vehicle_cd, dvr_datetime);
+            return Ok(());
+        };
+        // 外部 API の呼び出し中はコネクションを返しておく
+        drop(conn);
+
+        let Some(conditions) = self.weather.conditions_at(point, local).await? else {
+            return Ok(());
+        };
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        sqlx::query("UPDATE dvr_notifications SET weather = $1 WHERE mp4_url = $2")
+            .bind(sqlx::types::Json(&conditions))
+            .bind(&payload.mp4_url)
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hourly_weather_at() {
+        let hourly = HourlyWeather {
+            time: vec!["2026-10-16T08:00".to_string(), "2026-10-16T09:00".to_string()],
+            weather_code: vec![Some(3), Some(63)],
+            temperature_2m: vec![Some(14.2), Some(13.8)],
+            precipitation: vec![Some(0.0), Some(4.5)],
+            wind_speed_10m: vec![Some(8.0), None],
+            visibility: vec![],
+        };
+        let local = NaiveDateTime::parse_from_str("2026-10-16 09:42:10", "%Y-%m-%d %H:%M:%S").unwrap();
+        let conditions = hourly.at(local).unwrap();
+        assert_eq!(conditions.time, "2026-10-16T09:00");
+        assert_eq!(conditions.description, "雨");
+        assert_eq!(conditions.precipitation_mm, Some(4.5));
+        assert_eq!(conditions.wind_speed_kmh, None);
+        assert_eq!(conditions.visibility_m, None);
+
+        let later = NaiveDateTime::parse_from_str("2026-10-16 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert!(hourly.at(later).is_none());
+    }
+
+    #[test]
+    fn test_describe_weather_code() {
+        assert_eq!(describe_weather_code(0), "快晴");
+        assert_eq!(describe_weather_code(45), "霧");
+        assert_eq!(describe_weather_code(75), "雪");
+        assert_eq!(describe_weather_code(96), "雷雨");
+        assert_eq!(describe_weather_code(4), "不明");
+    }
+}
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+
+use super::signing::{sign_payload, SIGNATURE_HEADER};
+use crate::db::OrgScopedPool;
+use crate::http_client::HttpClient;
+use crate::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::notifications::{Notification, Notifier, WEBHOOK_DISABLED};
+use crate::outbox::{OutboxDelivery, OutboxMessage};
+use crate::services::lineworks_auth;
+
+/// outbox の配送先名（常に有効）
+pub const WEBHOOK_TARGET: &str = "webhooks";
+/// webhook_deliveries 1行を送る job
+pub const WEBHOOK_DELIVER_JOB: &str = "webhooks.deliver";
+/// 1配送あたりの送信回数（job の指数バックオフで 30秒〜約1時間後まで再試行）
+pub const WEBHOOK_MAX_ATTEMPTS: i32 = 8;
+/// 連続失敗がこの回数を超え、かつ成功が DISABLE_AFTER_HOURS 以上ないエンドポイントは自動で無効化
+pub const DISABLE_AFTER_FAILURES: i32 = 50;
+pub const DISABLE_AFTER_HOURS: i32 = 24;
+/// webhook_delivery_attempts に残すレスポンス本文の上限
+const RESPONSE_EXCERPT_CHARS: usize = 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDeliverPayload {
+    pub delivery_id: i64,
+}
+
+impl WebhookDeliverPayload {
+    pub fn job(delivery_id: i64) -> NewJob {
+        NewJob::new(WEBHOOK_DELIVER_JOB, Self { delivery_id })
+            .dedupe_key(delivery_id.to_string())
+            .max_attempts(WEBHOOK_MAX_ATTEMPTS)
+    }
+}
+
+/// outbox のイベントを購読中のエンドポイントごとの webhook_deliveries に展開する
+pub struct WebhookFanout {
+    pool: PgPool,
+}
+
+impl WebhookFanout {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl OutboxDelivery for WebhookFanout {
+    async fn deliver(&self, message: &OutboxMessage) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin_scoped(&message.organization_id).await?;
+
+        // 受信側に渡す本文はここで確定させる（再送しても同じ内容）
+        let body = serde_json::json!({
+            "event_type": message.event_type,
+            "organization_id": message.organization_id,
+            "outbox_id": message.id,
+            "data": message.payload,
+        });
+        let delivery_ids: Vec<i64> = sqlx::query_scalar(
+            r#"
+            INSERT INTO webhook_deliveries (organization_id, endpoint_id, outbox_id, event_type, payload)
+            SELECT organization_id, id, $1, $2, $3
+            FROM webhook_endpoints
+            WHERE enabled = TRUE AND (cardinality(event_types) = 0 OR $2 = ANY(event_types))
+            ON CONFLICT (endpoint_id, outbox_id) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(message.id)
+        .bind(&message.event_type)
+        .bind(&body)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for delivery_id in &delivery_ids {
+            enqueue(&mut tx, &message.organization_id, WebhookDeliverPayload::job(*delivery_id)).await?;
+        }
+        tx.commit().await?;
+
+        if !delivery_ids.is_empty() {
+            tracing::debug!("Outbox {} ({}) fanned out to {} webhooks", message.id, message.event_type, delivery_ids.len());
+        }
+        Ok(())
+    }
+}
+
+/// 送信する配送とエンドポイント
+#[derive(Debug, FromRow)]
+struct PendingDelivery {
+    id: i64,
+    endpoint_id: uuid::Uuid,
+    attempts: i32,
+    event_type: String,
+    payload: serde_json::Value,
+    url: String,
+    secret_encrypted: String,
+}
+
+/// webhook_deliveries を署名付きで POST する job ハンドラ
+pub struct WebhookDeliveryJobHandler {
+    pool: PgPool,
+    http_client: Arc<HttpClient>,
+    /// 署名キーの復号キー（JWT_SECRET）
+    secret_key: String,
+    /// 自動無効化を管理者に知らせる
+    notifier: Notifier,
+}
+
+impl WebhookDeliveryJobHandler {
+    pub fn new(pool: PgPool, http_client: Arc<HttpClient>, secret_key: String, notifier: Notifier) -> Self {
+        Self {
+            pool,
+            http_client,
+            secret_key,
+            notifier,
+        }
+    }
+
+    /// 連続失敗を数え、閾値を超えたらエンドポイントを無効化して管理者に通知する
+    async fn record_endpoint_failure(
+        &self,
+        organization_id: &str,
+        endpoint_id: uuid::Uuid,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin_scoped(organization_id).await?;
+        // 更新前の enabled と比べ、今回の失敗で無効化されたときだけ通知する
+        let (url, failures, just_disabled): (String, i32, bool) = sqlx::query_as(
+            r#"
+            UPDATE webhook_endpoints e
+            SET consecutive_failures = e.consecutive_failures + 1,
+                enabled = e.enabled AND NOT (e.consecutive_failures + 1 >= $2
+                    AND COALESCE(e.last_success_at, e.created_at) < NOW() - make_interval(hours => $3)),
+                disabled_reason = CASE
+                    WHEN e.enabled AND e.consecutive_failures + 1 >= $2
+                        AND COALESCE(e.last_success_at, e.created_at) < NOW() - make_interval(hours => $3)
+                    THEN $4 ELSE e.disabled_reason END,
+                updated_at = NOW()
+            FROM (SELECT id, enabled FROM webhook_endpoints WHERE id = $1 FOR UPDATE) old
+            WHERE e.id = old.id
+            RETURNING e.url, e.consecutive_failures, old.enabled AND NOT e.enabled
+            "#,
+        )
+        .bind(endpoint_id)
+        .bind(DISABLE_AFTER_FAILURES)
+        .bind(DISABLE_AFTER_HOURS)
+        .bind(format!("Disabled after {} consecutive failures: {}", DISABLE_AFTER_FAILURES, error))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if just_disabled {
+            tracing::warn!("Webhook endpoint {} disabled after {} consecutive failures", endpoint_id, failures);
+            let admins = Notifier::admin_recipients(&mut tx, organization_id).await?;
+            let admin_feed = Notifier::in_app_recipients(&mut tx, organization_id, true).await?;
+            let notification = Notification::new(WEBHOOK_DISABLED)
+                .var("url", &url)
+                .var("failures", failures)
+                .var("last_error", error)
+                .to_all(admins)
+                .to_all(admin_feed);
+            self.notifier.send(&mut tx, organization_id, &notification).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl JobHandler for WebhookDeliveryJobHandler {
+    async fn run(&self, job: &Job) -> anyhow::Result<()> {
+        let payload: WebhookDeliverPayload = job.payload()?;
+
+        let mut conn = self.pool.acquire_scoped(&job.organization_id).await?;
+        let delivery: Option<PendingDelivery> = sqlx::query_as(
+            r#"
+            SELECT d.id, d.endpoint_id, d.attempts, d.event_type, d.payload, e.url, e.secret_encrypted
+            FROM webhook_deliveries d
+            JOIN webhook_endpoints e ON e.id = d.endpoint_id
+            WHERE d.id = $1 AND d.status <> 'delivered' AND e.enabled = TRUE
+            "#,
+        )
+        .bind(payload.delivery_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some(delivery) = delivery else {
+            // 送信済み、またはエンドポイントが削除・無効化された
+            return Ok(());
+        };
+        drop(conn);
+
+        let secret = lineworks_auth::decrypt_secret(&delivery.secret_encrypted, &self.secret_key)
+            .map_err(|e| anyhow::anyhow!("Failed to decrypt webhook secret: {}", e))?;
+        let body = serde_json::to_string(&delivery.payload)?;
+        let signature = sign_payload(&secret, chrono::Utc::now().timestamp(), &body);
+        let delivery_id = delivery.id.to_string();
+        let headers = [
+            (SIGNATURE_HEADER, signature.as_str()),
+            ("X-Logi-Event", delivery.event_type.as_str()),
+            ("X-Logi-Delivery", delivery_id.as_str()),
+        ];
+
+        let started = std::time::Instant::now();
+        let (status_code, response_body, result) =
+            match self.http_client.post_json_with_headers(&delivery.url, &headers, body).await {
+                Ok(response) => {
+                    let status = response.status();
+                    let text = response.text().await.unwrap_or_default();
+                    let excerpt: String = text.chars().take(RESPONSE_EXCERPT_CHARS).collect();
+                    let result = if status.is_success() {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("Webhook endpoint returned {}", status))
+                    };
+                    (Some(i32::from(status.as_u16())), Some(excerpt), result)
+                }
+                Err(e) => (None, None, Err(anyhow::anyhow!("Webhook request failed: {}", e))),
+            };
+        let duration_ms = i32::try_from(started.elapsed().as_millis()).unwrap_or(i32::MAX);
+        let error = result.as_ref().err().map(|e| e.to_string());
+
+        // 再送（RedeliverWebhook）でも通し番号にする
+        let attempt = delivery.attempts + 1;
+        // job の再試行が尽きたら failed
+        let status = match &result {
+            Ok(()) => "delivered",
+            Err(_) if job.attempts >= job.max_attempts => "failed",
+            Err(_) => "pending",
+        };
+        let mut tx = self.pool.begin_scoped(&job.organization_id).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_delivery_attempts
+                (organization_id, delivery_id, attempt, status_code, error, response_body, duration_ms)
+            VALUES ($1::uuid, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(&job.organization_id)
+        .bind(delivery.id)
+        .bind(attempt)
+        .bind(status_code)
+        .bind(&error)
+        .bind(&response_body)
+        .bind(duration_ms)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2, attempts = $3, last_status_code = $4, last_error = $5, updated_at = NOW(),
+                delivered_at = CASE WHEN $2 = 'delivered' THEN NOW() END
+            WHERE id = $1
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(status)
+        .bind(attempt)
+        .bind(status_code)
+        .bind(&error)
+        .execute(&mut *tx)
+        .await?;
+        if result.is_ok() {
+            sqlx::query(
+                "UPDATE webhook_endpoints SET consecutive_failures = 0, last_success_at = NOW() WHERE id = $1",
+            )
+            .bind(delivery.endpoint_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        if let Some(error) = &error {
+            self.record_endpoint_failure(&job.organization_id, delivery.endpoint_id, error).await?;
+        }
+        result
+    }
+}
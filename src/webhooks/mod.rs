@@ -0,0 +1,16 @@
+// Tenant webhooks
+//
+// 組織が登録したエンドポイントへ outbox のイベントを HMAC-SHA256 署名付きで POST する。
+// outbox の webhooks 配送先（WebhookFanout）が購読しているエンドポイントごとに webhook_deliveries を作り、
+// 送信は job（webhooks.deliver）で行う。エンドポイントごとに独立して再試行されるので、
+// 1つのエンドポイントの障害が他の配送や outbox の後続を止めない。
+// 送信のたびに webhook_delivery_attempts に結果を残し、失敗が続くエンドポイントは自動で無効化する。
+
+pub mod delivery;
+pub mod signing;
+
+pub use delivery::{
+    WebhookDeliverPayload, WebhookDeliveryJobHandler, WebhookFanout, DISABLE_AFTER_FAILURES, WEBHOOK_DELIVER_JOB,
+    WEBHOOK_MAX_ATTEMPTS, WEBHOOK_TARGET,
+};
+pub use signing::{generate_secret, sign_payload, SIGNATURE_HEADER};